@@ -2,67 +2,192 @@
 
 use anoma_apps::cli;
 use anoma_apps::cli::cmds::*;
-use anoma_apps::client::{gossip, rpc, tx, utils};
+use anoma_apps::cli::Context;
+use anoma_apps::client::tendermint_rpc_types::TxBroadcastResult;
+use anoma_apps::client::{gossip, history, rpc, tx, utils};
 use color_eyre::eyre::Result;
 
+use crate::console;
+
 pub async fn main() -> Result<()> {
     match cli::anoma_client_cli() {
+        cli::AnomaClient::Console(global_args) => {
+            console::main(global_args).await
+        }
+        cmd => {
+            run(cmd).await;
+            Ok(())
+        }
+    }
+}
+
+/// Run a client command
+pub async fn run(cmd: cli::AnomaClient) {
+    match cmd {
         cli::AnomaClient::WithContext(cmd_box) => {
             let (cmd, ctx) = *cmd_box;
             use AnomaClientWithContext as Sub;
             match cmd {
                 // Ledger cmds
                 Sub::TxCustom(TxCustom(args)) => {
-                    tx::submit_custom(ctx, args).await;
+                    let tx_args = args.tx.clone();
+                    let (ctx, result) =
+                        handle_tx_result(tx::submit_custom(ctx, args).await);
+                    let accounts = result.initialized_accounts();
+                    tx::save_initialized_accounts(ctx, &tx_args, accounts)
+                        .await;
                 }
                 Sub::TxTransfer(TxTransfer(args)) => {
-                    tx::submit_transfer(ctx, args).await;
+                    handle_tx_result(tx::submit_transfer(ctx, args).await);
+                }
+                Sub::TxIbcTransfer(TxIbcTransfer(args)) => {
+                    handle_tx_result(tx::submit_ibc_transfer(ctx, args).await);
+                }
+                Sub::TxFaucet(TxFaucet(args)) => {
+                    handle_tx_result(tx::submit_faucet(ctx, args).await);
+                }
+                Sub::TxMultiTransfer(TxMultiTransfer(args)) => {
+                    handle_tx_result(
+                        tx::submit_multi_transfer(ctx, args).await,
+                    );
                 }
                 Sub::TxUpdateVp(TxUpdateVp(args)) => {
-                    tx::submit_update_vp(ctx, args).await;
+                    handle_tx_result(tx::submit_update_vp(ctx, args).await);
                 }
                 Sub::TxInitAccount(TxInitAccount(args)) => {
-                    tx::submit_init_account(ctx, args).await;
+                    let tx_args = args.tx.clone();
+                    let (ctx, result) = handle_tx_result(
+                        tx::submit_init_account(ctx, args).await,
+                    );
+                    let accounts = result.initialized_accounts();
+                    tx::save_initialized_accounts(ctx, &tx_args, accounts)
+                        .await;
+                }
+                Sub::TxRevealPk(TxRevealPk(args)) => {
+                    handle_tx_result(tx::submit_reveal_pk(ctx, args).await);
                 }
                 Sub::TxInitValidator(TxInitValidator(args)) => {
-                    tx::submit_init_validator(ctx, args).await;
+                    exit_on_error(tx::submit_init_validator(ctx, args).await);
+                }
+                Sub::Apply(Apply(args)) => {
+                    exit_on_error(tx::submit_apply(ctx, args).await);
+                }
+                Sub::TxBatch(TxBatch(args)) => {
+                    let tx_args = args.tx.clone();
+                    let (ctx, result) =
+                        handle_tx_result(tx::submit_batch(ctx, args).await);
+                    let accounts = result.initialized_accounts();
+                    tx::save_initialized_accounts(ctx, &tx_args, accounts)
+                        .await;
+                }
+                Sub::TxSubmitDraft(TxSubmitDraft(args)) => {
+                    let tx_args = args.tx.clone();
+                    let (ctx, result) =
+                        handle_tx_result(tx::submit_draft(ctx, args).await);
+                    let accounts = result.initialized_accounts();
+                    tx::save_initialized_accounts(ctx, &tx_args, accounts)
+                        .await;
                 }
                 Sub::TxInitNft(TxInitNft(args)) => {
-                    tx::submit_init_nft(ctx, args).await;
+                    handle_tx_result(tx::submit_init_nft(ctx, args).await);
                 }
                 Sub::TxMintNft(TxMintNft(args)) => {
-                    tx::submit_mint_nft(ctx, args).await;
+                    if args.assets_dir.is_some() {
+                        exit_on_error(
+                            tx::submit_mint_nft_assets(ctx, args).await,
+                        );
+                    } else {
+                        handle_tx_result(tx::submit_mint_nft(ctx, args).await);
+                    }
+                }
+                Sub::Nft(Nft::Validate(NftValidate(args))) => {
+                    let path = args.data_path.clone();
+                    exit_on_error(tx::validate_nft(args));
+                    println!("The NFT file {} is valid.", path.display());
                 }
                 Sub::TxInitProposal(TxInitProposal(args)) => {
-                    tx::submit_init_proposal(ctx, args).await;
+                    if args.offline {
+                        let file = exit_on_error(
+                            tx::create_offline_proposal(ctx, args).await,
+                        );
+                        println!("Proposal created: {}.", file);
+                    } else {
+                        handle_tx_result(
+                            tx::submit_init_proposal(ctx, args).await,
+                        );
+                    }
                 }
                 Sub::TxVoteProposal(TxVoteProposal(args)) => {
-                    tx::submit_vote_proposal(ctx, args).await;
+                    if args.offline {
+                        let file = exit_on_error(
+                            tx::create_offline_vote(ctx, args).await,
+                        );
+                        println!("Proposal vote created: {}.", file);
+                    } else {
+                        handle_tx_result(
+                            tx::submit_vote_proposal(ctx, args).await,
+                        );
+                    }
                 }
                 Sub::Bond(Bond(args)) => {
-                    tx::submit_bond(ctx, args).await;
+                    handle_tx_result(tx::submit_bond(ctx, args).await);
+                }
+                Sub::BondMany(BondMany(args)) => {
+                    handle_tx_result(tx::submit_bond_many(ctx, args).await);
                 }
                 Sub::Unbond(Unbond(args)) => {
-                    tx::submit_unbond(ctx, args).await;
+                    handle_tx_result(tx::submit_unbond(ctx, args).await);
                 }
                 Sub::Withdraw(Withdraw(args)) => {
-                    tx::submit_withdraw(ctx, args).await;
+                    handle_tx_result(tx::submit_withdraw(ctx, args).await);
+                }
+                Sub::ClaimRewards(ClaimRewards(args)) => {
+                    handle_tx_result(tx::submit_claim_rewards(ctx, args).await);
+                }
+                Sub::AutoCompound(AutoCompound(args)) => {
+                    handle_tx_result(tx::submit_auto_compound(ctx, args).await);
+                }
+                Sub::ChangeCommissionRate(ChangeCommissionRate(args)) => {
+                    handle_tx_result(
+                        tx::submit_change_commission(ctx, args).await,
+                    );
+                }
+                Sub::Unjail(Unjail(args)) => {
+                    handle_tx_result(tx::submit_unjail(ctx, args).await);
                 }
                 // Ledger queries
                 Sub::QueryEpoch(QueryEpoch(args)) => {
                     rpc::query_epoch(args).await;
                 }
                 Sub::QueryBalance(QueryBalance(args)) => {
-                    rpc::query_balance(ctx, args).await;
+                    rpc::token::query_balance(ctx, args).await;
                 }
                 Sub::QueryBonds(QueryBonds(args)) => {
-                    rpc::query_bonds(ctx, args).await;
+                    rpc::pos::query_bonds(ctx, args).await;
+                }
+                Sub::QueryUnbonds(QueryUnbonds(args)) => {
+                    rpc::pos::query_unbonds(ctx, args).await;
                 }
                 Sub::QueryVotingPower(QueryVotingPower(args)) => {
-                    rpc::query_voting_power(ctx, args).await;
+                    rpc::pos::query_voting_power(ctx, args).await;
+                }
+                Sub::QueryValidators(QueryValidators(args)) => {
+                    rpc::pos::query_validators(ctx, args).await;
                 }
                 Sub::QuerySlashes(QuerySlashes(args)) => {
-                    rpc::query_slashes(ctx, args).await;
+                    rpc::pos::query_slashes(ctx, args).await;
+                }
+                Sub::QueryIbcPacketStatus(QueryIbcPacketStatus(args)) => {
+                    rpc::ibc::query_ibc_packet_status(ctx, args).await;
+                }
+                Sub::QueryNfts(QueryNfts(args)) => {
+                    rpc::nft::query_nfts(ctx, args).await;
+                }
+                Sub::QueryNftCollections(QueryNftCollections(args)) => {
+                    rpc::nft::query_nft_collections(ctx, args).await;
+                }
+                Sub::Report(Report(args)) => {
+                    history::report(ctx, args).await;
                 }
                 Sub::QueryResult(QueryResult(args)) => {
                     rpc::query_result(ctx, args).await;
@@ -72,21 +197,39 @@ pub async fn main() -> Result<()> {
                 }
 
                 Sub::QueryProposal(QueryProposal(args)) => {
-                    rpc::query_proposal(ctx, args).await;
+                    rpc::gov::query_proposal(ctx, args).await;
                 }
                 Sub::QueryProposalResult(QueryProposalResult(args)) => {
-                    rpc::query_proposal_result(ctx, args).await;
+                    rpc::gov::query_proposal_result(ctx, args).await;
                 }
                 Sub::QueryProtocolParameters(QueryProtocolParameters(args)) => {
                     rpc::query_protocol_parameters(ctx, args).await;
                 }
+                Sub::Account(Account::Show(AccountShow(args))) => {
+                    rpc::account::query_account(ctx, args).await;
+                }
                 // Gossip cmds
                 Sub::Intent(Intent(args)) => {
                     gossip::gossip_intent(ctx, args).await;
                 }
+                Sub::IntentSimulate(IntentSimulate(args)) => {
+                    gossip::simulate_intent(ctx, args).await;
+                }
                 Sub::SubscribeTopic(SubscribeTopic(args)) => {
                     gossip::subscribe_topic(ctx, args).await;
                 }
+                Sub::IntentTrace(IntentTrace(args)) => {
+                    gossip::intent_trace(ctx, args).await;
+                }
+                Sub::CancelIntent(CancelIntent(args)) => {
+                    gossip::cancel_intent(ctx, args).await;
+                }
+                Sub::QueryIntents(QueryIntents(args)) => {
+                    gossip::query_intents(ctx, args).await;
+                }
+                Sub::QueryDepth(QueryDepth(args)) => {
+                    gossip::query_depth(ctx, args).await;
+                }
             }
         }
         cli::AnomaClient::WithoutContext(cmd, global_args) => match cmd {
@@ -100,7 +243,30 @@ pub async fn main() -> Result<()> {
             Utils::InitGenesisValidator(InitGenesisValidator(args)) => {
                 utils::init_genesis_validator(global_args, args)
             }
+            Utils::WasmAbiVersion(WasmAbiVersion(args)) => {
+                utils::wasm_abi_version(args)
+            }
+            Utils::GenTestVectors(GenTestVectors(args)) => {
+                utils::gen_test_vectors(args)
+            }
         },
+        cli::AnomaClient::Console(_) => {
+            eprintln!("The console is already running.");
+        }
     }
-    Ok(())
+}
+
+/// Print the result of a submitted tx. On error, print it and exit.
+fn handle_tx_result(result: tx::SubmitResult) -> (Context, TxBroadcastResult) {
+    let (ctx, result) = exit_on_error(result);
+    tx::print_tx_result(&ctx, &result);
+    (ctx, result)
+}
+
+/// Print the error, if any, and exit
+fn exit_on_error<T>(result: Result<T, tx::Error>) -> T {
+    result.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        cli::safe_exit(1)
+    })
 }
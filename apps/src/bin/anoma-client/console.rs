@@ -0,0 +1,116 @@
+//! Anoma client interactive console.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use anoma_apps::cli::{self, args, cmds};
+use anoma_apps::client::console::{self, LineEditor};
+use anoma_apps::wallet::{DecryptedKeyCache, Wallet};
+use color_eyre::eyre::Result;
+use futures::FutureExt;
+
+use crate::cli::run;
+
+const PROMPT: &str = "anoma> ";
+
+/// The commands handled by the console, in addition to the client commands
+const CONSOLE_CMDS: [&str; 4] = ["exit", "help", "history", "quit"];
+
+/// Run the console until the end of the input or until it's exited.
+pub async fn main(global_args: args::Global) -> Result<()> {
+    let mut editor =
+        LineEditor::new(global_args.base_dir.join(console::HISTORY_FILE));
+    // The keys decrypted by a command are shared with the following commands,
+    // so that the wallet only has to be unlocked once
+    let key_cache = DecryptedKeyCache::default();
+    // Recover from a failed command instead of exiting the console
+    cli::set_unwind_on_exit(true);
+
+    let commands = cli::anoma_client_console_completions();
+    let ctx = panic::catch_unwind(AssertUnwindSafe(|| {
+        cli::Context::new(global_args.clone())
+    }));
+    editor.set_completions(completions(
+        &commands,
+        ctx.as_ref().ok().map(|ctx| &ctx.wallet),
+    ));
+    drop(ctx);
+
+    println!(
+        "Anoma client console. Enter `help` for the list of commands, \
+         `history` for the command history or `exit` to quit."
+    );
+    while let Some(line) = editor.read_line(PROMPT)? {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history(line);
+        let words = match console::split_words(line) {
+            Ok(words) => words,
+            Err(err) => {
+                eprintln!("{}", err);
+                continue;
+            }
+        };
+        match words[0].as_str() {
+            "exit" | "quit" => break,
+            "history" => {
+                for (ix, entry) in editor.history().iter().enumerate() {
+                    println!("{:>5}  {}", ix + 1, entry);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let cmd = match cli::anoma_client_console_cmd(words) {
+            Ok(Some(cmds::AnomaClient::Console(_))) => {
+                eprintln!("The console is already running.");
+                continue;
+            }
+            Ok(Some(cmd)) => cmd,
+            Ok(None) => continue,
+            // Also used to display the help
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        };
+        let result = AssertUnwindSafe(async {
+            let mut cmd = cli::AnomaClient::new(cmd, global_args.clone());
+            if let cli::AnomaClient::WithContext(cmd_box) = &mut cmd {
+                let wallet = &mut cmd_box.1.wallet;
+                wallet.set_decrypted_key_cache(key_cache.clone());
+                editor.set_completions(completions(&commands, Some(wallet)));
+            }
+            run(cmd).await
+        })
+        .catch_unwind()
+        .await;
+        if let Err(payload) = result {
+            match payload.downcast_ref::<cli::ExitUnwind>() {
+                Some(cli::ExitUnwind(0)) => {}
+                Some(cli::ExitUnwind(code)) => {
+                    eprintln!("The command failed with exit code {}.", code)
+                }
+                None => eprintln!("The command panicked."),
+            }
+        }
+    }
+    cli::set_unwind_on_exit(false);
+    Ok(())
+}
+
+/// The words that can be tab completed: the console and client commands,
+/// their arguments and the aliases from the wallet, if it's loaded.
+fn completions(commands: &[String], wallet: Option<&Wallet>) -> Vec<String> {
+    let mut completions: Vec<String> = CONSOLE_CMDS
+        .iter()
+        .map(|cmd| cmd.to_string())
+        .chain(commands.iter().cloned())
+        .collect();
+    if let Some(wallet) = wallet {
+        completions.extend(wallet.get_addresses().into_keys());
+        completions.extend(wallet.get_keys().into_keys());
+    }
+    completions
+}
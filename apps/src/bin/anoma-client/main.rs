@@ -1,4 +1,5 @@
 mod cli;
+mod console;
 
 use anoma_apps::logging;
 use color_eyre::eyre::Result;
@@ -1,7 +1,8 @@
 //! Anoma node CLI.
 
+use anoma_apps::cli::context::{WalletAddress, WalletKeypair};
 use anoma_apps::cli::{self, args, cmds};
-use anoma_apps::node::{gossip, ledger, matchmaker};
+use anoma_apps::node::{doctor, gossip, ledger, matchmaker};
 use eyre::{Context, Result};
 
 pub fn main() -> Result<()> {
@@ -19,15 +20,21 @@ pub fn main() -> Result<()> {
                 ledger::reset(ctx.config.ledger)
                     .wrap_err("Failed to reset Anoma node")?;
             }
+            cmds::Ledger::Compact(_) => {
+                ledger::compact(ctx.config.ledger)
+                    .wrap_err("Failed to compact Anoma node's storage")?;
+            }
         },
         cmds::AnomaNode::Gossip(sub) => match sub {
             cmds::Gossip::Run(cmds::GossipRun(args::GossipRun {
                 addr,
                 rpc,
+                mdns,
+                bootstrap_peers,
             })) => {
                 let config = ctx.config;
                 let mut gossip_cfg = config.intent_gossiper;
-                gossip_cfg.update(addr, rpc);
+                gossip_cfg.update(addr, rpc, mdns, bootstrap_peers);
                 gossip::run(
                     gossip_cfg,
                     &config
@@ -43,12 +50,36 @@ pub fn main() -> Result<()> {
             intent_gossiper_addr,
             matchmaker_path,
             tx_code_path,
+            fee,
+            batch_interval,
             ledger_addr,
             tx_signing_key,
             tx_source_address,
         })) => {
-            let tx_signing_key = ctx.get_cached(&tx_signing_key);
-            let tx_source_address = ctx.get(&tx_source_address);
+            // The signing key and source given as arguments override the
+            // configured settlement's ones
+            let settlement_config = ctx.config.matchmaker.settlement.take();
+            let signing_key_and_source =
+                match (tx_signing_key, tx_source_address) {
+                    (Some(signing_key), Some(source)) => {
+                        Some((signing_key, source))
+                    }
+                    _ => settlement_config.as_ref().map(|config| {
+                        (
+                            WalletKeypair::new(config.signing_key.clone()),
+                            WalletAddress::new(config.source.clone()),
+                        )
+                    }),
+                };
+            let settlement =
+                signing_key_and_source.map(|(signing_key, source)| {
+                    matchmaker::Settlement::new(
+                        ctx.get_cached(&signing_key),
+                        ctx.get(&source),
+                        ledger_addr,
+                        settlement_config.as_ref(),
+                    )
+                });
 
             let wasm_dir = ctx.wasm_dir();
             let config = ctx.config;
@@ -59,13 +90,17 @@ pub fn main() -> Result<()> {
             if tx_code_path.is_some() {
                 mm_config.tx_code_path = tx_code_path;
             }
+            if fee.is_some() {
+                mm_config.fee = fee;
+            }
+            if batch_interval.is_some() {
+                mm_config.batch_interval = batch_interval;
+            }
 
             matchmaker::run(
                 mm_config,
                 intent_gossiper_addr,
-                ledger_addr,
-                tx_signing_key,
-                tx_source_address,
+                settlement,
                 wasm_dir,
             );
         }
@@ -89,6 +124,12 @@ pub fn main() -> Result<()> {
                 );
             }
         },
+        cmds::AnomaNode::Doctor(cmds::NodeDoctor) => {
+            let wasm_dir = ctx.wasm_dir();
+            if !doctor::run(&ctx.config, &wasm_dir) {
+                cli::safe_exit(1)
+            }
+        }
     }
     Ok(())
 }
@@ -2,14 +2,17 @@
 
 use std::fs::File;
 use std::io::{self, Write};
+use std::time::Duration;
 
+use anoma::types::address::Address;
 use anoma::types::key::*;
 use anoma_apps::cli;
 use anoma_apps::cli::{args, cmds, Context};
-use anoma_apps::wallet::DecryptionError;
+use anoma_apps::wallet::{agent, DecryptionError};
 use borsh::BorshSerialize;
 use color_eyre::eyre::Result;
 use itertools::sorted;
+use serde::Serialize;
 
 pub fn main() -> Result<()> {
     let (cmd, ctx) = cli::anoma_wallet_cli();
@@ -36,26 +39,104 @@ pub fn main() -> Result<()> {
                 address_add(ctx, args)
             }
         },
+        cmds::AnomaWallet::Agent(cmds::WalletAgent(args)) => {
+            run_agent(ctx, args)
+        }
     }
     Ok(())
 }
 
-/// Generate a new keypair and derive implicit address from it and store them in
-/// the wallet.
+/// Run the wallet agent or remove all the keys from a running agent.
+fn run_agent(
+    ctx: Context,
+    args::WalletAgent { key_ttl, clear }: args::WalletAgent,
+) {
+    let socket = ctx.wallet.agent_socket();
+    if clear {
+        match agent::request(&socket, &agent::Request::RemoveAll) {
+            Ok(_) => println!("All the keys have been removed from the agent."),
+            Err(err) => {
+                eprintln!("Unable to reach the wallet agent: {}", err);
+                cli::safe_exit(1)
+            }
+        }
+    } else if let Err(err) = agent::run(&socket, Duration::from_secs(key_ttl)) {
+        eprintln!("The wallet agent failed: {}", err);
+        cli::safe_exit(1)
+    }
+}
+
+/// Generate new keypairs and derive implicit addresses from them and store
+/// them in the wallet. Optionally, export the addresses into a manifest.
 fn key_and_address_gen(
     ctx: Context,
     args::KeyAndAddressGen {
         alias,
         unsafe_dont_encrypt,
+        count,
+        manifest,
     }: args::KeyAndAddressGen,
 ) {
+    if count == 0 {
+        eprintln!("The number of keys to generate must be at least 1.");
+        cli::safe_exit(1)
+    }
+    if let Some(manifest) = &manifest {
+        if manifest.exists() {
+            eprintln!(
+                "The manifest file {} already exists.",
+                manifest.to_string_lossy()
+            );
+            cli::safe_exit(1)
+        }
+    }
     let mut wallet = ctx.wallet;
-    let (alias, _key) = wallet.gen_key(alias, unsafe_dont_encrypt);
+    let keys = wallet.gen_keys(alias, count, unsafe_dont_encrypt);
     wallet.save().unwrap_or_else(|err| eprintln!("{}", err));
-    println!(
-        "Successfully added a key and an address with alias: \"{}\"",
-        alias
-    );
+    for (alias, _key) in &keys {
+        println!(
+            "Successfully added a key and an address with alias: \"{}\"",
+            alias
+        );
+    }
+    if let Some(manifest) = manifest {
+        let entries = keys
+            .iter()
+            .map(|(alias, key)| {
+                let public_key = key.ref_to();
+                let address: Address = (&public_key).into();
+                AddressManifestEntry {
+                    alias: alias.clone(),
+                    address: address.encode(),
+                    public_key: public_key.to_string(),
+                }
+            })
+            .collect();
+        let toml = toml::to_string(&AddressManifest { address: entries })
+            .expect("Encoding the address manifest shouldn't fail");
+        std::fs::write(&manifest, toml).unwrap_or_else(|err| {
+            eprintln!(
+                "Failed to write the manifest file {}: {}",
+                manifest.to_string_lossy(),
+                err
+            );
+            cli::safe_exit(1)
+        });
+        println!("Exported the addresses to {}", manifest.to_string_lossy());
+    }
+}
+
+/// A manifest of generated addresses, e.g. for deposits to a cold storage
+#[derive(Serialize)]
+struct AddressManifest {
+    address: Vec<AddressManifestEntry>,
+}
+
+#[derive(Serialize)]
+struct AddressManifestEntry {
+    alias: String,
+    address: String,
+    public_key: String,
 }
 
 /// Find a keypair in the wallet store.
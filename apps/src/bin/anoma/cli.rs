@@ -4,12 +4,21 @@
 //! node and the client. The other commands for the node, client and wallet can
 //! be dispatched via `anoma node ...`, `anoma client ...` or `anoma wallet
 //! ...`, respectively.
+//!
+//! Any other command `anoma <cmd> ...` is dispatched to an external plugin
+//! executable `anoma-<cmd>`, found either in the directory of the `anoma`
+//! binary or in the `PATH`.
 
+use std::collections::BTreeSet;
 use std::env;
+use std::path::PathBuf;
 use std::process::Command;
 
 use anoma_apps::cli;
-use eyre::Result;
+use eyre::{eyre, Result};
+
+/// The file name prefix of the plugin executables
+const PLUGIN_PREFIX: &str = "anoma-";
 
 pub fn main() -> Result<()> {
     let (cmd, raw_sub_cmd) = cli::anoma_cli();
@@ -24,6 +33,7 @@ fn handle_command(cmd: cli::cmds::Anoma, raw_sub_cmd: String) -> Result<()> {
         cli::cmds::Anoma::Node(_)
             | cli::cmds::Anoma::Client(_)
             | cli::cmds::Anoma::Wallet(_)
+            | cli::cmds::Anoma::Plugin(_)
     );
 
     // Skip the first arg, which is the name of the binary
@@ -53,9 +63,89 @@ fn handle_command(cmd: cli::cmds::Anoma, raw_sub_cmd: String) -> Result<()> {
         | cli::cmds::Anoma::TxMintNft(_)
         | cli::cmds::Anoma::TxInitProposal(_)
         | cli::cmds::Anoma::TxVoteProposal(_)
-        | cli::cmds::Anoma::Intent(_) => handle_subcommand("anomac", sub_args),
+        | cli::cmds::Anoma::Intent(_)
+        | cli::cmds::Anoma::IntentSimulate(_) => {
+            handle_subcommand("anomac", sub_args)
+        }
         cli::cmds::Anoma::Wallet(_) => handle_subcommand("anomaw", sub_args),
+        cli::cmds::Anoma::Plugins(_) => {
+            list_plugins();
+            Ok(())
+        }
+        cli::cmds::Anoma::Plugin(plugin) => handle_plugin(plugin, sub_args),
+    }
+}
+
+/// Run an external plugin command with the remaining arguments, including any
+/// global arguments given before the command.
+fn handle_plugin(
+    plugin: cli::cmds::Plugin,
+    sub_args: Vec<String>,
+) -> Result<()> {
+    let program = plugin_file_name(&plugin.name);
+    let cmd_name = plugin_dirs()
+        .into_iter()
+        .map(|dir| dir.join(&program))
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            eyre!(
+                "Unknown command {}. No plugin {} found in the directory of \
+                 the anoma binary or in the PATH. Run `anoma plugins` to list \
+                 the available plugins.",
+                plugin.name,
+                program
+            )
+        })?;
+
+    let mut cmd = Command::new(cmd_name);
+    cmd.args(sub_args).envs(env::vars_os());
+    exec_subcommand(&program, cmd)
+}
+
+/// Print the names of all the plugin commands that can be found.
+fn list_plugins() {
+    let plugins: BTreeSet<String> = plugin_dirs()
+        .into_iter()
+        .filter_map(|dir| dir.read_dir().ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let name = file_name
+                .strip_prefix(PLUGIN_PREFIX)?
+                .strip_suffix(env::consts::EXE_SUFFIX)?;
+            (!name.is_empty()).then(|| name.to_string())
+        })
+        .collect();
+    if plugins.is_empty() {
+        println!("No plugins found.");
+    } else {
+        println!("Installed plugins:");
+        for name in plugins {
+            println!("    {}", name);
+        }
+    }
+}
+
+/// The directories searched for plugins, in order of precedence: the
+/// directory of the current `anoma` binary, followed by the `PATH`.
+fn plugin_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(PathBuf::from))
+    {
+        dirs.push(dir);
+    }
+    if let Some(path) = env::var_os("PATH") {
+        dirs.extend(env::split_paths(&path));
     }
+    dirs
+}
+
+/// The file name of the executable for a plugin command.
+fn plugin_file_name(name: &str) -> String {
+    format!("{}{}{}", PLUGIN_PREFIX, name, env::consts::EXE_SUFFIX)
 }
 
 fn handle_subcommand(program: &str, mut sub_args: Vec<String>) -> Result<()> {
@@ -9,9 +9,11 @@
 pub mod context;
 mod utils;
 
+use std::collections::BTreeSet;
+
 use clap::{crate_authors, AppSettings, ArgMatches};
-pub use utils::safe_exit;
 use utils::*;
+pub use utils::{safe_exit, set_unwind_on_exit, ExitUnwind};
 
 pub use self::context::Context;
 
@@ -53,6 +55,11 @@ pub mod cmds {
         TxInitProposal(TxInitProposal),
         TxVoteProposal(TxVoteProposal),
         Intent(Intent),
+        IntentSimulate(IntentSimulate),
+
+        // External `anoma-<cmd>` plugin commands.
+        Plugins(Plugins),
+        Plugin(Plugin),
     }
 
     impl Cmd for Anoma {
@@ -71,6 +78,7 @@ pub mod cmds {
                 .subcommand(TxInitProposal::def())
                 .subcommand(TxVoteProposal::def())
                 .subcommand(Intent::def())
+                .subcommand(Plugins::def())
         }
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
@@ -90,6 +98,10 @@ pub mod cmds {
             let tx_vote_proposal =
                 SubCmd::parse(matches).map(Self::TxVoteProposal);
             let intent = SubCmd::parse(matches).map(Self::Intent);
+            let intent_simulate =
+                SubCmd::parse(matches).map(Self::IntentSimulate);
+            let plugins = SubCmd::parse(matches).map(Self::Plugins);
+            let plugin = Plugin::parse(matches).map(Self::Plugin);
             node.or(client)
                 .or(wallet)
                 .or(ledger)
@@ -103,6 +115,79 @@ pub mod cmds {
                 .or(tx_init_proposal)
                 .or(tx_vote_proposal)
                 .or(intent)
+                .or(intent_simulate)
+                .or(plugins)
+                .or(plugin)
+        }
+    }
+
+    impl Anoma {
+        /// The names of the built-in commands, which cannot be overridden by
+        /// a plugin.
+        const BUILTIN_CMDS: &'static [&'static str] = &[
+            AnomaNode::CMD,
+            AnomaClient::CMD,
+            AnomaWallet::CMD,
+            Ledger::CMD,
+            Gossip::CMD,
+            Matchmaker::CMD,
+            TxCustom::CMD,
+            TxTransfer::CMD,
+            TxUpdateVp::CMD,
+            TxInitNft::CMD,
+            TxMintNft::CMD,
+            TxInitProposal::CMD,
+            TxVoteProposal::CMD,
+            Intent::CMD,
+            Plugins::CMD,
+        ];
+    }
+
+    /// List the external plugin commands
+    #[derive(Clone, Debug)]
+    pub struct Plugins;
+
+    impl SubCmd for Plugins {
+        const CMD: &'static str = "plugins";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|_matches| Plugins)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(
+                "List the external plugin commands. A plugin is any \
+                 executable named `anoma-<cmd>` found in the directory of the \
+                 `anoma` binary or in the `PATH`, which can be invoked as \
+                 `anoma <cmd>`.",
+            )
+        }
+    }
+
+    /// An external plugin command `anoma <cmd>`, dispatched to an
+    /// `anoma-<cmd>` executable
+    #[derive(Clone, Debug)]
+    pub struct Plugin {
+        /// The name of the plugin command, without the `anoma-` prefix
+        pub name: String,
+    }
+
+    impl Plugin {
+        /// Parse a sub-command that is not one of the built-in commands as a
+        /// plugin command.
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            match matches.subcommand() {
+                Some((name, _matches))
+                    if !Anoma::BUILTIN_CMDS.contains(&name) =>
+                {
+                    Some(Plugin {
+                        name: name.to_string(),
+                    })
+                }
+                _ => None,
+            }
         }
     }
 
@@ -115,6 +200,7 @@ pub mod cmds {
         Gossip(Gossip),
         Matchmaker(Matchmaker),
         Config(Config),
+        Doctor(NodeDoctor),
     }
 
     impl Cmd for AnomaNode {
@@ -123,6 +209,7 @@ pub mod cmds {
                 .subcommand(Gossip::def())
                 .subcommand(Matchmaker::def())
                 .subcommand(Config::def())
+                .subcommand(NodeDoctor::def())
         }
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
@@ -130,7 +217,8 @@ pub mod cmds {
             let gossip = SubCmd::parse(matches).map(Self::Gossip);
             let matchmaker = SubCmd::parse(matches).map(Self::Matchmaker);
             let config = SubCmd::parse(matches).map(Self::Config);
-            ledger.or(gossip).or(matchmaker).or(config)
+            let doctor = SubCmd::parse(matches).map(Self::Doctor);
+            ledger.or(gossip).or(matchmaker).or(config).or(doctor)
         }
     }
     impl SubCmd for AnomaNode {
@@ -162,6 +250,9 @@ pub mod cmds {
         WithContext(AnomaClientWithContext),
         /// Utils don't have [`super::Context`], only the global arguments.
         WithoutContext(Utils),
+        /// The interactive console loads a [`super::Context`] for every
+        /// command entered in it.
+        Console(Console),
     }
 
     impl Cmd for AnomaClient {
@@ -170,60 +261,108 @@ pub mod cmds {
                 // Simple transactions
                 .subcommand(TxCustom::def().display_order(1))
                 .subcommand(TxTransfer::def().display_order(1))
+                .subcommand(TxIbcTransfer::def().display_order(1))
+                .subcommand(TxFaucet::def().display_order(1))
+                .subcommand(TxMultiTransfer::def().display_order(1))
                 .subcommand(TxUpdateVp::def().display_order(1))
                 .subcommand(TxInitAccount::def().display_order(1))
+                .subcommand(TxRevealPk::def().display_order(1))
                 .subcommand(TxInitValidator::def().display_order(1))
+                .subcommand(Apply::def().display_order(1))
+                .subcommand(TxBatch::def().display_order(1))
+                .subcommand(TxSubmitDraft::def().display_order(1))
                 // Nft transactions
                 .subcommand(TxInitNft::def().display_order(1))
                 .subcommand(TxMintNft::def().display_order(1))
+                .subcommand(Nft::def().display_order(1))
                 // Proposal transactions
                 .subcommand(TxInitProposal::def().display_order(1))
                 .subcommand(TxVoteProposal::def().display_order(1))
                 // PoS transactions
                 .subcommand(Bond::def().display_order(2))
+                .subcommand(BondMany::def().display_order(2))
                 .subcommand(Unbond::def().display_order(2))
                 .subcommand(Withdraw::def().display_order(2))
+                .subcommand(ClaimRewards::def().display_order(2))
+                .subcommand(AutoCompound::def().display_order(2))
+                .subcommand(ChangeCommissionRate::def().display_order(2))
+                .subcommand(Unjail::def().display_order(2))
                 // Queries
                 .subcommand(QueryEpoch::def().display_order(3))
                 .subcommand(QueryBalance::def().display_order(3))
                 .subcommand(QueryBonds::def().display_order(3))
+                .subcommand(QueryUnbonds::def().display_order(3))
                 .subcommand(QueryVotingPower::def().display_order(3))
+                .subcommand(QueryValidators::def().display_order(3))
                 .subcommand(QuerySlashes::def().display_order(3))
+                .subcommand(QueryIbcPacketStatus::def().display_order(3))
+                .subcommand(QueryNfts::def().display_order(3))
+                .subcommand(QueryNftCollections::def().display_order(3))
                 .subcommand(QueryResult::def().display_order(3))
                 .subcommand(QueryRawBytes::def().display_order(3))
                 .subcommand(QueryProposal::def().display_order(3))
                 .subcommand(QueryProposalResult::def().display_order(3))
                 .subcommand(QueryProtocolParameters::def().display_order(3))
+                .subcommand(Account::def().display_order(3))
+                .subcommand(Report::def().display_order(3))
                 // Intents
                 .subcommand(Intent::def().display_order(4))
                 .subcommand(SubscribeTopic::def().display_order(4))
+                .subcommand(IntentTrace::def().display_order(4))
+                .subcommand(CancelIntent::def().display_order(4))
+                .subcommand(QueryIntents::def().display_order(4))
+                .subcommand(QueryDepth::def().display_order(4))
                 // Utils
                 .subcommand(Utils::def().display_order(5))
+                .subcommand(Console::def().display_order(5))
         }
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             use AnomaClientWithContext::*;
             let tx_custom = Self::parse_with_ctx(matches, TxCustom);
             let tx_transfer = Self::parse_with_ctx(matches, TxTransfer);
+            let tx_ibc_transfer = Self::parse_with_ctx(matches, TxIbcTransfer);
+            let tx_faucet = Self::parse_with_ctx(matches, TxFaucet);
+            let tx_multi_transfer =
+                Self::parse_with_ctx(matches, TxMultiTransfer);
             let tx_update_vp = Self::parse_with_ctx(matches, TxUpdateVp);
             let tx_init_account = Self::parse_with_ctx(matches, TxInitAccount);
+            let tx_reveal_pk = Self::parse_with_ctx(matches, TxRevealPk);
             let tx_init_validator =
                 Self::parse_with_ctx(matches, TxInitValidator);
+            let apply = Self::parse_with_ctx(matches, Apply);
+            let tx_batch = Self::parse_with_ctx(matches, TxBatch);
+            let tx_submit_draft = Self::parse_with_ctx(matches, TxSubmitDraft);
             let tx_nft_create = Self::parse_with_ctx(matches, TxInitNft);
             let tx_nft_mint = Self::parse_with_ctx(matches, TxMintNft);
+            let nft = Self::parse_with_ctx(matches, Nft);
             let tx_init_proposal =
                 Self::parse_with_ctx(matches, TxInitProposal);
             let tx_vote_proposal =
                 Self::parse_with_ctx(matches, TxVoteProposal);
             let bond = Self::parse_with_ctx(matches, Bond);
+            let bond_many = Self::parse_with_ctx(matches, BondMany);
             let unbond = Self::parse_with_ctx(matches, Unbond);
             let withdraw = Self::parse_with_ctx(matches, Withdraw);
+            let claim_rewards = Self::parse_with_ctx(matches, ClaimRewards);
+            let auto_compound = Self::parse_with_ctx(matches, AutoCompound);
+            let change_commission_rate =
+                Self::parse_with_ctx(matches, ChangeCommissionRate);
+            let unjail = Self::parse_with_ctx(matches, Unjail);
             let query_epoch = Self::parse_with_ctx(matches, QueryEpoch);
             let query_balance = Self::parse_with_ctx(matches, QueryBalance);
             let query_bonds = Self::parse_with_ctx(matches, QueryBonds);
+            let query_unbonds = Self::parse_with_ctx(matches, QueryUnbonds);
             let query_voting_power =
                 Self::parse_with_ctx(matches, QueryVotingPower);
+            let query_validators =
+                Self::parse_with_ctx(matches, QueryValidators);
             let query_slashes = Self::parse_with_ctx(matches, QuerySlashes);
+            let query_ibc_packet_status =
+                Self::parse_with_ctx(matches, QueryIbcPacketStatus);
+            let query_nfts = Self::parse_with_ctx(matches, QueryNfts);
+            let query_nft_collections =
+                Self::parse_with_ctx(matches, QueryNftCollections);
             let query_result = Self::parse_with_ctx(matches, QueryResult);
             let query_raw_bytes = Self::parse_with_ctx(matches, QueryRawBytes);
             let query_proposal = Self::parse_with_ctx(matches, QueryProposal);
@@ -231,34 +370,68 @@ pub mod cmds {
                 Self::parse_with_ctx(matches, QueryProposalResult);
             let query_protocol_parameters =
                 Self::parse_with_ctx(matches, QueryProtocolParameters);
+            let account = Self::parse_with_ctx(matches, Account);
+            let report = Self::parse_with_ctx(matches, Report);
             let intent = Self::parse_with_ctx(matches, Intent);
+            let intent_simulate = Self::parse_with_ctx(matches, IntentSimulate);
             let subscribe_topic = Self::parse_with_ctx(matches, SubscribeTopic);
+            let intent_trace = Self::parse_with_ctx(matches, IntentTrace);
+            let cancel_intent = Self::parse_with_ctx(matches, CancelIntent);
+            let query_intents = Self::parse_with_ctx(matches, QueryIntents);
+            let query_depth = Self::parse_with_ctx(matches, QueryDepth);
             let utils = SubCmd::parse(matches).map(Self::WithoutContext);
+            let console = SubCmd::parse(matches).map(Self::Console);
             tx_custom
                 .or(tx_transfer)
+                .or(tx_ibc_transfer)
+                .or(tx_faucet)
+                .or(tx_multi_transfer)
                 .or(tx_update_vp)
                 .or(tx_init_account)
+                .or(tx_reveal_pk)
                 .or(tx_init_validator)
+                .or(apply)
+                .or(tx_batch)
+                .or(tx_submit_draft)
                 .or(tx_nft_create)
                 .or(tx_nft_mint)
+                .or(nft)
                 .or(tx_init_proposal)
                 .or(tx_vote_proposal)
                 .or(bond)
+                .or(bond_many)
                 .or(unbond)
                 .or(withdraw)
+                .or(claim_rewards)
+                .or(auto_compound)
+                .or(change_commission_rate)
+                .or(unjail)
                 .or(query_epoch)
                 .or(query_balance)
                 .or(query_bonds)
+                .or(query_unbonds)
                 .or(query_voting_power)
+                .or(query_validators)
                 .or(query_slashes)
+                .or(query_ibc_packet_status)
+                .or(query_nfts)
+                .or(query_nft_collections)
                 .or(query_result)
                 .or(query_raw_bytes)
                 .or(query_proposal)
                 .or(query_proposal_result)
                 .or(query_protocol_parameters)
+                .or(account)
+                .or(report)
                 .or(intent)
+                .or(intent_simulate)
                 .or(subscribe_topic)
+                .or(intent_trace)
+                .or(cancel_intent)
+                .or(query_intents)
+                .or(query_depth)
                 .or(utils)
+                .or(console)
         }
     }
 
@@ -296,29 +469,54 @@ pub mod cmds {
         // Ledger cmds
         TxCustom(TxCustom),
         TxTransfer(TxTransfer),
+        TxIbcTransfer(TxIbcTransfer),
+        TxFaucet(TxFaucet),
+        TxMultiTransfer(TxMultiTransfer),
         QueryResult(QueryResult),
         TxUpdateVp(TxUpdateVp),
         TxInitAccount(TxInitAccount),
+        TxRevealPk(TxRevealPk),
         TxInitValidator(TxInitValidator),
+        Apply(Apply),
+        TxBatch(TxBatch),
+        TxSubmitDraft(TxSubmitDraft),
         TxInitNft(TxInitNft),
         TxMintNft(TxMintNft),
+        Nft(Nft),
         TxInitProposal(TxInitProposal),
         TxVoteProposal(TxVoteProposal),
         Bond(Bond),
+        BondMany(BondMany),
         Unbond(Unbond),
         Withdraw(Withdraw),
+        ClaimRewards(ClaimRewards),
+        AutoCompound(AutoCompound),
+        ChangeCommissionRate(ChangeCommissionRate),
+        Unjail(Unjail),
         QueryEpoch(QueryEpoch),
         QueryBalance(QueryBalance),
         QueryBonds(QueryBonds),
+        QueryUnbonds(QueryUnbonds),
         QueryVotingPower(QueryVotingPower),
+        QueryValidators(QueryValidators),
         QuerySlashes(QuerySlashes),
+        QueryIbcPacketStatus(QueryIbcPacketStatus),
+        QueryNfts(QueryNfts),
+        QueryNftCollections(QueryNftCollections),
         QueryRawBytes(QueryRawBytes),
         QueryProposal(QueryProposal),
         QueryProposalResult(QueryProposalResult),
         QueryProtocolParameters(QueryProtocolParameters),
+        Account(Account),
+        Report(Report),
         // Gossip cmds
         Intent(Intent),
+        IntentSimulate(IntentSimulate),
         SubscribeTopic(SubscribeTopic),
+        IntentTrace(IntentTrace),
+        CancelIntent(CancelIntent),
+        QueryIntents(QueryIntents),
+        QueryDepth(QueryDepth),
     }
 
     #[derive(Clone, Debug)]
@@ -327,18 +525,22 @@ pub mod cmds {
         Key(WalletKey),
         /// Address management commands
         Address(WalletAddress),
+        /// Run the wallet agent
+        Agent(WalletAgent),
     }
 
     impl Cmd for AnomaWallet {
         fn add_sub(app: App) -> App {
             app.subcommand(WalletKey::def())
                 .subcommand(WalletAddress::def())
+                .subcommand(WalletAgent::def())
         }
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             let key = SubCmd::parse(matches).map(Self::Key);
             let address = SubCmd::parse(matches).map(Self::Address);
-            key.or(address)
+            let agent = SubCmd::parse(matches).map(Self::Agent);
+            key.or(address).or(agent)
         }
     }
 
@@ -594,10 +796,38 @@ pub mod cmds {
         }
     }
 
+    /// Run the wallet agent, which caches decrypted keys
+    #[derive(Clone, Debug)]
+    pub struct WalletAgent(pub args::WalletAgent);
+
+    impl SubCmd for WalletAgent {
+        const CMD: &'static str = "agent";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| WalletAgent(args::WalletAgent::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Run the wallet agent. The agent caches decrypted keys \
+                     for a limited time, so that the password of an encrypted \
+                     key doesn't have to be entered for every transaction. \
+                     The agent listens on a socket inside the chain \
+                     directory, unless the `ANOMA_WALLET_AGENT_SOCK` \
+                     environment variable is set.",
+                )
+                .add_args::<args::WalletAgent>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub enum Ledger {
         Run(LedgerRun),
         Reset(LedgerReset),
+        Compact(LedgerCompact),
     }
 
     impl SubCmd for Ledger {
@@ -607,7 +837,9 @@ pub mod cmds {
             matches.subcommand_matches(Self::CMD).and_then(|matches| {
                 let run = SubCmd::parse(matches).map(Self::Run);
                 let reset = SubCmd::parse(matches).map(Self::Reset);
+                let compact = SubCmd::parse(matches).map(Self::Compact);
                 run.or(reset)
+                    .or(compact)
                     // The `run` command is the default if no sub-command given
                     .or(Some(Self::Run(LedgerRun)))
             })
@@ -621,6 +853,7 @@ pub mod cmds {
                 )
                 .subcommand(LedgerRun::def())
                 .subcommand(LedgerReset::def())
+                .subcommand(LedgerCompact::def())
         }
     }
 
@@ -657,6 +890,24 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct LedgerCompact;
+
+    impl SubCmd for LedgerCompact {
+        const CMD: &'static str = "compact";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(
+                "Delete the unreachable data from Anoma ledger node's storage \
+                 and compact it. The node must not be running.",
+            )
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub enum Gossip {
         Run(GossipRun),
@@ -765,6 +1016,26 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct NodeDoctor;
+
+    impl SubCmd for NodeDoctor {
+        const CMD: &'static str = "doctor";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(
+                "Check the node's home directory for common \
+                 misconfigurations: secret key files accessible by other \
+                 users, a Tendermint genesis of another chain, missing or \
+                 stale WASMs and a skewed clock.",
+            )
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct QueryResult(pub args::QueryResult);
 
@@ -890,6 +1161,73 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxIbcTransfer(pub args::TxIbcTransfer);
+
+    impl SubCmd for TxIbcTransfer {
+        const CMD: &'static str = "ibc-transfer";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxIbcTransfer(args::TxIbcTransfer::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed IBC transfer transaction to move tokens to \
+                     another chain.",
+                )
+                .add_args::<args::TxIbcTransfer>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxFaucet(pub args::TxFaucet);
+
+    impl SubCmd for TxFaucet {
+        const CMD: &'static str = "faucet";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxFaucet(args::TxFaucet::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Withdraw tokens from a testnet faucet account to the \
+                     target account.",
+                )
+                .add_args::<args::TxFaucet>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxMultiTransfer(pub args::TxMultiTransfer);
+
+    impl SubCmd for TxMultiTransfer {
+        const CMD: &'static str = "multi-transfer";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxMultiTransfer(args::TxMultiTransfer::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction that transfers a token from \
+                     the source to many targets at once, e.g. for an airdrop \
+                     or a payroll.",
+                )
+                .add_args::<args::TxMultiTransfer>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxUpdateVp(pub args::TxUpdateVp);
 
@@ -934,6 +1272,29 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxRevealPk(pub args::TxRevealPk);
+
+    impl SubCmd for TxRevealPk {
+        const CMD: &'static str = "reveal-pk";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxRevealPk(args::TxRevealPk::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Send a signed transaction to reveal the public key of an \
+                     implicit address on chain, so that the ledger can verify \
+                     its signatures.",
+                )
+                .add_args::<args::TxRevealPk>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct TxInitValidator(pub args::TxInitValidator);
 
@@ -950,481 +1311,1178 @@ pub mod cmds {
             App::new(Self::CMD)
                 .about(
                     "Send a signed transaction to create a new validator and \
-                     its staking reward account.",
+                     its staking reward account, with an initial commission \
+                     rate and self-bond. The validator's keys are generated \
+                     and stored in the wallet if not given.",
                 )
                 .add_args::<args::TxInitValidator>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Bond(pub args::Bond);
+    pub struct Apply(pub args::Apply);
 
-    impl SubCmd for Bond {
-        const CMD: &'static str = "bond";
+    impl SubCmd for Apply {
+        const CMD: &'static str = "apply";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Bond(args::Bond::parse(matches)))
+                .map(|matches| Apply(args::Apply::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Bond tokens in PoS system.")
-                .add_args::<args::Bond>()
+                .about(
+                    "Apply an application manifest. Submits the transactions \
+                     needed to create the accounts and tokens, set the \
+                     validity predicates and top up the balances described in \
+                     the manifest, skipping what's already on chain.",
+                )
+                .add_args::<args::Apply>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Unbond(pub args::Unbond);
+    pub struct TxBatch(pub args::TxBatch);
 
-    impl SubCmd for Unbond {
-        const CMD: &'static str = "unbond";
+    impl SubCmd for TxBatch {
+        const CMD: &'static str = "batch";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Unbond(args::Unbond::parse(matches)))
+                .map(|matches| TxBatch(args::TxBatch::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Unbond tokens from a PoS bond.")
-                .add_args::<args::Unbond>()
+                .about(
+                    "Send a signed transaction with a batch of operations \
+                     that are applied atomically, i.e. either all of them or \
+                     none.",
+                )
+                .add_args::<args::TxBatch>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Withdraw(pub args::Withdraw);
+    pub struct TxSubmitDraft(pub args::TxSubmitDraft);
 
-    impl SubCmd for Withdraw {
-        const CMD: &'static str = "withdraw";
+    impl SubCmd for TxSubmitDraft {
+        const CMD: &'static str = "submit-draft";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| Withdraw(args::Withdraw::parse(matches)))
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxSubmitDraft(args::TxSubmitDraft::parse(matches))
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Withdraw tokens from previously unbonded PoS bond.")
-                .add_args::<args::Withdraw>()
+                .about(
+                    "Submit a transaction saved as a draft with the \
+                     `--save-draft` argument. An unsigned draft is signed \
+                     first.",
+                )
+                .add_args::<args::TxSubmitDraft>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryEpoch(pub args::Query);
+    pub struct Bond(pub args::Bond);
 
-    impl SubCmd for QueryEpoch {
-        const CMD: &'static str = "epoch";
+    impl SubCmd for Bond {
+        const CMD: &'static str = "bond";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QueryEpoch(args::Query::parse(matches)))
+                .map(|matches| Bond(args::Bond::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query the epoch of the last committed block.")
-                .add_args::<args::Query>()
+                .about("Bond tokens in PoS system.")
+                .add_args::<args::Bond>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryBalance(pub args::QueryBalance);
+    pub struct BondMany(pub args::BondMany);
 
-    impl SubCmd for QueryBalance {
-        const CMD: &'static str = "balance";
+    impl SubCmd for BondMany {
+        const CMD: &'static str = "bond-many";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QueryBalance(args::QueryBalance::parse(matches)))
+                .map(|matches| BondMany(args::BondMany::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query balance(s) of tokens.")
-                .add_args::<args::QueryBalance>()
+                .about(
+                    "Delegate tokens to several validators at once in a \
+                     single transaction, as given in a JSON or TOML file. \
+                     Either all the bonds are applied or none of them.",
+                )
+                .add_args::<args::BondMany>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryBonds(pub args::QueryBonds);
+    pub struct Unbond(pub args::Unbond);
 
-    impl SubCmd for QueryBonds {
-        const CMD: &'static str = "bonds";
+    impl SubCmd for Unbond {
+        const CMD: &'static str = "unbond";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QueryBonds(args::QueryBonds::parse(matches)))
+                .map(|matches| Unbond(args::Unbond::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query PoS bond(s).")
-                .add_args::<args::QueryBonds>()
+                .about("Unbond tokens from a PoS bond.")
+                .add_args::<args::Unbond>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryVotingPower(pub args::QueryVotingPower);
+    pub struct Withdraw(pub args::Withdraw);
 
-    impl SubCmd for QueryVotingPower {
-        const CMD: &'static str = "voting-power";
+    impl SubCmd for Withdraw {
+        const CMD: &'static str = "withdraw";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                QueryVotingPower(args::QueryVotingPower::parse(matches))
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Withdraw(args::Withdraw::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query PoS voting power.")
-                .add_args::<args::QueryVotingPower>()
+                .about("Withdraw tokens from previously unbonded PoS bond.")
+                .add_args::<args::Withdraw>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QuerySlashes(pub args::QuerySlashes);
+    pub struct ClaimRewards(pub args::ClaimRewards);
 
-    impl SubCmd for QuerySlashes {
-        const CMD: &'static str = "slashes";
+    impl SubCmd for ClaimRewards {
+        const CMD: &'static str = "claim-rewards";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| QuerySlashes(args::QuerySlashes::parse(matches)))
+                .map(|matches| ClaimRewards(args::ClaimRewards::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query PoS applied slashes.")
-                .add_args::<args::QuerySlashes>()
+                .about(
+                    "Claim the staking rewards accumulated by a PoS bond into \
+                     the delegator's balance, or into the validator's staking \
+                     reward account for a self-bond.",
+                )
+                .add_args::<args::ClaimRewards>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct QueryRawBytes(pub args::QueryRawBytes);
+    pub struct AutoCompound(pub args::AutoCompound);
 
-    impl SubCmd for QueryRawBytes {
-        const CMD: &'static str = "query-bytes";
+    impl SubCmd for AutoCompound {
+        const CMD: &'static str = "auto-compound";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                QueryRawBytes(args::QueryRawBytes::parse(matches))
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| AutoCompound(args::AutoCompound::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Query the raw bytes of a given storage key")
-                .add_args::<args::QueryRawBytes>()
+                .about(
+                    "Enable or disable re-bonding the staking rewards of a \
+                     PoS bond to its validator at the end of each epoch.",
+                )
+                .add_args::<args::AutoCompound>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxInitNft(pub args::NftCreate);
+    pub struct ChangeCommissionRate(pub args::ChangeCommissionRate);
 
-    impl SubCmd for TxInitNft {
-        const CMD: &'static str = "init-nft";
+    impl SubCmd for ChangeCommissionRate {
+        const CMD: &'static str = "change-commission-rate";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| TxInitNft(args::NftCreate::parse(matches)))
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                ChangeCommissionRate(args::ChangeCommissionRate::parse(matches))
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Create a new NFT.")
-                .add_args::<args::NftCreate>()
+                .about(
+                    "Change a validator's commission rate. The new rate takes \
+                     effect at the pipeline offset.",
+                )
+                .add_args::<args::ChangeCommissionRate>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxMintNft(pub args::NftMint);
+    pub struct Unjail(pub args::Unjail);
 
-    impl SubCmd for TxMintNft {
-        const CMD: &'static str = "mint-nft";
+    impl SubCmd for Unjail {
+        const CMD: &'static str = "unjail";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
+        fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| TxMintNft(args::NftMint::parse(matches)))
+                .map(|matches| Unjail(args::Unjail::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Mint new NFT tokens.")
-                .add_args::<args::NftMint>()
+                .about(
+                    "Unjail a validator that has been jailed for downtime. \
+                     The validator re-enters the validator set at the \
+                     pipeline offset, once its jail period is over.",
+                )
+                .add_args::<args::Unjail>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxInitProposal(pub args::InitProposal);
+    pub struct QueryEpoch(pub args::Query);
 
-    impl SubCmd for TxInitProposal {
-        const CMD: &'static str = "init-proposal";
+    impl SubCmd for QueryEpoch {
+        const CMD: &'static str = "epoch";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                TxInitProposal(args::InitProposal::parse(matches))
-            })
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryEpoch(args::Query::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Create a new proposal.")
-                .add_args::<args::InitProposal>()
+                .about("Query the epoch of the last committed block.")
+                .add_args::<args::Query>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct TxVoteProposal(pub args::VoteProposal);
+    pub struct QueryBalance(pub args::QueryBalance);
 
-    impl SubCmd for TxVoteProposal {
-        const CMD: &'static str = "vote-proposal";
+    impl SubCmd for QueryBalance {
+        const CMD: &'static str = "balance";
 
-        fn parse(matches: &ArgMatches) -> Option<Self>
-        where
-            Self: Sized,
-        {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                TxVoteProposal(args::VoteProposal::parse(matches))
-            })
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryBalance(args::QueryBalance::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Vote a proposal.")
-                .add_args::<args::VoteProposal>()
+                .about("Query balance(s) of tokens.")
+                .add_args::<args::QueryBalance>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct Intent(pub args::Intent);
+    pub enum Account {
+        Show(AccountShow),
+    }
 
-    impl SubCmd for Intent {
-        const CMD: &'static str = "intent";
+    impl SubCmd for Account {
+        const CMD: &'static str = "account";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Intent(args::Intent::parse(matches)))
+                .and_then(|matches| SubCmd::parse(matches).map(Self::Show))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Send an intent.")
-                .add_args::<args::Intent>()
+                .about("Inspect accounts.")
+                .subcommand(AccountShow::def())
+                .setting(AppSettings::SubcommandRequiredElseHelp)
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct SubscribeTopic(pub args::SubscribeTopic);
+    pub struct AccountShow(pub args::QueryAccount);
 
-    impl SubCmd for SubscribeTopic {
-        const CMD: &'static str = "subscribe-topic";
+    impl SubCmd for AccountShow {
+        const CMD: &'static str = "show";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                SubscribeTopic(args::SubscribeTopic::parse(matches))
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| AccountShow(args::QueryAccount::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(
-                    "Subscribe intent gossip node with a matchmaker to a \
-                     topic.",
+                    "Show the whole storage sub-space of an account. The \
+                     known keys, such as the validity predicate, public keys \
+                     and balances, are decoded and the unknown keys are \
+                     listed separately.",
                 )
-                .add_args::<args::SubscribeTopic>()
+                .add_args::<args::QueryAccount>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub enum Utils {
-        JoinNetwork(JoinNetwork),
-        InitNetwork(InitNetwork),
-        InitGenesisValidator(InitGenesisValidator),
+    pub struct QueryBonds(pub args::QueryBonds);
+
+    impl SubCmd for QueryBonds {
+        const CMD: &'static str = "bonds";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryBonds(args::QueryBonds::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query PoS bonds and unbonds per validator, with their \
+                     epochs and the amounts that can be withdrawn now.",
+                )
+                .add_args::<args::QueryBonds>()
+        }
     }
 
-    impl SubCmd for Utils {
-        const CMD: &'static str = "utils";
+    #[derive(Clone, Debug)]
+    pub struct QueryUnbonds(pub args::QueryUnbonds);
+
+    impl SubCmd for QueryUnbonds {
+        const CMD: &'static str = "unbonds";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).and_then(|matches| {
-                let join_network =
-                    SubCmd::parse(matches).map(Self::JoinNetwork);
-                let init_network =
-                    SubCmd::parse(matches).map(Self::InitNetwork);
-                let init_genesis =
-                    SubCmd::parse(matches).map(Self::InitGenesisValidator);
-                join_network.or(init_network).or(init_genesis)
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryUnbonds(args::QueryUnbonds::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query PoS unbonds waiting to be withdrawn and when they \
+                     can be withdrawn.",
+                )
+                .add_args::<args::QueryUnbonds>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryVotingPower(pub args::QueryVotingPower);
+
+    impl SubCmd for QueryVotingPower {
+        const CMD: &'static str = "voting-power";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryVotingPower(args::QueryVotingPower::parse(matches))
             })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Utilities.")
-                .subcommand(JoinNetwork::def())
-                .subcommand(InitNetwork::def())
-                .subcommand(InitGenesisValidator::def())
-                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .about("Query PoS voting power.")
+                .add_args::<args::QueryVotingPower>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct JoinNetwork(pub args::JoinNetwork);
+    pub struct QueryValidators(pub args::QueryValidators);
 
-    impl SubCmd for JoinNetwork {
-        const CMD: &'static str = "join-network";
+    impl SubCmd for QueryValidators {
+        const CMD: &'static str = "validators";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryValidators(args::QueryValidators::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query PoS validators with their state, stake and share \
+                     of the voting power.",
+                )
+                .add_args::<args::QueryValidators>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QuerySlashes(pub args::QuerySlashes);
+
+    impl SubCmd for QuerySlashes {
+        const CMD: &'static str = "slashes";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Self(args::JoinNetwork::parse(matches)))
+                .map(|matches| QuerySlashes(args::QuerySlashes::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Configure Anoma to join an existing network.")
-                .add_args::<args::JoinNetwork>()
+                .about("Query PoS applied slashes.")
+                .add_args::<args::QuerySlashes>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct InitNetwork(pub args::InitNetwork);
+    pub struct QueryIbcPacketStatus(pub args::QueryIbcPacketStatus);
 
-    impl SubCmd for InitNetwork {
-        const CMD: &'static str = "init-network";
+    impl SubCmd for QueryIbcPacketStatus {
+        const CMD: &'static str = "ibc-packet-status";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryIbcPacketStatus(args::QueryIbcPacketStatus::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Track an IBC packet, such as the one of an IBC token \
+                     transfer, from the transaction that sent it to its \
+                     delivery and acknowledgement.",
+                )
+                .add_args::<args::QueryIbcPacketStatus>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryNfts(pub args::QueryNfts);
+
+    impl SubCmd for QueryNfts {
+        const CMD: &'static str = "nfts";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Self(args::InitNetwork::parse(matches)))
+                .map(|matches| QueryNfts(args::QueryNfts::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about("Initialize a new test network.")
-                .add_args::<args::InitNetwork>()
+                .about(
+                    "List the NFT tokens owned by an address, with their \
+                     values and metadata. The NFTs are searched for among the \
+                     addresses in the wallet.",
+                )
+                .add_args::<args::QueryNfts>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct InitGenesisValidator(pub args::InitGenesisValidator);
+    pub struct QueryNftCollections(pub args::QueryNftCollections);
 
-    impl SubCmd for InitGenesisValidator {
-        const CMD: &'static str = "init-genesis-validator";
+    impl SubCmd for QueryNftCollections {
+        const CMD: &'static str = "nft-collections";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryNftCollections(args::QueryNftCollections::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "List the NFT collections with their creator, VP hash and \
+                     number of tokens, or a page of the token IDs of a \
+                     collection.",
+                )
+                .add_args::<args::QueryNftCollections>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Report(pub args::Report);
+
+    impl SubCmd for Report {
+        const CMD: &'static str = "report";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Self(args::InitGenesisValidator::parse(matches)))
+                .map(|matches| Report(args::Report::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(
-                    "Initialize genesis validator's address, staking reward \
-                     address, consensus key, validator account key and \
-                     staking rewards key and use it in the ledger's node.",
+                    "Summarize the transfers from and to the wallet's \
+                     addresses in the current period by token and \
+                     counterparty, from the tx history recorded by the \
+                     `history` tx middleware.",
                 )
-                .add_args::<args::InitGenesisValidator>()
+                .add_args::<args::Report>()
         }
     }
-}
 
-pub mod args {
+    #[derive(Clone, Debug)]
+    pub struct QueryRawBytes(pub args::QueryRawBytes);
 
-    use std::convert::TryFrom;
-    use std::env;
-    use std::fs::File;
-    use std::net::SocketAddr;
-    use std::path::PathBuf;
-    use std::str::FromStr;
+    impl SubCmd for QueryRawBytes {
+        const CMD: &'static str = "query-bytes";
 
-    use anoma::types::address::Address;
-    use anoma::types::chain::{ChainId, ChainIdPrefix};
-    use anoma::types::governance::ProposalVote;
-    use anoma::types::intent::{DecimalWrapper, Exchange};
-    use anoma::types::key::*;
-    use anoma::types::storage::{self, Epoch};
-    use anoma::types::token;
-    use anoma::types::transaction::GasLimit;
-    use libp2p::Multiaddr;
-    use serde::Deserialize;
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint::Timeout;
-    #[cfg(not(feature = "ABCI"))]
-    use tendermint_config::net::Address as TendermintAddress;
-    #[cfg(feature = "ABCI")]
-    use tendermint_config_abci::net::Address as TendermintAddress;
-    #[cfg(feature = "ABCI")]
-    use tendermint_stable::Timeout;
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryRawBytes(args::QueryRawBytes::parse(matches))
+            })
+        }
 
-    use super::context::{WalletAddress, WalletKeypair, WalletPublicKey};
-    use super::utils::*;
-    use super::ArgMatches;
-    use crate::config;
-    use crate::config::TendermintMode;
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Query the raw bytes of a given storage key")
+                .add_args::<args::QueryRawBytes>()
+        }
+    }
 
-    const ADDRESS: Arg<WalletAddress> = arg("address");
-    const ALIAS_OPT: ArgOpt<String> = ALIAS.opt();
-    const ALIAS: Arg<String> = arg("alias");
-    const ALLOW_DUPLICATE_IP: ArgFlag = flag("allow-duplicate-ip");
-    const AMOUNT: Arg<token::Amount> = arg("amount");
-    const ARCHIVE_DIR: ArgOpt<PathBuf> = arg_opt("archive-dir");
-    const BASE_DIR: ArgDefault<PathBuf> = arg_default(
-        "base-dir",
-        DefaultFn(|| match env::var("ANOMA_BASE_DIR") {
-            Ok(dir) => dir.into(),
-            Err(_) => config::DEFAULT_BASE_DIR.into(),
-        }),
-    );
-    const BROADCAST_ONLY: ArgFlag = flag("broadcast-only");
-    const CHAIN_ID: Arg<ChainId> = arg("chain-id");
-    const CHAIN_ID_OPT: ArgOpt<ChainId> = CHAIN_ID.opt();
-    const CHAIN_ID_PREFIX: Arg<ChainIdPrefix> = arg("chain-prefix");
-    const CODE_PATH: Arg<PathBuf> = arg("code-path");
-    const CODE_PATH_OPT: ArgOpt<PathBuf> = CODE_PATH.opt();
-    const CONSENSUS_TIMEOUT_COMMIT: ArgDefault<Timeout> = arg_default(
-        "consensus-timeout-commit",
+    #[derive(Clone, Debug)]
+    pub struct TxInitNft(pub args::NftCreate);
+
+    impl SubCmd for TxInitNft {
+        const CMD: &'static str = "init-nft";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxInitNft(args::NftCreate::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Create a new NFT.")
+                .add_args::<args::NftCreate>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxMintNft(pub args::NftMint);
+
+    impl SubCmd for TxMintNft {
+        const CMD: &'static str = "mint-nft";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| TxMintNft(args::NftMint::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Mint new NFT tokens.")
+                .add_args::<args::NftMint>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub enum Nft {
+        Validate(NftValidate),
+    }
+
+    impl SubCmd for Nft {
+        const CMD: &'static str = "nft";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .and_then(|matches| SubCmd::parse(matches).map(Self::Validate))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .about("NFT sub-commands.")
+                .subcommand(NftValidate::def())
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct NftValidate(pub args::NftValidate);
+
+    impl SubCmd for NftValidate {
+        const CMD: &'static str = "validate";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| NftValidate(args::NftValidate::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Check an NFT definition file for the init-nft command, \
+                     or a file of tokens for the mint-nft command, without \
+                     submitting it. The files may be in JSON or, with the \
+                     toml extension, in TOML.",
+                )
+                .add_args::<args::NftValidate>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxInitProposal(pub args::InitProposal);
+
+    impl SubCmd for TxInitProposal {
+        const CMD: &'static str = "init-proposal";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxInitProposal(args::InitProposal::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Create a new proposal.")
+                .add_args::<args::InitProposal>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct TxVoteProposal(pub args::VoteProposal);
+
+    impl SubCmd for TxVoteProposal {
+        const CMD: &'static str = "vote-proposal";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxVoteProposal(args::VoteProposal::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Vote a proposal.")
+                .add_args::<args::VoteProposal>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Intent(pub args::Intent);
+
+    impl SubCmd for Intent {
+        const CMD: &'static str = "intent";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).and_then(|matches| {
+                // The sub-commands have their own arguments
+                if matches.subcommand_name().is_some() {
+                    None
+                } else {
+                    Some(Intent(args::Intent::parse(matches)))
+                }
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Send an intent.")
+                .add_args::<args::Intent>()
+                .subcommand(IntentSimulate::def())
+                .setting(AppSettings::SubcommandsNegateReqs)
+                .setting(AppSettings::ArgsNegateSubcommands)
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct IntentSimulate(pub args::IntentSimulate);
+
+    impl SubCmd for IntentSimulate {
+        const CMD: &'static str = "simulate";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Intent::CMD)
+                .and_then(|matches| matches.subcommand_matches(Self::CMD))
+                .map(|matches| {
+                    IntentSimulate(args::IntentSimulate::parse(matches))
+                })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Simulate an intent without sending it. The intent is \
+                     matched with the given counterparty intents by the \
+                     matchmaker running locally and the settlement \
+                     transaction of every match is dry-run on the ledger, \
+                     showing the expected transfers.",
+                )
+                .add_args::<args::IntentSimulate>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct SubscribeTopic(pub args::SubscribeTopic);
+
+    impl SubCmd for SubscribeTopic {
+        const CMD: &'static str = "subscribe-topic";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                SubscribeTopic(args::SubscribeTopic::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Subscribe intent gossip node with a matchmaker to a \
+                     topic.",
+                )
+                .add_args::<args::SubscribeTopic>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct IntentTrace(pub args::IntentTrace);
+
+    impl SubCmd for IntentTrace {
+        const CMD: &'static str = "intent-trace";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| IntentTrace(args::IntentTrace::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Show the propagation path of an intent through the \
+                     gossip network, as recorded by an intent gossip node in \
+                     the trace mode.",
+                )
+                .add_args::<args::IntentTrace>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct CancelIntent(pub args::CancelIntent);
+
+    impl SubCmd for CancelIntent {
+        const CMD: &'static str = "cancel-intent";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| CancelIntent(args::CancelIntent::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Cancel an intent published to an intent gossip node. The \
+                     cancellation is signed with the key that signed the \
+                     intent and gossiped, so that the nodes drop the intent \
+                     from their mempool.",
+                )
+                .add_args::<args::CancelIntent>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryIntents(pub args::QueryIntents);
+
+    impl SubCmd for QueryIntents {
+        const CMD: &'static str = "query-intents";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryIntents(args::QueryIntents::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the intents held in an intent gossip node's \
+                     mempool that have been received on a topic and show the \
+                     terms of their exchanges.",
+                )
+                .add_args::<args::QueryIntents>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryDepth(pub args::QueryDepth);
+
+    impl SubCmd for QueryDepth {
+        const CMD: &'static str = "query-depth";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| QueryDepth(args::QueryDepth::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Query the depth of the orderbook of a token pair, \
+                     aggregated from the intents held in an intent gossip \
+                     node's mempool that have been received on a topic, as \
+                     price levels with their cumulative sizes on each side.",
+                )
+                .add_args::<args::QueryDepth>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub enum Utils {
+        JoinNetwork(JoinNetwork),
+        InitNetwork(InitNetwork),
+        InitGenesisValidator(InitGenesisValidator),
+        WasmAbiVersion(WasmAbiVersion),
+        GenTestVectors(GenTestVectors),
+    }
+
+    impl SubCmd for Utils {
+        const CMD: &'static str = "utils";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).and_then(|matches| {
+                let join_network =
+                    SubCmd::parse(matches).map(Self::JoinNetwork);
+                let init_network =
+                    SubCmd::parse(matches).map(Self::InitNetwork);
+                let init_genesis =
+                    SubCmd::parse(matches).map(Self::InitGenesisValidator);
+                let wasm_abi_version =
+                    SubCmd::parse(matches).map(Self::WasmAbiVersion);
+                let gen_test_vectors =
+                    SubCmd::parse(matches).map(Self::GenTestVectors);
+                join_network
+                    .or(init_network)
+                    .or(init_genesis)
+                    .or(wasm_abi_version)
+                    .or(gen_test_vectors)
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Utilities.")
+                .subcommand(JoinNetwork::def())
+                .subcommand(InitNetwork::def())
+                .subcommand(InitGenesisValidator::def())
+                .subcommand(WasmAbiVersion::def())
+                .subcommand(GenTestVectors::def())
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+        }
+    }
+
+    /// The interactive client console
+    #[derive(Clone, Debug)]
+    pub struct Console;
+
+    impl SubCmd for Console {
+        const CMD: &'static str = "console";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|_matches| Console)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(
+                "Start an interactive console in which the client commands \
+                 can be entered without the `anomac` prefix. The decrypted \
+                 keys are kept for the whole session, so the wallet only has \
+                 to be unlocked once. Supports command history and tab \
+                 completion of commands, arguments and wallet aliases.",
+            )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct JoinNetwork(pub args::JoinNetwork);
+
+    impl SubCmd for JoinNetwork {
+        const CMD: &'static str = "join-network";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::JoinNetwork::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Configure Anoma to join an existing network.")
+                .add_args::<args::JoinNetwork>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InitNetwork(pub args::InitNetwork);
+
+    impl SubCmd for InitNetwork {
+        const CMD: &'static str = "init-network";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::InitNetwork::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about("Initialize a new test network.")
+                .add_args::<args::InitNetwork>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InitGenesisValidator(pub args::InitGenesisValidator);
+
+    impl SubCmd for InitGenesisValidator {
+        const CMD: &'static str = "init-genesis-validator";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::InitGenesisValidator::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Initialize genesis validator's address, staking reward \
+                     address, consensus key, validator account key and \
+                     staking rewards key and use it in the ledger's node.",
+                )
+                .add_args::<args::InitGenesisValidator>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct WasmAbiVersion(pub args::WasmAbiVersion);
+
+    impl SubCmd for WasmAbiVersion {
+        const CMD: &'static str = "wasm-abi-version";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::WasmAbiVersion::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Report the host functions ABI version of tx and VP wasm \
+                     files and whether they can be run by this version of the \
+                     ledger.",
+                )
+                .add_args::<args::WasmAbiVersion>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct GenTestVectors(pub args::GenTestVectors);
+
+    impl SubCmd for GenTestVectors {
+        const CMD: &'static str = "gen-test-vectors";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::GenTestVectors::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(
+                    "Generate deterministic test vectors of the encodings of \
+                     keys, addresses, txs and intents and of their \
+                     signatures, for the implementations of signing in \
+                     wallets and HSMs.",
+                )
+                .add_args::<args::GenTestVectors>()
+        }
+    }
+}
+
+pub mod args {
+
+    use std::convert::TryFrom;
+    use std::env;
+    use std::fs::File;
+    use std::net::SocketAddr;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use anoma::ibc::core::ics24_host::identifier::{ChannelId, PortId};
+    use anoma::types::address::Address;
+    use anoma::types::chain::{ChainId, ChainIdPrefix};
+    use anoma::types::governance::ProposalVote;
+    use anoma::types::intent::{
+        DecimalWrapper, Exchange, ExchangeNft, MatchmakerFee, NftSide,
+    };
+    use anoma::types::key::*;
+    use anoma::types::storage::{self, BlockHeight, Epoch};
+    use anoma::types::time::DateTimeUtc;
+    use anoma::types::token;
+    use anoma::types::transaction::GasLimit;
+    use libp2p::Multiaddr;
+    use serde::de::DeserializeOwned;
+    use serde::Deserialize;
+    #[cfg(not(feature = "ABCI"))]
+    use tendermint::Timeout;
+    #[cfg(not(feature = "ABCI"))]
+    use tendermint_config::net::Address as TendermintAddress;
+    #[cfg(feature = "ABCI")]
+    use tendermint_config_abci::net::Address as TendermintAddress;
+    #[cfg(feature = "ABCI")]
+    use tendermint_stable::Timeout;
+
+    use super::context::{WalletAddress, WalletKeypair, WalletPublicKey};
+    use super::utils::*;
+    use super::ArgMatches;
+    use crate::client::history::Period;
+    use crate::config;
+    use crate::config::{PeerAddress, TendermintMode};
+
+    const ADDRESS: Arg<WalletAddress> = arg("address");
+    const AFTER: ArgMulti<String> = arg("after").multi();
+    const ALIAS_OPT: ArgOpt<String> = ALIAS.opt();
+    const ALIAS: Arg<String> = arg("alias");
+    const ALLOW_DUPLICATE_IP: ArgFlag = flag("allow-duplicate-ip");
+    const AMOUNT: Arg<token::Amount> = arg("amount");
+    const AMOUNT_OPT: ArgOpt<token::Amount> = AMOUNT.opt();
+    const ARCHIVE_DIR: ArgOpt<PathBuf> = arg_opt("archive-dir");
+    const ASSETS_DIR: ArgOpt<PathBuf> = arg_opt("assets-dir");
+    const ARCHIVE_LEDGER_ADDRESSES: ArgMulti<TendermintAddress> =
+        arg("archive-ledger-address").multi();
+    const BASE_TOKEN: Arg<WalletAddress> = arg("base-token");
+    const BASE_DIR: ArgDefault<PathBuf> = arg_default(
+        "base-dir",
+        DefaultFn(|| match env::var("ANOMA_BASE_DIR") {
+            Ok(dir) => dir.into(),
+            Err(_) => config::DEFAULT_BASE_DIR.into(),
+        }),
+    );
+    const BATCH_PATH: Arg<PathBuf> = arg("file");
+    const BONDS_PATH: Arg<PathBuf> = arg("file");
+    const BOOTSTRAP_PEERS: ArgMulti<PeerAddress> =
+        arg("bootstrap-peer").multi();
+    const BATCH_INTERVAL: ArgOpt<Timeout> = arg_opt("batch-interval");
+    const BROADCAST_MODE: ArgDefault<BroadcastMode> =
+        arg_default("broadcast-mode", DefaultFn(|| BroadcastMode::Commit));
+    const BROADCAST_MAX_ATTEMPTS: ArgDefault<u64> =
+        arg_default("broadcast-max-attempts", DefaultFn(|| 3));
+    const BROADCAST_ONLY: ArgFlag = flag("broadcast-only");
+    const BROADCAST_RETRY_DELAY: ArgDefault<Timeout> = arg_default(
+        "broadcast-retry-delay",
+        DefaultFn(|| Timeout::from_str("1s").unwrap()),
+    );
+    const CHAIN_ID: Arg<ChainId> = arg("chain-id");
+    const CHANNEL_ID: Arg<ChannelId> = arg("channel-id");
+    const CHAIN_ID_OPT: ArgOpt<ChainId> = CHAIN_ID.opt();
+    const CHAIN_ID_PREFIX: Arg<ChainIdPrefix> = arg("chain-prefix");
+    const CLEAR: ArgFlag = flag("clear");
+    const CODE_PATH: Arg<PathBuf> = arg("code-path");
+    const CODE_PATH_OPT: ArgOpt<PathBuf> = CODE_PATH.opt();
+    const COMMISSION_RATE: Arg<u64> = arg("commission-rate");
+    const COMMISSION_RATE_DEFAULT: ArgDefault<u64> =
+        arg_default("commission-rate", DefaultFn(|| 0));
+    const CONSENSUS_TIMEOUT_COMMIT: ArgDefault<Timeout> = arg_default(
+        "consensus-timeout-commit",
         DefaultFn(|| Timeout::from_str("1s").unwrap()),
     );
+    const COUNT: ArgDefault<u64> = arg_default("count", DefaultFn(|| 1));
+    const COUNTERPARTY_INTENTS: ArgMulti<PathBuf> =
+        arg("counterparty-intent").multi();
+    const DATA_BASE64: ArgOpt<Base64Data> = arg_opt("data-base64");
+    const DATA_HEX: ArgOpt<HexData> = arg_opt("data-hex");
     const DATA_PATH_OPT: ArgOpt<PathBuf> = arg_opt("data-path");
     const DATA_PATH: Arg<PathBuf> = arg("data-path");
     const DECRYPT: ArgFlag = flag("decrypt");
+    const DISABLE: ArgFlag = flag("disable");
+    const DEPTH_LEVELS: ArgDefault<u32> =
+        arg_default("levels", DefaultFn(|| 20));
+    const DESTINATION_LEDGER_ADDRESS: ArgOpt<TendermintAddress> =
+        arg_opt("destination-ledger-address");
     const DONT_ARCHIVE: ArgFlag = flag("dont-archive");
+    const DRAFT_PATH: Arg<PathBuf> = arg("file");
+    const DRY_RUN_INTENT: ArgFlag = flag("dry-run");
     const DRY_RUN_TX: ArgFlag = flag("dry-run");
     const EPOCH: ArgOpt<Epoch> = arg_opt("epoch");
+    const EXPIRATION_OPT: ArgOpt<DateTimeUtc> = arg_opt("expiration");
+    const ESTIMATE_GAS: ArgFlag = flag("estimate-gas");
+    const EXPIRES_IN: ArgOpt<Timeout> = arg_opt("expires-in");
+    const EVENT_TIMEOUT: ArgDefault<Timeout> = arg_default(
+        "event-timeout",
+        DefaultFn(|| Timeout::from_str("60s").unwrap()),
+    );
+    const FAUCET: ArgDefaultFromCtx<WalletAddress> =
+        arg_default_from_ctx("faucet", DefaultFn(|| "faucet".into()));
+    const FAUCET_TOKEN: ArgDefaultFromCtx<WalletAddress> =
+        arg_default_from_ctx("token", DefaultFn(|| "XAN".into()));
     const FEE_AMOUNT: ArgDefault<token::Amount> =
         arg_default("fee-amount", DefaultFn(|| token::Amount::from(0)));
+    const FEE_PAYER_OPT: ArgOpt<WalletKeypair> = arg_opt("fee-payer");
     const FEE_TOKEN: ArgDefaultFromCtx<WalletAddress> =
         arg_default_from_ctx("fee-token", DefaultFn(|| "XAN".into()));
     const FORCE: ArgFlag = flag("force");
+    const FROM_FILE: ArgOpt<PathBuf> = arg_opt("from-file");
+    const FROM_HEX: ArgOpt<HexData> = arg_opt("from-hex");
     const GAS_LIMIT: ArgDefault<token::Amount> =
         arg_default("gas-limit", DefaultFn(|| token::Amount::from(0)));
+    const GAS_LIMIT_AUTO: ArgFlag = flag("auto-gas-limit");
+    const GAS_MARGIN: ArgDefault<u64> =
+        arg_default("gas-margin", DefaultFn(|| 20));
     const GENESIS_PATH: Arg<PathBuf> = arg("genesis-path");
     const GENESIS_VALIDATOR: ArgOpt<String> = arg("genesis-validator").opt();
+    const HASH_MEDIA: ArgFlag = flag("hash-media");
+    const HASH_MEDIA_ABOUT: &str = "Download the media of every NFT token \
+                                    from its HTTP(S) URI and embed its \
+                                    SHA-256 hash in the token's metadata.";
+    const HEIGHT_OPT: ArgOpt<u64> = arg_opt("height");
+    const INTENT_FORMAT: ArgDefault<IntentFormat> =
+        arg_default("format", DefaultFn(|| IntentFormat::Borsh));
     const INTENT_GOSSIPER_ADDR: ArgDefault<SocketAddr> = arg_default(
         "intent-gossiper",
         DefaultFn(|| {
@@ -1432,6 +2490,11 @@ pub mod args {
             SocketAddr::from_str(raw).unwrap()
         }),
     );
+    const INTENT_HASH: Arg<String> = arg("intent-hash");
+    const KEY_TTL: ArgDefault<u64> = arg_default(
+        "key-ttl",
+        DefaultFn(|| crate::wallet::agent::DEFAULT_KEY_TTL.as_secs()),
+    );
     const LEDGER_ADDRESS_ABOUT: &str =
         "Address of a ledger node as \"{scheme}://{host}:{port}\". If the \
          scheme is not supplied, it is assumed to be TCP.";
@@ -1441,1069 +2504,2551 @@ pub mod args {
             TendermintAddress::from_str(raw).unwrap()
         }));
 
-    const LEDGER_ADDRESS: Arg<TendermintAddress> = arg("ledger-address");
-    const LOCALHOST: ArgFlag = flag("localhost");
-    const MATCHMAKER_PATH: ArgOpt<PathBuf> = arg_opt("matchmaker-path");
-    const MODE: ArgOpt<String> = arg_opt("mode");
-    const MULTIADDR_OPT: ArgOpt<Multiaddr> = arg_opt("address");
-    const NET_ADDRESS: Arg<SocketAddr> = arg("net-address");
-    const NODE_OPT: ArgOpt<String> = arg_opt("node");
-    const NODE: Arg<String> = arg("node");
-    const NFT_ADDRESS: Arg<Address> = arg("nft-address");
-    const OWNER: ArgOpt<WalletAddress> = arg_opt("owner");
-    const PROPOSAL_OFFLINE: ArgFlag = flag("offline");
-    const PROTOCOL_KEY: ArgOpt<WalletPublicKey> = arg_opt("protocol-key");
-    const PRE_GENESIS_PATH: ArgOpt<PathBuf> = arg_opt("pre-genesis-path");
-    const PUBLIC_KEY: Arg<WalletPublicKey> = arg("public-key");
-    const PROPOSAL_ID: Arg<u64> = arg("proposal-id");
-    const PROPOSAL_ID_OPT: ArgOpt<u64> = arg_opt("proposal-id");
-    const PROPOSAL_VOTE: Arg<ProposalVote> = arg("vote");
-    const RAW_ADDRESS: Arg<Address> = arg("address");
-    const RAW_PUBLIC_KEY_OPT: ArgOpt<common::PublicKey> = arg_opt("public-key");
-    const REWARDS_CODE_PATH: ArgOpt<PathBuf> = arg_opt("rewards-code-path");
-    const REWARDS_KEY: ArgOpt<WalletPublicKey> = arg_opt("rewards-key");
-    const RPC_SOCKET_ADDR: ArgOpt<SocketAddr> = arg_opt("rpc");
-    const SIGNER: ArgOpt<WalletAddress> = arg_opt("signer");
-    const SIGNING_KEY_OPT: ArgOpt<WalletKeypair> = SIGNING_KEY.opt();
-    const SIGNING_KEY: Arg<WalletKeypair> = arg("signing-key");
-    const SOURCE: Arg<WalletAddress> = arg("source");
-    const SOURCE_OPT: ArgOpt<WalletAddress> = SOURCE.opt();
-    const STORAGE_KEY: Arg<storage::Key> = arg("storage-key");
-    const TARGET: Arg<WalletAddress> = arg("target");
-    const TO_STDOUT: ArgFlag = flag("stdout");
-    const TOKEN_OPT: ArgOpt<WalletAddress> = TOKEN.opt();
-    const TOKEN: Arg<WalletAddress> = arg("token");
-    const TOPIC_OPT: ArgOpt<String> = arg_opt("topic");
-    const TOPIC: Arg<String> = arg("topic");
-    const TX_CODE_PATH: ArgOpt<PathBuf> = arg_opt("tx-code-path");
-    const TX_HASH: Arg<String> = arg("tx-hash");
-    const UNSAFE_DONT_ENCRYPT: ArgFlag = flag("unsafe-dont-encrypt");
-    const UNSAFE_SHOW_SECRET: ArgFlag = flag("unsafe-show-secret");
-    const VALIDATOR: Arg<WalletAddress> = arg("validator");
-    const VALIDATOR_OPT: ArgOpt<WalletAddress> = VALIDATOR.opt();
-    const VALIDATOR_ACCOUNT_KEY: ArgOpt<WalletPublicKey> =
-        arg_opt("account-key");
-    const VALIDATOR_CONSENSUS_KEY: ArgOpt<WalletKeypair> =
-        arg_opt("consensus-key");
-    const VALIDATOR_CODE_PATH: ArgOpt<PathBuf> = arg_opt("validator-code-path");
-    const VALUE: ArgOpt<String> = arg_opt("value");
-    const WASM_CHECKSUMS_PATH: Arg<PathBuf> = arg("wasm-checksums-path");
-    const WASM_DIR: ArgOpt<PathBuf> = arg_opt("wasm-dir");
+    const LEDGER_ADDRESS: Arg<TendermintAddress> = arg("ledger-address");
+    const LEDGER_ADDRESS_OPT: ArgOpt<TendermintAddress> = LEDGER_ADDRESS.opt();
+    const LOCALHOST: ArgFlag = flag("localhost");
+    const MANIFEST_OUTPUT: ArgOpt<PathBuf> = arg_opt("manifest");
+    const MANIFEST_PATH: Arg<PathBuf> = arg("file");
+    const MATCHMAKER_FEE: ArgOpt<MatchmakerFee> = arg_opt("fee");
+    const MATCHMAKER_PATH: ArgOpt<PathBuf> = arg_opt("matchmaker-path");
+    // Tendermint's default limit of the size of a tx in the mempool
+    const MAX_TX_BYTES: ArgDefault<u64> =
+        arg_default("max-tx-bytes", DefaultFn(|| 1024 * 1024));
+    const MDNS: ArgFlag = flag("mdns");
+    const MEMO_OPT: ArgOpt<String> = arg_opt("memo");
+    const MODE: ArgOpt<String> = arg_opt("mode");
+    const MULTIADDR_OPT: ArgOpt<Multiaddr> = arg_opt("address");
+    const NET_ADDRESS: Arg<SocketAddr> = arg("net-address");
+    const NODES: ArgMulti<String> = NODE.multi();
+    const NODE: Arg<String> = arg("node");
+    const NFT_ADDRESS: Arg<Address> = arg("nft-address");
+    const NFT_ADDRESS_OPT: ArgOpt<Address> = NFT_ADDRESS.opt();
+    const NFT_DATA_PATH: ArgOpt<PathBuf> = arg_opt("nft-data-path");
+    const NFT_TOKENS: ArgFlag = flag("tokens");
+    const OFFSET: ArgDefault<u64> = arg_default("offset", DefaultFn(|| 0));
+    const OUTPUT: ArgDefault<OutputMode> =
+        arg_default("output", DefaultFn(|| OutputMode::Text));
+    const OUTPUT_PATH: ArgOpt<PathBuf> = arg_opt("output-path");
+    const OWNER: Arg<WalletAddress> = arg("owner");
+    const OWNER_OPT: ArgOpt<WalletAddress> = OWNER.opt();
+    const PAGE_LIMIT: ArgDefault<u64> = arg_default("limit", DefaultFn(|| 100));
+    const PERIOD: ArgDefault<Period> =
+        arg_default("period", DefaultFn(|| Period::Month));
+    const PROPOSAL_OFFLINE: ArgFlag = flag("offline");
+    const PROVE: ArgFlag = flag("prove");
+    const PROTOCOL_KEY: ArgOpt<WalletPublicKey> = arg_opt("protocol-key");
+    const PORT_ID: ArgDefault<PortId> = arg_default(
+        "port-id",
+        DefaultFn(|| PortId::from_str("transfer").unwrap()),
+    );
+    const PRE_GENESIS_PATH: ArgOpt<PathBuf> = arg_opt("pre-genesis-path");
+    const PUBLIC_KEY: Arg<WalletPublicKey> = arg("public-key");
+    const QUOTE_TOKEN: Arg<WalletAddress> = arg("quote-token");
+    const PROPOSAL_ID: Arg<u64> = arg("proposal-id");
+    const PROPOSAL_ID_OPT: ArgOpt<u64> = arg_opt("proposal-id");
+    const PROPOSAL_VOTE: Arg<ProposalVote> = arg("vote");
+    const RAW_ADDRESS: Arg<Address> = arg("address");
+    const RAW_PUBLIC_KEY_OPT: ArgOpt<common::PublicKey> = arg_opt("public-key");
+    const RECEIVER: Arg<String> = arg("receiver");
+    const REVEAL: ArgOpt<PathBuf> = arg_opt("reveal");
+    const REWARDS_CODE_PATH: ArgOpt<PathBuf> = arg_opt("rewards-code-path");
+    const REWARDS_KEY: ArgOpt<WalletPublicKey> = arg_opt("rewards-key");
+    const RING: ArgMulti<WalletAddress> = arg("ring").multi();
+    const RPC_SOCKET_ADDR: ArgOpt<SocketAddr> = arg_opt("rpc");
+    const SAVE_DRAFT: ArgOpt<PathBuf> = arg_opt("save-draft");
+    const SEQUENCE: Arg<u64> = arg("sequence");
+    const SIGNER: ArgOpt<WalletAddress> = arg_opt("signer");
+    const SIGNING_KEY_OPT: ArgOpt<WalletKeypair> = SIGNING_KEY.opt();
+    const SIGNING_KEY: Arg<WalletKeypair> = arg("signing-key");
+    const SOURCE: Arg<WalletAddress> = arg("source");
+    const SOURCE_OPT: ArgOpt<WalletAddress> = SOURCE.opt();
+    const STORAGE_KEY: Arg<storage::Key> = arg("storage-key");
+    const TARGET: Arg<WalletAddress> = arg("target");
+    const TARGET_HEIGHT: ArgOpt<u64> = arg_opt("target-height");
+    const TIMEOUT_HEIGHT: ArgOpt<u64> = arg_opt("timeout-height");
+    const TIMEOUT_SEC_OFFSET: ArgOpt<u64> = arg_opt("timeout-sec-offset");
+    const TO_STDOUT: ArgFlag = flag("stdout");
+    const TOKEN_OPT: ArgOpt<WalletAddress> = TOKEN.opt();
+    const TOKEN: Arg<WalletAddress> = arg("token");
+    const TOPIC_OPT: ArgOpt<String> = arg_opt("topic");
+    const TOPIC: Arg<String> = arg("topic");
+    const TX_CODE_PATH: ArgOpt<PathBuf> = arg_opt("tx-code-path");
+    const TX_HASH: Arg<String> = arg("tx-hash");
+    const UNSAFE_DONT_ENCRYPT: ArgFlag = flag("unsafe-dont-encrypt");
+    const UNSAFE_SHOW_SECRET: ArgFlag = flag("unsafe-show-secret");
+    const VALIDATOR: Arg<WalletAddress> = arg("validator");
+    const VALIDATOR_OPT: ArgOpt<WalletAddress> = VALIDATOR.opt();
+    const VALIDATOR_ACCOUNT_KEY: ArgOpt<WalletPublicKey> =
+        arg_opt("account-key");
+    const VALIDATOR_CONSENSUS_KEY: ArgOpt<WalletKeypair> =
+        arg_opt("consensus-key");
+    const VALIDATOR_CODE_PATH: ArgOpt<PathBuf> = arg_opt("validator-code-path");
+    const VALUE: ArgOpt<String> = arg_opt("value");
+    const WAIT_FOR_MATCH: ArgFlag = flag("wait-for-match");
+    const WASM_CHECKSUMS_PATH: Arg<PathBuf> = arg("wasm-checksums-path");
+    const WASM_DIR: ArgOpt<PathBuf> = arg_opt("wasm-dir");
+    const WASM_PATH: Arg<PathBuf> = arg("path");
+
+    /// Global command arguments
+    #[derive(Clone, Debug)]
+    pub struct Global {
+        pub chain_id: Option<ChainId>,
+        pub base_dir: PathBuf,
+        pub wasm_dir: Option<PathBuf>,
+        pub mode: Option<TendermintMode>,
+        pub output: OutputMode,
+    }
+
+    impl Global {
+        /// Parse global arguments
+        pub fn parse(matches: &ArgMatches) -> Self {
+            let chain_id = CHAIN_ID_OPT.parse(matches);
+            let base_dir = BASE_DIR.parse(matches);
+            let wasm_dir = WASM_DIR.parse(matches);
+            let mode = MODE.parse(matches).map(TendermintMode::from);
+            let output = OUTPUT.parse(matches);
+            Global {
+                chain_id,
+                base_dir,
+                wasm_dir,
+                mode,
+                output,
+            }
+        }
+
+        /// Add global args definition. Should be added to every top-level
+        /// command.
+        pub fn def(app: App) -> App {
+            app.arg(CHAIN_ID_OPT.def().about("The chain ID."))
+                .arg(BASE_DIR.def().about(
+                    "The base directory is where the nodes, client and wallet \
+                     configuration and state is stored. This value can also \
+                     be set via `ANOMA_BASE_DIR` environment variable, but \
+                     the argument takes precedence, if specified. Defaults to \
+                     `.anoma`.",
+                ))
+                .arg(WASM_DIR.def().about(
+                    "Directory with built WASM validity predicates, \
+                     transactions and matchmaker files. This must not be an \
+                     absolute path as the directory is nested inside the \
+                     chain directory. This value can also be set via \
+                     `ANOMA_WASM_DIR` environment variable, but the argument \
+                     takes precedence, if specified.",
+                ))
+                .arg(MODE.def().about(
+                    "The mode in which to run Anoma. Options are \n\t * \
+                     Validator (default)\n\t * Full\n\t * Seed",
+                ))
+                .arg(OUTPUT.def().about(
+                    "The format of the output of the client commands. Options \
+                     are \n\t * text (default)\n\t * json",
+                ))
+        }
+    }
+
+    /// The format in which the client commands print their results
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum OutputMode {
+        /// Human-readable text
+        Text,
+        /// A single JSON document on stdout. Any other messages are printed
+        /// to stderr.
+        Json,
+    }
+
+    impl FromStr for OutputMode {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "text" => Ok(Self::Text),
+                "json" => Ok(Self::Json),
+                _ => Err(format!(
+                    "Unknown output mode \"{}\", expected \"text\" or \"json\"",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// Transaction associated results arguments
+    #[derive(Clone, Debug)]
+    pub struct QueryResult {
+        /// Common query args
+        pub query: Query,
+        /// Hash of transaction to lookup
+        pub tx_hash: String,
+    }
+
+    impl Args for QueryResult {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let tx_hash = TX_HASH.parse(matches);
+            Self { query, tx_hash }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(
+                TX_HASH
+                    .def()
+                    .about("The hash of the transaction being looked up."),
+            )
+        }
+    }
+
+    /// Custom transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxCustom {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Path to the tx WASM code file
+        pub code_path: PathBuf,
+        /// The tx data, if any
+        pub data: Option<TxData>,
+    }
+
+    /// The source of the data of a custom transaction
+    #[derive(Clone, Debug)]
+    pub enum TxData {
+        /// Read the data from a file
+        File(PathBuf),
+        /// Read the data from stdin
+        Stdin,
+        /// The data given inline
+        Bytes(Vec<u8>),
+    }
+
+    /// Bytes given as a hex string
+    #[derive(Clone, Debug)]
+    pub struct HexData(pub Vec<u8>);
+
+    impl FromStr for HexData {
+        type Err = hex::FromHexError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            hex::decode(s).map(Self)
+        }
+    }
+
+    /// Bytes given as a base64 string
+    #[derive(Clone, Debug)]
+    pub struct Base64Data(pub Vec<u8>);
+
+    impl FromStr for Base64Data {
+        type Err = base64::DecodeError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            base64::decode(s).map(Self)
+        }
+    }
+
+    impl Args for TxCustom {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let code_path = CODE_PATH.parse(matches);
+            let data = if let Some(HexData(bytes)) = DATA_HEX.parse(matches) {
+                Some(TxData::Bytes(bytes))
+            } else if let Some(Base64Data(bytes)) = DATA_BASE64.parse(matches) {
+                Some(TxData::Bytes(bytes))
+            } else {
+                DATA_PATH_OPT.parse(matches).map(|path| {
+                    if path.as_os_str() == "-" {
+                        TxData::Stdin
+                    } else {
+                        TxData::File(path)
+                    }
+                })
+            };
+            Self {
+                tx,
+                code_path,
+                data,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(
+                    CODE_PATH
+                        .def()
+                        .about("The path to the transaction's WASM code."),
+                )
+                .arg(
+                    DATA_PATH_OPT
+                        .def()
+                        .about(
+                            "The data file at this path containing arbitrary \
+                             bytes will be passed to the transaction code \
+                             when it's executed. Use `-` to read the data \
+                             from stdin.",
+                        )
+                        .conflicts_with_all(&[DATA_HEX.name, DATA_BASE64.name]),
+                )
+                .arg(
+                    DATA_HEX
+                        .def()
+                        .about(
+                            "The hex encoded data that will be passed to the \
+                             transaction code when it's executed.",
+                        )
+                        .conflicts_with_all(&[
+                            DATA_PATH_OPT.name,
+                            DATA_BASE64.name,
+                        ]),
+                )
+                .arg(
+                    DATA_BASE64
+                        .def()
+                        .about(
+                            "The base64 encoded data that will be passed to \
+                             the transaction code when it's executed.",
+                        )
+                        .conflicts_with_all(&[
+                            DATA_PATH_OPT.name,
+                            DATA_HEX.name,
+                        ]),
+                )
+        }
+    }
+
+    /// Transfer transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxTransfer {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Transfer source address
+        pub source: WalletAddress,
+        /// Transfer target address
+        pub target: WalletAddress,
+        /// Transferred token address
+        pub token: WalletAddress,
+        /// Transferred token amount
+        pub amount: token::Amount,
+        /// Optional memo attached to the transfer
+        pub memo: Option<String>,
+    }
+
+    impl Args for TxTransfer {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let target = TARGET.parse(matches);
+            let token = TOKEN.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            let memo = MEMO_OPT.parse(matches);
+            Self {
+                tx,
+                source,
+                target,
+                token,
+                amount,
+                memo,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account address. The source's key is used to \
+                     produce the signature.",
+                ))
+                .arg(TARGET.def().about("The target account address."))
+                .arg(TOKEN.def().about("The transfer token."))
+                .arg(AMOUNT.def().about("The amount to transfer in decimal."))
+                .arg(MEMO_OPT.def().about(
+                    "An optional memo attached to the transfer, e.g. to \
+                     identify a deposit, of at most 256 bytes. The memo is \
+                     public and included in the applied transaction's event.",
+                ))
+        }
+    }
+
+    /// IBC transfer transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxIbcTransfer {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Transfer source address
+        pub source: WalletAddress,
+        /// Transfer receiver address on the other chain
+        pub receiver: String,
+        /// Transferred token address
+        pub token: WalletAddress,
+        /// Transferred token amount
+        pub amount: token::Amount,
+        /// The port from which the packet is sent
+        pub port_id: PortId,
+        /// The channel from which the packet is sent
+        pub channel_id: ChannelId,
+        /// The timeout height of the receiver's chain
+        pub timeout_height: Option<u64>,
+        /// The timeout in seconds from now
+        pub timeout_sec_offset: Option<u64>,
+    }
+
+    impl Args for TxIbcTransfer {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let receiver = RECEIVER.parse(matches);
+            let token = TOKEN.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            let port_id = PORT_ID.parse(matches);
+            let channel_id = CHANNEL_ID.parse(matches);
+            let timeout_height = TIMEOUT_HEIGHT.parse(matches);
+            let timeout_sec_offset = TIMEOUT_SEC_OFFSET.parse(matches);
+            Self {
+                tx,
+                source,
+                receiver,
+                token,
+                amount,
+                port_id,
+                channel_id,
+                timeout_height,
+                timeout_sec_offset,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account address. The source's key is used to \
+                     produce the signature.",
+                ))
+                .arg(RECEIVER.def().about(
+                    "The receiver address on the destination chain as string.",
+                ))
+                .arg(TOKEN.def().about("The transfer token."))
+                .arg(AMOUNT.def().about("The amount to transfer in decimal."))
+                .arg(PORT_ID.def().about("The port ID."))
+                .arg(CHANNEL_ID.def().about("The channel ID."))
+                .arg(
+                    TIMEOUT_HEIGHT
+                        .def()
+                        .about("The timeout height of the destination chain."),
+                )
+                .arg(TIMEOUT_SEC_OFFSET.def().about(
+                    "The timeout as seconds from now. Without a timeout \
+                     height or seconds, the transfer times out in an hour.",
+                ))
+        }
+    }
+
+    /// Testnet faucet withdrawal transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxFaucet {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The faucet account address
+        pub faucet: WalletAddress,
+        /// Withdrawal target address
+        pub target: WalletAddress,
+        /// Withdrawn token address
+        pub token: WalletAddress,
+        /// Withdrawn token amount
+        pub amount: token::Amount,
+    }
+
+    impl Args for TxFaucet {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let faucet = FAUCET.parse(matches);
+            let target = TARGET.parse(matches);
+            let token = FAUCET_TOKEN.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            Self {
+                tx,
+                faucet,
+                target,
+                token,
+                amount,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(FAUCET.def().about(
+                    "The faucet account address. Defaults to the address with \
+                     the alias \"faucet\".",
+                ))
+                .arg(TARGET.def().about(
+                    "The target account address. The target's key is used to \
+                     produce the signature.",
+                ))
+                .arg(FAUCET_TOKEN.def().about(
+                    "The token to withdraw. Defaults to the native token.",
+                ))
+                .arg(AMOUNT.def().about(
+                    "The amount to withdraw in decimal. A faucet allows at \
+                     most 1000 tokens in a single transaction.",
+                ))
+        }
+    }
+
+    /// Multi-target transfer transaction arguments
+    #[derive(Clone, Debug)]
+    pub struct TxMultiTransfer {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Transfer source address
+        pub source: WalletAddress,
+        /// Transferred token address
+        pub token: WalletAddress,
+        /// Path to the JSON file with the targets
+        pub data_path: PathBuf,
+    }
+
+    impl Args for TxMultiTransfer {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let token = TOKEN.parse(matches);
+            let data_path = DATA_PATH.parse(matches);
+            Self {
+                tx,
+                source,
+                token,
+                data_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account address. The source's key is used to \
+                     produce the signature.",
+                ))
+                .arg(TOKEN.def().about("The transfer token."))
+                .arg(DATA_PATH.def().about(
+                    "The path to a JSON file with the list of targets, each \
+                     with a \"target\" address or alias, an \"amount\" in \
+                     decimal and an optional \"memo\".",
+                ))
+        }
+    }
+
+    /// Transaction to initialize a new account
+    #[derive(Clone, Debug)]
+    pub struct TxInitAccount {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Address of the source account
+        pub source: WalletAddress,
+        /// Path to the VP WASM code file for the new account
+        pub vp_code_path: Option<PathBuf>,
+        /// Public key for the new account
+        pub public_key: WalletPublicKey,
+    }
+
+    impl Args for TxInitAccount {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let vp_code_path = CODE_PATH_OPT.parse(matches);
+            let public_key = PUBLIC_KEY.parse(matches);
+            Self {
+                tx,
+                source,
+                vp_code_path,
+                public_key,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account's address that signs the transaction.",
+                ))
+                .arg(CODE_PATH_OPT.def().about(
+                    "The path to the validity predicate WASM code to be used \
+                     for the new account. Uses the default user VP if none \
+                     specified.",
+                ))
+                .arg(PUBLIC_KEY.def().about(
+                    "A public key to be used for the new account in \
+                     hexadecimal encoding.",
+                ))
+        }
+    }
+
+    /// Transaction to reveal the public key of an implicit address
+    #[derive(Clone, Debug)]
+    pub struct TxRevealPk {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The public key to reveal
+        pub public_key: WalletPublicKey,
+    }
+
+    impl Args for TxRevealPk {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let public_key = PUBLIC_KEY.parse(matches);
+            Self { tx, public_key }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>().arg(PUBLIC_KEY.def().about(
+                "The public key to reveal. The transaction is signed with its \
+                 secret key, unless another signer is given.",
+            ))
+        }
+    }
+
+    /// Apply an application manifest
+    #[derive(Clone, Debug)]
+    pub struct Apply {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Path to the application manifest TOML file
+        pub manifest_path: PathBuf,
+    }
+
+    impl Args for Apply {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let manifest_path = MANIFEST_PATH.parse(matches);
+            Self { tx, manifest_path }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(MANIFEST_PATH.def().short('f').about(
+                    "The path to the application manifest TOML file. With \
+                     `--dry-run`, the transactions needed to apply it are \
+                     only printed.",
+                ))
+        }
+    }
+
+    /// Transaction with a batch of operations
+    #[derive(Clone, Debug)]
+    pub struct TxBatch {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Path to the batch TOML file
+        pub batch_path: PathBuf,
+    }
+
+    impl Args for TxBatch {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let batch_path = BATCH_PATH.parse(matches);
+            Self { tx, batch_path }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>().arg(
+                BATCH_PATH
+                    .def()
+                    .short('f')
+                    .about("The path to the TOML file with the operations."),
+            )
+        }
+    }
+
+    /// Transaction saved as a draft
+    #[derive(Clone, Debug)]
+    pub struct TxSubmitDraft {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Path to the draft file
+        pub draft_path: PathBuf,
+    }
+
+    impl Args for TxSubmitDraft {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let draft_path = DRAFT_PATH.parse(matches);
+            Self { tx, draft_path }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>().arg(DRAFT_PATH.def().short('f').about(
+                "The path to the draft file, in the JSON format if it has the \
+                 `json` extension, otherwise in the Borsh encoding.",
+            ))
+        }
+    }
+
+    /// Transaction to initialize a new account
+    #[derive(Clone, Debug)]
+    pub struct TxInitValidator {
+        pub tx: Tx,
+        pub source: WalletAddress,
+        pub account_key: Option<WalletPublicKey>,
+        pub consensus_key: Option<WalletKeypair>,
+        pub rewards_account_key: Option<WalletPublicKey>,
+        pub protocol_key: Option<WalletPublicKey>,
+        pub validator_vp_code_path: Option<PathBuf>,
+        pub rewards_vp_code_path: Option<PathBuf>,
+        pub commission_rate: u64,
+        pub self_bond: Option<token::Amount>,
+        pub unsafe_dont_encrypt: bool,
+    }
+
+    impl Args for TxInitValidator {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let account_key = VALIDATOR_ACCOUNT_KEY.parse(matches);
+            let consensus_key = VALIDATOR_CONSENSUS_KEY.parse(matches);
+            let rewards_account_key = REWARDS_KEY.parse(matches);
+            let protocol_key = PROTOCOL_KEY.parse(matches);
+            let validator_vp_code_path = VALIDATOR_CODE_PATH.parse(matches);
+            let rewards_vp_code_path = REWARDS_CODE_PATH.parse(matches);
+            let commission_rate = COMMISSION_RATE_DEFAULT.parse(matches);
+            let self_bond = AMOUNT_OPT.parse(matches);
+            let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            Self {
+                tx,
+                source,
+                account_key,
+                consensus_key,
+                rewards_account_key,
+                protocol_key,
+                validator_vp_code_path,
+                rewards_vp_code_path,
+                commission_rate,
+                self_bond,
+                unsafe_dont_encrypt,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about(
+                    "The source account's address that signs the transaction.",
+                ))
+                .arg(VALIDATOR_ACCOUNT_KEY.def().about(
+                    "A public key for the validator account. A new one will \
+                     be generated if none given.",
+                ))
+                .arg(VALIDATOR_CONSENSUS_KEY.def().about(
+                    "A consensus key for the validator account. A new one \
+                     will be generated if none given.",
+                ))
+                .arg(REWARDS_KEY.def().about(
+                    "A public key for the staking reward account. A new one \
+                     will be generated if none given.",
+                ))
+                .arg(PROTOCOL_KEY.def().about(
+                    "A public key for signing protocol transactions. A new \
+                     one will be generated if none given.",
+                ))
+                .arg(VALIDATOR_CODE_PATH.def().about(
+                    "The path to the validity predicate WASM code to be used \
+                     for the validator account. Uses the default validator VP \
+                     if none specified.",
+                ))
+                .arg(REWARDS_CODE_PATH.def().about(
+                    "The path to the validity predicate WASM code to be used \
+                     for the staking reward account. Uses the default staking \
+                     reward VP if none specified.",
+                ))
+                .arg(COMMISSION_RATE_DEFAULT.def().about(
+                    "The initial commission rate in basis points, i.e. 100 is \
+                     1%. Defaults to 0.",
+                ))
+                .arg(AMOUNT_OPT.def().about(
+                    "The amount of tokens to transfer from the source to the \
+                     new validator and self-bond. The validator has no \
+                     self-bond if none given.",
+                ))
+                .arg(UNSAFE_DONT_ENCRYPT.def().about(
+                    "UNSAFE: Do not encrypt the generated keypairs. Do not \
+                     use this for keys used in a live network.",
+                ))
+        }
+    }
+
+    /// Transaction to update a VP arguments
+    #[derive(Clone, Debug)]
+    pub struct TxUpdateVp {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Path to the VP WASM code file
+        pub vp_code_path: PathBuf,
+        /// Address of the account whose VP is to be updated
+        pub addr: WalletAddress,
+    }
+
+    impl Args for TxUpdateVp {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let vp_code_path = CODE_PATH.parse(matches);
+            let addr = ADDRESS.parse(matches);
+            Self {
+                tx,
+                vp_code_path,
+                addr,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(
+                    CODE_PATH.def().about(
+                        "The path to the new validity predicate WASM code.",
+                    ),
+                )
+                .arg(ADDRESS.def().about(
+                    "The account's address. It's key is used to produce the \
+                     signature.",
+                ))
+        }
+    }
+
+    /// Bond arguments
+    #[derive(Clone, Debug)]
+    pub struct Bond {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Amount of tokens to stake in a bond
+        pub amount: token::Amount,
+        /// Source address for delegations. For self-bonds, the validator is
+        /// also the source.
+        pub source: Option<WalletAddress>,
+    }
+
+    impl Args for Bond {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
+            Self {
+                tx,
+                validator,
+                amount,
+                source,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(AMOUNT.def().about("Amount of tokens to stake in a bond."))
+                .arg(SOURCE_OPT.def().about(
+                    "Source address for delegations. For self-bonds, the \
+                     validator is also the source.",
+                ))
+        }
+    }
 
-    /// Global command arguments
+    /// Bonds to several validators arguments
     #[derive(Clone, Debug)]
-    pub struct Global {
-        pub chain_id: Option<ChainId>,
-        pub base_dir: PathBuf,
-        pub wasm_dir: Option<PathBuf>,
-        pub mode: Option<TendermintMode>,
+    pub struct BondMany {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Source address of the delegations
+        pub source: WalletAddress,
+        /// Path to the bonds file
+        pub bonds_path: PathBuf,
     }
 
-    impl Global {
-        /// Parse global arguments
-        pub fn parse(matches: &ArgMatches) -> Self {
-            let chain_id = CHAIN_ID_OPT.parse(matches);
-            let base_dir = BASE_DIR.parse(matches);
-            let wasm_dir = WASM_DIR.parse(matches);
-            let mode = MODE.parse(matches).map(TendermintMode::from);
-            Global {
-                chain_id,
-                base_dir,
-                wasm_dir,
-                mode,
+    impl Args for BondMany {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let source = SOURCE.parse(matches);
+            let bonds_path = BONDS_PATH.parse(matches);
+            Self {
+                tx,
+                source,
+                bonds_path,
             }
         }
 
-        /// Add global args definition. Should be added to every top-level
-        /// command.
-        pub fn def(app: App) -> App {
-            app.arg(CHAIN_ID_OPT.def().about("The chain ID."))
-                .arg(BASE_DIR.def().about(
-                    "The base directory is where the nodes, client and wallet \
-                     configuration and state is stored. This value can also \
-                     be set via `ANOMA_BASE_DIR` environment variable, but \
-                     the argument takes precedence, if specified. Defaults to \
-                     `.anoma`.",
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(SOURCE.def().about("Source address of the delegations."))
+                .arg(BONDS_PATH.def().short('f').about(
+                    "The path to the file with the validators and amounts to \
+                     bond, in the JSON format if it has the `json` extension, \
+                     otherwise in the TOML format.",
                 ))
-                .arg(WASM_DIR.def().about(
-                    "Directory with built WASM validity predicates, \
-                     transactions and matchmaker files. This must not be an \
-                     absolute path as the directory is nested inside the \
-                     chain directory. This value can also be set via \
-                     `ANOMA_WASM_DIR` environment variable, but the argument \
-                     takes precedence, if specified.",
+        }
+    }
+
+    /// Unbond arguments
+    #[derive(Clone, Debug)]
+    pub struct Unbond {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Amount of tokens to unbond from a bond
+        pub amount: token::Amount,
+        /// Source address for unbonding from delegations. For unbonding from
+        /// self-bonds, the validator is also the source
+        pub source: Option<WalletAddress>,
+    }
+
+    impl Args for Unbond {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let amount = AMOUNT.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
+            Self {
+                tx,
+                validator,
+                amount,
+                source,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(
+                    AMOUNT
+                        .def()
+                        .about("Amount of tokens to unbond from a bond."),
+                )
+                .arg(SOURCE_OPT.def().about(
+                    "Source address for unbonding from delegations. For \
+                     unbonding from self-bonds, the validator is also the \
+                     source.",
                 ))
-                .arg(MODE.def().about(
-                    "The mode in which to run Anoma. Options are \n\t * \
-                     Validator (default)\n\t * Full\n\t * Seed",
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InitProposal {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// The proposal file path
+        pub proposal_data: PathBuf,
+        /// Flag if proposal should be run offline
+        pub offline: bool,
+    }
+
+    impl Args for InitProposal {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let proposal_data = DATA_PATH.parse(matches);
+            let offline = PROPOSAL_OFFLINE.parse(matches);
+
+            Self {
+                tx,
+                proposal_data,
+                offline,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(DATA_PATH.def().about(
+                    "The data path file that describes the proposal, in JSON \
+                     or, with the `toml` extension, in TOML.",
+                ))
+                .arg(
+                    PROPOSAL_OFFLINE
+                        .def()
+                        .about("Flag if the proposal vote should run offline."),
+                )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct VoteProposal {
+        /// Common tx arguments
+        pub tx: Tx,
+        /// Proposal id
+        pub proposal_id: Option<u64>,
+        /// The vote
+        pub vote: ProposalVote,
+        /// Flag if proposal vote should be run offline
+        pub offline: bool,
+        /// The proposal file path
+        pub proposal_data: Option<PathBuf>,
+    }
+
+    impl Args for VoteProposal {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
+            let vote = PROPOSAL_VOTE.parse(matches);
+            let offline = PROPOSAL_OFFLINE.parse(matches);
+            let proposal_data = DATA_PATH_OPT.parse(matches);
+
+            Self {
+                tx,
+                proposal_id,
+                vote,
+                offline,
+                proposal_data,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(
+                    PROPOSAL_ID_OPT
+                        .def()
+                        .about("The proposal identifier.")
+                        .conflicts_with_all(&[
+                            PROPOSAL_OFFLINE.name,
+                            DATA_PATH_OPT.name,
+                        ]),
+                )
+                .arg(PROPOSAL_VOTE.def().about(
+                    "The vote for the proposal. Either yay, nay or abstain.",
                 ))
+                .arg(
+                    PROPOSAL_OFFLINE
+                        .def()
+                        .about("Flag if the proposal vote should run offline.")
+                        .conflicts_with(PROPOSAL_ID.name),
+                )
+                .arg(
+                    DATA_PATH_OPT
+                        .def()
+                        .about(
+                            "The data path file (json) that describes the \
+                             proposal.",
+                        )
+                        .conflicts_with(PROPOSAL_ID.name),
+                )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryProposal {
+        /// Common query args
+        pub query: Query,
+        /// Proposal id
+        pub proposal_id: Option<u64>,
+    }
+
+    impl Args for QueryProposal {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
+
+            Self { query, proposal_id }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx>()
+                .arg(PROPOSAL_ID_OPT.def().about("The proposal identifier."))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct QueryProposalResult {
+        /// Common query args
+        pub query: Query,
+        /// Proposal id
+        pub proposal_id: Option<u64>,
+        /// Flag if proposal result should be run on offline data
+        pub offline: bool,
+        /// The folder containing the proposal and votes
+        pub proposal_folder: Option<PathBuf>,
+    }
+
+    impl Args for QueryProposalResult {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
+            let offline = PROPOSAL_OFFLINE.parse(matches);
+            let proposal_folder = DATA_PATH_OPT.parse(matches);
+
+            Self {
+                query,
+                proposal_id,
+                offline,
+                proposal_folder,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(PROPOSAL_ID_OPT.def().about("The proposal identifier."))
+                .arg(
+                    PROPOSAL_OFFLINE
+                        .def()
+                        .about(
+                            "Flag if the proposal result should run on \
+                             offline data.",
+                        )
+                        .conflicts_with(PROPOSAL_ID.name),
+                )
+                .arg(
+                    DATA_PATH_OPT
+                        .def()
+                        .about(
+                            "The path to the folder containing the proposal \
+                             json and votes",
+                        )
+                        .conflicts_with(PROPOSAL_ID.name),
+                )
         }
     }
 
-    /// Transaction associated results arguments
     #[derive(Clone, Debug)]
-    pub struct QueryResult {
+    pub struct QueryProtocolParameters {
         /// Common query args
         pub query: Query,
-        /// Hash of transaction to lookup
-        pub tx_hash: String,
     }
 
-    impl Args for QueryResult {
+    impl Args for QueryProtocolParameters {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
-            let tx_hash = TX_HASH.parse(matches);
-            Self { query, tx_hash }
+
+            Self { query }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Query>().arg(
-                TX_HASH
-                    .def()
-                    .about("The hash of the transaction being looked up."),
-            )
+            app.add_args::<Query>()
         }
     }
 
-    /// Custom transaction arguments
+    /// Withdraw arguments
     #[derive(Clone, Debug)]
-    pub struct TxCustom {
+    pub struct Withdraw {
         /// Common tx arguments
         pub tx: Tx,
-        /// Path to the tx WASM code file
-        pub code_path: PathBuf,
-        /// Path to the data file
-        pub data_path: Option<PathBuf>,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Source address for withdrawing from delegations. For withdrawing
+        /// from self-bonds, the validator is also the source
+        pub source: Option<WalletAddress>,
     }
 
-    impl Args for TxCustom {
+    impl Args for Withdraw {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let code_path = CODE_PATH.parse(matches);
-            let data_path = DATA_PATH_OPT.parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
             Self {
                 tx,
-                code_path,
-                data_path,
+                validator,
+                source,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(
-                    CODE_PATH
-                        .def()
-                        .about("The path to the transaction's WASM code."),
-                )
-                .arg(DATA_PATH_OPT.def().about(
-                    "The data file at this path containing arbitrary bytes \
-                     will be passed to the transaction code when it's \
-                     executed.",
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(SOURCE_OPT.def().about(
+                    "Source address for withdrawing from delegations. For \
+                     withdrawing from self-bonds, the validator is also the \
+                     source.",
                 ))
         }
     }
 
-    /// Transfer transaction arguments
+    /// Claim rewards arguments
     #[derive(Clone, Debug)]
-    pub struct TxTransfer {
+    pub struct ClaimRewards {
         /// Common tx arguments
         pub tx: Tx,
-        /// Transfer source address
-        pub source: WalletAddress,
-        /// Transfer target address
-        pub target: WalletAddress,
-        /// Transferred token address
-        pub token: WalletAddress,
-        /// Transferred token amount
-        pub amount: token::Amount,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Source address for claiming the rewards of delegations. For
+        /// claiming the rewards of self-bonds, the validator is also the
+        /// source
+        pub source: Option<WalletAddress>,
     }
 
-    impl Args for TxTransfer {
+    impl Args for ClaimRewards {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let source = SOURCE.parse(matches);
-            let target = TARGET.parse(matches);
-            let token = TOKEN.parse(matches);
-            let amount = AMOUNT.parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
             Self {
                 tx,
+                validator,
                 source,
-                target,
-                token,
-                amount,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(SOURCE.def().about(
-                    "The source account address. The source's key is used to \
-                     produce the signature.",
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(SOURCE_OPT.def().about(
+                    "Source address for claiming the rewards of delegations. \
+                     For claiming the rewards of self-bonds, the validator is \
+                     also the source.",
                 ))
-                .arg(TARGET.def().about("The target account address."))
-                .arg(TOKEN.def().about("The transfer token."))
-                .arg(AMOUNT.def().about("The amount to transfer in decimal."))
         }
     }
 
-    /// Transaction to initialize a new account
+    /// Auto-compounding arguments
     #[derive(Clone, Debug)]
-    pub struct TxInitAccount {
+    pub struct AutoCompound {
         /// Common tx arguments
         pub tx: Tx,
-        /// Address of the source account
-        pub source: WalletAddress,
-        /// Path to the VP WASM code file for the new account
-        pub vp_code_path: Option<PathBuf>,
-        /// Public key for the new account
-        pub public_key: WalletPublicKey,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// Source address for delegations. For self-bonds, the validator is
+        /// also the source
+        pub source: Option<WalletAddress>,
+        /// Disable auto-compounding instead of enabling it
+        pub disable: bool,
     }
 
-    impl Args for TxInitAccount {
+    impl Args for AutoCompound {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let source = SOURCE.parse(matches);
-            let vp_code_path = CODE_PATH_OPT.parse(matches);
-            let public_key = PUBLIC_KEY.parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
+            let disable = DISABLE.parse(matches);
             Self {
                 tx,
+                validator,
                 source,
-                vp_code_path,
-                public_key,
+                disable,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(SOURCE.def().about(
-                    "The source account's address that signs the transaction.",
-                ))
-                .arg(CODE_PATH_OPT.def().about(
-                    "The path to the validity predicate WASM code to be used \
-                     for the new account. Uses the default user VP if none \
-                     specified.",
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(SOURCE_OPT.def().about(
+                    "Source address for delegations. For self-bonds, the \
+                     validator is also the source.",
                 ))
-                .arg(PUBLIC_KEY.def().about(
-                    "A public key to be used for the new account in \
-                     hexadecimal encoding.",
+                .arg(DISABLE.def().about(
+                    "Disable auto-compounding, so that the rewards accumulate \
+                     until they are claimed.",
                 ))
         }
     }
 
-    /// Transaction to initialize a new account
+    /// Commission rate change arguments
     #[derive(Clone, Debug)]
-    pub struct TxInitValidator {
+    pub struct ChangeCommissionRate {
+        /// Common tx arguments
         pub tx: Tx,
-        pub source: WalletAddress,
-        pub account_key: Option<WalletPublicKey>,
-        pub consensus_key: Option<WalletKeypair>,
-        pub rewards_account_key: Option<WalletPublicKey>,
-        pub protocol_key: Option<WalletPublicKey>,
-        pub validator_vp_code_path: Option<PathBuf>,
-        pub rewards_vp_code_path: Option<PathBuf>,
-        pub unsafe_dont_encrypt: bool,
+        /// Validator address
+        pub validator: WalletAddress,
+        /// The new commission rate in basis points
+        pub rate: u64,
     }
 
-    impl Args for TxInitValidator {
+    impl Args for ChangeCommissionRate {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let source = SOURCE.parse(matches);
-            let account_key = VALIDATOR_ACCOUNT_KEY.parse(matches);
-            let consensus_key = VALIDATOR_CONSENSUS_KEY.parse(matches);
-            let rewards_account_key = REWARDS_KEY.parse(matches);
-            let protocol_key = PROTOCOL_KEY.parse(matches);
-            let validator_vp_code_path = VALIDATOR_CODE_PATH.parse(matches);
-            let rewards_vp_code_path = REWARDS_CODE_PATH.parse(matches);
-            let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            let validator = VALIDATOR.parse(matches);
+            let rate = COMMISSION_RATE.parse(matches);
             Self {
                 tx,
-                source,
-                account_key,
-                consensus_key,
-                rewards_account_key,
-                protocol_key,
-                validator_vp_code_path,
-                rewards_vp_code_path,
-                unsafe_dont_encrypt,
+                validator,
+                rate,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(SOURCE.def().about(
-                    "The source account's address that signs the transaction.",
-                ))
-                .arg(VALIDATOR_ACCOUNT_KEY.def().about(
-                    "A public key for the validator account. A new one will \
-                     be generated if none given.",
-                ))
-                .arg(VALIDATOR_CONSENSUS_KEY.def().about(
-                    "A consensus key for the validator account. A new one \
-                     will be generated if none given.",
-                ))
-                .arg(REWARDS_KEY.def().about(
-                    "A public key for the staking reward account. A new one \
-                     will be generated if none given.",
-                ))
-                .arg(PROTOCOL_KEY.def().about(
-                    "A public key for signing protocol transactions. A new \
-                     one will be generated if none given.",
-                ))
-                .arg(VALIDATOR_CODE_PATH.def().about(
-                    "The path to the validity predicate WASM code to be used \
-                     for the validator account. Uses the default validator VP \
-                     if none specified.",
-                ))
-                .arg(REWARDS_CODE_PATH.def().about(
-                    "The path to the validity predicate WASM code to be used \
-                     for the staking reward account. Uses the default staking \
-                     reward VP if none specified.",
-                ))
-                .arg(UNSAFE_DONT_ENCRYPT.def().about(
-                    "UNSAFE: Do not encrypt the generated keypairs. Do not \
-                     use this for keys used in a live network.",
+                .arg(VALIDATOR.def().about("Validator address."))
+                .arg(COMMISSION_RATE.def().about(
+                    "The new commission rate in basis points, i.e. 100 is 1%. \
+                     It must not differ from the current rate by more than \
+                     the maximum change per epoch.",
                 ))
         }
     }
 
-    /// Transaction to update a VP arguments
+    /// Validator unjail arguments
     #[derive(Clone, Debug)]
-    pub struct TxUpdateVp {
+    pub struct Unjail {
         /// Common tx arguments
         pub tx: Tx,
-        /// Path to the VP WASM code file
-        pub vp_code_path: PathBuf,
-        /// Address of the account whose VP is to be updated
-        pub addr: WalletAddress,
+        /// Validator address
+        pub validator: WalletAddress,
     }
 
-    impl Args for TxUpdateVp {
+    impl Args for Unjail {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let vp_code_path = CODE_PATH.parse(matches);
-            let addr = ADDRESS.parse(matches);
-            Self {
-                tx,
-                vp_code_path,
-                addr,
-            }
+            let validator = VALIDATOR.parse(matches);
+            Self { tx, validator }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(
-                    CODE_PATH.def().about(
-                        "The path to the new validity predicate WASM code.",
-                    ),
-                )
-                .arg(ADDRESS.def().about(
-                    "The account's address. It's key is used to produce the \
-                     signature.",
-                ))
+                .arg(VALIDATOR.def().about("Validator address."))
         }
     }
 
-    /// Bond arguments
+    // Transaction to create a new nft
     #[derive(Clone, Debug)]
-    pub struct Bond {
-        /// Common tx arguments
+    pub struct NftCreate {
+        /// Common tx argumentsips
         pub tx: Tx,
-        /// Validator address
-        pub validator: WalletAddress,
-        /// Amount of tokens to stake in a bond
-        pub amount: token::Amount,
-        /// Source address for delegations. For self-bonds, the validator is
-        /// also the source.
-        pub source: Option<WalletAddress>,
+        /// Path to the nft file description
+        pub nft_data: PathBuf,
+        /// Embed the hashes of the tokens' media in their metadata
+        pub hash_media: bool,
     }
 
-    impl Args for Bond {
+    impl Args for NftCreate {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let validator = VALIDATOR.parse(matches);
-            let amount = AMOUNT.parse(matches);
-            let source = SOURCE_OPT.parse(matches);
+            let data_path = DATA_PATH.parse(matches);
+            let hash_media = HASH_MEDIA.parse(matches);
+
             Self {
                 tx,
-                validator,
-                amount,
-                source,
+                nft_data: data_path,
+                hash_media,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(VALIDATOR.def().about("Validator address."))
-                .arg(AMOUNT.def().about("Amount of tokens to stake in a bond."))
-                .arg(SOURCE_OPT.def().about(
-                    "Source address for delegations. For self-bonds, the \
-                     validator is also the source.",
+                .arg(DATA_PATH.def().about(
+                    "The path nft description file, in JSON or, with the toml \
+                     extension, in TOML.",
                 ))
+                .arg(HASH_MEDIA.def().about(HASH_MEDIA_ABOUT))
         }
     }
-
-    /// Unbond arguments
-    #[derive(Clone, Debug)]
-    pub struct Unbond {
-        /// Common tx arguments
-        pub tx: Tx,
-        /// Validator address
-        pub validator: WalletAddress,
-        /// Amount of tokens to unbond from a bond
-        pub amount: token::Amount,
-        /// Source address for unbonding from delegations. For unbonding from
-        /// self-bonds, the validator is also the source
-        pub source: Option<WalletAddress>,
+
+    /// Check an NFT definition file
+    #[derive(Clone, Debug)]
+    pub struct NftValidate {
+        /// Path to the NFT definition file
+        pub data_path: PathBuf,
+        /// The file defines tokens to mint rather than an NFT
+        pub tokens: bool,
     }
 
-    impl Args for Unbond {
+    impl Args for NftValidate {
         fn parse(matches: &ArgMatches) -> Self {
-            let tx = Tx::parse(matches);
-            let validator = VALIDATOR.parse(matches);
-            let amount = AMOUNT.parse(matches);
-            let source = SOURCE_OPT.parse(matches);
-            Self {
-                tx,
-                validator,
-                amount,
-                source,
-            }
+            let data_path = DATA_PATH.parse(matches);
+            let tokens = NFT_TOKENS.parse(matches);
+            Self { data_path, tokens }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
-                .arg(VALIDATOR.def().about("Validator address."))
-                .arg(
-                    AMOUNT
-                        .def()
-                        .about("Amount of tokens to unbond from a bond."),
-                )
-                .arg(SOURCE_OPT.def().about(
-                    "Source address for unbonding from delegations. For \
-                     unbonding from self-bonds, the validator is also the \
-                     source.",
+            app.arg(DATA_PATH.def().about("The path to the NFT file."))
+                .arg(NFT_TOKENS.def().about(
+                    "The file defines tokens to mint with the mint-nft \
+                     command rather than a new NFT.",
                 ))
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct InitProposal {
+    pub struct NftMint {
         /// Common tx arguments
         pub tx: Tx,
-        /// The proposal file path
-        pub proposal_data: PathBuf,
-        /// Flag if proposal should be run offline
-        pub offline: bool,
+        /// The nft address
+        pub nft_address: Address,
+        /// The nft token description
+        pub nft_data: Option<PathBuf>,
+        /// A directory of assets to pin to IPFS and mint as tokens, instead
+        /// of the token description
+        pub assets_dir: Option<PathBuf>,
+        /// The maximum size of every tx minting the assets
+        pub max_tx_bytes: u64,
+        /// Embed the hashes of the tokens' media in their metadata
+        pub hash_media: bool,
     }
 
-    impl Args for InitProposal {
+    impl Args for NftMint {
         fn parse(matches: &ArgMatches) -> Self {
             let tx = Tx::parse(matches);
-            let proposal_data = DATA_PATH.parse(matches);
-            let offline = PROPOSAL_OFFLINE.parse(matches);
+            let nft_address = NFT_ADDRESS.parse(matches);
+            let data_path = DATA_PATH_OPT.parse(matches);
+            let assets_dir = ASSETS_DIR.parse(matches);
+            let max_tx_bytes = MAX_TX_BYTES.parse(matches);
+            let hash_media = HASH_MEDIA.parse(matches);
 
             Self {
                 tx,
-                proposal_data,
-                offline,
+                nft_address,
+                nft_data: data_path,
+                assets_dir,
+                max_tx_bytes,
+                hash_media,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Tx>()
-                .arg(DATA_PATH.def().about(
-                    "The data path file (json) that describes the proposal.",
+                .arg(NFT_ADDRESS.def().about("The nft address."))
+                .arg(
+                    DATA_PATH_OPT
+                        .def()
+                        .about(
+                            "The data path file that describes the nft \
+                             tokens, in JSON or, with the toml extension, in \
+                             TOML.",
+                        )
+                        .required_unless_present(ASSETS_DIR.name)
+                        .conflicts_with(ASSETS_DIR.name),
+                )
+                .arg(ASSETS_DIR.def().about(
+                    "A directory of asset files to pin to the IPFS node \
+                     configured in the client's config and mint as new \
+                     tokens, in as many transactions as needed.",
+                ))
+                .arg(MAX_TX_BYTES.def().about(
+                    "The maximum size in bytes of every transaction minting \
+                     the tokens of the assets. Defaults to 1 MiB.",
                 ))
                 .arg(
-                    PROPOSAL_OFFLINE
+                    HASH_MEDIA
                         .def()
-                        .about("Flag if the proposal vote should run offline."),
+                        .about(HASH_MEDIA_ABOUT)
+                        .conflicts_with(ASSETS_DIR.name),
                 )
         }
     }
 
+    /// Query the storage sub-space of an account
     #[derive(Clone, Debug)]
-    pub struct VoteProposal {
-        /// Common tx arguments
-        pub tx: Tx,
-        /// Proposal id
-        pub proposal_id: Option<u64>,
-        /// The vote
-        pub vote: ProposalVote,
-        /// Flag if proposal vote should be run offline
-        pub offline: bool,
-        /// The proposal file path
-        pub proposal_data: Option<PathBuf>,
+    pub struct QueryAccount {
+        /// Common query args
+        pub query: Query,
+        /// Address of the account
+        pub address: WalletAddress,
     }
 
-    impl Args for VoteProposal {
+    impl Args for QueryAccount {
         fn parse(matches: &ArgMatches) -> Self {
-            let tx = Tx::parse(matches);
-            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
-            let vote = PROPOSAL_VOTE.parse(matches);
-            let offline = PROPOSAL_OFFLINE.parse(matches);
-            let proposal_data = DATA_PATH_OPT.parse(matches);
+            let query = Query::parse(matches);
+            let address = ADDRESS.parse(matches);
+            Self { query, address }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(ADDRESS.def().about("The address of the account to show."))
+        }
+    }
+
+    /// Query token balance(s)
+    #[derive(Clone, Debug)]
+    pub struct QueryBalance {
+        /// Common query args
+        pub query: Query,
+        /// Address of an owner
+        pub owner: Option<WalletAddress>,
+        /// Address of a token
+        pub token: Option<WalletAddress>,
+    }
 
+    impl Args for QueryBalance {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let owner = OWNER_OPT.parse(matches);
+            let token = TOKEN_OPT.parse(matches);
             Self {
-                tx,
-                proposal_id,
-                vote,
-                offline,
-                proposal_data,
+                query,
+                owner,
+                token,
             }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
+            app.add_args::<Query>()
                 .arg(
-                    PROPOSAL_ID_OPT
+                    OWNER_OPT
                         .def()
-                        .about("The proposal identifier.")
-                        .conflicts_with_all(&[
-                            PROPOSAL_OFFLINE.name,
-                            DATA_PATH_OPT.name,
-                        ]),
+                        .about("The account address whose balance to query."),
                 )
                 .arg(
-                    PROPOSAL_VOTE
+                    TOKEN_OPT
                         .def()
-                        .about("The vote for the proposal. Either yay or nay."),
+                        .about("The token's address whose balance to query."),
                 )
+        }
+    }
+
+    /// Helper struct for generating intents
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct ExchangeDefinition {
+        /// The source address
+        pub addr: String,
+        /// The token to be sold
+        pub token_sell: String,
+        /// The minimum rate
+        pub rate_min: String,
+        /// The maximum amount of token to be sold
+        pub max_sell: String,
+        /// The token to be bought
+        pub token_buy: String,
+        /// The amount of token to be bought
+        pub min_buy: String,
+        /// The path to the wasm vp code
+        pub vp_path: Option<String>,
+        /// Whether the exchange can be filled in parts
+        #[serde(default)]
+        pub partial: bool,
+        /// The maximum fee paid to the matchmaker, if any
+        #[serde(default)]
+        pub max_fee: Option<String>,
+    }
+
+    impl TryFrom<ExchangeDefinition> for Exchange {
+        type Error = &'static str;
+
+        fn try_from(
+            value: ExchangeDefinition,
+        ) -> Result<Exchange, Self::Error> {
+            let vp = read_exchange_vp(value.vp_path);
+
+            let addr = Address::decode(value.addr)
+                .expect("Addr should be a valid address");
+            let token_buy = Address::decode(value.token_buy)
+                .expect("Token_buy should be a valid address");
+            let token_sell = Address::decode(value.token_sell)
+                .expect("Token_sell should be a valid address");
+            let min_buy = token::Amount::from_str(&value.min_buy)
+                .expect("Min_buy must be convertible to number");
+            let max_sell = token::Amount::from_str(&value.max_sell)
+                .expect("Max_sell must be convertible to number");
+            let rate_min = DecimalWrapper::from_str(&value.rate_min)
+                .expect("Max_sell must be convertible to decimal.");
+            let max_fee = read_exchange_max_fee(value.max_fee);
+
+            Ok(Exchange {
+                addr,
+                token_sell,
+                rate_min,
+                max_sell,
+                token_buy,
+                min_buy,
+                vp,
+                nft: None,
+                expires: None,
+                partial: value.partial,
+                max_fee,
+            })
+        }
+    }
+
+    /// Helper struct for generating intents that exchange a token of an NFT
+    /// for an amount of a fungible token
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct NftExchangeDefinition {
+        /// The source address
+        pub addr: String,
+        /// The address of the NFT
+        pub nft: String,
+        /// The ID of the NFT's token
+        pub token_id: String,
+        /// Whether the token is sold or bought
+        pub side: NftSide,
+        /// The fungible token paid for the NFT's token
+        pub token: String,
+        /// The minimum price when selling, or the maximum price including
+        /// the NFT's royalty when buying
+        pub price: String,
+        /// The path to the wasm vp code
+        pub vp_path: Option<String>,
+        /// The maximum fee paid to the matchmaker when buying, if any
+        #[serde(default)]
+        pub max_fee: Option<String>,
+    }
+
+    impl TryFrom<NftExchangeDefinition> for Exchange {
+        type Error = &'static str;
+
+        fn try_from(
+            value: NftExchangeDefinition,
+        ) -> Result<Exchange, Self::Error> {
+            let vp = read_exchange_vp(value.vp_path);
+
+            let addr = Address::decode(value.addr)
+                .expect("Addr should be a valid address");
+            let nft = Address::decode(value.nft)
+                .expect("Nft should be a valid address");
+            let token = Address::decode(value.token)
+                .expect("Token should be a valid address");
+            let price = token::Amount::from_str(&value.price)
+                .expect("Price must be convertible to number");
+            let max_fee = read_exchange_max_fee(value.max_fee);
+            let (token_sell, max_sell, token_buy, min_buy) = match value.side {
+                NftSide::Sell => (nft, token::Amount::default(), token, price),
+                NftSide::Buy => (token, price, nft, token::Amount::default()),
+            };
+
+            Ok(Exchange {
+                addr,
+                token_sell,
+                rate_min: DecimalWrapper::default(),
+                max_sell,
+                token_buy,
+                min_buy,
+                vp,
+                nft: Some(ExchangeNft {
+                    side: value.side,
+                    token_id: value.token_id,
+                }),
+                expires: None,
+                partial: false,
+                max_fee,
+            })
+        }
+    }
+
+    /// Read the vp code of an exchange, if any
+    fn read_exchange_vp(vp_path: Option<String>) -> Option<Vec<u8>> {
+        let path = vp_path?;
+        match std::fs::read(&path) {
+            Ok(wasm) => Some(wasm),
+            Err(_) => {
+                eprintln!("File {} was not found.", path);
+                None
+            }
+        }
+    }
+
+    /// Read the maximum fee of an exchange, which is zero if not set
+    fn read_exchange_max_fee(max_fee: Option<String>) -> token::Amount {
+        max_fee
+            .map(|max_fee| {
+                token::Amount::from_str(&max_fee)
+                    .expect("Max_fee must be convertible to number")
+            })
+            .unwrap_or_default()
+    }
+
+    /// Query PoS bond(s)
+    #[derive(Clone, Debug)]
+    pub struct QueryBonds {
+        /// Common query args
+        pub query: Query,
+        /// Address of an owner
+        pub owner: Option<WalletAddress>,
+        /// Address of a validator
+        pub validator: Option<WalletAddress>,
+    }
+
+    impl Args for QueryBonds {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let owner = OWNER_OPT.parse(matches);
+            let validator = VALIDATOR_OPT.parse(matches);
+            Self {
+                query,
+                owner,
+                validator,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
                 .arg(
-                    PROPOSAL_OFFLINE
-                        .def()
-                        .about("Flag if the proposal vote should run offline.")
-                        .conflicts_with(PROPOSAL_ID.name),
+                    OWNER_OPT.def().about(
+                        "The owner account address whose bonds to query.",
+                    ),
                 )
                 .arg(
-                    DATA_PATH_OPT
+                    VALIDATOR_OPT
                         .def()
-                        .about(
-                            "The data path file (json) that describes the \
-                             proposal.",
-                        )
-                        .conflicts_with(PROPOSAL_ID.name),
+                        .about("The validator's address whose bonds to query."),
                 )
         }
     }
 
+    /// Query PoS unbonds
+    #[derive(Clone, Debug)]
+    pub struct QueryUnbonds {
+        /// Common query args
+        pub query: Query,
+        /// Address of an owner
+        pub owner: WalletAddress,
+    }
+
+    impl Args for QueryUnbonds {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let owner = OWNER.parse(matches);
+            Self { query, owner }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query>().arg(
+                OWNER
+                    .def()
+                    .about("The owner account address whose unbonds to query."),
+            )
+        }
+    }
+
+    /// Query PoS voting power
     #[derive(Clone, Debug)]
-    pub struct QueryProposal {
+    pub struct QueryVotingPower {
         /// Common query args
         pub query: Query,
-        /// Proposal id
-        pub proposal_id: Option<u64>,
+        /// Address of a validator
+        pub validator: Option<WalletAddress>,
+        /// Epoch in which to find voting power
+        pub epoch: Option<Epoch>,
     }
 
-    impl Args for QueryProposal {
+    impl Args for QueryVotingPower {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
-            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
-
-            Self { query, proposal_id }
+            let validator = VALIDATOR_OPT.parse(matches);
+            let epoch = EPOCH.parse(matches);
+            Self {
+                query,
+                validator,
+                epoch,
+            }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
-                .arg(PROPOSAL_ID_OPT.def().about("The proposal identifier."))
+            app.add_args::<Query>()
+                .arg(VALIDATOR_OPT.def().about(
+                    "The validator's address whose voting power to query.",
+                ))
+                .arg(EPOCH.def().about(
+                    "The epoch at which to query (last committed, if not \
+                     specified).",
+                ))
         }
     }
 
+    /// Query PoS validators
     #[derive(Clone, Debug)]
-    pub struct QueryProposalResult {
+    pub struct QueryValidators {
         /// Common query args
         pub query: Query,
-        /// Proposal id
-        pub proposal_id: Option<u64>,
-        /// Flag if proposal result should be run on offline data
-        pub offline: bool,
-        /// The folder containing the proposal and votes
-        pub proposal_folder: Option<PathBuf>,
+        /// Epoch in which to find the validators
+        pub epoch: Option<Epoch>,
     }
 
-    impl Args for QueryProposalResult {
+    impl Args for QueryValidators {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
-            let proposal_id = PROPOSAL_ID_OPT.parse(matches);
-            let offline = PROPOSAL_OFFLINE.parse(matches);
-            let proposal_folder = DATA_PATH_OPT.parse(matches);
-
-            Self {
-                query,
-                proposal_id,
-                offline,
-                proposal_folder,
-            }
+            let epoch = EPOCH.parse(matches);
+            Self { query, epoch }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Query>()
-                .arg(PROPOSAL_ID_OPT.def().about("The proposal identifier."))
-                .arg(
-                    PROPOSAL_OFFLINE
-                        .def()
-                        .about(
-                            "Flag if the proposal result should run on \
-                             offline data.",
-                        )
-                        .conflicts_with(PROPOSAL_ID.name),
-                )
-                .arg(
-                    DATA_PATH_OPT
-                        .def()
-                        .about(
-                            "The path to the folder containing the proposal \
-                             json and votes",
-                        )
-                        .conflicts_with(PROPOSAL_ID.name),
-                )
+            app.add_args::<Query>().arg(EPOCH.def().about(
+                "The epoch at which to query (last committed, if not \
+                 specified).",
+            ))
         }
     }
 
+    /// Query PoS slashes
     #[derive(Clone, Debug)]
-    pub struct QueryProtocolParameters {
+    pub struct QuerySlashes {
         /// Common query args
         pub query: Query,
+        /// Address of a validator
+        pub validator: Option<WalletAddress>,
     }
 
-    impl Args for QueryProtocolParameters {
+    impl Args for QuerySlashes {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
-
-            Self { query }
+            let validator = VALIDATOR_OPT.parse(matches);
+            Self { query, validator }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Query>()
+            app.add_args::<Query>().arg(
+                VALIDATOR_OPT
+                    .def()
+                    .about("The validator's address whose slashes to query."),
+            )
         }
     }
 
-    /// Withdraw arguments
+    /// Query the status of an IBC packet
     #[derive(Clone, Debug)]
-    pub struct Withdraw {
-        /// Common tx arguments
-        pub tx: Tx,
-        /// Validator address
-        pub validator: WalletAddress,
-        /// Source address for withdrawing from delegations. For withdrawing
-        /// from self-bonds, the validator is also the source
-        pub source: Option<WalletAddress>,
+    pub struct QueryIbcPacketStatus {
+        /// Common query args
+        pub query: Query,
+        /// The port from which the packet was sent
+        pub port_id: PortId,
+        /// The channel from which the packet was sent
+        pub channel_id: ChannelId,
+        /// The sequence number of the packet
+        pub sequence: u64,
+        /// The address of a ledger node of the destination chain
+        pub destination_ledger_address: Option<TendermintAddress>,
     }
 
-    impl Args for Withdraw {
+    impl Args for QueryIbcPacketStatus {
         fn parse(matches: &ArgMatches) -> Self {
-            let tx = Tx::parse(matches);
-            let validator = VALIDATOR.parse(matches);
-            let source = SOURCE_OPT.parse(matches);
+            let query = Query::parse(matches);
+            let port_id = PORT_ID.parse(matches);
+            let channel_id = CHANNEL_ID.parse(matches);
+            let sequence = SEQUENCE.parse(matches);
+            let destination_ledger_address =
+                DESTINATION_LEDGER_ADDRESS.parse(matches);
             Self {
-                tx,
-                validator,
-                source,
+                query,
+                port_id,
+                channel_id,
+                sequence,
+                destination_ledger_address,
             }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
-                .arg(VALIDATOR.def().about("Validator address."))
-                .arg(SOURCE_OPT.def().about(
-                    "Source address for withdrawing from delegations. For \
-                     withdrawing from self-bonds, the validator is also the \
-                     source.",
+            app.add_args::<Query>()
+                .arg(PORT_ID.def().about("The port from which it was sent."))
+                .arg(
+                    CHANNEL_ID
+                        .def()
+                        .about("The channel from which it was sent."),
+                )
+                .arg(SEQUENCE.def().about("The sequence number of the packet."))
+                .arg(DESTINATION_LEDGER_ADDRESS.def().about(
+                    "Address of a ledger node of the destination chain, if \
+                     it's an Anoma chain. When given, the receipt of the \
+                     packet is looked up on the destination too.",
                 ))
         }
     }
 
-    // Transaction to create a new nft
+    /// Query the NFT tokens owned by an address
     #[derive(Clone, Debug)]
-    pub struct NftCreate {
-        /// Common tx argumentsips
-        pub tx: Tx,
-        /// Path to the nft file description
-        pub nft_data: PathBuf,
+    pub struct QueryNfts {
+        /// Common query args
+        pub query: Query,
+        /// Address of the owner
+        pub owner: WalletAddress,
+        /// Address of an NFT to search instead of the wallet's addresses
+        pub nft: Option<Address>,
     }
 
-    impl Args for NftCreate {
+    impl Args for QueryNfts {
         fn parse(matches: &ArgMatches) -> Self {
-            let tx = Tx::parse(matches);
-            let data_path = DATA_PATH.parse(matches);
-
-            Self {
-                tx,
-                nft_data: data_path,
-            }
+            let query = Query::parse(matches);
+            let owner = OWNER.parse(matches);
+            let nft = NFT_ADDRESS_OPT.parse(matches);
+            Self { query, owner, nft }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
-                .arg(DATA_PATH.def().about("The path nft description file."))
+            app.add_args::<Query>()
+                .arg(OWNER.def().about("The address of the tokens' owner."))
+                .arg(NFT_ADDRESS_OPT.def().about(
+                    "The address of an NFT to search. Defaults to all the \
+                     addresses in the wallet.",
+                ))
         }
     }
 
+    /// Query the NFT collections or the token IDs of a collection
     #[derive(Clone, Debug)]
-    pub struct NftMint {
-        /// Common tx arguments
-        pub tx: Tx,
-        /// The nft address
-        pub nft_address: Address,
-        /// The nft token description
-        pub nft_data: PathBuf,
+    pub struct QueryNftCollections {
+        /// Common query args
+        pub query: Query,
+        /// Address of a collection whose token IDs to list
+        pub nft: Option<Address>,
+        /// The number of token IDs to skip
+        pub offset: u64,
+        /// The maximum number of token IDs to list
+        pub limit: u64,
     }
 
-    impl Args for NftMint {
+    impl Args for QueryNftCollections {
         fn parse(matches: &ArgMatches) -> Self {
-            let tx = Tx::parse(matches);
-            let nft_address = NFT_ADDRESS.parse(matches);
-            let data_path = DATA_PATH.parse(matches);
-
+            let query = Query::parse(matches);
+            let nft = NFT_ADDRESS_OPT.parse(matches);
+            let offset = OFFSET.parse(matches);
+            let limit = PAGE_LIMIT.parse(matches);
             Self {
-                tx,
-                nft_address,
-                nft_data: data_path,
+                query,
+                nft,
+                offset,
+                limit,
             }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Tx>()
-                .arg(NFT_ADDRESS.def().about("The nft address."))
-                .arg(
-                    DATA_PATH.def().about(
-                        "The data path file that describes the nft tokens.",
-                    ),
-                )
+            app.add_args::<Query>()
+                .arg(NFT_ADDRESS_OPT.def().about(
+                    "The address of a collection whose token IDs to list \
+                     instead of the collections.",
+                ))
+                .arg(OFFSET.def().about(
+                    "The number of token IDs of the collection to skip.",
+                ))
+                .arg(PAGE_LIMIT.def().about(
+                    "The maximum number of token IDs of the collection to \
+                     list, at most 1000.",
+                ))
         }
     }
 
-    /// Query token balance(s)
+    /// Spending report arguments
     #[derive(Clone, Debug)]
-    pub struct QueryBalance {
+    pub struct Report {
         /// Common query args
         pub query: Query,
-        /// Address of an owner
+        /// The period of the report
+        pub period: Period,
+        /// Address of an owner to report instead of the wallet's addresses
         pub owner: Option<WalletAddress>,
-        /// Address of a token
-        pub token: Option<WalletAddress>,
     }
 
-    impl Args for QueryBalance {
+    impl Args for Report {
         fn parse(matches: &ArgMatches) -> Self {
             let query = Query::parse(matches);
-            let owner = OWNER.parse(matches);
-            let token = TOKEN_OPT.parse(matches);
+            let period = PERIOD.parse(matches);
+            let owner = OWNER_OPT.parse(matches);
             Self {
                 query,
+                period,
                 owner,
-                token,
             }
         }
 
         fn def(app: App) -> App {
             app.add_args::<Query>()
-                .arg(
-                    OWNER
-                        .def()
-                        .about("The account address whose balance to query."),
-                )
-                .arg(
-                    TOKEN_OPT
-                        .def()
-                        .about("The token's address whose balance to query."),
-                )
+                .arg(PERIOD.def().about(
+                    "The calendar period of the report, in UTC: day, week, \
+                     month or year. Defaults to month.",
+                ))
+                .arg(OWNER_OPT.def().about(
+                    "The address whose transfers to report. Defaults to all \
+                     the addresses in the wallet.",
+                ))
         }
     }
 
-    /// Helper struct for generating intents
-    #[derive(Debug, Clone, Deserialize)]
-    pub struct ExchangeDefinition {
-        /// The source address
-        pub addr: String,
-        /// The token to be sold
-        pub token_sell: String,
-        /// The minimum rate
-        pub rate_min: String,
-        /// The maximum amount of token to be sold
-        pub max_sell: String,
-        /// The token to be bought
-        pub token_buy: String,
-        /// The amount of token to be bought
-        pub min_buy: String,
-        /// The path to the wasm vp code
-        pub vp_path: Option<String>,
+    /// Query the raw bytes of given storage key
+    #[derive(Clone, Debug)]
+    pub struct QueryRawBytes {
+        /// The storage key to query
+        pub storage_key: storage::Key,
+        /// The block height to query at, defaults to the last committed
+        /// block
+        pub height: Option<u64>,
+        /// Request the merkle proof of the value
+        pub prove: bool,
+        /// Archive nodes used for the heights that the ledger node doesn't
+        /// serve
+        pub archive_ledger_addresses: Vec<TendermintAddress>,
+        /// Common query args
+        pub query: Query,
     }
 
-    impl TryFrom<ExchangeDefinition> for Exchange {
-        type Error = &'static str;
+    impl Args for QueryRawBytes {
+        fn parse(matches: &ArgMatches) -> Self {
+            let storage_key = STORAGE_KEY.parse(matches);
+            let height = HEIGHT_OPT.parse(matches);
+            let prove = PROVE.parse(matches);
+            let archive_ledger_addresses =
+                ARCHIVE_LEDGER_ADDRESSES.parse(matches);
+            let query = Query::parse(matches);
+            Self {
+                storage_key,
+                height,
+                prove,
+                archive_ledger_addresses,
+                query,
+            }
+        }
 
-        fn try_from(
-            value: ExchangeDefinition,
-        ) -> Result<Exchange, Self::Error> {
-            let vp = if let Some(path) = value.vp_path {
-                if let Ok(wasm) = std::fs::read(path.clone()) {
-                    Some(wasm)
-                } else {
-                    eprintln!("File {} was not found.", path);
-                    None
-                }
+        fn def(app: App) -> App {
+            app.add_args::<Query>()
+                .arg(STORAGE_KEY.def().about("Storage key"))
+                .arg(HEIGHT_OPT.def().about(
+                    "The block height to query at. Defaults to the last \
+                     committed block.",
+                ))
+                .arg(PROVE.def().about(
+                    "Also print the merkle proof of the value. Nodes that \
+                     aren't in the archive mode only serve the proofs from \
+                     the first block of the epochs that they track.",
+                ))
+                .arg(ARCHIVE_LEDGER_ADDRESSES.def().about(
+                    "Addresses of archive nodes to query when the ledger node \
+                     doesn't serve the proof at the requested height, because \
+                     it has pruned its merkle trees. The first archive node \
+                     that serves it is used.",
+                ))
+        }
+    }
+    /// Intent arguments
+    #[derive(Clone, Debug)]
+    pub struct Intent {
+        /// The addresses of the gossip nodes to submit the intent to. If
+        /// empty, the client's configured gossip nodes are used.
+        pub node_addrs: Vec<String>,
+        /// Intent topic
+        pub topic: Option<String>,
+        /// Source address
+        pub source: Option<WalletAddress>,
+        /// Signing key
+        pub signing_key: Option<WalletKeypair>,
+        /// Exchanges description, including the exchanges of tokens of NFTs
+        pub exchanges: Vec<Exchange>,
+        /// The addresses of a ring to sign the intent with a ring signature,
+        /// if any
+        pub ring: Vec<WalletAddress>,
+        /// The address of the ledger node as host:port
+        pub ledger_address: TendermintAddress,
+        /// Print output to stdout
+        pub to_stdout: bool,
+        /// Only find how the gossip node's matchmakers would match the
+        /// intent, without gossiping it
+        pub dry_run: bool,
+        /// The duration from now after which the intent's exchanges expire,
+        /// if any
+        pub expires_in: Option<Duration>,
+        /// Wait until the submitted intent is fully matched
+        pub wait_for_match: bool,
+        /// An intent that has already been signed, to submit instead of
+        /// signing the exchanges, if any
+        pub signed: Option<SignedIntentData>,
+        /// A ring-signed intent whose source is revealed with the source's
+        /// key to have it matched and settled, if any
+        pub reveal: Option<SignedIntentData>,
+        /// The encoding of the intent printed to stdout or read from a file
+        pub format: IntentFormat,
+    }
+
+    /// The source of the data of an intent that has already been signed
+    #[derive(Clone, Debug)]
+    pub enum SignedIntentData {
+        /// Read the data from a file, encoded in the intent's format
+        File(PathBuf),
+        /// Read the data from stdin, encoded in the intent's format
+        Stdin,
+        /// The data given inline
+        Bytes(Vec<u8>),
+    }
+
+    impl SignedIntentData {
+        /// Read the data from the file at the given path, or from stdin if
+        /// the path is "-"
+        fn from_path(path: PathBuf) -> Self {
+            if path.as_os_str() == "-" {
+                Self::Stdin
             } else {
-                None
-            };
-
-            let addr = Address::decode(value.addr)
-                .expect("Addr should be a valid address");
-            let token_buy = Address::decode(value.token_buy)
-                .expect("Token_buy should be a valid address");
-            let token_sell = Address::decode(value.token_sell)
-                .expect("Token_sell should be a valid address");
-            let min_buy = token::Amount::from_str(&value.min_buy)
-                .expect("Min_buy must be convertible to number");
-            let max_sell = token::Amount::from_str(&value.max_sell)
-                .expect("Max_sell must be convertible to number");
-            let rate_min = DecimalWrapper::from_str(&value.rate_min)
-                .expect("Max_sell must be convertible to decimal.");
-
-            Ok(Exchange {
-                addr,
-                token_sell,
-                rate_min,
-                max_sell,
-                token_buy,
-                min_buy,
-                vp,
-            })
+                Self::File(path)
+            }
         }
     }
 
-    /// Query PoS bond(s)
-    #[derive(Clone, Debug)]
-    pub struct QueryBonds {
-        /// Common query args
-        pub query: Query,
-        /// Address of an owner
-        pub owner: Option<WalletAddress>,
-        /// Address of a validator
-        pub validator: Option<WalletAddress>,
+    /// The encoding of a signed intent
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum IntentFormat {
+        /// A hex string
+        Hex,
+        /// A base64 string
+        Base64,
+        /// The raw Borsh bytes
+        Borsh,
     }
 
-    impl Args for QueryBonds {
+    impl FromStr for IntentFormat {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "hex" => Ok(Self::Hex),
+                "base64" => Ok(Self::Base64),
+                "borsh" => Ok(Self::Borsh),
+                _ => Err(format!(
+                    "Unknown intent format \"{}\", expected \"hex\", \
+                     \"base64\" or \"borsh\"",
+                    s
+                )),
+            }
+        }
+    }
+
+    impl Args for Intent {
         fn parse(matches: &ArgMatches) -> Self {
-            let query = Query::parse(matches);
-            let owner = OWNER.parse(matches);
-            let validator = VALIDATOR_OPT.parse(matches);
+            let node_addrs = NODES.parse(matches);
+            let data_path = DATA_PATH_OPT.parse(matches);
+            let nft_data_path = NFT_DATA_PATH.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
+            let signing_key = SIGNING_KEY_OPT.parse(matches);
+            let to_stdout = TO_STDOUT.parse(matches);
+            let dry_run = DRY_RUN_INTENT.parse(matches);
+            let expires_in = EXPIRES_IN.parse(matches).map(Into::into);
+            let wait_for_match = WAIT_FOR_MATCH.parse(matches);
+            let topic = TOPIC_OPT.parse(matches);
+            let format = INTENT_FORMAT.parse(matches);
+            let signed = if let Some(HexData(bytes)) = FROM_HEX.parse(matches) {
+                Some(SignedIntentData::Bytes(bytes))
+            } else {
+                FROM_FILE.parse(matches).map(SignedIntentData::from_path)
+            };
+            let reveal = REVEAL.parse(matches).map(SignedIntentData::from_path);
+
+            let mut exchanges = data_path
+                .map(|path| read_exchanges(&path))
+                .unwrap_or_default();
+            if let Some(path) = nft_data_path {
+                exchanges.extend(read_nft_exchanges(&path));
+            }
+            let ring = RING.parse(matches);
+            let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
+
             Self {
-                query,
-                owner,
-                validator,
+                node_addrs,
+                topic,
+                source,
+                signing_key,
+                exchanges,
+                ring,
+                ledger_address,
+                to_stdout,
+                dry_run,
+                expires_in,
+                wait_for_match,
+                signed,
+                reveal,
+                format,
             }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Query>()
-                .arg(
-                    OWNER.def().about(
-                        "The owner account address whose bonds to query.",
-                    ),
+            app.arg(
+                NODES
+                    .def()
+                    .about(
+                        "The addresses of the gossip nodes to submit the \
+                         intent to concurrently. Defaults to the client's \
+                         configured gossip nodes. A dry-run is only requested \
+                         from the first node.",
+                    )
+                    .conflicts_with(TO_STDOUT.name),
+            )
+            .arg(
+                DATA_PATH_OPT
+                    .def()
+                    .about(
+                        "The exchanges of the intent, in a JSON file with an \
+                         array of exchanges or in a TOML file (with the \
+                         `.toml` extension) with an `[[exchange]]` table per \
+                         exchange. All the exchanges are signed and submitted \
+                         in a single intent.",
+                    )
+                    .required_unless_present_any(&[
+                        NFT_DATA_PATH.name,
+                        FROM_FILE.name,
+                        FROM_HEX.name,
+                        REVEAL.name,
+                    ]),
+            )
+            .arg(NFT_DATA_PATH.def().about(
+                "The exchanges of tokens of NFTs of the intent, in a JSON or \
+                 TOML file as for `--data-path`. Every exchange sells or buys \
+                 a token of an NFT for a price in a fungible token.",
+            ))
+            .arg(
+                SOURCE_OPT
+                    .def()
+                    .about(
+                        "Sign the intent with the key of a given address or \
+                         address alias from your wallet.",
+                    )
+                    .conflicts_with(SIGNING_KEY_OPT.name),
+            )
+            .arg(
+                SIGNING_KEY_OPT
+                    .def()
+                    .about(
+                        "Sign the intent with the key for the given public \
+                         key, public key hash or alias from your wallet.",
+                    )
+                    .conflicts_with(SOURCE_OPT.name),
+            )
+            .arg(RING.def().about(
+                "Sign the intent with a ring signature over the keys of the \
+                 given addresses, which must include the source, so that the \
+                 intent doesn't show which of them is its source until it's \
+                 settled.",
+            ))
+            .arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
+            .arg(
+                TOPIC_OPT
+                    .def()
+                    .about("The subnetwork where the intent should be sent to.")
+                    .conflicts_with(TO_STDOUT.name),
+            )
+            .arg(
+                TO_STDOUT
+                    .def()
+                    .about(
+                        "Echo the serialized intent to stdout, encoded with \
+                         `--format`. Note that with this option, the intent \
+                         won't be submitted to the intent gossiper RPC.",
+                    )
+                    .conflicts_with_all(&[NODES.name, TOPIC.name]),
+            )
+            .arg(
+                DRY_RUN_INTENT
+                    .def()
+                    .about(
+                        "Only show how the matchmakers connected to the \
+                         gossip node would match the intent with their \
+                         current intents, without gossiping it. The intent is \
+                         not added to the matchmakers.",
+                    )
+                    .conflicts_with_all(&[TO_STDOUT.name, TOPIC_OPT.name]),
+            )
+            .arg(EXPIRES_IN.def().about(
+                "The duration from now after which the exchanges of the \
+                 intent expire, e.g. \"3600s\". Expired intents are no longer \
+                 gossiped nor matched.",
+            ))
+            .arg(
+                WAIT_FOR_MATCH
+                    .def()
+                    .about(
+                        "Wait until the intent is fully matched by the \
+                         matchmakers connected to the gossip node and print \
+                         the hashes of the transactions that it's matched \
+                         into.",
+                    )
+                    .conflicts_with_all(&[TO_STDOUT.name, DRY_RUN_INTENT.name]),
+            )
+            .arg(INTENT_FORMAT.def().about(
+                "The encoding of the intent echoed to stdout or read from the \
+                 `--from-file` file. Options are \n\t * borsh (default): the \
+                 raw bytes\n\t * hex\n\t * base64",
+            ))
+            .arg(
+                FROM_FILE
+                    .def()
+                    .about(
+                        "Submit an intent that has already been signed, e.g. \
+                         echoed to stdout by `intent --stdout`, from a file \
+                         encoded with `--format`, or from stdin with \"-\".",
+                    )
+                    .conflicts_with_all(&[
+                        FROM_HEX.name,
+                        DATA_PATH_OPT.name,
+                        NFT_DATA_PATH.name,
+                        SOURCE_OPT.name,
+                        SIGNING_KEY_OPT.name,
+                        RING.name,
+                        EXPIRES_IN.name,
+                        TO_STDOUT.name,
+                    ]),
+            )
+            .arg(
+                FROM_HEX
+                    .def()
+                    .about(
+                        "Submit an intent that has already been signed, given \
+                         as a hex string, e.g. as echoed to stdout by `intent \
+                         --stdout --format hex`.",
+                    )
+                    .conflicts_with_all(&[
+                        FROM_FILE.name,
+                        DATA_PATH_OPT.name,
+                        NFT_DATA_PATH.name,
+                        SOURCE_OPT.name,
+                        SIGNING_KEY_OPT.name,
+                        RING.name,
+                        EXPIRES_IN.name,
+                        TO_STDOUT.name,
+                    ]),
+            )
+            .arg(
+                REVEAL
+                    .def()
+                    .about(
+                        "Reveal the source of a ring-signed intent with the \
+                         key of the `--source` or the `--signing-key` and \
+                         submit it to be matched and settled. The ring-signed \
+                         intent, e.g. echoed to stdout by `intent --ring \
+                         --stdout`, is read from a file encoded with \
+                         `--format`, or from stdin with \"-\".",
+                    )
+                    .conflicts_with_all(&[
+                        FROM_FILE.name,
+                        FROM_HEX.name,
+                        DATA_PATH_OPT.name,
+                        NFT_DATA_PATH.name,
+                        RING.name,
+                        EXPIRES_IN.name,
+                    ]),
+            )
+        }
+    }
+
+    /// The exchange definitions of a TOML file, each one in an `[[exchange]]`
+    /// table
+    #[derive(Debug, Deserialize)]
+    struct ExchangeDefinitions<T> {
+        exchange: Vec<T>,
+    }
+
+    /// Read the exchange definitions of an intent from a file. A file with the
+    /// `.toml` extension is read as TOML, see [`ExchangeDefinitions`], and any
+    /// other file as a JSON array.
+    fn read_definitions<T: DeserializeOwned>(data_path: &Path) -> Vec<T> {
+        let is_toml = data_path
+            .extension()
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("toml"));
+        if is_toml {
+            let raw =
+                std::fs::read_to_string(data_path).expect("File must exist.");
+            let definitions: ExchangeDefinitions<T> =
+                toml::from_str(&raw).expect("TOML was not well-formatted");
+            definitions.exchange
+        } else {
+            let file = File::open(data_path).expect("File must exist.");
+            serde_json::from_reader(file).expect("JSON was not well-formatted")
+        }
+    }
+
+    /// Read the exchanges of an intent from a JSON or TOML file
+    fn read_exchanges(data_path: &Path) -> Vec<Exchange> {
+        let exchange_definitions: Vec<ExchangeDefinition> =
+            read_definitions(data_path);
+
+        exchange_definitions
+            .iter()
+            .map(|item| {
+                Exchange::try_from(item.clone()).expect(
+                    "Conversion from ExchangeDefinition to Exchange should \
+                     not fail.",
                 )
-                .arg(
-                    VALIDATOR_OPT
-                        .def()
-                        .about("The validator's address whose bonds to query."),
+            })
+            .collect()
+    }
+
+    /// Read the exchanges of tokens of NFTs of an intent from a JSON or TOML
+    /// file
+    fn read_nft_exchanges(data_path: &Path) -> Vec<Exchange> {
+        let exchange_definitions: Vec<NftExchangeDefinition> =
+            read_definitions(data_path);
+
+        exchange_definitions
+            .into_iter()
+            .map(|item| {
+                Exchange::try_from(item).expect(
+                    "Conversion from NftExchangeDefinition to Exchange should \
+                     not fail.",
                 )
-        }
+            })
+            .collect()
     }
 
-    /// Query PoS voting power
+    /// Intent simulation arguments
     #[derive(Clone, Debug)]
-    pub struct QueryVotingPower {
-        /// Common query args
-        pub query: Query,
-        /// Address of a validator
-        pub validator: Option<WalletAddress>,
-        /// Epoch in which to find voting power
-        pub epoch: Option<Epoch>,
+    pub struct IntentSimulate {
+        /// Source address
+        pub source: Option<WalletAddress>,
+        /// Signing key
+        pub signing_key: Option<WalletKeypair>,
+        /// Exchanges description
+        pub exchanges: Vec<Exchange>,
+        /// Files with the counterparty intents
+        pub counterparty_intents: Vec<PathBuf>,
+        /// The matchmaker's library, if not configured
+        pub matchmaker_path: Option<PathBuf>,
+        /// The settlement transaction's code, if not configured
+        pub tx_code_path: Option<PathBuf>,
+        /// The address of the ledger node as host:port
+        pub ledger_address: TendermintAddress,
     }
 
-    impl Args for QueryVotingPower {
+    impl Args for IntentSimulate {
         fn parse(matches: &ArgMatches) -> Self {
-            let query = Query::parse(matches);
-            let validator = VALIDATOR_OPT.parse(matches);
-            let epoch = EPOCH.parse(matches);
+            let data_path = DATA_PATH.parse(matches);
+            let source = SOURCE_OPT.parse(matches);
+            let signing_key = SIGNING_KEY_OPT.parse(matches);
+            let counterparty_intents = COUNTERPARTY_INTENTS.parse(matches);
+            let matchmaker_path = MATCHMAKER_PATH.parse(matches);
+            let tx_code_path = TX_CODE_PATH.parse(matches);
+            let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
             Self {
-                query,
-                validator,
-                epoch,
+                source,
+                signing_key,
+                exchanges: read_exchanges(&data_path),
+                counterparty_intents,
+                matchmaker_path,
+                tx_code_path,
+                ledger_address,
             }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Query>()
-                .arg(VALIDATOR_OPT.def().about(
-                    "The validator's address whose voting power to query.",
-                ))
-                .arg(EPOCH.def().about(
-                    "The epoch at which to query (last committed, if not \
-                     specified).",
-                ))
+            app.arg(DATA_PATH.def().about(
+                "The exchanges of the intent, in a JSON or TOML file as for \
+                 `intent --data-path`.",
+            ))
+            .arg(
+                SOURCE_OPT
+                    .def()
+                    .about(
+                        "Sign the intent with the key of a given address or \
+                         address alias from your wallet.",
+                    )
+                    .conflicts_with(SIGNING_KEY_OPT.name),
+            )
+            .arg(
+                SIGNING_KEY_OPT
+                    .def()
+                    .about(
+                        "Sign the intent with the key for the given public \
+                         key, public key hash or alias from your wallet.",
+                    )
+                    .conflicts_with(SOURCE_OPT.name),
+            )
+            .arg(COUNTERPARTY_INTENTS.def().about(
+                "Files with the counterparty intents to match the intent \
+                 with, e.g. as written by `intent --stdout`.",
+            ))
+            .arg(MATCHMAKER_PATH.def().about(
+                "The file name of the matchmaker compiled to a dynamic \
+                 library (the filename extension is optional). Defaults to \
+                 the configured matchmaker.",
+            ))
+            .arg(TX_CODE_PATH.def().about(
+                "The settlement transaction code. Defaults to the configured \
+                 matchmaker transaction code or `tx_from_intent.wasm`.",
+            ))
+            .arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
         }
     }
 
-    /// Query PoS slashes
+    /// Subscribe intent topic arguments
     #[derive(Clone, Debug)]
-    pub struct QuerySlashes {
-        /// Common query args
-        pub query: Query,
-        /// Address of a validator
-        pub validator: Option<WalletAddress>,
+    pub struct SubscribeTopic {
+        /// Gossip node address
+        pub node_addr: String,
+        /// Intent topic
+        pub topic: String,
     }
 
-    impl Args for QuerySlashes {
+    impl Args for SubscribeTopic {
         fn parse(matches: &ArgMatches) -> Self {
-            let query = Query::parse(matches);
-            let validator = VALIDATOR_OPT.parse(matches);
-            Self { query, validator }
+            let node_addr = NODE.parse(matches);
+            let topic = TOPIC.parse(matches);
+            Self { node_addr, topic }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Query>().arg(
-                VALIDATOR_OPT
+            app.arg(NODE.def().about("The gossip node address.")).arg(
+                TOPIC
                     .def()
-                    .about("The validator's address whose slashes to query."),
+                    .about("The new topic of interest for that node."),
             )
         }
     }
-    /// Query the raw bytes of given storage key
-    #[derive(Clone, Debug)]
-    pub struct QueryRawBytes {
-        /// The storage key to query
-        pub storage_key: storage::Key,
-        /// Common query args
-        pub query: Query,
+
+    /// Intent trace arguments
+    #[derive(Clone, Debug)]
+    pub struct IntentTrace {
+        /// Gossip node address
+        pub node_addr: String,
+        /// Hex encoded SHA-256 hash of the intent
+        pub intent_hash: String,
     }
 
-    impl Args for QueryRawBytes {
+    impl Args for IntentTrace {
         fn parse(matches: &ArgMatches) -> Self {
-            let storage_key = STORAGE_KEY.parse(matches);
-            let query = Query::parse(matches);
-            Self { storage_key, query }
+            let node_addr = NODE.parse(matches);
+            let intent_hash = INTENT_HASH.parse(matches);
+            Self {
+                node_addr,
+                intent_hash,
+            }
         }
 
         fn def(app: App) -> App {
-            app.add_args::<Query>()
-                .arg(STORAGE_KEY.def().about("Storage key"))
+            app.arg(NODE.def().about("The gossip node address.")).arg(
+                INTENT_HASH.def().about(
+                    "The hex encoded hash of the intent, as shown in the \
+                     gossip node's receipt of the intent.",
+                ),
+            )
         }
     }
-    /// Intent arguments
+
+    /// Intent cancellation arguments
     #[derive(Clone, Debug)]
-    pub struct Intent {
+    pub struct CancelIntent {
         /// Gossip node address
-        pub node_addr: Option<String>,
+        pub node_addr: String,
         /// Intent topic
-        pub topic: Option<String>,
-        /// Source address
+        pub topic: String,
+        /// Hex encoded SHA-256 hash of the intent
+        pub intent_hash: String,
+        /// Source address whose key signed the intent
         pub source: Option<WalletAddress>,
-        /// Signing key
+        /// The key that signed the intent
         pub signing_key: Option<WalletKeypair>,
-        /// Exchanges description
-        pub exchanges: Vec<Exchange>,
         /// The address of the ledger node as host:port
         pub ledger_address: TendermintAddress,
-        /// Print output to stdout
-        pub to_stdout: bool,
     }
 
-    impl Args for Intent {
+    impl Args for CancelIntent {
         fn parse(matches: &ArgMatches) -> Self {
-            let node_addr = NODE_OPT.parse(matches);
-            let data_path = DATA_PATH.parse(matches);
+            let node_addr = NODE.parse(matches);
+            let topic = TOPIC.parse(matches);
+            let intent_hash = INTENT_HASH.parse(matches);
             let source = SOURCE_OPT.parse(matches);
             let signing_key = SIGNING_KEY_OPT.parse(matches);
-            let to_stdout = TO_STDOUT.parse(matches);
-            let topic = TOPIC_OPT.parse(matches);
-
-            let file = File::open(&data_path).expect("File must exist.");
-            let exchange_definitions: Vec<ExchangeDefinition> =
-                serde_json::from_reader(file)
-                    .expect("JSON was not well-formatted");
-
-            let exchanges: Vec<Exchange> = exchange_definitions
-                .iter()
-                .map(|item| {
-                    Exchange::try_from(item.clone()).expect(
-                        "Conversion from ExchangeDefinition to Exchange \
-                         should not fail.",
-                    )
-                })
-                .collect();
             let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
-
             Self {
                 node_addr,
                 topic,
+                intent_hash,
                 source,
                 signing_key,
-                exchanges,
                 ledger_address,
-                to_stdout,
             }
         }
 
         fn def(app: App) -> App {
-            app.arg(
-                NODE_OPT
-                    .def()
-                    .about("The gossip node address.")
-                    .conflicts_with(TO_STDOUT.name),
-            )
-            .arg(DATA_PATH.def().about(
-                "The data of the intent, that contains all value necessary \
-                 for the matchmaker.",
-            ))
-            .arg(
-                SOURCE_OPT
-                    .def()
-                    .about(
-                        "Sign the intent with the key of a given address or \
-                         address alias from your wallet.",
-                    )
-                    .conflicts_with(SIGNING_KEY_OPT.name),
-            )
-            .arg(
-                SIGNING_KEY_OPT
-                    .def()
-                    .about(
-                        "Sign the intent with the key for the given public \
-                         key, public key hash or alias from your wallet.",
-                    )
-                    .conflicts_with(SOURCE_OPT.name),
-            )
-            .arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
-            .arg(
-                TOPIC_OPT
-                    .def()
-                    .about("The subnetwork where the intent should be sent to.")
-                    .conflicts_with(TO_STDOUT.name),
-            )
-            .arg(
-                TO_STDOUT
-                    .def()
-                    .about(
-                        "Echo the serialized intent to stdout. Note that with \
-                         this option, the intent won't be submitted to the \
-                         intent gossiper RPC.",
-                    )
-                    .conflicts_with_all(&[NODE_OPT.name, TOPIC.name]),
-            )
+            app.arg(NODE.def().about("The gossip node address."))
+                .arg(
+                    TOPIC
+                        .def()
+                        .about("The subnetwork where the intent was sent to."),
+                )
+                .arg(INTENT_HASH.def().about(
+                    "The hex encoded hash of the intent, as shown in the \
+                     gossip node's receipt of the intent.",
+                ))
+                .arg(
+                    SOURCE_OPT
+                        .def()
+                        .about(
+                            "Sign the cancellation with the key of a given \
+                             address or address alias from your wallet, which \
+                             signed the intent.",
+                        )
+                        .conflicts_with(SIGNING_KEY_OPT.name),
+                )
+                .arg(
+                    SIGNING_KEY_OPT
+                        .def()
+                        .about(
+                            "Sign the cancellation with the key for the given \
+                             public key, public key hash or alias from your \
+                             wallet, which signed the intent.",
+                        )
+                        .conflicts_with(SOURCE_OPT.name),
+                )
+                .arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
         }
     }
 
-    /// Subscribe intent topic arguments
+    /// Intents query arguments
     #[derive(Clone, Debug)]
-    pub struct SubscribeTopic {
+    pub struct QueryIntents {
         /// Gossip node address
         pub node_addr: String,
         /// Intent topic
         pub topic: String,
     }
 
-    impl Args for SubscribeTopic {
+    impl Args for QueryIntents {
         fn parse(matches: &ArgMatches) -> Self {
             let node_addr = NODE.parse(matches);
             let topic = TOPIC.parse(matches);
@@ -2511,11 +5056,59 @@ pub mod args {
         }
 
         fn def(app: App) -> App {
-            app.arg(NODE.def().about("The gossip node address.")).arg(
-                TOPIC
-                    .def()
-                    .about("The new topic of interest for that node."),
-            )
+            app.arg(NODE.def().about("The gossip node address."))
+                .arg(TOPIC.def().about("The subnetwork of the intents."))
+        }
+    }
+
+    /// Orderbook depth query arguments
+    #[derive(Clone, Debug)]
+    pub struct QueryDepth {
+        /// Gossip node address
+        pub node_addr: String,
+        /// Intent topic
+        pub topic: String,
+        /// The token that is bought and sold
+        pub base_token: WalletAddress,
+        /// The token in which the prices are quoted
+        pub quote_token: WalletAddress,
+        /// The maximum number of price levels on each side
+        pub levels: u32,
+    }
+
+    impl Args for QueryDepth {
+        fn parse(matches: &ArgMatches) -> Self {
+            let node_addr = NODE.parse(matches);
+            let topic = TOPIC.parse(matches);
+            let base_token = BASE_TOKEN.parse(matches);
+            let quote_token = QUOTE_TOKEN.parse(matches);
+            let levels = DEPTH_LEVELS.parse(matches);
+            Self {
+                node_addr,
+                topic,
+                base_token,
+                quote_token,
+                levels,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(NODE.def().about("The gossip node address."))
+                .arg(TOPIC.def().about("The subnetwork of the intents."))
+                .arg(BASE_TOKEN.def().about(
+                    "The token that is bought and sold, in which the sizes \
+                     are.",
+                ))
+                .arg(
+                    QUOTE_TOKEN
+                        .def()
+                        .about("The token in which the prices are quoted."),
+                )
+                .arg(
+                    DEPTH_LEVELS.def().about(
+                        "The maximum number of price levels on each side.",
+                    ),
+                )
         }
     }
 
@@ -2523,13 +5116,22 @@ pub mod args {
     pub struct GossipRun {
         pub addr: Option<Multiaddr>,
         pub rpc: Option<SocketAddr>,
+        pub mdns: bool,
+        pub bootstrap_peers: Vec<PeerAddress>,
     }
 
     impl Args for GossipRun {
         fn parse(matches: &ArgMatches) -> Self {
             let addr = MULTIADDR_OPT.parse(matches);
             let rpc = RPC_SOCKET_ADDR.parse(matches);
-            Self { addr, rpc }
+            let mdns = MDNS.parse(matches);
+            let bootstrap_peers = BOOTSTRAP_PEERS.parse(matches);
+            Self {
+                addr,
+                rpc,
+                mdns,
+                bootstrap_peers,
+            }
         }
 
         fn def(app: App) -> App {
@@ -2539,6 +5141,15 @@ pub mod args {
                     .about("Gossip service address as host:port."),
             )
             .arg(RPC_SOCKET_ADDR.def().about("Enable RPC service."))
+            .arg(MDNS.def().about(
+                "Discover the peers on the local network with mDNS and \
+                 connect to them.",
+            ))
+            .arg(BOOTSTRAP_PEERS.def().about(
+                "The address of a peer with which to bootstrap Kademlia peer \
+                 discovery, in addition to the seed peers of the config. The \
+                 format is {protocol}/{ip}/tcp/{port}/p2p/{peerid}.",
+            ))
         }
     }
 
@@ -2546,10 +5157,12 @@ pub mod args {
     pub struct Matchmaker {
         pub matchmaker_path: Option<PathBuf>,
         pub tx_code_path: Option<PathBuf>,
+        pub fee: Option<MatchmakerFee>,
+        pub batch_interval: Option<Timeout>,
         pub intent_gossiper_addr: SocketAddr,
-        pub ledger_addr: TendermintAddress,
-        pub tx_signing_key: WalletKeypair,
-        pub tx_source_address: WalletAddress,
+        pub ledger_addr: Option<TendermintAddress>,
+        pub tx_signing_key: Option<WalletKeypair>,
+        pub tx_source_address: Option<WalletAddress>,
     }
 
     impl Args for Matchmaker {
@@ -2557,13 +5170,17 @@ pub mod args {
             let intent_gossiper_addr = INTENT_GOSSIPER_ADDR.parse(matches);
             let matchmaker_path = MATCHMAKER_PATH.parse(matches);
             let tx_code_path = TX_CODE_PATH.parse(matches);
-            let ledger_addr = LEDGER_ADDRESS_DEFAULT.parse(matches);
-            let tx_signing_key = SIGNING_KEY.parse(matches);
-            let tx_source_address = SOURCE.parse(matches);
+            let fee = MATCHMAKER_FEE.parse(matches);
+            let batch_interval = BATCH_INTERVAL.parse(matches);
+            let ledger_addr = LEDGER_ADDRESS_OPT.parse(matches);
+            let tx_signing_key = SIGNING_KEY_OPT.parse(matches);
+            let tx_source_address = SOURCE_OPT.parse(matches);
             Self {
                 intent_gossiper_addr,
                 matchmaker_path,
                 tx_code_path,
+                fee,
+                batch_interval,
                 ledger_addr,
                 tx_signing_key,
                 tx_source_address,
@@ -2584,21 +5201,49 @@ pub mod args {
                     .def()
                     .about("The transaction code to use with the matchmaker."),
             )
-            .arg(LEDGER_ADDRESS_DEFAULT.def().about(
-                "The address of the ledger as \"{scheme}://{host}:{port}\" \
-                 that the matchmaker must send transactions to. If the scheme \
-                 is not supplied, it is assumed to be TCP.",
+            .arg(MATCHMAKER_FEE.def().about(
+                "The fee charged to the sources of the matched exchanges, \
+                 paid to the source address in the tokens that they sell. \
+                 Either a flat amount or basis points of the sold amounts \
+                 with the \"bps\" suffix, e.g. \"30bps\". It's capped by the \
+                 maximum fee of every exchange.",
             ))
-            .arg(SIGNING_KEY.def().about(
-                "Sign the transactions created by the matchmaker with the key \
-                 for the given public key, public key hash or alias from your \
-                 wallet.",
+            .arg(BATCH_INTERVAL.def().about(
+                "Match the intents in batch auctions at the given interval, \
+                 e.g. \"5s\", instead of matching every intent as soon as \
+                 it's received. The intents collected during an interval that \
+                 cross at a uniform clearing price are settled in a single \
+                 transaction.",
             ))
-            .arg(SOURCE.def().about(
-                "Source address or alias of an address of the transactions \
-                 created by the matchmaker. This must be matching the signing \
-                 key.",
+            .arg(LEDGER_ADDRESS_OPT.def().about(
+                "The address of the ledger as \"{scheme}://{host}:{port}\" \
+                 that the matchmaker must send transactions to. If the scheme \
+                 is not supplied, it is assumed to be TCP. Defaults to the \
+                 configured settlement's ledger address, if any, or to \
+                 \"127.0.0.1:26657\".",
             ))
+            .arg(
+                SIGNING_KEY_OPT
+                    .def()
+                    .about(
+                        "Sign the transactions created by the matchmaker with \
+                         the key for the given public key, public key hash or \
+                         alias from your wallet. Without a signing key given \
+                         here or in the matchmaker's settlement config, the \
+                         matched transactions are only logged.",
+                    )
+                    .requires(SOURCE_OPT.name),
+            )
+            .arg(
+                SOURCE_OPT
+                    .def()
+                    .about(
+                        "Source address or alias of an address of the \
+                         transactions created by the matchmaker. This must be \
+                         matching the signing key.",
+                    )
+                    .requires(SIGNING_KEY_OPT.name),
+            )
         }
     }
 
@@ -2607,10 +5252,17 @@ pub mod args {
     pub struct Tx {
         /// Simulate applying the transaction
         pub dry_run: bool,
+        /// Estimate the gas needed by the transaction without submitting it
+        pub estimate_gas: bool,
         /// Submit the transaction even if it doesn't pass client checks
         pub force: bool,
-        /// Do not wait for the transaction to be added to the blockchain
-        pub broadcast_only: bool,
+        /// How long to wait for the transaction after it's broadcast
+        pub broadcast_mode: BroadcastMode,
+        /// How to retry broadcasting the transaction if it fails
+        pub broadcast_retry: BroadcastRetry,
+        /// How long to wait for the result of the applied transaction
+        /// before querying the ledger for it instead
+        pub event_timeout: Duration,
         /// The address of the ledger node as host:port
         pub ledger_address: TendermintAddress,
         /// If any new account is initialized by the tx, use the given alias to
@@ -2620,12 +5272,32 @@ pub mod args {
         pub fee_amount: token::Amount,
         /// The token in which the fee is being paid
         pub fee_token: WalletAddress,
+        /// Sign the wrapper with the key for the given alias from your
+        /// wallet, so that the fee is paid by its implicit account instead of
+        /// the tx signer's
+        pub fee_payer: Option<WalletKeypair>,
         /// The max amount of gas used to process tx
         pub gas_limit: GasLimit,
+        /// The time after which the tx is no longer valid
+        pub expiration: Option<DateTimeUtc>,
+        /// Set the gas limit from the gas estimated with a dry run, instead
+        /// of using the `gas_limit`
+        pub auto_gas_limit: bool,
+        /// The margin in percent added to the estimated gas for the
+        /// `auto_gas_limit`
+        pub gas_margin: u64,
         /// Sign the tx with the key for the given alias from your wallet
         pub signing_key: Option<WalletKeypair>,
         /// Sign the tx with the keypair of the public key of the given address
         pub signer: Option<WalletAddress>,
+        /// Save the tx as a draft to the given file instead of submitting it
+        pub save_draft: Option<PathBuf>,
+        /// Wait until the chain reaches the block before the given height
+        /// before broadcasting the tx, so that it can be included in it
+        pub target_height: Option<BlockHeight>,
+        /// Wait until the txs with the given hashes have been applied
+        /// successfully before broadcasting the tx
+        pub after: Vec<String>,
     }
 
     impl Args for Tx {
@@ -2635,12 +5307,50 @@ pub mod args {
                     .def()
                     .about("Simulate the transaction application."),
             )
+            .arg(ESTIMATE_GAS.def().about(
+                "Estimate the gas needed by the transaction with a dry run \
+                 and print it, without submitting the transaction.",
+            ))
             .arg(FORCE.def().about(
                 "Submit the transaction even if it doesn't pass client checks.",
             ))
-            .arg(BROADCAST_ONLY.def().about(
-                "Do not wait for the transaction to be applied. This will \
-                 return once the transaction is added to the mempool.",
+            .arg(
+                BROADCAST_MODE
+                    .def()
+                    .about(
+                        "How long to wait for the transaction after it's \
+                         broadcast. Options are \n\t * async - return \
+                         immediately without waiting for any result\n\t * \
+                         sync - wait for the transaction to be added to the \
+                         mempool\n\t * commit (default) - wait for the \
+                         transaction to be applied",
+                    )
+                    .conflicts_with(BROADCAST_ONLY.name),
+            )
+            .arg(
+                BROADCAST_ONLY
+                    .def()
+                    .about(
+                        "Do not wait for the transaction to be applied. This \
+                         will return once the transaction is added to the \
+                         mempool. Equivalent to `--broadcast-mode sync`.",
+                    )
+                    .conflicts_with(BROADCAST_MODE.name),
+            )
+            .arg(BROADCAST_MAX_ATTEMPTS.def().about(
+                "The maximum number of attempts to broadcast the transaction \
+                 when the connection to the ledger fails or its mempool is \
+                 full. Defaults to 3.",
+            ))
+            .arg(BROADCAST_RETRY_DELAY.def().about(
+                "The delay before retrying to broadcast the transaction. It's \
+                 doubled after each failed attempt. Defaults to 1s.",
+            ))
+            .arg(EVENT_TIMEOUT.def().about(
+                "How long to wait for the result of the applied transaction. \
+                 If it's not received in time, e.g. because the node dropped \
+                 the subscription, the ledger is queried for the result for \
+                 up to the same time. Defaults to 60s.",
             ))
             .arg(LEDGER_ADDRESS_DEFAULT.def().about(LEDGER_ADDRESS_ABOUT))
             .arg(ALIAS_OPT.def().about(
@@ -2653,11 +5363,33 @@ pub mod args {
                 "The amount being paid for the inclusion of this transaction",
             ))
             .arg(FEE_TOKEN.def().about("The token for paying the fee"))
+            .arg(FEE_PAYER_OPT.def().about(
+                "Pay the fee from the implicit account of the key for the \
+                 given public key, public key hash or alias from your wallet. \
+                 The wrapper transaction is signed by this key, while the \
+                 inner transaction is still signed by the signer. Defaults to \
+                 the signer's key.",
+            ))
             .arg(
                 GAS_LIMIT.def().about(
                     "The maximum amount of gas needed to run transaction",
                 ),
             )
+            .arg(EXPIRATION_OPT.def().about(
+                "The time after which the transaction is no longer valid, as \
+                 an RFC 3339 timestamp (e.g. 2022-01-01T00:00:00Z). The \
+                 ledger rejects the transaction if it's included in a block \
+                 after this time.",
+            ))
+            .arg(GAS_LIMIT_AUTO.def().about(
+                "Set the gas limit from the gas estimated with a dry run of \
+                 the transaction plus the gas margin. Overrides the \
+                 `--gas-limit`.",
+            ))
+            .arg(GAS_MARGIN.def().about(
+                "The safety margin in percent that is added to the estimated \
+                 gas when the `--auto-gas-limit` is used. Defaults to 20.",
+            ))
             .arg(
                 SIGNING_KEY_OPT
                     .def()
@@ -2677,31 +5409,155 @@ pub mod args {
                     )
                     .conflicts_with(SIGNING_KEY_OPT.name),
             )
+            .arg(
+                SAVE_DRAFT
+                    .def()
+                    .about(
+                        "Save the transaction as a draft to the given file \
+                         instead of submitting it, to be submitted later with \
+                         the `submit-draft` command. The draft is only signed \
+                         if the `--signing-key` or the `--signer` is given. \
+                         It is saved in the JSON format if the file has the \
+                         `json` extension, otherwise in the Borsh encoding.",
+                    )
+                    .conflicts_with_all(&[DRY_RUN_TX.name, ESTIMATE_GAS.name]),
+            )
+            .arg(
+                TARGET_HEIGHT
+                    .def()
+                    .about(
+                        "Wait until the chain reaches the block before the \
+                         given height and only then broadcast the \
+                         transaction, so that it can be included in the block \
+                         at the given height, e.g. to coordinate it with an \
+                         epoch boundary. Fails if the chain is already past \
+                         it.",
+                    )
+                    .conflicts_with_all(&[DRY_RUN_TX.name, ESTIMATE_GAS.name]),
+            )
+            .arg(
+                AFTER
+                    .def()
+                    .about(
+                        "Wait until the transaction with the given hash has \
+                         been applied successfully before broadcasting this \
+                         transaction, and fail if it has failed. Can be given \
+                         multiple times. The transaction must be applied \
+                         within the `--event-timeout`.",
+                    )
+                    .conflicts_with_all(&[DRY_RUN_TX.name, ESTIMATE_GAS.name]),
+            )
         }
 
         fn parse(matches: &ArgMatches) -> Self {
             let dry_run = DRY_RUN_TX.parse(matches);
+            let estimate_gas = ESTIMATE_GAS.parse(matches);
             let force = FORCE.parse(matches);
-            let broadcast_only = BROADCAST_ONLY.parse(matches);
+            let broadcast_mode = if BROADCAST_ONLY.parse(matches) {
+                BroadcastMode::Sync
+            } else {
+                BROADCAST_MODE.parse(matches)
+            };
+            let broadcast_retry = BroadcastRetry {
+                max_attempts: BROADCAST_MAX_ATTEMPTS.parse(matches),
+                base_delay: BROADCAST_RETRY_DELAY.parse(matches).into(),
+            };
+            let event_timeout = EVENT_TIMEOUT.parse(matches).into();
             let ledger_address = LEDGER_ADDRESS_DEFAULT.parse(matches);
             let initialized_account_alias = ALIAS_OPT.parse(matches);
             let fee_amount = FEE_AMOUNT.parse(matches);
             let fee_token = FEE_TOKEN.parse(matches);
+            let fee_payer = FEE_PAYER_OPT.parse(matches);
             let gas_limit = GAS_LIMIT.parse(matches).into();
+            let expiration = EXPIRATION_OPT.parse(matches);
+            let auto_gas_limit = GAS_LIMIT_AUTO.parse(matches);
+            let gas_margin = GAS_MARGIN.parse(matches);
 
             let signing_key = SIGNING_KEY_OPT.parse(matches);
             let signer = SIGNER.parse(matches);
+            let save_draft = SAVE_DRAFT.parse(matches);
+            let target_height = TARGET_HEIGHT.parse(matches).map(BlockHeight);
+            let after = AFTER.parse(matches);
             Self {
                 dry_run,
+                estimate_gas,
                 force,
-                broadcast_only,
+                broadcast_mode,
+                broadcast_retry,
+                event_timeout,
                 ledger_address,
                 initialized_account_alias,
                 fee_amount,
                 fee_token,
+                fee_payer,
                 gas_limit,
+                expiration,
+                auto_gas_limit,
+                gas_margin,
                 signing_key,
                 signer,
+                save_draft,
+                target_height,
+                after,
+            }
+        }
+    }
+
+    /// How long the client waits for a transaction after it's broadcast
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum BroadcastMode {
+        /// Return as soon as the transaction is sent to the node
+        Async,
+        /// Wait for the transaction to be validated and added to the mempool
+        Sync,
+        /// Wait for the transaction to be applied on chain
+        Commit,
+    }
+
+    impl FromStr for BroadcastMode {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "async" => Ok(Self::Async),
+                "sync" => Ok(Self::Sync),
+                "commit" => Ok(Self::Commit),
+                _ => Err(format!(
+                    "Unknown broadcast mode \"{}\", expected \"async\", \
+                     \"sync\" or \"commit\"",
+                    s
+                )),
+            }
+        }
+    }
+
+    /// How to retry broadcasting a transaction
+    #[derive(Clone, Copy, Debug)]
+    pub struct BroadcastRetry {
+        /// The maximum number of attempts, including the first one
+        pub max_attempts: u64,
+        /// The delay before the first retry. It's doubled after each failed
+        /// attempt.
+        pub base_delay: Duration,
+    }
+
+    impl BroadcastRetry {
+        /// The delay before retrying after the given failed attempt, counted
+        /// from 1
+        pub fn delay(&self, attempt: u64) -> Duration {
+            let exp =
+                u32::try_from(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+            self.base_delay
+                .checked_mul(2_u32.saturating_pow(exp))
+                .unwrap_or(Duration::MAX)
+        }
+    }
+
+    impl Default for BroadcastRetry {
+        fn default() -> Self {
+            Self {
+                max_attempts: 3,
+                base_delay: Duration::from_secs(1),
             }
         }
     }
@@ -2731,15 +5587,23 @@ pub mod args {
         pub alias: Option<String>,
         /// Don't encrypt the keypair
         pub unsafe_dont_encrypt: bool,
+        /// Number of keys and addresses to generate
+        pub count: u64,
+        /// Path of a manifest file to export the addresses into
+        pub manifest: Option<PathBuf>,
     }
 
     impl Args for KeyAndAddressGen {
         fn parse(matches: &ArgMatches) -> Self {
             let alias = ALIAS_OPT.parse(matches);
             let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            let count = COUNT.parse(matches);
+            let manifest = MANIFEST_OUTPUT.parse(matches);
             Self {
                 alias,
                 unsafe_dont_encrypt,
+                count,
+                manifest,
             }
         }
 
@@ -2752,6 +5616,16 @@ pub mod args {
                 "UNSAFE: Do not encrypt the keypair. Do not use this for keys \
                  used in a live network.",
             ))
+            .arg(COUNT.def().about(
+                "The number of keys and addresses to generate. When more than \
+                 one, the alias is suffixed with the key's number. All the \
+                 keys are encrypted with the same password.",
+            ))
+            .arg(MANIFEST_OUTPUT.def().about(
+                "Export the aliases, addresses and public keys of the \
+                 generated keys into a TOML manifest file at this path. The \
+                 manifest doesn't contain any secret keys.",
+            ))
         }
     }
 
@@ -2903,6 +5777,34 @@ pub mod args {
         }
     }
 
+    /// Wallet agent arguments
+    #[derive(Clone, Debug)]
+    pub struct WalletAgent {
+        /// For how many seconds the agent keeps a decrypted key
+        pub key_ttl: u64,
+        /// Remove all the keys from a running agent instead of starting one
+        pub clear: bool,
+    }
+
+    impl Args for WalletAgent {
+        fn parse(matches: &ArgMatches) -> Self {
+            let key_ttl = KEY_TTL.parse(matches);
+            let clear = CLEAR.parse(matches);
+            Self { key_ttl, clear }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(KEY_TTL.def().about(
+                "For how many seconds the agent keeps a decrypted key. \
+                 Defaults to 900 seconds.",
+            ))
+            .arg(CLEAR.def().about(
+                "Remove all the keys from a running agent, instead of \
+                 starting a new agent.",
+            ))
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct JoinNetwork {
         pub chain_id: ChainId,
@@ -3042,6 +5944,43 @@ pub mod args {
                 ))
         }
     }
+
+    #[derive(Clone, Debug)]
+    pub struct WasmAbiVersion {
+        pub path: PathBuf,
+    }
+
+    impl Args for WasmAbiVersion {
+        fn parse(matches: &ArgMatches) -> Self {
+            let path = WASM_PATH.parse(matches);
+            Self { path }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(WASM_PATH.def().about(
+                "The path to a wasm file or to a directory with wasm files.",
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct GenTestVectors {
+        pub output_path: Option<PathBuf>,
+    }
+
+    impl Args for GenTestVectors {
+        fn parse(matches: &ArgMatches) -> Self {
+            let output_path = OUTPUT_PATH.parse(matches);
+            Self { output_path }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(OUTPUT_PATH.def().about(
+                "The path of the JSON file to write the test vectors to. \
+                 Defaults to printing them to stdout.",
+            ))
+        }
+    }
 }
 
 pub fn anoma_cli() -> (cmds::Anoma, String) {
@@ -3067,6 +6006,26 @@ pub fn anoma_node_cli() -> (cmds::AnomaNode, Context) {
 pub enum AnomaClient {
     WithoutContext(cmds::Utils, args::Global),
     WithContext(Box<(cmds::AnomaClientWithContext, Context)>),
+    Console(args::Global),
+}
+
+impl AnomaClient {
+    /// Prepare a parsed command to be run, loading the context if the command
+    /// needs it.
+    pub fn new(cmd: cmds::AnomaClient, global_args: args::Global) -> Self {
+        match cmd {
+            cmds::AnomaClient::WithContext(sub_cmd) => {
+                let context = Context::new(global_args);
+                AnomaClient::WithContext(Box::new((sub_cmd, context)))
+            }
+            cmds::AnomaClient::WithoutContext(sub_cmd) => {
+                AnomaClient::WithoutContext(sub_cmd, global_args)
+            }
+            cmds::AnomaClient::Console(cmds::Console) => {
+                AnomaClient::Console(global_args)
+            }
+        }
+    }
 }
 
 pub fn anoma_client_cli() -> AnomaClient {
@@ -3076,15 +6035,7 @@ pub fn anoma_client_cli() -> AnomaClient {
     match Cmd::parse(&matches) {
         Some(cmd) => {
             let global_args = args::Global::parse(&matches);
-            match cmd {
-                cmds::AnomaClient::WithContext(sub_cmd) => {
-                    let context = Context::new(global_args);
-                    AnomaClient::WithContext(Box::new((sub_cmd, context)))
-                }
-                cmds::AnomaClient::WithoutContext(sub_cmd) => {
-                    AnomaClient::WithoutContext(sub_cmd, global_args)
-                }
-            }
+            AnomaClient::new(cmd, global_args)
         }
         None => {
             app.print_help().unwrap();
@@ -3093,6 +6044,35 @@ pub fn anoma_client_cli() -> AnomaClient {
     }
 }
 
+/// Parse a command entered in the client console, without the binary name.
+/// The global arguments are ignored, the console uses its own.
+pub fn anoma_client_console_cmd(
+    words: Vec<String>,
+) -> Result<Option<cmds::AnomaClient>, clap::Error> {
+    let app = anoma_client_app().setting(AppSettings::NoBinaryName);
+    let matches = app.try_get_matches_from(words)?;
+    Ok(Cmd::parse(&matches))
+}
+
+/// The names of the client commands and their long arguments (with the `--`
+/// prefix), used for tab completion in the client console.
+pub fn anoma_client_console_completions() -> Vec<String> {
+    fn add_completions(app: &App, completions: &mut BTreeSet<String>) {
+        for arg in app.get_arguments() {
+            if let Some(long) = arg.get_long() {
+                completions.insert(format!("--{}", long));
+            }
+        }
+        for sub in app.get_subcommands() {
+            completions.insert(sub.get_name().to_owned());
+            add_completions(sub, completions);
+        }
+    }
+    let mut completions = BTreeSet::new();
+    add_completions(&anoma_client_app(), &mut completions);
+    completions.into_iter().collect()
+}
+
 pub fn anoma_wallet_cli() -> (cmds::AnomaWallet, Context) {
     let app = anoma_wallet_app();
     cmds::AnomaWallet::parse_or_print_help(app)
@@ -3103,7 +6083,8 @@ fn anoma_app() -> App {
         .version(anoma_version())
         .author(crate_authors!("\n"))
         .about("Anoma command line interface.")
-        .setting(AppSettings::SubcommandRequiredElseHelp);
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::AllowExternalSubcommands);
     cmds::Anoma::add_sub(args::Global::def(app))
 }
 
@@ -3133,3 +6114,36 @@ fn anoma_wallet_app() -> App {
         .setting(AppSettings::SubcommandRequiredElseHelp);
     cmds::AnomaWallet::add_sub(args::Global::def(app))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::args::BroadcastRetry;
+
+    /// Test that the delay before retrying to broadcast a tx is doubled after
+    /// each failed attempt, without overflowing
+    #[test]
+    fn test_broadcast_retry_delay() {
+        let retry = BroadcastRetry {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(500),
+        };
+        assert_eq!(retry.delay(1), Duration::from_millis(500));
+        assert_eq!(retry.delay(2), Duration::from_secs(1));
+        assert_eq!(retry.delay(3), Duration::from_secs(2));
+        assert_eq!(retry.delay(4), Duration::from_secs(4));
+        assert_eq!(retry.delay(64), Duration::from_millis(500) * u32::MAX);
+        assert_eq!(
+            retry.delay(u64::MAX),
+            Duration::from_millis(500) * u32::MAX
+        );
+
+        let retry = BroadcastRetry {
+            max_attempts: 5,
+            base_delay: Duration::MAX,
+        };
+        assert_eq!(retry.delay(1), Duration::MAX);
+        assert_eq!(retry.delay(2), Duration::MAX);
+    }
+}
@@ -12,6 +12,7 @@ use anoma::types::key::*;
 
 use super::args;
 use crate::cli::safe_exit;
+use crate::client::progress::TxProgressHook;
 use crate::config::genesis::genesis_config;
 use crate::config::global::GlobalConfig;
 use crate::config::{self, Config};
@@ -46,6 +47,8 @@ pub struct Context {
     pub global_config: GlobalConfig,
     /// The ledger & intent gossip configuration for a specific chain ID
     pub config: Config,
+    /// The hook to which the progress of submitted txs is reported
+    pub tx_progress: TxProgressHook,
 }
 
 impl Context {
@@ -101,6 +104,7 @@ impl Context {
             wallet,
             global_config,
             config,
+            tx_progress: TxProgressHook::default(),
         }
     }
 
@@ -164,9 +168,11 @@ impl Context {
         wasm_dir
     }
 
-    /// Read the given WASM file from the WASM directory or an absolute path.
+    /// Read the given WASM file from the WASM directory or an absolute path
+    /// and verify it against the checksums file. A custom WASM that isn't
+    /// listed in the checksums file is read with a warning.
     pub fn read_wasm(&self, file_name: impl AsRef<Path>) -> Vec<u8> {
-        wasm_loader::read_wasm(self.wasm_dir(), file_name)
+        wasm_loader::read_verified_wasm(self.wasm_dir(), file_name)
     }
 }
 
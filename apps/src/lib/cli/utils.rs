@@ -2,6 +2,7 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use clap::ArgMatches;
 
@@ -317,6 +318,21 @@ where
     })
 }
 
+/// When set, [`safe_exit`] unwinds instead of exiting the process
+static UNWIND_ON_EXIT: AtomicBool = AtomicBool::new(false);
+
+/// The payload of the unwinding started by [`safe_exit`] in place of exiting,
+/// when enabled with [`set_unwind_on_exit`]
+#[derive(Debug)]
+pub struct ExitUnwind(pub i32);
+
+/// Make [`safe_exit`] unwind with an [`ExitUnwind`] payload instead of
+/// exiting the process. This is used by the client console to recover from a
+/// failed command.
+pub fn set_unwind_on_exit(unwind: bool) {
+    UNWIND_ON_EXIT.store(unwind, Ordering::SeqCst);
+}
+
 /// A helper to exit after flushing output, borrowed from `clap::util` module.
 pub fn safe_exit(code: i32) -> ! {
     use std::io::Write;
@@ -324,5 +340,9 @@ pub fn safe_exit(code: i32) -> ! {
     let _ = std::io::stdout().lock().flush();
     let _ = std::io::stderr().lock().flush();
 
+    if UNWIND_ON_EXIT.load(Ordering::SeqCst) {
+        // Unwinding with `resume_unwind` doesn't invoke the panic hook
+        std::panic::resume_unwind(Box::new(ExitUnwind(code)))
+    }
     std::process::exit(code)
 }
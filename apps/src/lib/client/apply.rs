@@ -0,0 +1,408 @@
+//! Declarative deployment of on-chain applications.
+//!
+//! An application manifest is a TOML file that describes the accounts that an
+//! application needs, together with their validity predicates, its tokens and
+//! the initial balances to be transferred to its accounts. The `apply` command
+//! compares the manifest with the state on chain and submits only the
+//! transactions needed to reach it, so that applying the same manifest again
+//! does nothing and applying a modified manifest only submits the changes.
+//!
+//! ```toml
+//! # The default source of the transactions that initialize new accounts
+//! source = "Bertha"
+//!
+//! # Established accounts, by their alias in the wallet
+//! [accounts.my-app]
+//! public_key = "my-app-key"
+//! # Defaults to `vp_user.wasm`
+//! vp = "my_app_vp.wasm"
+//!
+//! # Token accounts, by their alias in the wallet
+//! [tokens.my-token]
+//! public_key = "my-app-key"
+//! # Defaults to `vp_token.wasm`
+//! vp = "vp_token.wasm"
+//!
+//! # The target's balance is topped up to the `amount`
+//! [[transfers]]
+//! source = "Bertha"
+//! target = "my-app"
+//! token = "XAN"
+//! amount = "1000"
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anoma::types::token;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// The VP used for accounts that don't specify one
+pub const DEFAULT_ACCOUNT_VP: &str = "vp_user.wasm";
+/// The VP used for tokens that don't specify one
+pub const DEFAULT_TOKEN_VP: &str = "vp_token.wasm";
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to read the manifest {0}: {1}")]
+    ReadFile(PathBuf, std::io::Error),
+    #[error("Failed to parse the manifest {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+    #[error("The alias {0} is used for more than one account")]
+    DuplicateAlias(String),
+    #[error(
+        "No source is specified for the account {0}, either in the account or \
+         at the top of the manifest"
+    )]
+    MissingSource(String),
+}
+
+/// An application manifest
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// The default source of the transactions that initialize new accounts
+    pub source: Option<String>,
+    /// Established accounts, by their alias in the wallet
+    #[serde(default)]
+    pub accounts: BTreeMap<String, AccountSpec>,
+    /// Token accounts, by their alias in the wallet
+    #[serde(default)]
+    pub tokens: BTreeMap<String, AccountSpec>,
+    /// Transfers that top up the balances of their targets
+    #[serde(default)]
+    pub transfers: Vec<TransferSpec>,
+}
+
+/// An account in the manifest
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountSpec {
+    /// The public key, public key hash or key alias used for a new account
+    pub public_key: String,
+    /// The path to the VP WASM code of the account
+    pub vp: Option<PathBuf>,
+    /// The source of the transaction that initializes the account, if
+    /// different from the manifest's source
+    pub source: Option<String>,
+}
+
+/// A transfer in the manifest
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransferSpec {
+    /// The source address or alias
+    pub source: String,
+    /// The target address or alias
+    pub target: String,
+    /// The token address or alias
+    pub token: String,
+    /// The balance that the target should have at least. Only the missing
+    /// amount is transferred.
+    pub amount: token::Amount,
+}
+
+/// The current state of the manifest's accounts on chain
+#[derive(Clone, Debug, Default)]
+pub struct ChainState {
+    /// The VP code of the accounts that exist on chain, by their alias
+    pub vps: HashMap<String, Vec<u8>>,
+    /// The balances of the transfers' targets, by the target's and the
+    /// token's address or alias
+    pub balances: HashMap<(String, String), token::Amount>,
+}
+
+/// A transaction needed to apply a manifest
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    /// Initialize a new account and save its address under the alias
+    InitAccount {
+        alias: String,
+        source: String,
+        public_key: String,
+        vp: PathBuf,
+    },
+    /// Update the VP of an existing account
+    UpdateVp { alias: String, vp: PathBuf },
+    /// Transfer the missing amount of a target's balance
+    Transfer {
+        source: String,
+        target: String,
+        token: String,
+        amount: token::Amount,
+    },
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::InitAccount {
+                alias,
+                source,
+                public_key,
+                vp,
+            } => write!(
+                f,
+                "Initialize account {} with public key {} and VP {}, from \
+                 source {}",
+                alias,
+                public_key,
+                vp.to_string_lossy(),
+                source
+            ),
+            Step::UpdateVp { alias, vp } => write!(
+                f,
+                "Update the VP of account {} to {}",
+                alias,
+                vp.to_string_lossy()
+            ),
+            Step::Transfer {
+                source,
+                target,
+                token,
+                amount,
+            } => write!(
+                f,
+                "Transfer {} {} from {} to {}",
+                amount, token, source, target
+            ),
+        }
+    }
+}
+
+impl Manifest {
+    /// Read a manifest from a TOML file
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let raw = fs::read_to_string(path)
+            .map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+        let manifest: Self = toml::from_str(&raw)
+            .map_err(|err| Error::Parse(path.to_owned(), err))?;
+        let mut aliases = HashSet::new();
+        for (alias, _, _) in manifest.all_accounts() {
+            if !aliases.insert(alias) {
+                return Err(Error::DuplicateAlias(alias.clone()));
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Iterate all the accounts and tokens, together with the path to their
+    /// VP code
+    pub fn all_accounts(
+        &self,
+    ) -> impl Iterator<Item = (&String, &AccountSpec, &Path)> {
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|(alias, account)| (alias, account, DEFAULT_ACCOUNT_VP));
+        let tokens = self
+            .tokens
+            .iter()
+            .map(|(alias, account)| (alias, account, DEFAULT_TOKEN_VP));
+        accounts.chain(tokens).map(|(alias, account, default_vp)| {
+            let vp = account
+                .vp
+                .as_deref()
+                .unwrap_or_else(|| Path::new(default_vp));
+            (alias, account, vp)
+        })
+    }
+
+    /// Compute the transactions needed to get from the given state on chain
+    /// to the state described by the manifest, in the order in which they
+    /// have to be submitted. The `vp_codes` are the VP codes of all the
+    /// accounts, by their alias.
+    pub fn plan(
+        &self,
+        state: &ChainState,
+        vp_codes: &HashMap<String, Vec<u8>>,
+    ) -> Result<Vec<Step>, Error> {
+        let mut init_steps = vec![];
+        let mut update_steps = vec![];
+        for (alias, account, vp) in self.all_accounts() {
+            match state.vps.get(alias) {
+                None => {
+                    let source = account
+                        .source
+                        .as_ref()
+                        .or_else(|| self.source.as_ref())
+                        .ok_or_else(|| Error::MissingSource(alias.clone()))?;
+                    init_steps.push(Step::InitAccount {
+                        alias: alias.clone(),
+                        source: source.clone(),
+                        public_key: account.public_key.clone(),
+                        vp: vp.to_owned(),
+                    })
+                }
+                Some(vp_on_chain) => {
+                    if vp_codes.get(alias) != Some(vp_on_chain) {
+                        update_steps.push(Step::UpdateVp {
+                            alias: alias.clone(),
+                            vp: vp.to_owned(),
+                        })
+                    }
+                }
+            }
+        }
+        // The transfers have to follow the accounts that they may depend on
+        let transfer_steps = self.transfers.iter().filter_map(|transfer| {
+            let balance = state
+                .balances
+                .get(&(transfer.target.clone(), transfer.token.clone()))
+                .copied()
+                .unwrap_or_default();
+            (balance < transfer.amount).then(|| Step::Transfer {
+                source: transfer.source.clone(),
+                target: transfer.target.clone(),
+                token: transfer.token.clone(),
+                amount: transfer.amount - balance,
+            })
+        });
+        Ok(init_steps
+            .into_iter()
+            .chain(update_steps)
+            .chain(transfer_steps)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+source = "Bertha"
+
+[accounts.my-app]
+public_key = "my-app-key"
+vp = "my_app_vp.wasm"
+
+[accounts.other-app]
+public_key = "other-app-key"
+source = "Albert"
+
+[tokens.my-token]
+public_key = "my-app-key"
+
+[[transfers]]
+source = "Bertha"
+target = "my-app"
+token = "XAN"
+amount = "1000"
+"#;
+
+    fn manifest() -> Manifest {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        fs::write(&path, MANIFEST).unwrap();
+        Manifest::load(&path).unwrap()
+    }
+
+    /// Test that nothing is on chain yet, all the accounts are initialized
+    /// and the full amount is transferred
+    #[test]
+    fn test_plan_from_empty_state() {
+        let manifest = manifest();
+        let plan = manifest
+            .plan(&ChainState::default(), &HashMap::new())
+            .unwrap();
+        assert_eq!(
+            plan,
+            vec![
+                Step::InitAccount {
+                    alias: "my-app".into(),
+                    source: "Bertha".into(),
+                    public_key: "my-app-key".into(),
+                    vp: "my_app_vp.wasm".into(),
+                },
+                Step::InitAccount {
+                    alias: "other-app".into(),
+                    source: "Albert".into(),
+                    public_key: "other-app-key".into(),
+                    vp: DEFAULT_ACCOUNT_VP.into(),
+                },
+                Step::InitAccount {
+                    alias: "my-token".into(),
+                    source: "Bertha".into(),
+                    public_key: "my-app-key".into(),
+                    vp: DEFAULT_TOKEN_VP.into(),
+                },
+                Step::Transfer {
+                    source: "Bertha".into(),
+                    target: "my-app".into(),
+                    token: "XAN".into(),
+                    amount: token::Amount::whole(1000),
+                },
+            ]
+        );
+    }
+
+    /// Test that only the differences from the state on chain are planned
+    #[test]
+    fn test_plan_only_the_diff() {
+        let manifest = manifest();
+        let vp_codes: HashMap<String, Vec<u8>> = [
+            ("my-app".to_owned(), vec![1]),
+            ("other-app".to_owned(), vec![2]),
+            ("my-token".to_owned(), vec![3]),
+        ]
+        .into_iter()
+        .collect();
+        let mut state = ChainState {
+            vps: vp_codes.clone(),
+            ..Default::default()
+        };
+        state.vps.insert("my-app".into(), vec![0]);
+        state
+            .balances
+            .insert(("my-app".into(), "XAN".into()), token::Amount::whole(400));
+        let plan = manifest.plan(&state, &vp_codes).unwrap();
+        assert_eq!(
+            plan,
+            vec![
+                Step::UpdateVp {
+                    alias: "my-app".into(),
+                    vp: "my_app_vp.wasm".into(),
+                },
+                Step::Transfer {
+                    source: "Bertha".into(),
+                    target: "my-app".into(),
+                    token: "XAN".into(),
+                    amount: token::Amount::whole(600),
+                },
+            ]
+        );
+
+        // When everything is on chain, there's nothing to do
+        state.vps = vp_codes.clone();
+        state.balances.insert(
+            ("my-app".into(), "XAN".into()),
+            token::Amount::whole(1000),
+        );
+        let plan = manifest.plan(&state, &vp_codes).unwrap();
+        assert!(plan.is_empty());
+    }
+
+    /// Test that an alias cannot be used for both an account and a token
+    #[test]
+    fn test_duplicate_alias() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        let raw = r#"
+[accounts.my-app]
+public_key = "my-app-key"
+
+[tokens.my-app]
+public_key = "my-app-key"
+"#;
+        fs::write(&path, raw).unwrap();
+        assert!(matches!(
+            Manifest::load(&path),
+            Err(Error::DuplicateAlias(alias)) if alias == "my-app"
+        ));
+    }
+}
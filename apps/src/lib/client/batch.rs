@@ -0,0 +1,314 @@
+//! Batches of operations submitted in a single transaction.
+//!
+//! A batch file is a TOML file with a list of operations that are applied
+//! atomically, in the given order. If any of the operations fails or the
+//! validity predicates reject any of their changes, none of them are applied.
+//! The batch is signed once, by default by the signer of its first operation.
+//!
+//! ```toml
+//! [[ops]]
+//! type = "transfer"
+//! source = "Bertha"
+//! target = "Albert"
+//! token = "XAN"
+//! amount = "10"
+//!
+//! # Also "unbond", and "withdraw" without the amount. The source defaults to
+//! # the validator.
+//! [[ops]]
+//! type = "bond"
+//! validator = "validator"
+//! source = "Bertha"
+//! amount = "100"
+//!
+//! [[ops]]
+//! type = "update-vp"
+//! address = "my-account"
+//! vp = "vp_user.wasm"
+//!
+//! # The data are optional
+//! [[ops]]
+//! type = "custom"
+//! code = "tx_custom.wasm"
+//! data = "tx_custom_data.bin"
+//! ```
+//!
+//! A bonds file is a shorthand for a batch of bonds from a single source to
+//! several validators. It's in the JSON format if it has the `json` extension,
+//! otherwise in the TOML format.
+//!
+//! ```toml
+//! [[bonds]]
+//! validator = "validator-1"
+//! amount = "100"
+//!
+//! [[bonds]]
+//! validator = "validator-2"
+//! amount = "50"
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anoma::types::token;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::draft::is_json;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to read the batch file {0}: {1}")]
+    ReadFile(PathBuf, std::io::Error),
+    #[error("Failed to parse the batch file {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+    #[error("The batch file {0} doesn't contain any operations")]
+    Empty(PathBuf),
+    #[error("Failed to parse the bonds file {0}: {1}")]
+    ParseBondsJson(PathBuf, serde_json::Error),
+    #[error("Failed to parse the bonds file {0}: {1}")]
+    ParseBondsToml(PathBuf, toml::de::Error),
+    #[error("The bonds file {0} doesn't contain any bonds")]
+    NoBonds(PathBuf),
+}
+
+/// A batch file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchFile {
+    /// The operations, in the order in which they are applied
+    #[serde(default)]
+    pub ops: Vec<OpSpec>,
+}
+
+/// An operation in a batch file. The addresses are given raw or as aliases
+/// in the wallet.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum OpSpec {
+    /// A token transfer
+    Transfer {
+        source: String,
+        target: String,
+        token: String,
+        amount: token::Amount,
+    },
+    /// A bond of tokens to a validator
+    Bond {
+        validator: String,
+        source: Option<String>,
+        amount: token::Amount,
+    },
+    /// An unbond of tokens from a validator
+    Unbond {
+        validator: String,
+        source: Option<String>,
+        amount: token::Amount,
+    },
+    /// A withdrawal of unbonded tokens
+    Withdraw {
+        validator: String,
+        source: Option<String>,
+    },
+    /// An update of the VP of an established account
+    UpdateVp { address: String, vp: PathBuf },
+    /// A custom tx code with optional data
+    Custom {
+        code: PathBuf,
+        data: Option<PathBuf>,
+    },
+}
+
+impl OpSpec {
+    /// The address that would sign this operation if it was submitted on its
+    /// own, if any
+    pub fn default_signer(&self) -> Option<&String> {
+        match self {
+            OpSpec::Transfer { source, .. } => Some(source),
+            OpSpec::Bond {
+                validator, source, ..
+            }
+            | OpSpec::Unbond {
+                validator, source, ..
+            }
+            | OpSpec::Withdraw { validator, source } => {
+                Some(source.as_ref().unwrap_or(validator))
+            }
+            OpSpec::UpdateVp { address, .. } => Some(address),
+            OpSpec::Custom { .. } => None,
+        }
+    }
+}
+
+impl BatchFile {
+    /// Read a batch from a TOML file
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let raw = fs::read_to_string(path)
+            .map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+        let batch: Self = toml::from_str(&raw)
+            .map_err(|err| Error::Parse(path.to_owned(), err))?;
+        if batch.ops.is_empty() {
+            return Err(Error::Empty(path.to_owned()));
+        }
+        Ok(batch)
+    }
+
+    /// The default signer of the batch is the signer of its first operation
+    /// that has one
+    pub fn default_signer(&self) -> Option<&String> {
+        self.ops.iter().find_map(OpSpec::default_signer)
+    }
+}
+
+/// A bonds file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BondsFile {
+    /// The bonds, in the order in which they are applied
+    #[serde(default)]
+    pub bonds: Vec<BondSpec>,
+}
+
+/// A bond in a bonds file. The validator address is given raw or as an alias
+/// in the wallet.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BondSpec {
+    pub validator: String,
+    pub amount: token::Amount,
+}
+
+impl BondsFile {
+    /// Read bonds from a JSON or TOML file
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let raw = fs::read_to_string(path)
+            .map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+        let bonds: Self = if is_json(path) {
+            serde_json::from_str(&raw)
+                .map_err(|err| Error::ParseBondsJson(path.to_owned(), err))?
+        } else {
+            toml::from_str(&raw)
+                .map_err(|err| Error::ParseBondsToml(path.to_owned(), err))?
+        };
+        if bonds.bonds.is_empty() {
+            return Err(Error::NoBonds(path.to_owned()));
+        }
+        Ok(bonds)
+    }
+
+    /// The total amount of all the bonds
+    pub fn total_amount(&self) -> token::Amount {
+        self.bonds
+            .iter()
+            .fold(token::Amount::default(), |acc, bond| acc + bond.amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BATCH: &str = r#"
+[[ops]]
+type = "custom"
+code = "tx_custom.wasm"
+
+[[ops]]
+type = "transfer"
+source = "Bertha"
+target = "Albert"
+token = "XAN"
+amount = "10"
+
+[[ops]]
+type = "withdraw"
+validator = "validator"
+"#;
+
+    /// Test that a batch file is parsed in order, with the default signer of
+    /// its first operation that has one
+    #[test]
+    fn test_load_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("batch.toml");
+        fs::write(&path, BATCH).unwrap();
+        let batch = BatchFile::load(&path).unwrap();
+        assert_eq!(
+            batch.ops,
+            vec![
+                OpSpec::Custom {
+                    code: "tx_custom.wasm".into(),
+                    data: None,
+                },
+                OpSpec::Transfer {
+                    source: "Bertha".into(),
+                    target: "Albert".into(),
+                    token: "XAN".into(),
+                    amount: token::Amount::whole(10),
+                },
+                OpSpec::Withdraw {
+                    validator: "validator".into(),
+                    source: None,
+                },
+            ]
+        );
+        assert_eq!(batch.default_signer(), Some(&"Bertha".to_owned()));
+        assert_eq!(
+            batch.ops[2].default_signer(),
+            Some(&"validator".to_owned())
+        );
+
+        fs::write(&path, "ops = []").unwrap();
+        assert!(matches!(BatchFile::load(&path), Err(Error::Empty(_))));
+    }
+
+    /// Test that a bonds file is parsed in both formats
+    #[test]
+    fn test_load_bonds() {
+        let expected = vec![
+            BondSpec {
+                validator: "validator-1".into(),
+                amount: token::Amount::whole(100),
+            },
+            BondSpec {
+                validator: "validator-2".into(),
+                amount: token::Amount::whole(50),
+            },
+        ];
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = dir.path().join("bonds.toml");
+        fs::write(
+            &path,
+            r#"
+[[bonds]]
+validator = "validator-1"
+amount = "100"
+
+[[bonds]]
+validator = "validator-2"
+amount = "50"
+"#,
+        )
+        .unwrap();
+        let bonds = BondsFile::load(&path).unwrap();
+        assert_eq!(bonds.bonds, expected);
+        assert_eq!(bonds.total_amount(), token::Amount::whole(150));
+
+        let path = dir.path().join("bonds.json");
+        fs::write(
+            &path,
+            r#"{"bonds": [
+                {"validator": "validator-1", "amount": "100"},
+                {"validator": "validator-2", "amount": "50"}
+            ]}"#,
+        )
+        .unwrap();
+        let bonds = BondsFile::load(&path).unwrap();
+        assert_eq!(bonds.bonds, expected);
+
+        fs::write(&path, r#"{"bonds": []}"#).unwrap();
+        assert!(matches!(BondsFile::load(&path), Err(Error::NoBonds(_))));
+    }
+}
@@ -0,0 +1,477 @@
+//! Line editing for the interactive client console.
+//!
+//! When the standard input is a terminal, the lines are read in the raw mode
+//! of the terminal, which allows to move the cursor, browse the command
+//! history with the up and down arrows and complete the words with tab.
+//! Otherwise, the lines are read from the standard input as they are.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// The file name of the console's command history inside the base directory
+pub const HISTORY_FILE: &str = "console_history";
+
+/// The maximum number of commands kept in the history
+const MAX_HISTORY: usize = 1000;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SplitError {
+    #[error("Missing a closing quote")]
+    UnterminatedQuote,
+    #[error("Missing a character to escape after a backslash")]
+    TrailingEscape,
+}
+
+/// A line editor with a command history that is persisted in a file and tab
+/// completion of words.
+#[derive(Debug)]
+pub struct LineEditor {
+    history: Vec<String>,
+    history_file: PathBuf,
+    completions: Vec<String>,
+}
+
+impl LineEditor {
+    /// Create a line editor with the history loaded from the given file, if
+    /// it exists.
+    pub fn new(history_file: PathBuf) -> Self {
+        let mut history: Vec<String> = fs::read_to_string(&history_file)
+            .map(|history| history.lines().map(String::from).collect())
+            .unwrap_or_default();
+        if history.len() > MAX_HISTORY {
+            history.drain(..history.len() - MAX_HISTORY);
+        }
+        Self {
+            history,
+            history_file,
+            completions: vec![],
+        }
+    }
+
+    /// Set the words that can be completed with tab.
+    pub fn set_completions(&mut self, mut completions: Vec<String>) {
+        completions.sort();
+        completions.dedup();
+        self.completions = completions;
+    }
+
+    /// Get the command history, the most recent command is the last one.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Add a command to the history and append it to the history file.
+    pub fn add_history(&mut self, line: &str) {
+        if self.history.last().map(String::as_str) == Some(line) {
+            return;
+        }
+        self.history.push(line.to_owned());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        let appended = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.history_file)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(err) = appended {
+            tracing::debug!("Failed to save the console history: {}", err);
+        }
+    }
+
+    /// Read a line with the given prompt. Returns `None` at the end of the
+    /// input.
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        #[cfg(unix)]
+        {
+            if raw::is_terminal() {
+                return self.read_line_raw(prompt);
+            }
+        }
+        print!("{}", prompt);
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim_end_matches(&['\n', '\r'][..]).to_owned()))
+    }
+
+    #[cfg(unix)]
+    fn read_line_raw(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        use std::io::Read;
+
+        // Control characters
+        const CTRL_A: u8 = 1;
+        const CTRL_C: u8 = 3;
+        const CTRL_D: u8 = 4;
+        const CTRL_E: u8 = 5;
+        const BACKSPACE: u8 = 8;
+        const TAB: u8 = 9;
+        const CTRL_U: u8 = 21;
+        const ESC: u8 = 27;
+        const DELETE: u8 = 127;
+
+        let _raw_mode = raw::RawMode::enable()?;
+        let mut stdin = io::stdin().lock().bytes();
+        let mut next_byte = move || stdin.next().transpose();
+        let mut stdout = io::stdout();
+
+        let mut line: Vec<char> = vec![];
+        let mut cursor = 0;
+        // The index of the history entry being edited, the new line is at the
+        // end of the history
+        let mut history_ix = self.history.len();
+        redraw(&mut stdout, prompt, &line, cursor)?;
+        loop {
+            let byte = match next_byte()? {
+                Some(byte) => byte,
+                None => return Ok(None),
+            };
+            match byte {
+                b'\r' | b'\n' => {
+                    write!(stdout, "\r\n")?;
+                    return Ok(Some(line.into_iter().collect()));
+                }
+                CTRL_C => {
+                    write!(stdout, "^C\r\n")?;
+                    line.clear();
+                    cursor = 0;
+                    history_ix = self.history.len();
+                }
+                CTRL_D if line.is_empty() => {
+                    write!(stdout, "\r\n")?;
+                    return Ok(None);
+                }
+                CTRL_D => {
+                    if cursor < line.len() {
+                        line.remove(cursor);
+                    }
+                }
+                BACKSPACE | DELETE => {
+                    if cursor > 0 {
+                        cursor -= 1;
+                        line.remove(cursor);
+                    }
+                }
+                CTRL_A => cursor = 0,
+                CTRL_E => cursor = line.len(),
+                CTRL_U => {
+                    line.drain(..cursor);
+                    cursor = 0;
+                }
+                TAB => {
+                    self.complete_word(&mut stdout, &mut line, &mut cursor)?
+                }
+                ESC => {
+                    if next_byte()? != Some(b'[') {
+                        continue;
+                    }
+                    match next_byte()? {
+                        // Up
+                        Some(b'A') if history_ix > 0 => {
+                            history_ix -= 1;
+                            line = self.history[history_ix].chars().collect();
+                            cursor = line.len();
+                        }
+                        // Down
+                        Some(b'B') if history_ix < self.history.len() => {
+                            history_ix += 1;
+                            line = self
+                                .history
+                                .get(history_ix)
+                                .map(|entry| entry.chars().collect())
+                                .unwrap_or_default();
+                            cursor = line.len();
+                        }
+                        // Right
+                        Some(b'C') if cursor < line.len() => cursor += 1,
+                        // Left
+                        Some(b'D') if cursor > 0 => cursor -= 1,
+                        // Home
+                        Some(b'H') => cursor = 0,
+                        // End
+                        Some(b'F') => cursor = line.len(),
+                        // Delete
+                        Some(b'3') => {
+                            if next_byte()? == Some(b'~') && cursor < line.len()
+                            {
+                                line.remove(cursor);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                byte if byte >= b' ' => {
+                    // Read the rest of a multi-byte UTF-8 character
+                    let len = match byte.leading_ones() {
+                        2 => 2,
+                        3 => 3,
+                        4 => 4,
+                        _ => 1,
+                    };
+                    let mut bytes = vec![byte];
+                    for _ in 1..len {
+                        if let Some(byte) = next_byte()? {
+                            bytes.push(byte);
+                        }
+                    }
+                    if let Ok(chars) = std::str::from_utf8(&bytes) {
+                        for char in chars.chars() {
+                            line.insert(cursor, char);
+                            cursor += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            redraw(&mut stdout, prompt, &line, cursor)?;
+        }
+    }
+
+    /// Complete the word before the cursor. If there are multiple possible
+    /// completions, they are extended to their common prefix and printed.
+    #[cfg(unix)]
+    fn complete_word(
+        &self,
+        stdout: &mut io::Stdout,
+        line: &mut Vec<char>,
+        cursor: &mut usize,
+    ) -> io::Result<()> {
+        let start = line[..*cursor]
+            .iter()
+            .rposition(|char| char.is_whitespace())
+            .map(|ix| ix + 1)
+            .unwrap_or_default();
+        let prefix: String = line[start..*cursor].iter().collect();
+        let candidates = complete(&self.completions, &prefix);
+        let completion = match candidates.as_slice() {
+            [] => return Ok(()),
+            [candidate] => format!("{} ", candidate),
+            candidates => {
+                let common = common_prefix(candidates);
+                if common.len() == prefix.len() {
+                    write!(stdout, "\r\n{}\r\n", candidates.join("  "))?;
+                }
+                common.to_owned()
+            }
+        };
+        let rest = line.split_off(*cursor);
+        line.truncate(start);
+        line.extend(completion.chars());
+        *cursor = line.len();
+        line.extend(rest);
+        Ok(())
+    }
+}
+
+/// Redraw the prompt and the line and move the cursor to its position.
+#[cfg(unix)]
+fn redraw(
+    stdout: &mut io::Stdout,
+    prompt: &str,
+    line: &[char],
+    cursor: usize,
+) -> io::Result<()> {
+    let line: String = line.iter().collect();
+    // Return to the start and clear the rest of the terminal line
+    write!(stdout, "\r{}{}\x1b[K", prompt, line)?;
+    let back = line.chars().count() - cursor;
+    if back > 0 {
+        write!(stdout, "\x1b[{}D", back)?;
+    }
+    stdout.flush()
+}
+
+/// Get the completions that start with the given prefix.
+pub fn complete<'a>(completions: &'a [String], prefix: &str) -> Vec<&'a str> {
+    completions
+        .iter()
+        .map(String::as_str)
+        .filter(|completion| completion.starts_with(prefix))
+        .collect()
+}
+
+/// Get the longest common prefix of the given words.
+pub fn common_prefix<'a>(words: &[&'a str]) -> &'a str {
+    let first = match words.first() {
+        Some(first) => *first,
+        None => return "",
+    };
+    let len = words[1..].iter().fold(first.len(), |len, word| {
+        first[..len]
+            .char_indices()
+            .zip(word.chars())
+            .find(|((_ix, a), b)| a != b)
+            .map(|((ix, _a), _b)| ix)
+            .unwrap_or_else(|| len.min(word.len()))
+    });
+    &first[..len]
+}
+
+/// Split a command line into words separated by whitespace. Like in a shell,
+/// whitespace can be kept in a word by quoting it with single or double
+/// quotes or by escaping it with a backslash. A backslash inside double
+/// quotes only escapes a double quote or a backslash.
+pub fn split_words(line: &str) -> Result<Vec<String>, SplitError> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            char if char.is_whitespace() => {
+                words.extend(word.take());
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(char) => word.push(char),
+                        None => return Err(SplitError::UnterminatedQuote),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(char @ ('"' | '\\')) => word.push(char),
+                            Some(char) => {
+                                word.push('\\');
+                                word.push(char);
+                            }
+                            None => return Err(SplitError::UnterminatedQuote),
+                        },
+                        Some(char) => word.push(char),
+                        None => return Err(SplitError::UnterminatedQuote),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(char) => word.get_or_insert_with(String::new).push(char),
+                None => return Err(SplitError::TrailingEscape),
+            },
+            char => word.get_or_insert_with(String::new).push(char),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(unix)]
+mod raw {
+    use std::io;
+    use std::mem::MaybeUninit;
+
+    /// Check if the standard input is a terminal
+    pub fn is_terminal() -> bool {
+        unsafe { libc::isatty(libc::STDIN_FILENO) == 1 }
+    }
+
+    /// The raw mode of the terminal, in which the input is available byte by
+    /// byte and isn't echoed. The original mode is restored on drop.
+    pub struct RawMode {
+        original: libc::termios,
+    }
+
+    impl RawMode {
+        pub fn enable() -> io::Result<Self> {
+            let mut original = MaybeUninit::<libc::termios>::uninit();
+            if unsafe {
+                libc::tcgetattr(libc::STDIN_FILENO, original.as_mut_ptr())
+            } != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            let original = unsafe { original.assume_init() };
+            let mut raw = original;
+            raw.c_lflag &=
+                !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw)
+            } != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { original })
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe {
+                libc::tcsetattr(
+                    libc::STDIN_FILENO,
+                    libc::TCSAFLUSH,
+                    &self.original,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_words() {
+        let split = |line| split_words(line).unwrap();
+        assert_eq!(split(""), Vec::<String>::new());
+        assert_eq!(
+            split("  transfer  --amount 10 "),
+            ["transfer", "--amount", "10"]
+        );
+        assert_eq!(
+            split(r#"a 'b c' "d \"e\" \f" g\ h"#),
+            ["a", "b c", r#"d "e" \f"#, "g h"]
+        );
+        assert_eq!(split("'' x"), ["", "x"]);
+        assert_eq!(split_words("'a"), Err(SplitError::UnterminatedQuote));
+        assert_eq!(split_words(r#""a\"#), Err(SplitError::UnterminatedQuote));
+        assert_eq!(split_words(r"a\"), Err(SplitError::TrailingEscape));
+    }
+
+    #[test]
+    fn test_complete() {
+        let completions: Vec<String> = ["balance", "bond", "bonds", "transfer"]
+            .iter()
+            .map(|word| word.to_string())
+            .collect();
+        assert_eq!(complete(&completions, "bo"), ["bond", "bonds"]);
+        assert_eq!(complete(&completions, "t"), ["transfer"]);
+        assert!(complete(&completions, "x").is_empty());
+
+        assert_eq!(common_prefix(&["bond", "bonds"]), "bond");
+        assert_eq!(common_prefix(&["balance", "bond", "bonds"]), "b");
+        assert_eq!(common_prefix(&["transfer"]), "transfer");
+        assert_eq!(common_prefix(&["ä1", "ä2"]), "ä");
+        assert_eq!(common_prefix(&[]), "");
+    }
+
+    #[test]
+    fn test_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let history_file = dir.path().join(HISTORY_FILE);
+        let mut editor = LineEditor::new(history_file.clone());
+        assert!(editor.history().is_empty());
+
+        editor.add_history("epoch");
+        editor.add_history("epoch");
+        editor.add_history("balance --owner bertha");
+        assert_eq!(editor.history(), ["epoch", "balance --owner bertha"]);
+
+        // The history is loaded from the file
+        let editor = LineEditor::new(history_file);
+        assert_eq!(editor.history(), ["epoch", "balance --owner bertha"]);
+    }
+}
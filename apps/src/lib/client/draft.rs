@@ -0,0 +1,183 @@
+//! Drafts of transactions saved to a file to be reviewed and submitted later,
+//! e.g. when one person prepares a transaction and another one signs it.
+//!
+//! A draft is saved in the Borsh encoding, unless the file has the `json`
+//! extension. A JSON draft is an envelope that shows the hash of the
+//! transaction's code, its data with the name of their schema, if they're
+//! tagged with one, and its timestamps for review, next to the
+//! hex encoded Borsh encoding of the transaction. The transaction is only
+//! loaded from a JSON draft if the envelope matches it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anoma::proto::{SignedTxData, Tx};
+use anoma::types::hash::Hash;
+use anoma::types::transaction::schema;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to read the draft {0}: {1}")]
+    ReadFile(PathBuf, std::io::Error),
+    #[error("Failed to write the draft {0}: {1}")]
+    WriteFile(PathBuf, std::io::Error),
+    #[error("Failed to decode the draft {0}: {1}")]
+    Decode(PathBuf, std::io::Error),
+    #[error("Failed to parse the draft {0}: {1}")]
+    Parse(PathBuf, serde_json::Error),
+    #[error("Failed to decode the transaction in the draft {0}: {1}")]
+    Hex(PathBuf, hex::FromHexError),
+    #[error(
+        "The draft {0} doesn't match the transaction in it. Was it edited \
+         after it had been saved?"
+    )]
+    Mismatch(PathBuf),
+}
+
+/// A transaction that has been prepared, but not submitted yet
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct TxDraft {
+    /// Whether the transaction's data are signed
+    pub signed: bool,
+    /// The transaction
+    pub tx: Tx,
+}
+
+/// The JSON envelope of a draft
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonDraft {
+    signed: bool,
+    code_hash: String,
+    /// Hex encoded data of the transaction, without the signature
+    data: Option<String>,
+    /// The name of the schema of the data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+    timestamp: String,
+    expiration: Option<String>,
+    /// Hex encoded Borsh encoding of the transaction
+    tx: String,
+}
+
+impl TxDraft {
+    /// Read a draft from a file
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let bytes = fs::read(path)
+            .map_err(|err| Error::ReadFile(path.to_owned(), err))?;
+        if is_json(path) {
+            let json: JsonDraft = serde_json::from_slice(&bytes)
+                .map_err(|err| Error::Parse(path.to_owned(), err))?;
+            let tx_bytes = hex::decode(&json.tx)
+                .map_err(|err| Error::Hex(path.to_owned(), err))?;
+            let tx = Tx::try_from_slice(&tx_bytes)
+                .map_err(|err| Error::Decode(path.to_owned(), err))?;
+            let draft = Self {
+                signed: json.signed,
+                tx,
+            };
+            if draft.to_json() != json {
+                return Err(Error::Mismatch(path.to_owned()));
+            }
+            Ok(draft)
+        } else {
+            Self::try_from_slice(&bytes)
+                .map_err(|err| Error::Decode(path.to_owned(), err))
+        }
+    }
+
+    /// Write the draft to a file
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let bytes = if is_json(path) {
+            serde_json::to_vec_pretty(&self.to_json())
+                .expect("Encoding a draft shouldn't fail")
+        } else {
+            self.try_to_vec().expect("Encoding a draft shouldn't fail")
+        };
+        fs::write(path, bytes)
+            .map_err(|err| Error::WriteFile(path.to_owned(), err))
+    }
+
+    /// The data of the transaction, without the signature of a signed draft
+    pub fn payload(&self) -> Option<Vec<u8>> {
+        if self.signed {
+            self.tx
+                .data
+                .as_ref()
+                .and_then(|data| SignedTxData::try_from_slice(data).ok())
+                .and_then(|signed| signed.data)
+        } else {
+            self.tx.data.clone()
+        }
+    }
+
+    fn to_json(&self) -> JsonDraft {
+        let payload = self.payload();
+        let schema = payload
+            .as_ref()
+            .and_then(|data| schema::identify(data))
+            .map(|(schema, _)| schema.name.to_owned());
+        JsonDraft {
+            signed: self.signed,
+            code_hash: Hash::sha256(&self.tx.code).to_string(),
+            data: payload.map(hex::encode),
+            schema,
+            timestamp: self.tx.timestamp.to_rfc3339(),
+            expiration: self.tx.expiration.map(|time| time.to_rfc3339()),
+            tx: hex::encode(
+                self.tx.try_to_vec().expect("Encoding a tx shouldn't fail"),
+            ),
+        }
+    }
+}
+
+/// Check if the file at the given path is in the JSON format, i.e. it has the
+/// `json` extension
+pub fn is_json(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::key::testing::keypair_1;
+
+    use super::*;
+
+    /// Test that drafts are saved and loaded in both formats and that an
+    /// edited JSON draft is refused
+    #[test]
+    fn test_save_and_load_draft() {
+        let dir = tempfile::tempdir().unwrap();
+        let tx = Tx::new(b"tx code".to_vec(), Some(b"tx data".to_vec()));
+        let unsigned = TxDraft {
+            signed: false,
+            tx: tx.clone(),
+        };
+        let signed = TxDraft {
+            signed: true,
+            tx: tx.sign(&keypair_1()),
+        };
+        assert_eq!(signed.payload(), unsigned.payload());
+
+        for file in ["draft.bin", "draft.json"] {
+            let path = dir.path().join(file);
+            for draft in [&unsigned, &signed] {
+                draft.save(&path).unwrap();
+                assert_eq!(&TxDraft::load(&path).unwrap(), draft);
+            }
+        }
+
+        let path = dir.path().join("draft.json");
+        let mut json: JsonDraft =
+            serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        json.data = Some(hex::encode(b"other data"));
+        fs::write(&path, serde_json::to_vec(&json).unwrap()).unwrap();
+        assert!(matches!(TxDraft::load(&path), Err(Error::Mismatch(_))));
+    }
+}
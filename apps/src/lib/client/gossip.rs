@@ -1,95 +1,677 @@
 use std::collections::HashSet;
-use std::io::Write;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{Read, Write};
+use std::rc::Rc;
 
-use anoma::proto::Signed;
-use anoma::types::intent::{Exchange, FungibleTokenIntent};
-use borsh::BorshSerialize;
+use anoma::proto::{IntentCancel, Signed, Tx};
+use anoma::types::address::Address;
+use anoma::types::intent::{
+    Exchange, FungibleTokenIntent, IntentTransfers, MatchedExchanges,
+    RevealedRingIntent, RingIntent, RingSignedIntent,
+};
+use anoma::types::key::*;
+use anoma::types::time::{DateTimeUtc, DurationSecs};
+use anoma::types::token;
+use anoma::types::transaction::schema;
+use borsh::{BorshDeserialize, BorshSerialize};
+use futures::{future, stream, StreamExt};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
 use tendermint_config_abci::net::Address as TendermintAddress;
+use tonic::transport::Channel;
+use tonic::{Status, Streaming};
 
-use super::signing;
+use super::{rpc, signing};
+use crate::cli::context::{WalletAddress, WalletKeypair};
 use crate::cli::{self, args, Context};
+use crate::node::gossip::rpc::{auth, rate_limit, tls};
+use crate::node::matchmaker;
 use crate::proto::services::rpc_service_client::RpcServiceClient;
-use crate::proto::{services, RpcMessage};
+use crate::proto::{services, IntentReceipt, RpcMessage};
 use crate::wallet::Wallet;
 
-/// Create an intent, sign it and submit it to the gossip node (unless
-/// `to_stdout` is `true`).
+/// The default code of the settlement transaction of matched intents
+const TX_FROM_INTENT_WASM: &str = "tx_from_intent.wasm";
+
+/// The maximum number of times that a message rate limited by the gossip node
+/// is sent again
+const RATE_LIMITED_RETRIES: usize = 3;
+
+/// Create an intent, sign it and submit it to the gossip nodes (unless
+/// `to_stdout` is `true`), or to the configured gossip nodes if none is
+/// given. With `dry_run`, the first gossip node only responds with the
+/// matches that its matchmakers would make for the intent. With
+/// `expires_in`, all the exchanges of the intent expire after that duration.
+/// With `wait_for_match`, the matches of the submitted intent are printed
+/// until it's fully matched. With `signed`, an intent that has already been
+/// signed is submitted instead. With `reveal`, the source of a ring-signed
+/// intent is revealed to have it matched and settled. The intent printed to
+/// stdout or read from a file is encoded in the given `format`.
 pub async fn gossip_intent(
     mut ctx: Context,
     args::Intent {
-        node_addr,
+        mut node_addrs,
         topic,
         source,
         signing_key,
-        exchanges,
+        mut exchanges,
+        ring,
         ledger_address,
         to_stdout,
+        dry_run,
+        expires_in,
+        wait_for_match,
+        signed,
+        reveal,
+        format,
     }: args::Intent,
 ) {
-    let mut signed_exchanges: HashSet<Signed<Exchange>> =
-        HashSet::with_capacity(exchanges.len());
-    for exchange in exchanges {
-        let signed =
-            sign_exchange(&mut ctx.wallet, exchange, ledger_address.clone())
-                .await;
-        signed_exchanges.insert(signed);
+    if node_addrs.is_empty() {
+        node_addrs = ctx.config.client.gossip_nodes.clone();
     }
-
-    let source_keypair = match ctx.get_opt_cached(&signing_key) {
-        Some(key) => key,
-        None => {
-            let source = ctx.get_opt(&source).unwrap_or_else(|| {
-                eprintln!("A source or a signing key is required.");
-                cli::safe_exit(1)
-            });
-            signing::find_keypair(
-                &mut ctx.wallet,
-                &source,
-                ledger_address.clone(),
-            )
-            .await
+    if let Some(signed) = signed {
+        let data_bytes = read_signed_intent(signed, format);
+        let intent_exchanges = signed_intent_exchanges(&data_bytes);
+        return submit_intent(
+            node_addrs,
+            topic,
+            data_bytes,
+            &intent_exchanges,
+            dry_run,
+            wait_for_match,
+        )
+        .await;
+    }
+    if let Some(expires_in) = expires_in {
+        let expires = DateTimeUtc::now() + DurationSecs(expires_in.as_secs());
+        for exchange in &mut exchanges {
+            exchange.expires = Some(expires);
         }
+    }
+    let mut intent_exchanges = if dry_run { exchanges.clone() } else { vec![] };
+    let data_bytes = if let Some(reveal) = reveal {
+        let data_bytes = read_signed_intent(reveal, format);
+        let revealed = reveal_ring_intent(
+            &mut ctx,
+            &source,
+            &signing_key,
+            &data_bytes,
+            ledger_address,
+        )
+        .await;
+        intent_exchanges = revealed
+            .exchange
+            .iter()
+            .map(|exchange| exchange.data.clone())
+            .collect();
+        revealed.try_to_vec().unwrap()
+    } else if ring.is_empty() {
+        let (signed_ft, _source_keypair) = sign_intent(
+            &mut ctx,
+            &source,
+            &signing_key,
+            exchanges,
+            ledger_address,
+        )
+        .await;
+        signed_ft.try_to_vec().unwrap()
+    } else {
+        sign_ring_intent(
+            &mut ctx,
+            &source,
+            &signing_key,
+            exchanges,
+            ring,
+            ledger_address,
+        )
+        .await
+        .try_to_vec()
+        .unwrap()
     };
-    let signed_ft: Signed<FungibleTokenIntent> = Signed::new(
-        &*source_keypair,
-        FungibleTokenIntent {
-            exchange: signed_exchanges,
-        },
-    );
-    let data_bytes = signed_ft.try_to_vec().unwrap();
 
     if to_stdout {
         let mut out = std::io::stdout();
-        out.write_all(&data_bytes).unwrap();
+        out.write_all(&encode_intent(data_bytes, format)).unwrap();
         out.flush().unwrap();
     } else {
-        let node_addr = node_addr.expect(
-            "Gossip node address must be defined to submit the intent to it.",
+        submit_intent(
+            node_addrs,
+            topic,
+            data_bytes,
+            &intent_exchanges,
+            dry_run,
+            wait_for_match,
+        )
+        .await
+    }
+}
+
+/// Submit a signed intent to the gossip nodes concurrently and print the
+/// result of every node or, with `dry_run`, only request the matches that the
+/// first node's matchmakers would make for it
+async fn submit_intent(
+    node_addrs: Vec<String>,
+    topic: Option<String>,
+    data_bytes: Vec<u8>,
+    intent_exchanges: &[Exchange],
+    dry_run: bool,
+    wait_for_match: bool,
+) {
+    if dry_run {
+        let node_addr = node_addrs.into_iter().next().expect(
+            "Gossip node address must be defined to dry-run the intent.",
         );
+        dry_run_intent(node_addr, data_bytes, intent_exchanges).await;
+    } else {
+        if node_addrs.is_empty() {
+            panic!(
+                "Gossip node address must be defined to submit the intent to \
+                 it."
+            );
+        }
         let topic = topic.expect(
             "The topic must be defined to submit the intent to a gossip node.",
         );
 
-        match RpcServiceClient::connect(node_addr.clone()).await {
-            Ok(mut client) => {
-                let intent = anoma::proto::Intent::new(data_bytes);
-                let message: services::RpcMessage =
-                    RpcMessage::new_intent(intent, topic).into();
-                let response = client.send_message(message).await.expect(
-                    "Failed to send message and/or receive rpc response",
+        let intent = anoma::proto::Intent::new(data_bytes);
+        let submissions = node_addrs.into_iter().map(|node_addr| {
+            submit_intent_to_node(node_addr, &intent, &topic, wait_for_match)
+        });
+        let results = future::join_all(submissions).await;
+        let total = results.len();
+        let multiple = total > 1;
+        let mut accepted = 0;
+        let mut subscriptions = vec![];
+        for (node_addr, result) in results {
+            if multiple {
+                println!("Gossip node {}:", node_addr);
+            }
+            match result {
+                Ok((response, matches)) => {
+                    println!("{}", response.result);
+                    if let Some(receipt) = response.receipt {
+                        accepted += 1;
+                        print_intent_receipt(&intent, receipt);
+                        if let Some(matches) = matches {
+                            subscriptions.push((node_addr, matches));
+                        }
+                    }
+                }
+                Err(err) => {
+                    eprintln!("{}", err);
+                }
+            }
+        }
+        if multiple {
+            println!(
+                "The intent has been received by {} of {} gossip nodes.",
+                accepted, total
+            );
+        }
+        if !subscriptions.is_empty() {
+            wait_for_intent_matches(subscriptions).await;
+        }
+    }
+}
+
+/// The response of a gossip node to a submitted intent with the subscription
+/// to the matches of the intent by the node's matchmakers, if any
+type IntentSubmission = (
+    services::RpcResponse,
+    Option<Streaming<services::IntentMatchNotification>>,
+);
+
+/// Submit a signed intent to a gossip node. Returns the node's address with
+/// its response and, with `wait_for_match`, the subscription to the matches
+/// of the intent, which is made before the intent is submitted, so that none
+/// of its matches is missed.
+async fn submit_intent_to_node(
+    node_addr: String,
+    intent: &anoma::proto::Intent,
+    topic: &str,
+    wait_for_match: bool,
+) -> (String, Result<IntentSubmission, String>) {
+    let mut client = match tls::connect(node_addr.clone()).await {
+        Ok(client) => client,
+        Err(e) => {
+            let err =
+                format!("Error connecting RPC client to {}: {}", node_addr, e);
+            return (node_addr, Err(err));
+        }
+    };
+    let matches = if wait_for_match {
+        subscribe_intent_matches(&mut client, intent).await
+    } else {
+        None
+    };
+    let message: services::RpcMessage =
+        RpcMessage::new_intent(intent.clone(), topic.to_owned()).into();
+    let result = try_send_rpc_message(&mut client, message)
+        .await
+        .map(|response| (response, matches))
+        .map_err(|status| {
+            format!(
+                "The gossip node responded with an error: {}",
+                status.message()
+            )
+        });
+    (node_addr, result)
+}
+
+/// Encode a signed intent in the given format. The hex and base64 strings end
+/// with a newline.
+fn encode_intent(data_bytes: Vec<u8>, format: args::IntentFormat) -> Vec<u8> {
+    match format {
+        args::IntentFormat::Borsh => data_bytes,
+        args::IntentFormat::Hex => {
+            format!("{}\n", hex::encode(data_bytes)).into()
+        }
+        args::IntentFormat::Base64 => {
+            format!("{}\n", base64::encode(data_bytes)).into()
+        }
+    }
+}
+
+/// Decode a signed intent encoded in the given format. The whitespace around
+/// the hex and base64 strings is ignored.
+fn decode_intent(
+    encoded: Vec<u8>,
+    format: args::IntentFormat,
+) -> Result<Vec<u8>, String> {
+    match format {
+        args::IntentFormat::Borsh => Ok(encoded),
+        args::IntentFormat::Hex => {
+            hex::decode(String::from_utf8_lossy(&encoded).trim())
+                .map_err(|err| err.to_string())
+        }
+        args::IntentFormat::Base64 => {
+            base64::decode(String::from_utf8_lossy(&encoded).trim())
+                .map_err(|err| err.to_string())
+        }
+    }
+}
+
+/// Read an intent that has already been signed. Exits if it can't be read or
+/// it's not a signed intent.
+fn read_signed_intent(
+    signed: args::SignedIntentData,
+    format: args::IntentFormat,
+) -> Vec<u8> {
+    let decoded = match signed {
+        args::SignedIntentData::File(path) => {
+            let encoded = fs::read(&path).unwrap_or_else(|err| {
+                eprintln!(
+                    "Unable to read the intent from {}: {}",
+                    path.to_string_lossy(),
+                    err
                 );
-                println!("{:#?}", response);
+                cli::safe_exit(1)
+            });
+            decode_intent(encoded, format)
+        }
+        args::SignedIntentData::Stdin => {
+            let mut encoded = vec![];
+            if let Err(err) = std::io::stdin().read_to_end(&mut encoded) {
+                eprintln!("Unable to read the intent from stdin: {}", err);
+                cli::safe_exit(1)
+            }
+            decode_intent(encoded, format)
+        }
+        args::SignedIntentData::Bytes(bytes) => Ok(bytes),
+    };
+    let data_bytes = decoded.unwrap_or_else(|err| {
+        eprintln!("Unable to decode the intent: {}", err);
+        cli::safe_exit(1)
+    });
+    if Signed::<FungibleTokenIntent>::try_from_slice(&data_bytes).is_err()
+        && RingSignedIntent::try_from_slice(&data_bytes).is_err()
+        && RevealedRingIntent::try_from_slice(&data_bytes).is_err()
+    {
+        eprintln!("The data is not a signed intent.");
+        cli::safe_exit(1)
+    }
+    data_bytes
+}
+
+/// The exchanges of a signed intent. The source of the exchanges of an intent
+/// signed with a ring signature isn't known until it's revealed, so it has
+/// none.
+fn signed_intent_exchanges(data_bytes: &[u8]) -> Vec<Exchange> {
+    let exchanges = if let Ok(signed) =
+        Signed::<FungibleTokenIntent>::try_from_slice(data_bytes)
+    {
+        signed.data.exchange
+    } else if let Ok(revealed) = RevealedRingIntent::try_from_slice(data_bytes)
+    {
+        revealed.exchange
+    } else {
+        return vec![];
+    };
+    exchanges
+        .into_iter()
+        .map(|exchange| exchange.data)
+        .collect()
+}
+
+/// Subscribe to the matches of an intent by the gossip node's matchmakers
+async fn subscribe_intent_matches(
+    client: &mut RpcServiceClient<Channel>,
+    intent: &anoma::proto::Intent,
+) -> Option<Streaming<services::IntentMatchNotification>> {
+    let subscription = services::IntentMatchesSubscription {
+        intent_hash: intent.sha256().to_vec(),
+    };
+    match client
+        .subscribe_intent_matches(auth::request(subscription))
+        .await
+    {
+        Ok(response) => Some(response.into_inner()),
+        Err(status) => {
+            eprintln!(
+                "Failed to subscribe to the matches of the intent: {}",
+                status.message()
+            );
+            None
+        }
+    }
+}
+
+/// Print the notifications of the matches of an intent by the matchmakers of
+/// the gossip nodes until it's fully matched or all the subscriptions have
+/// ended
+async fn wait_for_intent_matches(
+    subscriptions: Vec<(String, Streaming<services::IntentMatchNotification>)>,
+) {
+    println!("Waiting for the intent to be matched...");
+    let multiple = subscriptions.len() > 1;
+    let mut notifications = stream::select_all(subscriptions.into_iter().map(
+        |(node_addr, matches)| {
+            Box::pin(
+                matches
+                    .map(move |notification| (node_addr.clone(), notification)),
+            )
+        },
+    ));
+    while let Some((node_addr, notification)) = notifications.next().await {
+        let via = if multiple {
+            format!("Gossip node {}: ", node_addr)
+        } else {
+            String::new()
+        };
+        match notification {
+            Ok(notification) => {
+                let matched = if notification.partial {
+                    "partially matched"
+                } else {
+                    "matched"
+                };
+                if notification.tx_hash.is_empty() {
+                    println!(
+                        "{}The intent has been {}, but the matchmaker \
+                         couldn't submit the transaction.",
+                        via, matched
+                    );
+                } else {
+                    println!(
+                        "{}The intent has been {} into the transaction {}.",
+                        via, matched, notification.tx_hash
+                    );
+                }
+                if !notification.partial {
+                    break;
+                }
             }
-            Err(e) => {
+            Err(status) => {
                 eprintln!(
-                    "Error connecting RPC client to {}: {}",
-                    node_addr, e
+                    "{}The subscription to the matches of the intent failed: \
+                     {}",
+                    via,
+                    status.message()
                 );
             }
+        }
+    }
+}
+
+/// Request the matches that the gossip node's matchmakers would make for an
+/// intent and print the expected fill of its exchanges
+async fn dry_run_intent(
+    node_addr: String,
+    data_bytes: Vec<u8>,
+    exchanges: &[Exchange],
+) {
+    let mut client = match tls::connect(node_addr.clone()).await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Error connecting RPC client to {}: {}", node_addr, e);
+            return;
+        }
+    };
+    let intent = anoma::proto::Intent::new(data_bytes);
+    let message: services::RpcMessage =
+        RpcMessage::new_intent_dry_run(intent).into();
+    let response = send_rpc_message(&mut client, message).await;
+    println!("{}", response.result);
+    for (ix, intent_match) in response.matches.into_iter().enumerate() {
+        println!(
+            "Match {} with {} intents:",
+            ix + 1,
+            intent_match.matched_intent_ids.len()
+        );
+        match MatchedExchanges::try_from_slice(&intent_match.tx_data[..]) {
+            Ok(matches) => print_intent_match(exchanges, &matches),
+            Err(_) => println!(
+                "  Transaction data: {}",
+                hex::encode(&intent_match.tx_data)
+            ),
+        }
+    }
+}
+
+/// Print the transfers of tokens of NFTs of a match
+fn print_nft_transfers(matches: &MatchedExchanges) {
+    for transfer in &matches.nft_transfers {
+        println!(
+            "  Transfer token {} of NFT {} from {} to {}",
+            transfer.token_id, transfer.nft, transfer.source, transfer.target
+        );
+    }
+}
+
+/// Print the transfers of a match of an intent with the given exchanges and
+/// the expected fill and price of the exchanges
+fn print_intent_match(exchanges: &[Exchange], matches: &MatchedExchanges) {
+    for transfer in &matches.transfers {
+        println!(
+            "  Transfer {} of token {} from {} to {}",
+            transfer.amount, transfer.token, transfer.source, transfer.target
+        );
+    }
+    print_nft_transfers(matches);
+    // The price of an exchange of a token of an NFT is the transfer above
+    for exchange in exchanges.iter().filter(|exchange| exchange.nft.is_none()) {
+        let mut sold = token::Amount::default();
+        let mut bought = token::Amount::default();
+        let mut counterparties = HashSet::new();
+        for transfer in &matches.transfers {
+            if transfer.source == exchange.addr
+                && transfer.token == exchange.token_sell
+            {
+                sold += transfer.amount;
+                counterparties.insert(&transfer.target);
+            } else if transfer.target == exchange.addr
+                && transfer.token == exchange.token_buy
+            {
+                bought += transfer.amount;
+                counterparties.insert(&transfer.source);
+            }
+        }
+        if sold == token::Amount::default() {
+            continue;
+        }
+        println!(
+            "  {} would sell {} of {} token {} for {} of token {} at a price \
+             of {} per sold token, with counterparties {}",
+            exchange.addr,
+            sold,
+            exchange.max_sell,
+            exchange.token_sell,
+            bought,
+            exchange.token_buy,
+            f64::from(bought) / f64::from(sold),
+            counterparties
+                .into_iter()
+                .map(|addr| addr.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+}
+
+/// Verify and print the gossip node's signed receipt of an intent
+fn print_intent_receipt(
+    intent: &anoma::proto::Intent,
+    receipt: services::IntentReceipt,
+) {
+    match IntentReceipt::try_from(receipt) {
+        Ok(receipt) if receipt.verify(intent) => {
+            let node = receipt
+                .node_peer_id()
+                .map(|peer_id| peer_id.to_string())
+                .unwrap_or_default();
+            println!(
+                "Intent receipt signed by the gossip node {}:\n  Intent hash: \
+                 {}\n  Topic: {}\n  Received at: {}\n  Signature: {}",
+                node,
+                hex::encode(&receipt.intent_hash),
+                receipt.topic,
+                receipt.received_at.to_rfc3339(),
+                hex::encode(&receipt.signature),
+            );
+        }
+        Ok(_) => {
+            eprintln!(
+                "The gossip node's receipt of the intent has an invalid \
+                 signature."
+            );
+        }
+        Err(err) => {
+            eprintln!("Invalid receipt of the intent: {}", err);
+        }
+    }
+}
+
+/// Simulate an intent without gossiping it. The signed intent is added to the
+/// matchmaker running locally after the counterparty intents. For every
+/// match found by the matchmaker, show the transfers and the result of a dry
+/// run of the settlement transaction on the ledger.
+pub async fn simulate_intent(
+    mut ctx: Context,
+    args::IntentSimulate {
+        source,
+        signing_key,
+        exchanges,
+        counterparty_intents,
+        matchmaker_path,
+        tx_code_path,
+        ledger_address,
+    }: args::IntentSimulate,
+) {
+    // The settlement transaction's source is the signer of the intent
+    let tx_source = ctx
+        .get_opt(&source)
+        .or_else(|| exchanges.first().map(|exchange| exchange.addr.clone()))
+        .unwrap_or_else(|| {
+            eprintln!("The intent must contain at least one exchange.");
+            cli::safe_exit(1)
+        });
+    let (signed_ft, source_keypair) = sign_intent(
+        &mut ctx,
+        &source,
+        &signing_key,
+        exchanges,
+        ledger_address.clone(),
+    )
+    .await;
+
+    let mut intents = Vec::with_capacity(counterparty_intents.len() + 1);
+    for path in counterparty_intents {
+        let data = fs::read(&path).unwrap_or_else(|err| {
+            eprintln!(
+                "Unable to read the counterparty intent from {}: {}",
+                path.to_string_lossy(),
+                err
+            );
+            cli::safe_exit(1)
+        });
+        if let Err(err) = Signed::<FungibleTokenIntent>::try_from_slice(&data) {
+            eprintln!(
+                "Invalid counterparty intent in {}: {}",
+                path.to_string_lossy(),
+                err
+            );
+            cli::safe_exit(1)
+        }
+        intents.push(data);
+    }
+    intents.push(signed_ft.try_to_vec().unwrap());
+
+    let matchmaker_path = matchmaker_path
+        .or_else(|| ctx.config.matchmaker.matchmaker_path.clone())
+        .unwrap_or_else(|| {
+            eprintln!("Please configure or specify the matchmaker path");
+            cli::safe_exit(1)
+        });
+    // The configured fee and strategy are used as by a running matchmaker
+    let fee = ctx.config.matchmaker.fee.clone();
+    let strategy = ctx.config.matchmaker.strategy;
+    let matches: Vec<MatchedExchanges> =
+        matchmaker::simulate(&matchmaker_path, strategy, intents)
+            .into_iter()
+            .filter_map(|result| result.tx)
+            .map(|tx_data| {
+                let mut matches =
+                    MatchedExchanges::try_from_slice(&tx_data[..]).unwrap();
+                if let Some(fee) = &fee {
+                    matches.add_fees(fee);
+                }
+                matches
+            })
+            .collect();
+    if matches.is_empty() {
+        println!("No match found for the intent.");
+        return;
+    }
+
+    let tx_code_path = tx_code_path
+        .or_else(|| ctx.config.matchmaker.tx_code_path.clone())
+        .unwrap_or_else(|| TX_FROM_INTENT_WASM.into());
+    let tx_code = ctx.read_wasm(tx_code_path);
+    for (ix, matches) in matches.into_iter().enumerate() {
+        println!("Match {}:", ix + 1);
+        for transfer in &matches.transfers {
+            println!(
+                "  Transfer {} of token {} from {} to {}",
+                transfer.amount,
+                transfer.token,
+                transfer.source,
+                transfer.target
+            );
+        }
+        print_nft_transfers(&matches);
+        for (payer, fee) in &matches.fees {
+            println!("  Fee {} paid by {} to {}", fee, payer, tx_source);
+        }
+        let tx_data = IntentTransfers {
+            matches,
+            source: tx_source.clone(),
         };
+        let tx = Tx::new(tx_code.clone(), Some(schema::encode(&tx_data)))
+            .sign(&source_keypair);
+        let result = rpc::dry_run_tx(&ledger_address, tx.to_bytes()).await;
+        println!("Settlement transaction dry run:\n{}", result);
     }
 }
 
@@ -99,15 +681,445 @@ pub async fn subscribe_topic(
     _ctx: Context,
     args::SubscribeTopic { node_addr, topic }: args::SubscribeTopic,
 ) {
-    let mut client = RpcServiceClient::connect(node_addr).await.unwrap();
+    let mut client = tls::connect(node_addr).await.unwrap();
     let message: services::RpcMessage = RpcMessage::new_topic(topic).into();
-    let response = client
-        .send_message(message)
-        .await
-        .expect("failed to send message and/or receive rpc response");
+    let response = send_rpc_message(&mut client, message).await;
     println!("{:#?}", response);
 }
 
+/// Request the propagation path of an intent from an intent gossip node in the
+/// trace mode and print its hops.
+pub async fn intent_trace(
+    _ctx: Context,
+    args::IntentTrace {
+        node_addr,
+        intent_hash,
+    }: args::IntentTrace,
+) {
+    let intent_hash = hex::decode(&intent_hash).unwrap_or_else(|err| {
+        eprintln!("Invalid intent hash {}: {}", intent_hash, err);
+        cli::safe_exit(1)
+    });
+    let mut client = tls::connect(node_addr).await.unwrap();
+    let message: services::RpcMessage =
+        RpcMessage::new_intent_trace(intent_hash).into();
+    let response = send_rpc_message(&mut client, message).await;
+    println!("{}", response.result);
+    let hops = response
+        .hops
+        .into_iter()
+        .filter_map(|hop| match anoma::proto::IntentHop::try_from(hop) {
+            Ok(hop) => Some(hop),
+            Err(err) => {
+                eprintln!("Ignoring an invalid intent hop: {}", err);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    if let Some(first) = hops.first() {
+        let first_received_at = first.received_at;
+        for hop in hops {
+            let delay =
+                (hop.received_at.0 - first_received_at.0).num_milliseconds();
+            println!(
+                "{} (+{} ms): {} received from {}",
+                hop.received_at.to_rfc3339(),
+                delay,
+                hop.node_id,
+                hop.from_node_id.as_deref().unwrap_or("RPC"),
+            );
+        }
+    }
+}
+
+/// Sign a cancellation of an intent with the given signing key or the key of
+/// the source address, which must have signed the intent, and submit it to
+/// the gossip node.
+pub async fn cancel_intent(
+    mut ctx: Context,
+    args::CancelIntent {
+        node_addr,
+        topic,
+        intent_hash,
+        source,
+        signing_key,
+        ledger_address,
+    }: args::CancelIntent,
+) {
+    let intent_hash = hex::decode(&intent_hash).unwrap_or_else(|err| {
+        eprintln!("Invalid intent hash {}: {}", intent_hash, err);
+        cli::safe_exit(1)
+    });
+    let keypair = match ctx.get_opt_cached(&signing_key) {
+        Some(key) => key,
+        None => {
+            let source = ctx.get_opt(&source).unwrap_or_else(|| {
+                eprintln!("A source or a signing key is required.");
+                cli::safe_exit(1)
+            });
+            signing::find_keypair(&mut ctx.wallet, &source, ledger_address)
+                .await
+        }
+    };
+    let cancel = IntentCancel::new(intent_hash, &*keypair);
+    let mut client = match tls::connect(node_addr.clone()).await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Error connecting RPC client to {}: {}", node_addr, e);
+            cli::safe_exit(1)
+        }
+    };
+    let message: services::RpcMessage =
+        RpcMessage::new_cancel_intent(cancel, topic).into();
+    let response = send_rpc_message(&mut client, message).await;
+    println!("{}", response.result);
+}
+
+/// Query the intents held in the mempool of the gossip node that have been
+/// received on the topic and print the terms of their exchanges
+pub async fn query_intents(
+    _ctx: Context,
+    args::QueryIntents { node_addr, topic }: args::QueryIntents,
+) {
+    let mut client = match tls::connect(node_addr.clone()).await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Error connecting RPC client to {}: {}", node_addr, e);
+            cli::safe_exit(1)
+        }
+    };
+    let message: services::RpcMessage =
+        RpcMessage::new_intents_query(topic).into();
+    let response = send_rpc_message(&mut client, message).await;
+    println!("{}", response.result);
+    for intent in response.intents {
+        let intent = match anoma::proto::Intent::try_from(intent) {
+            Ok(intent) => intent,
+            Err(err) => {
+                eprintln!("Ignoring an invalid intent: {}", err);
+                continue;
+            }
+        };
+        println!(
+            "Intent {} received at {}:",
+            hex::encode(intent.sha256()),
+            intent.timestamp.to_rfc3339()
+        );
+        if let Ok(signed) =
+            Signed::<FungibleTokenIntent>::try_from_slice(&intent.data)
+        {
+            for exchange in &signed.data.exchange {
+                let owner = exchange.data.addr.to_string();
+                println!("  {}", describe_exchange(&owner, &exchange.data));
+            }
+        } else if let Ok(signed) =
+            RingSignedIntent::try_from_slice(&intent.data)
+        {
+            // The source of the exchanges is one of the ring's addresses
+            let ring = &signed.data.ring;
+            let owner = format!(
+                "One of {}",
+                ring.iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            for exchange in &signed.data.exchange {
+                if let Some(addr) = ring.first() {
+                    let exchange = exchange.with_source(addr.clone());
+                    println!("  {}", describe_exchange(&owner, &exchange));
+                }
+            }
+        } else {
+            println!("  Data: {}", hex::encode(&intent.data));
+        }
+    }
+}
+
+/// Send a message to the gossip node. A message rate limited by the node is
+/// sent again once the node's delay has passed, up to
+/// [`RATE_LIMITED_RETRIES`] times. Exits on any other error.
+async fn send_rpc_message(
+    client: &mut RpcServiceClient<Channel>,
+    message: services::RpcMessage,
+) -> services::RpcResponse {
+    try_send_rpc_message(client, message)
+        .await
+        .unwrap_or_else(|status| {
+            eprintln!(
+                "The gossip node responded with an error: {}",
+                status.message()
+            );
+            cli::safe_exit(1)
+        })
+}
+
+/// Send a message to the gossip node. A message rate limited by the node is
+/// sent again once the node's delay has passed, up to
+/// [`RATE_LIMITED_RETRIES`] times.
+async fn try_send_rpc_message(
+    client: &mut RpcServiceClient<Channel>,
+    message: services::RpcMessage,
+) -> Result<services::RpcResponse, Status> {
+    let mut retries = 0;
+    loop {
+        match client.send_message(auth::request(message.clone())).await {
+            Ok(response) => return Ok(response.into_inner()),
+            Err(status) => match rate_limit::retry_after(&status) {
+                Some(delay) if retries < RATE_LIMITED_RETRIES => {
+                    eprintln!(
+                        "Rate limited by the gossip node, retrying in {} ms.",
+                        delay.as_millis()
+                    );
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                _ => return Err(status),
+            },
+        }
+    }
+}
+
+/// Query the depth of the orderbook of a token pair from the intents held in
+/// the mempool of the gossip node that have been received on the topic and
+/// print its price levels on each side
+pub async fn query_depth(
+    ctx: Context,
+    args::QueryDepth {
+        node_addr,
+        topic,
+        base_token,
+        quote_token,
+        levels,
+    }: args::QueryDepth,
+) {
+    let base_token = ctx.get(&base_token);
+    let quote_token = ctx.get(&quote_token);
+    let mut client = match tls::connect(node_addr.clone()).await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Error connecting RPC client to {}: {}", node_addr, e);
+            cli::safe_exit(1)
+        }
+    };
+    let message: services::RpcMessage = RpcMessage::new_orderbook_depth_query(
+        topic,
+        base_token.clone(),
+        quote_token.clone(),
+        levels,
+    )
+    .into();
+    let response = send_rpc_message(&mut client, message).await;
+    println!("{}", response.result);
+    let depth = match response.depth {
+        Some(depth) => depth,
+        None => cli::safe_exit(1),
+    };
+    println!(
+        "Prices in {} per unit of {}, sizes in {}:",
+        quote_token, base_token, base_token
+    );
+    for (side, levels) in [("Asks", depth.asks), ("Bids", depth.bids)] {
+        println!("{}:", side);
+        for level in levels {
+            println!(
+                "  {} for {} ({} cumulative)",
+                level.price, level.size, level.cumulative_size
+            );
+        }
+    }
+}
+
+/// Describe the terms of an exchange of the given owner
+fn describe_exchange(owner: &str, exchange: &Exchange) -> String {
+    let mut terms = if let Some((nft, token_id)) = exchange.nft_sold() {
+        format!(
+            "{} sells token {} of NFT {} for at least {} of token {}",
+            owner, token_id, nft, exchange.min_buy, exchange.token_buy
+        )
+    } else if let Some((nft, token_id)) = exchange.nft_bought() {
+        format!(
+            "{} buys token {} of NFT {} for at most {} of token {}",
+            owner, token_id, nft, exchange.max_sell, exchange.token_sell
+        )
+    } else {
+        format!(
+            "{} sells at most {} of token {} for at least {} of token {} at a \
+             rate of at least {}",
+            owner,
+            exchange.max_sell,
+            exchange.token_sell,
+            exchange.min_buy,
+            exchange.token_buy,
+            exchange.rate_min.0
+        )
+    };
+    if exchange.fills_in_parts() {
+        terms.push_str(", in parts");
+    }
+    if exchange.max_fee != token::Amount::default() {
+        terms
+            .push_str(&format!(", with a fee of at most {}", exchange.max_fee));
+    }
+    if let Some(expires) = exchange.expires {
+        terms.push_str(&format!(", until {}", expires.to_rfc3339()));
+    }
+    terms
+}
+
+/// Sign the exchanges of an intent with the keys of their addresses and the
+/// intent with the given signing key or the key of the source address.
+/// Returns the signed intent and its signing key.
+async fn sign_intent(
+    ctx: &mut Context,
+    source: &Option<WalletAddress>,
+    signing_key: &Option<WalletKeypair>,
+    exchanges: Vec<Exchange>,
+    ledger_address: TendermintAddress,
+) -> (Signed<FungibleTokenIntent>, Rc<common::SecretKey>) {
+    let mut signed_exchanges: HashSet<Signed<Exchange>> =
+        HashSet::with_capacity(exchanges.len());
+    for exchange in exchanges {
+        let signed =
+            sign_exchange(&mut ctx.wallet, exchange, ledger_address.clone())
+                .await;
+        signed_exchanges.insert(signed);
+    }
+
+    let source_keypair = match ctx.get_opt_cached(signing_key) {
+        Some(key) => key,
+        None => {
+            let source = ctx.get_opt(source).unwrap_or_else(|| {
+                eprintln!("A source or a signing key is required.");
+                cli::safe_exit(1)
+            });
+            signing::find_keypair(&mut ctx.wallet, &source, ledger_address)
+                .await
+        }
+    };
+    let signed_ft: Signed<FungibleTokenIntent> = Signed::new(
+        &*source_keypair,
+        FungibleTokenIntent {
+            exchange: signed_exchanges,
+            nonce: intent_nonce(),
+        },
+    );
+    (signed_ft, source_keypair)
+}
+
+/// Sign the exchanges of an intent with a ring signature over the keys of the
+/// ring's addresses. The source's key is found the same way as in
+/// [`sign_intent`].
+async fn sign_ring_intent(
+    ctx: &mut Context,
+    source: &Option<WalletAddress>,
+    signing_key: &Option<WalletKeypair>,
+    exchanges: Vec<Exchange>,
+    ring: Vec<WalletAddress>,
+    ledger_address: TendermintAddress,
+) -> RingSignedIntent {
+    let ring: Vec<Address> = ring.iter().map(|addr| ctx.get(addr)).collect();
+    let mut ring_keys = Vec::with_capacity(ring.len());
+    for addr in &ring {
+        match rpc::get_public_key(addr, ledger_address.clone()).await {
+            Some(pk) => ring_keys.push(pk),
+            None => {
+                eprintln!(
+                    "No public key found on chain for the ring address {}.",
+                    addr.encode()
+                );
+                cli::safe_exit(1)
+            }
+        }
+    }
+
+    let source_keypair = match ctx.get_opt_cached(signing_key) {
+        Some(key) => key,
+        None => {
+            let source = ctx.get_opt(source).unwrap_or_else(|| {
+                eprintln!("A source or a signing key is required.");
+                cli::safe_exit(1)
+            });
+            signing::find_keypair(&mut ctx.wallet, &source, ledger_address)
+                .await
+        }
+    };
+    let intent = RingIntent {
+        ring,
+        exchange: exchanges.into_iter().map(Into::into).collect(),
+        nonce: intent_nonce(),
+    };
+    RingSignedIntent::new(&*source_keypair, &ring_keys, intent).unwrap_or_else(
+        |err| {
+            eprintln!("Failed to sign the intent with the ring: {}", err);
+            cli::safe_exit(1)
+        },
+    )
+}
+
+/// Reveal the source of a ring-signed intent with its key. With a signing key
+/// instead of a source, the source is the address of the ring whose public
+/// key is the signing key's.
+async fn reveal_ring_intent(
+    ctx: &mut Context,
+    source: &Option<WalletAddress>,
+    signing_key: &Option<WalletKeypair>,
+    data_bytes: &[u8],
+    ledger_address: TendermintAddress,
+) -> RevealedRingIntent {
+    let intent =
+        RingSignedIntent::try_from_slice(data_bytes).unwrap_or_else(|_| {
+            eprintln!("The data is not a ring-signed intent.");
+            cli::safe_exit(1)
+        });
+    let (source, source_keypair) = match ctx.get_opt_cached(signing_key) {
+        Some(key) => {
+            let pk = key.ref_to();
+            let mut source = None;
+            for addr in &intent.data.ring {
+                if rpc::get_public_key(addr, ledger_address.clone()).await
+                    == Some(pk.clone())
+                {
+                    source = Some(addr.clone());
+                    break;
+                }
+            }
+            let source = source.unwrap_or_else(|| {
+                eprintln!(
+                    "The signing key is not the key of any of the addresses \
+                     of the intent's ring."
+                );
+                cli::safe_exit(1)
+            });
+            (source, key)
+        }
+        None => {
+            let source = ctx.get_opt(source).unwrap_or_else(|| {
+                eprintln!("A source or a signing key is required.");
+                cli::safe_exit(1)
+            });
+            if !intent.data.ring.contains(&source) {
+                eprintln!(
+                    "The source {} is not in the intent's ring.",
+                    source.encode()
+                );
+                cli::safe_exit(1)
+            }
+            let key =
+                signing::find_keypair(&mut ctx.wallet, &source, ledger_address)
+                    .await;
+            (source, key)
+        }
+    };
+    intent.reveal(&*source_keypair, source)
+}
+
+/// A nonce for a new intent, the time of signing in nanoseconds since the
+/// UNIX epoch
+fn intent_nonce() -> u64 {
+    u64::try_from(DateTimeUtc::now().0.timestamp_nanos()).unwrap_or_default()
+}
+
 async fn sign_exchange(
     wallet: &mut Wallet,
     exchange: Exchange,
@@ -0,0 +1,574 @@
+//! The local history of the transfers made by the client, recorded by the
+//! `history` tx middleware, and the spending reports and budgets built on it.
+//!
+//! ```toml
+//! [[client.tx_middlewares]]
+//! kind = "history"
+//! path = "history.jsonl"
+//!
+//! # Warn when the transfers of a token in a period exceed the budget
+//! [[client.tx_middlewares.budgets]]
+//! token = "atest1..."
+//! period = "month"
+//! max_amount = "1000"
+//! ```
+//!
+//! Only the transfers that have been confirmed on chain are recorded. The
+//! transfers to the client's addresses made by others are not in the history.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{self, Display};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anoma::types::address::Address;
+use anoma::types::time::{DateTimeUtc, Datelike, Duration, TimeZone, Utc};
+use anoma::types::token;
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::HttpClient;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::HttpClient;
+
+use super::middleware::TxInfo;
+use super::names::AddressNames;
+use super::rpc;
+use crate::cli::{self, args, Context};
+use crate::config::{self, Config};
+
+/// A calendar period in UTC
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Period {
+    /// The start of the current period. The weeks start on Monday.
+    pub fn start(self, now: DateTimeUtc) -> DateTimeUtc {
+        let today = now.0.date();
+        let start = match self {
+            Period::Day => today,
+            Period::Week => {
+                let days = today.weekday().num_days_from_monday();
+                today - Duration::days(days as i64)
+            }
+            Period::Month => Utc.ymd(today.year(), today.month(), 1),
+            Period::Year => Utc.ymd(today.year(), 1, 1),
+        };
+        DateTimeUtc(start.and_hms(0, 0, 0))
+    }
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Period::Day => write!(f, "day"),
+            Period::Week => write!(f, "week"),
+            Period::Month => write!(f, "month"),
+            Period::Year => write!(f, "year"),
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            "year" => Ok(Period::Year),
+            _ => Err(format!(
+                "Unknown period {}, expected one of day, week, month or year",
+                s
+            )),
+        }
+    }
+}
+
+/// A confirmed transfer in the history
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// RFC 3339 timestamp of the confirmation
+    pub time: String,
+    /// The hash of the tx
+    pub hash: String,
+    pub source: Address,
+    pub target: Address,
+    pub token: Address,
+    pub amount: token::Amount,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+impl HistoryRecord {
+    fn time(&self) -> Option<DateTimeUtc> {
+        DateTimeUtc::from_str(&self.time).ok()
+    }
+}
+
+/// The history records of the transfers of a confirmed tx. A transfer to
+/// many targets has a record for each target and a batch has the records of
+/// all its operations.
+pub fn records(
+    tx: &TxInfo,
+    hash: &str,
+    time: DateTimeUtc,
+) -> Vec<HistoryRecord> {
+    let ops_records = tx.ops.iter().flat_map(|op| records(op, hash, time));
+    let time = time.to_rfc3339();
+    let record = |source: &Address,
+                  target: &Address,
+                  token: &Address,
+                  amount,
+                  memo: &Option<String>| HistoryRecord {
+        time: time.clone(),
+        hash: hash.to_owned(),
+        source: source.clone(),
+        target: target.clone(),
+        token: token.clone(),
+        amount,
+        memo: memo.clone(),
+    };
+    let mut records = vec![];
+    if let Some(transfer) = &tx.transfer {
+        records.push(record(
+            &transfer.source,
+            &transfer.target,
+            &transfer.token,
+            transfer.amount,
+            &transfer.memo,
+        ));
+    }
+    if let Some(disbursement) = &tx.disbursement {
+        for entry in &disbursement.entries {
+            records.push(record(
+                &disbursement.source,
+                &entry.target,
+                &disbursement.token,
+                entry.amount,
+                &entry.memo,
+            ));
+        }
+    }
+    records.extend(ops_records);
+    records
+}
+
+/// Append records as JSON lines to a history file
+pub fn append(path: &Path, records: &[HistoryRecord]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for record in records {
+        let line = serde_json::to_string(record)
+            .expect("Encoding a history record shouldn't fail");
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Read the records of a history file. A missing file is an empty history.
+pub fn read(path: &Path) -> Result<Vec<HistoryRecord>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Ok(vec![]);
+        }
+        Err(err) => {
+            return Err(format!("Failed to read {}: {}", path.display(), err));
+        }
+    };
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|err| {
+                format!("Failed to parse {}: {}", path.display(), err)
+            })
+        })
+        .collect()
+}
+
+/// The amounts transferred to and from a counterparty, or in total
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Flows {
+    pub outgoing: token::Amount,
+    pub incoming: token::Amount,
+}
+
+/// The transfers of a token in a period
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenSummary {
+    pub total: Flows,
+    pub counterparties: BTreeMap<Address, Flows>,
+    /// The owners whose transfers are in the summary
+    pub owners: BTreeSet<Address>,
+}
+
+/// Summarize the transfers from and to the owners since the given time by
+/// token
+pub fn summarize(
+    records: &[HistoryRecord],
+    owners: &HashSet<Address>,
+    since: DateTimeUtc,
+) -> BTreeMap<Address, TokenSummary> {
+    let mut summaries: BTreeMap<Address, TokenSummary> = BTreeMap::new();
+    for record in records {
+        if !matches!(record.time(), Some(time) if time >= since) {
+            continue;
+        }
+        let is_outgoing = owners.contains(&record.source);
+        let is_incoming = owners.contains(&record.target);
+        if !is_outgoing && !is_incoming {
+            continue;
+        }
+        let summary = summaries.entry(record.token.clone()).or_default();
+        if is_outgoing {
+            summary.total.outgoing += record.amount;
+            summary
+                .counterparties
+                .entry(record.target.clone())
+                .or_default()
+                .outgoing += record.amount;
+            summary.owners.insert(record.source.clone());
+        }
+        if is_incoming {
+            summary.total.incoming += record.amount;
+            summary
+                .counterparties
+                .entry(record.source.clone())
+                .or_default()
+                .incoming += record.amount;
+            summary.owners.insert(record.target.clone());
+        }
+    }
+    summaries
+}
+
+/// The amount of a token transferred in the history since the given time
+pub fn spent(
+    records: &[HistoryRecord],
+    token: &Address,
+    since: DateTimeUtc,
+) -> token::Amount {
+    records
+        .iter()
+        .filter(|record| &record.token == token)
+        .filter(|record| matches!(record.time(), Some(time) if time >= since))
+        .fold(token::Amount::default(), |sum, record| sum + record.amount)
+}
+
+/// The warnings of the budgets of the given tokens that have been exceeded
+/// in their current period
+pub fn budget_warnings(
+    budgets: &[config::Budget],
+    records: &[HistoryRecord],
+    tokens: &BTreeSet<&Address>,
+    now: DateTimeUtc,
+) -> Vec<String> {
+    budgets
+        .iter()
+        .filter(|budget| tokens.contains(&budget.token))
+        .filter_map(|budget| {
+            let amount =
+                spent(records, &budget.token, budget.period.start(now));
+            (amount > budget.max_amount).then(|| {
+                format!(
+                    "{} of token {} has been transferred this {}, which \
+                     exceeds the budget of {}.",
+                    amount, budget.token, budget.period, budget.max_amount
+                )
+            })
+        })
+        .collect()
+}
+
+/// The history files and the budgets of the `history` middlewares in the
+/// config
+fn history_config(config: &Config) -> (Vec<PathBuf>, Vec<config::Budget>) {
+    let chain_dir = config.ledger.chain_dir();
+    let mut paths = vec![];
+    let mut budgets = vec![];
+    for middleware in &config.client.tx_middlewares {
+        if let config::TxMiddleware::History {
+            path,
+            budgets: history_budgets,
+        } = middleware
+        {
+            paths.push(chain_dir.join(path));
+            budgets.extend(history_budgets.iter().cloned());
+        }
+    }
+    (paths, budgets)
+}
+
+/// A counterparty in a spending report
+#[derive(Clone, Debug, Serialize)]
+struct CounterpartyReport {
+    address: String,
+    #[serde(flatten)]
+    flows: Flows,
+}
+
+/// A token in a spending report
+#[derive(Clone, Debug, Serialize)]
+struct TokenReport {
+    token: String,
+    #[serde(flatten)]
+    total: Flows,
+    /// The sum of the balances of the owners with transfers of the token
+    balance: token::Amount,
+    counterparties: Vec<CounterpartyReport>,
+    /// The amount transferred and the budget of this period, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    budget: Option<(token::Amount, token::Amount)>,
+}
+
+/// Summarize the transfers from and to the wallet's addresses, or an owner,
+/// in the current period with the owners' balances
+pub async fn report(ctx: Context, args: args::Report) {
+    let (paths, budgets) = history_config(&ctx.config);
+    if paths.is_empty() {
+        eprintln!(
+            "No tx history is recorded. Add a `history` middleware to the \
+             `client.tx_middlewares` in the config to record it."
+        );
+        cli::safe_exit(1)
+    }
+    let mut records = vec![];
+    for path in paths {
+        match read(&path) {
+            Ok(path_records) => records.extend(path_records),
+            Err(err) => {
+                eprintln!("{}", err);
+                cli::safe_exit(1)
+            }
+        }
+    }
+    let owners: HashSet<Address> = match &args.owner {
+        Some(owner) => HashSet::from([ctx.get(owner)]),
+        None => ctx.wallet.get_addresses().into_values().collect(),
+    };
+    let since = args.period.start(DateTimeUtc::now());
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let names = AddressNames::new(&ctx.wallet);
+
+    let mut tokens = vec![];
+    for (token, summary) in summarize(&records, &owners, since) {
+        let mut balance = token::Amount::default();
+        for owner in &summary.owners {
+            if let Some(owner_balance) =
+                rpc::token::get_token_balance(&client, &token, owner).await
+            {
+                balance += owner_balance;
+            }
+        }
+        let budget = budgets
+            .iter()
+            .filter(|budget| {
+                budget.token == token && budget.period == args.period
+            })
+            .map(|budget| (spent(&records, &token, since), budget.max_amount))
+            .next();
+        tokens.push(TokenReport {
+            token: names.annotate(&token),
+            total: summary.total,
+            balance,
+            counterparties: summary
+                .counterparties
+                .into_iter()
+                .map(|(address, flows)| CounterpartyReport {
+                    address: names.annotate(&address),
+                    flows,
+                })
+                .collect(),
+            budget,
+        });
+    }
+
+    if ctx.global_args.output == args::OutputMode::Json {
+        let report = serde_json::json!({
+            "period": args.period,
+            "since": since.to_rfc3339(),
+            "tokens": tokens,
+        });
+        println!("{}", report);
+        return;
+    }
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    writeln!(
+        w,
+        "Transfers this {} since {}:",
+        args.period,
+        since.to_rfc3339()
+    )
+    .unwrap();
+    if tokens.is_empty() {
+        writeln!(w, "  No transfers.").unwrap();
+    }
+    for report in tokens {
+        writeln!(w, "  Token {}:", report.token).unwrap();
+        writeln!(
+            w,
+            "    Outgoing {}, incoming {}, balance {}",
+            report.total.outgoing, report.total.incoming, report.balance
+        )
+        .unwrap();
+        for counterparty in report.counterparties {
+            let Flows { outgoing, incoming } = counterparty.flows;
+            if outgoing != token::Amount::default() {
+                writeln!(w, "    To {}: {}", counterparty.address, outgoing)
+                    .unwrap();
+            }
+            if incoming != token::Amount::default() {
+                writeln!(w, "    From {}: {}", counterparty.address, incoming)
+                    .unwrap();
+            }
+        }
+        if let Some((spent, max_amount)) = report.budget {
+            let exceeded = if spent > max_amount { ", exceeded" } else { "" };
+            writeln!(w, "    Budget: {} of {}{}", spent, max_amount, exceeded)
+                .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+    };
+
+    use super::*;
+
+    fn record(
+        time: &str,
+        source: &Address,
+        target: &Address,
+        amount: u64,
+    ) -> HistoryRecord {
+        HistoryRecord {
+            time: time.to_owned(),
+            hash: "hash".to_owned(),
+            source: source.clone(),
+            target: target.clone(),
+            token: anoma::types::address::xan(),
+            amount: token::Amount::whole(amount),
+            memo: None,
+        }
+    }
+
+    fn time(time: &str) -> DateTimeUtc {
+        DateTimeUtc::from_str(time).unwrap()
+    }
+
+    /// Test the start of the periods
+    #[test]
+    fn test_period_start() {
+        // A Thursday
+        let now = time("2022-06-16T15:30:00+00:00");
+        let start = |period: Period| period.start(now).to_rfc3339();
+        assert_eq!(start(Period::Day), "2022-06-16T00:00:00+00:00");
+        assert_eq!(start(Period::Week), "2022-06-13T00:00:00+00:00");
+        assert_eq!(start(Period::Month), "2022-06-01T00:00:00+00:00");
+        assert_eq!(start(Period::Year), "2022-01-01T00:00:00+00:00");
+        assert_eq!("month".parse(), Ok(Period::Month));
+        assert!("fortnight".parse::<Period>().is_err());
+    }
+
+    /// Test that the transfers in the period are summarized by token and
+    /// counterparty
+    #[test]
+    fn test_summarize() {
+        let owner = established_address_1();
+        let alice = established_address_2();
+        let bob = established_address_3();
+        let records = [
+            record("2022-05-31T23:59:59+00:00", &owner, &alice, 1000),
+            record("2022-06-01T10:00:00+00:00", &owner, &alice, 10),
+            record("2022-06-02T10:00:00+00:00", &owner, &alice, 5),
+            record("2022-06-03T10:00:00+00:00", &owner, &bob, 20),
+            record("2022-06-04T10:00:00+00:00", &bob, &owner, 7),
+            record("2022-06-05T10:00:00+00:00", &alice, &bob, 100),
+        ];
+        let owners = HashSet::from([owner.clone()]);
+        let since = time("2022-06-01T00:00:00+00:00");
+
+        let summaries = summarize(&records, &owners, since);
+        assert_eq!(summaries.len(), 1);
+        let summary = &summaries[&anoma::types::address::xan()];
+        let amount = token::Amount::whole;
+        assert_eq!(summary.total.outgoing, amount(35));
+        assert_eq!(summary.total.incoming, amount(7));
+        assert_eq!(summary.counterparties[&alice].outgoing, amount(15));
+        assert_eq!(
+            summary.counterparties[&bob],
+            Flows {
+                outgoing: amount(20),
+                incoming: amount(7),
+            }
+        );
+        assert_eq!(summary.owners, BTreeSet::from([owner]));
+    }
+
+    /// Test that only the exceeded budgets of the transferred tokens are
+    /// warned about
+    #[test]
+    fn test_budget_warnings() {
+        let owner = established_address_1();
+        let alice = established_address_2();
+        let xan = anoma::types::address::xan();
+        let records = [
+            record("2022-06-01T10:00:00+00:00", &owner, &alice, 60),
+            record("2022-06-16T10:00:00+00:00", &owner, &alice, 50),
+        ];
+        let budget = |period, max_amount| config::Budget {
+            token: xan.clone(),
+            period,
+            max_amount: token::Amount::whole(max_amount),
+        };
+        let budgets = [
+            budget(Period::Month, 100),
+            budget(Period::Week, 100),
+            budget(Period::Year, 1000),
+        ];
+        let now = time("2022-06-16T15:30:00+00:00");
+
+        let warnings =
+            budget_warnings(&budgets, &records, &BTreeSet::from([&xan]), now);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("this month"));
+
+        let other_token = established_address_2();
+        let warnings = budget_warnings(
+            &budgets,
+            &records,
+            &BTreeSet::from([&other_token]),
+            now,
+        );
+        assert!(warnings.is_empty());
+    }
+
+    /// Test that the records are appended to and read from a history file
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        assert_eq!(read(&path).unwrap(), vec![]);
+
+        let owner = established_address_1();
+        let alice = established_address_2();
+        let first = [record("2022-06-01T10:00:00+00:00", &owner, &alice, 1)];
+        let second = [record("2022-06-02T10:00:00+00:00", &alice, &owner, 2)];
+        append(&path, &first).unwrap();
+        append(&path, &second).unwrap();
+        assert_eq!(read(&path).unwrap(), [first, second].concat());
+    }
+}
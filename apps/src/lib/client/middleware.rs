@@ -0,0 +1,806 @@
+//! Middlewares through which the client passes every tx that it's about to
+//! broadcast. They are configured in the `[client]` section of the chain's
+//! config, so that e.g. institutional users can keep a log of their txs and
+//! enforce policies on them without changes to the client:
+//!
+//! ```toml
+//! [[client.tx_middlewares]]
+//! kind = "log"
+//! path = "txs.log"
+//!
+//! [[client.tx_middlewares]]
+//! kind = "max_daily_amount"
+//! token = "atest1..."
+//! max_amount = "10000"
+//! path = "daily-amount.json"
+//!
+//! [[client.tx_middlewares]]
+//! kind = "approval"
+//! min_amount = "1000"
+//! approvers = ["alice", "bob"]
+//!
+//! [[client.tx_middlewares]]
+//! kind = "history"
+//! path = "history.jsonl"
+//! ```
+//!
+//! See [`crate::client::history`] for the budgets of the `history`
+//! middleware.
+//!
+//! The middlewares are run in order before a tx is broadcast and any of them
+//! can reject it. Once the tx has been broadcast, all of them are notified
+//! about the result. Relative paths are resolved against the chain directory.
+
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anoma::types::address::{self, Address};
+use anoma::types::hash::Hash;
+use anoma::types::ibc::data::{FungibleTokenPacketData, IbcMessage};
+use anoma::types::time::{DateTimeUtc, DurationSecs};
+use anoma::types::token;
+use anoma::types::transaction::batch::{TxBatch, BATCH_TX_CODE};
+use anoma::types::transaction::pos;
+use anoma::types::transaction::schema::{self, TxDataSchema};
+use borsh::BorshDeserialize;
+use serde::{Deserialize, Serialize};
+
+use super::history;
+use super::tendermint_rpc_types::TxBroadcastResult;
+use super::tx::Error;
+use crate::config;
+
+/// The duration over which [`MaxDailyAmount`] sums up the transfers
+const DAY: DurationSecs = DurationSecs(24 * 60 * 60);
+
+/// The codes of the txs that the middlewares recognize by their code rather
+/// than by the schema of their data
+pub struct TxCodes {
+    /// The transfer tx code, for untagged transfers
+    pub transfer: Vec<u8>,
+    /// The bond tx code. The unbond tx has the same data, but it doesn't
+    /// spend any tokens.
+    pub bond: Vec<u8>,
+    /// The IBC tx code
+    pub ibc: Vec<u8>,
+}
+
+/// A tx that is about to be broadcast, as seen by the middlewares
+#[derive(Clone, Debug, Serialize)]
+pub struct TxInfo {
+    /// The hash of the tx code
+    pub code_hash: String,
+    /// The hex encoded data of the tx, without a signature
+    pub data: Option<String>,
+    /// The name of the schema of the data, if they're tagged with one
+    pub schema: Option<&'static str>,
+    /// The transfer, if the tx is a token transfer
+    pub transfer: Option<token::Transfer>,
+    /// The disbursement, if the tx is a transfer to many targets
+    pub disbursement: Option<token::Disbursement>,
+    /// The bond, if the tx bonds tokens
+    pub bond: Option<pos::Bond>,
+    /// The token transfer, if the tx is an IBC transfer
+    pub ibc_transfer: Option<FungibleTokenPacketData>,
+    /// The operations, if the tx is a batch
+    pub ops: Vec<TxInfo>,
+    /// Whether the kind of the tx is unknown, in which case the tokens that
+    /// it transfers cannot be determined
+    pub is_unknown: bool,
+}
+
+impl TxInfo {
+    /// Describe a tx with the given code and data without a signature. The
+    /// data are decoded by their schema if they're tagged with one, or else
+    /// by the tx code. The operations of a batch are described one by one.
+    pub fn new(code: &[u8], data: Option<Vec<u8>>, codes: &TxCodes) -> Self {
+        let schema = data
+            .as_deref()
+            .and_then(schema::identify)
+            .map(|(schema, _)| schema.name);
+        let is_transfer = match schema {
+            Some(name) => name == token::Transfer::NAME,
+            None => code == codes.transfer,
+        };
+        let transfer = if is_transfer {
+            data.as_deref()
+                .and_then(|data| schema::decode::<token::Transfer>(data).ok())
+        } else {
+            None
+        };
+        let disbursement = if schema == Some(token::Disbursement::NAME) {
+            data.as_deref().and_then(|data| schema::decode(data).ok())
+        } else {
+            None
+        };
+        let bond = if schema == Some(pos::Bond::NAME) && code == codes.bond {
+            data.as_deref().and_then(|data| schema::decode(data).ok())
+        } else {
+            None
+        };
+        let ibc_message = if schema.is_none() && code == codes.ibc {
+            data.as_deref()
+                .and_then(|data| IbcMessage::decode(data).ok())
+        } else {
+            None
+        };
+        let ibc_transfer = ibc_message
+            .clone()
+            .and_then(|message| message.msg_transfer().ok())
+            .filter(|msg| msg.token.is_some())
+            .map(FungibleTokenPacketData::from);
+        let batch = if code == BATCH_TX_CODE {
+            data.as_deref()
+                .and_then(|data| TxBatch::try_from_slice(data).ok())
+        } else {
+            None
+        };
+        let is_unknown = schema.is_none()
+            && transfer.is_none()
+            && ibc_message.is_none()
+            && batch.is_none();
+        let ops = batch
+            .map(|batch| {
+                batch
+                    .ops
+                    .into_iter()
+                    .map(|op| Self::new(&op.code, op.data, codes))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            code_hash: Hash::sha256(code).to_string(),
+            data: data.map(hex::encode),
+            schema,
+            transfer,
+            disbursement,
+            bond,
+            ibc_transfer,
+            ops,
+            is_unknown,
+        }
+    }
+
+    /// The amounts of tokens transferred or bonded by the tx, including the
+    /// operations of a batch, summed up by token. `None` if the tx or any of
+    /// its operations is of an unknown kind.
+    fn amounts(&self) -> Option<BTreeMap<Address, token::Amount>> {
+        if self.is_unknown {
+            return None;
+        }
+        let mut amounts: BTreeMap<Address, token::Amount> = BTreeMap::new();
+        let mut add = |token: &Address, amount: token::Amount| {
+            let sum = amounts.entry(token.clone()).or_default();
+            *sum = u64::from(*sum).saturating_add(amount.into()).into();
+        };
+        if let Some(transfer) = &self.transfer {
+            add(&transfer.token, transfer.amount);
+        }
+        if let Some(disbursement) = &self.disbursement {
+            add(
+                &disbursement.token,
+                disbursement.total().unwrap_or_else(token::Amount::max),
+            );
+        }
+        if let Some(bond) = &self.bond {
+            add(&address::xan(), bond.amount);
+        }
+        if let Some(ibc_transfer) = &self.ibc_transfer {
+            let token = ibc_transfer
+                .denomination
+                .split('/')
+                .last()
+                .and_then(|token| Address::decode(token).ok())?;
+            let amount = token::Amount::from_str(&ibc_transfer.amount).ok()?;
+            add(&token, amount);
+        }
+        for op in &self.ops {
+            for (token, amount) in op.amounts()? {
+                add(&token, amount);
+            }
+        }
+        Some(amounts)
+    }
+}
+
+/// A middleware in the client's submit path
+pub trait TxMiddleware: Send {
+    /// The name of the middleware shown when it rejects a tx
+    fn name(&self) -> &'static str;
+
+    /// Check a tx before it's broadcast. An error rejects the tx with the
+    /// given reason.
+    fn before_broadcast(&mut self, tx: &TxInfo) -> Result<(), String>;
+
+    /// Handle the result of a tx once it has been broadcast
+    fn after_broadcast(
+        &mut self,
+        _tx: &TxInfo,
+        _result: Result<&TxBroadcastResult, &Error>,
+    ) {
+    }
+}
+
+/// A chain of middlewares, run in order
+#[derive(Default)]
+pub struct TxMiddlewares(Vec<Box<dyn TxMiddleware>>);
+
+impl TxMiddlewares {
+    /// Build the middlewares configured for the client
+    pub fn from_config(config: &config::Config) -> Self {
+        let chain_dir = config.ledger.chain_dir();
+        let mut middlewares = Self::default();
+        for middleware in &config.client.tx_middlewares {
+            match middleware {
+                config::TxMiddleware::Log { path } => {
+                    middlewares.push(LogTxs {
+                        path: chain_dir.join(path),
+                    })
+                }
+                config::TxMiddleware::MaxDailyAmount {
+                    token,
+                    max_amount,
+                    path,
+                } => middlewares.push(MaxDailyAmount {
+                    token: token.clone(),
+                    max_amount: *max_amount,
+                    path: chain_dir.join(path),
+                }),
+                config::TxMiddleware::Approval {
+                    min_amount,
+                    approvers,
+                } => middlewares.push(Approval {
+                    min_amount: *min_amount,
+                    approvers: approvers.clone(),
+                }),
+                config::TxMiddleware::History { path, budgets } => middlewares
+                    .push(RecordHistory {
+                        path: chain_dir.join(path),
+                        budgets: budgets.clone(),
+                    }),
+            }
+        }
+        middlewares
+    }
+
+    /// Add a middleware at the end of the chain
+    pub fn push(&mut self, middleware: impl TxMiddleware + 'static) {
+        self.0.push(Box::new(middleware))
+    }
+
+    /// Whether there are no middlewares in the chain
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Run the middlewares before a tx is broadcast, stopping at the first
+    /// one that rejects it
+    pub fn before_broadcast(&mut self, tx: &TxInfo) -> Result<(), Error> {
+        for middleware in &mut self.0 {
+            middleware.before_broadcast(tx).map_err(|reason| {
+                Error::MiddlewareRejected(middleware.name(), reason)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Notify all the middlewares about the result of a broadcast tx
+    pub fn after_broadcast(
+        &mut self,
+        tx: &TxInfo,
+        result: Result<&TxBroadcastResult, &Error>,
+    ) {
+        for middleware in &mut self.0 {
+            middleware.after_broadcast(tx, result)
+        }
+    }
+}
+
+/// Appends every tx before it's broadcast and its result as JSON lines to a
+/// file. A tx is rejected if it cannot be logged.
+struct LogTxs {
+    path: PathBuf,
+}
+
+impl LogTxs {
+    fn append(&self, entry: serde_json::Value) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", entry)
+    }
+}
+
+impl TxMiddleware for LogTxs {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn before_broadcast(&mut self, tx: &TxInfo) -> Result<(), String> {
+        self.append(serde_json::json!({
+            "time": DateTimeUtc::now().to_rfc3339(),
+            "tx": tx,
+        }))
+        .map_err(|err| {
+            format!("Failed to write to {}: {}", self.path.display(), err)
+        })
+    }
+
+    fn after_broadcast(
+        &mut self,
+        tx: &TxInfo,
+        result: Result<&TxBroadcastResult, &Error>,
+    ) {
+        let result = match result {
+            Ok(result) => serde_json::json!({ "ok": result }),
+            Err(err) => serde_json::json!({ "error": err.to_string() }),
+        };
+        let entry = serde_json::json!({
+            "time": DateTimeUtc::now().to_rfc3339(),
+            "code_hash": tx.code_hash,
+            "result": result,
+        });
+        if let Err(err) = self.append(entry) {
+            tracing::error!(
+                "Failed to write to {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// Rejects the transfers of a token that would exceed the maximum amount
+/// transferred in the last 24 hours. The bonds of the token and the
+/// operations of a batch are counted as well, while the txs of an unknown kind
+/// are rejected. The amounts of the transfers that have been broadcast are
+/// recorded in a file.
+struct MaxDailyAmount {
+    token: Address,
+    max_amount: token::Amount,
+    path: PathBuf,
+}
+
+/// A transfer recorded by [`MaxDailyAmount`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct TransferRecord {
+    /// RFC 3339 timestamp of the transfer
+    time: String,
+    amount: token::Amount,
+}
+
+impl MaxDailyAmount {
+    /// Read the records of the transfers in the last 24 hours
+    fn read_records(&self) -> Result<Vec<TransferRecord>, String> {
+        let records: Vec<TransferRecord> = match fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|err| {
+                format!("Failed to parse {}: {}", self.path.display(), err)
+            })?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+            Err(err) => {
+                return Err(format!(
+                    "Failed to read {}: {}",
+                    self.path.display(),
+                    err
+                ));
+            }
+        };
+        let now = DateTimeUtc::now();
+        Ok(records
+            .into_iter()
+            .filter(|record| match DateTimeUtc::from_str(&record.time) {
+                Ok(time) => time + DAY > now,
+                Err(_) => false,
+            })
+            .collect())
+    }
+
+    fn write_records(
+        path: &Path,
+        records: &[TransferRecord],
+    ) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(records)
+            .expect("Encoding transfer records shouldn't fail");
+        fs::write(path, json).map_err(|err| {
+            format!("Failed to write {}: {}", path.display(), err)
+        })
+    }
+}
+
+impl TxMiddleware for MaxDailyAmount {
+    fn name(&self) -> &'static str {
+        "max_daily_amount"
+    }
+
+    fn before_broadcast(&mut self, tx: &TxInfo) -> Result<(), String> {
+        let amounts = tx.amounts().ok_or_else(|| {
+            format!(
+                "The amount of token {} transferred by the tx cannot be \
+                 determined, because the tx is of an unknown kind.",
+                self.token
+            )
+        })?;
+        let amount = match amounts.get(&self.token) {
+            Some(amount) => *amount,
+            None => return Ok(()),
+        };
+        let transferred = self
+            .read_records()?
+            .into_iter()
+            .fold(token::Amount::default(), |sum, record| sum + record.amount);
+        if transferred + amount > self.max_amount {
+            return Err(format!(
+                "Transferring {} of token {} would exceed the maximum of {} \
+                 per day, as {} has already been transferred in the last 24 \
+                 hours.",
+                amount, self.token, self.max_amount, transferred
+            ));
+        }
+        Ok(())
+    }
+
+    fn after_broadcast(
+        &mut self,
+        tx: &TxInfo,
+        result: Result<&TxBroadcastResult, &Error>,
+    ) {
+        let amount = match (tx.amounts(), result) {
+            (Some(amounts), Ok(_)) => match amounts.get(&self.token) {
+                Some(amount) => *amount,
+                None => return,
+            },
+            _ => return,
+        };
+        let result = self.read_records().and_then(|mut records| {
+            records.push(TransferRecord {
+                time: DateTimeUtc::now().to_rfc3339(),
+                amount,
+            });
+            Self::write_records(&self.path, &records)
+        });
+        if let Err(err) = result {
+            tracing::error!("Failed to record a transfer: {}", err);
+        }
+    }
+}
+
+/// Asks for the approval of a second person before a tx is broadcast. With a
+/// minimum amount, only the transfers of at least that amount and the txs of
+/// an unknown kind need to be approved. With a list of approvers, only they
+/// can approve.
+struct Approval {
+    min_amount: Option<token::Amount>,
+    approvers: Vec<String>,
+}
+
+impl Approval {
+    fn prompt(msg: &str) -> Result<String, String> {
+        eprint!("{}", msg);
+        io::stderr().flush().unwrap();
+        let mut input = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .map_err(|err| format!("Failed to read the approval: {}", err))?;
+        Ok(input.trim().to_owned())
+    }
+}
+
+impl TxMiddleware for Approval {
+    fn name(&self) -> &'static str {
+        "approval"
+    }
+
+    fn before_broadcast(&mut self, tx: &TxInfo) -> Result<(), String> {
+        if let (Some(min_amount), Some(amounts)) =
+            (self.min_amount, tx.amounts())
+        {
+            if amounts.values().all(|amount| *amount < min_amount) {
+                return Ok(());
+            }
+        }
+        eprintln!(
+            "The transaction needs to be approved before it's broadcast:\n{}",
+            serde_json::to_string_pretty(tx).unwrap()
+        );
+        let approver = Self::prompt("Approver: ")?;
+        if !self.approvers.is_empty() && !self.approvers.contains(&approver) {
+            return Err(format!("{} is not an approver.", approver));
+        }
+        let answer = Self::prompt("Approve the transaction? [y/N]: ")?;
+        if answer.eq_ignore_ascii_case("y") {
+            tracing::info!("The transaction has been approved by {}", approver);
+            Ok(())
+        } else {
+            Err(format!(
+                "The transaction has not been approved by {}.",
+                approver
+            ))
+        }
+    }
+}
+
+/// Records the confirmed transfers in a history file and warns when they
+/// exceed any of the budgets
+struct RecordHistory {
+    path: PathBuf,
+    budgets: Vec<config::Budget>,
+}
+
+impl TxMiddleware for RecordHistory {
+    fn name(&self) -> &'static str {
+        "history"
+    }
+
+    fn before_broadcast(&mut self, _tx: &TxInfo) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn after_broadcast(
+        &mut self,
+        tx: &TxInfo,
+        result: Result<&TxBroadcastResult, &Error>,
+    ) {
+        let response = match result {
+            Ok(TxBroadcastResult::Commit(response)) if response.code == "0" => {
+                response
+            }
+            _ => return,
+        };
+        let now = DateTimeUtc::now();
+        let records = history::records(tx, &response.hash, now);
+        if records.is_empty() {
+            return;
+        }
+        if let Err(err) = history::append(&self.path, &records) {
+            tracing::error!(
+                "Failed to write to {}: {}",
+                self.path.display(),
+                err
+            );
+            return;
+        }
+        let all_records = match history::read(&self.path) {
+            Ok(all_records) => all_records,
+            Err(err) => {
+                tracing::error!("Failed to check the budgets: {}", err);
+                return;
+            }
+        };
+        let tokens = records.iter().map(|record| &record.token).collect();
+        for warning in
+            history::budget_warnings(&self.budgets, &all_records, &tokens, now)
+        {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+    use anoma::types::governance::ProposalVote;
+    use anoma::types::transaction::batch::BatchOp;
+    use anoma::types::transaction::governance::VoteProposalData;
+
+    use super::*;
+    use crate::client::tendermint_rpc_types::TxResponse;
+
+    fn codes() -> TxCodes {
+        TxCodes {
+            transfer: b"transfer".to_vec(),
+            bond: b"bond".to_vec(),
+            ibc: b"ibc".to_vec(),
+        }
+    }
+
+    /// Test that a max daily amount middleware rejects the transfers of its
+    /// token over the limit, counting only those that have been broadcast
+    #[test]
+    fn test_max_daily_amount() {
+        let dir = tempfile::tempdir().unwrap();
+        let token = established_address_1();
+        let other_token = established_address_2();
+        let mut middleware = MaxDailyAmount {
+            token: token.clone(),
+            max_amount: token::Amount::whole(100),
+            path: dir.path().join("daily-amount.json"),
+        };
+        let codes = codes();
+        let transfer = |token: &Address, amount: u64| {
+            let transfer = token::Transfer {
+                source: established_address_2(),
+                target: established_address_2(),
+                token: token.clone(),
+                amount: token::Amount::whole(amount),
+                memo: None,
+            };
+            let data = borsh::BorshSerialize::try_to_vec(&transfer).unwrap();
+            TxInfo::new(&codes.transfer, Some(data), &codes)
+        };
+        let result = TxBroadcastResult::Async {
+            hash: "hash".to_owned(),
+            inner_hash: None,
+        };
+
+        let tx = transfer(&token, 60);
+        assert_eq!(
+            tx.amounts().unwrap().get(&token),
+            Some(&token::Amount::whole(60))
+        );
+        middleware.before_broadcast(&tx).unwrap();
+        middleware.after_broadcast(&tx, Ok(&result));
+        // A failed broadcast is not counted
+        middleware.after_broadcast(&tx, Err(&Error::MissingSigner));
+        middleware.before_broadcast(&transfer(&token, 40)).unwrap();
+        assert!(middleware.before_broadcast(&transfer(&token, 41)).is_err());
+        // Other tokens and other kinds of txs are not limited
+        middleware
+            .before_broadcast(&transfer(&other_token, 1000))
+            .unwrap();
+        let vote = VoteProposalData {
+            id: 0,
+            vote: ProposalVote::Yay,
+            voter: established_address_2(),
+            delegations: vec![],
+        };
+        let other_tx =
+            TxInfo::new(b"other", Some(schema::encode(&vote)), &codes);
+        assert_eq!(other_tx.transfer, None);
+        assert!(!other_tx.is_unknown);
+        middleware.before_broadcast(&other_tx).unwrap();
+        // The txs of an unknown kind are rejected
+        let unknown_tx = TxInfo::new(b"other", None, &codes);
+        assert!(unknown_tx.is_unknown);
+        assert!(middleware.before_broadcast(&unknown_tx).is_err());
+        // Tagged transfers are recognized by their schema, whatever the code
+        let tagged = token::Transfer {
+            source: established_address_2(),
+            target: established_address_2(),
+            token: token.clone(),
+            amount: token::Amount::whole(1),
+            memo: None,
+        };
+        let tagged_tx =
+            TxInfo::new(b"other", Some(schema::encode(&tagged)), &codes);
+        assert_eq!(tagged_tx.schema, Some(token::Transfer::NAME));
+        assert_eq!(tagged_tx.transfer, Some(tagged));
+        // The disbursements count with their total amount
+        let entry = |amount: u64| token::DisbursementEntry {
+            target: established_address_2(),
+            amount: token::Amount::whole(amount),
+            memo: None,
+        };
+        let disbursement = token::Disbursement {
+            source: established_address_2(),
+            token: token.clone(),
+            entries: vec![entry(30), entry(11)],
+        };
+        let disbursement_tx =
+            TxInfo::new(b"other", Some(schema::encode(&disbursement)), &codes);
+        assert_eq!(
+            disbursement_tx.amounts().unwrap().get(&token),
+            Some(&token::Amount::whole(41))
+        );
+        assert!(middleware.before_broadcast(&disbursement_tx).is_err());
+
+        // Transfers older than a day are not counted
+        MaxDailyAmount::write_records(
+            &middleware.path,
+            &[TransferRecord {
+                time: "2000-01-01T00:00:00Z".to_owned(),
+                amount: token::Amount::whole(100),
+            }],
+        )
+        .unwrap();
+        middleware.before_broadcast(&transfer(&token, 100)).unwrap();
+    }
+
+    /// Test that the amounts of a batch's operations are summed up, counting
+    /// the bonds but not the unbonds, and that they're unknown when any of
+    /// the operations is of an unknown kind
+    #[test]
+    fn test_batch_amounts() {
+        let codes = codes();
+        let token = established_address_1();
+        let transfer = token::Transfer {
+            source: established_address_2(),
+            target: established_address_2(),
+            token: token.clone(),
+            amount: token::Amount::whole(10),
+            memo: None,
+        };
+        let bond = pos::Bond {
+            validator: established_address_2(),
+            amount: token::Amount::whole(5),
+            source: None,
+        };
+        let op = |code: &[u8], data: Vec<u8>| BatchOp {
+            code: code.to_vec(),
+            data: Some(data),
+        };
+        let mut batch = TxBatch {
+            ops: vec![
+                op(&codes.transfer, schema::encode(&transfer)),
+                op(&codes.bond, schema::encode(&bond)),
+                op(b"unbond", schema::encode(&bond)),
+                op(&codes.transfer, schema::encode(&transfer)),
+            ],
+        };
+        let tx = batch.to_tx();
+        let tx_info = TxInfo::new(&tx.code, tx.data, &codes);
+        assert_eq!(tx_info.ops.len(), 4);
+        let amounts = tx_info.amounts().unwrap();
+        assert_eq!(amounts.get(&token), Some(&token::Amount::whole(20)));
+        assert_eq!(
+            amounts.get(&address::xan()),
+            Some(&token::Amount::whole(5))
+        );
+
+        batch.ops.push(BatchOp {
+            code: b"custom".to_vec(),
+            data: None,
+        });
+        let tx = batch.to_tx();
+        assert_eq!(TxInfo::new(&tx.code, tx.data, &codes).amounts(), None);
+    }
+
+    /// Test that the history middleware records the transfers of the txs
+    /// that have been applied on chain
+    #[test]
+    fn test_record_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let mut middleware = RecordHistory {
+            path: path.clone(),
+            budgets: vec![],
+        };
+        let entry = |amount: u64| token::DisbursementEntry {
+            target: established_address_2(),
+            amount: token::Amount::whole(amount),
+            memo: Some("memo".to_owned()),
+        };
+        let disbursement = token::Disbursement {
+            source: established_address_1(),
+            token: established_address_1(),
+            entries: vec![entry(30), entry(11)],
+        };
+        let tx = TxInfo::new(
+            b"other",
+            Some(schema::encode(&disbursement)),
+            &codes(),
+        );
+        let response = |code: &str| {
+            TxBroadcastResult::Commit(TxResponse {
+                info: String::new(),
+                log: String::new(),
+                height: "1".to_owned(),
+                hash: "hash".to_owned(),
+                code: code.to_owned(),
+                gas_used: "0".to_owned(),
+                initialized_accounts: vec![],
+                batch_results: vec![],
+            })
+        };
+
+        // Rejected txs are not recorded
+        middleware.after_broadcast(&tx, Ok(&response("1")));
+        assert!(history::read(&path).unwrap().is_empty());
+
+        middleware.after_broadcast(&tx, Ok(&response("0")));
+        let records = history::read(&path).unwrap();
+        let amounts: Vec<_> =
+            records.iter().map(|record| record.amount).collect();
+        assert_eq!(
+            amounts,
+            [token::Amount::whole(30), token::Amount::whole(11)]
+        );
+        assert_eq!(records[0].memo.as_deref(), Some("memo"));
+        assert_eq!(records[0].hash, "hash");
+    }
+}
@@ -1,8 +1,19 @@
+pub mod apply;
+pub mod batch;
+pub mod console;
+pub mod draft;
 pub mod gossip;
+pub mod history;
+pub mod middleware;
+pub mod names;
+pub mod nft_assets;
+pub mod nft_definition;
+pub mod progress;
 pub mod rpc;
 pub mod signing;
 pub mod tendermint_rpc_types;
 mod tendermint_websocket_client;
+pub mod test_vectors;
 mod tm_jsonrpc_client;
 pub mod tx;
 pub mod utils;
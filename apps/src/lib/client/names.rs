@@ -0,0 +1,136 @@
+//! Reverse look-up of the human-readable names of addresses, used to annotate
+//! the addresses printed by the client.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anoma::types::address::{self, Address, ADDRESS_LEN};
+
+use crate::wallet::Wallet;
+
+/// The names of addresses, from the aliases in the wallet and the names known
+/// on chain, i.e. the names of the tokens and of the internal addresses.
+#[derive(Clone, Debug, Default)]
+pub struct AddressNames {
+    names: HashMap<Address, String>,
+}
+
+impl AddressNames {
+    /// Collect the names of the addresses known to the wallet and the chain
+    pub fn new(wallet: &Wallet) -> Self {
+        Self::with_aliases(wallet.get_addresses())
+    }
+
+    /// Collect the names of the given aliased addresses and of the addresses
+    /// known on chain. The aliases take precedence over the names known on
+    /// chain. If an address has more than one alias, the first one in
+    /// alphabetical order is used.
+    pub fn with_aliases(
+        aliases: impl IntoIterator<Item = (String, Address)>,
+    ) -> Self {
+        let mut aliases: Vec<_> = aliases.into_iter().collect();
+        aliases.sort();
+        let mut names = HashMap::new();
+        for (alias, address) in aliases {
+            names.entry(address).or_insert(alias);
+        }
+        for (token, name) in address::tokens() {
+            names.entry(token).or_insert_with(|| name.to_owned());
+        }
+        Self { names }
+    }
+
+    /// Find the name of an address
+    pub fn find(&self, address: &Address) -> Option<String> {
+        match self.names.get(address) {
+            Some(name) => Some(name.clone()),
+            None => match address {
+                Address::Internal(internal) => Some(internal.to_string()),
+                Address::Established(_) | Address::Implicit(_) => None,
+            },
+        }
+    }
+
+    /// Format an address followed by its name in parentheses, if it's known
+    pub fn annotate(&self, address: &Address) -> String {
+        match self.find(address) {
+            Some(name) => format!("{} ({})", address.encode(), name),
+            None => address.encode(),
+        }
+    }
+
+    /// Annotate all the encoded addresses in a text with their names, if
+    /// they're known
+    pub fn annotate_text(&self, text: &str) -> String {
+        let mut annotated = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric())
+        {
+            annotated.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            annotated.push_str(word);
+            if word.len() == ADDRESS_LEN {
+                let name = Address::decode(word)
+                    .ok()
+                    .and_then(|address| self.find(&address));
+                if let Some(name) = name {
+                    write!(annotated, " ({})", name).unwrap();
+                }
+            }
+            rest = &rest[end..];
+        }
+        annotated.push_str(rest);
+        annotated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::{testing, InternalAddress};
+
+    use super::*;
+
+    #[test]
+    fn test_address_names() {
+        let bertha = testing::established_address_1();
+        let unknown = testing::established_address_2();
+        let names = AddressNames::with_aliases(vec![
+            ("bertha".to_owned(), bertha.clone()),
+            ("bertha-2".to_owned(), bertha.clone()),
+        ]);
+
+        assert_eq!(names.find(&bertha), Some("bertha".to_owned()));
+        assert_eq!(names.find(&unknown), None);
+        assert_eq!(names.find(&address::xan()), Some("XAN".to_owned()));
+        assert_eq!(
+            names.find(&Address::Internal(InternalAddress::PoS)),
+            Some("PoS".to_owned())
+        );
+
+        assert_eq!(
+            names.annotate(&bertha),
+            format!("{} (bertha)", bertha.encode())
+        );
+        assert_eq!(names.annotate(&unknown), unknown.encode());
+
+        let text = format!(
+            "Transfer from {} to {}, token \"{}\".",
+            bertha.encode(),
+            unknown.encode(),
+            address::xan().encode()
+        );
+        assert_eq!(
+            names.annotate_text(&text),
+            format!(
+                "Transfer from {} (bertha) to {}, token \"{} (XAN)\".",
+                bertha.encode(),
+                unknown.encode(),
+                address::xan().encode()
+            )
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Minting NFT tokens from a directory of assets.
+//!
+//! Every file in the directory is pinned to an IPFS node through its HTTP API
+//! and becomes a token whose metadata points to the file's CID, with the
+//! file's name without the extension as the token's name. The tokens are then
+//! minted in as many transactions as needed to keep each of them under the
+//! size limit. The IPFS node is configured in the client's section of the
+//! config:
+//!
+//! ```toml
+//! [client.ipfs]
+//! api_url = "http://127.0.0.1:5001"
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anoma::types::hash::Hash;
+use anoma::types::nft::{MetadataError, NftToken, NftTokenMetadata};
+use borsh::BorshSerialize;
+use serde::Deserialize;
+use thiserror::Error;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to read the assets directory {0}: {1}")]
+    ReadDir(PathBuf, std::io::Error),
+    #[error("Failed to read the asset {0}: {1}")]
+    ReadAsset(PathBuf, std::io::Error),
+    #[error("The assets directory {0} doesn't contain any files")]
+    NoAssets(PathBuf),
+    #[error("Failed to pin the asset {0} to IPFS: {1}")]
+    Pin(PathBuf, String),
+    #[error("Invalid metadata of the token for the asset {0}: {1}")]
+    InvalidMetadata(PathBuf, MetadataError),
+    #[error(
+        "The NFT token {id} takes {size} bytes, which is more than the {max} \
+         bytes that fit in a transaction"
+    )]
+    TokenTooLarge { id: u64, size: usize, max: usize },
+}
+
+/// The response of the IPFS HTTP API to `add`
+#[derive(Deserialize)]
+struct AddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+/// Find the asset files in a directory, in the order of their names. Hidden
+/// files and sub-directories are left out.
+pub fn list_assets(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| Error::ReadDir(dir.to_owned(), err))?;
+    let mut assets = vec![];
+    for entry in entries {
+        let entry = entry.map_err(|err| Error::ReadDir(dir.to_owned(), err))?;
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if path.is_file() && !hidden {
+            assets.push(path);
+        }
+    }
+    if assets.is_empty() {
+        return Err(Error::NoAssets(dir.to_owned()));
+    }
+    assets.sort();
+    Ok(assets)
+}
+
+/// Read an asset file
+pub fn read_asset(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|err| Error::ReadAsset(path.to_owned(), err))
+}
+
+/// Pin the content of an asset to an IPFS node and return its CID
+pub async fn pin(
+    api_url: &str,
+    path: &Path,
+    content: &[u8],
+) -> Result<String, Error> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (boundary, body) = multipart_body(&name, content);
+    let url = format!(
+        "{}/api/v0/add?pin=true&cid-version=1",
+        api_url.trim_end_matches('/')
+    );
+    let response = async {
+        reqwest::Client::new()
+            .post(&url)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await
+    }
+    .await
+    .map_err(|err| Error::Pin(path.to_owned(), err.to_string()))?;
+    let response: AddResponse = serde_json::from_slice(&response)
+        .map_err(|err| Error::Pin(path.to_owned(), err.to_string()))?;
+    Ok(response.hash)
+}
+
+/// Encode a file as the body of a `multipart/form-data` request. Returns the
+/// boundary between the parts with the body.
+fn multipart_body(name: &str, content: &[u8]) -> (String, Vec<u8>) {
+    // The hash of the content can't appear in the content
+    let boundary = format!("anoma-{}", Hash::sha256(content));
+    let mut body = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"file\"; \
+         filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+        boundary,
+        name.replace('"', "%22")
+    )
+    .into_bytes();
+    body.extend(content);
+    body.extend(format!("\r\n--{}--\r\n", boundary).into_bytes());
+    (boundary, body)
+}
+
+/// Make the token of an asset that has been pinned with the given CID
+pub fn asset_token(
+    id: u64,
+    path: &Path,
+    cid: &str,
+    content: &[u8],
+) -> Result<NftToken, Error> {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let metadata = NftTokenMetadata {
+        name,
+        description: String::new(),
+        media_uri: format!("ipfs://{}", cid),
+        media_hash: Some(Hash::sha256(content).to_string()),
+        attributes: Default::default(),
+    };
+    metadata
+        .validate()
+        .map_err(|err| Error::InvalidMetadata(path.to_owned(), err))?;
+    Ok(NftToken {
+        id,
+        metadata: metadata.encode(),
+        current_owner: None,
+        past_owners: vec![],
+        approvals: vec![],
+        values: vec![],
+        opt_values: vec![],
+        burnt: false,
+    })
+}
+
+/// Split the tokens into chunks whose Borsh encoding takes at most
+/// `max_bytes`, keeping their order
+pub fn chunk_tokens(
+    tokens: Vec<NftToken>,
+    max_bytes: usize,
+) -> Result<Vec<Vec<NftToken>>, Error> {
+    // The length of a vector is encoded in 4 bytes
+    const LEN_BYTES: usize = 4;
+    let mut chunks: Vec<Vec<NftToken>> = vec![];
+    let mut chunk_bytes = 0;
+    for token in tokens {
+        let size = token.try_to_vec().unwrap().len();
+        if LEN_BYTES + size > max_bytes {
+            return Err(Error::TokenTooLarge {
+                id: token.id,
+                size,
+                max: max_bytes.saturating_sub(LEN_BYTES),
+            });
+        }
+        match chunks.last_mut() {
+            Some(chunk) if LEN_BYTES + chunk_bytes + size <= max_bytes => {
+                chunk.push(token);
+                chunk_bytes += size;
+            }
+            _ => {
+                chunks.push(vec![token]);
+                chunk_bytes = size;
+            }
+        }
+    }
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that only the visible files are listed, in order
+    #[test]
+    fn test_list_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(list_assets(dir.path()), Err(Error::NoAssets(_))));
+
+        for name in ["b.png", "a.png", ".DS_Store"] {
+            fs::write(dir.path().join(name), name).unwrap();
+        }
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        let assets = list_assets(dir.path()).unwrap();
+        assert_eq!(
+            assets,
+            [dir.path().join("a.png"), dir.path().join("b.png")]
+        );
+    }
+
+    /// Test that the token of an asset points to its CID
+    #[test]
+    fn test_asset_token() {
+        let cid = "bafkreigh2akiscaildcqabsyg3dfr6chu3fgpregiymsck7e7aqa4s52zy";
+        let token =
+            asset_token(7, Path::new("assets/Sunset.png"), cid, b"sunset")
+                .unwrap();
+        assert_eq!(token.id, 7);
+        let metadata = token.decode_metadata().unwrap();
+        assert_eq!(metadata.name, "Sunset");
+        assert_eq!(metadata.media_uri, format!("ipfs://{}", cid));
+        assert_eq!(
+            metadata.media_hash,
+            Some(Hash::sha256(b"sunset").to_string())
+        );
+    }
+
+    /// Test that the tokens are split into chunks under the size limit
+    #[test]
+    fn test_chunk_tokens() {
+        let tokens: Vec<NftToken> = (0..10)
+            .map(|id| {
+                asset_token(id, Path::new("asset.png"), "cid", b"asset")
+                    .unwrap()
+            })
+            .collect();
+        let size = tokens[0].try_to_vec().unwrap().len();
+
+        let chunks = chunk_tokens(tokens.clone(), 4 + 3 * size).unwrap();
+        let lens: Vec<usize> = chunks.iter().map(Vec::len).collect();
+        assert_eq!(lens, [3, 3, 3, 1]);
+        assert_eq!(chunks.concat(), tokens);
+        for chunk in &chunks {
+            assert!(chunk.try_to_vec().unwrap().len() <= 4 + 3 * size);
+        }
+
+        assert!(matches!(
+            chunk_tokens(tokens, size),
+            Err(Error::TokenTooLarge { id: 0, .. })
+        ));
+    }
+
+    /// Test that the file is enclosed by the boundary in the request body
+    #[test]
+    fn test_multipart_body() {
+        let (boundary, body) = multipart_body("a\"b.png", b"content");
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.starts_with(&format!("--{}\r\n", boundary)));
+        assert!(body.contains("filename=\"a%22b.png\""));
+        assert!(body.ends_with(&format!("\r\ncontent\r\n--{}--\r\n", boundary)));
+    }
+}
@@ -0,0 +1,489 @@
+//! Reading and checking the files that define NFTs and their tokens.
+//!
+//! A file is read as TOML if it has the `toml` extension and as JSON
+//! otherwise. A file of tokens to mint is a list of tokens in JSON, or a
+//! table with the list of `tokens` in either format. Every field is decoded
+//! on its own, so that all the mistakes in a file are reported with the path
+//! of their field, e.g. `tokens[2].values`.
+
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anoma::types::address::Address;
+use anoma::types::nft::{Nft, NftTag, NftToken, Royalty};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// The fields of an NFT definition
+const NFT_FIELDS: [&str; 7] = [
+    "tag", "creator", "vp_path", "keys", "opt_keys", "tokens", "royalty",
+];
+
+/// The fields of a token definition
+const TOKEN_FIELDS: [&str; 8] = [
+    "id",
+    "metadata",
+    "current_owner",
+    "past_owners",
+    "approvals",
+    "values",
+    "opt_values",
+    "burnt",
+];
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to read the NFT file {0}: {1}")]
+    Read(PathBuf, std::io::Error),
+    #[error("Failed to parse the NFT file {0}: {1}")]
+    Parse(PathBuf, String),
+    #[error("The NFT file {0} is invalid:{1}")]
+    Invalid(PathBuf, Issues),
+}
+
+/// A mistake in a definition, at the path of its field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// The path of the field, empty for the whole definition
+    pub field: String,
+    /// What's wrong with the field
+    pub message: String,
+}
+
+impl Issue {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.field, self.message)
+        }
+    }
+}
+
+/// The mistakes in a definition, displayed one per line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issues(pub Vec<Issue>);
+
+impl Display for Issues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.0 {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the definition of an NFT
+pub fn read_nft(path: &Path) -> Result<Nft, Error> {
+    let value = parse(path)?;
+    decode_nft(&value)
+        .map_err(|issues| Error::Invalid(path.to_owned(), Issues(issues)))
+}
+
+/// Read the definitions of tokens to mint
+pub fn read_nft_tokens(path: &Path) -> Result<Vec<NftToken>, Error> {
+    let value = parse(path)?;
+    let tokens = match &value {
+        Value::Array(tokens) => Some(tokens),
+        Value::Object(object) => match object.get("tokens") {
+            Some(Value::Array(tokens)) => Some(tokens),
+            _ => None,
+        },
+        _ => None,
+    };
+    let issues = match tokens {
+        Some(tokens) => {
+            let mut issues = vec![];
+            let tokens = decode_tokens(tokens, &mut issues);
+            if issues.is_empty() {
+                return Ok(tokens);
+            }
+            issues
+        }
+        None => vec![Issue::new("tokens", "expected a list of tokens")],
+    };
+    Err(Error::Invalid(path.to_owned(), Issues(issues)))
+}
+
+/// Parse a file in the format given by its extension
+fn parse(path: &Path) -> Result<Value, Error> {
+    let raw = fs::read_to_string(path)
+        .map_err(|err| Error::Read(path.to_owned(), err))?;
+    let is_toml = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("toml"))
+        .unwrap_or_default();
+    if is_toml {
+        toml::from_str(&raw)
+            .map_err(|err| Error::Parse(path.to_owned(), err.to_string()))
+    } else {
+        serde_json::from_str(&raw)
+            .map_err(|err| Error::Parse(path.to_owned(), err.to_string()))
+    }
+}
+
+/// Decode the definition of an NFT, field by field
+fn decode_nft(value: &Value) -> Result<Nft, Vec<Issue>> {
+    let object = match value {
+        Value::Object(object) => object,
+        _ => return Err(vec![Issue::new("", "expected a table of fields")]),
+    };
+    let mut issues = vec![];
+    check_fields(
+        object,
+        "",
+        &NFT_FIELDS,
+        &["creator", "keys", "tokens"],
+        &mut issues,
+    );
+    let tag = decode_field::<NftTag>(object, "", "tag", &mut issues);
+    let creator = decode_field::<Address>(object, "", "creator", &mut issues);
+    let vp_path =
+        decode_field::<Option<String>>(object, "", "vp_path", &mut issues)
+            .flatten();
+    let keys = decode_field::<Vec<String>>(object, "", "keys", &mut issues);
+    let opt_keys =
+        decode_field::<Vec<String>>(object, "", "opt_keys", &mut issues);
+    let royalty =
+        decode_field::<Option<Royalty>>(object, "", "royalty", &mut issues)
+            .flatten();
+    let tokens = match object.get("tokens") {
+        Some(Value::Array(tokens)) => decode_tokens(tokens, &mut issues),
+        Some(_) => {
+            issues.push(Issue::new("tokens", "expected a list of tokens"));
+            vec![]
+        }
+        None => vec![],
+    };
+    match (creator, keys) {
+        (Some(creator), Some(keys)) if issues.is_empty() => Ok(Nft {
+            tag: tag.unwrap_or_default(),
+            creator,
+            vp_path,
+            keys,
+            opt_keys: opt_keys.unwrap_or_default(),
+            tokens,
+            royalty,
+        }),
+        _ => Err(issues),
+    }
+}
+
+/// Decode the definitions of tokens, field by field. The tokens with issues
+/// are left out.
+fn decode_tokens(values: &[Value], issues: &mut Vec<Issue>) -> Vec<NftToken> {
+    values
+        .iter()
+        .enumerate()
+        .filter_map(|(ix, value)| {
+            let path = format!("tokens[{}]", ix);
+            let object = match value {
+                Value::Object(object) => object,
+                _ => {
+                    issues.push(Issue::new(path, "expected a table of fields"));
+                    return None;
+                }
+            };
+            let issues_before = issues.len();
+            check_fields(
+                object,
+                &path,
+                &TOKEN_FIELDS,
+                &["id", "metadata", "approvals", "values"],
+                issues,
+            );
+            decode_field::<u64>(object, &path, "id", issues);
+            if let Some(metadata) = object.get("metadata") {
+                if !metadata.is_string() && !metadata.is_object() {
+                    issues.push(Issue::new(
+                        field_path(&path, "metadata"),
+                        "expected a string or a table",
+                    ));
+                }
+            }
+            decode_field::<Option<Address>>(
+                object,
+                &path,
+                "current_owner",
+                issues,
+            );
+            for field in ["past_owners", "approvals"] {
+                decode_field::<Vec<Address>>(object, &path, field, issues);
+            }
+            for field in ["values", "opt_values"] {
+                decode_field::<Vec<String>>(object, &path, field, issues);
+            }
+            decode_field::<bool>(object, &path, "burnt", issues);
+            if issues.len() > issues_before {
+                return None;
+            }
+            serde_json::from_value(value.clone())
+                .map_err(|err| issues.push(Issue::new(path, err.to_string())))
+                .ok()
+        })
+        .collect()
+}
+
+/// Check that the required fields are present and that there are no unknown
+/// fields, e.g. with a typo in their name
+fn check_fields(
+    object: &Map<String, Value>,
+    parent: &str,
+    known: &[&str],
+    required: &[&str],
+    issues: &mut Vec<Issue>,
+) {
+    for field in required {
+        if !object.contains_key(*field) {
+            issues.push(Issue::new(field_path(parent, field), "missing field"));
+        }
+    }
+    for field in object.keys() {
+        if !known.contains(&field.as_str()) {
+            issues.push(Issue::new(
+                field_path(parent, field),
+                format!("unknown field, expected one of {}", known.join(", ")),
+            ));
+        }
+    }
+}
+
+/// Decode the value of a field, if it's present
+fn decode_field<T: DeserializeOwned>(
+    object: &Map<String, Value>,
+    parent: &str,
+    field: &str,
+    issues: &mut Vec<Issue>,
+) -> Option<T> {
+    let value = object.get(field)?;
+    serde_json::from_value(value.clone())
+        .map_err(|err| {
+            issues.push(Issue::new(field_path(parent, field), err.to_string()))
+        })
+        .ok()
+}
+
+fn field_path(parent: &str, field: &str) -> String {
+    if parent.is_empty() {
+        field.to_owned()
+    } else {
+        format!("{}.{}", parent, field)
+    }
+}
+
+/// Check the consistency of a decoded NFT definition: the keys must be
+/// unique, every token must have a value for each of the mandatory keys and
+/// the royalty must be at most 100%. The tokens' metadata are checked by
+/// [`check_metadata`].
+pub fn check_nft(nft: &Nft) -> Vec<Issue> {
+    let mut issues = vec![];
+    let mut keys = HashSet::new();
+    for key in &nft.keys {
+        if !keys.insert(key) {
+            issues.push(Issue::new(
+                "keys",
+                format!("the key {} is defined more than once", key),
+            ));
+        }
+    }
+    for key in &nft.opt_keys {
+        if !keys.insert(key) {
+            issues.push(Issue::new(
+                "opt_keys",
+                format!("the key {} is defined more than once", key),
+            ));
+        }
+    }
+    if let Some(royalty) = nft.royalty.as_ref().filter(|r| !r.is_valid()) {
+        issues.push(Issue::new(
+            "royalty.percentage",
+            format!(
+                "the percentage {} is greater than 100",
+                royalty.percentage
+            ),
+        ));
+    }
+    if let Some(vp_path) = &nft.vp_path {
+        if !Path::new(vp_path).is_file() {
+            issues
+                .push(Issue::new("vp_path", format!("no file at {}", vp_path)));
+        }
+    }
+    issues.extend(check_tokens(&nft.tokens));
+    for (ix, token) in nft.tokens.iter().enumerate() {
+        if token.values.len() != nft.keys.len() {
+            issues.push(Issue::new(
+                format!("tokens[{}].values", ix),
+                format!(
+                    "expected {} values for the keys, got {}",
+                    nft.keys.len(),
+                    token.values.len()
+                ),
+            ));
+        }
+        if token.opt_values.len() > nft.opt_keys.len() {
+            issues.push(Issue::new(
+                format!("tokens[{}].opt_values", ix),
+                format!(
+                    "expected at most {} values for the optional keys, got {}",
+                    nft.opt_keys.len(),
+                    token.opt_values.len()
+                ),
+            ));
+        }
+    }
+    issues
+}
+
+/// Check that the IDs of tokens are unique
+pub fn check_tokens(tokens: &[NftToken]) -> Vec<Issue> {
+    let mut ids = HashSet::new();
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| !ids.insert(token.id))
+        .map(|(ix, token)| {
+            Issue::new(
+                format!("tokens[{}].id", ix),
+                format!("the token ID {} is used more than once", token.id),
+            )
+        })
+        .collect()
+}
+
+/// Check that the metadata of tokens conform to the standard
+pub fn check_metadata(tokens: &[NftToken]) -> Vec<Issue> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(ix, token)| {
+            let err = token.decode_metadata().err()?;
+            Some(Issue::new(
+                format!("tokens[{}].metadata", ix),
+                err.to_string(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NFT: &str = r#"
+creator = "atest1v4ehgw36xvcyyvejgvenxs34g3zygv3jxqunjd6rxyeyys3sxy6rwvfkx4qnj33hg9qnvse4lsfctw"
+keys = ["color"]
+
+[royalty]
+percentage = 10
+beneficiary = "atest1v4ehgw36xvcyyvejgvenxs34g3zygv3jxqunjd6rxyeyys3sxy6rwvfkx4qnj33hg9qnvse4lsfctw"
+
+[[tokens]]
+id = 1
+metadata = { name = "Sunset", media_uri = "ipfs://QmSunset" }
+approvals = []
+values = ["orange"]
+"#;
+
+    /// Test that an NFT is read from a TOML file and that the same tokens
+    /// can be read for minting
+    #[test]
+    fn test_read_toml_nft() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nft.toml");
+        fs::write(&path, NFT).unwrap();
+
+        let nft = read_nft(&path).unwrap();
+        assert_eq!(nft.tag, NftTag::V1);
+        assert_eq!(nft.keys, ["color"]);
+        assert_eq!(nft.royalty.as_ref().unwrap().percentage, 10);
+        assert_eq!(nft.tokens.len(), 1);
+        assert_eq!(nft.tokens[0].values, ["orange"]);
+        assert_eq!(check_nft(&nft), vec![]);
+        assert_eq!(check_metadata(&nft.tokens), vec![]);
+
+        let tokens = read_nft_tokens(&path).unwrap();
+        assert_eq!(tokens, nft.tokens);
+    }
+
+    /// Test that all the mistakes in a file are reported at their field
+    #[test]
+    fn test_field_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nft.json");
+        let nft = r#"{
+            "creator": "not-an-address",
+            "keys": ["color"],
+            "tokens": [
+                { "id": 1, "metadata": "{}", "approvals": [], "values": [] },
+                { "id": "two", "metadata": 2, "approvals": [], "value": [] }
+            ]
+        }"#;
+        fs::write(&path, nft).unwrap();
+
+        let issues = match read_nft(&path) {
+            Err(Error::Invalid(_, Issues(issues))) => issues,
+            result => panic!("Unexpected result {:?}", result),
+        };
+        let fields: Vec<&str> =
+            issues.iter().map(|issue| issue.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "creator",
+                "tokens[1].values",
+                "tokens[1].value",
+                "tokens[1].id",
+                "tokens[1].metadata",
+            ]
+        );
+        assert_eq!(issues[1].message, "missing field");
+
+        fs::write(&path, "{").unwrap();
+        assert!(matches!(read_nft(&path), Err(Error::Parse(_, _))));
+    }
+
+    /// Test the consistency checks of a decoded NFT
+    #[test]
+    fn test_check_nft() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nft.toml");
+        fs::write(&path, NFT).unwrap();
+        let mut nft = read_nft(&path).unwrap();
+        nft.opt_keys = vec!["color".to_owned()];
+        nft.royalty.as_mut().unwrap().percentage = 101;
+        nft.tokens.push(nft.tokens[0].clone());
+        nft.tokens[1].values.clear();
+        nft.tokens[1].metadata = "raw".to_owned();
+
+        let fields: Vec<String> = check_nft(&nft)
+            .into_iter()
+            .map(|issue| issue.field)
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "opt_keys",
+                "royalty.percentage",
+                "tokens[1].id",
+                "tokens[1].values"
+            ]
+        );
+        let issues = check_metadata(&nft.tokens);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "tokens[1].metadata");
+    }
+}
@@ -0,0 +1,108 @@
+//! The progress of submitting a tx, reported to a hook set in the
+//! [`crate::cli::Context`], so that wallets and GUIs embedding the client can
+//! show it without parsing the client's output. A hook can either call a
+//! function on every stage or send the stages to a channel, which can be
+//! consumed as a stream.
+
+use std::fmt;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use super::tendermint_rpc_types::TxResponse;
+
+/// A stage of submitting a tx
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "stage", rename_all = "kebab-case")]
+pub enum TxProgress {
+    /// The tx has been built and it's being prepared for signing
+    Building {
+        /// The hash of the tx code
+        code_hash: String,
+    },
+    /// The tx is being signed and wrapped, which includes the gas estimation
+    /// with the `--auto-gas-limit` argument
+    Signing,
+    /// The broadcast is delayed until the txs with the given hashes, given
+    /// with the `--after` argument, have been applied
+    AwaitingDependencies { hashes: Vec<String> },
+    /// The broadcast is delayed until the chain reaches the block before the
+    /// target height given with the `--target-height` argument
+    Waiting { target_height: u64 },
+    /// The wrapped tx is being broadcast
+    Broadcasting {
+        hash: String,
+        /// The hash of the inner tx
+        inner_hash: Option<String>,
+    },
+    /// The tx has been accepted into the mempool of the ledger
+    InMempool { hash: String },
+    /// The tx has been applied on chain
+    Applied(TxResponse),
+}
+
+/// A hook to which the stages of submitting a tx are reported. The default
+/// hook ignores them.
+#[derive(Clone, Default)]
+pub struct TxProgressHook(Option<Arc<dyn Fn(&TxProgress) + Send + Sync>>);
+
+impl TxProgressHook {
+    /// A hook that calls the given function on every stage
+    pub fn new(callback: impl Fn(&TxProgress) + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(callback)))
+    }
+
+    /// A hook that sends every stage to the returned channel
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<TxProgress>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let hook = Self::new(move |progress| {
+            // The receiver might have been dropped, in which case nobody is
+            // interested in the progress anymore
+            let _ = sender.send(progress.clone());
+        });
+        (hook, receiver)
+    }
+
+    /// Report a stage of submitting a tx
+    pub fn report(&self, progress: TxProgress) {
+        if let Some(callback) = &self.0 {
+            callback(&progress)
+        }
+    }
+}
+
+impl fmt::Debug for TxProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TxProgressHook")
+            .field(&self.0.as_ref().map(|_| "callback"))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the stages are sent to the channel of a hook
+    #[test]
+    fn test_progress_channel() {
+        let (hook, mut receiver) = TxProgressHook::channel();
+        hook.report(TxProgress::Signing);
+        hook.report(TxProgress::InMempool {
+            hash: "hash".to_owned(),
+        });
+        assert!(matches!(receiver.try_recv(), Ok(TxProgress::Signing)));
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(TxProgress::InMempool { hash }) if hash == "hash"
+        ));
+        assert!(receiver.try_recv().is_err());
+
+        // Reporting to the default hook or after the receiver has been
+        // dropped doesn't fail
+        drop(receiver);
+        hook.report(TxProgress::Signing);
+        TxProgressHook::default().report(TxProgress::Signing);
+    }
+}
@@ -0,0 +1,324 @@
+//! Client RPC query of an account's storage sub-space. The known keys are
+//! rendered in a structured report and the unknown keys are flagged.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use anoma::types::address::{self, Address};
+use anoma::types::key::dkg_session_keys::{self, DkgPublicKey};
+use anoma::types::key::{self, common};
+use anoma::types::storage::{self, DbKeySeg, KeySeg, PrefixValue};
+use anoma::types::{intent, nft, token};
+use borsh::BorshDeserialize;
+use sha2::{Digest, Sha256};
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::HttpClient;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::HttpClient;
+
+use super::query_storage_prefix_bytes;
+use super::token::get_token_balance;
+use crate::cli::{self, args, Context};
+use crate::client::names::AddressNames;
+use crate::config::DEFAULT_WASM_CHECKSUMS_FILE;
+use crate::wasm_loader::Checksums;
+
+/// A storage key in an account's sub-space, as recognized by the client
+#[derive(Debug, PartialEq)]
+enum AccountKey<'a> {
+    /// The account's validity predicate
+    Vp,
+    /// The account's public key
+    PublicKey,
+    /// The validator account's protocol public key
+    ProtocolPublicKey,
+    /// The validator account's DKG session public key
+    DkgPublicKey,
+    /// The set of intents of the account that have been fulfilled
+    InvalidIntents,
+    /// The amounts sold by the exchanges of the account that are filled in
+    /// parts
+    FilledIntents,
+    /// A balance of the given owner, if the account is a token
+    Balance(&'a Address),
+    /// The creator, if the account is an NFT
+    NftCreator,
+    /// The current owner of the NFT token with the given ID
+    NftTokenOwner(String),
+    /// The metadata of the NFT token with the given ID
+    NftTokenMetadata(String),
+    /// Any other data, if the account is an NFT
+    Nft,
+    /// A key that is not recognized by the client
+    Unknown,
+}
+
+impl<'a> AccountKey<'a> {
+    /// Recognize a storage key in the sub-space of the given account
+    fn new(account: &Address, key: &'a storage::Key) -> Self {
+        if key.is_validity_predicate().is_some() {
+            Self::Vp
+        } else if key::is_pk_key(key).is_some() {
+            Self::PublicKey
+        } else if key::is_protocol_pk_key(key).is_some() {
+            Self::ProtocolPublicKey
+        } else if dkg_session_keys::is_dkg_pk_key(key).is_some() {
+            Self::DkgPublicKey
+        } else if intent::is_invalid_intent_key(key).is_some() {
+            Self::InvalidIntents
+        } else if intent::is_filled_intent_key(key).is_some() {
+            Self::FilledIntents
+        } else if let Some(owner) = token::is_any_token_balance_key(key) {
+            Self::Balance(owner)
+        } else if nft::is_nft_creator_key(key, account).is_some() {
+            Self::NftCreator
+        } else if let Some((_, id)) =
+            nft::is_nft_current_owner_key(key, account)
+        {
+            Self::NftTokenOwner(id)
+        } else if let Some((_, id)) = nft::is_nft_metadata_key(key, account) {
+            Self::NftTokenMetadata(id)
+        } else if nft::is_nft_key(key).is_some() {
+            Self::Nft
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+/// Show the whole storage sub-space of an account. The known keys are decoded
+/// and rendered in sections and the unknown keys, or known keys with values
+/// that cannot be decoded, are listed at the end.
+pub async fn query_account(ctx: Context, args: args::QueryAccount) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let account = ctx.get(&args.address);
+    let names = AddressNames::new(&ctx.wallet);
+    let tokens = address::tokens();
+    let currency_code = |token: &Address| {
+        tokens
+            .get(token)
+            .map(|c| Cow::Borrowed(*c))
+            .unwrap_or_else(|| Cow::Owned(names.annotate(token)))
+    };
+
+    let prefix = storage::Key::from(account.to_db_key());
+    let values = query_storage_prefix_bytes(client.clone(), prefix)
+        .await
+        .unwrap_or_default();
+    if values.is_empty() && matches!(account, Address::Established(_)) {
+        eprintln!("No account found for {}", names.annotate(&account));
+        cli::safe_exit(1)
+    }
+
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    writeln!(w, "Account {}", names.annotate(&account)).unwrap();
+
+    // The balances of the account in the known tokens
+    let mut balances = vec![];
+    for token in tokens.keys() {
+        if let Some(balance) = get_token_balance(&client, token, &account).await
+        {
+            balances.push((currency_code(token), balance));
+        }
+    }
+    balances.sort();
+    if !balances.is_empty() {
+        writeln!(w, "Balances:").unwrap();
+        for (currency_code, balance) in balances {
+            writeln!(w, "  {}: {}", currency_code, balance).unwrap();
+        }
+    }
+
+    let mut holders = vec![];
+    let mut nft_tokens = vec![];
+    let mut nft_data = vec![];
+    let mut unknown = vec![];
+    for PrefixValue { key, value } in &values {
+        let rendered = match AccountKey::new(&account, key) {
+            AccountKey::Vp => {
+                let hash = hex::encode(Sha256::digest(value));
+                let name = vp_name(&ctx, &hash)
+                    .map(|name| format!("{} ", name))
+                    .unwrap_or_default();
+                writeln!(
+                    w,
+                    "Validity predicate: {}(sha256 {}, {} bytes)",
+                    name,
+                    hash,
+                    value.len()
+                )
+                .unwrap();
+                true
+            }
+            AccountKey::PublicKey => decode::<common::PublicKey>(value)
+                .map(|pk| writeln!(w, "Public key: {}", pk).unwrap())
+                .is_some(),
+            AccountKey::ProtocolPublicKey => decode::<common::PublicKey>(value)
+                .map(|pk| writeln!(w, "Protocol public key: {}", pk).unwrap())
+                .is_some(),
+            AccountKey::DkgPublicKey => decode::<DkgPublicKey>(value)
+                .map(|pk| writeln!(w, "DKG public key: {}", pk).unwrap())
+                .is_some(),
+            AccountKey::InvalidIntents => {
+                decode::<HashSet<common::Signature>>(value)
+                    .map(|intents| {
+                        writeln!(w, "Fulfilled intents: {}", intents.len())
+                            .unwrap()
+                    })
+                    .is_some()
+            }
+            AccountKey::FilledIntents => decode::<
+                HashMap<common::Signature, token::Amount>,
+            >(value)
+            .map(|intents| {
+                writeln!(w, "Intents filled in parts: {}", intents.len())
+                    .unwrap()
+            })
+            .is_some(),
+            AccountKey::Balance(owner) => decode::<token::Amount>(value)
+                .map(|balance| holders.push((balance, owner)))
+                .is_some(),
+            AccountKey::NftCreator => decode::<Address>(value)
+                .map(|creator| {
+                    nft_data
+                        .push(format!("Creator: {}", names.annotate(&creator)))
+                })
+                .is_some(),
+            AccountKey::NftTokenOwner(id) => decode::<Address>(value)
+                .map(|owner| {
+                    nft_tokens.push((
+                        id,
+                        format!("owned by {}", names.annotate(&owner)),
+                    ))
+                })
+                .is_some(),
+            AccountKey::NftTokenMetadata(id) => decode::<String>(value)
+                .map(|metadata| {
+                    nft_tokens.push((id, format!("metadata {}", metadata)))
+                })
+                .is_some(),
+            AccountKey::Nft => {
+                nft_data.push(format!(
+                    "{}: {} bytes",
+                    sub_key(key),
+                    value.len()
+                ));
+                true
+            }
+            AccountKey::Unknown => false,
+        };
+        if !rendered {
+            unknown.push((key, value.len()));
+        }
+    }
+
+    if !holders.is_empty() {
+        holders.sort();
+        writeln!(w, "Token balances held by:").unwrap();
+        for (balance, owner) in holders {
+            writeln!(w, "  {}: {}", names.annotate(owner), balance).unwrap();
+        }
+    }
+    if !nft_data.is_empty() || !nft_tokens.is_empty() {
+        writeln!(w, "NFT:").unwrap();
+        for data in nft_data {
+            writeln!(w, "  {}", data).unwrap();
+        }
+        nft_tokens.sort();
+        for (id, data) in nft_tokens {
+            writeln!(w, "  Token {}: {}", id, data).unwrap();
+        }
+    }
+    if !unknown.is_empty() {
+        writeln!(w, "Unknown keys:").unwrap();
+        for (key, len) in unknown {
+            writeln!(w, "  {}: {} bytes", key, len).unwrap();
+        }
+    }
+}
+
+/// Decode a storage value, or `None` if it's not of the expected type
+fn decode<T: BorshDeserialize>(value: &[u8]) -> Option<T> {
+    T::try_from_slice(value).ok()
+}
+
+/// Find the name of a VP with the given hash in the WASM checksums, if the
+/// checksums file exists
+fn vp_name(ctx: &Context, hash: &str) -> Option<String> {
+    let wasm_dir = ctx.wasm_dir();
+    if !wasm_dir.join(DEFAULT_WASM_CHECKSUMS_FILE).exists() {
+        return None;
+    }
+    Checksums::read_checksums(wasm_dir)
+        .find_name(hash)
+        .map(str::to_owned)
+}
+
+/// Format the segments of a key after the account's address
+fn sub_key(key: &storage::Key) -> String {
+    key.segments
+        .iter()
+        .skip(1)
+        .map(DbKeySeg::raw)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::{
+        established_address_1, established_address_2,
+    };
+
+    use super::*;
+
+    /// Test that the keys in an account's sub-space are recognized
+    #[test]
+    fn test_account_keys() {
+        let account = established_address_1();
+        let owner = established_address_2();
+
+        let vp_key = storage::Key::validity_predicate(&account);
+        assert_eq!(AccountKey::new(&account, &vp_key), AccountKey::Vp);
+        let pk_key = key::pk_key(&account);
+        assert_eq!(AccountKey::new(&account, &pk_key), AccountKey::PublicKey);
+        let protocol_pk_key = key::protocol_pk_key(&account);
+        assert_eq!(
+            AccountKey::new(&account, &protocol_pk_key),
+            AccountKey::ProtocolPublicKey
+        );
+        let intents_key = intent::invalid_intent_key(&account);
+        assert_eq!(
+            AccountKey::new(&account, &intents_key),
+            AccountKey::InvalidIntents
+        );
+        let filled_key = intent::filled_intent_key(&account);
+        assert_eq!(
+            AccountKey::new(&account, &filled_key),
+            AccountKey::FilledIntents
+        );
+        let balance_key = token::balance_key(&account, &owner);
+        assert_eq!(
+            AccountKey::new(&account, &balance_key),
+            AccountKey::Balance(&owner)
+        );
+        let owner_key = nft::get_token_current_owner_key(&account, "1");
+        assert_eq!(
+            AccountKey::new(&account, &owner_key),
+            AccountKey::NftTokenOwner("1".to_owned())
+        );
+        let tag_key = nft::get_tag_key(&account);
+        assert_eq!(AccountKey::new(&account, &tag_key), AccountKey::Nft);
+        assert_eq!(sub_key(&tag_key), "nft/tag");
+
+        let unknown_key = storage::Key::from(account.to_db_key())
+            .push(&"counter".to_owned())
+            .unwrap();
+        assert_eq!(
+            AccountKey::new(&account, &unknown_key),
+            AccountKey::Unknown
+        );
+    }
+}
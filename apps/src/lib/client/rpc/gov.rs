@@ -0,0 +1,443 @@
+//! Client RPC queries of the governance proposals
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+
+use anoma::ledger::governance::storage as gov_storage;
+use anoma::ledger::governance::utils::Votes;
+use anoma::ledger::pos;
+use anoma::types::address::Address;
+use anoma::types::governance::{
+    OfflineProposal, OfflineVote, ProposalVote, TallyResult,
+};
+use anoma::types::key::*;
+use anoma::types::storage::Epoch;
+use anoma::types::token::Amount;
+use async_std::fs;
+use async_std::path::PathBuf;
+use async_std::prelude::*;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::HttpClient;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::HttpClient;
+
+use super::pos::{
+    get_all_validators, get_bond_amount_at, get_total_staked_tokes,
+    get_validator_stake, is_delegator_at,
+};
+use super::{
+    get_public_key, query_epoch, query_storage_prefix, query_storage_value,
+};
+use crate::cli::{self, args, Context};
+use crate::client::names::AddressNames;
+
+/// Query Proposals
+pub async fn query_proposal(ctx: Context, args: args::QueryProposal) {
+    async fn print_proposal(
+        client: &HttpClient,
+        names: &AddressNames,
+        id: u64,
+        current_epoch: Epoch,
+        details: bool,
+    ) -> Option<()> {
+        let author_key = gov_storage::get_author_key(id);
+        let start_epoch_key = gov_storage::get_voting_start_epoch_key(id);
+        let end_epoch_key = gov_storage::get_voting_end_epoch_key(id);
+
+        let author =
+            query_storage_value::<Address>(client, &author_key).await?;
+        let start_epoch =
+            query_storage_value::<Epoch>(client, &start_epoch_key).await?;
+        let end_epoch =
+            query_storage_value::<Epoch>(client, &end_epoch_key).await?;
+
+        if details {
+            let content_key = gov_storage::get_content_key(id);
+            let grace_epoch_key = gov_storage::get_grace_epoch_key(id);
+            let content = query_storage_value::<HashMap<String, String>>(
+                client,
+                &content_key,
+            )
+            .await?;
+            let grace_epoch =
+                query_storage_value::<Epoch>(client, &grace_epoch_key).await?;
+
+            println!("Proposal: {}", id);
+            println!("{:4}Author: {}", "", names.annotate(&author));
+            println!("{:4}Content:", "");
+            for (key, value) in &content {
+                println!("{:8}{}: {}", "", key, value);
+            }
+            println!("{:4}Start Epoch: {}", "", start_epoch);
+            println!("{:4}End Epoch: {}", "", end_epoch);
+            println!("{:4}Grace Epoch: {}", "", grace_epoch);
+            if start_epoch > current_epoch {
+                println!("{:4}Status: pending", "");
+            } else if start_epoch <= current_epoch && current_epoch <= end_epoch
+            {
+                println!("{:4}Status: on-going", "");
+            } else {
+                let votes = get_proposal_votes(client, start_epoch, id).await;
+                let proposal_result =
+                    compute_tally(client, start_epoch, votes).await;
+                println!("{:4}Status: done", "");
+                println!("{:4}Result: {}", "", proposal_result);
+            }
+        } else {
+            println!("Proposal: {}", id);
+            println!("{:4}Author: {}", "", names.annotate(&author));
+            println!("{:4}Start Epoch: {}", "", start_epoch);
+            println!("{:4}End Epoch: {}", "", end_epoch);
+            if start_epoch > current_epoch {
+                println!("{:4}Status: pending", "");
+            } else if start_epoch <= current_epoch && current_epoch <= end_epoch
+            {
+                println!("{:4}Status: on-going", "");
+            } else {
+                println!("{:4}Status: done", "");
+            }
+        }
+
+        Some(())
+    }
+
+    let client = HttpClient::new(args.query.ledger_address.clone()).unwrap();
+    let names = AddressNames::new(&ctx.wallet);
+    let current_epoch = query_epoch(args.query.clone()).await;
+    match args.proposal_id {
+        Some(id) => {
+            if print_proposal(&client, &names, id, current_epoch, true)
+                .await
+                .is_none()
+            {
+                eprintln!("No valid proposal was found with id {}", id)
+            }
+        }
+        None => {
+            let last_proposal_id_key = gov_storage::get_counter_key();
+            let last_proposal_id =
+                query_storage_value::<u64>(&client, &last_proposal_id_key)
+                    .await
+                    .unwrap();
+
+            for id in 0..last_proposal_id {
+                if print_proposal(&client, &names, id, current_epoch, false)
+                    .await
+                    .is_none()
+                {
+                    eprintln!("No valid proposal was found with id {}", id)
+                };
+            }
+        }
+    }
+}
+
+pub async fn query_proposal_result(
+    _ctx: Context,
+    args: args::QueryProposalResult,
+) {
+    let client = HttpClient::new(args.query.ledger_address.clone()).unwrap();
+    let current_epoch = query_epoch(args.query.clone()).await;
+
+    match args.proposal_id {
+        Some(id) => {
+            let start_epoch_key = gov_storage::get_voting_start_epoch_key(id);
+            let end_epoch_key = gov_storage::get_voting_end_epoch_key(id);
+            let start_epoch =
+                query_storage_value::<Epoch>(&client, &start_epoch_key).await;
+            let end_epoch =
+                query_storage_value::<Epoch>(&client, &end_epoch_key).await;
+
+            match (start_epoch, end_epoch) {
+                (Some(start_epoch), Some(end_epoch)) => {
+                    if current_epoch > end_epoch {
+                        let votes =
+                            get_proposal_votes(&client, start_epoch, id).await;
+                        let proposal_result =
+                            compute_tally(&client, start_epoch, votes).await;
+                        println!("Proposal: {}", id);
+                        println!("{:4}Result: {}", "", proposal_result);
+                    } else {
+                        eprintln!("Proposal is still in progress.");
+                        cli::safe_exit(1)
+                    }
+                }
+                _ => {
+                    eprintln!("Error while retriving proposal.");
+                    cli::safe_exit(1)
+                }
+            }
+        }
+        None => {
+            if args.offline {
+                match args.proposal_folder {
+                    Some(path) => {
+                        let mut dir = fs::read_dir(&path)
+                            .await
+                            .expect("Should be able to read the directory.");
+                        let mut files = HashSet::new();
+                        let mut is_proposal_present = false;
+
+                        while let Some(entry) = dir.next().await {
+                            match entry {
+                                Ok(entry) => match entry.file_type().await {
+                                    Ok(entry_stat) => {
+                                        if entry_stat.is_file() {
+                                            if entry.file_name().eq(&"proposal")
+                                            {
+                                                is_proposal_present = true
+                                            } else {
+                                                files.insert(entry.path());
+                                            }
+                                        }
+                                    }
+                                    Err(e) => {
+                                        eprintln!(
+                                            "Can't read entry type: {}.",
+                                            e
+                                        );
+                                        cli::safe_exit(1)
+                                    }
+                                },
+                                Err(e) => {
+                                    eprintln!("Can't read entry: {}.", e);
+                                    cli::safe_exit(1)
+                                }
+                            }
+                        }
+
+                        if !is_proposal_present {
+                            eprintln!(
+                                "The folder must contain a the offline \
+                                 proposal in a file named proposal"
+                            );
+                            cli::safe_exit(1)
+                        }
+
+                        let file = File::open(&path.join("proposal"))
+                            .expect("Proposal file must exist.");
+                        let proposal: OfflineProposal =
+                            serde_json::from_reader(file).expect(
+                                "JSON was not well-formatted for proposal.",
+                            );
+
+                        let public_key = get_public_key(
+                            &proposal.address,
+                            args.query.ledger_address.clone(),
+                        )
+                        .await
+                        .expect("Public key should exist.");
+
+                        if !proposal.check_signature(&public_key) {
+                            eprintln!("Bad proposal signature.");
+                            cli::safe_exit(1)
+                        }
+
+                        let votes = get_proposal_offline_votes(
+                            &client,
+                            proposal.clone(),
+                            files,
+                        )
+                        .await;
+                        let proposal_result =
+                            compute_tally(&client, proposal.tally_epoch, votes)
+                                .await;
+
+                        println!("{:4}Result: {}", "", proposal_result);
+                    }
+                    None => {
+                        eprintln!(
+                            "Offline flag must be followed by data-path."
+                        );
+                        cli::safe_exit(1)
+                    }
+                };
+            } else {
+                eprintln!("Either id or offline should be used as arguments.");
+                cli::safe_exit(1)
+            }
+        }
+    }
+}
+
+pub async fn get_proposal_votes(
+    client: &HttpClient,
+    epoch: Epoch,
+    proposal_id: u64,
+) -> Votes {
+    let validators = get_all_validators(client, epoch).await;
+
+    let vote_prefix_key =
+        gov_storage::get_proposal_vote_prefix_key(proposal_id);
+    let vote_iter =
+        query_storage_prefix::<ProposalVote>(client.clone(), vote_prefix_key)
+            .await;
+
+    let mut yay_validators: HashMap<Address, Amount> = HashMap::new();
+    let mut yay_delegators: HashMap<Address, Amount> = HashMap::new();
+    let mut nay_delegators: HashMap<Address, Amount> = HashMap::new();
+
+    if let Some(vote_iter) = vote_iter {
+        for (key, vote) in vote_iter {
+            let voter_address = gov_storage::get_voter_address(&key)
+                .expect("Vote key should contains the voting address.")
+                .clone();
+            if vote.is_yay() && validators.contains(&voter_address) {
+                let amount =
+                    get_validator_stake(client, epoch, &voter_address).await;
+                yay_validators.insert(voter_address, amount);
+            } else if !validators.contains(&voter_address) {
+                let validator_address =
+                    gov_storage::get_vote_delegation_address(&key)
+                        .expect(
+                            "Vote key should contains the delegation address.",
+                        )
+                        .clone();
+                let delegator_token_amount = get_bond_amount_at(
+                    client,
+                    &voter_address,
+                    &validator_address,
+                    epoch,
+                )
+                .await;
+                if let Some(amount) = delegator_token_amount {
+                    if vote.is_yay() {
+                        yay_delegators.insert(voter_address, amount);
+                    } else {
+                        nay_delegators.insert(voter_address, amount);
+                    }
+                }
+            }
+        }
+    }
+
+    Votes {
+        yay_validators,
+        yay_delegators,
+        nay_delegators,
+    }
+}
+
+pub async fn get_proposal_offline_votes(
+    client: &HttpClient,
+    proposal: OfflineProposal,
+    files: HashSet<PathBuf>,
+) -> Votes {
+    let validators = get_all_validators(client, proposal.tally_epoch).await;
+
+    let proposal_hash = proposal.compute_hash();
+
+    let mut yay_validators: HashMap<Address, Amount> = HashMap::new();
+    let mut yay_delegators: HashMap<Address, Amount> = HashMap::new();
+    let mut nay_delegators: HashMap<Address, Amount> = HashMap::new();
+
+    for path in files {
+        let file = File::open(&path).expect("Proposal file must exist.");
+        let proposal_vote: OfflineVote = serde_json::from_reader(file)
+            .expect("JSON was not well-formatted for offline vote.");
+
+        let key = pk_key(&proposal_vote.address);
+        let public_key = query_storage_value(client, &key)
+            .await
+            .expect("Public key should exist.");
+
+        if !proposal_vote.proposal_hash.eq(&proposal_hash)
+            || !proposal_vote.check_signature(&public_key)
+        {
+            continue;
+        }
+
+        if proposal_vote.vote.is_yay()
+            && validators.contains(&proposal_vote.address)
+        {
+            let amount = get_validator_stake(
+                client,
+                proposal.tally_epoch,
+                &proposal_vote.address,
+            )
+            .await;
+            yay_validators.insert(proposal_vote.address, amount);
+        } else if is_delegator_at(
+            client,
+            &proposal_vote.address,
+            proposal.tally_epoch,
+        )
+        .await
+        {
+            let key = pos::bonds_for_source_prefix(&proposal_vote.address);
+            let bonds_iter =
+                query_storage_prefix::<pos::Bonds>(client.clone(), key).await;
+            if let Some(bonds) = bonds_iter {
+                for (key, epoched_amount) in bonds {
+                    let bond = epoched_amount
+                        .get(proposal.tally_epoch)
+                        .expect("Delegation bond should be definied.");
+                    let epoch = anoma::ledger::pos::types::Epoch::from(
+                        proposal.tally_epoch.0,
+                    );
+                    let amount = *bond
+                        .deltas
+                        .get(&epoch)
+                        .expect("Delegation amount should be definied.");
+                    let validator_address =
+                        pos::get_validator_address_from_bond(&key).expect(
+                            "Delegation key should contain validator address.",
+                        );
+                    if proposal_vote.vote.is_yay() {
+                        yay_delegators.insert(validator_address, amount);
+                    } else {
+                        nay_delegators.insert(validator_address, amount);
+                    }
+                }
+            }
+        }
+    }
+
+    Votes {
+        yay_validators,
+        yay_delegators,
+        nay_delegators,
+    }
+}
+
+// Compute the result of a proposal
+pub async fn compute_tally(
+    client: &HttpClient,
+    epoch: Epoch,
+    votes: Votes,
+) -> TallyResult {
+    let validators = get_all_validators(client, epoch).await;
+    let total_stacked_tokens =
+        get_total_staked_tokes(client, epoch, &validators).await;
+
+    let Votes {
+        yay_validators,
+        yay_delegators,
+        nay_delegators,
+    } = votes;
+
+    let mut total_yay_stacked_tokens = Amount::from(0);
+    for (_, amount) in yay_validators.clone().into_iter() {
+        total_yay_stacked_tokens += amount;
+    }
+
+    // YAY: Add delegator amount whose validator didn't vote / voted nay
+    for (validator_address, amount) in yay_delegators.into_iter() {
+        if !yay_validators.contains_key(&validator_address) {
+            total_yay_stacked_tokens += amount;
+        }
+    }
+
+    // NAY: Remove delegator amount whose validator validator vote yay
+    for (validator_address, amount) in nay_delegators.into_iter() {
+        if yay_validators.contains_key(&validator_address) {
+            total_yay_stacked_tokens -= amount;
+        }
+    }
+
+    if 3 * total_yay_stacked_tokens >= 2 * total_stacked_tokens {
+        TallyResult::Passed
+    } else {
+        TallyResult::Rejected
+    }
+}
@@ -0,0 +1,336 @@
+//! Client RPC queries of the IBC packets
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+use anoma::ibc::core::ics04_channel::channel::ChannelEnd;
+use anoma::ibc::core::ics04_channel::packet::Sequence;
+use anoma::ibc::core::ics24_host::identifier::{
+    ChannelId, PortChannelId, PortId,
+};
+use anoma::ledger::ibc::storage::channel_key;
+use anoma::tendermint_proto::Protobuf;
+use serde::Serialize;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::query::Query;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::Order;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::query::Query;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::Order;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::{Client, HttpClient};
+
+use super::{query_path, query_storage_bytes};
+use crate::cli::{self, args, Context};
+use crate::node::ledger::rpc::{PacketStateInfo, Path};
+
+/// The progress of an IBC packet from the source to the destination chain
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PacketStatus {
+    /// The packet commitment is stored on the source chain, but the packet
+    /// hasn't been found on the destination chain
+    InFlight,
+    /// The destination chain has received the packet, but its
+    /// acknowledgement hasn't been relayed back to the source chain yet
+    Received,
+    /// The acknowledgement has been relayed back to the source chain
+    Acknowledged,
+    /// The packet timed out and its tokens, if any, have been refunded on
+    /// the source chain
+    TimedOut,
+    /// The packet commitment has been cleared on the source chain, but the
+    /// acknowledgement or the timeout that cleared it hasn't been found
+    Completed,
+}
+
+impl Display for PacketStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PacketStatus::InFlight => write!(f, "in flight"),
+            PacketStatus::Received => {
+                write!(f, "received, awaiting the acknowledgement")
+            }
+            PacketStatus::Acknowledged => write!(f, "acknowledged"),
+            PacketStatus::TimedOut => write!(f, "timed out and refunded"),
+            PacketStatus::Completed => {
+                write!(f, "completed (acknowledged or timed out)")
+            }
+        }
+    }
+}
+
+/// A transaction that emitted an event of an IBC packet
+#[derive(Clone, Debug, Serialize)]
+struct PacketTx {
+    /// The height of the block in which the transaction was applied
+    height: u64,
+    /// The hash of the transaction, if its event has been found
+    hash: Option<String>,
+}
+
+/// The status of an IBC packet with the transactions that moved it along
+#[derive(Clone, Debug, Serialize)]
+struct PacketStatusInfo {
+    source_port: String,
+    source_channel: String,
+    destination_port: String,
+    destination_channel: String,
+    sequence: u64,
+    status: PacketStatus,
+    /// The transaction that sent the packet on the source chain
+    send_tx: Option<PacketTx>,
+    /// The transaction that received the packet on the destination chain
+    receive_tx: Option<PacketTx>,
+    /// The transaction that acknowledged or timed out the packet on the
+    /// source chain
+    completion_tx: Option<PacketTx>,
+}
+
+/// The identifiers of a packet on both ends of its channel
+struct PacketId<'a> {
+    source_port: &'a PortId,
+    source_channel: &'a ChannelId,
+    destination_port: &'a PortId,
+    destination_channel: &'a ChannelId,
+    sequence: Sequence,
+}
+
+/// Query the status of an IBC packet sent from this chain
+pub async fn query_ibc_packet_status(
+    ctx: Context,
+    args: args::QueryIbcPacketStatus,
+) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let port_channel_id = PortChannelId {
+        port_id: args.port_id.clone(),
+        channel_id: args.channel_id.clone(),
+    };
+    let sequence = Sequence::from(args.sequence);
+
+    let source_state: PacketStateInfo = query_path(
+        &client,
+        Path::packet_state(
+            args.port_id.clone(),
+            args.channel_id.clone(),
+            args.sequence,
+        ),
+    )
+    .await;
+    if !source_state.sent {
+        eprintln!(
+            "No packet with the sequence {} has been sent from port {} on \
+             channel {}.",
+            sequence, args.port_id, args.channel_id
+        );
+        cli::safe_exit(1)
+    }
+
+    let channel =
+        match query_storage_bytes(&client, &channel_key(&port_channel_id))
+            .await
+            .map(|bytes| ChannelEnd::decode_vec(&bytes))
+        {
+            Some(Ok(channel)) => channel,
+            Some(Err(err)) => {
+                eprintln!("Error decoding the channel: {}", err);
+                cli::safe_exit(1)
+            }
+            None => {
+                eprintln!(
+                    "The channel {} doesn't exist on port {}.",
+                    args.channel_id, args.port_id
+                );
+                cli::safe_exit(1)
+            }
+        };
+    let counterparty = channel.counterparty();
+    let destination_channel = match counterparty.channel_id() {
+        Some(channel_id) => channel_id,
+        None => {
+            eprintln!(
+                "The counterparty of the channel {} hasn't been opened yet.",
+                args.channel_id
+            );
+            cli::safe_exit(1)
+        }
+    };
+    let packet = PacketId {
+        source_port: &args.port_id,
+        source_channel: &args.channel_id,
+        destination_port: &counterparty.port_id,
+        destination_channel,
+        sequence,
+    };
+
+    let send_tx = query_packet_event(&client, "send_packet", &packet).await;
+
+    // The receipt and the acknowledgement are written on the destination
+    // chain, which can only be queried when it's an Anoma chain
+    let (received, receive_tx) = match &args.destination_ledger_address {
+        Some(address) => {
+            let destination_client = HttpClient::new(address.clone()).unwrap();
+            let destination_state: PacketStateInfo = query_path(
+                &destination_client,
+                Path::packet_state(
+                    packet.destination_port.clone(),
+                    packet.destination_channel.clone(),
+                    args.sequence,
+                ),
+            )
+            .await;
+            let receive_tx = query_packet_event(
+                &destination_client,
+                "write_acknowledgement",
+                &packet,
+            )
+            .await;
+            (
+                destination_state.received || destination_state.acknowledged,
+                receive_tx,
+            )
+        }
+        None => (false, None),
+    };
+
+    // The packet commitment is deleted on the source chain once the packet
+    // is either acknowledged or timed out
+    let (status, completion_tx) = if source_state.committed {
+        let status = if received {
+            PacketStatus::Received
+        } else {
+            PacketStatus::InFlight
+        };
+        (status, None)
+    } else if let Some(tx) =
+        query_packet_event(&client, "acknowledge_packet", &packet).await
+    {
+        (PacketStatus::Acknowledged, Some(tx))
+    } else if let Some(tx) =
+        query_packet_event(&client, "timeout_packet", &packet).await
+    {
+        (PacketStatus::TimedOut, Some(tx))
+    } else {
+        (PacketStatus::Completed, None)
+    };
+
+    let info = PacketStatusInfo {
+        source_port: packet.source_port.to_string(),
+        source_channel: packet.source_channel.to_string(),
+        destination_port: packet.destination_port.to_string(),
+        destination_channel: packet.destination_channel.to_string(),
+        sequence: args.sequence,
+        status,
+        send_tx,
+        receive_tx,
+        completion_tx,
+    };
+    if ctx.global_args.output == args::OutputMode::Json {
+        println!("{}", serde_json::to_string(&info).unwrap());
+        return;
+    }
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    writeln!(
+        w,
+        "Packet {} from {}/{} to {}/{}: {}",
+        info.sequence,
+        info.source_port,
+        info.source_channel,
+        info.destination_port,
+        info.destination_channel,
+        info.status
+    )
+    .unwrap();
+    let steps = [
+        ("Sent", &info.send_tx),
+        ("Received on the destination", &info.receive_tx),
+        (
+            match info.status {
+                PacketStatus::TimedOut => "Timed out",
+                _ => "Acknowledged",
+            },
+            &info.completion_tx,
+        ),
+    ];
+    for (step, tx) in steps {
+        if let Some(PacketTx { height, hash }) = tx {
+            writeln!(
+                w,
+                "  {} at height {} by transaction {}",
+                step,
+                height,
+                hash.as_deref().unwrap_or("unknown")
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Find the transaction that emitted an event of the given type for the
+/// packet.
+async fn query_packet_event(
+    client: &HttpClient,
+    event_type: &str,
+    packet: &PacketId<'_>,
+) -> Option<PacketTx> {
+    let sequence = packet.sequence.to_string();
+    let attributes = [
+        ("packet_src_port", packet.source_port.to_string()),
+        ("packet_src_channel", packet.source_channel.to_string()),
+        ("packet_dst_port", packet.destination_port.to_string()),
+        ("packet_dst_channel", packet.destination_channel.to_string()),
+        ("packet_sequence", sequence),
+    ];
+    let query =
+        attributes
+            .iter()
+            .fold(Query::default(), |query, (key, value)| {
+                query.and_eq(format!("{}.{}", event_type, key), value.clone())
+            });
+    let blocks = client
+        .block_search(query, 1, 1, Order::Ascending)
+        .await
+        .ok()?
+        .blocks;
+    let height = blocks.get(0)?.block.header.height;
+    let events = client
+        .block_results(height)
+        .await
+        .ok()?
+        .end_block_events
+        .unwrap_or_default();
+    let events: Vec<(&str, HashMap<&str, &str>)> = events
+        .iter()
+        .map(|event| {
+            let attributes = event
+                .attributes
+                .iter()
+                .map(|tag| (tag.key.as_ref(), tag.value.as_ref()))
+                .collect();
+            (event.type_str.as_str(), attributes)
+        })
+        .collect();
+    let position = events.iter().position(|(ty, event_attributes)| {
+        *ty == event_type
+            && attributes.iter().all(|(key, value)| {
+                event_attributes.get(key) == Some(&value.as_str())
+            })
+    })?;
+    // The event of the transaction that emitted an IBC event follows the IBC
+    // event in the block
+    let hash = events[position + 1..]
+        .iter()
+        .find(|(ty, _)| *ty == "applied")
+        .and_then(|(_, attributes)| attributes.get("hash"))
+        .map(|hash| hash.to_string());
+    Some(PacketTx {
+        height: height.value(),
+        hash,
+    })
+}
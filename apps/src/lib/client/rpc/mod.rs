@@ -0,0 +1,600 @@
+//! Client RPC queries. The queries specific to the proof-of-stake, token and
+//! governance modules are in their sub-modules, mirroring the ledger's query
+//! router. The inspection of an account's storage is in the `account`
+//! sub-module, the tracking of IBC packets in the `ibc` sub-module and the
+//! search for the NFTs of an owner in the `nft` sub-module.
+
+pub mod account;
+pub mod gov;
+pub mod ibc;
+pub mod nft;
+pub mod pos;
+pub mod token;
+
+use std::iter::Iterator;
+
+use anoma::ledger::governance::storage as gov_storage;
+use anoma::ledger::parameters::{storage as param_storage, EpochDuration};
+use anoma::ledger::treasury::storage as treasury_storage;
+use anoma::types::address::Address;
+use anoma::types::key::*;
+use anoma::types::storage::{self, BlockHeight, Epoch, PrefixValue};
+use anoma::types::token::Amount;
+use anoma::types::transaction::TxResult;
+use borsh::BorshDeserialize;
+use itertools::Itertools;
+#[cfg(not(feature = "ABCI"))]
+use tendermint::abci::Code;
+#[cfg(not(feature = "ABCI"))]
+use tendermint::block::Height;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_config::net::Address as TendermintAddress;
+#[cfg(feature = "ABCI")]
+use tendermint_config_abci::net::Address as TendermintAddress;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_stable::abci::Code;
+#[cfg(feature = "ABCI")]
+use tendermint_stable::block::Height;
+
+use crate::cli::{self, args, Context};
+use crate::client::names::AddressNames;
+use crate::client::tendermint_rpc_types::DryRunResult;
+use crate::node::ledger::events::{query_tx_response, TxEventQuery};
+use crate::node::ledger::rpc::{HistoryInfo, Path};
+
+/// Query the epoch of the last committed block
+pub async fn query_epoch(args: args::Query) -> Epoch {
+    let epoch = query_last_epoch(args.ledger_address).await;
+    println!("Last committed epoch: {}", epoch);
+    epoch
+}
+
+/// Query the epoch of the last committed block without printing it
+pub async fn query_last_epoch(ledger_address: TendermintAddress) -> Epoch {
+    let client = HttpClient::new(ledger_address).unwrap();
+    let path = Path::epoch();
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match Epoch::try_from_slice(&response.value[..]) {
+            Ok(epoch) => {
+                return epoch;
+            }
+
+            Err(err) => {
+                eprintln!("Error decoding the epoch value: {}", err)
+            }
+        },
+        Code::Err(err) => eprintln!(
+            "Error in the query {} (error code {})",
+            response.info, err
+        ),
+    }
+    cli::safe_exit(1)
+}
+
+/// Query the raw bytes of given storage key. A query for a proof at a given
+/// height is sent to the first of the ledger node and the archive nodes that
+/// serves it.
+pub async fn query_raw_bytes(_ctx: Context, args: args::QueryRawBytes) {
+    let height = args.height.map(BlockHeight);
+    let ledger_address = match height {
+        Some(height) => select_ledger_address(
+            args.query.ledger_address,
+            args.archive_ledger_addresses,
+            height,
+            args.prove,
+        )
+        .await
+        .unwrap_or_else(|| {
+            eprintln!(
+                "None of the given ledger nodes serves proofs at height {}",
+                height
+            );
+            cli::safe_exit(1)
+        }),
+        None => args.query.ledger_address,
+    };
+    let client = HttpClient::new(ledger_address).unwrap();
+    let path = Path::value(args.storage_key);
+    let data = vec![];
+    let height = height.map(|height| Height::try_from(height.0).unwrap());
+    let response = client
+        .abci_query(Some(path.into()), data, height, args.prove)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => {
+            println!("{}", hex::encode(&response.value));
+            for op in response.proof.into_iter().flat_map(|proof| proof.ops) {
+                println!(
+                    "Proof {}: key {}, data {}",
+                    op.field_type,
+                    hex::encode(&op.key),
+                    hex::encode(&op.data)
+                );
+            }
+        }
+        Code::Err(err) => {
+            eprintln!(
+                "Error in the query {}  (error code {})",
+                response.info, err
+            );
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// Query the historical queries served by a ledger node. Returns `None` if
+/// the node cannot be reached or if it doesn't respond to the query.
+pub async fn query_history_info(
+    ledger_address: TendermintAddress,
+) -> Option<HistoryInfo> {
+    let client = HttpClient::new(ledger_address).ok()?;
+    let path = Path::history();
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .ok()?;
+    match response.code {
+        Code::Ok => HistoryInfo::try_from_slice(&response.value[..]).ok(),
+        Code::Err(_) => None,
+    }
+}
+
+/// Select the ledger node to query at the given height, with or without a
+/// proof. The given ledger node is preferred, if it serves the height.
+/// Otherwise, the first archive node that serves it is selected.
+pub async fn select_ledger_address(
+    ledger_address: TendermintAddress,
+    archive_addresses: Vec<TendermintAddress>,
+    height: BlockHeight,
+    prove: bool,
+) -> Option<TendermintAddress> {
+    for address in std::iter::once(ledger_address).chain(archive_addresses) {
+        if let Some(history) = query_history_info(address.clone()).await {
+            if history.serves_height(height, prove) {
+                return Some(address);
+            }
+        }
+    }
+    None
+}
+
+pub async fn query_protocol_parameters(
+    _ctx: Context,
+    args: args::QueryProtocolParameters,
+) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+
+    println!("Goveranance parameters");
+    let key = gov_storage::get_max_proposal_code_size_key();
+    let max_proposal_code_size = query_storage_value::<u64>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!(
+        "{:4}Max. proposal code size: {}",
+        "", max_proposal_code_size
+    );
+
+    let key = gov_storage::get_max_proposal_content_key();
+    let max_proposal_content = query_storage_value::<u64>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!(
+        "{:4}Max. proposal content size: {}",
+        "", max_proposal_content
+    );
+
+    let key = gov_storage::get_min_proposal_fund_key();
+    let min_proposal_fund = query_storage_value::<Amount>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!("{:4}Min. proposal funds: {}", "", min_proposal_fund);
+
+    let key = gov_storage::get_min_proposal_grace_epoch_key();
+    let min_proposal_grace_epoch = query_storage_value::<u64>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!(
+        "{:4}Min. proposal grace epoch: {}",
+        "", min_proposal_grace_epoch
+    );
+
+    let key = gov_storage::get_min_proposal_period_key();
+    let min_proposal_period = query_storage_value::<u64>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!("{:4}Min. proposal period: {}", "", min_proposal_period);
+
+    println!("Protocol parameters");
+    let key = param_storage::get_epoch_storage_key();
+    let epoch_duration = query_storage_value::<EpochDuration>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!(
+        "{:4}Min. epoch duration: {}",
+        "", epoch_duration.min_duration
+    );
+    println!(
+        "{:4}Min. number of blocks: {}",
+        "", epoch_duration.min_num_of_blocks
+    );
+
+    let key = param_storage::get_max_expected_time_per_block_key();
+    let max_block_duration = query_storage_value::<u64>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!("{:4}Max. block duration: {}", "", max_block_duration);
+
+    let key = param_storage::get_tx_whitelist_storage_key();
+    let vp_whitelist = query_storage_value::<Vec<String>>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!("{:4}VP whitelist: {:?}", "", vp_whitelist);
+
+    let key = param_storage::get_tx_whitelist_storage_key();
+    let tx_whitelist = query_storage_value::<Vec<String>>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!("{:4}Transactions whitelist: {:?}", "", tx_whitelist);
+
+    println!("Treasury parameters");
+    let key = treasury_storage::get_max_transferable_fund_key();
+    let max_transferable_amount = query_storage_value::<Amount>(&client, &key)
+        .await
+        .expect("Parameter should be definied.");
+    println!(
+        "{:4}Max. transferable amount: {}",
+        "", max_transferable_amount
+    );
+
+    println!("PoS parameters");
+    let pos_params = pos::get_params(&client)
+        .await
+        .expect("Parameter should be definied.");
+    println!(
+        "{:4}Block proposer reward: {}",
+        "", pos_params.block_proposer_reward
+    );
+    println!(
+        "{:4}Block vote reward: {}",
+        "", pos_params.block_vote_reward
+    );
+    println!(
+        "{:4}Duplicate vote slash rate: {}",
+        "", pos_params.duplicate_vote_slash_rate
+    );
+    println!("{:4}Jail length: {}", "", pos_params.jail_len);
+    println!(
+        "{:4}Light client attack slash rate: {}",
+        "", pos_params.light_client_attack_slash_rate
+    );
+    println!(
+        "{:4}Max. commission rate change: {}",
+        "", pos_params.max_commission_rate_change
+    );
+    println!(
+        "{:4}Max. missed blocks: {}",
+        "", pos_params.max_missed_blocks
+    );
+    println!(
+        "{:4}Max. validator slots: {}",
+        "", pos_params.max_validator_slots
+    );
+    println!("{:4}Pipeline length: {}", "", pos_params.pipeline_len);
+    println!("{:4}Unbonding length: {}", "", pos_params.unbonding_len);
+    println!("{:4}Votes per token: {}", "", pos_params.votes_per_token);
+}
+
+/// Dry run a transaction and get its structured result
+pub async fn dry_run_tx(
+    ledger_address: &TendermintAddress,
+    tx_bytes: Vec<u8>,
+) -> DryRunResult {
+    let client = HttpClient::new(ledger_address.clone()).unwrap();
+    let path = Path::dry_run_tx();
+    let response = client
+        .abci_query(Some(path.into()), tx_bytes, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match TxResult::try_from_slice(&response.value[..]) {
+            Ok(result) => DryRunResult::from(result),
+            Err(err) => {
+                eprintln!("Error decoding the dry run result: {}", err);
+                cli::safe_exit(1)
+            }
+        },
+        Code::Err(err) => DryRunResult::failed(err, response.log.to_string()),
+    }
+}
+
+/// Estimate the gas needed to apply a transaction by dry running it. Exits if
+/// the dry run fails.
+pub async fn estimate_gas(
+    ledger_address: &TendermintAddress,
+    tx_bytes: Vec<u8>,
+) -> u64 {
+    let client = HttpClient::new(ledger_address.clone()).unwrap();
+    let path = Path::dry_run_tx();
+    let response = client
+        .abci_query(Some(path.into()), tx_bytes, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match TxResult::try_from_slice(&response.value[..]) {
+            Ok(result) => {
+                if !result.is_accepted() {
+                    eprintln!(
+                        "Warning: The transaction was rejected in the dry run \
+                         by the validity predicates of: {}",
+                        result.vps_result.rejected_vps.iter().join(", ")
+                    );
+                }
+                return result.gas_used;
+            }
+            Err(err) => {
+                eprintln!("Error decoding the dry run result: {}", err)
+            }
+        },
+        Code::Err(err) => eprintln!(
+            "The dry run of the transaction failed: {} (error code {})",
+            response.log, err
+        ),
+    }
+    cli::safe_exit(1)
+}
+
+/// Get account's public key stored in its storage sub-space
+pub async fn get_public_key(
+    address: &Address,
+    ledger_address: TendermintAddress,
+) -> Option<common::PublicKey> {
+    let client = HttpClient::new(ledger_address).unwrap();
+    let key = pk_key(address);
+    query_storage_value(&client, &key).await
+}
+
+/// Check if the address exists on chain. Established address exists if it has a
+/// stored validity predicate. Implicit and internal addresses always return
+/// true.
+pub async fn known_address(
+    address: &Address,
+    ledger_address: TendermintAddress,
+) -> bool {
+    let client = HttpClient::new(ledger_address).unwrap();
+    match address {
+        Address::Established(_) => {
+            // Established account exists if it has a VP
+            let key = storage::Key::validity_predicate(address);
+            query_has_storage_key(client, key).await
+        }
+        Address::Implicit(_) | Address::Internal(_) => true,
+    }
+}
+
+/// Query a storage value and decode it with [`BorshDeserialize`].
+pub async fn query_storage_value<T>(
+    client: &HttpClient,
+    key: &storage::Key,
+) -> Option<T>
+where
+    T: BorshDeserialize,
+{
+    let path = Path::value(key.to_owned());
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match T::try_from_slice(&response.value[..]) {
+            Ok(value) => return Some(value),
+            Err(err) => eprintln!("Error decoding the value: {}", err),
+        },
+        Code::Err(err) => {
+            if err == 1 {
+                return None;
+            } else {
+                eprintln!(
+                    "Error in the query {} (error code {})",
+                    response.info, err
+                )
+            }
+        }
+    }
+    cli::safe_exit(1)
+}
+
+/// Query a storage value as raw bytes, for values that are not encoded with
+/// [`BorshDeserialize`], such as the code of validity predicates.
+pub async fn query_storage_bytes(
+    client: &HttpClient,
+    key: &storage::Key,
+) -> Option<Vec<u8>> {
+    let path = Path::value(key.to_owned());
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => return Some(response.value),
+        Code::Err(err) => {
+            if err == 1 {
+                return None;
+            } else {
+                eprintln!(
+                    "Error in the query {} (error code {})",
+                    response.info, err
+                )
+            }
+        }
+    }
+    cli::safe_exit(1)
+}
+
+/// Query a range of storage values with a matching prefix and decode them with
+/// [`BorshDeserialize`]. Returns an iterator of the storage keys paired with
+/// their associated values.
+pub async fn query_storage_prefix<T>(
+    client: HttpClient,
+    key: storage::Key,
+) -> Option<impl Iterator<Item = (storage::Key, T)>>
+where
+    T: BorshDeserialize,
+{
+    let values = query_storage_prefix_bytes(client, key).await?;
+    let decode =
+        |PrefixValue { key, value }: PrefixValue| match T::try_from_slice(
+            &value[..],
+        ) {
+            Err(err) => {
+                eprintln!(
+                    "Skipping a value for key {}. Error in decoding: {}",
+                    key, err
+                );
+                None
+            }
+            Ok(value) => Some((key, value)),
+        };
+    Some(values.into_iter().filter_map(decode))
+}
+
+/// Query a range of storage values with a matching prefix as raw bytes, for
+/// values that are not all of the same type.
+pub async fn query_storage_prefix_bytes(
+    client: HttpClient,
+    key: storage::Key,
+) -> Option<Vec<PrefixValue>> {
+    let path = Path::prefix(key);
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => {
+            match Vec::<PrefixValue>::try_from_slice(&response.value[..]) {
+                Ok(values) => return Some(values),
+                Err(err) => eprintln!("Error decoding the values: {}", err),
+            }
+        }
+        Code::Err(err) => {
+            if err == 1 {
+                return None;
+            } else {
+                eprintln!(
+                    "Error in the query {} (error code {})",
+                    response.info, err
+                )
+            }
+        }
+    }
+    cli::safe_exit(1)
+}
+
+/// Query to check if the given storage key exists.
+pub async fn query_has_storage_key(
+    client: HttpClient,
+    key: storage::Key,
+) -> bool {
+    let path = Path::has_key(key);
+    let data = vec![];
+    let response = client
+        .abci_query(Some(path.into()), data, None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match bool::try_from_slice(&response.value[..]) {
+            Ok(value) => return value,
+            Err(err) => eprintln!("Error decoding the value: {}", err),
+        },
+        Code::Err(err) => {
+            eprintln!(
+                "Error in the query {} (error code {})",
+                response.info, err
+            )
+        }
+    }
+    cli::safe_exit(1)
+}
+
+/// Query a module path of the ledger and decode its response
+pub async fn query_path<T: BorshDeserialize>(
+    client: &HttpClient,
+    path: Path,
+) -> T {
+    let response = client
+        .abci_query(Some(path.into()), vec![], None, false)
+        .await
+        .unwrap();
+    match response.code {
+        Code::Ok => match T::try_from_slice(&response.value[..]) {
+            Ok(value) => value,
+            Err(err) => {
+                eprintln!("Error decoding the response: {}", err);
+                cli::safe_exit(1)
+            }
+        },
+        Code::Err(err) => {
+            eprintln!(
+                "Error in the query {} (error code {})",
+                response.info, err
+            );
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// Lookup the results of applying the specified transaction to the
+/// blockchain.
+pub async fn query_result(ctx: Context, args: args::QueryResult) {
+    let names = AddressNames::new(&ctx.wallet);
+    // First try looking up application event pertaining to given hash.
+    let tx_response = query_tx_response(
+        &args.query.ledger_address,
+        TxEventQuery::Applied(args.tx_hash.clone()),
+    )
+    .await;
+    match tx_response {
+        Ok(result) => {
+            println!(
+                "Transaction was applied with result: {}",
+                names.annotate_text(
+                    &serde_json::to_string_pretty(&result).unwrap()
+                )
+            )
+        }
+        Err(err1) => {
+            // If this fails then instead look for an acceptance event.
+            let tx_response = query_tx_response(
+                &args.query.ledger_address,
+                TxEventQuery::Accepted(args.tx_hash),
+            )
+            .await;
+            match tx_response {
+                Ok(result) => println!(
+                    "Transaction was accepted with result: {}",
+                    names.annotate_text(
+                        &serde_json::to_string_pretty(&result).unwrap()
+                    )
+                ),
+                Err(err2) => {
+                    // Print the errors that caused the lookups to fail
+                    eprintln!("{}\n{}", err1, err2);
+                    cli::safe_exit(1)
+                }
+            }
+        }
+    }
+}
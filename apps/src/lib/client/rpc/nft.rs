@@ -0,0 +1,305 @@
+//! Client RPC queries of the NFT collections and of the NFTs owned by an
+//! address
+
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+use anoma::types::address::Address;
+use anoma::types::nft::{self, NftTokenMetadata};
+use anoma::types::storage::{self, PrefixValue};
+use borsh::BorshDeserialize;
+use serde::Serialize;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::HttpClient;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::HttpClient;
+
+use super::{query_path, query_storage_prefix_bytes};
+use crate::cli::{args, Context};
+use crate::client::names::AddressNames;
+use crate::node::ledger::rpc::{NftCollectionInfo, NftTokensPage, Path};
+
+/// An NFT collection with the tokens of an owner
+#[derive(Clone, Debug, Serialize)]
+struct OwnedCollection {
+    /// The address of the NFT
+    address: String,
+    /// The alias of the NFT in the wallet, if any
+    alias: Option<String>,
+    /// The tag of the NFT
+    tag: Option<String>,
+    tokens: Vec<OwnedToken>,
+}
+
+/// A token of an NFT collection
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct OwnedToken {
+    id: String,
+    /// The values of the NFT's mandatory fields
+    values: Vec<String>,
+    /// The metadata, if it conforms to the [`NftTokenMetadata`] standard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<NftTokenMetadata>,
+    /// The raw metadata, if it doesn't conform to the standard
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_metadata: Option<String>,
+}
+
+/// Query the NFT tokens owned by an address. There is no index of the NFTs
+/// in storage, so the collections are searched for among the addresses known
+/// by the wallet, unless a collection is given.
+pub async fn query_nfts(ctx: Context, args: args::QueryNfts) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let owner = ctx.get(&args.owner);
+    let names = AddressNames::new(&ctx.wallet);
+
+    let candidates: BTreeSet<Address> = match args.nft {
+        Some(nft) => BTreeSet::from([nft]),
+        None => ctx
+            .wallet
+            .get_addresses()
+            .into_values()
+            .filter(|address| matches!(address, Address::Established(_)))
+            .collect(),
+    };
+    let mut collections = vec![];
+    for address in candidates {
+        let values = query_storage_prefix_bytes(
+            client.clone(),
+            nft::_nft_prefix(&address),
+        )
+        .await
+        .unwrap_or_default();
+        if values.is_empty() {
+            continue;
+        }
+        let tokens = owned_tokens(&address, &owner, &values);
+        if tokens.is_empty() {
+            continue;
+        }
+        let tag = find_value::<String>(&values, &nft::get_tag_key(&address));
+        collections.push(OwnedCollection {
+            address: address.encode(),
+            alias: names.find(&address),
+            tag,
+            tokens,
+        });
+    }
+
+    if ctx.global_args.output == args::OutputMode::Json {
+        println!("{}", serde_json::to_string(&collections).unwrap());
+        return;
+    }
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    if collections.is_empty() {
+        writeln!(w, "No NFTs owned by {}", names.annotate(&owner)).unwrap();
+        return;
+    }
+    writeln!(w, "NFTs owned by {}:", names.annotate(&owner)).unwrap();
+    for collection in collections {
+        let name = match &collection.alias {
+            Some(alias) => format!("{} ({})", collection.address, alias),
+            None => collection.address.clone(),
+        };
+        match &collection.tag {
+            Some(tag) => writeln!(w, "  {}, tag {}:", name, tag).unwrap(),
+            None => writeln!(w, "  {}:", name).unwrap(),
+        }
+        for token in collection.tokens {
+            match (&token.metadata, &token.raw_metadata) {
+                (Some(metadata), _) => writeln!(
+                    w,
+                    "    Token {}: {} ({})",
+                    token.id, metadata.name, metadata.media_uri
+                )
+                .unwrap(),
+                (None, Some(raw)) => {
+                    writeln!(w, "    Token {}: metadata {}", token.id, raw)
+                        .unwrap()
+                }
+                (None, None) => writeln!(w, "    Token {}", token.id).unwrap(),
+            }
+            if let Some(metadata) = &token.metadata {
+                if !metadata.description.is_empty() {
+                    writeln!(w, "      {}", metadata.description).unwrap();
+                }
+                for (name, value) in &metadata.attributes {
+                    writeln!(w, "      {}: {}", name, value).unwrap();
+                }
+            }
+            if !token.values.is_empty() {
+                writeln!(w, "      Values: {}", token.values.join(", "))
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// Query the NFT collections of the chain, or a page of the token IDs of a
+/// collection
+pub async fn query_nft_collections(
+    ctx: Context,
+    args: args::QueryNftCollections,
+) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let names = AddressNames::new(&ctx.wallet);
+    match args.nft {
+        None => {
+            let collections: Vec<NftCollectionInfo> =
+                query_path(&client, Path::nft_collections()).await;
+            if collections.is_empty() {
+                println!("No NFT collections found");
+                return;
+            }
+            println!("NFT collections:");
+            for collection in collections {
+                let vp_hash = collection
+                    .vp_hash
+                    .map(|hash| hash.to_string())
+                    .unwrap_or_else(|| "none".to_owned());
+                println!(
+                    "  {}: creator {}, {} tokens, VP sha256 {}",
+                    names.annotate(&collection.address),
+                    names.annotate(&collection.creator),
+                    collection.token_count,
+                    vp_hash
+                );
+            }
+        }
+        Some(nft) => {
+            let path = Path::nft_tokens(nft.clone(), args.offset, args.limit);
+            let page: NftTokensPage = query_path(&client, path).await;
+            if page.token_ids.is_empty() {
+                println!(
+                    "No tokens of {} from offset {}, out of {}",
+                    names.annotate(&nft),
+                    args.offset,
+                    page.total
+                );
+                return;
+            }
+            println!(
+                "Tokens {}-{} of {} out of {}:",
+                args.offset + 1,
+                args.offset + page.token_ids.len() as u64,
+                names.annotate(&nft),
+                page.total
+            );
+            for id in &page.token_ids {
+                println!("  {}", id);
+            }
+            if let Some(next) = page.next_offset(args.offset) {
+                println!("More tokens from --offset {}", next);
+            }
+        }
+    }
+}
+
+/// Find the tokens of an owner in the storage values of an NFT. The burnt
+/// tokens are left out.
+fn owned_tokens(
+    address: &Address,
+    owner: &Address,
+    values: &[PrefixValue],
+) -> Vec<OwnedToken> {
+    let mut tokens: Vec<OwnedToken> = values
+        .iter()
+        .filter_map(|PrefixValue { key, value }| {
+            let (_, id) = nft::is_nft_current_owner_key(key, address)?;
+            let current_owner = Address::try_from_slice(value).ok()?;
+            (&current_owner == owner).then(|| id)
+        })
+        .filter(|id| {
+            !find_value::<bool>(values, &nft::get_token_burnt_key(address, id))
+                .unwrap_or_default()
+        })
+        .map(|id| {
+            let token_values = find_value::<Vec<String>>(
+                values,
+                &nft::get_token_value_key(address, &id),
+            )
+            .unwrap_or_default();
+            let raw_metadata = find_value::<String>(
+                values,
+                &nft::get_token_metadata_key(address, &id),
+            );
+            let metadata = raw_metadata.as_ref().and_then(|raw| {
+                let metadata: NftTokenMetadata = raw.parse().ok()?;
+                metadata.validate().is_ok().then(|| metadata)
+            });
+            OwnedToken {
+                id,
+                values: token_values,
+                raw_metadata: raw_metadata.filter(|_| metadata.is_none()),
+                metadata,
+            }
+        })
+        .collect();
+    // The token IDs are numbers
+    tokens.sort_by_key(|token| token.id.parse::<u64>().ok());
+    tokens
+}
+
+/// Find and decode the value of a key in the storage values of an NFT
+fn find_value<T: BorshDeserialize>(
+    values: &[PrefixValue],
+    key: &storage::Key,
+) -> Option<T> {
+    values
+        .iter()
+        .find(|value| &value.key == key)
+        .and_then(|PrefixValue { value, .. }| T::try_from_slice(value).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+    };
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    fn value(key: storage::Key, value: impl BorshSerialize) -> PrefixValue {
+        PrefixValue {
+            key,
+            value: value.try_to_vec().unwrap(),
+        }
+    }
+
+    /// Test that only the tokens of the owner that haven't been burnt are
+    /// found
+    #[test]
+    fn test_owned_tokens() {
+        let nft = established_address_1();
+        let owner = established_address_2();
+        let other = established_address_3();
+        let metadata = r#"{"name":"Token","media_uri":"ipfs://QmToken"}"#;
+        let values = vec![
+            value(nft::get_tag_key(&nft), "art".to_owned()),
+            value(nft::get_token_current_owner_key(&nft, "10"), owner.clone()),
+            value(nft::get_token_current_owner_key(&nft, "2"), owner.clone()),
+            value(nft::get_token_metadata_key(&nft, "2"), metadata.to_owned()),
+            value(nft::get_token_value_key(&nft, "2"), vec!["blue".to_owned()]),
+            value(nft::get_token_metadata_key(&nft, "10"), "raw".to_owned()),
+            value(nft::get_token_current_owner_key(&nft, "3"), other),
+            value(nft::get_token_current_owner_key(&nft, "4"), owner.clone()),
+            value(nft::get_token_burnt_key(&nft, "4"), true),
+        ];
+
+        let tokens = owned_tokens(&nft, &owner, &values);
+        let ids: Vec<&str> =
+            tokens.iter().map(|token| token.id.as_str()).collect();
+        assert_eq!(ids, ["2", "10"]);
+        assert_eq!(tokens[0].values, ["blue"]);
+        assert_eq!(tokens[0].metadata.as_ref().unwrap().name, "Token");
+        assert_eq!(tokens[0].raw_metadata, None);
+        assert_eq!(tokens[1].metadata, None);
+        assert_eq!(tokens[1].raw_metadata.as_deref(), Some("raw"));
+        assert_eq!(
+            find_value::<String>(&values, &nft::get_tag_key(&nft)).as_deref(),
+            Some("art")
+        );
+    }
+}
@@ -0,0 +1,1149 @@
+//! Client RPC queries of the proof-of-stake bonds, validators and slashes
+
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use anoma::ledger::pos::types::{
+    Epoch as PosEpoch, SlashType, ValidatorState, VotingPower,
+    WeightedValidator,
+};
+use anoma::ledger::pos::{
+    self, is_validator_slashes_key, BondId, Bonds, PosParams, Slash, Unbonds,
+};
+use anoma::types::address::Address;
+use anoma::types::storage::{Epoch, PrefixValue};
+use anoma::types::time::DateTimeUtc;
+use anoma::types::token::{self, Amount};
+use borsh::BorshDeserialize;
+use itertools::Itertools;
+use serde::Serialize;
+#[cfg(not(feature = "ABCI"))]
+use tendermint::abci::Code;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_config::net::Address as TendermintAddress;
+#[cfg(feature = "ABCI")]
+use tendermint_config_abci::net::Address as TendermintAddress;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_stable::abci::Code;
+
+use super::{
+    query_epoch, query_storage_prefix, query_storage_prefix_bytes,
+    query_storage_value,
+};
+use crate::cli::{self, args, Context};
+use crate::client::names::AddressNames;
+use crate::node::ledger::rpc::{Path, UnbondsInfo};
+
+/// Query PoS bond(s)
+pub async fn query_bonds(ctx: Context, args: args::QueryBonds) {
+    let epoch = query_epoch(args.query.clone()).await;
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let names = AddressNames::new(&ctx.wallet);
+    match (args.owner, args.validator) {
+        (Some(owner), Some(validator)) => {
+            let source = ctx.get(&owner);
+            let validator = ctx.get(&validator);
+            // Find owner's delegations to the given validator
+            let bond_id = pos::BondId { source, validator };
+            let bond_key = pos::bond_key(&bond_id);
+            let bonds =
+                query_storage_value::<pos::Bonds>(&client, &bond_key).await;
+            // Find owner's unbonded delegations from the given
+            // validator
+            let unbond_key = pos::unbond_key(&bond_id);
+            let unbonds =
+                query_storage_value::<pos::Unbonds>(&client, &unbond_key).await;
+            // Find validator's slashes, if any
+            let slashes_key = pos::validator_slashes_key(&bond_id.validator);
+            let slashes =
+                query_storage_value::<pos::Slashes>(&client, &slashes_key)
+                    .await
+                    .unwrap_or_default();
+            let auto_compound = query_auto_compound(&client, &bond_id).await;
+
+            let stdout = io::stdout();
+            let mut w = stdout.lock();
+
+            if let Some(bonds) = &bonds {
+                let bond_type = if bond_id.source == bond_id.validator {
+                    "Self-bonds"
+                } else {
+                    "Delegations"
+                };
+                writeln!(w, "{}:", bond_type).unwrap();
+                process_bonds_query(
+                    bonds, &slashes, &epoch, None, None, None, &mut w,
+                );
+                print_auto_compound(auto_compound, &mut w);
+            }
+
+            if let Some(unbonds) = &unbonds {
+                let bond_type = if bond_id.source == bond_id.validator {
+                    "Unbonded self-bonds"
+                } else {
+                    "Unbonded delegations"
+                };
+                writeln!(w, "{}:", bond_type).unwrap();
+                process_unbonds_query(
+                    unbonds, &slashes, &epoch, None, None, None, &mut w,
+                );
+            }
+
+            if bonds.is_none() && unbonds.is_none() {
+                writeln!(
+                    w,
+                    "No delegations found for {} to validator {}",
+                    names.annotate(&bond_id.source),
+                    names.annotate(&bond_id.validator)
+                )
+                .unwrap();
+            }
+        }
+        (None, Some(validator)) => {
+            let validator = ctx.get(&validator);
+            // Find validator's self-bonds
+            let bond_id = pos::BondId {
+                source: validator.clone(),
+                validator,
+            };
+            let bond_key = pos::bond_key(&bond_id);
+            let bonds =
+                query_storage_value::<pos::Bonds>(&client, &bond_key).await;
+            // Find validator's unbonded self-bonds
+            let unbond_key = pos::unbond_key(&bond_id);
+            let unbonds =
+                query_storage_value::<pos::Unbonds>(&client, &unbond_key).await;
+            // Find validator's slashes, if any
+            let slashes_key = pos::validator_slashes_key(&bond_id.validator);
+            let slashes =
+                query_storage_value::<pos::Slashes>(&client, &slashes_key)
+                    .await
+                    .unwrap_or_default();
+            let auto_compound = query_auto_compound(&client, &bond_id).await;
+
+            let stdout = io::stdout();
+            let mut w = stdout.lock();
+
+            if let Some(bonds) = &bonds {
+                writeln!(w, "Self-bonds:").unwrap();
+                process_bonds_query(
+                    bonds, &slashes, &epoch, None, None, None, &mut w,
+                );
+                print_auto_compound(auto_compound, &mut w);
+            }
+
+            if let Some(unbonds) = &unbonds {
+                writeln!(w, "Unbonded self-bonds:").unwrap();
+                process_unbonds_query(
+                    unbonds, &slashes, &epoch, None, None, None, &mut w,
+                );
+            }
+
+            if bonds.is_none() && unbonds.is_none() {
+                writeln!(
+                    w,
+                    "No self-bonds found for validator {}",
+                    names.annotate(&bond_id.validator)
+                )
+                .unwrap();
+            }
+        }
+        (Some(owner), None) => {
+            let owner = ctx.get(&owner);
+            // Find owner's bonds to any validator
+            let bonds_prefix = pos::bonds_for_source_prefix(&owner);
+            let bonds = query_storage_prefix::<pos::Bonds>(
+                client.clone(),
+                bonds_prefix,
+            )
+            .await;
+            // Find owner's unbonds to any validator
+            let unbonds_prefix = pos::unbonds_for_source_prefix(&owner);
+            let unbonds = query_storage_prefix::<pos::Unbonds>(
+                client.clone(),
+                unbonds_prefix,
+            )
+            .await;
+
+            let mut total: token::Amount = 0.into();
+            let mut total_active: token::Amount = 0.into();
+            let mut any_bonds = false;
+            if let Some(bonds) = bonds {
+                for (key, bonds) in bonds {
+                    match pos::is_bond_key(&key) {
+                        Some(bond_id) => {
+                            let pos::BondId { source, validator } = &bond_id;
+                            // Find validator's slashes, if any
+                            let slashes_key =
+                                pos::validator_slashes_key(validator);
+                            let slashes = query_storage_value::<pos::Slashes>(
+                                &client,
+                                &slashes_key,
+                            )
+                            .await
+                            .unwrap_or_default();
+                            let auto_compound =
+                                query_auto_compound(&client, &bond_id).await;
+
+                            let stdout = io::stdout();
+                            let mut w = stdout.lock();
+                            any_bonds = true;
+                            let bond_type: Cow<str> = if source == validator {
+                                "Self-bonds".into()
+                            } else {
+                                format!(
+                                    "Delegations from {} to {}",
+                                    names.annotate(source),
+                                    names.annotate(validator)
+                                )
+                                .into()
+                            };
+                            writeln!(w, "{}:", bond_type).unwrap();
+                            let (tot, tot_active) = process_bonds_query(
+                                &bonds,
+                                &slashes,
+                                &epoch,
+                                Some(&names.annotate(source)),
+                                Some(total),
+                                Some(total_active),
+                                &mut w,
+                            );
+                            print_auto_compound(auto_compound, &mut w);
+                            total = tot;
+                            total_active = tot_active;
+                        }
+                        None => {
+                            panic!("Unexpected storage key {}", key)
+                        }
+                    }
+                }
+            }
+            if total_active != 0.into() && total_active != total {
+                println!("Active bonds total: {}", total_active);
+            }
+
+            let mut total: token::Amount = 0.into();
+            let mut total_withdrawable: token::Amount = 0.into();
+            if let Some(unbonds) = unbonds {
+                for (key, unbonds) in unbonds {
+                    match pos::is_unbond_key(&key) {
+                        Some(pos::BondId { source, validator }) => {
+                            // Find validator's slashes, if any
+                            let slashes_key =
+                                pos::validator_slashes_key(&validator);
+                            let slashes = query_storage_value::<pos::Slashes>(
+                                &client,
+                                &slashes_key,
+                            )
+                            .await
+                            .unwrap_or_default();
+
+                            let stdout = io::stdout();
+                            let mut w = stdout.lock();
+                            any_bonds = true;
+                            let bond_type: Cow<str> = if source == validator {
+                                "Unbonded self-bonds".into()
+                            } else {
+                                format!(
+                                    "Unbonded delegations from {} to {}",
+                                    names.annotate(&source),
+                                    names.annotate(&validator)
+                                )
+                                .into()
+                            };
+                            writeln!(w, "{}:", bond_type).unwrap();
+                            let (tot, tot_withdrawable) = process_unbonds_query(
+                                &unbonds,
+                                &slashes,
+                                &epoch,
+                                Some(&names.annotate(&source)),
+                                Some(total),
+                                Some(total_withdrawable),
+                                &mut w,
+                            );
+                            total = tot;
+                            total_withdrawable = tot_withdrawable;
+                        }
+                        None => {
+                            panic!("Unexpected storage key {}", key)
+                        }
+                    }
+                }
+            }
+            if total_withdrawable != 0.into() {
+                println!("Withdrawable total: {}", total_withdrawable);
+            }
+
+            if !any_bonds {
+                println!(
+                    "No self-bonds or delegations found for {}",
+                    names.annotate(&owner)
+                );
+            }
+        }
+        (None, None) => {
+            // Find all the bonds
+            let bonds_prefix = pos::bonds_prefix();
+            let bonds = query_storage_prefix::<pos::Bonds>(
+                client.clone(),
+                bonds_prefix,
+            )
+            .await;
+            // Find all the unbonds
+            let unbonds_prefix = pos::unbonds_prefix();
+            let unbonds = query_storage_prefix::<pos::Unbonds>(
+                client.clone(),
+                unbonds_prefix,
+            )
+            .await;
+
+            let mut total: token::Amount = 0.into();
+            let mut total_active: token::Amount = 0.into();
+            if let Some(bonds) = bonds {
+                for (key, bonds) in bonds {
+                    match pos::is_bond_key(&key) {
+                        Some(bond_id) => {
+                            let pos::BondId { source, validator } = &bond_id;
+                            // Find validator's slashes, if any
+                            let slashes_key =
+                                pos::validator_slashes_key(validator);
+                            let slashes = query_storage_value::<pos::Slashes>(
+                                &client,
+                                &slashes_key,
+                            )
+                            .await
+                            .unwrap_or_default();
+                            let auto_compound =
+                                query_auto_compound(&client, &bond_id).await;
+
+                            let stdout = io::stdout();
+                            let mut w = stdout.lock();
+                            let bond_type = if source == validator {
+                                format!(
+                                    "Self-bonds for {}",
+                                    names.annotate(validator)
+                                )
+                            } else {
+                                format!(
+                                    "Delegations from {} to validator {}",
+                                    names.annotate(source),
+                                    names.annotate(validator)
+                                )
+                            };
+                            writeln!(w, "{}:", bond_type).unwrap();
+                            let (tot, tot_active) = process_bonds_query(
+                                &bonds,
+                                &slashes,
+                                &epoch,
+                                Some(&names.annotate(source)),
+                                Some(total),
+                                Some(total_active),
+                                &mut w,
+                            );
+                            print_auto_compound(auto_compound, &mut w);
+                            total = tot;
+                            total_active = tot_active;
+                        }
+                        None => {
+                            panic!("Unexpected storage key {}", key)
+                        }
+                    }
+                }
+            }
+            if total_active != 0.into() && total_active != total {
+                println!("Bond total active: {}", total_active);
+            }
+            println!("Bond total: {}", total);
+
+            let mut total: token::Amount = 0.into();
+            let mut total_withdrawable: token::Amount = 0.into();
+            if let Some(unbonds) = unbonds {
+                for (key, unbonds) in unbonds {
+                    match pos::is_unbond_key(&key) {
+                        Some(pos::BondId { source, validator }) => {
+                            // Find validator's slashes, if any
+                            let slashes_key =
+                                pos::validator_slashes_key(&validator);
+                            let slashes = query_storage_value::<pos::Slashes>(
+                                &client,
+                                &slashes_key,
+                            )
+                            .await
+                            .unwrap_or_default();
+
+                            let stdout = io::stdout();
+                            let mut w = stdout.lock();
+                            let bond_type = if source == validator {
+                                format!(
+                                    "Unbonded self-bonds for {}",
+                                    names.annotate(&validator)
+                                )
+                            } else {
+                                format!(
+                                    "Unbonded delegations from {} to \
+                                     validator {}",
+                                    names.annotate(&source),
+                                    names.annotate(&validator)
+                                )
+                            };
+                            writeln!(w, "{}:", bond_type).unwrap();
+                            let (tot, tot_withdrawable) = process_unbonds_query(
+                                &unbonds,
+                                &slashes,
+                                &epoch,
+                                Some(&names.annotate(&source)),
+                                Some(total),
+                                Some(total_withdrawable),
+                                &mut w,
+                            );
+                            total = tot;
+                            total_withdrawable = tot_withdrawable;
+                        }
+                        None => {
+                            panic!("Unexpected storage key {}", key)
+                        }
+                    }
+                }
+            }
+            if total_withdrawable != 0.into() {
+                println!("Withdrawable total: {}", total_withdrawable);
+            }
+            println!("Unbonded total: {}", total);
+        }
+    }
+}
+
+/// Query PoS voting power
+pub async fn query_voting_power(ctx: Context, args: args::QueryVotingPower) {
+    let epoch = match args.epoch {
+        Some(epoch) => epoch,
+        None => query_epoch(args.query.clone()).await,
+    };
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let names = AddressNames::new(&ctx.wallet);
+
+    // Find the validator set
+    let validator_set_key = pos::validator_set_key();
+    let validator_sets =
+        query_storage_value::<pos::ValidatorSets>(&client, &validator_set_key)
+            .await
+            .expect("Validator set should always be set");
+    let validator_set = validator_sets
+        .get(epoch)
+        .expect("Validator set should be always set in the current epoch");
+    match args.validator {
+        Some(validator) => {
+            let validator = ctx.get(&validator);
+            // Find voting power for the given validator
+            let voting_power_key = pos::validator_voting_power_key(&validator);
+            let voting_powers =
+                query_storage_value::<pos::ValidatorVotingPowers>(
+                    &client,
+                    &voting_power_key,
+                )
+                .await;
+            match voting_powers.and_then(|data| data.get(epoch)) {
+                Some(voting_power_delta) => {
+                    let voting_power: VotingPower =
+                        voting_power_delta.try_into().expect(
+                            "The sum voting power deltas shouldn't be negative",
+                        );
+                    let weighted = WeightedValidator {
+                        address: validator.clone(),
+                        voting_power,
+                    };
+                    let is_active = validator_set.active.contains(&weighted);
+                    if !is_active {
+                        debug_assert!(
+                            validator_set.inactive.contains(&weighted)
+                        );
+                    }
+                    println!(
+                        "Validator {} is {}, voting power: {}",
+                        names.annotate(&validator),
+                        if is_active { "active" } else { "inactive" },
+                        voting_power
+                    )
+                }
+                None => {
+                    println!(
+                        "No voting power found for {}",
+                        names.annotate(&validator)
+                    )
+                }
+            }
+        }
+        None => {
+            // Iterate all validators
+            let stdout = io::stdout();
+            let mut w = stdout.lock();
+
+            writeln!(w, "Active validators:").unwrap();
+            for active in &validator_set.active {
+                writeln!(
+                    w,
+                    "  {}: {}",
+                    names.annotate(&active.address),
+                    active.voting_power
+                )
+                .unwrap();
+            }
+            if !validator_set.inactive.is_empty() {
+                writeln!(w, "Inactive validators:").unwrap();
+                for inactive in &validator_set.inactive {
+                    writeln!(
+                        w,
+                        "  {}: {}",
+                        names.annotate(&inactive.address),
+                        inactive.voting_power
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+    let total_voting_power_key = pos::total_voting_power_key();
+    let total_voting_powers = query_storage_value::<pos::TotalVotingPowers>(
+        &client,
+        &total_voting_power_key,
+    )
+    .await
+    .expect("Total voting power should always be set");
+    let total_voting_power = total_voting_powers
+        .get(epoch)
+        .expect("Total voting power should be always set in the current epoch");
+    println!("Total voting power: {}", total_voting_power);
+}
+
+/// A validator's state, stake and voting power in an epoch
+#[derive(Debug, Serialize)]
+struct ValidatorInfo {
+    address: Address,
+    state: ValidatorStatus,
+    stake: token::Amount,
+    voting_power: u64,
+    /// The share of the total voting power in percent
+    voting_power_percentage: f64,
+}
+
+/// A validator's state with respect to the validator set
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ValidatorStatus {
+    Active,
+    Inactive,
+    Pending,
+    Jailed,
+}
+
+/// Query PoS validators with their state, stake and voting power
+pub async fn query_validators(ctx: Context, args: args::QueryValidators) {
+    let epoch = match args.epoch {
+        Some(epoch) => epoch,
+        None => query_epoch(args.query.clone()).await,
+    };
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+
+    let validator_set_key = pos::validator_set_key();
+    let validator_sets =
+        query_storage_value::<pos::ValidatorSets>(&client, &validator_set_key)
+            .await
+            .expect("Validator set should always be set");
+    let validator_set = validator_sets
+        .get(epoch)
+        .expect("Validator set should be always set in the current epoch");
+    let total_voting_power_key = pos::total_voting_power_key();
+    let total_voting_powers = query_storage_value::<pos::TotalVotingPowers>(
+        &client,
+        &total_voting_power_key,
+    )
+    .await
+    .expect("Total voting power should always be set");
+    let total_voting_power: VotingPower = total_voting_powers
+        .get(epoch)
+        .expect("Total voting power should be always set in the current epoch")
+        .try_into()
+        .expect("The sum voting power deltas shouldn't be negative");
+    let total_voting_power: u64 = total_voting_power.into();
+
+    // Find all the validators from their states, including the ones that are
+    // not in the validator set
+    let validator_states =
+        query_storage_prefix_bytes(client.clone(), pos::validators_prefix())
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|PrefixValue { key, value }| {
+                let validator = pos::is_validator_state_key(&key)?.clone();
+                let states =
+                    pos::ValidatorStates::try_from_slice(&value[..]).ok()?;
+                Some((validator, states))
+            });
+    let mut validators = vec![];
+    for (address, states) in validator_states {
+        let voting_power_key = pos::validator_voting_power_key(&address);
+        let voting_power: u64 =
+            query_storage_value::<pos::ValidatorVotingPowers>(
+                &client,
+                &voting_power_key,
+            )
+            .await
+            .and_then(|data| data.get(epoch))
+            .map(|delta| {
+                let voting_power: VotingPower = delta.try_into().expect(
+                    "The sum voting power deltas shouldn't be negative",
+                );
+                voting_power.into()
+            })
+            .unwrap_or_default();
+        let weighted = WeightedValidator {
+            address: address.clone(),
+            voting_power: voting_power.into(),
+        };
+        let state = match states.get(epoch) {
+            Some(ValidatorState::Jailed { .. }) => ValidatorStatus::Jailed,
+            Some(ValidatorState::Candidate)
+                if validator_set.active.contains(&weighted) =>
+            {
+                ValidatorStatus::Active
+            }
+            Some(ValidatorState::Candidate | ValidatorState::Inactive) => {
+                ValidatorStatus::Inactive
+            }
+            // A validator that becomes a candidate in a future epoch
+            Some(ValidatorState::Pending) | None => ValidatorStatus::Pending,
+        };
+        let stake = get_validator_stake(&client, epoch, &address).await;
+        let voting_power_percentage = if total_voting_power == 0 {
+            0.0
+        } else {
+            voting_power as f64 * 100.0 / total_voting_power as f64
+        };
+        validators.push(ValidatorInfo {
+            address,
+            state,
+            stake,
+            voting_power,
+            voting_power_percentage,
+        });
+    }
+    // Active validators first, each group by the voting power descending
+    validators.sort_by(|a, b| {
+        a.state
+            .cmp(&b.state)
+            .then(b.voting_power.cmp(&a.voting_power))
+    });
+
+    if ctx.global_args.output == args::OutputMode::Json {
+        let json = serde_json::json!({
+            "epoch": epoch,
+            "total_voting_power": total_voting_power,
+            "validators": validators,
+        });
+        println!("{}", json);
+        return;
+    }
+    let names = AddressNames::new(&ctx.wallet);
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    writeln!(w, "Validators in epoch {}:", epoch).unwrap();
+    for validator in &validators {
+        writeln!(
+            w,
+            "  {}: {:?}, stake: {}, voting power: {} ({:.2}%)",
+            names.annotate(&validator.address),
+            validator.state,
+            validator.stake,
+            validator.voting_power,
+            validator.voting_power_percentage
+        )
+        .unwrap();
+    }
+    writeln!(w, "Total voting power: {}", total_voting_power).unwrap();
+}
+
+/// A slash applied to a validator
+#[derive(Debug, Serialize)]
+struct SlashInfo {
+    validator: Address,
+    epoch: u64,
+    block_height: u64,
+    r#type: &'static str,
+    /// The slashed portion of the stake in basis points
+    rate: u64,
+}
+
+/// Query PoS slashes
+pub async fn query_slashes(ctx: Context, args: args::QuerySlashes) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let names = AddressNames::new(&ctx.wallet);
+    let validator = args.validator.map(|validator| ctx.get(&validator));
+    let mut slashes: Vec<(Address, Slash)> = match &validator {
+        // Find slashes for the given validator
+        Some(validator) => {
+            let slashes_key = pos::validator_slashes_key(validator);
+            query_storage_value::<pos::Slashes>(&client, &slashes_key)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|slash| (validator.clone(), slash))
+                .collect()
+        }
+        // Iterate slashes for all validators
+        None => {
+            let slashes_prefix = pos::slashes_prefix();
+            query_storage_prefix::<pos::Slashes>(client.clone(), slashes_prefix)
+                .await
+                .into_iter()
+                .flatten()
+                .filter_map(|(slashes_key, slashes)| {
+                    match is_validator_slashes_key(&slashes_key) {
+                        Some(validator) => Some((validator.clone(), slashes)),
+                        None => {
+                            eprintln!("Unexpected slashes key {}", slashes_key);
+                            None
+                        }
+                    }
+                })
+                .flat_map(|(validator, slashes)| {
+                    slashes
+                        .into_iter()
+                        .map(move |slash| (validator.clone(), slash))
+                })
+                .collect()
+        }
+    };
+    slashes.sort_by_key(|(_, slash)| (slash.epoch, slash.block_height));
+
+    if ctx.global_args.output == args::OutputMode::Json {
+        let slashes: Vec<SlashInfo> = slashes
+            .into_iter()
+            .map(|(validator, slash)| SlashInfo {
+                validator,
+                epoch: slash.epoch.into(),
+                block_height: slash.block_height,
+                r#type: match slash.r#type {
+                    SlashType::DuplicateVote => "duplicate_vote",
+                    SlashType::LightClientAttack => "light_client_attack",
+                },
+                rate: slash.rate.into(),
+            })
+            .collect();
+        println!("{}", serde_json::to_string(&slashes).unwrap());
+        return;
+    }
+    if slashes.is_empty() {
+        match &validator {
+            Some(validator) => {
+                println!("No slashes found for {}", names.annotate(validator))
+            }
+            None => println!("No slashes found"),
+        }
+        return;
+    }
+    let stdout = io::stdout();
+    let mut w = stdout.lock();
+    for (validator, slash) in slashes {
+        writeln!(
+            w,
+            "Slash epoch {}, block height {}, rate {}, type {}, validator {}",
+            slash.epoch,
+            slash.block_height,
+            slash.rate,
+            slash.r#type,
+            names.annotate(&validator),
+        )
+        .unwrap();
+    }
+}
+
+/// Query the unbonds of an owner that are waiting to be withdrawn, with a
+/// countdown to the epoch from which they can be withdrawn
+pub async fn query_unbonds(ctx: Context, args: args::QueryUnbonds) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let names = AddressNames::new(&ctx.wallet);
+    let owner = ctx.get(&args.owner);
+    let path = Path::unbonds(owner.clone());
+    let response = client
+        .abci_query(Some(path.into()), vec![], None, false)
+        .await
+        .unwrap();
+    let info = match response.code {
+        Code::Ok => match UnbondsInfo::try_from_slice(&response.value[..]) {
+            Ok(info) => info,
+            Err(err) => {
+                eprintln!("Error decoding the unbonds: {}", err);
+                cli::safe_exit(1)
+            }
+        },
+        Code::Err(err) => {
+            eprintln!(
+                "Error in the query {} (error code {})",
+                response.info, err
+            );
+            cli::safe_exit(1)
+        }
+    };
+    println!("Last committed epoch: {}", info.epoch);
+    if info.unbonds.is_empty() {
+        println!("No unbonds found for {}", names.annotate(&owner));
+        return;
+    }
+    let now = DateTimeUtc::now();
+    let mut withdrawable = token::Amount::default();
+    for unbond in &info.unbonds {
+        let epochs = info.epochs_until_withdrawable(unbond);
+        let countdown = if epochs == 0 {
+            withdrawable += unbond.amount;
+            "withdrawable now".to_owned()
+        } else {
+            format_countdown(epochs, info.time_until_withdrawable(unbond, now))
+        };
+        println!(
+            "{} from {} (active from epoch {}), withdrawable from epoch {}: {}",
+            unbond.amount,
+            names.annotate(&unbond.validator),
+            unbond.bond_start,
+            unbond.withdrawable_epoch,
+            countdown
+        );
+    }
+    println!("Withdrawable total: {}", withdrawable);
+}
+
+/// Format the countdown until unbonded tokens can be withdrawn, e.g.
+/// "withdrawable in 3 epochs, ~21h"
+fn format_countdown(epochs: u64, time: Duration) -> String {
+    let epochs = match epochs {
+        1 => "1 epoch".to_owned(),
+        epochs => format!("{} epochs", epochs),
+    };
+    let minutes = (time.as_secs() + 59) / 60;
+    if minutes < 60 {
+        format!("withdrawable in {}, ~{}min", epochs, minutes)
+    } else {
+        format!("withdrawable in {}, ~{}h", epochs, (minutes + 59) / 60)
+    }
+}
+
+/// Check if the given address is a known validator.
+pub async fn is_validator(
+    address: &Address,
+    ledger_address: TendermintAddress,
+) -> bool {
+    let client = HttpClient::new(ledger_address).unwrap();
+    let key = pos::validator_state_key(address);
+    let state: Option<pos::ValidatorStates> =
+        query_storage_value(&client, &key).await;
+    state.is_some()
+}
+
+/// Check if a given address is a known delegator
+pub async fn is_delegator(
+    address: &Address,
+    ledger_address: TendermintAddress,
+) -> bool {
+    let client = HttpClient::new(ledger_address).unwrap();
+    let bonds_prefix = pos::bonds_for_source_prefix(address);
+    let bonds =
+        query_storage_prefix::<pos::Bonds>(client.clone(), bonds_prefix).await;
+    bonds.is_some() && bonds.unwrap().count() > 0
+}
+
+pub async fn is_delegator_at(
+    client: &HttpClient,
+    address: &Address,
+    epoch: Epoch,
+) -> bool {
+    let key = pos::bonds_for_source_prefix(address);
+    let bonds_iter =
+        query_storage_prefix::<pos::Bonds>(client.clone(), key).await;
+    if let Some(mut bonds) = bonds_iter {
+        bonds.any(|(_, bond)| bond.get(epoch).is_some())
+    } else {
+        false
+    }
+}
+
+pub async fn get_bond_amount_at(
+    client: &HttpClient,
+    delegator: &Address,
+    validator: &Address,
+    epoch: Epoch,
+) -> Option<token::Amount> {
+    let slashes_key = pos::validator_slashes_key(validator);
+    let slashes = query_storage_value::<pos::Slashes>(client, &slashes_key)
+        .await
+        .unwrap_or_default();
+    let bond_key = pos::bond_key(&BondId {
+        source: delegator.clone(),
+        validator: validator.clone(),
+    });
+    let epoched_bonds = query_storage_value::<Bonds>(client, &bond_key).await;
+    match epoched_bonds {
+        Some(epoched_bonds) => {
+            let mut delegated_amount: token::Amount = 0.into();
+            for bond in epoched_bonds.iter() {
+                for (epoch_start, &(mut delta)) in bond.deltas.iter().sorted() {
+                    delta = apply_slashes(
+                        &slashes,
+                        delta,
+                        *epoch_start,
+                        None,
+                        None,
+                    );
+                    let epoch_start: Epoch = (*epoch_start).into();
+                    if epoch >= epoch_start {
+                        delegated_amount += delta;
+                    }
+                }
+            }
+            Some(delegated_amount)
+        }
+        None => None,
+    }
+}
+
+pub async fn get_all_validators(
+    client: &HttpClient,
+    epoch: Epoch,
+) -> Vec<Address> {
+    let validator_set_key = pos::validator_set_key();
+    let validator_sets =
+        query_storage_value::<pos::ValidatorSets>(client, &validator_set_key)
+            .await
+            .expect("Validator set should always be set");
+    let validator_set = validator_sets
+        .get(epoch)
+        .expect("Validator set should be always set in the current epoch");
+    let all_validators = validator_set.active.union(&validator_set.inactive);
+    all_validators
+        .map(|validator| validator.address.clone())
+        .collect()
+}
+
+pub async fn get_total_staked_tokes(
+    client: &HttpClient,
+    epoch: Epoch,
+    validators: &[Address],
+) -> token::Amount {
+    let mut total = Amount::from(0);
+
+    for validator in validators {
+        total += get_validator_stake(client, epoch, validator).await;
+    }
+    total
+}
+
+/// Get the stake of a validator at the given epoch
+pub async fn get_validator_stake(
+    client: &HttpClient,
+    epoch: Epoch,
+    validator: &Address,
+) -> token::Amount {
+    let total_voting_power_key = pos::validator_total_deltas_key(validator);
+    let total_voting_power = query_storage_value::<pos::ValidatorTotalDeltas>(
+        client,
+        &total_voting_power_key,
+    )
+    .await
+    .expect("Total deltas should be defined");
+    let epoched_total_voting_power = total_voting_power.get(epoch);
+    if let Some(epoched_total_voting_power) = epoched_total_voting_power {
+        token::Amount::from_change(epoched_total_voting_power)
+    } else {
+        token::Amount::from(0)
+    }
+}
+
+pub async fn get_delegators_delegation(
+    client: &HttpClient,
+    address: &Address,
+    _epoch: Epoch,
+) -> Vec<Address> {
+    let key = pos::bonds_for_source_prefix(address);
+    let bonds_iter =
+        query_storage_prefix::<pos::Bonds>(client.clone(), key).await;
+
+    let mut delegation_addresses: Vec<Address> = Vec::new();
+    if let Some(bonds) = bonds_iter {
+        for (key, _epoched_amount) in bonds {
+            let validator_address = pos::get_validator_address_from_bond(&key)
+                .expect("Delegation key should contain validator address.");
+            delegation_addresses.push(validator_address);
+        }
+    }
+    delegation_addresses
+}
+
+/// Get the proof-of-stake parameters
+pub async fn get_params(client: &HttpClient) -> Option<PosParams> {
+    let key = pos::params_key();
+    query_storage_value::<PosParams>(client, &key).await
+}
+
+/// Accumulate slashes starting from `epoch_start` until (optionally)
+/// `withdraw_epoch` and apply them to the token amount `delta`.
+fn apply_slashes(
+    slashes: &[Slash],
+    mut delta: token::Amount,
+    epoch_start: PosEpoch,
+    withdraw_epoch: Option<PosEpoch>,
+    mut w: Option<&mut std::io::StdoutLock>,
+) -> token::Amount {
+    let mut slashed = token::Amount::default();
+    for slash in slashes {
+        if slash.epoch >= epoch_start
+            && slash.epoch < withdraw_epoch.unwrap_or_else(|| u64::MAX.into())
+        {
+            if let Some(w) = w.as_mut() {
+                writeln!(
+                    *w,
+                    "    ⚠ Slash: {} from epoch {}",
+                    slash.rate, slash.epoch
+                )
+                .unwrap();
+            }
+            let raw_delta: u64 = delta.into();
+            let current_slashed = token::Amount::from(slash.rate * raw_delta);
+            slashed += current_slashed;
+            delta -= current_slashed;
+        }
+    }
+    if let Some(w) = w.as_mut() {
+        if slashed != 0.into() {
+            writeln!(*w, "    ⚠ Slash total: {}", slashed).unwrap();
+            writeln!(*w, "    ⚠ After slashing: Δ {}", delta).unwrap();
+        }
+    }
+    delta
+}
+
+/// Query whether the rewards of a bond are re-bonded to its validator at the
+/// end of each epoch
+async fn query_auto_compound(client: &HttpClient, bond_id: &BondId) -> bool {
+    let key = pos::bond_auto_compound_key(bond_id);
+    query_storage_value::<bool>(client, &key)
+        .await
+        .unwrap_or_default()
+}
+
+/// Print that the rewards of a bond are re-bonded automatically, if they are
+fn print_auto_compound(auto_compound: bool, w: &mut std::io::StdoutLock) {
+    if auto_compound {
+        writeln!(w, "  Rewards are re-bonded every epoch (auto-compounding)")
+            .unwrap();
+    }
+}
+
+/// Process the result of a blonds query to determine total bonds
+/// and total active bonds. This includes taking into account
+/// an aggregation of slashes since the start of the given epoch.
+fn process_bonds_query(
+    bonds: &Bonds,
+    slashes: &[Slash],
+    epoch: &Epoch,
+    source: Option<&str>,
+    total: Option<token::Amount>,
+    total_active: Option<token::Amount>,
+    w: &mut std::io::StdoutLock,
+) -> (token::Amount, token::Amount) {
+    let mut total_active = total_active.unwrap_or_else(|| 0.into());
+    let mut current_total: token::Amount = 0.into();
+    for bond in bonds.iter() {
+        for (epoch_start, &(mut delta)) in bond.deltas.iter().sorted() {
+            writeln!(w, "  Active from epoch {}: Δ {}", epoch_start, delta)
+                .unwrap();
+            delta = apply_slashes(slashes, delta, *epoch_start, None, Some(w));
+            current_total += delta;
+            let epoch_start: Epoch = (*epoch_start).into();
+            if epoch >= &epoch_start {
+                total_active += delta;
+            }
+        }
+    }
+    let total = total.unwrap_or_else(|| 0.into()) + current_total;
+    match source {
+        Some(addr) => {
+            writeln!(w, "  Bonded total from {}: {}", addr, current_total)
+                .unwrap();
+        }
+        None => {
+            if total_active != 0.into() && total_active != total {
+                writeln!(w, "Active bonds total: {}", total_active).unwrap();
+            }
+            writeln!(w, "Bonds total: {}", total).unwrap();
+        }
+    }
+    (total, total_active)
+}
+
+/// Process the result of an unbonds query to determine total bonds
+/// and total withdrawable bonds. This includes taking into account
+/// an aggregation of slashes since the start of the given epoch up
+/// until the withdrawal epoch.
+fn process_unbonds_query(
+    unbonds: &Unbonds,
+    slashes: &[Slash],
+    epoch: &Epoch,
+    source: Option<&str>,
+    total: Option<token::Amount>,
+    total_withdrawable: Option<token::Amount>,
+    w: &mut std::io::StdoutLock,
+) -> (token::Amount, token::Amount) {
+    let mut withdrawable = total_withdrawable.unwrap_or_else(|| 0.into());
+    let mut current_total: token::Amount = 0.into();
+    let mut current_withdrawable: token::Amount = 0.into();
+    for deltas in unbonds.iter() {
+        for ((epoch_start, epoch_end), &(mut delta)) in
+            deltas.deltas.iter().sorted()
+        {
+            let withdraw_epoch = *epoch_end + 1_u64;
+            writeln!(
+                w,
+                "  Withdrawable from epoch {} (active from {}): Δ {}",
+                withdraw_epoch, epoch_start, delta
+            )
+            .unwrap();
+            delta = apply_slashes(
+                slashes,
+                delta,
+                *epoch_start,
+                Some(withdraw_epoch),
+                Some(w),
+            );
+            current_total += delta;
+            let epoch_end: Epoch = (*epoch_end).into();
+            if epoch > &epoch_end {
+                current_withdrawable += delta;
+            }
+        }
+    }
+    let total = total.unwrap_or_else(|| 0.into()) + current_total;
+    withdrawable += current_withdrawable;
+    match source {
+        Some(addr) => {
+            writeln!(w, "  Unbonded total from {}: {}", addr, current_total)
+                .unwrap();
+            writeln!(
+                w,
+                "  Withdrawable now from {}: {}",
+                addr, current_withdrawable
+            )
+            .unwrap();
+        }
+        None => {
+            if withdrawable != 0.into() {
+                writeln!(w, "Withdrawable total: {}", withdrawable).unwrap();
+            }
+            writeln!(w, "Unbonded total: {}", total).unwrap();
+        }
+    }
+    (total, withdrawable)
+}
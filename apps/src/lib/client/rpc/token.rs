@@ -0,0 +1,134 @@
+//! Client RPC queries of the token balances
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use anoma::types::address::{self, Address};
+use anoma::types::token::{self, balance_key, Amount};
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::HttpClient;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::HttpClient;
+
+use super::{query_storage_prefix, query_storage_value};
+use crate::cli::{args, Context};
+use crate::client::names::AddressNames;
+
+/// Query token balance(s)
+pub async fn query_balance(ctx: Context, args: args::QueryBalance) {
+    let client = HttpClient::new(args.query.ledger_address).unwrap();
+    let tokens = address::tokens();
+    let names = AddressNames::new(&ctx.wallet);
+    match (args.token, args.owner) {
+        (Some(token), Some(owner)) => {
+            let token = ctx.get(&token);
+            let owner = ctx.get(&owner);
+            let key = token::balance_key(&token, &owner);
+            let currency_code = tokens
+                .get(&token)
+                .map(|c| Cow::Borrowed(*c))
+                .unwrap_or_else(|| Cow::Owned(token.to_string()));
+            match query_storage_value::<token::Amount>(&client, &key).await {
+                Some(balance) => {
+                    println!("{}: {}", currency_code, balance);
+                }
+                None => {
+                    println!(
+                        "No {} balance found for {}",
+                        currency_code,
+                        names.annotate(&owner)
+                    )
+                }
+            }
+        }
+        (None, Some(owner)) => {
+            let owner = ctx.get(&owner);
+            let mut found_any = false;
+            for (token, currency_code) in tokens {
+                let key = token::balance_key(&token, &owner);
+                if let Some(balance) =
+                    query_storage_value::<token::Amount>(&client, &key).await
+                {
+                    println!("{}: {}", currency_code, balance);
+                    found_any = true;
+                }
+            }
+            if !found_any {
+                println!("No balance found for {}", names.annotate(&owner));
+            }
+        }
+        (Some(token), None) => {
+            let token = ctx.get(&token);
+            let key = token::balance_prefix(&token);
+            let balances =
+                query_storage_prefix::<token::Amount>(client, key).await;
+            match balances {
+                Some(balances) => {
+                    let currency_code = tokens
+                        .get(&token)
+                        .map(|c| Cow::Borrowed(*c))
+                        .unwrap_or_else(|| Cow::Owned(token.to_string()));
+                    let stdout = io::stdout();
+                    let mut w = stdout.lock();
+                    writeln!(w, "Token {}:", currency_code).unwrap();
+                    for (key, balance) in balances {
+                        let owner =
+                            token::is_any_token_balance_key(&key).unwrap();
+                        writeln!(
+                            w,
+                            "  {}, owned by {}",
+                            balance,
+                            names.annotate(owner)
+                        )
+                        .unwrap();
+                    }
+                }
+                None => {
+                    println!("No balances for token {}", names.annotate(&token))
+                }
+            }
+        }
+        (None, None) => {
+            let stdout = io::stdout();
+            let mut w = stdout.lock();
+            for (token, currency_code) in tokens {
+                let key = token::balance_prefix(&token);
+                let balances =
+                    query_storage_prefix::<token::Amount>(client.clone(), key)
+                        .await;
+                match balances {
+                    Some(balances) => {
+                        writeln!(w, "Token {}:", currency_code).unwrap();
+                        for (key, balance) in balances {
+                            let owner =
+                                token::is_any_token_balance_key(&key).unwrap();
+                            writeln!(
+                                w,
+                                "  {}, owned by {}",
+                                balance,
+                                names.annotate(owner)
+                            )
+                            .unwrap();
+                        }
+                    }
+                    None => {
+                        println!(
+                            "No balances for token {}",
+                            names.annotate(&token)
+                        )
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Query token amount of owner.
+pub async fn get_token_balance(
+    client: &HttpClient,
+    token: &Address,
+    owner: &Address,
+) -> Option<Amount> {
+    let balance_key = balance_key(token, owner);
+    query_storage_value(client, &balance_key).await
+}
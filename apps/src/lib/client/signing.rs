@@ -7,18 +7,23 @@ use anoma::proto::Tx;
 use anoma::types::address::{Address, ImplicitAddress};
 use anoma::types::key::*;
 use anoma::types::storage::Epoch;
-use anoma::types::transaction::{hash_tx, Fee, WrapperTx};
+use anoma::types::token;
+use anoma::types::transaction::{hash_tx, Fee, GasLimit, WrapperTx};
 use borsh::BorshSerialize;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
 use tendermint_config_abci::net::Address as TendermintAddress;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::HttpClient;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::HttpClient;
 
 use super::rpc;
 use crate::cli::context::WalletAddress;
 use crate::cli::{self, args, Context};
 use crate::client::tendermint_rpc_types::TxBroadcastData;
-use crate::wallet::Wallet;
+use crate::wallet::{Signer, Wallet};
 
 /// Find the public key for the given address and try to load the keypair
 /// for it from the wallet. Panics if the key cannot be found or loaded.
@@ -29,7 +34,7 @@ pub async fn find_keypair(
 ) -> Rc<common::SecretKey> {
     match addr {
         Address::Established(_) => {
-            println!(
+            eprintln!(
                 "Looking-up public key of {} from the ledger...",
                 addr.encode()
             );
@@ -72,43 +77,158 @@ pub async fn find_keypair(
     }
 }
 
+/// Find the public key for the given address and the signer for it in the
+/// wallet. A key that is stored encrypted is used through the wallet agent,
+/// if it's running, so that it doesn't have to be decrypted. Exits if the key
+/// cannot be found or loaded.
+pub async fn find_signer(
+    wallet: &mut Wallet,
+    addr: &Address,
+    ledger_address: TendermintAddress,
+) -> Signer {
+    let public_key = match addr {
+        Address::Established(_) => {
+            eprintln!(
+                "Looking-up public key of {} from the ledger...",
+                addr.encode()
+            );
+            let public_key =
+                rpc::get_public_key(addr, ledger_address.clone()).await;
+            if public_key.is_none() {
+                eprintln!(
+                    "No public key found for the address {}",
+                    addr.encode()
+                );
+                cli::safe_exit(1);
+            }
+            public_key
+        }
+        // The public key of an implicit address is only known once it has
+        // been revealed on chain
+        Address::Implicit(_) => {
+            rpc::get_public_key(addr, ledger_address.clone()).await
+        }
+        Address::Internal(_) => None,
+    };
+    match public_key {
+        Some(public_key) => {
+            wallet.find_signer_by_pk(&public_key).unwrap_or_else(|err| {
+                eprintln!(
+                    "Unable to load the keypair from the wallet for public \
+                     key {}. Failed with: {}",
+                    public_key, err
+                );
+                cli::safe_exit(1)
+            })
+        }
+        None => find_keypair(wallet, addr, ledger_address).await.into(),
+    }
+}
+
+/// Sign the data with the signer's key. Exits if the key cannot be loaded.
+pub fn sign(
+    wallet: &mut Wallet,
+    signer: &Signer,
+    data: &[u8],
+) -> common::Signature {
+    wallet.sign(signer, data).unwrap_or_else(|err| {
+        eprintln!(
+            "Unable to sign with the key of the public key {}. Failed with: {}",
+            signer.public_key(),
+            err
+        );
+        cli::safe_exit(1)
+    })
+}
+
+/// Find the signer of a transaction, given by the signing key or by the
+/// public key of the signer. If no explicit signer given, use the `default`.
+/// Returns `None` if there's neither.
+pub async fn find_signing_key(
+    ctx: &mut Context,
+    args: &args::Tx,
+    default: Option<&WalletAddress>,
+) -> Option<Signer> {
+    if let Some(signing_key) = &args.signing_key {
+        Some(ctx.get_cached(signing_key).into())
+    } else if let Some(signer) = args.signer.as_ref().or(default) {
+        let signer = ctx.get(signer);
+        Some(
+            find_signer(&mut ctx.wallet, &signer, args.ledger_address.clone())
+                .await,
+        )
+    } else {
+        None
+    }
+}
+
 /// Sign a transaction with a given signing key or public key of a given signer.
 /// If no explicit signer given, use the `default`. If no `default` is given,
 /// panics.
 ///
-/// If this is not a dry run, the tx is put in a wrapper and returned along with
-/// hashes needed for monitoring the tx on chain.
-///
-/// If it is a dry run, it is not put in a wrapper, but returned as is.
+/// The signed tx is then prepared for broadcasting with [`wrap_tx`].
 pub async fn sign_tx(
     mut ctx: Context,
     tx: Tx,
     args: &args::Tx,
     default: Option<&WalletAddress>,
 ) -> (Context, TxBroadcastData) {
-    let (tx, keypair) = if let Some(signing_key) = &args.signing_key {
-        let signing_key = ctx.get_cached(signing_key);
-        (tx.sign(&signing_key), signing_key)
-    } else if let Some(signer) = args.signer.as_ref().or(default) {
-        let signer = ctx.get(signer);
-        let signing_key =
-            find_keypair(&mut ctx.wallet, &signer, args.ledger_address.clone())
-                .await;
-        (tx.sign(&signing_key), signing_key)
-    } else {
-        panic!(
-            "All transactions must be signed; please either specify the key \
-             or the address from which to look up the signing key."
-        );
+    let signer = find_signing_key(&mut ctx, args, default)
+        .await
+        .unwrap_or_else(|| {
+            panic!(
+                "All transactions must be signed; please either specify the \
+                 key or the address from which to look up the signing key."
+            )
+        });
+    let tx = tx.sign_with(|to_sign| sign(&mut ctx.wallet, &signer, to_sign));
+    wrap_tx(ctx, tx, args, signer).await
+}
+
+/// Prepare a signed transaction for broadcasting.
+///
+/// If this is not a dry run, the tx is put in a wrapper signed by the given
+/// signer and returned along with hashes needed for monitoring the tx on
+/// chain.
+///
+/// If it is a dry run or a gas estimation, it is not put in a wrapper, but
+/// returned as is.
+///
+/// With the `auto_gas_limit` argument, the wrapper's gas limit is set from the
+/// gas used in a dry run of the tx plus the `gas_margin`.
+///
+/// With the `fee_payer` argument, the wrapper is signed by the fee payer's key
+/// instead of the given signer's key, so the fee is paid by the fee payer.
+pub async fn wrap_tx(
+    mut ctx: Context,
+    tx: Tx,
+    args: &args::Tx,
+    signer: Signer,
+) -> (Context, TxBroadcastData) {
+    // The wrapper's key determines the implicit account that pays the fee
+    let signer: Signer = match &args.fee_payer {
+        Some(fee_payer) => ctx.get_cached(fee_payer).into(),
+        None => signer,
     };
-    let epoch = rpc::query_epoch(args::Query {
-        ledger_address: args.ledger_address.clone(),
-    })
-    .await;
-    let broadcast_data = if args.dry_run {
+    let epoch = rpc::query_last_epoch(args.ledger_address.clone()).await;
+    let broadcast_data = if args.dry_run || args.estimate_gas {
         TxBroadcastData::DryRun(tx)
+    } else if args.auto_gas_limit {
+        let gas_used =
+            rpc::estimate_gas(&args.ledger_address, tx.to_bytes()).await;
+        let gas_limit = gas_limit_with_margin(gas_used, args.gas_margin);
+        eprintln!(
+            "Estimated gas: {}, setting the gas limit to {}.",
+            gas_used,
+            u64::from(&gas_limit)
+        );
+        let args = args::Tx {
+            gas_limit,
+            ..args.clone()
+        };
+        sign_wrapper(&mut ctx, &args, epoch, tx, &signer).await
     } else {
-        sign_wrapper(&ctx, args, epoch, tx, &keypair).await
+        sign_wrapper(&mut ctx, args, epoch, tx, &signer).await
     };
     (ctx, broadcast_data)
 }
@@ -116,20 +236,25 @@ pub async fn sign_tx(
 /// Create a wrapper tx from a normal tx. Get the hash of the
 /// wrapper and its payload which is needed for monitoring its
 /// progress on chain.
+///
+/// The fee is paid by the implicit account of the given signer's key, which is
+/// checked to have sufficient balance.
 pub async fn sign_wrapper(
-    ctx: &Context,
+    ctx: &mut Context,
     args: &args::Tx,
     epoch: Epoch,
     tx: Tx,
-    keypair: &common::SecretKey,
+    signer: &Signer,
 ) -> TxBroadcastData {
+    let pk = signer.public_key();
+    check_fee_balance(ctx, args, &pk).await;
     let tx = {
-        WrapperTx::new(
+        WrapperTx::new_with_pk(
             Fee {
                 amount: args.fee_amount,
                 token: ctx.get(&args.fee_token),
             },
-            keypair,
+            pk,
             epoch,
             args.gas_limit.clone(),
             tx,
@@ -152,10 +277,48 @@ pub async fn sign_wrapper(
         None
     };
     TxBroadcastData::Wrapper {
-        tx: tx
-            .sign(keypair)
-            .expect("Wrapper tx signing keypair should be correct"),
+        tx: tx.sign_with(args.expiration, |to_sign| {
+            sign(&mut ctx.wallet, signer, to_sign)
+        }),
         wrapper_hash,
         decrypted_hash,
     }
 }
+
+/// Check that the implicit account of the fee payer has sufficient balance to
+/// pay the fee. Exits if it doesn't, unless forced.
+async fn check_fee_balance(
+    ctx: &Context,
+    args: &args::Tx,
+    fee_payer: &common::PublicKey,
+) {
+    if args.fee_amount == token::Amount::default() {
+        return;
+    }
+    let fee_payer = Address::from(fee_payer);
+    let token = ctx.get(&args.fee_token);
+    let client = HttpClient::new(args.ledger_address.clone()).unwrap();
+    let balance = rpc::token::get_token_balance(&client, &token, &fee_payer)
+        .await
+        .unwrap_or_default();
+    if balance < args.fee_amount {
+        eprintln!(
+            "The fee payer {} doesn't have sufficient balance of token {} to \
+             pay the fee of {}. Its balance is {}.",
+            fee_payer.encode(),
+            token.encode(),
+            args.fee_amount,
+            balance
+        );
+        if !args.force {
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// Add a safety margin in percent to the estimated gas. The resulting gas
+/// limit is rounded up to the gas limit resolution.
+fn gas_limit_with_margin(gas_used: u64, margin_percent: u64) -> GasLimit {
+    let margin = gas_used.saturating_mul(margin_percent) / 100;
+    GasLimit::from(gas_used.saturating_add(margin))
+}
@@ -1,12 +1,26 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+
 use anoma::proto::Tx;
 use anoma::types::address::Address;
+use anoma::types::transaction::TxResult;
 use jsonpath_lib as jsonpath;
 use serde::Serialize;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::endpoint::broadcast::tx_sync::Response;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::endpoint::tx::Response as TxQueryResponse;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::endpoint::broadcast::tx_sync::Response;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::endpoint::tx::Response as TxQueryResponse;
 use thiserror::Error;
 
 use crate::cli::safe_exit;
 #[cfg(not(feature = "ABCI"))]
 use crate::node::ledger::events::Attributes;
+pub use crate::node::ledger::events::TxResponse;
 
 /// Errors from interacting with Tendermint's jsonrpc endpoint
 #[derive(Error, Debug)]
@@ -44,16 +58,216 @@ pub enum TxBroadcastData {
     },
 }
 
-/// A parsed event from tendermint relating to a transaction
+/// The result of broadcasting a tx. The available information depends on the
+/// broadcast mode.
 #[derive(Debug, Serialize)]
-pub struct TxResponse {
-    pub info: String,
+#[serde(tag = "mode", rename_all = "kebab-case")]
+pub enum TxBroadcastResult {
+    /// The tx has been dry-run without committing any changes
+    DryRun(DryRunResult),
+    /// The gas of the tx has been estimated without committing any changes
+    EstimateGas { gas_used: u64 },
+    /// The tx has been sent without waiting for any result, so only its
+    /// hashes are known. The inner tx hash is only known for wrapped txs.
+    Async {
+        hash: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        inner_hash: Option<String>,
+    },
+    /// The response from checking the tx before it's added to the mempool
+    Sync {
+        hash: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        inner_hash: Option<String>,
+        response: Response,
+    },
+    /// The tx had already been received by the ledger when its broadcast was
+    /// retried, so the response from checking it was lost. If the tx has
+    /// already been included in a block, its result is known.
+    Received {
+        hash: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        inner_hash: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        included: Option<TxQueryResponse>,
+    },
+    /// The tx has been applied on chain
+    Commit(TxResponse),
+    /// The tx has been saved as a draft without being broadcast
+    Draft { path: PathBuf, signed: bool },
+}
+
+impl TxBroadcastResult {
+    /// Addresses of the accounts initialized by the tx. These are only known
+    /// once the tx has been applied, so this is always empty in dry run.
+    pub fn initialized_accounts(&self) -> Vec<Address> {
+        match self {
+            Self::Commit(response) => response.initialized_accounts.clone(),
+            Self::DryRun(_)
+            | Self::EstimateGas { .. }
+            | Self::Async { .. }
+            | Self::Sync { .. }
+            | Self::Received { .. }
+            | Self::Draft { .. } => vec![],
+        }
+    }
+}
+
+/// The result of a dry run of a tx, simulated by the ledger without
+/// committing any changes
+#[derive(Debug, Default, Serialize)]
+pub struct DryRunResult {
+    /// The code of the dry run query response, `0` on success
+    pub code: u32,
+    /// The error message if the dry run failed
     pub log: String,
-    pub height: String,
-    pub hash: String,
-    pub code: String,
-    pub gas_used: String,
+    /// Whether the tx has been accepted by all the triggered VPs
+    pub accepted: bool,
+    /// Total gas used by the tx, including the VPs
+    pub gas_used: u64,
+    /// Storage keys written by the tx
+    pub changed_keys: Vec<String>,
+    /// The addresses whose VPs accepted the tx
+    pub accepted_vps: Vec<Address>,
+    /// The addresses whose VPs rejected the tx
+    pub rejected_vps: Vec<Address>,
+    /// Errors that occurred in the VPs
+    pub vp_errors: Vec<DryRunVpError>,
+    /// New established addresses created by the tx
     pub initialized_accounts: Vec<Address>,
+    /// The events emitted by the tx
+    pub events: Vec<DryRunEvent>,
+}
+
+/// An error that occurred in a VP during a dry run
+#[derive(Debug, Serialize)]
+pub struct DryRunVpError {
+    pub address: Address,
+    pub error: String,
+}
+
+/// An event emitted by a tx during a dry run
+#[derive(Debug, Serialize)]
+pub struct DryRunEvent {
+    pub event_type: String,
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl DryRunResult {
+    /// The result of a dry run that failed with the given query response code
+    /// and error message
+    pub fn failed(code: u32, log: impl Into<String>) -> Self {
+        Self {
+            code,
+            log: log.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<TxResult> for DryRunResult {
+    fn from(result: TxResult) -> Self {
+        Self {
+            code: 0,
+            log: String::new(),
+            accepted: result.is_accepted(),
+            gas_used: result.gas_used,
+            changed_keys: result
+                .changed_keys
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+            accepted_vps: result.vps_result.accepted_vps.into_iter().collect(),
+            rejected_vps: result.vps_result.rejected_vps.into_iter().collect(),
+            vp_errors: result
+                .vps_result
+                .errors
+                .into_iter()
+                .map(|(address, error)| DryRunVpError { address, error })
+                .collect(),
+            initialized_accounts: result.initialized_accounts,
+            events: result
+                .ibc_event
+                .into_iter()
+                .map(|event| DryRunEvent {
+                    event_type: event.event_type,
+                    attributes: event.attributes.into_iter().collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Display for DryRunResult {
+    /// Render the result as a table with a row per field. Fields with
+    /// multiple values span multiple lines.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn row(
+            f: &mut fmt::Formatter<'_>,
+            label: &str,
+            values: impl IntoIterator<Item = String>,
+        ) -> fmt::Result {
+            let mut values = values.into_iter().peekable();
+            if values.peek().is_none() {
+                return writeln!(f, "{:<22}-", label);
+            }
+            for (ix, value) in values.enumerate() {
+                let label = if ix == 0 { label } else { "" };
+                writeln!(f, "{:<22}{}", label, value)?;
+            }
+            Ok(())
+        }
+
+        let status = if self.code != 0 {
+            "failed"
+        } else if self.accepted {
+            "accepted"
+        } else {
+            "rejected"
+        };
+        row(f, "Status", [status.to_string()])?;
+        row(f, "Code", [self.code.to_string()])?;
+        if self.code != 0 {
+            return row(f, "Error", [self.log.clone()]);
+        }
+        row(f, "Gas used", [self.gas_used.to_string()])?;
+        row(f, "Changed keys", self.changed_keys.iter().cloned())?;
+        row(
+            f,
+            "Accepted VPs",
+            self.accepted_vps.iter().map(Address::encode),
+        )?;
+        row(
+            f,
+            "Rejected VPs",
+            self.rejected_vps.iter().map(Address::encode),
+        )?;
+        row(
+            f,
+            "VP errors",
+            self.vp_errors
+                .iter()
+                .map(|err| format!("{}: {}", err.address, err.error)),
+        )?;
+        row(
+            f,
+            "Initialized accounts",
+            self.initialized_accounts.iter().map(Address::encode),
+        )?;
+        row(
+            f,
+            "Events",
+            self.events.iter().map(|event| {
+                let attributes = event
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{} ({})", event.event_type, attributes)
+            }),
+        )
+    }
 }
 
 impl TxResponse {
@@ -122,6 +336,19 @@ impl TxResponse {
             }
             _ => vec![],
         };
+        let batch_results = selector(&format!(
+            "$.events.['{}.batch_results'][{}]",
+            evt_key, index
+        ));
+        let batch_results = match batch_results {
+            // Encoded the same way as the initialized accounts
+            Ok(values) if !values.is_empty() => {
+                let raw: String =
+                    serde_json::from_value(values[0].clone()).unwrap();
+                serde_json::from_str(&raw).unwrap()
+            }
+            _ => vec![],
+        };
         TxResponse {
             info: serde_json::from_value(info[0].clone()).unwrap(),
             log: serde_json::from_value(log[0].clone()).unwrap(),
@@ -130,6 +357,7 @@ impl TxResponse {
             code: serde_json::from_value(code[0].clone()).unwrap(),
             gas_used: serde_json::from_value(gas_used[0].clone()).unwrap(),
             initialized_accounts,
+            batch_results,
         }
     }
 }
@@ -303,6 +531,10 @@ mod params {
             Some(values) => serde_json::from_str(&values).unwrap(),
             _ => vec![],
         };
+        let batch_results = match event.take("batch_results") {
+            Some(values) => serde_json::from_str(&values).unwrap(),
+            _ => vec![],
+        };
 
         Some(TxResponse {
             info,
@@ -312,6 +544,7 @@ mod params {
             code,
             gas_used,
             initialized_accounts,
+            batch_results,
         })
     }
 
@@ -341,3 +574,52 @@ mod params {
 
 #[cfg(not(feature = "ABCI"))]
 pub use params::*;
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::storage::Key;
+
+    use super::*;
+
+    /// Test that a dry run result is rendered with its status, gas and
+    /// changed keys
+    #[test]
+    fn test_dry_run_result() {
+        let key = Key::parse("key").unwrap();
+        let tx_result = TxResult {
+            gas_used: 100,
+            changed_keys: [key.clone()].into_iter().collect(),
+            ..Default::default()
+        };
+        let result = DryRunResult::from(tx_result);
+        let rendered = result.to_string();
+        assert!(rendered.contains("accepted"));
+        assert!(rendered.contains(&key.to_string()));
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["gas_used"], 100);
+        assert_eq!(json["changed_keys"][0], key.to_string());
+
+        let failed = DryRunResult::failed(1, "error");
+        assert!(failed.to_string().contains("failed"));
+    }
+
+    /// Test that a broadcast result is serialized with its broadcast mode
+    #[test]
+    fn test_broadcast_result_mode() {
+        let result = TxBroadcastResult::Async {
+            hash: "hash".into(),
+            inner_hash: None,
+        };
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["mode"], "async");
+        assert_eq!(json["hash"], "hash");
+        assert!(json.get("inner_hash").is_none());
+
+        let result = TxBroadcastResult::EstimateGas { gas_used: 100 };
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["mode"], "estimate-gas");
+        assert_eq!(json["gas_used"], 100);
+        assert!(result.initialized_accounts().is_empty());
+    }
+}
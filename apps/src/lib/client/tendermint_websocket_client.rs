@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
+#[cfg(feature = "ABCI")]
+use std::io;
 use std::net::TcpStream;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -43,10 +45,17 @@ pub enum Error {
     NotSubscribed,
     #[error("Received an error response: {0}")]
     Response(String),
+    #[error("The ledger is temporarily unavailable: {0}")]
+    Unavailable(String),
     #[error("Encountered JSONRPC request/response without an id")]
     MissingId,
     #[error("Connection timed out")]
     ConnectionTimeout,
+    #[error(
+        "The transaction {0} has not been included in a block yet. Its result \
+         can be queried later with its hash."
+    )]
+    NotYetIncluded(String),
 }
 
 type Json = serde_json::Value;
@@ -194,7 +203,8 @@ use rpc_types::{RpcResponse, RpcSubscription, SubscribeType};
 /// trait from `tendermint_rpc` only takes `&self` as an argument
 /// Furthermore, TendermintWebsocketClient must be `Send` since it will be
 /// used in async methods
-type Websocket = Arc<Mutex<websocket::sync::client::Client<TcpStream>>>;
+type Websocket = Arc<Mutex<WebsocketClient>>;
+type WebsocketClient = websocket::sync::client::Client<TcpStream>;
 type ResponseQueue = Arc<Mutex<HashMap<String, String>>>;
 
 #[cfg(feature = "ABCI")]
@@ -343,16 +353,21 @@ impl TendermintWebsocketClient {
                 let mut websocket = self.websocket.lock().unwrap();
                 let start = Instant::now();
                 loop {
-                    if Instant::now().duration_since(start)
-                        > self.connection_timeout
-                    {
+                    let elapsed = Instant::now().duration_since(start);
+                    if elapsed >= self.connection_timeout {
                         tracing::error!(
                             "Websocket connection timed out while waiting for \
                              response"
                         );
                         return Err(Error::ConnectionTimeout);
                     }
-                    match websocket.recv_message().map_err(Error::Websocket)? {
+                    // Don't block on the socket for longer than the time
+                    // left, in case the node stops sending any messages
+                    let message = recv_message_timeout(
+                        &mut websocket,
+                        self.connection_timeout - elapsed,
+                    )?;
+                    match message {
                         text @ OwnedMessage::Text(_) => break text,
                         OwnedMessage::Ping(data) => {
                             tracing::debug!(
@@ -460,6 +475,40 @@ impl Client for TendermintWebsocketClient {
     }
 }
 
+/// Receive the next message from the websocket, giving up with
+/// [`Error::ConnectionTimeout`] if none is received within the given timeout.
+#[cfg(feature = "ABCI")]
+fn recv_message_timeout(
+    websocket: &mut WebsocketClient,
+    timeout: Duration,
+) -> Result<OwnedMessage, Error> {
+    websocket
+        .stream_ref()
+        .set_read_timeout(Some(timeout))
+        .map_err(|err| Error::Websocket(err.into()))?;
+    let message = websocket.recv_message();
+    // Other requests on this connection don't expect a read timeout
+    websocket
+        .stream_ref()
+        .set_read_timeout(None)
+        .map_err(|err| Error::Websocket(err.into()))?;
+    match message {
+        Ok(message) => Ok(message),
+        Err(WebSocketError::IoError(err))
+            if matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            tracing::error!(
+                "Websocket connection timed out while waiting for response"
+            );
+            Err(Error::ConnectionTimeout)
+        }
+        Err(err) => Err(Error::Websocket(err)),
+    }
+}
+
 fn get_id(req_json: &str) -> Result<String, Error> {
     if let serde_json::Value::Object(req) =
         serde_json::from_str(req_json).unwrap()
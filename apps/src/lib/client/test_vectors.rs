@@ -0,0 +1,396 @@
+//! Deterministic test vectors of the byte-level encodings that signing
+//! integrations, e.g. wallets and HSMs, have to reproduce, generated with
+//! `anoma client utils gen-test-vectors`.
+//!
+//! The keys are derived from fixed seeds and the txs have fixed timestamps,
+//! so every run generates the same vectors. Ed25519 signatures are
+//! deterministic, so an implementation that signs the signing payload of a
+//! vector with its key must get the same signature. The code of the txs is
+//! the name of their WASM instead of the WASM itself, to keep the vectors
+//! short and independent of the build of the WASMs.
+//!
+//! All the bytes are hex encoded. The vectors are versioned, and the
+//! [`VERSION`] must be bumped whenever any of them changes.
+
+use std::str::FromStr;
+
+use anoma::proto::{Signed, SignedTxData, Tx};
+use anoma::types::address::{self, Address, EstablishedAddressGen};
+use anoma::types::hash::Hash;
+use anoma::types::intent::{DecimalWrapper, Exchange, FungibleTokenIntent};
+use anoma::types::key::*;
+use anoma::types::time::DateTimeUtc;
+use anoma::types::token;
+use anoma::types::transaction::pos::{Bond, Withdraw};
+use anoma::types::transaction::schema::{self, TxDataSchema};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// The version of the test vectors
+pub const VERSION: u32 = 6;
+
+/// The number of keys in the test vectors
+const KEYS_COUNT: u64 = 2;
+
+/// The time of the txs in the test vectors
+const TIMESTAMP: &str = "2022-01-01T00:00:00Z";
+
+/// The time after which the txs with an expiration expire
+const EXPIRATION: &str = "2022-01-01T01:00:00Z";
+
+/// The test vectors
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub version: u32,
+    pub keys: Vec<KeyVector>,
+    pub addresses: Vec<AddressVector>,
+    pub txs: Vec<TxVector>,
+    pub intents: Vec<IntentVector>,
+}
+
+/// A keypair with its implicit address
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyVector {
+    pub name: String,
+    /// The Borsh encoding of the secret key, prefixed with its scheme
+    pub secret_key: String,
+    /// The Borsh encoding of the public key, prefixed with its scheme
+    pub public_key: String,
+    pub public_key_hash: String,
+    /// The Bech32m encoding of the implicit address of the key
+    pub address: String,
+}
+
+/// An address with its encodings
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AddressVector {
+    pub name: String,
+    /// The Bech32m encoding
+    pub address: String,
+    /// The Borsh encoding
+    pub borsh: String,
+}
+
+/// A tx before and after it has been signed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TxVector {
+    pub name: String,
+    /// The schema of the data
+    pub schema: String,
+    /// The name of the key that signs the tx
+    pub signer: String,
+    pub code: String,
+    /// The data tagged with their schema
+    pub data: String,
+    pub timestamp: String,
+    pub expiration: Option<String>,
+    /// The Protobuf encoding of the tx before it is signed
+    pub unsigned_tx: String,
+    /// The SHA-256 hash of the unsigned tx, which is what gets signed
+    pub signing_payload: String,
+    /// The Borsh encoding of the signature, prefixed with its scheme
+    pub signature: String,
+    /// The Protobuf encoding of the signed tx, whose data are the Borsh
+    /// encoding of the data with the signature
+    pub signed_tx: String,
+    /// The hash of the signed tx, by which it's identified on chain
+    pub signed_tx_hash: String,
+}
+
+/// Signed intent data
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IntentVector {
+    pub name: String,
+    /// The name of the key that signs the data
+    pub signer: String,
+    /// The Borsh encoding of the data, which is what gets signed
+    pub signing_payload: String,
+    /// The Borsh encoding of the signature, prefixed with its scheme
+    pub signature: String,
+    /// The Borsh encoding of the signed data
+    pub signed: String,
+}
+
+/// Generate the test vectors
+pub fn generate() -> TestVectors {
+    let keys: Vec<common::SecretKey> = (1..=KEYS_COUNT).map(keypair).collect();
+    let implicit = |index: usize| Address::from(&keys[index].ref_to());
+    let mut address_gen = EstablishedAddressGen::new("anoma test vectors");
+    let established = address_gen.generate_address("established");
+    let validator = address_gen.generate_address("validator");
+    let token = address::xan();
+
+    let addresses = vec![
+        address_vector("established", &established),
+        address_vector("validator", &validator),
+        address_vector("implicit", &implicit(0)),
+        address_vector("token", &token),
+    ];
+
+    let transfer = token::Transfer {
+        source: implicit(0),
+        target: established.clone(),
+        token: token.clone(),
+        amount: token::Amount::from(1_250_000),
+        memo: Some("invoice 42".to_owned()),
+    };
+    let bond = Bond {
+        validator: validator.clone(),
+        amount: token::Amount::from(10_000_000),
+        source: Some(implicit(1)),
+    };
+    let withdraw = Withdraw {
+        validator: validator.clone(),
+        source: Some(implicit(1)),
+    };
+    let txs = vec![
+        tx_vector("transfer", "tx_transfer.wasm", &transfer, &keys, 0, false),
+        tx_vector(
+            "transfer_with_expiration",
+            "tx_transfer.wasm",
+            &transfer,
+            &keys,
+            0,
+            true,
+        ),
+        tx_vector("bond", "tx_bond.wasm", &bond, &keys, 1, false),
+        tx_vector("withdraw", "tx_withdraw.wasm", &withdraw, &keys, 1, false),
+    ];
+
+    let exchange = Exchange {
+        addr: implicit(0),
+        token_sell: token.clone(),
+        rate_min: DecimalWrapper::from_str("0.5").unwrap(),
+        max_sell: token::Amount::from(300_000_000),
+        token_buy: address::btc(),
+        min_buy: token::Amount::from(100_000_000),
+        vp: None,
+        nft: None,
+        expires: None,
+        partial: false,
+        max_fee: token::Amount::default(),
+    };
+    let signed_exchange = Signed::new(&keys[0], exchange.clone());
+    let intent = FungibleTokenIntent {
+        exchange: [signed_exchange].into_iter().collect(),
+        nonce: 0,
+    };
+    let intents = vec![
+        intent_vector("exchange", exchange, &keys, 0),
+        intent_vector("fungible_token_intent", intent, &keys, 0),
+    ];
+
+    TestVectors {
+        version: VERSION,
+        keys: keys
+            .iter()
+            .enumerate()
+            .map(|(index, secret_key)| {
+                let public_key = secret_key.ref_to();
+                KeyVector {
+                    name: key_name(index),
+                    secret_key: secret_key.to_string(),
+                    public_key: public_key.to_string(),
+                    public_key_hash: PublicKeyHash::from(&public_key)
+                        .to_string(),
+                    address: implicit(index).encode(),
+                }
+            })
+            .collect(),
+        addresses,
+        txs,
+        intents,
+    }
+}
+
+/// The keypair derived from the SHA-256 hash of a seed with the given number
+fn keypair(number: u64) -> common::SecretKey {
+    let seed = Hash::sha256(format!("anoma test vector key {}", number));
+    ed25519::SecretKey::try_from_slice(&seed.0)
+        .expect("Any 32 bytes are an Ed25519 secret key")
+        .try_to_sk()
+        .expect("An Ed25519 key is a common key")
+}
+
+fn key_name(index: usize) -> String {
+    format!("key_{}", index + 1)
+}
+
+fn address_vector(name: &str, address: &Address) -> AddressVector {
+    AddressVector {
+        name: name.to_owned(),
+        address: address.encode(),
+        borsh: hex::encode(
+            address
+                .try_to_vec()
+                .expect("Encoding an address shouldn't fail"),
+        ),
+    }
+}
+
+fn tx_vector<T: TxDataSchema>(
+    name: &str,
+    code: &str,
+    data: &T,
+    keys: &[common::SecretKey],
+    signer: usize,
+    with_expiration: bool,
+) -> TxVector {
+    let timestamp = DateTimeUtc::from_str(TIMESTAMP).unwrap();
+    let expiration =
+        with_expiration.then(|| DateTimeUtc::from_str(EXPIRATION).unwrap());
+    let tx = Tx {
+        code: code.as_bytes().to_vec(),
+        data: Some(schema::encode(data)),
+        timestamp,
+        expiration,
+    };
+    let signed = tx.clone().sign(&keys[signer]);
+    let signature = signed
+        .data
+        .as_ref()
+        .and_then(|data| SignedTxData::try_from_slice(data).ok())
+        .expect("A signed tx has signed data")
+        .sig;
+    TxVector {
+        name: name.to_owned(),
+        schema: T::NAME.to_owned(),
+        signer: key_name(signer),
+        code: hex::encode(&tx.code),
+        data: hex::encode(tx.data.as_ref().unwrap()),
+        timestamp: timestamp.to_rfc3339(),
+        expiration: expiration.map(|time| time.to_rfc3339()),
+        unsigned_tx: hex::encode(tx.to_bytes()),
+        signing_payload: hex::encode(tx.hash()),
+        signature: hex::encode(
+            signature
+                .try_to_vec()
+                .expect("Encoding a signature shouldn't fail"),
+        ),
+        signed_tx: hex::encode(signed.to_bytes()),
+        signed_tx_hash: hex::encode(signed.hash()),
+    }
+}
+
+fn intent_vector<T: BorshSerialize + BorshDeserialize>(
+    name: &str,
+    data: T,
+    keys: &[common::SecretKey],
+    signer: usize,
+) -> IntentVector {
+    let signed = Signed::new(&keys[signer], data);
+    IntentVector {
+        name: name.to_owned(),
+        signer: key_name(signer),
+        signing_payload: hex::encode(
+            signed
+                .data
+                .try_to_vec()
+                .expect("Encoding intent data shouldn't fail"),
+        ),
+        signature: hex::encode(
+            signed
+                .sig
+                .try_to_vec()
+                .expect("Encoding a signature shouldn't fail"),
+        ),
+        signed: hex::encode(
+            signed
+                .try_to_vec()
+                .expect("Encoding signed intent data shouldn't fail"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    /// Test that the same vectors are generated every time
+    #[test]
+    fn test_vectors_are_deterministic() {
+        assert_eq!(generate(), generate());
+    }
+
+    /// Test the key derivation and signing against the first test vector of
+    /// RFC 8032
+    #[test]
+    fn test_rfc8032_vector() {
+        let keypair: common::SecretKey = ed25519::SecretKey::from_str(
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        )
+        .unwrap()
+        .try_to_sk()
+        .unwrap();
+        assert_eq!(
+            keypair.ref_to().to_string(),
+            "00d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+        );
+        let signature = common::SigScheme::sign(&keypair, b"");
+        assert_eq!(
+            hex::encode(signature.try_to_vec().unwrap()),
+            "00e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901\
+             555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+        );
+    }
+
+    /// Test that the signed txs decode and verify with the keys of their
+    /// signers
+    #[test]
+    fn test_tx_vectors_verify() {
+        let vectors = generate();
+        for vector in &vectors.txs {
+            let key = vectors
+                .keys
+                .iter()
+                .find(|key| key.name == vector.signer)
+                .unwrap();
+            let public_key =
+                common::PublicKey::from_str(&key.public_key).unwrap();
+            let signature = common::Signature::try_from_slice(
+                &hex::decode(&vector.signature).unwrap(),
+            )
+            .unwrap();
+            let signed =
+                Tx::try_from(&hex::decode(&vector.signed_tx).unwrap()[..])
+                    .unwrap();
+            signed.verify_sig(&public_key, &signature).unwrap();
+            common::SigScheme::verify_signature_raw(
+                &public_key,
+                &hex::decode(&vector.signing_payload).unwrap(),
+                &signature,
+            )
+            .unwrap();
+
+            let unsigned =
+                Tx::try_from(&hex::decode(&vector.unsigned_tx).unwrap()[..])
+                    .unwrap();
+            let data = hex::decode(&vector.data).unwrap();
+            assert_eq!(unsigned.data.as_ref(), Some(&data));
+            let (schema, _) = schema::identify(&data).unwrap();
+            assert_eq!(schema.name, vector.schema);
+        }
+    }
+
+    /// Test that the signed intent data verify with the keys of their
+    /// signers
+    #[test]
+    fn test_intent_vectors_verify() {
+        let vectors = generate();
+        let exchange = &vectors.intents[0];
+        let key = &vectors.keys[0];
+        assert_eq!(exchange.signer, key.name);
+        let public_key = common::PublicKey::from_str(&key.public_key).unwrap();
+        let signed = Signed::<Exchange>::try_from_slice(
+            &hex::decode(&exchange.signed).unwrap(),
+        )
+        .unwrap();
+        signed.verify(&public_key).unwrap();
+        assert_eq!(
+            hex::encode(signed.data.try_to_vec().unwrap()),
+            exchange.signing_payload
+        );
+    }
+}
@@ -191,18 +191,17 @@ mod tm_jsonrpc {
 
     /// Given a query looking for a particular Anoma event,
     /// query the Tendermint's jsonrpc endpoint for the events
-    /// log. Returns the appropriate event if found in the log.
+    /// log, waiting for new events for up to the `wait_time`. Returns the
+    /// appropriate event if found in the log.
     pub async fn fetch_event(
         address: &str,
         filter: Query,
         tx_hash: &str,
+        wait_time: std::time::Duration,
     ) -> Result<TxResponse, Error> {
         // craft the body of the request
-        let request = Request::from(EventParams::new(
-            filter,
-            NUM_EVENTS,
-            std::time::Duration::from_secs(60),
-        ));
+        let request =
+            Request::from(EventParams::new(filter, NUM_EVENTS, wait_time));
         // construct a curl client
         let mut client = Client::new(address, request, tx_hash);
         // perform the request
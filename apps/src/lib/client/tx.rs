@@ -1,28 +1,47 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fs::File;
-
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anoma::ibc::applications::ics20_fungible_token_transfer::msgs::transfer::MsgTransfer;
+use anoma::ibc::signer::Signer;
+use anoma::ibc::timestamp::Timestamp;
+use anoma::ibc::tx_msg::Msg;
+use anoma::ibc::Height as IbcHeight;
+use anoma::ibc_proto::cosmos::base::v1beta1::Coin;
 use anoma::ledger::governance::storage as gov_storage;
-use anoma::ledger::pos::{BondId, Bonds, Unbonds};
+use anoma::ledger::pos::types::{BasisPoints, ValidatorState};
+use anoma::ledger::pos::{
+    BondId, Bonds, Unbonds, ValidatorCommissionRates, ValidatorStates,
+    ValidatorTotalDeltas,
+};
 use anoma::proto::Tx;
 use anoma::types::address::{xan as m1t, Address};
 use anoma::types::governance::{
     OfflineProposal, OfflineVote, Proposal, ProposalVote,
 };
+use anoma::types::hash::Hash;
 use anoma::types::key::*;
-use anoma::types::nft::{self, Nft, NftToken};
-use anoma::types::storage::Epoch;
+use anoma::types::nft::{self, NftToken};
+use anoma::types::storage::{self, BlockHeight, Epoch};
+use anoma::types::time::DateTimeUtc;
 use anoma::types::token::Amount;
+use anoma::types::transaction::batch::{BatchOp, TxBatch};
 use anoma::types::transaction::governance::{
     InitProposalData, VoteProposalData,
 };
 use anoma::types::transaction::nft::{CreateNft, MintNft};
-use anoma::types::transaction::{pos, InitAccount, InitValidator, UpdateVp};
+use anoma::types::transaction::{
+    hash_tx, pos, schema, InitAccount, InitValidator, UpdateVp,
+};
 use anoma::types::{address, token};
 use anoma::{ledger, vm};
 use async_std::io::{self, WriteExt};
-use borsh::BorshSerialize;
-use itertools::Either::*;
+#[cfg(not(feature = "ABCI"))]
+use tendermint::abci::transaction::Hash as TmTxHash;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
@@ -30,138 +49,501 @@ use tendermint_config_abci::net::Address as TendermintAddress;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_rpc::endpoint::broadcast::tx_sync::Response;
 #[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::endpoint::tx::Response as TxQueryResponse;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::error::ErrorDetail as RpcErrorDetail;
+#[cfg(not(feature = "ABCI"))]
 use tendermint_rpc::query::{EventType, Query};
 #[cfg(not(feature = "ABCI"))]
-use tendermint_rpc::{Client, HttpClient};
+use tendermint_rpc::{Client, Error as TmRpcError, HttpClient};
 #[cfg(feature = "ABCI")]
 use tendermint_rpc_abci::endpoint::broadcast::tx_sync::Response;
 #[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::endpoint::tx::Response as TxQueryResponse;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::error::ErrorDetail as RpcErrorDetail;
+#[cfg(feature = "ABCI")]
 use tendermint_rpc_abci::query::{EventType, Query};
 #[cfg(feature = "ABCI")]
-use tendermint_rpc_abci::{Client, HttpClient};
-
-use super::rpc;
-use crate::cli::context::WalletAddress;
-use crate::cli::{args, safe_exit, Context};
-use crate::client::signing::{find_keypair, sign_tx};
+use tendermint_rpc_abci::{Client, Error as TmRpcError, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_stable::abci::transaction::Hash as TmTxHash;
+use thiserror::Error;
+
+use super::apply::{self, ChainState, Manifest, Step};
+use super::batch::{self, BatchFile, BondSpec, BondsFile, OpSpec};
+use super::draft::{self, TxDraft};
+use super::middleware::{TxCodes, TxInfo, TxMiddlewares};
+use super::progress::{TxProgress, TxProgressHook};
+use super::{nft_assets, nft_definition, rpc};
+use crate::cli::context::{WalletAddress, WalletPublicKey};
+use crate::cli::{args, Context};
+use crate::client::names::AddressNames;
+use crate::client::signing::{
+    self, find_keypair, find_signing_key, sign_tx, wrap_tx,
+};
 #[cfg(not(feature = "ABCI"))]
-use crate::client::tendermint_rpc_types::Error;
-use crate::client::tendermint_rpc_types::{TxBroadcastData, TxResponse};
+use crate::client::tendermint_rpc_types::Error as RpcError;
+use crate::client::tendermint_rpc_types::{
+    TxBroadcastData, TxBroadcastResult, TxResponse,
+};
 use crate::client::tendermint_websocket_client::{
     Error as WsError, TendermintWebsocketClient, WebSocketAddress,
 };
 #[cfg(not(feature = "ABCI"))]
 use crate::client::tm_jsonrpc_client::{fetch_event, JsonRpcAddress};
-use crate::node::ledger::tendermint_node;
+use crate::node::ledger::{events, tendermint_node};
 
 #[cfg(not(feature = "ABCI"))]
 const ACCEPTED_QUERY_KEY: &str = "accepted.hash";
 const APPLIED_QUERY_KEY: &str = "applied.hash";
 const TX_INIT_ACCOUNT_WASM: &str = "tx_init_account.wasm";
 const TX_INIT_VALIDATOR_WASM: &str = "tx_init_validator.wasm";
+const TX_REVEAL_PK_WASM: &str = "tx_reveal_pk.wasm";
 const TX_INIT_PROPOSAL: &str = "tx_init_proposal.wasm";
 const TX_VOTE_PROPOSAL: &str = "tx_vote_proposal.wasm";
 const TX_UPDATE_VP_WASM: &str = "tx_update_vp.wasm";
 const TX_TRANSFER_WASM: &str = "tx_transfer.wasm";
+const TX_MULTI_TRANSFER_WASM: &str = "tx_multi_transfer.wasm";
+const TX_IBC_WASM: &str = "tx_ibc.wasm";
 const TX_INIT_NFT: &str = "tx_init_nft.wasm";
 const TX_MINT_NFT: &str = "tx_mint_nft.wasm";
 const VP_USER_WASM: &str = "vp_user.wasm";
 const TX_BOND_WASM: &str = "tx_bond.wasm";
 const TX_UNBOND_WASM: &str = "tx_unbond.wasm";
 const TX_WITHDRAW_WASM: &str = "tx_withdraw.wasm";
+const TX_CLAIM_REWARDS_WASM: &str = "tx_claim_rewards.wasm";
+const TX_AUTO_COMPOUND_WASM: &str = "tx_auto_compound.wasm";
+const TX_CHANGE_COMMISSION_WASM: &str = "tx_change_commission.wasm";
+const TX_UNJAIL_WASM: &str = "tx_unjail.wasm";
 const VP_NFT: &str = "vp_nft.wasm";
+/// The bytes of a tx that mints NFT tokens taken by everything but its code
+/// and its tokens, i.e. the addresses, the signature and the fee
+const MINT_NFT_TX_OVERHEAD_BYTES: usize = 4 * 1024;
+/// The timeout of an IBC transfer without a timeout height or timestamp
+const DEFAULT_IBC_TIMEOUT_SECS: u64 = 60 * 60;
+/// The most tokens that the testnet faucet's VP allows to withdraw in a single
+/// tx without the faucet's signature.
+const FAUCET_MAX_WITHDRAWAL: u64 = 1_000;
+
+/// Errors from building and submitting transactions. The checks of a tx's
+/// arguments against the state on chain can be overridden with `--force`.
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The address {0} doesn't exist on chain.")]
+    AddressNotFound(Address),
+    #[error("The source address {0} doesn't exist on chain.")]
+    SourceNotFound(Address),
+    #[error("The target address {0} doesn't exist on chain.")]
+    TargetNotFound(Address),
+    #[error("The token address {0} doesn't exist on chain.")]
+    TokenNotFound(Address),
+    #[error("The address {0} doesn't belong to any known validator account.")]
+    NotAValidator(Address),
+    #[error(
+        "A validity predicate of an implicit address cannot be directly \
+         updated. You can use an established address for this purpose."
+    )]
+    ImplicitVpUpdate,
+    #[error(
+        "A validity predicate of an internal address cannot be directly \
+         updated."
+    )]
+    InternalVpUpdate,
+    #[error("The public key of the address {0} has already been revealed.")]
+    PkAlreadyRevealed(Address),
+    #[error("Validity predicate code validation failed with {0}")]
+    InvalidVp(vm::WasmValidationError),
+    #[error("Validator validity predicate code validation failed with {0}")]
+    InvalidValidatorVp(vm::WasmValidationError),
+    #[error(
+        "Staking reward account validity predicate code validation failed \
+         with {0}"
+    )]
+    InvalidRewardsVp(vm::WasmValidationError),
+    #[error(
+        "Validity predicate code validation failed for account {0} with {1}"
+    )]
+    InvalidAccountVp(String, vm::WasmValidationError),
+    #[error(
+        "The balance of the source {owner} of token {token} is lower than the \
+         amount to be transferred. Amount to transfer is {amount} and the \
+         balance is {balance}."
+    )]
+    BalanceTooLow {
+        owner: Address,
+        token: Address,
+        amount: token::Amount,
+        balance: token::Amount,
+    },
+    #[error(
+        "The faucet allows to withdraw at most {max} tokens in a single \
+         transaction, but {amount} was requested."
+    )]
+    FaucetWithdrawalTooHigh {
+        amount: token::Amount,
+        max: token::Amount,
+    },
+    #[error("Failed to parse the targets file {0}: {1}")]
+    ParseDisbursement(String, serde_json::Error),
+    #[error("There are no targets to transfer to.")]
+    EmptyDisbursement,
+    #[error("The total amount to transfer overflows.")]
+    DisbursementOverflow,
+    #[error(
+        "The memo is {0} bytes long, but at most {} bytes are allowed.",
+        token::MAX_MEMO_LEN
+    )]
+    MemoTooLong(usize),
+    #[error("No balance found for the source {owner} of token {token}")]
+    NoBalance { owner: Address, token: Address },
+    #[error(
+        "The total bonds of the source {owner} is lower than the amount to be \
+         unbonded. Amount to unbond is {amount} and the total bonds is \
+         {bonds}."
+    )]
+    BondsTooLow {
+        owner: Address,
+        amount: token::Amount,
+        bonds: token::Amount,
+    },
+    #[error("No bonds found")]
+    NoBonds,
+    #[error(
+        "The self-bond of the validator {validator} would be {self_bond}, \
+         which is below the minimum self-bond {min_self_bond}."
+    )]
+    SelfBondBelowMinimum {
+        validator: Address,
+        self_bond: token::Amount,
+        min_self_bond: token::Amount,
+    },
+    #[error(
+        "The initial self-bond {self_bond} of the new validator is below the \
+         minimum self-bond {min_self_bond}."
+    )]
+    InitialSelfBondBelowMinimum {
+        self_bond: token::Amount,
+        min_self_bond: token::Amount,
+    },
+    #[error(
+        "The remaining self-bond {remaining} of the validator {validator} \
+         would be below the minimum self-bond {min_self_bond}. Either unbond \
+         less or unbond the whole self-bond."
+    )]
+    RemainingSelfBondBelowMinimum {
+        validator: Address,
+        remaining: token::Amount,
+        min_self_bond: token::Amount,
+    },
+    #[error(
+        "The tokens delegated to the validator {validator} would be \
+         {delegations}, which is over the delegation cap {max_delegations}."
+    )]
+    DelegationCapExceeded {
+        validator: Address,
+        delegations: token::Amount,
+        max_delegations: token::Amount,
+    },
+    #[error("The commission rate {0} is greater than 100%.")]
+    CommissionRateTooHigh(BasisPoints),
+    #[error(
+        "The commission rate of the validator {validator} would change by \
+         {change}, which is over the maximum change {max_change} per epoch."
+    )]
+    CommissionRateChangeTooLarge {
+        validator: Address,
+        change: BasisPoints,
+        max_change: BasisPoints,
+    },
+    #[error("The validator {0} is not jailed.")]
+    ValidatorNotJailed(Address),
+    #[error(
+        "The validator {validator} is jailed until epoch {until}, which is \
+         after the pipeline epoch {pipeline_epoch}."
+    )]
+    JailPeriodNotOver {
+        validator: Address,
+        until: Epoch,
+        pipeline_epoch: Epoch,
+    },
+    #[error(
+        "There are no unbonded bonds ready to withdraw in the current epoch \
+         {0}."
+    )]
+    NothingToWithdraw(Epoch),
+    #[error("No unbonded bonds found")]
+    NoUnbonds,
+    #[error("There are no rewards to claim for {0}.")]
+    NoRewards(BondId),
+    #[error("No creator key found for {0}")]
+    NftCreatorNotFound(Address),
+    #[error("{0}")]
+    NftDefinition(nft_definition::Error),
+    #[error(
+        "No IPFS node is configured to pin the assets to. Set the \
+         `client.ipfs.api_url` in the config."
+    )]
+    IpfsNotConfigured,
+    #[error("{0}")]
+    NftAssets(nft_assets::Error),
+    #[error("Invalid metadata of the NFT token {0}: {1}")]
+    InvalidNftMetadata(u64, nft::MetadataError),
+    #[error("The media of the NFT token {0} at {1} can't be fetched")]
+    NftMediaNotFetchable(u64, String),
+    #[error("Failed to download the media of the NFT token {0}: {1}")]
+    NftMediaDownload(u64, reqwest::Error),
+    #[error(
+        "The media of the NFT token {id} has the hash {actual}, but its \
+         metadata expects {expected}"
+    )]
+    NftMediaHashMismatch {
+        id: u64,
+        expected: String,
+        actual: String,
+    },
+    #[error("Invalid data for init proposal transaction.")]
+    InvalidProposal,
+    #[error("Error while reading the proposal file {0}: {1}.")]
+    ReadProposal(String, std::io::Error),
+    #[error("Failed to parse the proposal file {0}: {1}")]
+    ParseProposal(String, String),
+    #[error("Invalid voting epochs of the proposal: {0}")]
+    InvalidProposalEpochs(String),
+    #[error(
+        "The proposal content has {0} bytes, but it must be shorter than {1} \
+         bytes."
+    )]
+    ProposalContentTooLarge(usize, u64),
+    #[error("Address {0} doesn't have enough funds.")]
+    ProposalFundsTooLow(Address),
+    #[error("Proposal signature mismatch!")]
+    ProposalSignatureMismatch,
+    #[error("Proposal start epoch is not in the storage.")]
+    ProposalStartEpochNotFound,
+    #[error("Proposal end epoch is not in the storage.")]
+    ProposalEndEpochNotFound,
+    #[error("Missing mandatory argument --signer.")]
+    MissingSigner,
+    #[error("Missing mandatory argument --proposal-id.")]
+    MissingProposalId,
+    #[error(
+        "The voting period of the proposal {id} from epoch {start} to epoch \
+         {end} is not open in the current epoch {current}."
+    )]
+    VotingPeriodNotOpen {
+        id: u64,
+        start: Epoch,
+        end: Epoch,
+        current: Epoch,
+    },
+    #[error(
+        "Validators can only vote on the proposal {0} in the first two thirds \
+         of its voting period, which ended before the current epoch {1}."
+    )]
+    ValidatorVotingPeriodOver(u64, Epoch),
+    #[error("The address {0} is neither a validator nor a delegator.")]
+    NotValidatorOrDelegator(Address),
+    #[error(
+        "The IBC transfer timeout of {0} seconds is too far in the future."
+    )]
+    InvalidIbcTimeout(u64),
+    #[error("Error while creating the file {0}: {1}.")]
+    WriteFile(String, std::io::Error),
+    #[error("Error while reading the tx data from {0}: {1}.")]
+    ReadTxData(String, std::io::Error),
+    #[error("Expected two accounts to be created, but {0} were created")]
+    ValidatorAccounts(usize),
+    #[error("The transaction expired at {0}.")]
+    Expired(String),
+    #[error(
+        "The transaction {hash} on which this transaction depends failed with \
+         the code {code}: {log}"
+    )]
+    DependencyFailed {
+        hash: String,
+        code: String,
+        log: String,
+    },
+    #[error(
+        "The chain is already at the height {current}, so the transaction \
+         cannot be included in the block at the target height {target}."
+    )]
+    TargetHeightPassed {
+        target: BlockHeight,
+        current: BlockHeight,
+    },
+    #[error("{0}")]
+    Manifest(apply::Error),
+    #[error("{0}")]
+    Batch(batch::Error),
+    #[error("{0}")]
+    Draft(draft::Error),
+    #[error("The transaction was rejected by the {0} middleware: {1}")]
+    MiddlewareRejected(&'static str, String),
+    #[error("The {0} transaction cannot be saved as a draft.")]
+    DraftUnsupported(&'static str),
+    #[error("Failed to create a client for the ledger node: {0}")]
+    LedgerClient(TmRpcError),
+    #[error("Encountered error while broadcasting transaction: {0}")]
+    Websocket(WsError),
+    #[cfg(not(feature = "ABCI"))]
+    #[error("Encountered error while broadcasting transaction: {0}")]
+    Rpc(RpcError),
+}
+
+impl From<WsError> for Error {
+    fn from(err: WsError) -> Self {
+        Self::Websocket(err)
+    }
+}
 
-pub async fn submit_custom(ctx: Context, args: args::TxCustom) {
+#[cfg(not(feature = "ABCI"))]
+impl From<RpcError> for Error {
+    fn from(err: RpcError) -> Self {
+        Self::Rpc(err)
+    }
+}
+
+/// Unless the tx is forced, fail with the error of a failed check of its
+/// arguments against the state on chain. A forced tx only logs the error.
+fn check(force: bool, result: Result<(), Error>) -> Result<(), Error> {
+    match result {
+        Err(err) if force => {
+            tracing::warn!("{}", err);
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// The result of submitting a tx, together with the context that has been
+/// used to sign it
+pub type SubmitResult = Result<(Context, TxBroadcastResult), Error>;
+
+/// Submit a tx with custom code and data. The accounts initialized by the tx
+/// are not saved in the wallet, see [`save_initialized_accounts`].
+pub async fn submit_custom(ctx: Context, args: args::TxCustom) -> SubmitResult {
     let tx_code = ctx.read_wasm(args.code_path);
-    let data = args.data_path.map(|data_path| {
-        std::fs::read(data_path).expect("Expected a file at given data path")
-    });
+    let data = args.data.map(read_tx_data).transpose()?;
     let tx = Tx::new(tx_code, data);
-    let (ctx, initialized_accounts) = process_tx(ctx, &args.tx, tx, None).await;
-    save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
+    process_tx(ctx, &args.tx, tx, None).await
+}
+
+/// Read the data of a custom tx from its source
+fn read_tx_data(data: args::TxData) -> Result<Vec<u8>, Error> {
+    match data {
+        args::TxData::File(path) => std::fs::read(&path).map_err(|err| {
+            Error::ReadTxData(path.to_string_lossy().into_owned(), err)
+        }),
+        args::TxData::Stdin => {
+            let mut bytes = vec![];
+            std::io::Read::read_to_end(&mut std::io::stdin(), &mut bytes)
+                .map_err(|err| Error::ReadTxData("stdin".into(), err))?;
+            Ok(bytes)
+        }
+        args::TxData::Bytes(bytes) => Ok(bytes),
+    }
 }
 
-pub async fn submit_update_vp(ctx: Context, args: args::TxUpdateVp) {
+/// Submit a tx that updates the VP of an established account
+pub async fn submit_update_vp(
+    ctx: Context,
+    args: args::TxUpdateVp,
+) -> SubmitResult {
     let addr = ctx.get(&args.addr);
 
     // Check that the address is established and exists on chain
-    match &addr {
+    let is_established = match &addr {
         Address::Established(_) => {
             let exists =
                 rpc::known_address(&addr, args.tx.ledger_address.clone()).await;
-            if !exists {
-                eprintln!("The address {} doesn't exist on chain.", addr);
-                if !args.tx.force {
-                    safe_exit(1)
-                }
+            if exists {
+                Ok(())
+            } else {
+                Err(Error::AddressNotFound(addr.clone()))
             }
         }
-        Address::Implicit(_) => {
-            eprintln!(
-                "A validity predicate of an implicit address cannot be \
-                 directly updated. You can use an established address for \
-                 this purpose."
-            );
-            if !args.tx.force {
-                safe_exit(1)
-            }
-        }
-        Address::Internal(_) => {
-            eprintln!(
-                "A validity predicate of an internal address cannot be \
-                 directly updated."
-            );
-            if !args.tx.force {
-                safe_exit(1)
-            }
-        }
-    }
+        Address::Implicit(_) => Err(Error::ImplicitVpUpdate),
+        Address::Internal(_) => Err(Error::InternalVpUpdate),
+    };
+    check(args.tx.force, is_established)?;
 
     let vp_code = ctx.read_wasm(args.vp_code_path);
     // Validate the VP code
-    if let Err(err) = vm::validate_untrusted_wasm(&vp_code) {
-        eprintln!("Validity predicate code validation failed with {}", err);
-        if !args.tx.force {
-            safe_exit(1)
-        }
-    }
+    check(
+        args.tx.force,
+        vm::validate_untrusted_wasm(&vp_code).map_err(Error::InvalidVp),
+    )?;
 
     let tx_code = ctx.read_wasm(TX_UPDATE_VP_WASM);
 
     let data = UpdateVp { addr, vp_code };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+    let data = schema::encode(&data);
 
     let tx = Tx::new(tx_code, Some(data));
-    process_tx(ctx, &args.tx, tx, Some(&args.addr)).await;
+    process_tx(ctx, &args.tx, tx, Some(&args.addr)).await
 }
 
-pub async fn submit_init_account(mut ctx: Context, args: args::TxInitAccount) {
+/// Submit a tx that initializes a new established account. The new account is
+/// not saved in the wallet, see [`save_initialized_accounts`].
+pub async fn submit_init_account(
+    mut ctx: Context,
+    args: args::TxInitAccount,
+) -> SubmitResult {
     let public_key = ctx.get_cached(&args.public_key);
     let vp_code = args
         .vp_code_path
         .map(|path| ctx.read_wasm(path))
         .unwrap_or_else(|| ctx.read_wasm(VP_USER_WASM));
     // Validate the VP code
-    if let Err(err) = vm::validate_untrusted_wasm(&vp_code) {
-        eprintln!("Validity predicate code validation failed with {}", err);
-        if !args.tx.force {
-            safe_exit(1)
-        }
-    }
+    check(
+        args.tx.force,
+        vm::validate_untrusted_wasm(&vp_code).map_err(Error::InvalidVp),
+    )?;
 
     let tx_code = ctx.read_wasm(TX_INIT_ACCOUNT_WASM);
     let data = InitAccount {
         public_key,
         vp_code,
     };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+    let data = schema::encode(&data);
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await
+}
+
+/// Submit a tx that reveals the public key of an implicit address on chain
+pub async fn submit_reveal_pk(
+    mut ctx: Context,
+    args: args::TxRevealPk,
+) -> SubmitResult {
+    let public_key = ctx.get_cached(&args.public_key);
+    let addr: Address = (&public_key).into();
+
+    let revealed =
+        rpc::get_public_key(&addr, args.tx.ledger_address.clone()).await;
+    check(
+        args.tx.force,
+        match revealed {
+            Some(_) => Err(Error::PkAlreadyRevealed(addr.clone())),
+            None => Ok(()),
+        },
+    )?;
+
+    let tx_code = ctx.read_wasm(TX_REVEAL_PK_WASM);
+    let data = schema::encode(&public_key);
 
     let tx = Tx::new(tx_code, Some(data));
-    let (ctx, initialized_accounts) =
-        process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
-    save_initialized_accounts(ctx, &args.tx, initialized_accounts).await;
+    let signer = WalletAddress::new(addr.encode());
+    process_tx(ctx, &args.tx, tx, Some(&signer)).await
 }
 
+/// Submit a tx that initializes a new validator, generating its keys if not
+/// given, and set up the ledger node to use it. This is an interactive
+/// command: it prompts for an alias if none is given and prints the aliases
+/// of the saved addresses and keys.
 pub async fn submit_init_validator(
     mut ctx: Context,
     args::TxInitValidator {
@@ -173,9 +555,16 @@ pub async fn submit_init_validator(
         protocol_key,
         validator_vp_code_path,
         rewards_vp_code_path,
+        commission_rate,
+        self_bond,
         unsafe_dont_encrypt,
     }: args::TxInitValidator,
-) {
+) -> Result<(), Error> {
+    // The validator's keys generated here would be lost with a draft
+    if tx_args.save_draft.is_some() {
+        return Err(Error::DraftUnsupported("init-validator"));
+    }
+    let output = ctx.global_args.output;
     let alias = tx_args
         .initialized_account_alias
         .as_ref()
@@ -186,7 +575,7 @@ pub async fn submit_init_validator(
     let consensus_key_alias = format!("{}-consensus-key", alias);
     let rewards_key_alias = format!("{}-rewards-key", alias);
     let account_key = ctx.get_opt_cached(&account_key).unwrap_or_else(|| {
-        println!("Generating validator account key...");
+        print_progress(output, "Generating validator account key...");
         ctx.wallet
             .gen_key(Some(validator_key_alias.clone()), unsafe_dont_encrypt)
             .1
@@ -195,7 +584,7 @@ pub async fn submit_init_validator(
 
     let consensus_key =
         ctx.get_opt_cached(&consensus_key).unwrap_or_else(|| {
-            println!("Generating consensus key...");
+            print_progress(output, "Generating consensus key...");
             ctx.wallet
                 .gen_key(Some(consensus_key_alias.clone()), unsafe_dont_encrypt)
                 .1
@@ -203,7 +592,7 @@ pub async fn submit_init_validator(
 
     let rewards_account_key =
         ctx.get_opt_cached(&rewards_account_key).unwrap_or_else(|| {
-            println!("Generating staking reward account key...");
+            print_progress(output, "Generating staking reward account key...");
             ctx.wallet
                 .gen_key(Some(rewards_key_alias.clone()), unsafe_dont_encrypt)
                 .1
@@ -212,7 +601,7 @@ pub async fn submit_init_validator(
     let protocol_key = ctx.get_opt_cached(&protocol_key);
 
     if protocol_key.is_none() {
-        println!("Generating protocol signing key...");
+        print_progress(output, "Generating protocol signing key...");
     }
     // Generate the validator keys
     let validator_keys = ctx.wallet.gen_validator_keys(protocol_key).unwrap();
@@ -229,29 +618,61 @@ pub async fn submit_init_validator(
         .map(|path| ctx.read_wasm(path))
         .unwrap_or_else(|| ctx.read_wasm(VP_USER_WASM));
     // Validate the validator VP code
-    if let Err(err) = vm::validate_untrusted_wasm(&validator_vp_code) {
-        eprintln!(
-            "Validator validity predicate code validation failed with {}",
-            err
-        );
-        if !tx_args.force {
-            safe_exit(1)
-        }
-    }
+    check(
+        tx_args.force,
+        vm::validate_untrusted_wasm(&validator_vp_code)
+            .map_err(Error::InvalidValidatorVp),
+    )?;
     let rewards_vp_code = rewards_vp_code_path
         .map(|path| ctx.read_wasm(path))
         .unwrap_or_else(|| ctx.read_wasm(VP_USER_WASM));
     // Validate the rewards VP code
-    if let Err(err) = vm::validate_untrusted_wasm(&rewards_vp_code) {
-        eprintln!(
-            "Staking reward account validity predicate code validation failed \
-             with {}",
-            err
-        );
-        if !tx_args.force {
-            safe_exit(1)
-        }
+    check(
+        tx_args.force,
+        vm::validate_untrusted_wasm(&rewards_vp_code)
+            .map_err(Error::InvalidRewardsVp),
+    )?;
+    if commission_rate > 10_000 {
+        check(
+            tx_args.force,
+            Err(Error::CommissionRateTooHigh(BasisPoints::new(
+                commission_rate,
+            ))),
+        )?;
     }
+    let self_bond = match self_bond {
+        Some(amount) => {
+            // Check the source's balance and the minimum self-bond
+            let bond_source = ctx.get(&source);
+            let client =
+                HttpClient::new(tx_args.ledger_address.clone()).unwrap();
+            let balance = rpc::token::get_token_balance(
+                &client,
+                &address::xan(),
+                &bond_source,
+            )
+            .await;
+            check(
+                tx_args.force,
+                check_balance(&bond_source, &address::xan(), amount, balance),
+            )?;
+            if let Some(params) = rpc::pos::get_params(&client).await {
+                let min_self_bond =
+                    token::Amount::from(params.min_validator_self_bond);
+                if amount < min_self_bond {
+                    check(
+                        tx_args.force,
+                        Err(Error::InitialSelfBondBelowMinimum {
+                            self_bond: amount,
+                            min_self_bond,
+                        }),
+                    )?;
+                }
+            }
+            Some((bond_source, amount))
+        }
+        None => None,
+    };
     let tx_code = ctx.read_wasm(TX_INIT_VALIDATOR_WASM);
 
     let data = InitValidator {
@@ -262,11 +683,15 @@ pub async fn submit_init_validator(
         dkg_key,
         validator_vp_code,
         rewards_vp_code,
+        commission_rate,
+        self_bond,
     };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+    let data = schema::encode(&data);
     let tx = Tx::new(tx_code, Some(data));
-    let (mut ctx, initialized_accounts) =
-        process_tx(ctx, &tx_args, tx, Some(&source)).await;
+    let (mut ctx, result) =
+        process_tx(ctx, &tx_args, tx, Some(&source)).await?;
+    print_tx_result(&ctx, &result);
+    let initialized_accounts = result.initialized_accounts();
     if !tx_args.dry_run {
         let (validator_address_alias, validator_address, rewards_address_alias) =
             match &initialized_accounts[..] {
@@ -275,8 +700,11 @@ pub async fn submit_init_validator(
                 [account_1, account_2] => {
                     // We need to find out which address is which
                     let (validator_address, rewards_address) =
-                        if rpc::is_validator(account_1, tx_args.ledger_address)
-                            .await
+                        if rpc::pos::is_validator(
+                            account_1,
+                            tx_args.ledger_address,
+                        )
+                        .await
                         {
                             (account_1, account_2)
                         } else {
@@ -288,10 +716,18 @@ pub async fn submit_init_validator(
                     {
                         Some(alias) => alias,
                         None => {
-                            print!(
-                                "Choose an alias for the validator address: "
-                            );
-                            io::stdout().flush().await.unwrap();
+                            let prompt =
+                                "Choose an alias for the validator address: ";
+                            match output {
+                                args::OutputMode::Text => {
+                                    print!("{}", prompt);
+                                    io::stdout().flush().await.unwrap();
+                                }
+                                args::OutputMode::Json => {
+                                    eprint!("{}", prompt);
+                                    io::stderr().flush().await.unwrap();
+                                }
+                            }
                             let mut alias = String::new();
                             io::stdin().read_line(&mut alias).await.unwrap();
                             alias.trim().to_owned()
@@ -299,9 +735,12 @@ pub async fn submit_init_validator(
                     };
                     let validator_address_alias =
                         if validator_address_alias.is_empty() {
-                            println!(
-                                "Empty alias given, using {} as the alias.",
-                                validator_address.encode()
+                            print_progress(
+                                output,
+                                format!(
+                                    "Empty alias given, using {} as the alias.",
+                                    validator_address.encode()
+                                ),
                             );
                             validator_address.encode()
                         } else {
@@ -311,10 +750,13 @@ pub async fn submit_init_validator(
                         validator_address_alias.clone(),
                         validator_address.clone(),
                     ) {
-                        println!(
-                            "Added alias {} for address {}.",
-                            new_alias,
-                            validator_address.encode()
+                        print_progress(
+                            output,
+                            format!(
+                                "Added alias {} for address {}.",
+                                new_alias,
+                                validator_address.encode()
+                            ),
                         );
                     }
                     let rewards_address_alias =
@@ -323,10 +765,13 @@ pub async fn submit_init_validator(
                         rewards_address_alias.clone(),
                         rewards_address.clone(),
                     ) {
-                        println!(
-                            "Added alias {} for address {}.",
-                            new_alias,
-                            rewards_address.encode()
+                        print_progress(
+                            output,
+                            format!(
+                                "Added alias {} for address {}.",
+                                new_alias,
+                                rewards_address.encode()
+                            ),
                         );
                     }
                     (
@@ -335,9 +780,8 @@ pub async fn submit_init_validator(
                         rewards_address_alias,
                     )
                 }
-                _ => {
-                    eprintln!("Expected two accounts to be created");
-                    safe_exit(1)
+                accounts => {
+                    return Err(Error::ValidatorAccounts(accounts.len()));
                 }
             };
         // add validator address and keys to the wallet
@@ -353,104 +797,347 @@ pub async fn submit_init_validator(
         );
         tendermint_node::write_validator_state(tendermint_home);
 
-        println!();
-        println!(
-            "The validator's addresses and keys were stored in the wallet:"
+        print_progress(output, "");
+        print_progress(
+            output,
+            "The validator's addresses and keys were stored in the wallet:",
         );
-        println!("  Validator address \"{}\"", validator_address_alias);
-        println!("  Staking reward address \"{}\"", rewards_address_alias);
-        println!("  Validator account key \"{}\"", validator_key_alias);
-        println!("  Consensus key \"{}\"", consensus_key_alias);
-        println!("  Staking reward key \"{}\"", rewards_key_alias);
-        println!(
+        for (name, alias) in [
+            ("Validator address", &validator_address_alias),
+            ("Staking reward address", &rewards_address_alias),
+            ("Validator account key", &validator_key_alias),
+            ("Consensus key", &consensus_key_alias),
+            ("Staking reward key", &rewards_key_alias),
+        ] {
+            print_progress(output, format!("  {} \"{}\"", name, alias));
+        }
+        print_progress(
+            output,
             "The ledger node has been setup to use this validator's address \
-             and consensus key."
+             and consensus key.",
         );
     } else {
-        println!("Transaction dry run. No addresses have been saved.")
+        print_progress(
+            output,
+            "Transaction dry run. No addresses have been saved.",
+        )
     }
+    Ok(())
 }
 
-pub async fn submit_transfer(ctx: Context, args: args::TxTransfer) {
+/// Submit a token transfer
+pub async fn submit_transfer(
+    ctx: Context,
+    args: args::TxTransfer,
+) -> SubmitResult {
+    let force = args.tx.force;
+    check_memo(&args.memo)?;
     let source = ctx.get(&args.source);
     // Check that the source address exists on chain
     let source_exists =
         rpc::known_address(&source, args.tx.ledger_address.clone()).await;
     if !source_exists {
-        eprintln!("The source address {} doesn't exist on chain.", source);
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        check(force, Err(Error::SourceNotFound(source.clone())))?;
     }
     let target = ctx.get(&args.target);
     // Check that the target address exists on chain
     let target_exists =
         rpc::known_address(&target, args.tx.ledger_address.clone()).await;
     if !target_exists {
-        eprintln!("The target address {} doesn't exist on chain.", target);
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        check(force, Err(Error::TargetNotFound(target.clone())))?;
     }
     let token = ctx.get(&args.token);
     // Check that the token address exists on chain
     let token_exists =
         rpc::known_address(&token, args.tx.ledger_address.clone()).await;
     if !token_exists {
-        eprintln!("The token address {} doesn't exist on chain.", token);
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        check(force, Err(Error::TokenNotFound(token.clone())))?;
     }
     // Check source balance
-    let balance_key = token::balance_key(&token, &source);
     let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
-    match rpc::query_storage_value::<token::Amount>(&client, &balance_key).await
-    {
-        Some(balance) => {
-            if balance < args.amount {
-                eprintln!(
-                    "The balance of the source {} of token {} is lower than \
-                     the amount to be transferred. Amount to transfer is {} \
-                     and the balance is {}.",
-                    source, token, args.amount, balance
-                );
-                if !args.tx.force {
-                    safe_exit(1)
-                }
-            }
+    let balance = rpc::token::get_token_balance(&client, &token, &source).await;
+    check(force, check_balance(&source, &token, args.amount, balance))?;
+    let tx_code = ctx.read_wasm(TX_TRANSFER_WASM);
+    let transfer = token::Transfer {
+        source,
+        target,
+        token,
+        amount: args.amount,
+        memo: args.memo,
+    };
+    tracing::debug!("Transfer data {:?}", transfer);
+    let data = schema::encode(&transfer);
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await
+}
+
+/// Check that the memo of a transfer is accepted by the token VP
+fn check_memo(memo: &Option<String>) -> Result<(), Error> {
+    match memo {
+        Some(memo) if memo.len() > token::MAX_MEMO_LEN => {
+            Err(Error::MemoTooLong(memo.len()))
         }
-        None => {
-            eprintln!(
-                "No balance found for the source {} of token {}",
-                source, token
-            );
-            if !args.tx.force {
-                safe_exit(1)
+        _ => Ok(()),
+    }
+}
+
+/// A target in the JSON file of a multi-target transfer
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DisbursementTarget {
+    /// The target's address or alias
+    target: String,
+    amount: token::Amount,
+    #[serde(default)]
+    memo: Option<String>,
+}
+
+/// Submit a token transfer from the source to many targets, which are read
+/// from a JSON file
+pub async fn submit_multi_transfer(
+    ctx: Context,
+    args: args::TxMultiTransfer,
+) -> SubmitResult {
+    let force = args.tx.force;
+    let path = args.data_path.to_string_lossy().into_owned();
+    let file = File::open(&args.data_path)
+        .map_err(|err| Error::ReadTxData(path.clone(), err))?;
+    let targets: Vec<DisbursementTarget> = serde_json::from_reader(file)
+        .map_err(|err| Error::ParseDisbursement(path, err))?;
+    if targets.is_empty() {
+        return Err(Error::EmptyDisbursement);
+    }
+
+    let source = ctx.get(&args.source);
+    // Check that the source address exists on chain
+    let source_exists =
+        rpc::known_address(&source, args.tx.ledger_address.clone()).await;
+    if !source_exists {
+        check(force, Err(Error::SourceNotFound(source.clone())))?;
+    }
+    let token = ctx.get(&args.token);
+    // Check that the token address exists on chain
+    let token_exists =
+        rpc::known_address(&token, args.tx.ledger_address.clone()).await;
+    if !token_exists {
+        check(force, Err(Error::TokenNotFound(token.clone())))?;
+    }
+    let mut entries: Vec<token::DisbursementEntry> =
+        Vec::with_capacity(targets.len());
+    for DisbursementTarget {
+        target,
+        amount,
+        memo,
+    } in targets
+    {
+        check_memo(&memo)?;
+        let target = ctx.get(&WalletAddress::new(target));
+        // Check that the target address exists on chain, once per target
+        if entries.iter().all(|entry| entry.target != target) {
+            let target_exists =
+                rpc::known_address(&target, args.tx.ledger_address.clone())
+                    .await;
+            if !target_exists {
+                check(force, Err(Error::TargetNotFound(target.clone())))?;
             }
         }
+        entries.push(token::DisbursementEntry {
+            target,
+            amount,
+            memo,
+        });
+    }
+    let disbursement = token::Disbursement {
+        source,
+        token,
+        entries,
+    };
+    let total = disbursement.total().ok_or(Error::DisbursementOverflow)?;
+    // Check source balance
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let balance = rpc::token::get_token_balance(
+        &client,
+        &disbursement.token,
+        &disbursement.source,
+    )
+    .await;
+    check(
+        force,
+        check_balance(
+            &disbursement.source,
+            &disbursement.token,
+            total,
+            balance,
+        ),
+    )?;
+    let tx_code = ctx.read_wasm(TX_MULTI_TRANSFER_WASM);
+    tracing::debug!("Disbursement data {:?}", disbursement);
+    let data = schema::encode(&disbursement);
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await
+}
+
+/// Submit a tx that withdraws tokens from a testnet faucet account. The
+/// faucet's VP lets anyone withdraw a limited amount without the faucet's
+/// signature, so the tx is signed with the target's key instead.
+pub async fn submit_faucet(ctx: Context, args: args::TxFaucet) -> SubmitResult {
+    let force = args.tx.force;
+    let faucet = ctx.get(&args.faucet);
+    // Check that the faucet address exists on chain
+    let faucet_exists =
+        rpc::known_address(&faucet, args.tx.ledger_address.clone()).await;
+    if !faucet_exists {
+        check(force, Err(Error::SourceNotFound(faucet.clone())))?;
+    }
+    let target = ctx.get(&args.target);
+    // Check that the target address exists on chain
+    let target_exists =
+        rpc::known_address(&target, args.tx.ledger_address.clone()).await;
+    if !target_exists {
+        check(force, Err(Error::TargetNotFound(target.clone())))?;
+    }
+    let token = ctx.get(&args.token);
+    // Check that the token address exists on chain
+    let token_exists =
+        rpc::known_address(&token, args.tx.ledger_address.clone()).await;
+    if !token_exists {
+        check(force, Err(Error::TokenNotFound(token.clone())))?;
     }
+    // Check that the amount is within the faucet's limit
+    let max = token::Amount::whole(FAUCET_MAX_WITHDRAWAL);
+    if args.amount > max {
+        check(
+            force,
+            Err(Error::FaucetWithdrawalTooHigh {
+                amount: args.amount,
+                max,
+            }),
+        )?;
+    }
+    // Check faucet balance
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let balance = rpc::token::get_token_balance(&client, &token, &faucet).await;
+    check(force, check_balance(&faucet, &token, args.amount, balance))?;
     let tx_code = ctx.read_wasm(TX_TRANSFER_WASM);
     let transfer = token::Transfer {
-        source,
+        source: faucet,
         target,
         token,
         amount: args.amount,
+        memo: None,
     };
-    tracing::debug!("Transfer data {:?}", transfer);
-    let data = transfer
-        .try_to_vec()
+    tracing::debug!("Faucet transfer data {:?}", transfer);
+    let data = schema::encode(&transfer);
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.target)).await
+}
+
+/// Submit a tx that transfers tokens to a receiver on another chain over IBC.
+/// The tx sends an ICS-20 fungible token packet from the given port and
+/// channel, which times out at the given height of the receiver's chain or
+/// after the given number of seconds. Without either, the packet times out
+/// in an hour.
+pub async fn submit_ibc_transfer(
+    ctx: Context,
+    args: args::TxIbcTransfer,
+) -> SubmitResult {
+    let force = args.tx.force;
+    let source = ctx.get(&args.source);
+    // Check that the source address exists on chain
+    let source_exists =
+        rpc::known_address(&source, args.tx.ledger_address.clone()).await;
+    if !source_exists {
+        check(force, Err(Error::SourceNotFound(source.clone())))?;
+    }
+    let token = ctx.get(&args.token);
+    // Check that the token address exists on chain
+    let token_exists =
+        rpc::known_address(&token, args.tx.ledger_address.clone()).await;
+    if !token_exists {
+        check(force, Err(Error::TokenNotFound(token.clone())))?;
+    }
+    // Check source balance
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let balance = rpc::token::get_token_balance(&client, &token, &source).await;
+    check(force, check_balance(&source, &token, args.amount, balance))?;
+
+    let timeout_height = match args.timeout_height {
+        Some(height) => IbcHeight::new(0, height),
+        None => IbcHeight::zero(),
+    };
+    let timeout_sec_offset =
+        match (args.timeout_height, args.timeout_sec_offset) {
+            (None, None) => Some(DEFAULT_IBC_TIMEOUT_SECS),
+            (_, offset) => offset,
+        };
+    let timeout_timestamp = match timeout_sec_offset {
+        Some(offset) => (Timestamp::now() + Duration::from_secs(offset))
+            .map_err(|_| Error::InvalidIbcTimeout(offset))?,
+        None => Timestamp::none(),
+    };
+    let msg = MsgTransfer {
+        source_port: args.port_id,
+        source_channel: args.channel_id,
+        token: Some(Coin {
+            denom: token.encode(),
+            amount: args.amount.to_string(),
+        }),
+        sender: Signer::new(source.encode()),
+        receiver: Signer::new(args.receiver),
+        timeout_height,
+        timeout_timestamp,
+    };
+    tracing::debug!("IBC transfer message {:?}", msg);
+    let mut data = vec![];
+    prost::Message::encode(&msg.to_any(), &mut data)
         .expect("Encoding tx data shouldn't fail");
 
+    let tx_code = ctx.read_wasm(TX_IBC_WASM);
     let tx = Tx::new(tx_code, Some(data));
-    process_tx(ctx, &args.tx, tx, Some(&args.source)).await;
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await
 }
 
-pub async fn submit_init_nft(ctx: Context, args: args::NftCreate) {
-    let file = File::open(&args.nft_data).expect("File must exist.");
-    let nft: Nft = serde_json::from_reader(file)
-        .expect("Couldn't deserialize nft data file");
+/// Check that the source's balance, if any, covers the amount to be spent
+fn check_balance(
+    source: &Address,
+    token: &Address,
+    amount: token::Amount,
+    balance: Option<token::Amount>,
+) -> Result<(), Error> {
+    match balance {
+        Some(balance) if balance < amount => Err(Error::BalanceTooLow {
+            owner: source.clone(),
+            token: token.clone(),
+            amount,
+            balance,
+        }),
+        Some(_) => Ok(()),
+        None => Err(Error::NoBalance {
+            owner: source.clone(),
+            token: token.clone(),
+        }),
+    }
+}
 
+/// Submit a tx that initializes a new NFT
+pub async fn submit_init_nft(
+    ctx: Context,
+    args: args::NftCreate,
+) -> SubmitResult {
+    let mut nft = nft_definition::read_nft(&args.nft_data)
+        .map_err(Error::NftDefinition)?;
+    let issues = nft_definition::check_nft(&nft);
+    if !issues.is_empty() {
+        let err = nft_definition::Error::Invalid(
+            args.nft_data.clone(),
+            nft_definition::Issues(issues),
+        );
+        check(args.tx.force, Err(Error::NftDefinition(err)))?;
+    }
+    prepare_nft_tokens(&mut nft.tokens, args.tx.force, args.hash_media).await?;
     let vp_code = match &nft.vp_path {
         Some(path) => {
             std::fs::read(path).expect("Expected a file at given code path")
@@ -467,35 +1154,70 @@ pub async fn submit_init_nft(ctx: Context, args: args::NftCreate) {
         keys: nft.keys,
         opt_keys: nft.opt_keys,
         tokens: nft.tokens,
+        royalty: nft.royalty,
     };
 
-    let data = data.try_to_vec().expect(
-        "Encoding transfer data to initialize a new account shouldn't fail",
-    );
+    let data = schema::encode(&data);
 
     let tx_code = ctx.read_wasm(TX_INIT_NFT);
 
     let tx = Tx::new(tx_code, Some(data));
-    process_tx(ctx, &args.tx, tx, signer.as_ref()).await;
+    process_tx(ctx, &args.tx, tx, signer.as_ref()).await
 }
 
-pub async fn submit_mint_nft(ctx: Context, args: args::NftMint) {
-    let file = File::open(&args.nft_data).expect("File must exist.");
-    let nft_tokens: Vec<NftToken> =
-        serde_json::from_reader(file).expect("JSON was not well-formatted");
+/// Check an NFT definition file, or a file of tokens to mint, without
+/// submitting it. All the issues found in the file are reported.
+pub fn validate_nft(args: args::NftValidate) -> Result<(), Error> {
+    let path = &args.data_path;
+    let issues = if args.tokens {
+        let tokens = nft_definition::read_nft_tokens(path)
+            .map_err(Error::NftDefinition)?;
+        let mut issues = nft_definition::check_tokens(&tokens);
+        issues.extend(nft_definition::check_metadata(&tokens));
+        issues
+    } else {
+        let nft =
+            nft_definition::read_nft(path).map_err(Error::NftDefinition)?;
+        let mut issues = nft_definition::check_nft(&nft);
+        issues.extend(nft_definition::check_metadata(&nft.tokens));
+        issues
+    };
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::NftDefinition(nft_definition::Error::Invalid(
+            path.clone(),
+            nft_definition::Issues(issues),
+        )))
+    }
+}
+
+/// Submit a tx that mints new tokens of an NFT
+pub async fn submit_mint_nft(
+    ctx: Context,
+    args: args::NftMint,
+) -> SubmitResult {
+    let nft_data = args.nft_data.as_ref().expect("The data path is required");
+    let mut nft_tokens = nft_definition::read_nft_tokens(nft_data)
+        .map_err(Error::NftDefinition)?;
+    let issues = nft_definition::check_tokens(&nft_tokens);
+    if !issues.is_empty() {
+        let err = nft_definition::Error::Invalid(
+            nft_data.clone(),
+            nft_definition::Issues(issues),
+        );
+        check(args.tx.force, Err(Error::NftDefinition(err)))?;
+    }
+    prepare_nft_tokens(&mut nft_tokens, args.tx.force, args.hash_media).await?;
 
     let nft_creator_key = nft::get_creator_key(&args.nft_address);
     let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
     let nft_creator_address =
-        match rpc::query_storage_value::<Address>(&client, &nft_creator_key)
+        rpc::query_storage_value::<Address>(&client, &nft_creator_key)
             .await
-        {
-            Some(addr) => addr,
-            None => {
-                eprintln!("No creator key found for {}", &args.nft_address);
-                safe_exit(1);
-            }
-        };
+            .ok_or_else(|| {
+                Error::NftCreatorNotFound(args.nft_address.clone())
+            })?;
 
     let signer = Some(WalletAddress::new(nft_creator_address.to_string()));
 
@@ -505,247 +1227,483 @@ pub async fn submit_mint_nft(ctx: Context, args: args::NftMint) {
         tokens: nft_tokens,
     };
 
-    let data = data.try_to_vec().expect(
-        "Encoding transfer data to initialize a new account shouldn't fail",
-    );
+    let data = schema::encode(&data);
 
     let tx_code = ctx.read_wasm(TX_MINT_NFT);
 
     let tx = Tx::new(tx_code, Some(data));
-    process_tx(ctx, &args.tx, tx, signer.as_ref()).await;
+    process_tx(ctx, &args.tx, tx, signer.as_ref()).await
 }
 
-pub async fn submit_init_proposal(mut ctx: Context, args: args::InitProposal) {
-    let file = File::open(&args.proposal_data).expect("File must exist.");
-    let proposal: Proposal =
-        serde_json::from_reader(file).expect("JSON was not well-formatted");
-
-    let signer = WalletAddress::new(proposal.clone().author.to_string());
+/// Pin the files in a directory of assets to the configured IPFS node and
+/// mint them as new tokens of an NFT, numbered after its existing tokens. The
+/// tokens are split into as many txs as needed to keep each of them under the
+/// maximum size. The txs are submitted one by one and if any of them fails,
+/// the remaining tokens are not minted.
+pub async fn submit_mint_nft_assets(
+    mut ctx: Context,
+    args: args::NftMint,
+) -> Result<(), Error> {
+    // The token IDs of the next txs depend on the previous ones
+    if args.tx.save_draft.is_some() {
+        return Err(Error::DraftUnsupported("mint-nft --assets-dir"));
+    }
+    let assets_dir = args.assets_dir.as_ref().expect("The assets are required");
+    let api_url = match &ctx.config.client.ipfs {
+        Some(ipfs) => ipfs.api_url.clone(),
+        None => return Err(Error::IpfsNotConfigured),
+    };
+    let output = ctx.global_args.output;
+    let assets =
+        nft_assets::list_assets(assets_dir).map_err(Error::NftAssets)?;
 
-    if args.offline {
-        let signer = ctx.get(&signer);
-        let signing_key = find_keypair(
-            &mut ctx.wallet,
-            &signer,
-            args.tx.ledger_address.clone(),
-        )
-        .await;
-        let offline_proposal =
-            OfflineProposal::new(proposal, signer, &signing_key);
-        let proposal_filename = "proposal".to_string();
-        let out = File::create(&proposal_filename).unwrap();
-        match serde_json::to_writer_pretty(out, &offline_proposal) {
-            Ok(_) => {
-                println!("Proposal created: {}.", proposal_filename);
-            }
-            Err(e) => {
-                eprintln!("Error while creating proposal file: {}.", e);
-                safe_exit(1)
-            }
-        }
-    } else {
-        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let nft_creator_key = nft::get_creator_key(&args.nft_address);
+    let nft_creator_address =
+        rpc::query_storage_value::<Address>(&client, &nft_creator_key)
+            .await
+            .ok_or_else(|| {
+                Error::NftCreatorNotFound(args.nft_address.clone())
+            })?;
+    let signer = WalletAddress::new(nft_creator_address.to_string());
+    let next_id = rpc::query_storage_prefix_bytes(
+        client.clone(),
+        nft::_nft_prefix(&args.nft_address),
+    )
+    .await
+    .unwrap_or_default()
+    .iter()
+    .filter_map(|value| {
+        nft::is_nft_current_owner_key(&value.key, &args.nft_address)
+    })
+    .filter_map(|(_, id)| id.parse::<u64>().ok())
+    .max()
+    .map_or(0, |id| id + 1);
+
+    let mut tokens = Vec::with_capacity(assets.len());
+    for (id, path) in (next_id..).zip(&assets) {
+        let content = nft_assets::read_asset(path).map_err(Error::NftAssets)?;
+        let cid = nft_assets::pin(&api_url, path, &content)
+            .await
+            .map_err(Error::NftAssets)?;
+        print_progress(
+            output,
+            format!("Pinned {} as {}.", path.to_string_lossy(), cid),
+        );
+        let token = nft_assets::asset_token(id, path, &cid, &content)
+            .map_err(Error::NftAssets)?;
+        tokens.push(token);
+    }
 
-        let tx_data: Result<InitProposalData, _> = proposal.clone().try_into();
-        let init_proposal_data = if let Ok(data) = tx_data {
-            data
-        } else {
-            eprintln!("Invalid data for init proposal transaction.");
-            safe_exit(1)
+    // Leave room in every tx for its code and for the rest of its data, the
+    // signature and the fee
+    let tx_code = ctx.read_wasm(TX_MINT_NFT);
+    let max_tokens_bytes = (args.max_tx_bytes as usize)
+        .saturating_sub(tx_code.len() + MINT_NFT_TX_OVERHEAD_BYTES);
+    let chunks = nft_assets::chunk_tokens(tokens, max_tokens_bytes)
+        .map_err(Error::NftAssets)?;
+    let total = chunks.len();
+    for (ix, tokens) in chunks.into_iter().enumerate() {
+        print_progress(
+            output,
+            format!(
+                "Minting the tokens {} to {} ({}/{}).",
+                tokens[0].id,
+                tokens[tokens.len() - 1].id,
+                ix + 1,
+                total
+            ),
+        );
+        let data = MintNft {
+            address: args.nft_address.clone(),
+            creator: nft_creator_address.clone(),
+            tokens,
         };
+        let data = schema::encode(&data);
+        let tx = Tx::new(tx_code.clone(), Some(data));
+        let (new_ctx, result) =
+            process_tx(ctx, &args.tx, tx, Some(&signer)).await?;
+        print_tx_result(&new_ctx, &result);
+        ctx = new_ctx;
+    }
+    Ok(())
+}
 
-        let min_proposal_funds_key = gov_storage::get_min_proposal_fund_key();
-        let min_proposal_funds: Amount =
-            rpc::query_storage_value(&client, &min_proposal_funds_key)
-                .await
-                .unwrap();
-        let balance = rpc::get_token_balance(&client, &m1t(), &proposal.author)
-            .await
-            .unwrap_or_default();
-        if balance < min_proposal_funds {
-            eprintln!(
-                "Address {} doesn't have enough funds.",
-                &proposal.author
-            );
-            safe_exit(1);
+/// Check that the metadata of NFT tokens conforms to the standard before
+/// they're submitted. With `hash_media`, the media of every token is
+/// downloaded and its hash is embedded in the token's metadata.
+async fn prepare_nft_tokens(
+    tokens: &mut [NftToken],
+    force: bool,
+    hash_media: bool,
+) -> Result<(), Error> {
+    for token in tokens {
+        let mut metadata = match token.decode_metadata() {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                check(force, Err(Error::InvalidNftMetadata(token.id, err)))?;
+                continue;
+            }
+        };
+        if !hash_media {
+            continue;
+        }
+        // Only the media served over HTTP can be fetched by the client
+        if !metadata.media_uri.starts_with("https://")
+            && !metadata.media_uri.starts_with("http://")
+        {
+            return Err(Error::NftMediaNotFetchable(
+                token.id,
+                metadata.media_uri,
+            ));
         }
-        let min_proposal_funds_key = gov_storage::get_min_proposal_fund_key();
-        let min_proposal_funds: Amount =
-            rpc::query_storage_value(&client, &min_proposal_funds_key)
+        let media = async {
+            reqwest::get(&metadata.media_uri)
+                .await?
+                .error_for_status()?
+                .bytes()
                 .await
-                .unwrap();
-
-        let balance = rpc::get_token_balance(&client, &m1t(), &proposal.author)
-            .await
-            .unwrap_or_default();
-        if balance < min_proposal_funds {
-            eprintln!(
-                "Address {} doesn't have enough funds.",
-                &proposal.author
-            );
-            safe_exit(1);
         }
+        .await
+        .map_err(|err| Error::NftMediaDownload(token.id, err))?;
+        let hash = Hash::sha256(&media).to_string();
+        if let Some(expected) = &metadata.media_hash {
+            if !expected.eq_ignore_ascii_case(&hash) {
+                return Err(Error::NftMediaHashMismatch {
+                    id: token.id,
+                    expected: expected.clone(),
+                    actual: hash,
+                });
+            }
+        }
+        metadata.media_hash = Some(hash);
+        token.metadata = metadata.encode();
+    }
+    Ok(())
+}
 
-        let data = init_proposal_data
-            .try_to_vec()
-            .expect("Encoding proposal data shouldn't fail");
-        let tx_code = ctx.read_wasm(TX_INIT_PROPOSAL);
-        let tx = Tx::new(tx_code, Some(data));
+/// Create a proposal signed by its author for offline governance. Returns the
+/// name of the file to which it has been written.
+pub async fn create_offline_proposal(
+    mut ctx: Context,
+    args: args::InitProposal,
+) -> Result<String, Error> {
+    let proposal = read_proposal(&args.proposal_data)?;
 
-        process_tx(ctx, &args.tx, tx, Some(&signer)).await;
-    }
+    let signer = WalletAddress::new(proposal.clone().author.to_string());
+    let signer = ctx.get(&signer);
+    let signing_key =
+        find_keypair(&mut ctx.wallet, &signer, args.tx.ledger_address.clone())
+            .await;
+    let offline_proposal = OfflineProposal::new(proposal, signer, &signing_key);
+    let proposal_filename = "proposal".to_string();
+    let out = File::create(&proposal_filename).unwrap();
+    serde_json::to_writer_pretty(out, &offline_proposal).map_err(|err| {
+        Error::WriteFile(proposal_filename.clone(), err.into())
+    })?;
+    Ok(proposal_filename)
 }
 
-pub async fn submit_vote_proposal(mut ctx: Context, args: args::VoteProposal) {
-    let signer = if let Some(addr) = &args.tx.signer {
-        addr
-    } else {
-        eprintln!("Missing mandatory argument --signer.");
-        safe_exit(1)
-    };
+/// Submit a tx that initializes a new governance proposal
+pub async fn submit_init_proposal(
+    ctx: Context,
+    args: args::InitProposal,
+) -> SubmitResult {
+    let proposal = read_proposal(&args.proposal_data)?;
 
-    if args.offline {
-        let signer = ctx.get(signer);
-        let proposal_file_path =
-            args.proposal_data.expect("Proposal file should exist.");
-        let file = File::open(&proposal_file_path).expect("File must exist.");
+    let signer = WalletAddress::new(proposal.clone().author.to_string());
 
-        let proposal: OfflineProposal =
-            serde_json::from_reader(file).expect("JSON was not well-formatted");
-        let public_key = rpc::get_public_key(
-            &proposal.address,
-            args.tx.ledger_address.clone(),
-        )
-        .await
-        .expect("Public key should exist.");
-        if !proposal.check_signature(&public_key) {
-            eprintln!("Proposal signature mismatch!");
-            safe_exit(1)
-        }
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
 
-        let signing_key = find_keypair(
-            &mut ctx.wallet,
-            &signer,
-            args.tx.ledger_address.clone(),
-        )
-        .await;
-        let offline_vote = OfflineVote::new(
+    let init_proposal_data: InitProposalData = proposal
+        .clone()
+        .try_into()
+        .map_err(|_| Error::InvalidProposal)?;
+
+    let current_epoch =
+        rpc::query_last_epoch(args.tx.ledger_address.clone()).await;
+    let min_period: u64 = rpc::query_storage_value(
+        &client,
+        &gov_storage::get_min_proposal_period_key(),
+    )
+    .await
+    .unwrap();
+    let min_grace_epochs: u64 = rpc::query_storage_value(
+        &client,
+        &gov_storage::get_min_proposal_grace_epoch_key(),
+    )
+    .await
+    .unwrap();
+    let max_content_size: u64 = rpc::query_storage_value(
+        &client,
+        &gov_storage::get_max_proposal_content_key(),
+    )
+    .await
+    .unwrap();
+    check(
+        args.tx.force,
+        check_proposal_epochs(
             &proposal,
-            args.vote,
-            signer.clone(),
-            &signing_key,
-        );
-
-        let proposal_vote_filename =
-            format!("proposal-vote-{}", &signer.to_string());
-        let out = File::create(&proposal_vote_filename).unwrap();
-        match serde_json::to_writer_pretty(out, &offline_vote) {
-            Ok(_) => {
-                println!("Proposal vote created: {}.", proposal_vote_filename);
-            }
-            Err(e) => {
-                eprintln!("Error while creating proposal vote file: {}.", e);
-                safe_exit(1)
-            }
-        }
-    } else {
-        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+            current_epoch,
+            min_period,
+            min_grace_epochs,
+        ),
+    )?;
+    if init_proposal_data.content.len() as u64 >= max_content_size {
+        check(
+            args.tx.force,
+            Err(Error::ProposalContentTooLarge(
+                init_proposal_data.content.len(),
+                max_content_size,
+            )),
+        )?;
+    }
 
-        let voter_address = ctx.get(signer);
-        let proposal_id = args.proposal_id.unwrap();
-        let proposal_start_epoch_key =
-            gov_storage::get_voting_start_epoch_key(proposal_id);
-        let proposal_start_epoch = rpc::query_storage_value::<Epoch>(
-            &client,
-            &proposal_start_epoch_key,
-        )
-        .await;
+    let min_proposal_funds_key = gov_storage::get_min_proposal_fund_key();
+    let min_proposal_funds: Amount =
+        rpc::query_storage_value(&client, &min_proposal_funds_key)
+            .await
+            .unwrap();
+    let balance =
+        rpc::token::get_token_balance(&client, &m1t(), &proposal.author)
+            .await
+            .unwrap_or_default();
+    if balance < min_proposal_funds {
+        return Err(Error::ProposalFundsTooLow(proposal.author));
+    }
 
-        match proposal_start_epoch {
-            Some(epoch) => {
-                let mut delegation_addresses = rpc::get_delegators_delegation(
-                    &client,
-                    &voter_address,
-                    epoch,
-                )
-                .await;
+    let data = schema::encode(&init_proposal_data);
+    let tx_code = ctx.read_wasm(TX_INIT_PROPOSAL);
+    let tx = Tx::new(tx_code, Some(data));
 
-                // Optimize by quering if a vote from a validator
-                // is equal to ours. If so, we can avoid voting, but ONLY if we
-                // are  voting in the last third of the voting
-                // window, otherwise there's  the risk of the
-                // validator changing his vote and, effectively, invalidating
-                // the delgator's vote
-                if !args.tx.force
-                    && is_safe_voting_window(
-                        args.tx.ledger_address.clone(),
-                        &client,
-                        proposal_id,
-                        epoch,
-                    )
-                    .await
-                {
-                    delegation_addresses = filter_delegations(
-                        &client,
-                        delegation_addresses,
-                        proposal_id,
-                        &args.vote,
-                    )
-                    .await;
-                }
+    process_tx(ctx, &args.tx, tx, Some(&signer)).await
+}
 
-                let tx_data = VoteProposalData {
-                    id: proposal_id,
-                    vote: args.vote,
-                    voter: voter_address,
-                    delegations: delegation_addresses,
-                };
+/// Read a proposal from a JSON file or, if it has the `toml` extension, from
+/// a TOML file
+fn read_proposal(path: &Path) -> Result<Proposal, Error> {
+    let path_str = path.to_string_lossy().into_owned();
+    let raw = std::fs::read_to_string(path)
+        .map_err(|err| Error::ReadProposal(path_str.clone(), err))?;
+    let is_toml = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("toml"))
+        .unwrap_or_default();
+    if is_toml {
+        toml::from_str(&raw)
+            .map_err(|err| Error::ParseProposal(path_str, err.to_string()))
+    } else {
+        serde_json::from_str(&raw)
+            .map_err(|err| Error::ParseProposal(path_str, err.to_string()))
+    }
+}
 
-                let data = tx_data
-                    .try_to_vec()
-                    .expect("Encoding proposal data shouldn't fail");
-                let tx_code = ctx.read_wasm(TX_VOTE_PROPOSAL);
-                let tx = Tx::new(tx_code, Some(data));
+/// Check the voting epochs of a proposal against the current epoch and the
+/// governance parameters, the same as the governance VP does
+fn check_proposal_epochs(
+    proposal: &Proposal,
+    current_epoch: Epoch,
+    min_period: u64,
+    min_grace_epochs: u64,
+) -> Result<(), Error> {
+    let Proposal {
+        voting_start_epoch: start,
+        voting_end_epoch: end,
+        grace_epoch: grace,
+        ..
+    } = proposal;
+    if *start <= current_epoch {
+        return Err(Error::InvalidProposalEpochs(format!(
+            "the voting start epoch {} must be after the current epoch {}",
+            start, current_epoch
+        )));
+    }
+    if end <= start {
+        return Err(Error::InvalidProposalEpochs(format!(
+            "the voting end epoch {} must be after the start epoch {}",
+            end, start
+        )));
+    }
+    let period = end.0 - start.0;
+    if period < min_period || period % min_period.max(1) != 0 {
+        return Err(Error::InvalidProposalEpochs(format!(
+            "the voting period of {} epochs must be a multiple of {} epochs",
+            period, min_period
+        )));
+    }
+    if grace <= end || grace.0 - end.0 < min_grace_epochs {
+        return Err(Error::InvalidProposalEpochs(format!(
+            "the grace epoch {} must be at least {} epochs after the voting \
+             end epoch {}",
+            grace, min_grace_epochs, end
+        )));
+    }
+    Ok(())
+}
 
-                process_tx(ctx, &args.tx, tx, Some(signer)).await;
-            }
-            None => {
-                eprintln!("Proposal start epoch is not in the storage.")
-            }
-        }
+/// Create a vote on a proposal for offline governance, signed by the voter.
+/// Returns the name of the file to which it has been written.
+pub async fn create_offline_vote(
+    mut ctx: Context,
+    args: args::VoteProposal,
+) -> Result<String, Error> {
+    let signer = args.tx.signer.as_ref().ok_or(Error::MissingSigner)?;
+    let signer = ctx.get(signer);
+    let proposal_file_path =
+        args.proposal_data.expect("Proposal file should exist.");
+    let file = File::open(&proposal_file_path).expect("File must exist.");
+
+    let proposal: OfflineProposal =
+        serde_json::from_reader(file).expect("JSON was not well-formatted");
+    let public_key =
+        rpc::get_public_key(&proposal.address, args.tx.ledger_address.clone())
+            .await
+            .expect("Public key should exist.");
+    if !proposal.check_signature(&public_key) {
+        return Err(Error::ProposalSignatureMismatch);
     }
+
+    let signing_key =
+        find_keypair(&mut ctx.wallet, &signer, args.tx.ledger_address.clone())
+            .await;
+    let offline_vote =
+        OfflineVote::new(&proposal, args.vote, signer.clone(), &signing_key);
+
+    let proposal_vote_filename =
+        format!("proposal-vote-{}", &signer.to_string());
+    let out = File::create(&proposal_vote_filename).unwrap();
+    serde_json::to_writer_pretty(out, &offline_vote).map_err(|err| {
+        Error::WriteFile(proposal_vote_filename.clone(), err.into())
+    })?;
+    Ok(proposal_vote_filename)
 }
 
-/// Check if current epoch is in the last third of the voting period of the
-/// proposal. This ensures that it is safe to optimize the vote writing to
-/// storage.
-async fn is_safe_voting_window(
-    ledger_address: TendermintAddress,
-    client: &HttpClient,
-    proposal_id: u64,
-    proposal_start_epoch: Epoch,
-) -> bool {
-    let current_epoch = rpc::query_epoch(args::Query { ledger_address }).await;
+/// Submit a tx that votes on a governance proposal. The voter must be a
+/// validator or a delegator and the voting period of the proposal must be
+/// open.
+pub async fn submit_vote_proposal(
+    ctx: Context,
+    args: args::VoteProposal,
+) -> SubmitResult {
+    let force = args.tx.force;
+    let signer = args.tx.signer.as_ref().ok_or(Error::MissingSigner)?;
+    let proposal_id = args.proposal_id.ok_or(Error::MissingProposalId)?;
+
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
 
+    let voter_address = ctx.get(signer);
+    let proposal_start_epoch_key =
+        gov_storage::get_voting_start_epoch_key(proposal_id);
+    let epoch =
+        rpc::query_storage_value::<Epoch>(&client, &proposal_start_epoch_key)
+            .await
+            .ok_or(Error::ProposalStartEpochNotFound)?;
     let proposal_end_epoch_key =
         gov_storage::get_voting_end_epoch_key(proposal_id);
-    let proposal_end_epoch =
-        rpc::query_storage_value::<Epoch>(client, &proposal_end_epoch_key)
+    let end_epoch =
+        rpc::query_storage_value::<Epoch>(&client, &proposal_end_epoch_key)
+            .await
+            .ok_or(Error::ProposalEndEpochNotFound)?;
+    let current_epoch =
+        rpc::query_last_epoch(args.tx.ledger_address.clone()).await;
+
+    let is_validator =
+        rpc::pos::is_validator(&voter_address, args.tx.ledger_address.clone())
             .await;
+    if !is_validator
+        && !rpc::pos::is_delegator(
+            &voter_address,
+            args.tx.ledger_address.clone(),
+        )
+        .await
+    {
+        check(
+            force,
+            Err(Error::NotValidatorOrDelegator(voter_address.clone())),
+        )?;
+    }
+    check(
+        force,
+        check_voting_period(
+            proposal_id,
+            is_validator,
+            current_epoch,
+            epoch,
+            end_epoch,
+        ),
+    )?;
 
-    match proposal_end_epoch {
-        Some(proposal_end_epoch) => {
-            !anoma::ledger::governance::vp::is_valid_validator_voting_period(
-                current_epoch,
-                proposal_start_epoch,
-                proposal_end_epoch,
-            )
-        }
-        None => {
-            eprintln!("Proposal end epoch is not in the storage.");
-            safe_exit(1)
-        }
+    let mut delegation_addresses =
+        rpc::pos::get_delegators_delegation(&client, &voter_address, epoch)
+            .await;
+
+    // Optimize by quering if a vote from a validator
+    // is equal to ours. If so, we can avoid voting, but ONLY if we
+    // are  voting in the last third of the voting
+    // window, otherwise there's  the risk of the
+    // validator changing his vote and, effectively, invalidating
+    // the delgator's vote
+    if !force
+        && !anoma::ledger::governance::vp::is_valid_validator_voting_period(
+            current_epoch,
+            epoch,
+            end_epoch,
+        )
+    {
+        delegation_addresses = filter_delegations(
+            &client,
+            delegation_addresses,
+            proposal_id,
+            &args.vote,
+        )
+        .await;
     }
+
+    let tx_data = VoteProposalData {
+        id: proposal_id,
+        vote: args.vote,
+        voter: voter_address,
+        delegations: delegation_addresses,
+    };
+
+    let data = schema::encode(&tx_data);
+    let tx_code = ctx.read_wasm(TX_VOTE_PROPOSAL);
+    let tx = Tx::new(tx_code, Some(data));
+
+    process_tx(ctx, &args.tx, tx, Some(signer)).await
+}
+
+/// Check that the voting period of a proposal is open in the current epoch,
+/// like the governance VP does. Validators can only vote in the first two
+/// thirds of the voting period.
+fn check_voting_period(
+    proposal_id: u64,
+    is_validator: bool,
+    current_epoch: Epoch,
+    start: Epoch,
+    end: Epoch,
+) -> Result<(), Error> {
+    if current_epoch < start || current_epoch > end {
+        return Err(Error::VotingPeriodNotOpen {
+            id: proposal_id,
+            start,
+            end,
+            current: current_epoch,
+        });
+    }
+    if is_validator
+        && !anoma::ledger::governance::vp::is_valid_validator_voting_period(
+            current_epoch,
+            start,
+            end,
+        )
+    {
+        return Err(Error::ValidatorVotingPeriodOver(
+            proposal_id,
+            current_epoch,
+        ));
+    }
+    Ok(())
 }
 
 /// Removes validators whose vote corresponds to that of the delegator (needless
@@ -781,19 +1739,17 @@ async fn filter_delegations(
     delegation_addresses
 }
 
-pub async fn submit_bond(ctx: Context, args: args::Bond) {
+/// Submit a tx that bonds tokens to a validator, either as a self-bond or a
+/// delegation
+pub async fn submit_bond(ctx: Context, args: args::Bond) -> SubmitResult {
+    let force = args.tx.force;
     let validator = ctx.get(&args.validator);
     // Check that the validator address exists on chain
     let is_validator =
-        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+        rpc::pos::is_validator(&validator, args.tx.ledger_address.clone())
+            .await;
     if !is_validator {
-        eprintln!(
-            "The address {} doesn't belong to any known validator account.",
-            validator
-        );
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        check(force, Err(Error::NotAValidator(validator.clone())))?;
     }
     let source = ctx.get_opt(&args.source);
     // Check that the source address exists on chain
@@ -801,10 +1757,7 @@ pub async fn submit_bond(ctx: Context, args: args::Bond) {
         let source_exists =
             rpc::known_address(source, args.tx.ledger_address.clone()).await;
         if !source_exists {
-            eprintln!("The source address {} doesn't exist on chain.", source);
-            if !args.tx.force {
-                safe_exit(1)
-            }
+            check(force, Err(Error::SourceNotFound(source.clone())))?;
         }
     }
     // Check bond's source (source for delegation or validator for self-bonds)
@@ -812,25 +1765,60 @@ pub async fn submit_bond(ctx: Context, args: args::Bond) {
     let bond_source = source.as_ref().unwrap_or(&validator);
     let balance_key = token::balance_key(&address::xan(), bond_source);
     let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
-    match rpc::query_storage_value::<token::Amount>(&client, &balance_key).await
-    {
-        Some(balance) => {
-            if balance < args.amount {
-                eprintln!(
-                    "The balance of the source {} is lower than the amount to \
-                     be transferred. Amount to transfer is {} and the balance \
-                     is {}.",
-                    bond_source, args.amount, balance
-                );
-                if !args.tx.force {
-                    safe_exit(1)
-                }
+    let balance =
+        rpc::query_storage_value::<token::Amount>(&client, &balance_key).await;
+    check(
+        force,
+        check_balance(bond_source, &address::xan(), args.amount, balance),
+    )?;
+    // Check the minimum self-bond and the delegation cap
+    if let Some(params) = rpc::pos::get_params(&client).await {
+        let self_bond_id = BondId {
+            source: validator.clone(),
+            validator: validator.clone(),
+        };
+        let self_bond_key = ledger::pos::bond_key(&self_bond_id);
+        let self_bond =
+            rpc::query_storage_value::<Bonds>(&client, &self_bond_key)
+                .await
+                .map(|bonds| sum_bonds(&bonds))
+                .unwrap_or_default();
+        if bond_source == &validator {
+            let self_bond = self_bond + args.amount;
+            let min_self_bond =
+                token::Amount::from(params.min_validator_self_bond);
+            if self_bond < min_self_bond {
+                check(
+                    force,
+                    Err(Error::SelfBondBelowMinimum {
+                        validator: validator.clone(),
+                        self_bond,
+                        min_self_bond,
+                    }),
+                )?;
             }
-        }
-        None => {
-            eprintln!("No balance found for the source {}", bond_source);
-            if !args.tx.force {
-                safe_exit(1)
+        } else if let Some(max_delegations) = params.max_validator_delegations {
+            let total_deltas_key =
+                ledger::pos::validator_total_deltas_key(&validator);
+            let total_stake: token::Change = rpc::query_storage_value::<
+                ValidatorTotalDeltas,
+            >(
+                &client, &total_deltas_key
+            )
+            .await
+            .map(|deltas| deltas.iter().sum())
+            .unwrap_or_default();
+            let delegations = (total_stake - self_bond.change()).max(0)
+                + args.amount.change();
+            if delegations > max_delegations as token::Change {
+                check(
+                    force,
+                    Err(Error::DelegationCapExceeded {
+                        validator: validator.clone(),
+                        delegations: token::Amount::from_change(delegations),
+                        max_delegations: max_delegations.into(),
+                    }),
+                )?;
             }
         }
     }
@@ -840,26 +1828,86 @@ pub async fn submit_bond(ctx: Context, args: args::Bond) {
         amount: args.amount,
         source,
     };
-    let data = bond.try_to_vec().expect("Encoding tx data shouldn't fail");
+    let data = schema::encode(&bond);
 
     let tx = Tx::new(tx_code, Some(data));
     let default_signer = args.source.as_ref().unwrap_or(&args.validator);
-    process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
+    process_tx(ctx, &args.tx, tx, Some(default_signer)).await
 }
 
-pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
+/// Sum up the bonded amounts of all the epochs of the given bonds
+fn sum_bonds(bonds: &Bonds) -> token::Amount {
+    let mut bond_amount: token::Amount = 0.into();
+    for bond in bonds.iter() {
+        for delta in bond.deltas.values() {
+            bond_amount += *delta;
+        }
+    }
+    bond_amount
+}
+
+/// Submit a batch tx that delegates tokens from the source to several
+/// validators at once, as given in a bonds file. Either all the bonds are
+/// applied or none of them.
+pub async fn submit_bond_many(
+    ctx: Context,
+    args: args::BondMany,
+) -> SubmitResult {
+    let force = args.tx.force;
+    let bonds = BondsFile::load(&args.bonds_path).map_err(Error::Batch)?;
+    let source = ctx.get(&args.source);
+    // Check that the source address exists on chain
+    let source_exists =
+        rpc::known_address(&source, args.tx.ledger_address.clone()).await;
+    if !source_exists {
+        check(force, Err(Error::SourceNotFound(source.clone())))?;
+    }
+    // Check the source's balance for all the bonds
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    let balance_key = token::balance_key(&address::xan(), &source);
+    let balance =
+        rpc::query_storage_value::<token::Amount>(&client, &balance_key).await;
+    check(
+        force,
+        check_balance(&source, &address::xan(), bonds.total_amount(), balance),
+    )?;
+
+    let tx_code = ctx.read_wasm(TX_BOND_WASM);
+    let mut ops = Vec::with_capacity(bonds.bonds.len());
+    for BondSpec { validator, amount } in bonds.bonds {
+        let validator = ctx.get(&WalletAddress::new(validator));
+        // Check that the validator address exists on chain
+        let is_validator =
+            rpc::pos::is_validator(&validator, args.tx.ledger_address.clone())
+                .await;
+        if !is_validator {
+            check(force, Err(Error::NotAValidator(validator.clone())))?;
+        }
+        let bond = pos::Bond {
+            validator,
+            amount,
+            source: Some(source.clone()),
+        };
+        ops.push(BatchOp {
+            code: tx_code.clone(),
+            data: Some(schema::encode(&bond)),
+        });
+    }
+
+    let tx = TxBatch { ops }.to_tx();
+    process_tx(ctx, &args.tx, tx, Some(&args.source)).await
+}
+
+/// Submit a tx that unbonds tokens from a validator
+pub async fn submit_unbond(ctx: Context, args: args::Unbond) -> SubmitResult {
+    let force = args.tx.force;
     let validator = ctx.get(&args.validator);
     // Check that the validator address exists on chain
     let is_validator =
-        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+        rpc::pos::is_validator(&validator, args.tx.ledger_address.clone())
+            .await;
     if !is_validator {
-        eprintln!(
-            "The address {} doesn't belong to any known validator account.",
-            validator
-        );
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        check(force, Err(Error::NotAValidator(validator.clone())))?;
     }
 
     let source = ctx.get_opt(&args.source);
@@ -874,30 +1922,38 @@ pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
     let bond_key = ledger::pos::bond_key(&bond_id);
     let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
     let bonds = rpc::query_storage_value::<Bonds>(&client, &bond_key).await;
-    match bonds {
-        Some(bonds) => {
-            let mut bond_amount: token::Amount = 0.into();
-            for bond in bonds.iter() {
-                for delta in bond.deltas.values() {
-                    bond_amount += *delta;
-                }
-            }
+    let bond_amount = bonds.as_ref().map(sum_bonds);
+    let has_bonds = match bond_amount {
+        Some(bond_amount) => {
             if args.amount > bond_amount {
-                eprintln!(
-                    "The total bonds of the source {} is lower than the \
-                     amount to be unbonded. Amount to unbond is {} and the \
-                     total bonds is {}.",
-                    bond_source, args.amount, bond_amount
-                );
-                if !args.tx.force {
-                    safe_exit(1)
-                }
+                Err(Error::BondsTooLow {
+                    owner: bond_source.clone(),
+                    amount: args.amount,
+                    bonds: bond_amount,
+                })
+            } else {
+                Ok(())
             }
         }
-        None => {
-            eprintln!("No bonds found");
-            if !args.tx.force {
-                safe_exit(1)
+        None => Err(Error::NoBonds),
+    };
+    check(force, has_bonds)?;
+    // A partial unbond of a self-bond must not leave it below the minimum
+    let bond_amount = bond_amount.unwrap_or_default();
+    if bond_source == validator && args.amount < bond_amount {
+        if let Some(params) = rpc::pos::get_params(&client).await {
+            let remaining = bond_amount - args.amount;
+            let min_self_bond =
+                token::Amount::from(params.min_validator_self_bond);
+            if remaining < min_self_bond {
+                check(
+                    force,
+                    Err(Error::RemainingSelfBondBelowMinimum {
+                        validator: validator.clone(),
+                        remaining,
+                        min_self_bond,
+                    }),
+                )?;
             }
         }
     }
@@ -907,31 +1963,28 @@ pub async fn submit_unbond(ctx: Context, args: args::Unbond) {
         amount: args.amount,
         source,
     };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+    let data = schema::encode(&data);
 
     let tx = Tx::new(tx_code, Some(data));
     let default_signer = args.source.as_ref().unwrap_or(&args.validator);
-    process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
+    process_tx(ctx, &args.tx, tx, Some(default_signer)).await
 }
 
-pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
-    let epoch = rpc::query_epoch(args::Query {
-        ledger_address: args.tx.ledger_address.clone(),
-    })
-    .await;
+/// Submit a tx that withdraws unbonded tokens that are no longer locked
+pub async fn submit_withdraw(
+    ctx: Context,
+    args: args::Withdraw,
+) -> SubmitResult {
+    let force = args.tx.force;
+    let epoch = rpc::query_last_epoch(args.tx.ledger_address.clone()).await;
 
     let validator = ctx.get(&args.validator);
     // Check that the validator address exists on chain
     let is_validator =
-        rpc::is_validator(&validator, args.tx.ledger_address.clone()).await;
+        rpc::pos::is_validator(&validator, args.tx.ledger_address.clone())
+            .await;
     if !is_validator {
-        eprintln!(
-            "The address {} doesn't belong to any known validator account.",
-            validator
-        );
-        if !args.tx.force {
-            safe_exit(1)
-        }
+        check(force, Err(Error::NotAValidator(validator.clone())))?;
     }
 
     let source = ctx.get_opt(&args.source);
@@ -940,13 +1993,13 @@ pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
     // Check the source's current unbond amount
     let bond_source = source.clone().unwrap_or_else(|| validator.clone());
     let bond_id = BondId {
-        source: bond_source.clone(),
+        source: bond_source,
         validator: validator.clone(),
     };
     let bond_key = ledger::pos::unbond_key(&bond_id);
     let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
     let unbonds = rpc::query_storage_value::<Unbonds>(&client, &bond_key).await;
-    match unbonds {
+    let has_unbonds = match unbonds {
         Some(unbonds) => {
             let mut unbonded_amount: token::Amount = 0.into();
             if let Some(unbond) = unbonds.get(epoch) {
@@ -954,42 +2007,634 @@ pub async fn submit_withdraw(ctx: Context, args: args::Withdraw) {
                     unbonded_amount += *delta;
                 }
             }
-            if unbonded_amount == 0.into() {
-                eprintln!(
-                    "There are no unbonded bonds ready to withdraw in the \
-                     current epoch {}.",
-                    epoch
-                );
-                if !args.tx.force {
-                    safe_exit(1)
+            if unbonded_amount == 0.into() {
+                Err(Error::NothingToWithdraw(epoch))
+            } else {
+                Ok(())
+            }
+        }
+        None => Err(Error::NoUnbonds),
+    };
+    check(force, has_unbonds)?;
+
+    let data = pos::Withdraw { validator, source };
+    let data = schema::encode(&data);
+
+    let tx = Tx::new(tx_code, Some(data));
+    let default_signer = args.source.as_ref().unwrap_or(&args.validator);
+    process_tx(ctx, &args.tx, tx, Some(default_signer)).await
+}
+
+/// Submit a tx that claims the staking rewards of a self-bond or a delegation.
+/// The rewards are credited to the validator's staking reward address for a
+/// self-bond or to the delegator for a delegation.
+pub async fn submit_claim_rewards(
+    ctx: Context,
+    args: args::ClaimRewards,
+) -> SubmitResult {
+    let force = args.tx.force;
+
+    let validator = ctx.get(&args.validator);
+    // Check that the validator address exists on chain
+    let is_validator =
+        rpc::pos::is_validator(&validator, args.tx.ledger_address.clone())
+            .await;
+    if !is_validator {
+        check(force, Err(Error::NotAValidator(validator.clone())))?;
+    }
+
+    let source = ctx.get_opt(&args.source);
+    let tx_code = ctx.read_wasm(TX_CLAIM_REWARDS_WASM);
+
+    // Check that the bond has some rewards to claim
+    let bond_source = source.clone().unwrap_or_else(|| validator.clone());
+    let bond_id = BondId {
+        source: bond_source,
+        validator: validator.clone(),
+    };
+    let rewards_key = ledger::pos::bond_rewards_key(&bond_id);
+    let client = HttpClient::new(args.tx.ledger_address.clone())
+        .map_err(Error::LedgerClient)?;
+    let rewards =
+        rpc::query_storage_value::<token::Amount>(&client, &rewards_key)
+            .await
+            .unwrap_or_default();
+    if rewards == 0.into() {
+        check(force, Err(Error::NoRewards(bond_id)))?;
+    } else {
+        print_progress(
+            ctx.global_args.output,
+            format!("Claiming rewards {}", rewards),
+        );
+    }
+
+    let data = pos::ClaimRewards { validator, source };
+    let data = schema::encode(&data);
+
+    let tx = Tx::new(tx_code, Some(data));
+    let default_signer = args.source.as_ref().unwrap_or(&args.validator);
+    process_tx(ctx, &args.tx, tx, Some(default_signer)).await
+}
+
+/// Submit a tx that enables or disables auto-compounding of the staking
+/// rewards of a self-bond or a delegation. With auto-compounding, the rewards
+/// are re-bonded to the validator at the end of each epoch.
+pub async fn submit_auto_compound(
+    ctx: Context,
+    args: args::AutoCompound,
+) -> SubmitResult {
+    let force = args.tx.force;
+
+    let validator = ctx.get(&args.validator);
+    // Check that the validator address exists on chain
+    let is_validator =
+        rpc::pos::is_validator(&validator, args.tx.ledger_address.clone())
+            .await;
+    if !is_validator {
+        check(force, Err(Error::NotAValidator(validator.clone())))?;
+    }
+
+    let source = ctx.get_opt(&args.source);
+    let tx_code = ctx.read_wasm(TX_AUTO_COMPOUND_WASM);
+
+    // Auto-compounding can only be enabled for an existing bond
+    let auto_compound = !args.disable;
+    if auto_compound {
+        let bond_source = source.clone().unwrap_or_else(|| validator.clone());
+        let bond_id = BondId {
+            source: bond_source,
+            validator: validator.clone(),
+        };
+        let bond_key = ledger::pos::bond_key(&bond_id);
+        let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+        let bonds = rpc::query_storage_value::<Bonds>(&client, &bond_key).await;
+        if bonds.is_none() {
+            check(force, Err(Error::NoBonds))?;
+        }
+    }
+
+    let data = pos::AutoCompound {
+        validator,
+        source,
+        auto_compound,
+    };
+    let data = schema::encode(&data);
+
+    let tx = Tx::new(tx_code, Some(data));
+    let default_signer = args.source.as_ref().unwrap_or(&args.validator);
+    process_tx(ctx, &args.tx, tx, Some(default_signer)).await
+}
+
+/// Submit a tx that changes a validator's commission rate. The new rate takes
+/// effect at the pipeline offset.
+pub async fn submit_change_commission(
+    ctx: Context,
+    args: args::ChangeCommissionRate,
+) -> SubmitResult {
+    let force = args.tx.force;
+    let epoch = rpc::query_last_epoch(args.tx.ledger_address.clone()).await;
+
+    let validator = ctx.get(&args.validator);
+    // Check that the validator address exists on chain
+    let is_validator =
+        rpc::pos::is_validator(&validator, args.tx.ledger_address.clone())
+            .await;
+    if !is_validator {
+        check(force, Err(Error::NotAValidator(validator.clone())))?;
+    }
+
+    let new_rate = BasisPoints::new(args.rate);
+    if new_rate > BasisPoints::new(10_000) {
+        check(force, Err(Error::CommissionRateTooHigh(new_rate)))?;
+    }
+    // Check the change from the rate in the epoch before the pipeline offset,
+    // which is 0 if the validator has never set its rate
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    if let Some(params) = rpc::pos::get_params(&client).await {
+        let rates_key = ledger::pos::validator_commission_rate_key(&validator);
+        let previous_rate =
+            rpc::query_storage_value::<ValidatorCommissionRates>(
+                &client, &rates_key,
+            )
+            .await
+            .and_then(|rates| {
+                rates.get(epoch + (params.pipeline_len - 1)).copied()
+            })
+            .unwrap_or_else(|| BasisPoints::new(0));
+        let change = new_rate.abs_diff(previous_rate);
+        if change > params.max_commission_rate_change {
+            check(
+                force,
+                Err(Error::CommissionRateChangeTooLarge {
+                    validator: validator.clone(),
+                    change,
+                    max_change: params.max_commission_rate_change,
+                }),
+            )?;
+        }
+    }
+
+    let tx_code = ctx.read_wasm(TX_CHANGE_COMMISSION_WASM);
+    let data = pos::CommissionChange {
+        validator,
+        new_rate: args.rate,
+    };
+    let data = schema::encode(&data);
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await
+}
+
+/// Submit a tx to unjail a validator. The validator must be jailed and its
+/// jail period must be over by the pipeline epoch.
+pub async fn submit_unjail(ctx: Context, args: args::Unjail) -> SubmitResult {
+    let force = args.tx.force;
+    let epoch = rpc::query_last_epoch(args.tx.ledger_address.clone()).await;
+
+    let validator = ctx.get(&args.validator);
+    // Check that the validator address exists on chain
+    let is_validator =
+        rpc::pos::is_validator(&validator, args.tx.ledger_address.clone())
+            .await;
+    if !is_validator {
+        check(force, Err(Error::NotAValidator(validator.clone())))?;
+    }
+
+    // Check the validator's state at the pipeline offset, at which it would
+    // re-enter the validator set
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+    if let Some(params) = rpc::pos::get_params(&client).await {
+        let pipeline_epoch = epoch + params.pipeline_len;
+        let state_key = ledger::pos::validator_state_key(&validator);
+        let state =
+            rpc::query_storage_value::<ValidatorStates>(&client, &state_key)
+                .await
+                .and_then(|states| states.get(pipeline_epoch).copied());
+        match state {
+            Some(ValidatorState::Jailed { until }) => {
+                let until = Epoch::from(until);
+                if until > pipeline_epoch {
+                    check(
+                        force,
+                        Err(Error::JailPeriodNotOver {
+                            validator: validator.clone(),
+                            until,
+                            pipeline_epoch,
+                        }),
+                    )?;
+                }
+            }
+            _ => {
+                check(
+                    force,
+                    Err(Error::ValidatorNotJailed(validator.clone())),
+                )?;
+            }
+        }
+    }
+
+    let tx_code = ctx.read_wasm(TX_UNJAIL_WASM);
+    let data = schema::encode(&validator);
+
+    let tx = Tx::new(tx_code, Some(data));
+    process_tx(ctx, &args.tx, tx, Some(&args.validator)).await
+}
+
+/// Apply an application manifest by submitting the transactions needed to get
+/// from the state on chain to the state described by the manifest. The
+/// transactions are submitted one by one and if any of them fails, applying
+/// the manifest again continues from there. In dry run, only the planned
+/// transactions are printed.
+pub async fn submit_apply(
+    mut ctx: Context,
+    args: args::Apply,
+) -> Result<(), Error> {
+    // The steps of a manifest depend on the state after the previous ones
+    if args.tx.save_draft.is_some() {
+        return Err(Error::DraftUnsupported("apply"));
+    }
+    let manifest =
+        Manifest::load(&args.manifest_path).map_err(Error::Manifest)?;
+    let output = ctx.global_args.output;
+    let client = HttpClient::new(args.tx.ledger_address.clone()).unwrap();
+
+    // Find the current state of the manifest's accounts on chain
+    let mut state = ChainState::default();
+    let mut vp_codes = HashMap::new();
+    for (alias, _account, vp) in manifest.all_accounts() {
+        let vp_code = ctx.read_wasm(vp);
+        // Validate the VP code
+        check(
+            args.tx.force,
+            vm::validate_untrusted_wasm(&vp_code)
+                .map_err(|err| Error::InvalidAccountVp(alias.clone(), err)),
+        )?;
+        vp_codes.insert(alias.clone(), vp_code);
+        if let Some(address) = ctx.wallet.find_address(alias) {
+            let key = storage::Key::validity_predicate(address);
+            if let Some(vp_code) = rpc::query_storage_bytes(&client, &key).await
+            {
+                state.vps.insert(alias.clone(), vp_code);
+            }
+        }
+    }
+    for transfer in &manifest.transfers {
+        let target = find_address(&ctx, &transfer.target);
+        let token = find_address(&ctx, &transfer.token);
+        if let (Some(target), Some(token)) = (target, token) {
+            if let Some(balance) =
+                rpc::token::get_token_balance(&client, &token, &target).await
+            {
+                state.balances.insert(
+                    (transfer.target.clone(), transfer.token.clone()),
+                    balance,
+                );
+            }
+        }
+    }
+
+    let plan = manifest.plan(&state, &vp_codes).map_err(Error::Manifest)?;
+    if plan.is_empty() {
+        print_progress(output, "The application is up to date.");
+        return Ok(());
+    }
+    print_progress(
+        output,
+        format!(
+            "Applying the manifest requires {} transaction{}:",
+            plan.len(),
+            if plan.len() == 1 { "" } else { "s" }
+        ),
+    );
+    for (ix, step) in plan.iter().enumerate() {
+        print_progress(output, format!("{}. {}", ix + 1, step));
+    }
+    if args.tx.dry_run {
+        print_progress(
+            output,
+            "Transaction dry run. No transactions have been submitted.",
+        );
+        return Ok(());
+    }
+
+    for step in plan {
+        print_progress(output, &step);
+        ctx = match step {
+            Step::InitAccount {
+                alias,
+                source,
+                public_key,
+                vp: _,
+            } => {
+                let public_key =
+                    ctx.get_cached(&WalletPublicKey::new(public_key));
+                let vp_code = vp_codes.remove(&alias).unwrap();
+                let tx_code = ctx.read_wasm(TX_INIT_ACCOUNT_WASM);
+                let data = InitAccount {
+                    public_key,
+                    vp_code,
+                };
+                let data = schema::encode(&data);
+                let tx = Tx::new(tx_code, Some(data));
+                let tx_args = args::Tx {
+                    initialized_account_alias: Some(alias),
+                    ..args.tx.clone()
+                };
+                let source = WalletAddress::new(source);
+                let (ctx, result) =
+                    process_tx(ctx, &tx_args, tx, Some(&source)).await?;
+                print_tx_result(&ctx, &result);
+                save_initialized_accounts(
+                    ctx,
+                    &tx_args,
+                    result.initialized_accounts(),
+                )
+                .await
+            }
+            Step::UpdateVp { alias, vp: _ } => {
+                let vp_code = vp_codes.remove(&alias).unwrap();
+                let alias = WalletAddress::new(alias);
+                let addr = ctx.get(&alias);
+                let tx_code = ctx.read_wasm(TX_UPDATE_VP_WASM);
+                let data = UpdateVp { addr, vp_code };
+                let data = schema::encode(&data);
+                let tx = Tx::new(tx_code, Some(data));
+                let (ctx, result) =
+                    process_tx(ctx, &args.tx, tx, Some(&alias)).await?;
+                print_tx_result(&ctx, &result);
+                ctx
+            }
+            Step::Transfer {
+                source,
+                target,
+                token: token_alias,
+                amount,
+            } => {
+                let source = WalletAddress::new(source);
+                let transfer = token::Transfer {
+                    source: ctx.get(&source),
+                    target: ctx.get(&WalletAddress::new(target)),
+                    token: ctx.get(&WalletAddress::new(token_alias)),
+                    amount,
+                    memo: None,
+                };
+                let tx_code = ctx.read_wasm(TX_TRANSFER_WASM);
+                let data = schema::encode(&transfer);
+                let tx = Tx::new(tx_code, Some(data));
+                let (ctx, result) =
+                    process_tx(ctx, &args.tx, tx, Some(&source)).await?;
+                print_tx_result(&ctx, &result);
+                ctx
+            }
+        };
+    }
+    print_progress(output, "The manifest has been applied.");
+    Ok(())
+}
+
+/// Submit a batch of operations from a batch file in a single transaction
+/// that applies all of them atomically. Unlike the transactions submitted on
+/// their own, the operations are not checked against the state on chain
+/// before they're submitted, use `--dry-run` for that.
+pub async fn submit_batch(ctx: Context, args: args::TxBatch) -> SubmitResult {
+    let batch = BatchFile::load(&args.batch_path).map_err(Error::Batch)?;
+    let mut ops = Vec::with_capacity(batch.ops.len());
+    for op in &batch.ops {
+        let op = match op {
+            OpSpec::Transfer {
+                source,
+                target,
+                token: token_alias,
+                amount,
+            } => {
+                let transfer = token::Transfer {
+                    source: ctx.get(&WalletAddress::new(source.clone())),
+                    target: ctx.get(&WalletAddress::new(target.clone())),
+                    token: ctx.get(&WalletAddress::new(token_alias.clone())),
+                    amount: *amount,
+                    memo: None,
+                };
+                BatchOp {
+                    code: ctx.read_wasm(TX_TRANSFER_WASM),
+                    data: Some(schema::encode(&transfer)),
+                }
+            }
+            OpSpec::Bond {
+                validator,
+                source,
+                amount,
+            }
+            | OpSpec::Unbond {
+                validator,
+                source,
+                amount,
+            } => {
+                let bond = pos::Bond {
+                    validator: ctx.get(&WalletAddress::new(validator.clone())),
+                    amount: *amount,
+                    source: source.as_ref().map(|source| {
+                        ctx.get(&WalletAddress::new(source.clone()))
+                    }),
+                };
+                let code = if matches!(op, OpSpec::Bond { .. }) {
+                    TX_BOND_WASM
+                } else {
+                    TX_UNBOND_WASM
+                };
+                BatchOp {
+                    code: ctx.read_wasm(code),
+                    data: Some(schema::encode(&bond)),
                 }
             }
-        }
-        None => {
-            eprintln!("No unbonded bonds found");
-            if !args.tx.force {
-                safe_exit(1)
+            OpSpec::Withdraw { validator, source } => {
+                let withdraw = pos::Withdraw {
+                    validator: ctx.get(&WalletAddress::new(validator.clone())),
+                    source: source.as_ref().map(|source| {
+                        ctx.get(&WalletAddress::new(source.clone()))
+                    }),
+                };
+                BatchOp {
+                    code: ctx.read_wasm(TX_WITHDRAW_WASM),
+                    data: Some(schema::encode(&withdraw)),
+                }
             }
-        }
+            OpSpec::UpdateVp { address, vp } => {
+                let addr = ctx.get(&WalletAddress::new(address.clone()));
+                let vp_code = ctx.read_wasm(vp);
+                // Validate the VP code
+                check(
+                    args.tx.force,
+                    vm::validate_untrusted_wasm(&vp_code)
+                        .map_err(Error::InvalidVp),
+                )?;
+                let data = UpdateVp { addr, vp_code };
+                BatchOp {
+                    code: ctx.read_wasm(TX_UPDATE_VP_WASM),
+                    data: Some(schema::encode(&data)),
+                }
+            }
+            OpSpec::Custom { code, data } => BatchOp {
+                code: ctx.read_wasm(code),
+                data: data.as_ref().map(|data_path| {
+                    std::fs::read(data_path)
+                        .expect("Expected a file at given data path")
+                }),
+            },
+        };
+        ops.push(op);
     }
 
-    let data = pos::Withdraw { validator, source };
-    let data = data.try_to_vec().expect("Encoding tx data shouldn't fail");
+    let tx = TxBatch { ops }.to_tx();
+    let default_signer =
+        batch.default_signer().cloned().map(WalletAddress::new);
+    process_tx(ctx, &args.tx, tx, default_signer.as_ref()).await
+}
 
-    let tx = Tx::new(tx_code, Some(data));
-    let default_signer = args.source.as_ref().unwrap_or(&args.validator);
-    process_tx(ctx, &args.tx, tx, Some(default_signer)).await;
+/// Find an address given raw or as an alias in the wallet
+fn find_address(ctx: &Context, raw: &str) -> Option<Address> {
+    Address::from_str(raw)
+        .ok()
+        .or_else(|| ctx.wallet.find_address(raw).cloned())
 }
 
-/// Submit transaction and wait for result. Returns a list of addresses
-/// initialized in the transaction if any. In dry run, this is always empty.
+/// Sign and submit a transaction, or dry-run it or estimate its gas, or save
+/// it as a draft, as requested by the tx arguments. Returns the context with
+/// which the tx has been signed and the result, which depends on the
+/// broadcast mode.
 async fn process_tx(
     ctx: Context,
     args: &args::Tx,
     tx: Tx,
     default_signer: Option<&WalletAddress>,
-) -> (Context, Vec<Address>) {
+) -> SubmitResult {
+    if let Some(draft_path) = &args.save_draft {
+        return save_draft(ctx, args, tx, draft_path).await;
+    }
+    let mut middlewares = TxMiddlewares::from_config(&ctx.config);
+    let tx_info =
+        middleware_tx_info(&ctx, args, &middlewares, &tx.code, tx.data.clone());
+    if let Some(tx_info) = &tx_info {
+        middlewares.before_broadcast(tx_info)?;
+    }
+    ctx.tx_progress.report(TxProgress::Building {
+        code_hash: Hash::sha256(&tx.code).to_string(),
+    });
+    ctx.tx_progress.report(TxProgress::Signing);
     let (ctx, to_broadcast) = sign_tx(ctx, tx, args, default_signer).await;
+    let result = process_signed_tx(ctx, args, to_broadcast).await;
+    if let Some(tx_info) = &tx_info {
+        middlewares
+            .after_broadcast(tx_info, result.as_ref().map(|(_, res)| res));
+    }
+    result
+}
+
+/// Describe a tx for the client's middlewares, unless there are none or the
+/// tx is not going to be broadcast
+fn middleware_tx_info(
+    ctx: &Context,
+    args: &args::Tx,
+    middlewares: &TxMiddlewares,
+    code: &[u8],
+    data: Option<Vec<u8>>,
+) -> Option<TxInfo> {
+    if middlewares.is_empty() || args.dry_run || args.estimate_gas {
+        return None;
+    }
+    let codes = TxCodes {
+        transfer: ctx.read_wasm(TX_TRANSFER_WASM),
+        bond: ctx.read_wasm(TX_BOND_WASM),
+        ibc: ctx.read_wasm(TX_IBC_WASM),
+    };
+    Some(TxInfo::new(code, data, &codes))
+}
+
+/// Save a tx as a draft instead of submitting it. The tx is only signed if
+/// the signing key or the signer is given explicitly, so that the draft can
+/// be prepared by someone who cannot sign it.
+async fn save_draft(
+    mut ctx: Context,
+    args: &args::Tx,
+    tx: Tx,
+    draft_path: &Path,
+) -> SubmitResult {
+    let draft = match find_signing_key(&mut ctx, args, None).await {
+        Some(signer) => TxDraft {
+            signed: true,
+            tx: tx.sign_with(|to_sign| {
+                signing::sign(&mut ctx.wallet, &signer, to_sign)
+            }),
+        },
+        None => TxDraft { signed: false, tx },
+    };
+    draft.save(draft_path).map_err(Error::Draft)?;
+    let result = TxBroadcastResult::Draft {
+        path: draft_path.to_owned(),
+        signed: draft.signed,
+    };
+    Ok((ctx, result))
+}
+
+/// Submit a tx saved as a draft. An unsigned draft is signed with the signing
+/// key or the signer's key, which must be given. The wrapper of a signed draft
+/// is signed by the fee payer's key, or else by the signing key or the
+/// signer's key. The draft can also be saved again, e.g. to sign it or to
+/// convert it to the other format.
+pub async fn submit_draft(
+    mut ctx: Context,
+    args: args::TxSubmitDraft,
+) -> SubmitResult {
+    let draft = TxDraft::load(&args.draft_path).map_err(Error::Draft)?;
+    if !draft.signed {
+        if args.tx.signing_key.is_none() && args.tx.signer.is_none() {
+            return Err(Error::MissingSigner);
+        }
+        return process_tx(ctx, &args.tx, draft.tx, None).await;
+    }
+    if let Some(draft_path) = &args.tx.save_draft {
+        draft.save(draft_path).map_err(Error::Draft)?;
+        let result = TxBroadcastResult::Draft {
+            path: draft_path.clone(),
+            signed: true,
+        };
+        return Ok((ctx, result));
+    }
+    let signer = match &args.tx.fee_payer {
+        Some(fee_payer) => Some(ctx.get_cached(fee_payer).into()),
+        None => find_signing_key(&mut ctx, &args.tx, None).await,
+    };
+    let signer = signer.ok_or(Error::MissingSigner)?;
+    let mut middlewares = TxMiddlewares::from_config(&ctx.config);
+    let tx_info = middleware_tx_info(
+        &ctx,
+        &args.tx,
+        &middlewares,
+        &draft.tx.code,
+        draft.payload(),
+    );
+    if let Some(tx_info) = &tx_info {
+        middlewares.before_broadcast(tx_info)?;
+    }
+    ctx.tx_progress.report(TxProgress::Building {
+        code_hash: Hash::sha256(&draft.tx.code).to_string(),
+    });
+    ctx.tx_progress.report(TxProgress::Signing);
+    let (ctx, to_broadcast) = wrap_tx(ctx, draft.tx, &args.tx, signer).await;
+    let result = process_signed_tx(ctx, &args.tx, to_broadcast).await;
+    if let Some(tx_info) = &tx_info {
+        middlewares
+            .after_broadcast(tx_info, result.as_ref().map(|(_, res)| res));
+    }
+    result
+}
+
+/// Submit a signed transaction, or dry-run it or estimate its gas, as
+/// requested by the tx arguments.
+async fn process_signed_tx(
+    ctx: Context,
+    args: &args::Tx,
+    to_broadcast: TxBroadcastData,
+) -> SubmitResult {
     // NOTE: use this to print the request JSON body:
 
     // let request =
@@ -1000,10 +2645,19 @@ async fn process_tx(
     // let request_body = request.into_json();
     // println!("HTTP request body: {}", request_body);
 
-    if args.dry_run {
+    if args.dry_run || args.estimate_gas {
         if let TxBroadcastData::DryRun(tx) = to_broadcast {
-            rpc::dry_run_tx(&args.ledger_address, tx.to_bytes()).await;
-            (ctx, vec![])
+            let result = if args.estimate_gas {
+                let gas_used =
+                    rpc::estimate_gas(&args.ledger_address, tx.to_bytes())
+                        .await;
+                TxBroadcastResult::EstimateGas { gas_used }
+            } else {
+                let result =
+                    rpc::dry_run_tx(&args.ledger_address, tx.to_bytes()).await;
+                TxBroadcastResult::DryRun(result)
+            };
+            Ok((ctx, result))
         } else {
             panic!(
                 "Expected a dry-run transaction, received a wrapper \
@@ -1011,49 +2665,280 @@ async fn process_tx(
             );
         }
     } else {
-        // Either broadcast or submit transaction and collect result into
-        // sum type
-        let result = if args.broadcast_only {
-            Left(broadcast_tx(args.ledger_address.clone(), &to_broadcast).await)
-        } else {
-            Right(submit_tx(args.ledger_address.clone(), to_broadcast).await)
+        let progress = &ctx.tx_progress;
+        if !args.after.is_empty() {
+            wait_for_dependencies(
+                &args.ledger_address,
+                &args.after,
+                args.event_timeout,
+                progress,
+            )
+            .await?;
+        }
+        if let Some(target_height) = args.target_height {
+            wait_for_target_height(
+                &args.ledger_address,
+                target_height,
+                progress,
+            )
+            .await?;
+        }
+        // The tx might have expired while waiting for the target height
+        check_expiration(args, &to_broadcast)?;
+        if let TxBroadcastData::Wrapper {
+            wrapper_hash,
+            decrypted_hash,
+            ..
+        } = &to_broadcast
+        {
+            progress.report(TxProgress::Broadcasting {
+                hash: wrapper_hash.clone(),
+                inner_hash: decrypted_hash.clone(),
+            });
+        }
+        let address = args.ledger_address.clone();
+        let result = match args.broadcast_mode {
+            args::BroadcastMode::Async => {
+                broadcast_tx_async(address, &to_broadcast).await?
+            }
+            args::BroadcastMode::Sync => {
+                let result =
+                    broadcast_tx(address, &to_broadcast, args.broadcast_retry)
+                        .await?;
+                if let TxBroadcastResult::Sync { hash, .. }
+                | TxBroadcastResult::Received { hash, .. } = &result
+                {
+                    progress
+                        .report(TxProgress::InMempool { hash: hash.clone() });
+                }
+                result
+            }
+            args::BroadcastMode::Commit => {
+                let response = submit_tx(
+                    address,
+                    to_broadcast,
+                    args.broadcast_retry,
+                    args.event_timeout,
+                    progress,
+                )
+                .await?;
+                progress.report(TxProgress::Applied(response.clone()));
+                TxBroadcastResult::Commit(response)
+            }
         };
-        // Return result based on executed operation, otherwise deal with
-        // the encountered errors uniformly
-        match result {
-            Right(Ok(result)) => (ctx, result.initialized_accounts),
-            Left(Ok(_)) => (ctx, Vec::default()),
-            Right(Err(err)) => {
-                eprintln!(
-                    "Encountered error while broadcasting transaction: {}",
-                    err
-                );
-                safe_exit(1)
+        Ok((ctx, result))
+    }
+}
+
+/// Wait until the txs with the given hashes have been applied successfully, so
+/// that a tx which depends on them can be broadcast. Fails if any of them has
+/// failed or if it hasn't been applied within the timeout.
+async fn wait_for_dependencies(
+    address: &TendermintAddress,
+    hashes: &[String],
+    timeout: Duration,
+    progress: &TxProgressHook,
+) -> Result<(), Error> {
+    progress.report(TxProgress::AwaitingDependencies {
+        hashes: hashes.to_vec(),
+    });
+    for hash in hashes {
+        tracing::info!("Waiting for the transaction {} to be applied.", hash);
+        let response = poll_applied_tx(address, hash, timeout).await?;
+        if response.code != 0.to_string() {
+            return Err(Error::DependencyFailed {
+                hash: hash.clone(),
+                code: response.code,
+                log: response.log,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// How often the ledger is queried for its last block height in
+/// [`wait_for_target_height`] when it doesn't accept websocket connections
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Wait until the chain reaches the block before the given target height, so
+/// that a tx broadcast right after it can be included in the block at the
+/// target height. The new blocks are received from a subscription, or polled
+/// for if the ledger doesn't accept websocket connections.
+async fn wait_for_target_height(
+    address: &TendermintAddress,
+    target: BlockHeight,
+    progress: &TxProgressHook,
+) -> Result<(), Error> {
+    let wait_until = target.0.saturating_sub(1);
+    let mut height = query_last_height(address).await?;
+    if height >= target.0 {
+        return Err(Error::TargetHeightPassed {
+            target,
+            current: BlockHeight(height),
+        });
+    }
+    if height >= wait_until {
+        return Ok(());
+    }
+    progress.report(TxProgress::Waiting {
+        target_height: target.0,
+    });
+    tracing::info!(
+        "Waiting for the block height {} to broadcast the transaction, the \
+         last block height is {}.",
+        wait_until,
+        height
+    );
+    #[cfg(feature = "ABCI")]
+    if let Some(mut client) = open_websocket(address, None)? {
+        client.subscribe(Query::from(EventType::NewBlock))?;
+        while height < wait_until {
+            let response = client.receive_response()?;
+            match new_block_height(&response) {
+                Some(new_height) => height = new_height,
+                None => tracing::debug!(
+                    "Ignoring a new block event without a height: {}",
+                    response
+                ),
             }
-            Left(Err(err)) => {
-                eprintln!(
-                    "Encountered error while broadcasting transaction: {}",
-                    err
-                );
-                safe_exit(1)
+        }
+        client.unsubscribe()?;
+        client.close();
+        return Ok(());
+    }
+    while height < wait_until {
+        tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+        height = query_last_height(address).await?;
+    }
+    Ok(())
+}
+
+/// Query the height of the last block committed by the ledger
+async fn query_last_height(address: &TendermintAddress) -> Result<u64, Error> {
+    let status = http_client(address)?
+        .status()
+        .await
+        .map_err(|err| WsError::Response(format!("{:?}", err)))?;
+    Ok(status.sync_info.latest_block_height.value())
+}
+
+/// Find the height of the block in a `NewBlock` event received from a
+/// subscription
+#[cfg(feature = "ABCI")]
+fn new_block_height(event: &serde_json::Value) -> Option<u64> {
+    event
+        .pointer("/data/value/block/header/height")?
+        .as_str()?
+        .parse()
+        .ok()
+}
+
+/// Refuse to broadcast a wrapper tx that has already expired, unless forced.
+fn check_expiration(
+    args: &args::Tx,
+    to_broadcast: &TxBroadcastData,
+) -> Result<(), Error> {
+    if let TxBroadcastData::Wrapper { tx, .. } = to_broadcast {
+        if let Some(expiration) = tx.expiration {
+            if tx.is_expired(DateTimeUtc::now()) {
+                let err = Error::Expired(expiration.to_rfc3339());
+                return check(args.force, Err(err));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print the result of a tx, in the output mode of the context. With the text
+/// output mode, the printed addresses are annotated with their names.
+pub fn print_tx_result(ctx: &Context, result: &TxBroadcastResult) {
+    let output = ctx.global_args.output;
+    if output == args::OutputMode::Json {
+        let json = match result {
+            // A dry run is printed without the broadcast mode
+            TxBroadcastResult::DryRun(result) => serde_json::to_string(result),
+            result => serde_json::to_string(result),
+        };
+        println!("{}", json.unwrap());
+        return;
+    }
+    let names = AddressNames::new(&ctx.wallet);
+    match result {
+        TxBroadcastResult::DryRun(result) => print!("{}", result),
+        TxBroadcastResult::EstimateGas { gas_used } => {
+            println!("Estimated gas: {}", gas_used)
+        }
+        TxBroadcastResult::Async { hash, inner_hash } => {
+            println!("Transaction sent.");
+            print_tx_hashes(hash, inner_hash);
+        }
+        TxBroadcastResult::Sync {
+            hash,
+            inner_hash,
+            response,
+        } => {
+            println!("Transaction added to mempool: {:?}", response);
+            print_tx_hashes(hash, inner_hash);
+        }
+        TxBroadcastResult::Received {
+            hash,
+            inner_hash,
+            included,
+        } => {
+            match included {
+                Some(included) => println!(
+                    "Transaction included in block {} with result: {:?}",
+                    included.height, included.tx_result
+                ),
+                None => println!("Transaction already received by the ledger."),
             }
+            print_tx_hashes(hash, inner_hash);
+        }
+        TxBroadcastResult::Draft { path, signed } => {
+            println!(
+                "{} transaction draft saved to {}.",
+                if *signed { "Signed" } else { "Unsigned" },
+                path.to_string_lossy()
+            );
         }
+        TxBroadcastResult::Commit(response) => {
+            println!(
+                "Transaction applied with result: {}",
+                names.annotate_text(
+                    &serde_json::to_string_pretty(response).unwrap()
+                )
+            )
+        }
+    }
+}
+
+/// Print a message about the progress of a transaction. With the JSON output
+/// mode, the message goes to stderr to keep stdout parseable.
+fn print_progress(output: args::OutputMode, msg: impl std::fmt::Display) {
+    match output {
+        args::OutputMode::Text => println!("{}", msg),
+        args::OutputMode::Json => eprintln!("{}", msg),
     }
 }
 
-/// Save accounts initialized from a tx into the wallet, if any.
-async fn save_initialized_accounts(
+/// Save accounts initialized from a tx into the wallet, if any, prompting for
+/// their aliases if not given. Returns the context with the updated wallet.
+pub async fn save_initialized_accounts(
     mut ctx: Context,
     args: &args::Tx,
     initialized_accounts: Vec<Address>,
-) {
+) -> Context {
+    let output = ctx.global_args.output;
     let len = initialized_accounts.len();
     if len != 0 {
         // Store newly initialized account addresses in the wallet
-        println!(
-            "The transaction initialized {} new account{}",
-            len,
-            if len == 1 { "" } else { "s" }
+        print_progress(
+            output,
+            format!(
+                "The transaction initialized {} new account{}",
+                len,
+                if len == 1 { "" } else { "s" }
+            ),
         );
         // Store newly initialized account addresses in the wallet
         let wallet = &mut ctx.wallet;
@@ -1073,8 +2958,16 @@ async fn save_initialized_accounts(
                     }
                 }
                 None => {
-                    print!("Choose an alias for {}: ", encoded);
-                    io::stdout().flush().await.unwrap();
+                    match output {
+                        args::OutputMode::Text => {
+                            print!("Choose an alias for {}: ", encoded);
+                            io::stdout().flush().await.unwrap();
+                        }
+                        args::OutputMode::Json => {
+                            eprint!("Choose an alias for {}: ", encoded);
+                            io::stderr().flush().await.unwrap();
+                        }
+                    }
                     let mut alias = String::new();
                     io::stdin().read_line(&mut alias).await.unwrap();
                     alias.trim().to_owned().into()
@@ -1084,31 +2977,86 @@ async fn save_initialized_accounts(
             let added = wallet.add_address(alias.clone(), address.clone());
             match added {
                 Some(new_alias) if new_alias != encoded => {
-                    println!(
-                        "Added alias {} for address {}.",
-                        new_alias, encoded
+                    print_progress(
+                        output,
+                        format!(
+                            "Added alias {} for address {}.",
+                            new_alias, encoded
+                        ),
                     );
                 }
-                _ => println!("No alias added for address {}.", encoded),
+                _ => print_progress(
+                    output,
+                    format!("No alias added for address {}.", encoded),
+                ),
             };
         }
         if !args.dry_run {
             wallet.save().unwrap_or_else(|err| eprintln!("{}", err));
         } else {
-            println!("Transaction dry run. No addresses have been saved.")
+            print_progress(
+                output,
+                "Transaction dry run. No addresses have been saved.",
+            )
+        }
+    }
+    ctx
+}
+
+/// Broadcast a transaction without waiting for any result. Returns the hashes
+/// of the transaction.
+///
+/// In the case of errors in sending the transaction, an error message is
+/// returned
+pub async fn broadcast_tx_async(
+    address: TendermintAddress,
+    to_broadcast: &TxBroadcastData,
+) -> Result<TxBroadcastResult, Error> {
+    let (tx, wrapper_tx_hash, decrypted_tx_hash) = match to_broadcast {
+        TxBroadcastData::Wrapper {
+            tx,
+            wrapper_hash,
+            decrypted_hash,
+        } => (tx, wrapper_hash, decrypted_hash),
+        _ => panic!("Cannot broadcast a dry-run transaction"),
+    };
+    let response = match open_websocket(&address, None)? {
+        Some(mut client) => {
+            let response =
+                client.broadcast_tx_async(tx.to_bytes().into()).await;
+            client.close();
+            response
+        }
+        None => {
+            http_client(&address)?
+                .broadcast_tx_async(tx.to_bytes().into())
+                .await
         }
     }
+    .map_err(|err| WsError::Response(format!("{:?}", err)))?;
+
+    tracing::debug!("Transaction sent: {:?}", response);
+    Ok(TxBroadcastResult::Async {
+        hash: wrapper_tx_hash.clone(),
+        inner_hash: decrypted_tx_hash.clone(),
+    })
 }
 
 /// Broadcast a transaction to be included in the blockchain and checks that
-/// the tx has been successfully included into the mempool of a validator
+/// the tx has been successfully included into the mempool of a validator.
+///
+/// If the connection to the ledger fails or its mempool is full, the broadcast
+/// is retried according to the given `retry` policy. Any other error is
+/// returned right away. Before each retry, we check that the tx hasn't already
+/// been included in a block, so that it's never submitted twice.
 ///
 /// In the case of errors in any of those stages, an error message is returned
 pub async fn broadcast_tx(
     address: TendermintAddress,
     to_broadcast: &TxBroadcastData,
-) -> Result<Response, WsError> {
-    let (tx, wrapper_tx_hash, _decrypted_tx_hash) = match to_broadcast {
+    retry: args::BroadcastRetry,
+) -> Result<TxBroadcastResult, Error> {
+    let (tx, wrapper_tx_hash, decrypted_tx_hash) = match to_broadcast {
         TxBroadcastData::Wrapper {
             tx,
             wrapper_hash,
@@ -1116,32 +3064,170 @@ pub async fn broadcast_tx(
         } => (tx, wrapper_hash, decrypted_hash),
         _ => panic!("Cannot broadcast a dry-run transaction"),
     };
-    let mut wrapper_tx_subscription = TendermintWebsocketClient::open(
+    let tx_bytes = tx.to_bytes();
+    // The hash under which Tendermint knows the tx
+    let tm_tx_hash = TmTxHash::new(hash_tx(&tx_bytes).0);
+
+    let mut attempt = 1;
+    let received = loop {
+        let err = match broadcast_tx_sync(&address, tx_bytes.clone()).await {
+            Ok(response) => break Received::Checked(response),
+            Err(err) => err,
+        };
+        // Re-submitting a tx that is already in the mempool cache is
+        // rejected, which means that a previous attempt has succeeded
+        if attempt > 1 && err.to_string().contains(TX_IN_CACHE_ERR) {
+            break Received::Included(None);
+        }
+        if attempt >= retry.max_attempts
+            || !matches!(err, WsError::Unavailable(_))
+        {
+            return Err(err.into());
+        }
+        let delay = retry.delay(attempt);
+        tracing::warn!(
+            "Failed to broadcast the transaction (attempt {} of {}): {}. \
+             Retrying in {:?}.",
+            attempt,
+            retry.max_attempts,
+            err,
+            delay
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+        // The tx might have been received despite the failure
+        if let Some(response) = query_included_tx(&address, tm_tx_hash).await {
+            break Received::Included(Some(response));
+        }
+    };
+
+    match received {
+        Received::Checked(response) if response.code == 0.into() => {
+            Ok(TxBroadcastResult::Sync {
+                hash: wrapper_tx_hash.clone(),
+                inner_hash: decrypted_tx_hash.clone(),
+                response,
+            })
+        }
+        Received::Checked(response) => {
+            Err(WsError::Response(response.log.to_string()).into())
+        }
+        Received::Included(included) => Ok(TxBroadcastResult::Received {
+            hash: wrapper_tx_hash.clone(),
+            inner_hash: decrypted_tx_hash.clone(),
+            included,
+        }),
+    }
+}
+
+/// How a broadcast tx has been received by the ledger
+enum Received {
+    /// The tx has been checked before it's added to the mempool
+    Checked(Response),
+    /// A previous attempt to broadcast the tx has succeeded. The tx is known
+    /// if it has already been included in a block.
+    Included(Option<TxQueryResponse>),
+}
+
+/// The error message with which Tendermint rejects a tx that it has already
+/// received
+const TX_IN_CACHE_ERR: &str = "tx already exists in cache";
+
+/// The error message with which Tendermint rejects a tx when its mempool is
+/// full
+const MEMPOOL_FULL_ERR: &str = "mempool is full";
+
+/// Send the tx bytes to the ledger and wait for the result of its mempool
+/// validation
+async fn broadcast_tx_sync(
+    address: &TendermintAddress,
+    tx_bytes: Vec<u8>,
+) -> Result<Response, WsError> {
+    let response = match open_websocket(address, None)? {
+        Some(mut client) => {
+            let response = client.broadcast_tx_sync(tx_bytes.into()).await;
+            client.close();
+            response
+        }
+        None => {
+            http_client(address)?
+                .broadcast_tx_sync(tx_bytes.into())
+                .await
+        }
+    };
+    response.map_err(|err| {
+        if is_transient(&err) {
+            WsError::Unavailable(format!("{:?}", err))
+        } else {
+            WsError::Response(format!("{:?}", err))
+        }
+    })
+}
+
+/// Whether an error from the ledger's RPC may go away if the request is
+/// retried, i.e. the ledger couldn't be reached or its mempool is full
+fn is_transient(err: &TmRpcError) -> bool {
+    match err.detail() {
+        RpcErrorDetail::Response(detail) => {
+            detail.source.to_string().contains(MEMPOOL_FULL_ERR)
+        }
+        RpcErrorDetail::Io(_)
+        | RpcErrorDetail::Http(_)
+        | RpcErrorDetail::Hyper(_)
+        | RpcErrorDetail::WebSocket(_)
+        | RpcErrorDetail::WebSocketTimeout(_) => true,
+        _ => false,
+    }
+}
+
+/// Open a websocket connection to the ledger. Returns `None` if the ledger
+/// doesn't accept it, as many public nodes only expose HTTP RPC, in which
+/// case the caller should fall back to an HTTP client.
+fn open_websocket(
+    address: &TendermintAddress,
+    connection_timeout: Option<Duration>,
+) -> Result<Option<TendermintWebsocketClient>, WsError> {
+    match TendermintWebsocketClient::open(
         WebSocketAddress::try_from(address.clone())?,
-        None,
-    )?;
+        connection_timeout,
+    ) {
+        Ok(client) => Ok(Some(client)),
+        Err(err) => {
+            tracing::debug!(
+                "Cannot open a websocket connection to the ledger, falling \
+                 back to HTTP RPC: {}",
+                err
+            );
+            Ok(None)
+        }
+    }
+}
 
-    let response = wrapper_tx_subscription
-        .broadcast_tx_sync(tx.to_bytes().into())
-        .await
-        .map_err(|err| WsError::Response(format!("{:?}", err)))?;
+/// Create an HTTP RPC client for the ledger
+fn http_client(address: &TendermintAddress) -> Result<HttpClient, WsError> {
+    HttpClient::new(address.clone())
+        .map_err(|err| WsError::Response(format!("{:?}", err)))
+}
 
-    wrapper_tx_subscription.close();
+/// Look-up a tx that has already been included in a block by its hash.
+/// Returns `None` if the tx cannot be found or the ledger cannot be reached.
+async fn query_included_tx(
+    address: &TendermintAddress,
+    hash: TmTxHash,
+) -> Option<TxQueryResponse> {
+    let client = HttpClient::new(address.clone()).ok()?;
+    client.tx(hash, false).await.ok()
+}
 
-    if response.code == 0.into() {
-        println!("Transaction added to mempool: {:?}", response);
-        // Print the transaction identifiers to enable the extraction of
-        // acceptance/application results later
-        #[cfg(not(feature = "ABCI"))]
-        {
-            println!("Wrapper transaction hash: {:?}", wrapper_tx_hash);
-            println!("Inner transaction hash: {:?}", _decrypted_tx_hash);
+/// Print the transaction identifiers to enable the extraction of
+/// acceptance/application results later
+fn print_tx_hashes(wrapper_hash: &str, inner_hash: &Option<String>) {
+    match inner_hash {
+        Some(inner_hash) => {
+            println!("Wrapper transaction hash: {:?}", wrapper_hash);
+            println!("Inner transaction hash: {:?}", inner_hash);
         }
-        #[cfg(feature = "ABCI")]
-        println!("Transaction hash: {:?}", wrapper_tx_hash);
-        Ok(response)
-    } else {
-        Err(WsError::Response(response.log.to_string()))
+        None => println!("Transaction hash: {:?}", wrapper_hash),
     }
 }
 
@@ -1152,11 +3238,17 @@ pub async fn broadcast_tx(
 /// 2. The tx with encrypted payload has been included on the blockchain
 /// 3. The decrypted payload of the tx has been included on the blockchain.
 ///
+/// If an event is not received within the given `event_timeout`, the ledger is
+/// polled for it instead for up to the same timeout.
+///
 /// In the case of errors in any of those stages, an error message is returned
 #[cfg(not(feature = "ABCI"))]
 pub async fn submit_tx(
     address: TendermintAddress,
     to_broadcast: TxBroadcastData,
+    retry: args::BroadcastRetry,
+    event_timeout: Duration,
+    progress: &TxProgressHook,
 ) -> Result<TxResponse, Error> {
     // the data for finding the relevant events
     let (_, wrapper_hash, decrypted_hash) = match &to_broadcast {
@@ -1178,33 +3270,34 @@ pub async fn submit_tx(
         .and_eq(APPLIED_QUERY_KEY, decrypted_hash.as_ref().unwrap().as_str());
 
     // broadcast the tx
-    if let Err(err) = broadcast_tx(address, &to_broadcast).await {
-        eprintln!("Encountered error while broadcasting transaction: {}", err);
-        safe_exit(1)
-    }
+    broadcast_tx(address.clone(), &to_broadcast, retry).await?;
+    progress.report(TxProgress::InMempool {
+        hash: wrapper_hash.clone(),
+    });
 
     // get the event for the wrapper tx
-    let response =
-        fetch_event(&url, wrapper_query, wrapper_hash.as_str()).await?;
-    println!(
-        "Transaction accepted with result: {}",
-        serde_json::to_string_pretty(&response).unwrap()
-    );
+    let response = fetch_tx_event(
+        &address,
+        &url,
+        wrapper_query,
+        events::TxEventQuery::Accepted(wrapper_hash.clone()),
+        event_timeout,
+    )
+    .await?;
+    tracing::debug!("Transaction accepted with result: {:?}", response);
 
     // The transaction is now on chain. We wait for it to be decrypted
     // and applied
     if response.code == 0.to_string() {
         // get the event for the inner tx
-        let response = fetch_event(
+        let response = fetch_tx_event(
+            &address,
             &url,
             tx_query,
-            decrypted_hash.as_ref().unwrap().as_str(),
+            events::TxEventQuery::Applied(decrypted_hash.clone().unwrap()),
+            event_timeout,
         )
         .await?;
-        println!(
-            "Transaction applied with result: {}",
-            serde_json::to_string_pretty(&response).unwrap()
-        );
         Ok(response)
     } else {
         tracing::warn!(
@@ -1215,6 +3308,30 @@ pub async fn submit_tx(
     }
 }
 
+/// Fetch the event of a tx from the events log of the ledger's JSON RPC,
+/// waiting for up to the `event_timeout`. If it's not found, the ledger is
+/// polled for the event instead for up to the same timeout.
+#[cfg(not(feature = "ABCI"))]
+async fn fetch_tx_event(
+    address: &TendermintAddress,
+    url: &str,
+    filter: Query,
+    tx_query: events::TxEventQuery,
+    event_timeout: Duration,
+) -> Result<TxResponse, Error> {
+    match fetch_event(url, filter, tx_query.tx_hash(), event_timeout).await {
+        Ok(response) => Ok(response),
+        Err(err) => {
+            tracing::warn!(
+                "Failed to fetch the event of the transaction: {}. Querying \
+                 the ledger for it instead.",
+                err
+            );
+            Ok(poll_tx_event(address, tx_query, event_timeout).await?)
+        }
+    }
+}
+
 /// Broadcast a transaction to be included in the blockchain.
 ///
 /// Checks that
@@ -1222,12 +3339,21 @@ pub async fn submit_tx(
 /// 2. The tx with encrypted payload has been included on the blockchain
 /// 3. The decrypted payload of the tx has been included on the blockchain.
 ///
+/// If the event of the applied tx is not received within the given
+/// `event_timeout`, e.g. because the node has dropped the subscription, the
+/// ledger is polled for the tx result instead for up to the same timeout. If
+/// the ledger doesn't accept websocket connections, the tx is submitted over
+/// HTTP RPC instead.
+///
 /// In the case of errors in any of those stages, an error message is returned
 #[cfg(feature = "ABCI")]
 pub async fn submit_tx(
     address: TendermintAddress,
     to_broadcast: TxBroadcastData,
-) -> Result<TxResponse, WsError> {
+    retry: args::BroadcastRetry,
+    event_timeout: Duration,
+    progress: &TxProgressHook,
+) -> Result<TxResponse, Error> {
     let (_, wrapper_hash, _decrypted_hash) = match &to_broadcast {
         TxBroadcastData::Wrapper {
             tx,
@@ -1236,10 +3362,19 @@ pub async fn submit_tx(
         } => (tx, wrapper_hash, decrypted_hash),
         _ => panic!("Cannot broadcast a dry-run transaction"),
     };
-    let mut wrapper_tx_subscription = TendermintWebsocketClient::open(
-        WebSocketAddress::try_from(address.clone())?,
-        None,
-    )?;
+    let mut wrapper_tx_subscription =
+        match open_websocket(&address, Some(event_timeout))? {
+            Some(client) => client,
+            None => {
+                return submit_tx_http(
+                    address,
+                    &to_broadcast,
+                    event_timeout,
+                    progress,
+                )
+                .await;
+            }
+        };
 
     // It is better to subscribe to the transaction before it is broadcast
     //
@@ -1250,21 +3385,194 @@ pub async fn submit_tx(
     wrapper_tx_subscription.subscribe(query)?;
 
     // Broadcast the supplied transaction
-    broadcast_tx(address, &to_broadcast).await?;
+    broadcast_tx(address.clone(), &to_broadcast, retry).await?;
+    progress.report(TxProgress::InMempool {
+        hash: wrapper_hash.clone(),
+    });
 
-    let parsed = {
-        let parsed = TxResponse::find_tx(
-            wrapper_tx_subscription.receive_response()?,
-            wrapper_hash,
-        );
-        println!(
-            "Transaction applied with result: {}",
-            serde_json::to_string_pretty(&parsed).unwrap()
-        );
-        Ok(parsed)
+    let parsed = match wrapper_tx_subscription.receive_response() {
+        Ok(response) => {
+            let parsed = TxResponse::find_tx(response, wrapper_hash);
+            wrapper_tx_subscription.unsubscribe()?;
+            wrapper_tx_subscription.close();
+            parsed
+        }
+        Err(err) => {
+            // The subscription cannot be relied on anymore
+            wrapper_tx_subscription.close();
+            tracing::warn!(
+                "Failed to receive the result of the transaction: {}. \
+                 Querying the ledger for it instead.",
+                err
+            );
+            poll_applied_tx(&address, wrapper_hash, event_timeout).await?
+        }
+    };
+    Ok(parsed)
+}
+
+/// Broadcast a transaction over HTTP RPC, for ledger nodes that don't expose
+/// the websocket endpoint, and wait until it's committed in a block. The
+/// result of the applied tx is then queried from the block's results.
+#[cfg(feature = "ABCI")]
+async fn submit_tx_http(
+    address: TendermintAddress,
+    to_broadcast: &TxBroadcastData,
+    event_timeout: Duration,
+    progress: &TxProgressHook,
+) -> Result<TxResponse, Error> {
+    let (tx, wrapper_hash) = match to_broadcast {
+        TxBroadcastData::Wrapper {
+            tx, wrapper_hash, ..
+        } => (tx, wrapper_hash),
+        _ => panic!("Cannot broadcast a dry-run transaction"),
     };
+    let response = http_client(&address)?
+        .broadcast_tx_commit(tx.to_bytes().into())
+        .await
+        .map_err(|err| WsError::Response(format!("{:?}", err)))?;
+    if response.check_tx.code != 0.into() {
+        return Err(WsError::Response(response.check_tx.log.to_string()).into());
+    }
+    progress.report(TxProgress::InMempool {
+        hash: wrapper_hash.clone(),
+    });
+    tracing::debug!("Transaction committed in block {}", response.height);
+    Ok(poll_applied_tx(&address, wrapper_hash, event_timeout).await?)
+}
+
+/// How often the ledger is queried for the event of a tx in
+/// [`poll_tx_event`]
+const TX_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Query the ledger for the result of a tx with the given hash until it's
+/// found or the timeout elapses, in which case the tx has not been included
+/// in a block yet.
+async fn poll_applied_tx(
+    address: &TendermintAddress,
+    tx_hash: &str,
+    timeout: Duration,
+) -> Result<TxResponse, WsError> {
+    let query = events::TxEventQuery::Applied(tx_hash.to_string());
+    poll_tx_event(address, query, timeout).await
+}
+
+/// Query the ledger for the event of a tx until it's found or the timeout
+/// elapses, in which case the tx has not been included in a block yet.
+async fn poll_tx_event(
+    address: &TendermintAddress,
+    query: events::TxEventQuery,
+    timeout: Duration,
+) -> Result<TxResponse, WsError> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match events::query_tx_response(address, query.clone()).await {
+            Ok(response) => return Ok(response),
+            Err(err) => {
+                tracing::debug!("The transaction event was not found: {}", err)
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(WsError::NotYetIncluded(query.tx_hash().clone()));
+        }
+        tokio::time::sleep(TX_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROPOSAL: &str = r#"
+author = "atest1v4ehgw36xvcyyvejgvenxs34g3zygv3jxqunjd6rxyeyys3sxy6rwvfkx4qnj33hg9qnvse4lsfctw"
+voting_start_epoch = 3
+voting_end_epoch = 6
+grace_epoch = 12
+
+[content]
+title = "A proposal"
+description = "What it's about"
+"#;
+
+    /// Test that a TOML proposal is read and that its voting epochs are
+    /// checked like in the governance VP
+    #[test]
+    fn test_read_and_check_proposal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("proposal.toml");
+        std::fs::write(&path, PROPOSAL).unwrap();
+        let proposal = read_proposal(&path).unwrap();
+        assert_eq!(proposal.content["title"], "A proposal");
+        assert_eq!(proposal.proposal_code_path, None);
+
+        check_proposal_epochs(&proposal, Epoch(2), 3, 6).unwrap();
+        // The voting must start after the current epoch
+        assert!(check_proposal_epochs(&proposal, Epoch(3), 3, 6).is_err());
+        // The voting period must be a multiple of the minimum
+        assert!(check_proposal_epochs(&proposal, Epoch(2), 2, 6).is_err());
+        // The grace epoch must be far enough after the end
+        assert!(check_proposal_epochs(&proposal, Epoch(2), 3, 7).is_err());
+
+        let path = dir.path().join("proposal.json");
+        std::fs::write(&path, PROPOSAL).unwrap();
+        assert!(matches!(
+            read_proposal(&path),
+            Err(Error::ParseProposal(_, _))
+        ));
+    }
+
+    /// Test that the block height is found in a `NewBlock` event
+    #[cfg(feature = "ABCI")]
+    #[test]
+    fn test_new_block_height() {
+        let event = serde_json::json!({
+            "query": "tm.event = 'NewBlock'",
+            "data": {
+                "type": "tendermint/event/NewBlock",
+                "value": {
+                    "block": {
+                        "header": { "chain_id": "anoma", "height": "42" }
+                    }
+                }
+            },
+            "events": { "tm.event": ["NewBlock"] }
+        });
+        assert_eq!(new_block_height(&event), Some(42));
+        assert_eq!(new_block_height(&serde_json::json!({})), None);
+    }
 
-    wrapper_tx_subscription.unsubscribe()?;
-    wrapper_tx_subscription.close();
-    parsed
+    /// Test that votes are only accepted in the voting period, which ends
+    /// earlier for validators
+    #[test]
+    fn test_check_voting_period() {
+        for is_validator in [false, true] {
+            for current in [3, 4, 5] {
+                check_voting_period(
+                    0,
+                    is_validator,
+                    Epoch(current),
+                    Epoch(3),
+                    Epoch(9),
+                )
+                .unwrap();
+            }
+            for current in [2, 10] {
+                assert!(matches!(
+                    check_voting_period(
+                        0,
+                        is_validator,
+                        Epoch(current),
+                        Epoch(3),
+                        Epoch(9)
+                    ),
+                    Err(Error::VotingPeriodNotOpen { .. })
+                ));
+            }
+        }
+        check_voting_period(0, false, Epoch(9), Epoch(3), Epoch(9)).unwrap();
+        assert!(matches!(
+            check_voting_period(0, true, Epoch(9), Epoch(3), Epoch(9)),
+            Err(Error::ValidatorVotingPeriodOver(0, Epoch(9)))
+        ));
+    }
 }
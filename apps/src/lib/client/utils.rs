@@ -9,6 +9,8 @@ use std::str::FromStr;
 use anoma::types::address;
 use anoma::types::chain::ChainId;
 use anoma::types::key::*;
+use anoma::types::matchmaker::MatchingStrategy;
+use anoma::vm;
 use borsh::BorshSerialize;
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
@@ -27,6 +29,7 @@ use tendermint_config_abci::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
 use tendermint_stable::node::Id as TendermintNodeId;
 
+use super::test_vectors;
 use crate::cli::context::ENV_VAR_WASM_DIR;
 use crate::cli::{self, args};
 use crate::config::genesis::genesis_config::{
@@ -639,6 +642,10 @@ pub fn init_network(
                         let matchmaker_config = config::Matchmaker {
                             matchmaker_path: Some(mm_code.clone().into()),
                             tx_code_path: Some(tx_code.clone().into()),
+                            strategy: MatchingStrategy::default(),
+                            batch_interval: None,
+                            fee: None,
+                            settlement: None,
                         };
                         matchmaker_configs
                             .insert(name.clone(), matchmaker_config);
@@ -907,7 +914,9 @@ pub fn init_network(
                         Ipv4Addr::new(0, 0, 0, 0)
                     }),
                     first_port + 4,
-                ),
+                )
+                .into(),
+                ..Default::default()
             });
             config
                 .intent_gossiper
@@ -1113,6 +1122,104 @@ pub fn init_genesis_validator(
     );
 }
 
+/// Report the host functions ABI version of a tx or VP wasm file, or of all
+/// the wasm files in a directory. Exits with an error if any of them cannot
+/// be run by this version of the VM.
+pub fn wasm_abi_version(args::WasmAbiVersion { path }: args::WasmAbiVersion) {
+    let paths = if path.is_dir() {
+        let mut paths: Vec<PathBuf> = fs::read_dir(&path)
+            .unwrap_or_else(|err| {
+                eprintln!(
+                    "Failed to read the directory {}: {}",
+                    path.to_string_lossy(),
+                    err
+                );
+                cli::safe_exit(1)
+            })
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension() == Some("wasm".as_ref()))
+            .collect();
+        paths.sort();
+        paths
+    } else {
+        vec![path]
+    };
+
+    println!(
+        "Supported ABI versions: {:?}, deprecated ABI versions: {:?}",
+        vm::SUPPORTED_WASM_ABI_VERSIONS,
+        vm::DEPRECATED_WASM_ABI_VERSIONS
+    );
+    let mut all_supported = true;
+    for path in paths {
+        let name = path.to_string_lossy();
+        let code = match fs::read(&path) {
+            Ok(code) => code,
+            Err(err) => {
+                eprintln!("{}: failed to read the file: {}", name, err);
+                all_supported = false;
+                continue;
+            }
+        };
+        match vm::wasm_abi_version(&code) {
+            Ok(Some(version))
+                if vm::SUPPORTED_WASM_ABI_VERSIONS.contains(&version) =>
+            {
+                println!("{}: ABI version {}", name, version);
+            }
+            Ok(Some(version))
+                if vm::DEPRECATED_WASM_ABI_VERSIONS.contains(&version) =>
+            {
+                println!("{}: ABI version {} (deprecated)", name, version);
+            }
+            Ok(Some(version)) => {
+                println!("{}: ABI version {} (unsupported)", name, version);
+                all_supported = false;
+            }
+            Ok(None)
+                if vm::DEPRECATED_WASM_ABI_VERSIONS
+                    .contains(&vm::LEGACY_WASM_ABI_VERSION) =>
+            {
+                println!("{}: no ABI version (deprecated)", name);
+            }
+            Ok(None) => {
+                println!("{}: no ABI version (unsupported)", name);
+                all_supported = false;
+            }
+            Err(err) => {
+                println!("{}: {}", name, err);
+                all_supported = false;
+            }
+        }
+    }
+    if !all_supported {
+        cli::safe_exit(1)
+    }
+}
+
+/// Generate the test vectors of the signing integrations and write them as
+/// JSON to the output file, or to stdout
+pub fn gen_test_vectors(
+    args::GenTestVectors { output_path }: args::GenTestVectors,
+) {
+    let json = serde_json::to_string_pretty(&test_vectors::generate())
+        .expect("Encoding the test vectors shouldn't fail");
+    match output_path {
+        Some(path) => {
+            fs::write(&path, json).unwrap_or_else(|err| {
+                eprintln!(
+                    "Failed to write the test vectors to {}: {}",
+                    path.to_string_lossy(),
+                    err
+                );
+                cli::safe_exit(1)
+            });
+            println!("Test vectors written to {}", path.to_string_lossy());
+        }
+        None => println!("{}", json),
+    }
+}
+
 async fn download_file(url: impl AsRef<str>) -> reqwest::Result<Bytes> {
     let url = url.as_ref();
     let response = reqwest::get(url).await?;
@@ -213,6 +213,25 @@ pub mod genesis_config {
         // Initial balances held by accounts defined elsewhere.
         // XXX: u64 doesn't work with toml-rs!
         pub balances: Option<HashMap<String, u64>>,
+        // Vesting schedules of the initial balances, keyed by the same
+        // aliases or addresses as the balances.
+        pub vesting: Option<HashMap<String, VestingConfig>>,
+    }
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct VestingConfig {
+        // Amount of tokens locked at genesis (in whole tokens). It must not
+        // be greater than the initial balance.
+        // XXX: u64 doesn't work with toml-rs!
+        pub locked: u64,
+        // The first epoch in which a part of the locked tokens is released.
+        // (default: 0)
+        // XXX: u64 doesn't work with toml-rs!
+        pub start_epoch: Option<u64>,
+        // Number of epochs over which the locked tokens are released in
+        // equal parts.
+        // XXX: u64 doesn't work with toml-rs!
+        pub epochs: u64,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -280,6 +299,27 @@ pub mod genesis_config {
         // light client attack (in basis points).
         // XXX: u64 doesn't work with toml-rs!
         pub light_client_attack_slash_rate: u64,
+        // Minimum amount of tokens that a validator has to self-bond (in
+        // whole tokens).
+        // XXX: u64 doesn't work with toml-rs!
+        pub min_validator_self_bond: u64,
+        // Maximum amount of tokens that may be delegated to a single
+        // validator (in whole tokens). Delegations are not capped when not
+        // set.
+        // XXX: u64 doesn't work with toml-rs!
+        pub max_validator_delegations: Option<u64>,
+        // Maximum change of a validator's commission rate from one epoch to
+        // the next (in basis points).
+        // XXX: u64 doesn't work with toml-rs!
+        pub max_commission_rate_change: u64,
+        // Maximum number of consecutive blocks that an active validator may
+        // miss signing before it's jailed for downtime.
+        // XXX: u64 doesn't work with toml-rs!
+        pub max_missed_blocks: u64,
+        // Minimum number of epochs for which a validator is jailed for
+        // downtime.
+        // XXX: u64 doesn't work with toml-rs!
+        pub jail_len: u64,
     }
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -390,58 +430,101 @@ pub mod genesis_config {
                 .iter()
                 .map(|(alias_or_address, amount)| {
                     (
-                        match Address::decode(&alias_or_address) {
-                            Ok(address) => address,
-                            Err(decode_err) => {
-                                if let Some(alias) =
-                                    alias_or_address.strip_suffix(".public_key")
-                                {
-                                    if let Some(established) =
-                                        established_accounts.get(alias)
-                                    {
-                                        established
-                                            .public_key
-                                            .as_ref()
-                                            .unwrap()
-                                            .into()
-                                    } else if let Some(validator) =
-                                        validators.get(alias)
-                                    {
-                                        (&validator.account_key).into()
-                                    } else {
-                                        eprintln!(
-                                            "No established or validator \
-                                             account with alias {} found",
-                                            alias
-                                        );
-                                        cli::safe_exit(1)
-                                    }
-                                } else if let Some(established) =
-                                    established_accounts.get(alias_or_address)
-                                {
-                                    established.address.clone()
-                                } else if let Some(validator) =
-                                    validators.get(alias_or_address)
-                                {
-                                    validator.pos_data.address.clone()
-                                } else if let Some(implicit) =
-                                    implicit_accounts.get(alias_or_address)
-                                {
-                                    (&implicit.public_key).into()
-                                } else {
-                                    eprintln!(
-                                        "{} is unknown alias and not a valid \
-                                         address: {}",
-                                        alias_or_address, decode_err
-                                    );
-                                    cli::safe_exit(1)
-                                }
-                            }
-                        },
+                        load_address(
+                            alias_or_address,
+                            validators,
+                            established_accounts,
+                            implicit_accounts,
+                        ),
                         token::Amount::whole(*amount),
                     )
                 })
                 .collect(),
+            vesting: config
+                .vesting
+                .as_ref()
+                .unwrap_or(&HashMap::default())
+                .iter()
+                .map(|(alias_or_address, vesting)| {
+                    let balance = config
+                        .balances
+                        .as_ref()
+                        .and_then(|balances| balances.get(alias_or_address))
+                        .copied()
+                        .unwrap_or_default();
+                    if vesting.locked > balance {
+                        eprintln!(
+                            "The locked amount {} of {} is greater than its \
+                             balance {}",
+                            vesting.locked, alias_or_address, balance
+                        );
+                        cli::safe_exit(1)
+                    }
+                    (
+                        load_address(
+                            alias_or_address,
+                            validators,
+                            established_accounts,
+                            implicit_accounts,
+                        ),
+                        token::VestingSchedule {
+                            locked: token::Amount::whole(vesting.locked),
+                            start_epoch: storage::Epoch(
+                                vesting.start_epoch.unwrap_or_default(),
+                            ),
+                            epochs: vesting.epochs,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Find the address of an account from an alias of a validator, an
+    /// established or an implicit account, or from an encoded address.
+    fn load_address(
+        alias_or_address: &str,
+        validators: &HashMap<String, Validator>,
+        established_accounts: &HashMap<String, EstablishedAccount>,
+        implicit_accounts: &HashMap<String, ImplicitAccount>,
+    ) -> Address {
+        match Address::decode(alias_or_address) {
+            Ok(address) => address,
+            Err(decode_err) => {
+                if let Some(alias) =
+                    alias_or_address.strip_suffix(".public_key")
+                {
+                    if let Some(established) = established_accounts.get(alias) {
+                        established.public_key.as_ref().unwrap().into()
+                    } else if let Some(validator) = validators.get(alias) {
+                        (&validator.account_key).into()
+                    } else {
+                        eprintln!(
+                            "No established or validator account with alias \
+                             {} found",
+                            alias
+                        );
+                        cli::safe_exit(1)
+                    }
+                } else if let Some(established) =
+                    established_accounts.get(alias_or_address)
+                {
+                    established.address.clone()
+                } else if let Some(validator) = validators.get(alias_or_address)
+                {
+                    validator.pos_data.address.clone()
+                } else if let Some(implicit) =
+                    implicit_accounts.get(alias_or_address)
+                {
+                    (&implicit.public_key).into()
+                } else {
+                    eprintln!(
+                        "{} is unknown alias and not a valid address: {}",
+                        alias_or_address, decode_err
+                    );
+                    cli::safe_exit(1)
+                }
+            }
         }
     }
 
@@ -576,6 +659,19 @@ pub mod genesis_config {
             light_client_attack_slash_rate: BasisPoints::new(
                 config.pos_params.light_client_attack_slash_rate,
             ),
+            min_validator_self_bond: token::Amount::whole(
+                config.pos_params.min_validator_self_bond,
+            )
+            .into(),
+            max_validator_delegations: config
+                .pos_params
+                .max_validator_delegations
+                .map(|max| token::Amount::whole(max).into()),
+            max_commission_rate_change: BasisPoints::new(
+                config.pos_params.max_commission_rate_change,
+            ),
+            max_missed_blocks: config.pos_params.max_missed_blocks,
+            jail_len: config.pos_params.jail_len,
         };
 
         let mut genesis = Genesis {
@@ -704,6 +800,9 @@ pub struct TokenAccount {
     /// Accounts' balances of this token
     #[derivative(PartialOrd = "ignore", Ord = "ignore")]
     pub balances: HashMap<Address, token::Amount>,
+    /// Vesting schedules of some of the accounts' balances
+    #[derivative(PartialOrd = "ignore", Ord = "ignore")]
+    pub vesting: HashMap<Address, token::VestingSchedule>,
 }
 
 #[derive(
@@ -847,6 +946,7 @@ pub fn genesis() -> Genesis {
             vp_code_path: vp_token_path.into(),
             vp_sha256: Default::default(),
             balances: balances.clone(),
+            vesting: HashMap::default(),
         })
         .collect();
     Genesis {
@@ -12,8 +12,12 @@ use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use anoma::types::address::Address;
 use anoma::types::chain::ChainId;
+use anoma::types::intent::MatchmakerFee;
+use anoma::types::matchmaker::MatchingStrategy;
 use anoma::types::time::Rfc3339String;
+use anoma::types::token;
 use libp2p::multiaddr::{Multiaddr, Protocol};
 use libp2p::multihash::Multihash;
 use libp2p::PeerId;
@@ -30,6 +34,7 @@ use tendermint_stable::Timeout;
 use thiserror::Error;
 
 use crate::cli;
+use crate::client::history::Period;
 
 /// Base directory contains global config and chain directories.
 pub const DEFAULT_BASE_DIR: &str = ".anoma";
@@ -52,6 +57,8 @@ pub struct Config {
     pub intent_gossiper: IntentGossiper,
     // TODO allow to configure multiple matchmakers
     pub matchmaker: Matchmaker,
+    #[serde(default)]
+    pub client: Client,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -88,6 +95,9 @@ pub struct Ledger {
     pub chain_id: ChainId,
     pub shell: Shell,
     pub tendermint: Tendermint,
+    /// The HTTP gateway for browser clients, disabled when not set
+    #[serde(default)]
+    pub gateway: Option<Gateway>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -103,6 +113,15 @@ pub struct Shell {
     /// Tx WASM compilation in-memory cache maximum size in bytes.
     /// When not set, defaults to 1/6 of the available memory.
     pub tx_wasm_compilation_cache_bytes: Option<u64>,
+    /// Storage read in-memory cache maximum size in bytes.
+    /// When not set, defaults to 64 MiB.
+    pub storage_read_cache_bytes: Option<u64>,
+    /// Run the node in the archive mode. An archive node never prunes the
+    /// merkle trees of old blocks, serves proofs at any height and indexes
+    /// all the events, while other nodes only serve proofs from the first
+    /// block of the epochs that they track.
+    #[serde(default)]
+    pub archive: bool,
     /// Use the [`Ledger::db_dir()`] method to read the value.
     db_dir: PathBuf,
     /// Use the [`Ledger::tendermint_dir()`] method to read the value.
@@ -132,6 +151,25 @@ pub struct Tendermint {
     pub instrumentation_namespace: String,
 }
 
+/// An HTTP gateway that serves a JSON API for submitting txs, querying the
+/// storage and looking-up the events of txs, so that browser clients can talk
+/// to a node without a proxy
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Gateway {
+    /// The address on which the gateway listens
+    pub address: ListenAddress,
+    /// Expect every connection to start with a PROXY protocol header, which
+    /// is sent by a reverse proxy in front of the gateway with the address
+    /// of the original client. Connections without it are rejected.
+    #[serde(default)]
+    pub proxy_protocol: bool,
+    /// The origins from which browsers may call the gateway, with `*`
+    /// allowing any origin. When empty, cross-origin requests are not
+    /// allowed.
+    #[serde(default)]
+    pub cors_allowed_origins: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IntentGossiper {
     // Simple values
@@ -139,23 +177,318 @@ pub struct IntentGossiper {
     pub topics: HashSet<String>,
     /// The server address to which matchmakers can connect to receive intents
     pub matchmakers_server_addr: SocketAddr,
+    /// Run in the trace mode, in which the node announces the intents it
+    /// receives and records the announcements of other nodes, so that the
+    /// propagation path of an intent can be retrieved via RPC. Only the nodes
+    /// in the trace mode propagate the announcements.
+    #[serde(default)]
+    pub trace: bool,
+    /// The address on which the Prometheus metrics of the node are served,
+    /// disabled when not set
+    #[serde(default)]
+    pub metrics_addr: Option<SocketAddr>,
 
     // Nested structures ⚠️ no simple values below any of these ⚠️
     pub subscription_filter: SubscriptionFilter,
+    /// The peers to connect to on start. They're used to bootstrap Kademlia
+    /// peer discovery, if it's enabled.
     pub seed_peers: HashSet<PeerAddress>,
     pub rpc: Option<RpcServer>,
     pub discover_peer: Option<DiscoverPeer>,
+    /// The requirements for intents to be admitted into the mempool
+    #[serde(default)]
+    pub admission: IntentAdmission,
+}
+
+/// The requirements for intents to be admitted into the intent gossiper's
+/// mempool, to raise the cost of spamming the gossip network. Intents that
+/// don't meet them are not sent to the matchmakers.
+#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+pub struct IntentAdmission {
+    /// The minimum notional value of an intent, which is the sum of the
+    /// maximum amounts sold by its exchanges
+    pub min_value: Option<token::Amount>,
+
+    // Nested structures ⚠️ no simple values below any of these ⚠️
+    pub deposit: Option<IntentDeposit>,
+}
+
+/// A deposit that the accounts of an intent's exchanges must hold in the
+/// ledger. The deposit is not spent, it can be transferred out once the
+/// account no longer sends intents.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IntentDeposit {
+    /// The token of the deposit
+    pub token: Address,
+    /// The amount of the deposit
+    pub amount: token::Amount,
+    /// The ledger node's address to query the deposits from
+    pub ledger_address: TendermintAddress,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RpcServer {
-    pub address: SocketAddr,
+    pub address: ListenAddress,
+    /// Expect every connection to start with a PROXY protocol header, which
+    /// is sent by a reverse proxy in front of the RPC server with the address
+    /// of the original client. The clients are rate limited by that address.
+    /// Connections without it are rejected.
+    #[serde(default)]
+    pub proxy_protocol: bool,
+    /// The topics of the intents, cancellations and subscriptions accepted
+    /// via RPC. All the topics are accepted if empty.
+    #[serde(default)]
+    pub topics: HashSet<String>,
+    /// The hex encoded public keys of the publishers allowed to submit
+    /// intents via RPC. If not empty, an intent must be a fungible token
+    /// intent signed with one of the keys.
+    #[serde(default)]
+    pub publishers: HashSet<String>,
+    /// The bearer tokens of the clients allowed to use the RPC service. Any
+    /// client is allowed if empty.
+    #[serde(default)]
+    pub auth_tokens: HashSet<String>,
+
+    // Nested structures ⚠️ no simple values below any of these ⚠️
+    /// The rate limit of the messages of every client, if any
+    #[serde(default)]
+    pub rate_limit: Option<RpcRateLimit>,
+    /// The TLS config of the RPC server, if any. Without it, the RPC server
+    /// is plaintext.
+    #[serde(default)]
+    pub tls: Option<RpcTls>,
+}
+
+/// The PEM encoded files of the TLS config of the RPC server
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RpcTls {
+    /// The path of the server's certificate
+    pub cert_path: PathBuf,
+    /// The path of the server's private key
+    pub key_path: PathBuf,
+    /// The path of the CA certificate of the clients, if any. With it, only
+    /// the clients with a certificate signed by this CA are accepted.
+    #[serde(default)]
+    pub client_ca_path: Option<PathBuf>,
+}
+
+/// The thresholds of the token bucket rate limiting of the messages received
+/// via RPC from every client IP address
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RpcRateLimit {
+    /// The number of messages that a client can send in a burst
+    pub burst: u32,
+    /// The number of messages per second that a client can send after a
+    /// burst
+    pub per_second: f64,
+}
+
+impl RpcRateLimit {
+    /// Check that a client can send at least one message and that the rate is
+    /// a non-negative number
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.burst < 1 {
+            return Err("the burst must be at least 1".to_owned());
+        }
+        if !self.per_second.is_finite() || self.per_second < 0.0 {
+            return Err(format!(
+                "the rate per second must be a non-negative number, got {}",
+                self.per_second
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for RpcRateLimit {
+    fn default() -> Self {
+        Self {
+            burst: 20,
+            per_second: 5.0,
+        }
+    }
+}
+
+/// The address on which a server listens, either a TCP socket address or the
+/// path of a Unix domain socket. It's written as `<ip>:<port>` or
+/// `tcp://<ip>:<port>` for TCP and as `unix://<path>` for a Unix socket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListenAddress {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl FromStr for ListenAddress {
+    type Err = SerdeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("unix://") {
+            if path.is_empty() {
+                return Err(SerdeError::BadListenAddress(s.to_owned()));
+            }
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+        let address = s.strip_prefix("tcp://").unwrap_or(s);
+        SocketAddr::from_str(address)
+            .map(Self::Tcp)
+            .map_err(|_| SerdeError::BadListenAddress(s.to_owned()))
+    }
+}
+
+impl Display for ListenAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(address) => write!(f, "{}", address),
+            Self::Unix(path) => write!(f, "unix://{}", path.to_string_lossy()),
+        }
+    }
+}
+
+impl From<SocketAddr> for ListenAddress {
+    fn from(address: SocketAddr) -> Self {
+        Self::Tcp(address)
+    }
+}
+
+impl Serialize for ListenAddress {
+    fn serialize<S>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ListenAddress {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let address = String::deserialize(deserializer)?;
+        Self::from_str(&address).map_err(D::Error::custom)
+    }
 }
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub struct Matchmaker {
     pub matchmaker_path: Option<PathBuf>,
     pub tx_code_path: Option<PathBuf>,
+    /// The strategy with which the matchmaker chooses between the intents
+    /// that a new intent can be matched with
+    #[serde(default)]
+    pub strategy: MatchingStrategy,
+    /// The interval at which the intents are matched in batch auctions, if
+    /// any. Otherwise, every intent is matched as soon as it's received.
+    #[serde(default)]
+    pub batch_interval: Option<Timeout>,
+
+    // Nested structures ⚠️ no simple values below any of these ⚠️
+    /// The fee charged to the sources of the matched exchanges, if any
+    #[serde(default)]
+    pub fee: Option<MatchmakerFee>,
+    /// The settlement of the matches with the matchmaker's own key. Without
+    /// it, the matched txs are only logged.
+    #[serde(default)]
+    pub settlement: Option<MatchmakerSettlement>,
+}
+
+/// The automatic settlement of the matches found by the matchmaker. The
+/// settlement txs are signed with a key from the wallet and their source
+/// receives the matchmaker's fees.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatchmakerSettlement {
+    /// The public key, public key hash or alias in the wallet of the key with
+    /// which the settlement txs are signed
+    pub signing_key: String,
+    /// The source address or its alias of the settlement txs. This must be
+    /// matching the signing key.
+    pub source: String,
+    /// The address of the ledger that the settlement txs are submitted to
+    pub ledger_address: TendermintAddress,
+    /// The maximum number of attempts to broadcast a settlement tx,
+    /// including the first one, 3 by default
+    #[serde(default)]
+    pub max_broadcast_attempts: Option<u64>,
+    /// The delay before the first retry to broadcast a settlement tx, 1s by
+    /// default. It's doubled after each failed attempt.
+    #[serde(default)]
+    pub broadcast_retry_delay: Option<Timeout>,
+    /// How long to wait for the result of a settlement tx, 60s by default
+    #[serde(default)]
+    pub event_timeout: Option<Timeout>,
+}
+
+/// The configuration of the client
+#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+pub struct Client {
+    /// The addresses of the gossip nodes to which intents are submitted when
+    /// none is given to the `intent` command
+    #[serde(default)]
+    pub gossip_nodes: Vec<String>,
+
+    // Nested structures ⚠️ no simple values below any of these ⚠️
+    /// The middlewares through which the client passes every tx before it's
+    /// broadcast, in order. See [`crate::client::middleware`].
+    #[serde(default)]
+    pub tx_middlewares: Vec<TxMiddleware>,
+    /// The IPFS node to which the client pins the assets of NFT tokens. See
+    /// [`crate::client::nft_assets`].
+    #[serde(default)]
+    pub ipfs: Option<Ipfs>,
+}
+
+/// The HTTP API of an IPFS node
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Ipfs {
+    /// The URL of the API, e.g. `http://127.0.0.1:5001`
+    pub api_url: String,
+}
+
+/// A middleware in the client's submit path
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TxMiddleware {
+    /// Append every tx and its result to a log file
+    Log { path: PathBuf },
+    /// Reject the transfers of the token that would exceed the maximum amount
+    /// transferred in the last 24 hours, which are recorded in a file. The
+    /// bonds and the batch operations are counted too and the txs of an
+    /// unknown kind are rejected.
+    MaxDailyAmount {
+        token: Address,
+        max_amount: token::Amount,
+        path: PathBuf,
+    },
+    /// Ask for the approval of a second person before a tx is broadcast,
+    /// optionally only for transfers of at least the minimum amount or txs of
+    /// an unknown kind and only by the given approvers
+    Approval {
+        #[serde(default)]
+        min_amount: Option<token::Amount>,
+        #[serde(default)]
+        approvers: Vec<String>,
+    },
+    /// Record the confirmed transfers in a file, from which the client's
+    /// `report` is made, and warn when a transfer exceeds any of the budgets
+    History {
+        path: PathBuf,
+        #[serde(default)]
+        budgets: Vec<Budget>,
+    },
+}
+
+/// A budget of the amount of a token transferred in every period. See
+/// [`crate::client::history`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Budget {
+    pub token: Address,
+    pub period: Period,
+    pub max_amount: token::Amount,
 }
 
 impl Ledger {
@@ -176,6 +509,8 @@ impl Ledger {
                 block_cache_bytes: None,
                 vp_wasm_compilation_cache_bytes: None,
                 tx_wasm_compilation_cache_bytes: None,
+                storage_read_cache_bytes: None,
+                archive: false,
                 db_dir: DB_DIR.into(),
                 tendermint_dir: TENDERMINT_DIR.into(),
             },
@@ -201,6 +536,7 @@ impl Ledger {
                 ),
                 instrumentation_namespace: "anoman_tm".to_string(),
             },
+            gateway: None,
         }
     }
 
@@ -278,6 +614,8 @@ pub enum Error {
     WriteError(std::io::Error),
     #[error("A config file already exists in {0}")]
     AlreadyExistingConfig(PathBuf),
+    #[error("Invalid RPC rate limit: {0}")]
+    InvalidRpcRateLimit(String),
     #[error(
         "Bootstrap peer {0} is not valid. Format needs to be \
          {{protocol}}/{{ip}}/tcp/{{port}}/p2p/{{peerid}}"
@@ -295,6 +633,11 @@ pub enum SerdeError {
          {{protocol}}/{{ip}}/tcp/{{port}}/p2p/{{peerid}}"
     )]
     BadBootstrapPeerFormat(String),
+    #[error(
+        "Listen address {0} is not valid. Format needs to be {{ip}}:{{port}}, \
+         tcp://{{ip}}:{{port}} or unix://{{path}}"
+    )]
+    BadListenAddress(String),
     #[error("{0}")]
     Message(String),
 }
@@ -310,6 +653,7 @@ impl Config {
             ledger: Ledger::new(base_dir, chain_id, mode),
             intent_gossiper: IntentGossiper::default(),
             matchmaker: Matchmaker::default(),
+            client: Client::default(),
         }
     }
 
@@ -368,7 +712,17 @@ impl Config {
                 )
             })
             .map_err(Error::ReadError)?;
-        config.try_into().map_err(Error::DeserializationError)
+        let config: Self =
+            config.try_into().map_err(Error::DeserializationError)?;
+        if let Some(rate_limit) = config
+            .intent_gossiper
+            .rpc
+            .as_ref()
+            .and_then(|rpc| rpc.rate_limit.as_ref())
+        {
+            rate_limit.validate().map_err(Error::InvalidRpcRateLimit)?;
+        }
+        Ok(config)
     }
 
     /// Generate configuration and write it to a file.
@@ -427,24 +781,40 @@ impl Default for IntentGossiper {
                 IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
                 26661,
             ),
+            trace: false,
+            metrics_addr: None,
             subscription_filter: SubscriptionFilter::RegexFilter(
                 Regex::new("asset_v\\d{1,2}").unwrap(),
             ),
             seed_peers: HashSet::default(),
             rpc: None,
             discover_peer: Some(DiscoverPeer::default()),
+            admission: IntentAdmission::default(),
         }
     }
 }
 
 impl IntentGossiper {
-    pub fn update(&mut self, addr: Option<Multiaddr>, rpc: Option<SocketAddr>) {
+    pub fn update(
+        &mut self,
+        addr: Option<Multiaddr>,
+        rpc: Option<SocketAddr>,
+        mdns: bool,
+        bootstrap_peers: Vec<PeerAddress>,
+    ) {
         if let Some(addr) = addr {
             self.address = addr;
         }
         if let Some(address) = rpc {
-            self.rpc = Some(RpcServer { address });
+            let rpc = self.rpc.get_or_insert_with(RpcServer::default);
+            rpc.address = address.into();
+        }
+        if mdns {
+            let discover_peer =
+                self.discover_peer.get_or_insert_with(DiscoverPeer::default);
+            discover_peer.mdns = true;
         }
+        self.seed_peers.extend(bootstrap_peers);
     }
 }
 
@@ -454,7 +824,14 @@ impl Default for RpcServer {
             address: SocketAddr::new(
                 IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
                 26660,
-            ),
+            )
+            .into(),
+            proxy_protocol: false,
+            topics: HashSet::default(),
+            publishers: HashSet::default(),
+            auth_tokens: HashSet::default(),
+            rate_limit: Some(RpcRateLimit::default()),
+            tls: None,
         }
     }
 }
@@ -486,19 +863,41 @@ impl<'de> Deserialize<'de> for PeerAddress {
     {
         use serde::de::Error;
 
-        let mut address = Multiaddr::deserialize(deserializer)
+        let address = Multiaddr::deserialize(deserializer)
             .map_err(|err| SerdeError::BadBootstrapPeerFormat(err.to_string()))
             .map_err(D::Error::custom)?;
-        if let Some(Protocol::P2p(mh)) = address.pop() {
-            let peer_id = PeerId::from_multihash(mh).unwrap();
-            Ok(Self { address, peer_id })
-        } else {
-            Err(SerdeError::BadBootstrapPeerFormat(address.to_string()))
-                .map_err(D::Error::custom)
+        Self::try_from(address).map_err(D::Error::custom)
+    }
+}
+
+impl TryFrom<Multiaddr> for PeerAddress {
+    type Error = SerdeError;
+
+    fn try_from(
+        mut address: Multiaddr,
+    ) -> std::result::Result<Self, Self::Error> {
+        match address.pop() {
+            Some(Protocol::P2p(mh)) => {
+                let peer_id = PeerId::from_multihash(mh).map_err(|_| {
+                    SerdeError::BadBootstrapPeerFormat(address.to_string())
+                })?;
+                Ok(Self { address, peer_id })
+            }
+            _ => Err(SerdeError::BadBootstrapPeerFormat(address.to_string())),
         }
     }
 }
 
+impl FromStr for PeerAddress {
+    type Err = SerdeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let address = Multiaddr::from_str(s)
+            .map_err(|_| SerdeError::BadBootstrapPeerFormat(s.to_owned()))?;
+        Self::try_from(address)
+    }
+}
+
 impl Default for DiscoverPeer {
     fn default() -> Self {
         Self {
@@ -0,0 +1,321 @@
+//! Checks of a node's home directory for common misconfigurations, run with
+//! `anoma node doctor`. Every problem that is found is reported with a fix.
+
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anoma::types::time::DateTimeUtc;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_config::net::Address as TendermintAddress;
+#[cfg(feature = "ABCI")]
+use tendermint_config_abci::net::Address as TendermintAddress;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::{Client, HttpClient};
+
+use crate::config::{self, Config, DEFAULT_WASM_CHECKSUMS_FILE};
+use crate::wallet;
+use crate::wasm_loader::{self, Checksums};
+
+/// The clock skew from the last block's time above which it's reported, in
+/// seconds
+const MAX_CLOCK_SKEW_SECS: i64 = 30;
+
+/// A problem found in the node's home directory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// What's wrong
+    pub problem: String,
+    /// How to fix it
+    pub fix: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n  Fix: {}", self.problem, self.fix)
+    }
+}
+
+/// Check the node's home directory of the configured chain and print the
+/// problems that have been found. Returns `true` if there are none.
+pub fn run(config: &Config, wasm_dir: &Path) -> bool {
+    let ledger = &config.ledger;
+    println!("Checking the node of the chain {}...", ledger.chain_id);
+    let tm_config_dir = ledger.tendermint_dir().join("config");
+
+    let mut findings = vec![];
+    findings.extend(check_key_permissions(&[
+        tm_config_dir.join("priv_validator_key.json"),
+        tm_config_dir.join("node_key.json"),
+        wallet::wallet_file(ledger.chain_dir()),
+    ]));
+    findings.extend(check_chain_id(ledger, &tm_config_dir));
+    findings.extend(check_wasm_checksums(wasm_dir));
+    let rpc_address =
+        TendermintAddress::from_str(&ledger.tendermint.rpc_address.to_string())
+            .unwrap();
+    findings.extend(
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(check_clock_skew(rpc_address, DateTimeUtc::now())),
+    );
+
+    for finding in &findings {
+        println!("{}", finding);
+    }
+    if findings.is_empty() {
+        println!("No problems found.");
+    } else {
+        println!("Found {} problem(s).", findings.len());
+    }
+    findings.is_empty()
+}
+
+/// Check that the files with secret keys, if they exist, can only be
+/// accessed by their owner
+#[cfg(unix)]
+fn check_key_permissions(paths: &[PathBuf]) -> Vec<Finding> {
+    use std::os::unix::fs::PermissionsExt;
+
+    paths
+        .iter()
+        .filter_map(|path| {
+            let mode = fs::metadata(path).ok()?.permissions().mode() & 0o777;
+            (mode & 0o077 != 0).then(|| Finding {
+                problem: format!(
+                    "The secret keys in {} can be accessed by other users \
+                     (mode {:o}).",
+                    path.to_string_lossy(),
+                    mode
+                ),
+                fix: format!("chmod 600 {}", path.to_string_lossy()),
+            })
+        })
+        .collect()
+}
+
+/// The permissions are only checked on Unix
+#[cfg(not(unix))]
+fn check_key_permissions(_paths: &[PathBuf]) -> Vec<Finding> {
+    vec![]
+}
+
+/// Check that Tendermint's genesis file is for the configured chain. The
+/// genesis file is only written once the ledger has been started.
+fn check_chain_id(
+    ledger: &config::Ledger,
+    tm_config_dir: &Path,
+) -> Option<Finding> {
+    let path = tm_config_dir.join("genesis.json");
+    let genesis: serde_json::Value =
+        serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+    let tm_chain_id = genesis.get("chain_id")?.as_str()?;
+    (tm_chain_id != ledger.chain_id.as_str()).then(|| Finding {
+        problem: format!(
+            "Tendermint's genesis file {} is for the chain {}, but the node \
+             is configured for the chain {}.",
+            path.to_string_lossy(),
+            tm_chain_id,
+            ledger.chain_id
+        ),
+        fix: format!(
+            "Set the `chain_id` in {} to the chain of this node, or reset the \
+             node's state with `anoma node ledger reset`.",
+            ledger.chain_dir().join(config::FILENAME).to_string_lossy()
+        ),
+    })
+}
+
+/// Check that every WASM in the checksums file exists and matches its
+/// checksum
+fn check_wasm_checksums(wasm_dir: &Path) -> Vec<Finding> {
+    let checksums_path = wasm_dir.join(DEFAULT_WASM_CHECKSUMS_FILE);
+    let checksums: Checksums = match fs::read(&checksums_path)
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| {
+            serde_json::from_slice(&bytes).map_err(|err| err.to_string())
+        }) {
+        Ok(checksums) => checksums,
+        Err(err) => {
+            return vec![Finding {
+                problem: format!(
+                    "The WASM checksums file {} can't be read: {}.",
+                    checksums_path.to_string_lossy(),
+                    err
+                ),
+                fix: "Join the network again with `anoma client utils \
+                      join-network` to restore it."
+                    .to_owned(),
+            }];
+        }
+    };
+    let mut names: Vec<_> = checksums.0.iter().collect();
+    names.sort();
+    names
+        .into_iter()
+        .filter_map(|(name, full_name)| {
+            let path = wasm_dir.join(full_name);
+            let problem = match fs::read(&path) {
+                Ok(bytes) => {
+                    wasm_loader::verify_checksum(name, full_name, &bytes)
+                        .err()?
+                        .to_string()
+                }
+                Err(_) => format!(
+                    "The WASM {} is missing from {}.",
+                    full_name,
+                    wasm_dir.to_string_lossy()
+                ),
+            };
+            Some(Finding {
+                problem,
+                fix: format!(
+                    "Remove {} if it exists and restart the node to download \
+                     it again.",
+                    path.to_string_lossy()
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Check that the local clock agrees with the time of the last block of a
+/// synced node. Nothing is reported when the node is not running or is
+/// still catching up.
+async fn check_clock_skew(
+    rpc_address: TendermintAddress,
+    now: DateTimeUtc,
+) -> Option<Finding> {
+    let client = HttpClient::new(rpc_address.clone()).ok()?;
+    let status = match client.status().await {
+        Ok(status) => status,
+        Err(_) => {
+            println!(
+                "The node's RPC at {} is not reachable, skipping the clock \
+                 check.",
+                rpc_address
+            );
+            return None;
+        }
+    };
+    if status.sync_info.catching_up {
+        println!("The node is catching up, skipping the clock check.");
+        return None;
+    }
+    let block_time =
+        DateTimeUtc::try_from(status.sync_info.latest_block_time).ok()?;
+    clock_skew_finding(block_time, now)
+}
+
+/// Report a skew between the local clock and the last block's time
+fn clock_skew_finding(
+    block_time: DateTimeUtc,
+    now: DateTimeUtc,
+) -> Option<Finding> {
+    let skew = now.0.signed_duration_since(block_time.0).num_seconds();
+    if skew < -MAX_CLOCK_SKEW_SECS {
+        Some(Finding {
+            problem: format!(
+                "The last block's time {} is {} seconds ahead of the local \
+                 clock.",
+                block_time.to_rfc3339(),
+                -skew
+            ),
+            fix: "The local clock is behind, synchronize it with NTP."
+                .to_owned(),
+        })
+    } else if skew > MAX_CLOCK_SKEW_SECS {
+        Some(Finding {
+            problem: format!(
+                "The last block's time {} is {} seconds behind the local \
+                 clock.",
+                block_time.to_rfc3339(),
+                skew
+            ),
+            fix: "Either the local clock is ahead and should be synchronized \
+                  with NTP, or the chain is not producing new blocks."
+                .to_owned(),
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use anoma::types::time::DurationSecs;
+
+    use super::*;
+
+    /// Test that the files with secret keys readable by other users are
+    /// reported
+    #[cfg(unix)]
+    #[test]
+    fn test_check_key_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let private = dir.path().join("private.json");
+        let public = dir.path().join("public.json");
+        for (path, mode) in [(&private, 0o600), (&public, 0o644)] {
+            fs::write(path, "{}").unwrap();
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))
+                .unwrap();
+        }
+        let missing = dir.path().join("missing.json");
+
+        let findings = check_key_permissions(&[private, public, missing]);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].problem.contains("public.json"));
+        assert!(findings[0].problem.contains("mode 644"));
+    }
+
+    /// Test that the missing and mismatched WASMs are reported
+    #[test]
+    fn test_check_wasm_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(check_wasm_checksums(dir.path()).len(), 1);
+
+        let hash = |bytes: &[u8]| {
+            anoma::types::hash::Hash::sha256(bytes)
+                .to_string()
+                .to_lowercase()
+        };
+        let checksums = HashMap::from([
+            ("ok.wasm", format!("ok.{}.wasm", hash(b"ok"))),
+            ("stale.wasm", format!("stale.{}.wasm", hash(b"fresh"))),
+            ("missing.wasm", format!("missing.{}.wasm", hash(b"missing"))),
+        ]);
+        fs::write(
+            dir.path().join(DEFAULT_WASM_CHECKSUMS_FILE),
+            serde_json::to_vec(&checksums).unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.path().join(&checksums["ok.wasm"]), b"ok").unwrap();
+        fs::write(dir.path().join(&checksums["stale.wasm"]), b"stale").unwrap();
+
+        let findings = check_wasm_checksums(dir.path());
+        assert_eq!(findings.len(), 2);
+        assert!(findings[0].problem.contains("missing"));
+        assert!(findings[1].problem.contains("stale"));
+    }
+
+    /// Test that a clock skew beyond the limit is reported in both
+    /// directions
+    #[test]
+    fn test_clock_skew_finding() {
+        let now = DateTimeUtc::now();
+        let later = |secs| now + DurationSecs(secs);
+        assert!(clock_skew_finding(now, now).is_none());
+        assert!(clock_skew_finding(now, later(10)).is_none());
+        assert!(clock_skew_finding(now, later(60)).is_some());
+        assert!(clock_skew_finding(later(60), now).is_some());
+    }
+}
@@ -0,0 +1,272 @@
+//! The requirements for intents to be admitted into the intent gossiper's
+//! mempool, which raise the cost of spamming the gossip network. An intent may
+//! be required to have a minimum notional value and the accounts of its
+//! exchanges may be required to hold a deposit in the ledger. Expired intents
+//! are never admitted.
+
+use std::collections::HashSet;
+
+use anoma::proto::{Intent, Signed};
+use anoma::types::address::Address;
+use anoma::types::intent::{
+    FungibleTokenIntent, RevealedRingIntent, RingSignedIntent,
+};
+use anoma::types::time::DateTimeUtc;
+use anoma::types::token;
+use borsh::BorshDeserialize;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::HttpClient;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::HttpClient;
+use thiserror::Error;
+
+use crate::client::rpc;
+use crate::config::IntentAdmission;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The intent expired at {0}")]
+    Expired(String),
+    #[error("The intent's data are not a fungible token intent")]
+    NotFungibleTokenIntent,
+    #[error(
+        "The intent's value {value} is below the minimum value {min_value}"
+    )]
+    ValueTooLow {
+        value: token::Amount,
+        min_value: token::Amount,
+    },
+    #[error(
+        "The account {owner} doesn't hold the intent deposit of {amount} of \
+         the token {token}"
+    )]
+    MissingDeposit {
+        owner: Address,
+        token: Address,
+        amount: token::Amount,
+    },
+}
+
+/// Check that an intent meets the admission requirements, if any. The
+/// deposits are queried from the ledger.
+pub async fn check_intent(
+    admission: &IntentAdmission,
+    intent: &Intent,
+) -> Result<(), Error> {
+    check_expiry(intent, DateTimeUtc::now())?;
+    if admission.min_value.is_none() && admission.deposit.is_none() {
+        return Ok(());
+    }
+    let intent = Signed::<FungibleTokenIntent>::try_from_slice(&intent.data)
+        .map_err(|_| Error::NotFungibleTokenIntent)?;
+    if let Some(min_value) = admission.min_value {
+        check_value(&intent.data, min_value)?;
+    }
+    if let Some(deposit) = &admission.deposit {
+        let client = HttpClient::new(deposit.ledger_address.clone()).unwrap();
+        let owners: HashSet<&Address> = intent
+            .data
+            .exchange
+            .iter()
+            .map(|exchange| &exchange.data.addr)
+            .collect();
+        for owner in owners {
+            let balance =
+                rpc::token::get_token_balance(&client, &deposit.token, owner)
+                    .await
+                    .unwrap_or_default();
+            if balance < deposit.amount {
+                return Err(Error::MissingDeposit {
+                    owner: owner.clone(),
+                    token: deposit.token.clone(),
+                    amount: deposit.amount,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that an intent hasn't expired at the given time, see [`expiry`]
+pub fn check_expiry(intent: &Intent, time: DateTimeUtc) -> Result<(), Error> {
+    match expiry(intent) {
+        Some(expires) if time > expires => {
+            Err(Error::Expired(expires.to_rfc3339()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The time at which an intent expires, once all of its exchanges have
+/// expired. Intents that are neither fungible token intents nor ring-signed
+/// intents, revealed or not, never expire.
+pub fn expiry(intent: &Intent) -> Option<DateTimeUtc> {
+    data_expiry(&intent.data)
+}
+
+/// The time at which an intent with the given data expires, see [`expiry`]
+pub fn data_expiry(data: &[u8]) -> Option<DateTimeUtc> {
+    if let Ok(intent) = Signed::<FungibleTokenIntent>::try_from_slice(data) {
+        intent.data.expires()
+    } else if let Ok(intent) = RingSignedIntent::try_from_slice(data) {
+        intent.data.expires()
+    } else if let Ok(intent) = RevealedRingIntent::try_from_slice(data) {
+        intent.expires()
+    } else {
+        None
+    }
+}
+
+/// Check that the notional value of an intent, which is the sum of the
+/// maximum amounts sold by its exchanges, is at least the minimum value. The
+/// exchanges that sell a token of an NFT count its price instead.
+fn check_value(
+    intent: &FungibleTokenIntent,
+    min_value: token::Amount,
+) -> Result<(), Error> {
+    let value = intent.exchange.iter().fold(
+        token::Amount::default(),
+        |acc, exchange| {
+            if exchange.data.nft_sold().is_some() {
+                acc + exchange.data.min_buy
+            } else {
+                acc + exchange.data.max_sell
+            }
+        },
+    );
+    if value < min_value {
+        return Err(Error::ValueTooLow { value, min_value });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anoma::types::address::{btc, xan};
+    use anoma::types::intent::{
+        DecimalWrapper, Exchange, ExchangeNft, NftSide,
+    };
+    use anoma::types::key::testing::keypair_1;
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    /// Test that the value of an intent sums the amounts sold by its
+    /// exchanges
+    #[test]
+    fn test_check_intent_value() {
+        let keypair = keypair_1();
+        let exchange = |max_sell: u64| {
+            Signed::new(
+                &keypair,
+                Exchange {
+                    addr: xan(),
+                    token_sell: xan(),
+                    rate_min: DecimalWrapper::from_str("1").unwrap(),
+                    max_sell: token::Amount::whole(max_sell),
+                    token_buy: btc(),
+                    min_buy: token::Amount::whole(1),
+                    vp: None,
+                    nft: None,
+                    expires: None,
+                    partial: false,
+                    max_fee: token::Amount::default(),
+                },
+            )
+        };
+        let intent = FungibleTokenIntent {
+            exchange: [exchange(10), exchange(20)].into_iter().collect(),
+            nonce: 0,
+        };
+
+        assert!(check_value(&intent, token::Amount::whole(30)).is_ok());
+        assert!(matches!(
+            check_value(&intent, token::Amount::whole(31)),
+            Err(Error::ValueTooLow { value, .. })
+                if value == token::Amount::whole(30)
+        ));
+    }
+
+    /// Test that an intent is admitted until all of its exchanges have
+    /// expired
+    #[test]
+    fn test_check_intent_expiry() {
+        let keypair = keypair_1();
+        let time = |raw: &str| DateTimeUtc::from_str(raw).unwrap();
+        let exchange = |expires: &str| {
+            Signed::new(
+                &keypair,
+                Exchange {
+                    addr: xan(),
+                    token_sell: xan(),
+                    rate_min: DecimalWrapper::from_str("1").unwrap(),
+                    max_sell: token::Amount::whole(10),
+                    token_buy: btc(),
+                    min_buy: token::Amount::whole(1),
+                    vp: None,
+                    nft: None,
+                    expires: Some(time(expires)),
+                    partial: false,
+                    max_fee: token::Amount::default(),
+                },
+            )
+        };
+        let intent = Signed::new(
+            &keypair,
+            FungibleTokenIntent {
+                exchange: [
+                    exchange("2022-01-01T00:00:00Z"),
+                    exchange("2022-01-02T00:00:00Z"),
+                ]
+                .into_iter()
+                .collect(),
+                nonce: 0,
+            },
+        );
+        let intent = Intent::new(intent.try_to_vec().unwrap());
+
+        assert!(check_expiry(&intent, time("2022-01-01T12:00:00Z")).is_ok());
+        assert!(matches!(
+            check_expiry(&intent, time("2022-01-02T00:00:01Z")),
+            Err(Error::Expired(_))
+        ));
+
+        // Other intents don't expire
+        let other = Intent::new(b"other".to_vec());
+        assert!(check_expiry(&other, time("2100-01-01T00:00:00Z")).is_ok());
+    }
+
+    /// Test that the value of an exchange that sells a token of an NFT is its
+    /// price
+    #[test]
+    fn test_check_nft_intent_value() {
+        let exchange = Exchange {
+            addr: xan(),
+            token_sell: btc(),
+            rate_min: DecimalWrapper::default(),
+            max_sell: token::Amount::default(),
+            token_buy: xan(),
+            min_buy: token::Amount::whole(25),
+            vp: None,
+            nft: Some(ExchangeNft {
+                side: NftSide::Sell,
+                token_id: "1".to_owned(),
+            }),
+            expires: None,
+            partial: false,
+            max_fee: token::Amount::default(),
+        };
+        let intent = FungibleTokenIntent {
+            exchange: [Signed::new(&keypair_1(), exchange)]
+                .into_iter()
+                .collect(),
+            nonce: 0,
+        };
+
+        assert!(check_value(&intent, token::Amount::whole(25)).is_ok());
+        assert!(check_value(&intent, token::Amount::whole(26)).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+//! The depth of the orderbook of a token pair, aggregated from the open
+//! intents held in the intent gossiper's mempool. The exchanges that sell the
+//! base token for the quote token are the asks and the ones that sell the
+//! quote token for the base token are the bids. The prices are in units of the
+//! quote token per unit of the base token and the sizes in the base token.
+//!
+//! The exchanges of tokens of NFTs and the expired exchanges are ignored. The
+//! sizes are the maximum amounts of the exchanges, as the amounts that have
+//! already been filled in part are only known to the matchmakers.
+
+use std::cmp::Ordering;
+
+use anoma::proto::{Intent, Signed};
+use anoma::types::address::Address;
+use anoma::types::intent::{Exchange, FungibleTokenIntent, RingSignedIntent};
+use anoma::types::time::DateTimeUtc;
+use anoma::types::token;
+use borsh::BorshDeserialize;
+
+use crate::proto::services;
+
+/// The maximum number of price levels on each side
+pub const MAX_LEVELS: usize = 1_000;
+
+/// The relative difference under which two prices are at the same level
+const PRICE_EPSILON: f64 = 1e-9;
+
+/// The price levels of the orderbook of a token pair on each side
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Depth {
+    /// The bids, from the highest price
+    pub bids: Vec<PriceLevel>,
+    /// The asks, from the lowest price
+    pub asks: Vec<PriceLevel>,
+}
+
+/// A price level of the orderbook
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceLevel {
+    /// The amount of the quote token per unit of the base token
+    pub price: f64,
+    /// The amount of the base token at this price
+    pub size: token::Amount,
+    /// The amount of the base token at this price and the better ones
+    pub cumulative_size: token::Amount,
+}
+
+impl From<Depth> for services::OrderbookDepth {
+    fn from(depth: Depth) -> Self {
+        services::OrderbookDepth {
+            bids: depth.bids.into_iter().map(Into::into).collect(),
+            asks: depth.asks.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<PriceLevel> for services::PriceLevel {
+    fn from(level: PriceLevel) -> Self {
+        services::PriceLevel {
+            price: level.price,
+            size: level.size.to_string(),
+            cumulative_size: level.cumulative_size.to_string(),
+        }
+    }
+}
+
+/// Aggregate the exchanges of the intents that are open at the given time on
+/// the token pair into up to `max_levels` price levels on each side
+pub fn depth<'a>(
+    intents: impl IntoIterator<Item = &'a Intent>,
+    base: &Address,
+    quote: &Address,
+    now: DateTimeUtc,
+    max_levels: usize,
+) -> Depth {
+    let mut bids = vec![];
+    let mut asks = vec![];
+    for exchange in intents.into_iter().flat_map(exchanges) {
+        if exchange.nft.is_some()
+            || exchange.expires.map_or(false, |expires| expires < now)
+        {
+            continue;
+        }
+        let rate = min_rate(&exchange);
+        if &exchange.token_sell == base && &exchange.token_buy == quote {
+            asks.push((rate, exchange.max_sell));
+        } else if &exchange.token_sell == quote && &exchange.token_buy == base {
+            // The bid pays at most the inverse of its rate for a unit of the
+            // base token
+            let size = token::Amount::from(f64::from(exchange.max_sell) * rate);
+            bids.push((1.0 / rate, size));
+        }
+    }
+    bids.retain(|(price, _size)| price.is_finite());
+    bids.sort_by(|(a, _), (b, _)| compare_prices(*b, *a));
+    asks.sort_by(|(a, _), (b, _)| compare_prices(*a, *b));
+    Depth {
+        bids: levels(bids, max_levels),
+        asks: levels(asks, max_levels),
+    }
+}
+
+/// The exchanges of an intent, if it's a fungible token intent or a
+/// ring-signed intent. The source of the exchanges of a ring-signed intent is
+/// one of the ring's addresses.
+fn exchanges(intent: &Intent) -> Vec<Exchange> {
+    if let Ok(intent) =
+        Signed::<FungibleTokenIntent>::try_from_slice(&intent.data)
+    {
+        intent
+            .data
+            .exchange
+            .into_iter()
+            .map(|exchange| exchange.data)
+            .collect()
+    } else if let Ok(intent) = RingSignedIntent::try_from_slice(&intent.data) {
+        match intent.data.ring.first() {
+            Some(addr) => intent
+                .data
+                .exchange
+                .iter()
+                .map(|exchange| exchange.with_source(addr.clone()))
+                .collect(),
+            None => vec![],
+        }
+    } else {
+        vec![]
+    }
+}
+
+/// The minimum amount of the `token_buy` of an exchange per unit of its
+/// `token_sell`
+fn min_rate(exchange: &Exchange) -> f64 {
+    let rate_min = f64::from(&exchange.rate_min);
+    if exchange.max_sell == token::Amount::default() {
+        rate_min
+    } else {
+        rate_min.max(f64::from(exchange.min_buy) / f64::from(exchange.max_sell))
+    }
+}
+
+/// Aggregate the sorted prices and sizes into price levels
+fn levels(
+    orders: Vec<(f64, token::Amount)>,
+    max_levels: usize,
+) -> Vec<PriceLevel> {
+    let mut levels: Vec<PriceLevel> = vec![];
+    let mut cumulative_size = token::Amount::default();
+    for (price, size) in orders {
+        cumulative_size += size;
+        match levels.last_mut() {
+            Some(level)
+                if compare_prices(level.price, price) == Ordering::Equal =>
+            {
+                level.size += size;
+                level.cumulative_size = cumulative_size;
+            }
+            _ if levels.len() >= max_levels => break,
+            _ => levels.push(PriceLevel {
+                price,
+                size,
+                cumulative_size,
+            }),
+        }
+    }
+    levels
+}
+
+/// Compare prices, which are the same up to [`PRICE_EPSILON`]
+fn compare_prices(a: f64, b: f64) -> Ordering {
+    if (a - b).abs() <= PRICE_EPSILON * a.abs().max(b.abs()) {
+        Ordering::Equal
+    } else {
+        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anoma::types::address::{btc, eth, xan};
+    use anoma::types::intent::DecimalWrapper;
+    use anoma::types::key::testing::keypair_1;
+    use borsh::BorshSerialize;
+
+    use super::*;
+
+    /// Test that the exchanges on a token pair are aggregated into price
+    /// levels with cumulative sizes on each side
+    #[test]
+    fn test_depth() {
+        let time = |raw: &str| DateTimeUtc::from_str(raw).unwrap();
+        let keypair = keypair_1();
+        let exchange = |token_sell, token_buy, rate: &str, max_sell| Exchange {
+            addr: xan(),
+            token_sell,
+            rate_min: DecimalWrapper::from_str(rate).unwrap(),
+            max_sell: token::Amount::whole(max_sell),
+            token_buy,
+            min_buy: token::Amount::default(),
+            vp: None,
+            nft: None,
+            expires: None,
+            partial: true,
+            max_fee: token::Amount::default(),
+        };
+        let mut expired = exchange(btc(), xan(), "1", 100);
+        expired.expires = Some(time("2022-01-01T00:00:00Z"));
+        let exchanges = vec![
+            // Asks of BTC in XAN
+            exchange(btc(), xan(), "2", 3),
+            exchange(btc(), xan(), "2", 2),
+            exchange(btc(), xan(), "3", 1),
+            expired,
+            // Bids of BTC in XAN
+            exchange(xan(), btc(), "0.5", 4),
+            exchange(xan(), btc(), "1", 10),
+            // Another pair
+            exchange(eth(), xan(), "1", 100),
+        ];
+        let intent = FungibleTokenIntent {
+            exchange: exchanges
+                .into_iter()
+                .map(|exchange| Signed::new(&keypair, exchange))
+                .collect(),
+            nonce: 0,
+        };
+        let intent =
+            Intent::new(Signed::new(&keypair, intent).try_to_vec().unwrap());
+
+        let depth = depth(
+            [&intent],
+            &btc(),
+            &xan(),
+            time("2022-01-02T00:00:00Z"),
+            MAX_LEVELS,
+        );
+        let level = |price, size, cumulative_size| PriceLevel {
+            price,
+            size: token::Amount::whole(size),
+            cumulative_size: token::Amount::whole(cumulative_size),
+        };
+        assert_eq!(depth.asks, vec![level(2.0, 5, 5), level(3.0, 1, 6)]);
+        assert_eq!(depth.bids, vec![level(2.0, 2, 2), level(1.0, 10, 12)]);
+    }
+}
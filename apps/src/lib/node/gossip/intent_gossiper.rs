@@ -1,12 +1,27 @@
+use std::collections::HashMap;
 use std::net::ToSocketAddrs;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use anoma::proto::{Intent, IntentId};
+use anoma::proto::{Intent, IntentCancel, IntentId, Signed};
+use anoma::types::address::Address;
+use anoma::types::intent::FungibleTokenIntent;
+use anoma::types::matchmaker::AddIntentResult;
+use anoma::types::time::DateTimeUtc;
+use borsh::BorshDeserialize;
+use thiserror::Error;
+use tokio::sync::mpsc;
 
+use super::admission;
+use super::depth::{self, Depth};
 use super::mempool::IntentMempool;
+use super::metrics::Metrics;
+use super::replay::{self, SeenIntents};
 use super::rpc::matchmakers::{
     MsgFromClient, MsgFromServer, ServerDialer, ServerListener,
 };
+use super::subscriptions::MatchSubscriptions;
+use crate::config::IntentAdmission;
 
 /// A server for connected matchmakers that can receive intents from the intent
 /// gossiper node and send back the results from their filter, if any, or from
@@ -18,6 +33,13 @@ pub struct MatchmakersServer {
     listener: Option<ServerListener>,
     /// Known intents mempool, shared with [`IntentGossiper`].
     mempool: Arc<RwLock<IntentMempool>>,
+    /// Pending intents dry-runs, shared with [`IntentGossiper`].
+    dry_runs: PendingDryRuns,
+    /// The node's metrics, shared with [`IntentGossiper`].
+    metrics: Arc<Metrics>,
+    /// The subscriptions to the matches of intents, shared with the RPC
+    /// server
+    subscriptions: Arc<MatchSubscriptions>,
 }
 
 /// Intent gossiper handle can be cloned and is thread safe.
@@ -27,26 +49,88 @@ pub struct IntentGossiper {
     mempool: Arc<RwLock<IntentMempool>>,
     /// A dialer can send messages to the connected matchmaker
     dialer: ServerDialer,
+    /// Pending intents dry-runs, shared with [`MatchmakersServer`].
+    dry_runs: PendingDryRuns,
+    /// The ID of the next intent dry-run request
+    next_dry_run_id: u64,
+    /// The requirements for intents to be admitted into the mempool
+    admission: IntentAdmission,
+    /// The node's metrics, shared with [`MatchmakersServer`].
+    metrics: Arc<Metrics>,
+    /// The intents seen by the node, shared with the P2P network
+    seen: Arc<SeenIntents>,
+}
+
+/// A message for the intent gossiper received from a peer in the gossip network
+#[derive(Debug)]
+pub enum PeerMessage {
+    /// An intent with the topic it has been received on
+    Intent(Intent, String),
+    /// A cancellation of an intent, whose signature has been verified
+    Cancel(IntentCancel),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum CancelError {
+    #[error("The signature of the cancellation is invalid")]
+    InvalidSignature,
+    #[error("The intent is not in the mempool")]
+    UnknownIntent,
+    #[error("The intent is not signed with the key of the cancellation")]
+    NotSigner,
+}
+
+/// The senders of the results of the pending intents dry-runs, keyed by their
+/// request IDs
+type PendingDryRuns =
+    Arc<RwLock<HashMap<u64, mpsc::UnboundedSender<AddIntentResult>>>>;
+
+/// An intent dry-run requested from the connected matchmakers. The dry-run is
+/// no longer pending once this is dropped.
+#[derive(Debug)]
+pub struct IntentDryRun {
+    /// The number of the matchmakers that the intent has been sent to
+    pub matchmakers: usize,
+    request_id: u64,
+    results: mpsc::UnboundedReceiver<AddIntentResult>,
+    dry_runs: PendingDryRuns,
 }
 
 impl MatchmakersServer {
-    /// Create a new gossip intent app with a matchmaker, if enabled.
+    /// Create a new gossip intent app with a matchmaker, if enabled. The
+    /// intents are kept in the given mempool and the intents received via
+    /// RPC are recorded in the seen intents.
     pub fn new_pair(
         matchmakers_server_addr: impl ToSocketAddrs,
+        admission: IntentAdmission,
+        mempool: IntentMempool,
+        metrics: Arc<Metrics>,
+        subscriptions: Arc<MatchSubscriptions>,
+        seen: Arc<SeenIntents>,
     ) -> (Self, IntentGossiper) {
         // Prepare a server for matchmakers connections
         let (listener, dialer) =
             ServerListener::new_pair(matchmakers_server_addr);
 
-        let mempool = Arc::new(RwLock::new(IntentMempool::default()));
+        let mempool = Arc::new(RwLock::new(mempool));
+        let dry_runs = PendingDryRuns::default();
         let intent_gossiper = IntentGossiper {
             mempool: mempool.clone(),
             dialer,
+            dry_runs: dry_runs.clone(),
+            next_dry_run_id: 0,
+            admission,
+            metrics: metrics.clone(),
+            seen,
         };
         (
             Self {
                 listener: Some(listener),
                 mempool,
+                dry_runs,
+                metrics,
+                subscriptions,
             },
             intent_gossiper,
         )
@@ -59,6 +143,7 @@ impl MatchmakersServer {
             .listen(|msg| match msg {
                 MsgFromClient::InvalidIntent { id } => {
                     let id = IntentId(id);
+                    self.metrics.matchmaker_responded(&id, Instant::now());
                     // Remove matched intents from mempool
                     tracing::info!("Removing matched intent ID {}", id);
                     let mut w_mempool = self.mempool.write().unwrap();
@@ -66,6 +151,7 @@ impl MatchmakersServer {
                 }
                 MsgFromClient::IntentConstraintsTooComplex { id } => {
                     let id = IntentId(id);
+                    self.metrics.matchmaker_responded(&id, Instant::now());
                     tracing::info!(
                         "Intent ID {} has constraints that are too complex \
                          for a connected matchmaker",
@@ -74,24 +160,86 @@ impl MatchmakersServer {
                 }
                 MsgFromClient::IgnoredIntent { id } => {
                     let id = IntentId(id);
+                    self.metrics.matchmaker_responded(&id, Instant::now());
                     tracing::info!(
                         "Intent ID {} ignored by a connected matchmaker",
                         id
                     );
                 }
-                MsgFromClient::Matched { intent_ids } => {
+                MsgFromClient::Matched {
+                    intent_ids,
+                    tx_hash,
+                } => {
+                    self.metrics.intents_matched(intent_ids.len());
                     // Remove matched intents from mempool
                     let mut w_mempool = self.mempool.write().unwrap();
                     for id in intent_ids {
                         let id = IntentId(id);
+                        self.metrics.matchmaker_responded(&id, Instant::now());
                         tracing::info!("Removing matched intent ID {}", id);
+                        if let Some(intent) = w_mempool.get(&id) {
+                            self.subscriptions.notify(
+                                intent,
+                                tx_hash.clone(),
+                                false,
+                            );
+                        }
                         w_mempool.remove(&id);
                     }
                 }
+                MsgFromClient::PartiallyMatched {
+                    intent_ids,
+                    tx_hash,
+                } => {
+                    // The intents stay in mempool to be matched and gossiped
+                    // for their remainder
+                    let r_mempool = self.mempool.read().unwrap();
+                    for id in intent_ids {
+                        let id = IntentId(id);
+                        self.metrics.matchmaker_responded(&id, Instant::now());
+                        tracing::info!(
+                            "Keeping partially matched intent ID {}",
+                            id
+                        );
+                        if let Some(intent) = r_mempool.get(&id) {
+                            self.subscriptions.notify(
+                                intent,
+                                tx_hash.clone(),
+                                true,
+                            );
+                        }
+                    }
+                }
                 MsgFromClient::Unmatched { id } => {
                     let id = IntentId(id);
+                    self.metrics.matchmaker_responded(&id, Instant::now());
                     tracing::info!("No match found for intent ID {}", id);
                 }
+                MsgFromClient::DryRunResult {
+                    request_id,
+                    tx,
+                    matched_intents,
+                } => {
+                    let r_dry_runs = self.dry_runs.read().unwrap();
+                    match r_dry_runs.get(&request_id) {
+                        Some(results) => {
+                            // The receiver may have been dropped in the
+                            // meantime
+                            let _ = results.send(AddIntentResult {
+                                tx,
+                                matched_intents,
+                                partially_matched_intents: None,
+                            });
+                        }
+                        None => {
+                            tracing::info!(
+                                "Ignoring a result of the intent dry-run {} \
+                                 that is no longer pending",
+                                request_id
+                            );
+                        }
+                    }
+                }
             })
             .await
     }
@@ -99,25 +247,180 @@ impl MatchmakersServer {
 
 impl IntentGossiper {
     // Apply the logic to a new intent. It only tries to apply the matchmaker if
-    // this one exists. If no matchmaker then returns true.
-    pub async fn add_intent(&mut self, intent: Intent) {
+    // this one exists. If no matchmaker then returns true. The intents that
+    // have expired in the meantime are removed from mempool. The intent is
+    // stored with the topic it has been received on.
+    pub async fn add_intent(&mut self, intent: Intent, topic: String) {
+        self.metrics.intent_received();
+        let mut w_mempool = self.mempool.write().unwrap();
+        let expired = w_mempool.remove_expired(DateTimeUtc::now());
+        drop(w_mempool);
+        self.metrics.intents_expired(expired.len());
+        for id in expired {
+            tracing::info!("Removing expired intent ID {}", id);
+        }
+
         let id = intent.id();
+        if let Err(err) = self.check_admission(&intent).await {
+            tracing::info!("Intent ID {} not admitted: {}", id, err);
+            self.metrics.intent_rejected();
+            return;
+        }
 
         let r_mempool = self.mempool.read().unwrap();
         let is_known = r_mempool.contains(&id);
         drop(r_mempool);
         if !is_known {
             let mut w_mempool = self.mempool.write().unwrap();
-            w_mempool.insert(intent.clone());
+            w_mempool.insert(intent.clone(), topic);
         }
 
         tracing::info!(
             "Sending intent ID {} to connected matchmakers, if any",
             id
         );
+        // Recorded before sending, as the matchmakers may respond before
+        // `send` returns
+        self.metrics
+            .intent_sent_to_matchmakers(id.clone(), Instant::now());
         self.dialer.send(MsgFromServer::AddIntent {
             id: id.0,
             data: intent.data,
+        });
+    }
+
+    /// Cancel an intent in the mempool. The cancellation must be signed with
+    /// the key that signed the intent, which is only known for fungible token
+    /// intents. Returns the ID of the removed intent.
+    pub fn cancel_intent(
+        &self,
+        cancel: &IntentCancel,
+    ) -> Result<IntentId, CancelError> {
+        cancel.verify().map_err(|_| CancelError::InvalidSignature)?;
+        let mut w_mempool = self.mempool.write().unwrap();
+        let intent = w_mempool
+            .find_by_hash(&cancel.intent_hash)
+            .ok_or(CancelError::UnknownIntent)?;
+        let is_signer =
+            Signed::<FungibleTokenIntent>::try_from_slice(&intent.data)
+                .map(|intent| intent.verify(&cancel.public_key).is_ok())
+                .unwrap_or_default();
+        if !is_signer {
+            return Err(CancelError::NotSigner);
+        }
+        let id = intent.id();
+        w_mempool.remove(&id);
+        tracing::info!("Removing cancelled intent ID {}", id);
+        Ok(id)
+    }
+
+    /// The intents in the mempool that have been received on the given topic
+    /// and haven't expired, in the order of their timestamps
+    pub fn intents_on_topic(&self, topic: &str) -> Vec<Intent> {
+        let now = DateTimeUtc::now();
+        let r_mempool = self.mempool.read().unwrap();
+        let mut intents: Vec<Intent> = r_mempool
+            .intents_on_topic(topic)
+            .filter(|intent| admission::check_expiry(intent, now).is_ok())
+            .cloned()
+            .collect();
+        intents.sort_by_key(|intent| intent.timestamp);
+        intents
+    }
+
+    /// The depth of the orderbook of a token pair with up to the given number
+    /// of price levels on each side, aggregated from the intents in the
+    /// mempool that have been received on the given topic and haven't expired
+    pub fn orderbook_depth(
+        &self,
+        topic: &str,
+        base_token: &Address,
+        quote_token: &Address,
+        levels: usize,
+    ) -> Depth {
+        let now = DateTimeUtc::now();
+        let r_mempool = self.mempool.read().unwrap();
+        let intents = r_mempool
+            .intents_on_topic(topic)
+            .filter(|intent| admission::check_expiry(intent, now).is_ok());
+        depth::depth(
+            intents,
+            base_token,
+            quote_token,
+            now,
+            levels.min(depth::MAX_LEVELS),
+        )
+    }
+
+    /// Check that an intent meets the requirements to be admitted into the
+    /// mempool, if any
+    pub async fn check_admission(
+        &self,
+        intent: &Intent,
+    ) -> Result<(), admission::Error> {
+        admission::check_intent(&self.admission, intent).await
+    }
+
+    /// Check that an intent hasn't already been seen, e.g. that it's not
+    /// replayed, and record that it has been seen
+    pub fn check_replay(&self, intent: &Intent) -> Result<(), replay::Error> {
+        self.seen.insert(&intent.data, DateTimeUtc::now())
+    }
+
+    /// Send an intent to the connected matchmakers to find how they would
+    /// match it, without adding it to the mempool or to the matchmakers.
+    pub fn dry_run_intent(&mut self, intent: Intent) -> IntentDryRun {
+        let request_id = self.next_dry_run_id;
+        self.next_dry_run_id = self.next_dry_run_id.wrapping_add(1);
+
+        // Register the dry-run before sending it to be ready for the results
+        let (results_send, results) = mpsc::unbounded_channel();
+        let mut w_dry_runs = self.dry_runs.write().unwrap();
+        w_dry_runs.insert(request_id, results_send);
+        drop(w_dry_runs);
+
+        let id = intent.id();
+        tracing::info!(
+            "Sending intent ID {} to connected matchmakers for a dry-run {}, \
+             if any",
+            id,
+            request_id
+        );
+        let matchmakers = self.dialer.send(MsgFromServer::DryRunIntent {
+            request_id,
+            id: id.0,
+            data: intent.data,
+        });
+        IntentDryRun {
+            matchmakers,
+            request_id,
+            results,
+            dry_runs: self.dry_runs.clone(),
+        }
+    }
+}
+
+impl IntentDryRun {
+    /// Wait for the results from all the matchmakers that the intent has
+    /// been sent to, or until the timeout, and return the received results.
+    pub async fn results(mut self, timeout: Duration) -> Vec<AddIntentResult> {
+        let mut results = Vec::with_capacity(self.matchmakers);
+        let _ = tokio::time::timeout(timeout, async {
+            while results.len() < self.matchmakers {
+                match self.results.recv().await {
+                    Some(result) => results.push(result),
+                    None => break,
+                }
+            }
         })
+        .await;
+        results
+    }
+}
+
+impl Drop for IntentDryRun {
+    fn drop(&mut self) {
+        let mut w_dry_runs = self.dry_runs.write().unwrap();
+        w_dry_runs.remove(&self.request_id);
     }
 }
@@ -1,26 +1,274 @@
+//! The intent gossiper's mempool. The intents are kept in memory and, if the
+//! mempool is opened with a store, persisted in RocksDB under the node's base
+//! directory, so that they're reloaded when the node restarts.
+//!
+//! The store is keyed by the SHA-256 hash of the intents, see
+//! [`Intent::sha256`]. The expired intents are pruned when they're reloaded
+//! and the intents removed from the mempool, e.g. once matched or cancelled,
+//! are deleted from the store.
+
 use std::collections::HashMap;
+use std::path::Path;
 
 use anoma::proto::{Intent, IntentId};
+use anoma::types::time::DateTimeUtc;
+use borsh::{BorshDeserialize, BorshSerialize};
+use rocksdb::{IteratorMode, Options};
+use thiserror::Error;
+
+use super::admission;
+
+/// The directory of the intents store in the node's base directory
+const STORE_DIR: &str = "intent_mempool";
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to open the intent mempool store: {0}")]
+    OpenStore(rocksdb::Error),
+}
+
+/// Intent mempool, optionally persisted in a store
+#[derive(Debug, Default)]
+pub struct IntentMempool {
+    /// The intents with the topics they have been received on
+    intents: HashMap<IntentId, (Intent, String)>,
+    /// The persistent store of the intents, if any
+    store: Option<rocksdb::DB>,
+}
 
-/// In-memory intent mempool
-#[derive(Clone, Debug, Default)]
-pub struct IntentMempool(HashMap<IntentId, Intent>);
+/// An intent in the store with the topic it has been received on
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+struct StoredIntent {
+    data: Vec<u8>,
+    timestamp: DateTimeUtc,
+    topic: String,
+    /// The expiry of the intent, if any, see [`admission::expiry`]
+    expires: Option<DateTimeUtc>,
+}
 
 impl IntentMempool {
-    /// Insert a new intent. If the mempool didn't have this intent present,
-    /// returns `true`.
-    pub fn insert(&mut self, intent: Intent) -> bool {
-        self.0.insert(intent.id(), intent).is_none()
+    /// Open the mempool with the store in the given base directory and reload
+    /// the stored intents. The intents that are expired at the given time are
+    /// pruned from the store.
+    pub fn open(
+        base_dir: impl AsRef<Path>,
+        time: DateTimeUtc,
+    ) -> Result<Self, Error> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let store = rocksdb::DB::open(&opts, base_dir.as_ref().join(STORE_DIR))
+            .map_err(Error::OpenStore)?;
+
+        let mut intents = HashMap::new();
+        let mut pruned = vec![];
+        for (key, bytes) in store.iterator(IteratorMode::Start) {
+            match StoredIntent::try_from_slice(&bytes) {
+                Ok(stored) if stored.expires.map_or(true, |e| time <= e) => {
+                    let intent = Intent {
+                        data: stored.data,
+                        timestamp: stored.timestamp,
+                    };
+                    tracing::info!(
+                        "Reloaded intent ID {} received on the topic {}",
+                        intent.id(),
+                        stored.topic
+                    );
+                    intents.insert(intent.id(), (intent, stored.topic));
+                }
+                Ok(_) => pruned.push(key),
+                Err(err) => {
+                    tracing::error!(
+                        "Failed to decode a stored intent: {}",
+                        err
+                    );
+                    pruned.push(key);
+                }
+            }
+        }
+        for key in pruned {
+            if let Err(err) = store.delete(key) {
+                tracing::error!("Failed to prune a stored intent: {}", err);
+            }
+        }
+        Ok(Self {
+            intents,
+            store: Some(store),
+        })
+    }
+
+    /// Insert a new intent received on the given topic. If the mempool didn't
+    /// have this intent present, returns `true`.
+    pub fn insert(&mut self, intent: Intent, topic: String) -> bool {
+        if let Some(store) = &self.store {
+            let stored = StoredIntent {
+                data: intent.data.clone(),
+                timestamp: intent.timestamp,
+                topic: topic.clone(),
+                expires: admission::expiry(&intent),
+            };
+            let bytes = stored
+                .try_to_vec()
+                .expect("Encoding a stored intent shouldn't fail");
+            if let Err(err) = store.put(intent.sha256(), bytes) {
+                tracing::error!(
+                    "Failed to store intent ID {}: {}",
+                    intent.id(),
+                    err
+                );
+            }
+        }
+        self.intents.insert(intent.id(), (intent, topic)).is_none()
     }
 
     /// Remove an intent from mempool. If the mempool didn't have this intent
     /// present, returns `true`. in the mempool.
     pub fn remove(&mut self, intent_id: &IntentId) -> bool {
-        self.0.remove(intent_id).is_some()
+        match self.intents.remove(intent_id) {
+            Some((intent, _topic)) => {
+                self.delete_stored(&intent);
+                true
+            }
+            None => false,
+        }
     }
 
     /// Returns `true` if the map contains intent with specified ID.
     pub fn contains(&self, intent_id: &IntentId) -> bool {
-        self.0.contains_key(intent_id)
+        self.intents.contains_key(intent_id)
+    }
+
+    /// The intent with the given ID, if any
+    pub fn get(&self, intent_id: &IntentId) -> Option<&Intent> {
+        self.intents.get(intent_id).map(|(intent, _topic)| intent)
+    }
+
+    /// Find an intent by its SHA-256 hash, see [`Intent::sha256`]
+    pub fn find_by_hash(&self, intent_hash: &[u8]) -> Option<&Intent> {
+        self.intents
+            .values()
+            .map(|(intent, _topic)| intent)
+            .find(|intent| intent.sha256().as_slice() == intent_hash)
+    }
+
+    /// All the intents
+    pub fn intents(&self) -> impl Iterator<Item = &Intent> {
+        self.intents.values().map(|(intent, _topic)| intent)
+    }
+
+    /// The intents that have been received on the given topic
+    pub fn intents_on_topic<'a>(
+        &'a self,
+        topic: &'a str,
+    ) -> impl Iterator<Item = &'a Intent> + 'a {
+        self.intents
+            .values()
+            .filter(move |(_intent, intent_topic)| intent_topic == topic)
+            .map(|(intent, _topic)| intent)
+    }
+
+    /// Remove the intents that are expired at the given time from mempool.
+    /// Returns the IDs of the removed intents.
+    pub fn remove_expired(&mut self, time: DateTimeUtc) -> Vec<IntentId> {
+        let expired: Vec<IntentId> = self
+            .intents
+            .iter()
+            .filter(|(_id, (intent, _topic))| {
+                admission::check_expiry(intent, time).is_err()
+            })
+            .map(|(id, _intent)| id.clone())
+            .collect();
+        for id in &expired {
+            self.remove(id);
+        }
+        expired
+    }
+
+    /// Delete an intent from the store, if any
+    fn delete_stored(&self, intent: &Intent) {
+        if let Some(store) = &self.store {
+            if let Err(err) = store.delete(intent.sha256()) {
+                tracing::error!(
+                    "Failed to delete stored intent ID {}: {}",
+                    intent.id(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anoma::proto::Signed;
+    use anoma::types::address::{btc, xan};
+    use anoma::types::intent::{DecimalWrapper, Exchange, FungibleTokenIntent};
+    use anoma::types::key::testing::keypair_1;
+    use anoma::types::token;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Test that the stored intents are reloaded, except for the expired and
+    /// removed ones
+    #[test]
+    fn test_reload_stored_intents() {
+        let base_dir = TempDir::new().unwrap();
+        let time = |raw: &str| DateTimeUtc::from_str(raw).unwrap();
+        let keypair = keypair_1();
+        let intent = |expires: &str| {
+            let exchange = Exchange {
+                addr: xan(),
+                token_sell: xan(),
+                rate_min: DecimalWrapper::from_str("1").unwrap(),
+                max_sell: token::Amount::whole(10),
+                token_buy: btc(),
+                min_buy: token::Amount::whole(1),
+                vp: None,
+                nft: None,
+                expires: Some(time(expires)),
+                partial: false,
+                max_fee: token::Amount::default(),
+            };
+            let intent = FungibleTokenIntent {
+                exchange: [Signed::new(&keypair, exchange)]
+                    .into_iter()
+                    .collect(),
+                nonce: 0,
+            };
+            Intent::new(Signed::new(&keypair, intent).try_to_vec().unwrap())
+        };
+        let kept = intent("2022-01-02T00:00:00Z");
+        let expired = intent("2022-01-01T00:00:00Z");
+        let removed = intent("2022-01-03T00:00:00Z");
+        let other = Intent::new(b"other".to_vec());
+
+        let mut mempool =
+            IntentMempool::open(&base_dir, time("2021-12-31T00:00:00Z"))
+                .unwrap();
+        for intent in [&kept, &expired, &removed, &other] {
+            assert!(mempool.insert(intent.clone(), "topic".to_owned()));
+        }
+        assert!(mempool.remove(&removed.id()));
+        drop(mempool);
+
+        let mempool =
+            IntentMempool::open(&base_dir, time("2022-01-01T12:00:00Z"))
+                .unwrap();
+        assert!(mempool.contains(&kept.id()));
+        assert!(mempool.contains(&other.id()));
+        assert!(!mempool.contains(&expired.id()));
+        assert!(!mempool.contains(&removed.id()));
+        assert_eq!(mempool.intents_on_topic("topic").count(), 2);
+        assert_eq!(mempool.intents_on_topic("other").count(), 0);
+        drop(mempool);
+
+        // The expired intent has been pruned from the store
+        let mempool =
+            IntentMempool::open(&base_dir, time("2021-12-31T00:00:00Z"))
+                .unwrap();
+        assert!(!mempool.contains(&expired.id()));
     }
 }
@@ -0,0 +1,290 @@
+//! The metrics of an intent gossip node, served in the Prometheus text format
+//! on `GET /metrics` when the node is configured with a `metrics_addr`.
+//!
+//! The matchmaker latency is the time from sending an intent to the connected
+//! matchmakers until the first of them responds with a result for it.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anoma::proto::IntentId;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
+use thiserror::Error;
+
+/// The upper bounds of the matchmaker latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The number of intents awaiting a matchmaker response above which the ones
+/// that have been waiting for longer than the last bucket are dropped, e.g.
+/// when no matchmaker is connected
+const MAX_PENDING: usize = 10_000;
+
+/// The content type of the Prometheus text format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to bind the metrics server to {0}: {1}")]
+    Bind(SocketAddr, hyper::Error),
+    #[error("The metrics server failed: {0}")]
+    Serve(hyper::Error),
+}
+
+/// The metrics of an intent gossip node, shared by its components
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// The number of intents received from peers and via RPC
+    intents_received: AtomicU64,
+    /// The number of intents that haven't met the admission requirements
+    intents_rejected: AtomicU64,
+    /// The number of intents matched by the connected matchmakers
+    intents_matched: AtomicU64,
+    /// The number of intents removed from the mempool once expired
+    intents_expired: AtomicU64,
+    /// The number of peers currently connected in the gossip network
+    peers_connected: AtomicI64,
+    /// The latencies of the connected matchmakers
+    matchmaker_latency: Mutex<Histogram>,
+    /// The times at which the intents have been sent to the connected
+    /// matchmakers, until one of them responds
+    pending: Mutex<HashMap<IntentId, Instant>>,
+}
+
+/// A histogram with the [`LATENCY_BUCKETS`]
+#[derive(Debug, Default)]
+struct Histogram {
+    /// The number of observations in each bucket, not cumulative
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Metrics {
+    /// Count an intent received from a peer or via RPC
+    pub fn intent_received(&self) {
+        self.intents_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count an intent that hasn't been admitted into the mempool
+    pub fn intent_rejected(&self) {
+        self.intents_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count the intents matched by a matchmaker
+    pub fn intents_matched(&self, count: usize) {
+        self.intents_matched
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    /// Count the intents removed from the mempool once expired
+    pub fn intents_expired(&self, count: usize) {
+        self.intents_expired
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    /// Count a newly connected peer
+    pub fn peer_connected(&self) {
+        self.peers_connected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a disconnected peer
+    pub fn peer_disconnected(&self) {
+        self.peers_connected.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Record that an intent has been sent to the connected matchmakers at
+    /// the given time
+    pub fn intent_sent_to_matchmakers(&self, id: IntentId, now: Instant) {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.len() >= MAX_PENDING {
+            let max_latency = Duration::from_secs_f64(
+                LATENCY_BUCKETS[LATENCY_BUCKETS.len() - 1],
+            );
+            pending.retain(|_id, sent| {
+                now.saturating_duration_since(*sent) <= max_latency
+            });
+        }
+        pending.insert(id, now);
+    }
+
+    /// Record that a matchmaker has responded with a result for an intent at
+    /// the given time. Only the first response to an intent is observed.
+    pub fn matchmaker_responded(&self, id: &IntentId, now: Instant) {
+        let sent = self.pending.lock().unwrap().remove(id);
+        if let Some(sent) = sent {
+            let latency = now.saturating_duration_since(sent).as_secs_f64();
+            self.matchmaker_latency.lock().unwrap().observe(latency);
+        }
+    }
+
+    /// Encode the metrics in the Prometheus text format
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "intent_gossip_intents_received_total",
+                "The number of intents received from peers and via RPC",
+                &self.intents_received,
+            ),
+            (
+                "intent_gossip_intents_rejected_total",
+                "The number of intents not admitted into the mempool",
+                &self.intents_rejected,
+            ),
+            (
+                "intent_gossip_intents_matched_total",
+                "The number of intents matched by the connected matchmakers",
+                &self.intents_matched,
+            ),
+            (
+                "intent_gossip_intents_expired_total",
+                "The number of expired intents removed from the mempool",
+                &self.intents_expired,
+            ),
+        ];
+        for (name, help, counter) in counters {
+            let value = counter.load(Ordering::Relaxed);
+            writeln!(out, "# HELP {} {}", name, help).unwrap();
+            writeln!(out, "# TYPE {} counter", name).unwrap();
+            writeln!(out, "{} {}", name, value).unwrap();
+        }
+
+        let name = "intent_gossip_peers_connected";
+        writeln!(
+            out,
+            "# HELP {} The number of peers connected in the gossip network",
+            name
+        )
+        .unwrap();
+        writeln!(out, "# TYPE {} gauge", name).unwrap();
+        let peers = self.peers_connected.load(Ordering::Relaxed);
+        writeln!(out, "{} {}", name, peers).unwrap();
+
+        let name = "intent_gossip_matchmaker_latency_seconds";
+        writeln!(
+            out,
+            "# HELP {} The time until a connected matchmaker responds to an \
+             intent",
+            name
+        )
+        .unwrap();
+        writeln!(out, "# TYPE {} histogram", name).unwrap();
+        let histogram = self.matchmaker_latency.lock().unwrap();
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+            cumulative += count;
+            writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative)
+                .unwrap();
+        }
+        writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, histogram.count)
+            .unwrap();
+        writeln!(out, "{}_sum {}", name, histogram.sum).unwrap();
+        writeln!(out, "{}_count {}", name, histogram.count).unwrap();
+        out
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if let Some(bucket) =
+            LATENCY_BUCKETS.iter().position(|bound| value <= *bound)
+        {
+            self.buckets[bucket] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Serve the metrics on `GET /metrics` at the given address
+pub async fn serve(
+    address: SocketAddr,
+    metrics: Arc<Metrics>,
+) -> Result<(), Error> {
+    let make_service = make_service_fn(move |_conn| {
+        let metrics = metrics.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let metrics = metrics.clone();
+                async move { Ok::<_, Infallible>(respond(&metrics, req)) }
+            }))
+        }
+    });
+    let server = Server::try_bind(&address)
+        .map_err(|err| Error::Bind(address, err))?
+        .serve(make_service);
+    tracing::info!("Serving the intent gossip metrics on {}", address);
+    server.await.map_err(Error::Serve)
+}
+
+fn respond(metrics: &Metrics, req: Request<Body>) -> Response<Body> {
+    let response = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header(header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(Body::from(metrics.encode())),
+        (_, "/metrics") => Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .body(Body::empty()),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty()),
+    };
+    response.expect("The metrics response should be valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the metrics are encoded in the Prometheus text format, with
+    /// the matchmaker latency observed once per intent
+    #[test]
+    fn test_encode_metrics() {
+        let metrics = Metrics::default();
+        metrics.intent_received();
+        metrics.intent_received();
+        metrics.intent_rejected();
+        metrics.intents_matched(2);
+        metrics.intents_expired(1);
+        metrics.peer_connected();
+        metrics.peer_connected();
+        metrics.peer_disconnected();
+
+        let id = IntentId(b"intent".to_vec());
+        let sent = Instant::now();
+        metrics.intent_sent_to_matchmakers(id.clone(), sent);
+        metrics.matchmaker_responded(&id, sent + Duration::from_millis(200));
+        // Only the first response is observed
+        metrics.matchmaker_responded(&id, sent + Duration::from_secs(20));
+        // An intent that hasn't been sent isn't observed
+        let other = IntentId(b"other".to_vec());
+        metrics.matchmaker_responded(&other, sent);
+
+        let encoded = metrics.encode();
+        let lines: Vec<&str> = encoded.lines().collect();
+        for expected in [
+            "intent_gossip_intents_received_total 2",
+            "intent_gossip_intents_rejected_total 1",
+            "intent_gossip_intents_matched_total 2",
+            "intent_gossip_intents_expired_total 1",
+            "intent_gossip_peers_connected 1",
+            "# TYPE intent_gossip_matchmaker_latency_seconds histogram",
+            "intent_gossip_matchmaker_latency_seconds_bucket{le=\"0.1\"} 0",
+            "intent_gossip_matchmaker_latency_seconds_bucket{le=\"0.25\"} 1",
+            "intent_gossip_matchmaker_latency_seconds_bucket{le=\"10\"} 1",
+            "intent_gossip_matchmaker_latency_seconds_bucket{le=\"+Inf\"} 1",
+            "intent_gossip_matchmaker_latency_seconds_count 1",
+        ] {
+            assert!(lines.contains(&expected), "missing {}", expected);
+        }
+    }
+}
@@ -1,16 +1,28 @@
+pub mod admission;
+pub mod depth;
 pub mod intent_gossiper;
 mod mempool;
+mod metrics;
 pub mod p2p;
+pub mod replay;
 pub mod rpc;
+mod subscriptions;
+pub mod trace;
 
 use std::path::Path;
+use std::sync::Arc;
 
-use anoma::proto::Intent;
+use anoma::types::key::ParsePublicKeyError;
+use anoma::types::time::DateTimeUtc;
 use thiserror::Error;
 use tokio::sync::mpsc;
 
-use self::intent_gossiper::IntentGossiper;
-use self::p2p::P2P;
+use self::intent_gossiper::{IntentGossiper, PeerMessage};
+use self::mempool::IntentMempool;
+use self::metrics::Metrics;
+use self::p2p::{Identity, P2P};
+use self::replay::SeenIntents;
+use self::subscriptions::MatchSubscriptions;
 use crate::config;
 use crate::proto::services::{rpc_message, RpcResponse};
 
@@ -18,6 +30,12 @@ use crate::proto::services::{rpc_message, RpcResponse};
 pub enum Error {
     #[error("Error initializing p2p: {0}")]
     P2pInit(p2p::Error),
+    #[error("Error opening the intent mempool: {0}")]
+    MempoolInit(mempool::Error),
+    #[error("Error opening the seen intents: {0}")]
+    SeenIntentsInit(replay::Error),
+    #[error("Invalid publisher key in the RPC config: {0}")]
+    RpcInit(ParsePublicKeyError),
 }
 
 type Result<T> = std::result::Result<T, Error>;
@@ -33,37 +51,96 @@ pub async fn run(
     config: config::IntentGossiper,
     base_dir: impl AsRef<Path>,
 ) -> Result<()> {
+    // Reload the intents persisted before the node was stopped
+    let mempool = IntentMempool::open(&base_dir, DateTimeUtc::now())
+        .map_err(Error::MempoolInit)?;
+
+    // Reload the intents seen before the node was stopped. The intents
+    // reloaded into the mempool have already been seen too. They can only be
+    // seen once if they differ only by their timestamps.
+    let now = DateTimeUtc::now();
+    let seen = Arc::new(
+        SeenIntents::open(&base_dir, now).map_err(Error::SeenIntentsInit)?,
+    );
+    for intent in mempool.intents() {
+        let _ = seen.insert(&intent.data, now);
+    }
+
+    // The metrics are collected even when they're not served
+    let metrics = Arc::new(Metrics::default());
+
+    // The RPC clients subscribe to the matches of intents made by the
+    // connected matchmakers
+    let subscriptions = Arc::new(MatchSubscriptions::default());
+
     // Prepare matchmakers server and dialer
     let (matchmakers_server, intent_gossiper) =
         intent_gossiper::MatchmakersServer::new_pair(
             &config.matchmakers_server_addr,
+            config.admission.clone(),
+            mempool,
+            metrics.clone(),
+            subscriptions.clone(),
+            seen.clone(),
         );
 
     // Async channel for intents received from peer
     let (peer_intent_send, peer_intent_recv) = tokio::sync::mpsc::channel(100);
 
+    // The node's identity is used in the P2P network and to sign the receipts
+    // of the intents received via RPC
+    let identity = p2p::Identity::load_or_gen(base_dir);
+
     // Create the P2P gossip network, which can send messages directly to the
     // matchmaker, if any
-    let p2p = p2p::P2P::new(&config, base_dir, peer_intent_send)
-        .await
-        .map_err(Error::P2pInit)?;
+    let p2p = p2p::P2P::new(
+        &config,
+        &identity,
+        peer_intent_send,
+        metrics.clone(),
+        seen,
+    )
+    .await
+    .map_err(Error::P2pInit)?;
 
     // Run the matchmakers server
     let mms_join_handle = tokio::task::spawn(async move {
         matchmakers_server.listen().await;
     });
 
-    // Start the RPC server, if enabled in the config
-    let rpc_receiver = config.rpc.map(|rpc_config| {
-        let (rpc_sender, rpc_receiver) = mpsc::channel(100);
+    // Serve the metrics, if enabled in the config. A failure of the metrics
+    // server doesn't stop the node.
+    if let Some(metrics_addr) = config.metrics_addr {
         tokio::spawn(async move {
-            rpc::client::start_rpc_server(&rpc_config, rpc_sender).await
+            if let Err(err) = metrics::serve(metrics_addr, metrics).await {
+                tracing::error!("{}", err);
+            }
         });
-        rpc_receiver
-    });
+    }
+
+    // Start the RPC server, if enabled in the config
+    let rpc_receiver = match config.rpc {
+        Some(rpc_config) => {
+            let policy = rpc::client::RpcPolicy::new(&rpc_config)
+                .map_err(Error::RpcInit)?;
+            let (rpc_sender, rpc_receiver) = mpsc::channel(100);
+            tokio::spawn(async move {
+                rpc::client::start_rpc_server(
+                    &rpc_config,
+                    policy,
+                    subscriptions,
+                    rpc_sender,
+                )
+                .await
+            });
+            Some(rpc_receiver)
+        }
+        None => None,
+    };
 
     dispatcher(
         p2p,
+        identity,
         rpc_receiver,
         peer_intent_recv,
         intent_gossiper,
@@ -78,8 +155,9 @@ pub async fn run(
 // logic.
 pub async fn dispatcher(
     mut p2p: P2P,
+    identity: Identity,
     mut rpc_receiver: Option<RpcReceiver>,
-    mut peer_intent_recv: tokio::sync::mpsc::Receiver<Intent>,
+    mut peer_intent_recv: tokio::sync::mpsc::Receiver<PeerMessage>,
     mut intent_gossiper: IntentGossiper,
     _mms_join_handle: tokio::task::JoinHandle<()>,
 ) -> Result<()> {
@@ -87,17 +165,31 @@ pub async fn dispatcher(
         tokio::select! {
             Some((event, inject_response)) = recv_rpc_option(rpc_receiver.as_mut()), if rpc_receiver.is_some() =>
             {
-                let gossip_sub = &mut p2p.0.behaviour_mut().intent_gossip_behaviour;
-                let (response, maybe_intent) = rpc::client::handle_rpc_event(event, gossip_sub).await;
-                inject_response.send(response).expect("failed to send response to rpc server");
+                if let rpc_message::Message::DryRun(message) = event {
+                    // The response is sent once the matchmakers respond
+                    rpc::client::handle_intent_dry_run(message, &mut intent_gossiper, inject_response);
+                } else {
+                    let behaviour = p2p.0.behaviour_mut();
+                    let gossip_sub = &mut behaviour.intent_gossip_behaviour;
+                    let traces = behaviour.traces.as_mut();
+                    let (response, maybe_intent) = rpc::client::handle_rpc_event(event, gossip_sub, traces, &identity, &intent_gossiper).await;
+                    inject_response.send(response).expect("failed to send response to rpc server");
 
-                if let Some(intent) = maybe_intent {
-                    intent_gossiper.add_intent(intent).await;
+                    if let Some(message) = maybe_intent {
+                        intent_gossiper.add_intent(message.intent, message.topic).await;
+                    }
+                }
+            },
+            Some(message) = peer_intent_recv.recv() => match message {
+                PeerMessage::Intent(intent, topic) => {
+                    intent_gossiper.add_intent(intent, topic).await;
+                }
+                PeerMessage::Cancel(cancel) => {
+                    if let Err(err) = intent_gossiper.cancel_intent(&cancel) {
+                        tracing::info!("Intent not cancelled: {}", err);
+                    }
                 }
             },
-            Some(intent) = peer_intent_recv.recv() => {
-                intent_gossiper.add_intent(intent).await;
-            }
             swarm_event = p2p.0.next() => {
                 // Never occurs, but call for the event must exists.
                 tracing::info!("event, {:?}", swarm_event);
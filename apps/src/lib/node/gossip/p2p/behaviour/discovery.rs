@@ -20,8 +20,8 @@ use libp2p::kad::{Kademlia, KademliaConfig, KademliaEvent, QueryId};
 use libp2p::mdns::{Mdns, MdnsConfig, MdnsEvent};
 use libp2p::swarm::toggle::{Toggle, ToggleIntoProtoHandler};
 use libp2p::swarm::{
-    IntoProtocolsHandler, NetworkBehaviour, NetworkBehaviourAction,
-    PollParameters, ProtocolsHandler,
+    DialPeerCondition, IntoProtocolsHandler, NetworkBehaviour,
+    NetworkBehaviourAction, PollParameters, ProtocolsHandler,
 };
 use libp2p::{Multiaddr, PeerId};
 use thiserror::Error;
@@ -64,9 +64,8 @@ pub struct DiscoveryConfig {
     discovery_max: u64,
     /// enable kademlia to find new peer
     enable_kademlia: bool,
-    /// look for new peer over local network.
-    // TODO: it seems that kademlia must activated where it should not be
-    // mandatory
+    /// look for new peer over local network and connect to them, with or
+    /// without kademlia
     enable_mdns: bool,
     // TODO: should this be optional? if not explain why
     /// use the option from kademlia. Prevent some type of attacks against
@@ -152,6 +151,8 @@ pub struct DiscoveryBehaviour {
     duration_to_next_kad: Duration,
     /// Events to return in priority when polled.
     pending_events: VecDeque<DiscoveryEvent>,
+    /// Peers discovered over the local network that we're going to dial.
+    pending_dials: VecDeque<PeerId>,
     /// Number of nodes we're currently connected to.
     num_connections: u64,
     /// Keeps hash set of peers connected.
@@ -244,13 +245,21 @@ impl DiscoveryBehaviour {
             None
         };
 
+        // Start the random Kademlia queries, so that we find the peers
+        // known by the bootstrap peers
+        let duration_to_next_kad = Duration::from_secs(1);
+        let next_kad_random_query = kademlia_opt
+            .is_some()
+            .then(|| stream::interval(duration_to_next_kad));
+
         Ok(DiscoveryBehaviour {
             user_defined,
             kademlia: kademlia_opt.into(),
             mdns: mdns_opt.into(),
-            next_kad_random_query: None,
-            duration_to_next_kad: Duration::from_secs(1),
+            next_kad_random_query,
+            duration_to_next_kad,
             pending_events: VecDeque::new(),
+            pending_dials: VecDeque::new(),
             num_connections: 0,
             peers,
             discovery_max,
@@ -419,6 +428,20 @@ impl NetworkBehaviour for DiscoveryBehaviour {
             return Poll::Ready(NetworkBehaviourAction::GenerateEvent(ev));
         }
 
+        // Dial the peers discovered over the local network. Their addresses
+        // are given by mdns in `addresses_of_peer`.
+        if let Some(peer_id) = self.pending_dials.pop_front() {
+            if !self.peers.contains(&peer_id)
+                && self.num_connections < self.discovery_max
+            {
+                tracing::debug!("Dialing peer {} discovered by mdns", peer_id);
+                return Poll::Ready(NetworkBehaviourAction::DialPeer {
+                    peer_id,
+                    condition: DialPeerCondition::Disconnected,
+                });
+            }
+        }
+
         // Poll Kademlia return every other event except kad event
         while let Poll::Ready(ev) = self.kademlia.poll(cx, params) {
             tracing::debug!("Kademlia event {:#?}", ev);
@@ -466,11 +489,20 @@ impl NetworkBehaviour for DiscoveryBehaviour {
                 NetworkBehaviourAction::GenerateEvent(event) => match event {
                     MdnsEvent::Discovered(list) => {
                         if self.num_connections < self.discovery_max {
-                            // Add any discovered peers to Kademlia
+                            // Add any discovered peers to Kademlia and dial
+                            // the ones we're not connected to yet
                             for (peer_id, multiaddr) in list {
                                 if let Some(kad) = self.kademlia.as_mut() {
                                     kad.add_address(&peer_id, multiaddr);
                                 }
+                                if !self.peers.contains(&peer_id)
+                                    && !self.pending_dials.contains(&peer_id)
+                                {
+                                    self.pending_dials.push_back(peer_id);
+                                }
+                            }
+                            if !self.pending_dials.is_empty() {
+                                cx.waker().wake_by_ref();
                             }
                         } else {
                             tracing::info!(
@@ -2,9 +2,11 @@ mod discovery;
 use std::collections::hash_map::DefaultHasher;
 use std::convert::TryFrom;
 use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 use std::time::Duration;
 
-use anoma::proto::{self, Intent, IntentGossipMessage};
+use anoma::proto::{self, Intent, IntentCancel, IntentGossipMessage};
+use anoma::types::time::DateTimeUtc;
 use libp2p::gossipsub::subscription_filter::regex::RegexSubscriptionFilter;
 use libp2p::gossipsub::subscription_filter::{
     TopicSubscriptionFilter, WhitelistSubscriptionFilter,
@@ -24,9 +26,14 @@ use tokio::sync::mpsc::Sender;
 
 use self::discovery::DiscoveryEvent;
 use crate::config;
+use crate::node::gossip::admission;
+use crate::node::gossip::intent_gossiper::PeerMessage;
+use crate::node::gossip::metrics::Metrics;
 use crate::node::gossip::p2p::behaviour::discovery::{
     DiscoveryBehaviour, DiscoveryConfigBuilder,
 };
+use crate::node::gossip::replay::SeenIntents;
+use crate::node::gossip::trace::{self, IntentTraces};
 
 /// Behaviour is composed of a `DiscoveryBehaviour` and an GossipsubBehaviour`.
 /// It automatically connect to newly discovered peer, except specified
@@ -41,7 +48,17 @@ pub struct Behaviour {
     /// every established connection
     ping: Ping,
     #[behaviour(ignore)]
-    pub peer_intent_send: Sender<Intent>,
+    pub peer_intent_send: Sender<PeerMessage>,
+    /// The hops of intents recorded in the trace mode, if enabled
+    #[behaviour(ignore)]
+    pub traces: Option<IntentTraces>,
+    /// The node's metrics, in which the connected peers are counted
+    #[behaviour(ignore)]
+    metrics: Arc<Metrics>,
+    /// The intents seen by the node, shared with the intent gossiper, so
+    /// that the replayed intents are not propagated
+    #[behaviour(ignore)]
+    seen: Arc<SeenIntents>,
 }
 
 #[derive(Error, Debug)]
@@ -150,7 +167,9 @@ impl Behaviour {
     pub async fn new(
         key: Keypair,
         config: &config::IntentGossiper,
-        peer_intent_send: Sender<Intent>,
+        peer_intent_send: Sender<PeerMessage>,
+        metrics: Arc<Metrics>,
+        seen: Arc<SeenIntents>,
     ) -> Self {
         let public_key = key.public();
         let peer_id = PeerId::from_public_key(public_key.clone());
@@ -220,19 +239,16 @@ impl Behaviour {
         let discover_behaviour = {
             // TODO: check that bootstrap_peers are in multiaddr (otherwise it
             // fails silently)
-            let discover_config =
-                if let Some(discover_config) = &config.discover_peer {
-                    DiscoveryConfigBuilder::default()
-                        .with_user_defined(config.seed_peers.clone())
-                        .discovery_limit(discover_config.max_discovery_peers)
-                        .with_kademlia(discover_config.kademlia)
-                        .with_mdns(discover_config.mdns)
-                        .use_kademlia_disjoint_query_paths(true)
-                        .build()
-                        .unwrap()
-                } else {
-                    DiscoveryConfigBuilder::default().build().unwrap()
-                };
+            let mut discover_config = DiscoveryConfigBuilder::default();
+            discover_config.with_user_defined(config.seed_peers.clone());
+            if let Some(discover_peer) = &config.discover_peer {
+                discover_config
+                    .discovery_limit(discover_peer.max_discovery_peers)
+                    .with_kademlia(discover_peer.kademlia)
+                    .with_mdns(discover_peer.mdns)
+                    .use_kademlia_disjoint_query_paths(true);
+            }
+            let discover_config = discover_config.build().unwrap();
             DiscoveryBehaviour::new(peer_id, discover_config)
                 .await
                 .unwrap()
@@ -246,13 +262,34 @@ impl Behaviour {
             )),
             ping: Ping::default(),
             peer_intent_send,
+            traces: config.trace.then(|| IntentTraces::new(peer_id)),
+            metrics,
+            seen,
         }
     }
 
     /// tries to apply a new intent. Fails if the logic fails or if the intent
-    /// is rejected. If the matchmaker fails the message is only ignore
-    fn handle_intent(&mut self, intent: Intent) -> MessageAcceptance {
-        if let Err(err) = self.peer_intent_send.try_send(intent) {
+    /// is rejected. If the matchmaker fails the message is only ignore. An
+    /// expired intent or an intent that has already been seen, e.g. a
+    /// replayed intent, is ignored, so that it's no longer propagated.
+    fn handle_intent(
+        &mut self,
+        intent: Intent,
+        topic: &TopicHash,
+    ) -> MessageAcceptance {
+        let now = DateTimeUtc::now();
+        if let Err(err) = admission::check_expiry(&intent, now) {
+            tracing::info!("Ignoring intent ID {}: {}", intent.id(), err);
+            return MessageAcceptance::Ignore;
+        }
+        if let Err(err) = self.seen.insert(&intent.data, now) {
+            tracing::info!("Ignoring intent ID {}: {}", intent.id(), err);
+            return MessageAcceptance::Ignore;
+        }
+        if let Err(err) = self
+            .peer_intent_send
+            .try_send(PeerMessage::Intent(intent, topic.as_str().to_owned()))
+        {
             tracing::error!("Error sending intent to the matchmaker: {}", err);
             // The buffer is full or the channel is closed
             return MessageAcceptance::Ignore;
@@ -260,14 +297,73 @@ impl Behaviour {
         MessageAcceptance::Accept
     }
 
+    /// Send a cancellation of an intent to the intent gossiper, which drops
+    /// the intent from its mempool. A cancellation with an invalid signature
+    /// is rejected, so that it's not propagated.
+    fn handle_intent_cancel(
+        &mut self,
+        cancel: IntentCancel,
+    ) -> MessageAcceptance {
+        if let Err(err) = cancel.verify() {
+            tracing::info!("Invalid intent cancellation signature: {}", err);
+            return MessageAcceptance::Reject;
+        }
+        if let Err(err) =
+            self.peer_intent_send.try_send(PeerMessage::Cancel(cancel))
+        {
+            tracing::error!("Error sending intent cancellation: {}", err);
+            // The buffer is full or the channel is closed
+            return MessageAcceptance::Ignore;
+        }
+        MessageAcceptance::Accept
+    }
+
     /// Tries to decoded the arbitrary data in an intent then call
-    /// [Self::handle_intent]. fails if the data does not contains an intent
+    /// [Self::handle_intent]. fails if the data does not contains an intent.
+    /// The hops of intents announced by the nodes in the trace mode are
+    /// recorded and propagated in the trace mode and otherwise ignored. A hop
+    /// must be announced by the node that it describes, i.e. the signed
+    /// source of the message.
     fn handle_raw_intent(
         &mut self,
         data: impl AsRef<[u8]>,
+        propagation_source: &PeerId,
+        source: Option<&PeerId>,
+        topic: &TopicHash,
     ) -> MessageAcceptance {
         match IntentGossipMessage::try_from(data.as_ref()) {
-            Ok(message) => self.handle_intent(message.intent),
+            Ok(IntentGossipMessage::Intent(intent)) => {
+                if let Some(traces) = self.traces.as_mut() {
+                    let hop = traces.receive(&intent, Some(propagation_source));
+                    trace::announce_hop(
+                        &mut self.intent_gossip_behaviour,
+                        hop,
+                        IdentTopic::new(topic.as_str()),
+                    );
+                }
+                self.handle_intent(intent, topic)
+            }
+            Ok(IntentGossipMessage::Hop(hop)) => {
+                let source = source.map(PeerId::to_string);
+                if source.as_ref() != Some(&hop.node_id) {
+                    tracing::info!(
+                        "Rejecting a hop of node {} announced by {:?}",
+                        hop.node_id,
+                        source
+                    );
+                    return MessageAcceptance::Reject;
+                }
+                match self.traces.as_mut() {
+                    Some(traces) => {
+                        traces.record(hop);
+                        MessageAcceptance::Accept
+                    }
+                    None => MessageAcceptance::Ignore,
+                }
+            }
+            Ok(IntentGossipMessage::Cancel(cancel)) => {
+                self.handle_intent_cancel(cancel)
+            }
             Err(proto::Error::NoIntentError) => {
                 tracing::info!("Empty message, rejecting it");
                 MessageAcceptance::Reject
@@ -276,7 +372,10 @@ impl Behaviour {
                 tracing::info!("error while decoding the intent: {:?}", err);
                 MessageAcceptance::Reject
             }
-            _ => unreachable!(),
+            Err(err) => {
+                tracing::info!("invalid intent gossip message: {}", err);
+                MessageAcceptance::Reject
+            }
         }
     }
 }
@@ -293,7 +392,12 @@ impl NetworkBehaviourEventProcess<GossipsubEvent> for Behaviour {
             } => {
                 // validity is the type of response return to the network
                 // (valid|reject|ignore)
-                let validity = self.handle_raw_intent(message.data);
+                let validity = self.handle_raw_intent(
+                    message.data,
+                    &propagation_source,
+                    message.source.as_ref(),
+                    &message.topic,
+                );
                 self.intent_gossip_behaviour
                     .report_message_validation_result(
                         &message_id,
@@ -331,10 +435,12 @@ impl NetworkBehaviourEventProcess<DiscoveryEvent> for Behaviour {
     fn inject_event(&mut self, event: DiscoveryEvent) {
         match event {
             DiscoveryEvent::Connected(peer) => {
-                tracing::info!("Connect to a new peer: {:?}", peer)
+                tracing::info!("Connect to a new peer: {:?}", peer);
+                self.metrics.peer_connected();
             }
             DiscoveryEvent::Disconnected(peer) => {
-                tracing::info!("Peer disconnected: {:?}", peer)
+                tracing::info!("Peer disconnected: {:?}", peer);
+                self.metrics.peer_disconnected();
             }
             _ => {}
         }
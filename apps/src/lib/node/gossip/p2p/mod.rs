@@ -1,10 +1,9 @@
 pub mod behaviour;
 mod identity;
 
-use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
-use anoma::proto::Intent;
 use behaviour::Behaviour;
 use libp2p::core::connection::ConnectionLimits;
 use libp2p::core::muxing::StreamMuxerBox;
@@ -20,6 +19,9 @@ use tokio::sync::mpsc::Sender;
 
 pub use self::identity::Identity;
 use crate::config;
+use crate::node::gossip::intent_gossiper::PeerMessage;
+use crate::node::gossip::metrics::Metrics;
+use crate::node::gossip::replay::SeenIntents;
 
 pub type Swarm = libp2p::Swarm<Behaviour>;
 
@@ -46,10 +48,11 @@ impl P2P {
     /// propagation of intents.
     pub async fn new(
         config: &config::IntentGossiper,
-        base_dir: impl AsRef<Path>,
-        peer_intent_send: Sender<Intent>,
+        identity: &Identity,
+        peer_intent_send: Sender<PeerMessage>,
+        metrics: Arc<Metrics>,
+        seen: Arc<SeenIntents>,
     ) -> Result<Self> {
-        let identity = Identity::load_or_gen(base_dir);
         let peer_key = identity.key();
         // Id of the node on the libp2p network derived from the public key
         let peer_id = identity.peer_id();
@@ -60,7 +63,8 @@ impl P2P {
 
         // create intent gossip specific behaviour
         let intent_gossip_behaviour =
-            Behaviour::new(peer_key, config, peer_intent_send).await;
+            Behaviour::new(peer_key, config, peer_intent_send, metrics, seen)
+                .await;
 
         let connection_limits = build_p2p_connections_limit();
 
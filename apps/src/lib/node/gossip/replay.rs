@@ -0,0 +1,283 @@
+//! The protection of the intent gossip nodes and the matchmakers against
+//! replayed intents. An intent is identified by the SHA-256 hash of its
+//! signed data, which doesn't include its timestamp, so that an intent that
+//! has been captured cannot be gossiped nor matched again with another
+//! timestamp. The signer of intents with the same exchanges tells them apart
+//! with their nonce, see [`FungibleTokenIntent::nonce`].
+//!
+//! The seen intents are remembered until they expire, after which they're
+//! rejected anyway, and the intents that never expire are never forgotten.
+//! The intent gossip node persists the seen intents in RocksDB next to its
+//! intent mempool, so that they're still rejected after it restarts.
+//!
+//! [`FungibleTokenIntent::nonce`]: anoma::types::intent::FungibleTokenIntent::nonce
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anoma::types::time::DateTimeUtc;
+use anoma::types::transaction::hash_tx;
+use borsh::{BorshDeserialize, BorshSerialize};
+use rocksdb::{IteratorMode, Options};
+use thiserror::Error;
+
+use super::admission;
+
+/// The directory of the seen intents store in the node's base directory
+const STORE_DIR: &str = "seen_intents";
+
+/// The number of seen intents above which the expired ones are forgotten
+const PRUNE_THRESHOLD: usize = 100_000;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The intent has already been seen")]
+    AlreadySeen,
+    #[error("Failed to open the seen intents store: {0}")]
+    OpenStore(rocksdb::Error),
+}
+
+/// The intents that have been seen, whether they are still pending, matched
+/// or cancelled, optionally persisted in a store
+#[derive(Debug, Default)]
+pub struct SeenIntents {
+    seen: Mutex<Seen>,
+    /// The persistent store of the seen intents' expiry, if any, keyed by the
+    /// hashes of their data
+    store: Option<rocksdb::DB>,
+}
+
+#[derive(Debug, Default)]
+struct Seen {
+    /// The expiry of the seen intents, if any, keyed by the hashes of their
+    /// data
+    expiry: HashMap<[u8; 32], Option<DateTimeUtc>>,
+    /// The number of seen intents above which the expired ones are
+    /// forgotten. It's raised when there are still as many unexpired
+    /// intents, so that they're not pruned again on every insert.
+    prune_threshold: usize,
+}
+
+impl SeenIntents {
+    /// Open the seen intents with the store in the given base directory and
+    /// reload the stored ones. The intents that are expired at the given time
+    /// are pruned from the store.
+    pub fn open(
+        base_dir: impl AsRef<Path>,
+        time: DateTimeUtc,
+    ) -> Result<Self, Error> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let store = rocksdb::DB::open(&opts, base_dir.as_ref().join(STORE_DIR))
+            .map_err(Error::OpenStore)?;
+
+        let mut seen = Seen::default();
+        let mut pruned = vec![];
+        for (key, bytes) in store.iterator(IteratorMode::Start) {
+            let hash = <[u8; 32]>::try_from(&key[..]).ok();
+            let expires = Option::<DateTimeUtc>::try_from_slice(&bytes);
+            match (hash, expires) {
+                (Some(hash), Ok(expires))
+                    if expires.map_or(true, |e| time <= e) =>
+                {
+                    seen.expiry.insert(hash, expires);
+                }
+                (Some(_), Ok(_)) => pruned.push(key),
+                _ => {
+                    tracing::error!("Failed to decode a stored seen intent");
+                    pruned.push(key);
+                }
+            }
+        }
+        for key in pruned {
+            if let Err(err) = store.delete(key) {
+                tracing::error!(
+                    "Failed to prune a stored seen intent: {}",
+                    err
+                );
+            }
+        }
+        Ok(Self {
+            seen: Mutex::new(seen),
+            store: Some(store),
+        })
+    }
+
+    /// Record that an intent with the given data has been seen at the given
+    /// time. Returns an error if it has already been seen.
+    pub fn insert(&self, data: &[u8], now: DateTimeUtc) -> Result<(), Error> {
+        let hash = hash_tx(data).0;
+        let mut seen = self.seen.lock().unwrap();
+        if seen.expiry.contains_key(&hash) {
+            return Err(Error::AlreadySeen);
+        }
+        if seen.expiry.len() >= seen.prune_threshold.max(PRUNE_THRESHOLD) {
+            for hash in seen.prune(now) {
+                self.delete_stored(&hash);
+            }
+        }
+        let expires = admission::data_expiry(data);
+        if let Some(store) = &self.store {
+            let bytes = expires
+                .try_to_vec()
+                .expect("Encoding a seen intent's expiry shouldn't fail");
+            if let Err(err) = store.put(hash, bytes) {
+                tracing::error!("Failed to store a seen intent: {}", err);
+            }
+        }
+        seen.expiry.insert(hash, expires);
+        Ok(())
+    }
+
+    /// Delete a seen intent from the store, if any
+    fn delete_stored(&self, hash: &[u8; 32]) {
+        if let Some(store) = &self.store {
+            if let Err(err) = store.delete(hash) {
+                tracing::error!(
+                    "Failed to delete a stored seen intent: {}",
+                    err
+                );
+            }
+        }
+    }
+}
+
+impl Seen {
+    /// Forget the intents that are expired at the given time. The unexpired
+    /// intents are never forgotten, so the next prune is deferred until
+    /// their number doubles. Returns the hashes of the forgotten intents.
+    fn prune(&mut self, now: DateTimeUtc) -> Vec<[u8; 32]> {
+        let expired: Vec<[u8; 32]> = self
+            .expiry
+            .iter()
+            .filter(|(_hash, expires)| expires.map_or(false, |e| now > e))
+            .map(|(hash, _expires)| *hash)
+            .collect();
+        for hash in &expired {
+            self.expiry.remove(hash);
+        }
+        self.prune_threshold = 2 * self.expiry.len();
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anoma::proto::Signed;
+    use anoma::types::address::{btc, xan};
+    use anoma::types::intent::{DecimalWrapper, Exchange, FungibleTokenIntent};
+    use anoma::types::key::testing::keypair_1;
+    use anoma::types::token;
+    use borsh::BorshSerialize;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn time(raw: &str) -> DateTimeUtc {
+        DateTimeUtc::from_str(raw).unwrap()
+    }
+
+    /// The data of a fungible token intent with the given nonce that expires
+    /// at the given time
+    fn intent(nonce: u64, expires: &str) -> Vec<u8> {
+        let keypair = keypair_1();
+        let exchange = Exchange {
+            addr: xan(),
+            token_sell: xan(),
+            rate_min: DecimalWrapper::from_str("1").unwrap(),
+            max_sell: token::Amount::whole(10),
+            token_buy: btc(),
+            min_buy: token::Amount::whole(1),
+            vp: None,
+            nft: None,
+            expires: Some(time(expires)),
+            partial: false,
+            max_fee: token::Amount::default(),
+        };
+        let intent = FungibleTokenIntent {
+            exchange: [Signed::new(&keypair, exchange)].into_iter().collect(),
+            nonce,
+        };
+        Signed::new(&keypair, intent).try_to_vec().unwrap()
+    }
+
+    /// Test that an intent is seen only once, unless it's signed with another
+    /// nonce, and that the expired intents are forgotten
+    #[test]
+    fn test_seen_intents() {
+        let expires = "2022-01-01T00:00:00Z";
+        let now = time("2021-12-31T00:00:00Z");
+        let seen = SeenIntents::default();
+        assert!(seen.insert(&intent(1, expires), now).is_ok());
+        assert!(matches!(
+            seen.insert(&intent(1, expires), now),
+            Err(Error::AlreadySeen)
+        ));
+        assert!(seen.insert(&intent(2, expires), now).is_ok());
+
+        let mut inner = seen.seen.lock().unwrap();
+        assert!(inner.prune(time(expires)).is_empty());
+        assert_eq!(inner.expiry.len(), 2);
+        assert_eq!(inner.prune(time("2022-01-01T00:00:01Z")).len(), 2);
+        assert!(inner.expiry.is_empty());
+    }
+
+    /// Test that when there are too many seen intents, only the expired ones
+    /// are forgotten, while the unexpired and the non-expiring ones are still
+    /// rejected
+    #[test]
+    fn test_seen_intents_eviction() {
+        let now = time("2022-01-01T12:00:00Z");
+        let seen = SeenIntents::default();
+        let expired = intent(0, "2022-01-01T00:00:00Z");
+        let unexpired = intent(0, "2022-01-02T00:00:00Z");
+        let other = b"other".to_vec();
+        for data in [&expired, &unexpired, &other] {
+            assert!(seen.insert(data, time("2021-12-31T00:00:00Z")).is_ok());
+        }
+        // Fill up the seen intents up to the pruning threshold with other
+        // intents, which never expire
+        for ix in 1..(PRUNE_THRESHOLD as u64 - 2) {
+            assert!(seen.insert(&ix.to_le_bytes(), now).is_ok());
+        }
+
+        // The next insert prunes only the expired intent
+        assert!(seen.insert(&intent(0, "2022-01-03T00:00:00Z"), now).is_ok());
+        for data in [&unexpired, &other] {
+            assert!(matches!(seen.insert(data, now), Err(Error::AlreadySeen)));
+        }
+        let inner = seen.seen.lock().unwrap();
+        assert_eq!(inner.expiry.len(), PRUNE_THRESHOLD);
+        assert_eq!(inner.prune_threshold, 2 * (PRUNE_THRESHOLD - 1));
+        drop(inner);
+        assert!(seen.insert(&expired, now).is_ok());
+    }
+
+    /// Test that the stored seen intents are reloaded, except for the expired
+    /// ones
+    #[test]
+    fn test_reload_seen_intents() {
+        let base_dir = TempDir::new().unwrap();
+        let kept = intent(0, "2022-01-02T00:00:00Z");
+        let expired = intent(0, "2022-01-01T00:00:00Z");
+        let other = b"other".to_vec();
+
+        let now = time("2021-12-31T00:00:00Z");
+        let seen = SeenIntents::open(&base_dir, now).unwrap();
+        for data in [&kept, &expired, &other] {
+            assert!(seen.insert(data, now).is_ok());
+        }
+        drop(seen);
+
+        let now = time("2022-01-01T12:00:00Z");
+        let seen = SeenIntents::open(&base_dir, now).unwrap();
+        for data in [&kept, &other] {
+            assert!(matches!(seen.insert(data, now), Err(Error::AlreadySeen)));
+        }
+        assert!(seen.insert(&expired, now).is_ok());
+    }
+}
@@ -0,0 +1,126 @@
+//! The authentication of the clients of the RPC server of an intent gossip
+//! node with bearer tokens. If the RPC server is configured with
+//! `auth_tokens`, every request must have the [`AUTHORIZATION`] metadata
+//! `Bearer <token>` with one of the tokens, or it's rejected with the
+//! `UNAUTHENTICATED` status. The client sends the token of the
+//! [`ENV_VAR_RPC_AUTH_TOKEN`] environment variable, if it's set.
+//!
+//! The tokens are sent in plaintext unless the RPC server is configured with
+//! TLS, see [`super::tls`], so a node that exposes its RPC server publicly
+//! should use TLS or do so behind a proxy that terminates TLS.
+
+use std::collections::HashSet;
+use std::env;
+
+use tonic::{Request, Status};
+
+/// The environment variable of the bearer token that the client sends to the
+/// RPC server
+pub const ENV_VAR_RPC_AUTH_TOKEN: &str = "ANOMA_RPC_AUTH_TOKEN";
+
+/// The metadata key of the bearer token
+pub const AUTHORIZATION: &str = "authorization";
+
+/// The prefix of the bearer token in the [`AUTHORIZATION`] metadata
+const BEARER: &str = "Bearer ";
+
+/// The authenticator of the RPC clients by their bearer tokens
+#[derive(Debug)]
+pub struct Authenticator {
+    tokens: Vec<String>,
+}
+
+impl Authenticator {
+    /// An authenticator that accepts any of the given tokens
+    pub fn new(tokens: &HashSet<String>) -> Self {
+        Self {
+            tokens: tokens.iter().cloned().collect(),
+        }
+    }
+
+    /// Check that a request has the bearer token of an authorized client
+    pub fn check<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let token = request
+            .metadata()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix(BEARER));
+        match token {
+            Some(token) if self.is_authorized(token) => Ok(()),
+            Some(_) => Err(Status::unauthenticated("Invalid bearer token")),
+            None => Err(Status::unauthenticated("Missing bearer token")),
+        }
+    }
+
+    /// Compare the token with all the authorized ones in constant time, so
+    /// that they cannot be guessed from the response times
+    fn is_authorized(&self, token: &str) -> bool {
+        self.tokens.iter().fold(false, |is_authorized, authorized| {
+            constant_time_eq(authorized.as_bytes(), token.as_bytes())
+                | is_authorized
+        })
+    }
+}
+
+/// A request to the RPC server with the bearer token of the
+/// [`ENV_VAR_RPC_AUTH_TOKEN`] environment variable, if it's set
+pub fn request<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    if let Ok(token) = env::var(ENV_VAR_RPC_AUTH_TOKEN) {
+        match format!("{}{}", BEARER, token).parse() {
+            Ok(value) => {
+                request.metadata_mut().insert(AUTHORIZATION, value);
+            }
+            Err(_) => eprintln!(
+                "The {} environment variable is not a valid token, it's \
+                 ignored",
+                ENV_VAR_RPC_AUTH_TOKEN
+            ),
+        }
+    }
+    request
+}
+
+/// Compare byte strings of the same length in constant time
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+
+    use super::*;
+
+    /// Test that only the requests with an authorized bearer token are
+    /// accepted
+    #[test]
+    fn test_authenticator() {
+        let tokens = ["secret".to_owned(), "other".to_owned()]
+            .into_iter()
+            .collect();
+        let authenticator = Authenticator::new(&tokens);
+        let request = |authorization: Option<&str>| {
+            let mut request = Request::new(());
+            if let Some(authorization) = authorization {
+                request
+                    .metadata_mut()
+                    .insert(AUTHORIZATION, authorization.parse().unwrap());
+            }
+            request
+        };
+
+        assert!(authenticator.check(&request(Some("Bearer secret"))).is_ok());
+        assert!(authenticator.check(&request(Some("Bearer other"))).is_ok());
+        for authorization in [
+            Some("Bearer secre"),
+            Some("Bearer secrets"),
+            Some("secret"),
+            None,
+        ] {
+            let status = authenticator.check(&request(authorization));
+            assert_eq!(status.unwrap_err().code(), Code::Unauthenticated);
+        }
+    }
+}
@@ -1,34 +1,212 @@
+use std::collections::HashSet;
 use std::convert::TryFrom;
-use std::net::SocketAddr;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use anoma::proto::{Intent, IntentGossipMessage};
+use anoma::proto::generated::types;
+use anoma::proto::{IntentGossipMessage, Signed};
+use anoma::types::intent::FungibleTokenIntent;
+use anoma::types::key::{common, ParsePublicKeyError};
+use borsh::BorshDeserialize;
+use futures::Stream;
 use libp2p::gossipsub::IdentTopic;
 use tokio::sync::mpsc::{self, Sender};
 use tokio::sync::oneshot;
-use tonic::transport::Server;
+use tonic::transport::server::TlsConnectInfo;
+use tonic::transport::{Server, ServerTlsConfig};
 use tonic::{Request as TonicRequest, Response as TonicResponse, Status};
 
 use crate::config::RpcServer;
+use crate::node::gossip::intent_gossiper::IntentGossiper;
 use crate::node::gossip::p2p::behaviour::Gossipsub;
+use crate::node::gossip::p2p::Identity;
+use crate::node::gossip::rpc::auth::Authenticator;
+use crate::node::gossip::rpc::rate_limit::{self, RateLimiter};
+use crate::node::gossip::rpc::tls;
+use crate::node::gossip::subscriptions::MatchSubscriptions;
+use crate::node::gossip::trace::{self, IntentTraces};
+use crate::node::listener::{Listener, StreamInfo};
 use crate::proto::services::rpc_service_server::{
     RpcService, RpcServiceServer,
 };
-use crate::proto::services::{rpc_message, RpcMessage, RpcResponse};
-use crate::proto::{IntentMessage, SubscribeTopicMessage};
+use crate::proto::services::{
+    self, rpc_message, IntentMatch, IntentMatchNotification,
+    IntentMatchesSubscription, RpcMessage, RpcResponse,
+};
+use crate::proto::{
+    IntentCancelMessage, IntentDryRunMessage, IntentMessage, IntentReceipt,
+    IntentsQueryMessage, OrderbookDepthQueryMessage, SubscribeTopicMessage,
+};
+
+/// The maximum time to wait for the results of an intent dry-run from the
+/// connected matchmakers
+const INTENT_DRY_RUN_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Debug)]
 struct Rpc {
     inject_message:
         mpsc::Sender<(rpc_message::Message, oneshot::Sender<RpcResponse>)>,
+    policy: RpcPolicy,
+    authenticator: Option<Authenticator>,
+    rate_limiter: Option<RateLimiter>,
+    subscriptions: Arc<MatchSubscriptions>,
+}
+
+/// The stream of the notifications of the matches of an intent
+type IntentMatchesStream =
+    Pin<Box<dyn Stream<Item = Result<IntentMatchNotification, Status>> + Send>>;
+
+/// The topics and the publishers of the messages accepted by the RPC server,
+/// see [`RpcServer`]. The messages that are not accepted are rejected with
+/// the `PERMISSION_DENIED` status before they reach the intent gossiper.
+#[derive(Debug)]
+pub struct RpcPolicy {
+    /// The accepted topics, all the topics if empty
+    topics: HashSet<String>,
+    /// The keys of the allowed publishers, anyone if empty
+    publishers: HashSet<common::PublicKey>,
+}
+
+impl RpcPolicy {
+    /// The policy of the RPC server's config
+    pub fn new(config: &RpcServer) -> Result<Self, ParsePublicKeyError> {
+        let publishers = config
+            .publishers
+            .iter()
+            .map(|key| common::PublicKey::from_str(key))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            topics: config.topics.clone(),
+            publishers,
+        })
+    }
+
+    /// Check that a message is accepted
+    fn check(&self, message: &rpc_message::Message) -> Result<(), Status> {
+        match message {
+            rpc_message::Message::Intent(message) => {
+                self.check_topic(&message.topic)?;
+                self.check_publisher(message.intent.as_ref())
+            }
+            rpc_message::Message::DryRun(message) => {
+                self.check_publisher(message.intent.as_ref())
+            }
+            rpc_message::Message::Cancel(message) => {
+                self.check_topic(&message.topic)
+            }
+            rpc_message::Message::Topic(message) => {
+                self.check_topic(&message.topic)
+            }
+            rpc_message::Message::Intents(message) => {
+                self.check_topic(&message.topic)
+            }
+            rpc_message::Message::Depth(message) => {
+                self.check_topic(&message.topic)
+            }
+            rpc_message::Message::Trace(_) | rpc_message::Message::Dkg(_) => {
+                Ok(())
+            }
+        }
+    }
+
+    fn check_topic(&self, topic: &str) -> Result<(), Status> {
+        if self.topics.is_empty() || self.topics.contains(topic) {
+            Ok(())
+        } else {
+            Err(Status::permission_denied(format!(
+                "The topic {} is not accepted by the node",
+                topic
+            )))
+        }
+    }
+
+    /// Check that an intent is signed by an allowed publisher, if any. Only
+    /// fungible token intents have a single signer.
+    fn check_publisher(
+        &self,
+        intent: Option<&types::Intent>,
+    ) -> Result<(), Status> {
+        if self.publishers.is_empty() {
+            return Ok(());
+        }
+        let is_allowed = intent
+            .and_then(|intent| {
+                Signed::<FungibleTokenIntent>::try_from_slice(&intent.data).ok()
+            })
+            .map(|intent| {
+                self.publishers.iter().any(|pk| intent.verify(pk).is_ok())
+            })
+            .unwrap_or_default();
+        if is_allowed {
+            Ok(())
+        } else {
+            Err(Status::permission_denied(
+                "The intent is not signed by a publisher allowed by the node",
+            ))
+        }
+    }
+}
+
+impl Rpc {
+    /// Check that the client of a request is authenticated, if the
+    /// authentication is enabled
+    fn check_auth<T>(&self, request: &TonicRequest<T>) -> Result<(), Status> {
+        if let Some(authenticator) = &self.authenticator {
+            if let Err(status) = authenticator.check(request) {
+                tracing::info!(
+                    "Rejected an unauthenticated RPC message: {}",
+                    status.message()
+                );
+                return Err(status);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the client of a request isn't rate limited, if the rate
+    /// limiting is enabled
+    fn check_rate_limit<T>(
+        &self,
+        request: &TonicRequest<T>,
+    ) -> Result<(), Status> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            let extensions = request.extensions();
+            let client = extensions
+                .get::<StreamInfo>()
+                .or_else(|| {
+                    extensions
+                        .get::<TlsConnectInfo<StreamInfo>>()
+                        .map(TlsConnectInfo::get_ref)
+                })
+                .and_then(|info| info.remote_addr)
+                .map(|addr| addr.ip());
+            if let Err(retry_after) = rate_limiter.check(client, Instant::now())
+            {
+                tracing::info!("Rate limited an RPC message from {:?}", client);
+                return Err(rate_limit::rate_limited(retry_after));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[tonic::async_trait]
 impl RpcService for Rpc {
+    type SubscribeIntentMatchesStream = IntentMatchesStream;
+
     async fn send_message(
         &self,
         request: TonicRequest<RpcMessage>,
     ) -> Result<TonicResponse<RpcResponse>, Status> {
+        self.check_rate_limit(&request)?;
+        self.check_auth(&request)?;
         if let RpcMessage { message: Some(msg) } = request.into_inner() {
+            if let Err(status) = self.policy.check(&msg) {
+                tracing::info!("Rejected an RPC message: {}", status.message());
+                return Err(status);
+            }
             let (sender, receiver) = oneshot::channel();
             self.inject_message
                 .send((msg, sender))
@@ -45,50 +223,166 @@ impl RpcService for Rpc {
             Ok(TonicResponse::new(RpcResponse::default()))
         }
     }
+
+    async fn subscribe_intent_matches(
+        &self,
+        request: TonicRequest<IntentMatchesSubscription>,
+    ) -> Result<TonicResponse<Self::SubscribeIntentMatchesStream>, Status> {
+        self.check_rate_limit(&request)?;
+        self.check_auth(&request)?;
+        let IntentMatchesSubscription { intent_hash } = request.into_inner();
+        if intent_hash.len() != 32 {
+            return Err(Status::invalid_argument(
+                "The intent hash must be a SHA-256 hash",
+            ));
+        }
+        let notifications = self
+            .subscriptions
+            .subscribe(intent_hash)
+            .map_err(|err| Status::resource_exhausted(err.to_string()))?;
+        // The stream ends once the intent is fully matched
+        let stream = futures::stream::unfold(
+            notifications,
+            |mut notifications| async move {
+                let notification = notifications.recv().await?;
+                let notification = IntentMatchNotification {
+                    intent_hash: notification.intent_hash,
+                    tx_hash: notification.tx_hash.unwrap_or_default(),
+                    partial: notification.partial,
+                };
+                Some((Ok(notification), notifications))
+            },
+        );
+        Ok(TonicResponse::new(Box::pin(stream)))
+    }
 }
 
 pub async fn rpc_server(
-    addr: SocketAddr,
+    listener: Listener,
+    proxy_protocol: bool,
+    policy: RpcPolicy,
+    authenticator: Option<Authenticator>,
+    rate_limiter: Option<RateLimiter>,
+    tls: Option<ServerTlsConfig>,
+    subscriptions: Arc<MatchSubscriptions>,
     inject_message: Sender<(
         rpc_message::Message,
         oneshot::Sender<RpcResponse>,
     )>,
 ) -> Result<(), tonic::transport::Error> {
-    let rpc = Rpc { inject_message };
+    let rpc = Rpc {
+        inject_message,
+        policy,
+        authenticator,
+        rate_limiter,
+        subscriptions,
+    };
     let svc = RpcServiceServer::new(rpc);
-    Server::builder().add_service(svc).serve(addr).await
+    let incoming = listener.incoming(proxy_protocol);
+    let mut server = Server::builder();
+    if let Some(tls) = tls {
+        server = server.tls_config(tls)?;
+    }
+    server.add_service(svc).serve_with_incoming(incoming).await
 }
 
 /// Start a rpc server in it's own thread. The used address to listen is in the
-/// `config` argument. All received event by the rpc are send to the channel
-/// return by this function.
+/// `config` argument. All received event by the rpc that are accepted by the
+/// `policy`, authenticated and not rate limited are send to the channel return
+/// by this function. The clients can subscribe to the matches of intents with
+/// the given `subscriptions`. The server uses TLS if it's configured and,
+/// behind a reverse proxy, it can read the clients' addresses from the PROXY
+/// protocol headers.
 pub async fn start_rpc_server(
     config: &RpcServer,
+    policy: RpcPolicy,
+    subscriptions: Arc<MatchSubscriptions>,
     rpc_sender: mpsc::Sender<(
         rpc_message::Message,
         tokio::sync::oneshot::Sender<RpcResponse>,
     )>,
 ) {
-    let addr = config.address;
+    let listener = Listener::bind(&config.address).await.unwrap();
     tracing::info!("RPC started at {}", config.address);
-    rpc_server(addr, rpc_sender).await.unwrap();
+    let authenticator = (!config.auth_tokens.is_empty())
+        .then(|| Authenticator::new(&config.auth_tokens));
+    let rate_limiter = config.rate_limit.clone().map(RateLimiter::new);
+    let tls = config
+        .tls
+        .as_ref()
+        .map(tls::server_config)
+        .transpose()
+        .expect("Failed to load the RPC server's TLS config");
+    rpc_server(
+        listener,
+        config.proxy_protocol,
+        policy,
+        authenticator,
+        rate_limiter,
+        tls,
+        subscriptions,
+        rpc_sender,
+    )
+    .await
+    .unwrap();
 }
 
+/// Handle a message received via RPC. A received intent is published to the
+/// gossip network and its receipt, signed with the node's identity, is
+/// returned in the response. In the trace mode, the intent's hop is announced
+/// too and the recorded hops of an intent can be requested. A cancellation of
+/// an intent in the node's mempool is gossiped once the intent is removed and
+/// the intents in the mempool can be queried by their topic. An intent
+/// dry-run must be handled by [`handle_intent_dry_run`] instead. The received
+/// intent is returned with its topic to be added to mempool.
 pub async fn handle_rpc_event(
     event: rpc_message::Message,
     gossip_sub: &mut Gossipsub,
-) -> (RpcResponse, Option<Intent>) {
+    traces: Option<&mut IntentTraces>,
+    identity: &Identity,
+    intent_gossiper: &IntentGossiper,
+) -> (RpcResponse, Option<IntentMessage>) {
     match event {
         rpc_message::Message::Intent(message) => {
             match IntentMessage::try_from(message) {
                 Ok(message) => {
+                    // Intents that wouldn't be admitted or that have already
+                    // been seen are not gossiped
+                    let admitted = match intent_gossiper
+                        .check_admission(&message.intent)
+                        .await
+                    {
+                        Ok(()) => intent_gossiper
+                            .check_replay(&message.intent)
+                            .map_err(|err| err.to_string()),
+                        Err(err) => Err(err.to_string()),
+                    };
+                    if let Err(err) = admitted {
+                        return (
+                            RpcResponse {
+                                result: format!("Intent not admitted: {}", err),
+                                receipt: None,
+                                hops: vec![],
+                                matches: vec![],
+                                intents: vec![],
+                                depth: None,
+                            },
+                            None,
+                        );
+                    }
+                    let receipt = IntentReceipt::new(
+                        &message.intent,
+                        message.topic.clone(),
+                        &identity.key,
+                    );
                     // Send the intent to gossip
                     let gossip_message =
                         IntentGossipMessage::new(message.intent.clone());
                     let intent_bytes = gossip_message.to_bytes();
+                    let topic = IdentTopic::new(message.topic.clone());
 
                     let gossip_result = match gossip_sub
-                        .publish(IdentTopic::new(message.topic), intent_bytes)
+                        .publish(topic.clone(), intent_bytes)
                     {
                         Ok(message_id) => {
                             format!(
@@ -104,24 +398,171 @@ pub async fn handle_rpc_event(
                             )
                         }
                     };
+                    if let Some(traces) = traces {
+                        let hop = traces.receive(&message.intent, None);
+                        trace::announce_hop(gossip_sub, hop, topic);
+                    }
                     (
                         RpcResponse {
                             result: format!(
                                 "Intent received. {}.",
                                 gossip_result,
                             ),
+                            receipt: Some(receipt.into()),
+                            hops: vec![],
+                            matches: vec![],
+                            intents: vec![],
+                            depth: None,
                         },
-                        Some(message.intent),
+                        Some(message),
                     )
                 }
                 Err(err) => (
                     RpcResponse {
                         result: format!("Error decoding intent: {:?}", err),
+                        receipt: None,
+                        hops: vec![],
+                        matches: vec![],
+                        intents: vec![],
+                        depth: None,
                     },
                     None,
                 ),
             }
         }
+        rpc_message::Message::Trace(message) => {
+            let response = match traces {
+                Some(traces) => {
+                    let hops = traces.get(&message.intent_hash);
+                    RpcResponse {
+                        result: format!(
+                            "Found {} hops of the intent.",
+                            hops.len()
+                        ),
+                        receipt: None,
+                        hops: hops.into_iter().map(Into::into).collect(),
+                        matches: vec![],
+                        intents: vec![],
+                        depth: None,
+                    }
+                }
+                None => RpcResponse {
+                    result: "The node doesn't run in the trace mode."
+                        .to_owned(),
+                    receipt: None,
+                    hops: vec![],
+                    matches: vec![],
+                    intents: vec![],
+                    depth: None,
+                },
+            };
+            (response, None)
+        }
+        rpc_message::Message::Cancel(message) => {
+            let result = match IntentCancelMessage::try_from(message) {
+                // The cancellation is only gossiped if the intent could be
+                // cancelled by this node
+                Ok(message) => match intent_gossiper
+                    .cancel_intent(&message.cancel)
+                {
+                    Ok(id) => {
+                        let gossip_message =
+                            IntentGossipMessage::Cancel(message.cancel);
+                        match gossip_sub.publish(
+                            IdentTopic::new(message.topic),
+                            gossip_message.to_bytes(),
+                        ) {
+                            Ok(message_id) => format!(
+                                "Intent ID {} cancelled. Cancellation \
+                                 published in intent gossiper with message \
+                                 ID: {}.",
+                                id, message_id
+                            ),
+                            Err(err) => format!(
+                                "Intent ID {} cancelled. Failed to publish \
+                                 the cancellation in gossiper: {:?}.",
+                                id, err
+                            ),
+                        }
+                    }
+                    Err(err) => format!("Intent not cancelled: {}.", err),
+                },
+                Err(err) => {
+                    format!("Error decoding intent cancellation: {:?}", err)
+                }
+            };
+            (
+                RpcResponse {
+                    result,
+                    receipt: None,
+                    hops: vec![],
+                    matches: vec![],
+                    intents: vec![],
+                    depth: None,
+                },
+                None,
+            )
+        }
+        rpc_message::Message::Intents(message) => {
+            let message = IntentsQueryMessage::from(message);
+            let intents = intent_gossiper.intents_on_topic(&message.topic);
+            (
+                RpcResponse {
+                    result: format!(
+                        "Found {} intents on the topic {}.",
+                        intents.len(),
+                        message.topic
+                    ),
+                    receipt: None,
+                    hops: vec![],
+                    matches: vec![],
+                    intents: intents.into_iter().map(Into::into).collect(),
+                    depth: None,
+                },
+                None,
+            )
+        }
+        rpc_message::Message::Depth(message) => {
+            let response = match OrderbookDepthQueryMessage::try_from(message) {
+                Ok(message) => {
+                    let depth = intent_gossiper.orderbook_depth(
+                        &message.topic,
+                        &message.base_token,
+                        &message.quote_token,
+                        usize::try_from(message.levels).unwrap_or(usize::MAX),
+                    );
+                    RpcResponse {
+                        result: format!(
+                            "Found {} bid and {} ask price levels on the \
+                             topic {}.",
+                            depth.bids.len(),
+                            depth.asks.len(),
+                            message.topic
+                        ),
+                        receipt: None,
+                        hops: vec![],
+                        matches: vec![],
+                        intents: vec![],
+                        depth: Some(depth.into()),
+                    }
+                }
+                Err(err) => RpcResponse {
+                    result: format!("Invalid token address: {}", err),
+                    receipt: None,
+                    hops: vec![],
+                    matches: vec![],
+                    intents: vec![],
+                    depth: None,
+                },
+            };
+            (response, None)
+        }
+        rpc_message::Message::DryRun(_) => {
+            unreachable!(
+                "Intent dry-runs are responded to asynchronously by \
+                 `handle_intent_dry_run`"
+            )
+        }
         rpc_message::Message::Dkg(dkg_msg) => {
             tracing::debug!("dkg not yet implemented {:?}", dkg_msg);
             (
@@ -130,6 +571,11 @@ pub async fn handle_rpc_event(
                         "DKG application not yet
     implemented",
                     ),
+                    receipt: None,
+                    hops: vec![],
+                    matches: vec![],
+                    intents: vec![],
+                    depth: None,
                 },
                 None,
             )
@@ -142,13 +588,27 @@ pub async fn handle_rpc_event(
                     Ok(true) => {
                         let result = format!("Node subscribed to {}", topic);
                         tracing::info!("{}", result);
-                        RpcResponse { result }
+                        RpcResponse {
+                            result,
+                            receipt: None,
+                            hops: vec![],
+                            matches: vec![],
+                            intents: vec![],
+                            depth: None,
+                        }
                     }
                     Ok(false) => {
                         let result =
                             format!("Node already subscribed to {}", topic);
                         tracing::info!("{}", result);
-                        RpcResponse { result }
+                        RpcResponse {
+                            result,
+                            receipt: None,
+                            hops: vec![],
+                            matches: vec![],
+                            intents: vec![],
+                            depth: None,
+                        }
                     }
                     Err(err) => {
                         let result = format!(
@@ -156,7 +616,14 @@ pub async fn handle_rpc_event(
                             topic, err
                         );
                         tracing::error!("{}", result);
-                        RpcResponse { result }
+                        RpcResponse {
+                            result,
+                            receipt: None,
+                            hops: vec![],
+                            matches: vec![],
+                            intents: vec![],
+                            depth: None,
+                        }
                     }
                 },
                 None,
@@ -164,3 +631,121 @@ pub async fn handle_rpc_event(
         }
     }
 }
+
+/// Handle an intent dry-run received via RPC. The intent is neither gossiped
+/// nor added to the mempool, it's only sent to the connected matchmakers to
+/// find how they would match it. The response is sent once all the
+/// matchmakers responded, or after a timeout.
+pub fn handle_intent_dry_run(
+    message: services::IntentDryRunMessage,
+    intent_gossiper: &mut IntentGossiper,
+    inject_response: oneshot::Sender<RpcResponse>,
+) {
+    let message = match IntentDryRunMessage::try_from(message) {
+        Ok(message) => message,
+        Err(err) => {
+            let response = RpcResponse {
+                result: format!("Error decoding intent: {:?}", err),
+                receipt: None,
+                hops: vec![],
+                matches: vec![],
+                intents: vec![],
+                depth: None,
+            };
+            let _ = inject_response.send(response);
+            return;
+        }
+    };
+    let dry_run = intent_gossiper.dry_run_intent(message.intent);
+    tokio::spawn(async move {
+        let matchmakers = dry_run.matchmakers;
+        let results = dry_run.results(INTENT_DRY_RUN_TIMEOUT).await;
+        let responded = results.len();
+        let matches: Vec<IntentMatch> = results
+            .into_iter()
+            .filter_map(|result| {
+                result.tx.map(|tx_data| IntentMatch {
+                    tx_data,
+                    matched_intent_ids: result
+                        .matched_intents
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect(),
+                })
+            })
+            .collect();
+        let response = RpcResponse {
+            result: format!(
+                "Intent dry-run by {} of {} connected matchmakers found {} \
+                 matches.",
+                responded,
+                matchmakers,
+                matches.len()
+            ),
+            receipt: None,
+            hops: vec![],
+            matches,
+            intents: vec![],
+            depth: None,
+        };
+        // The client may have disconnected in the meantime
+        let _ = inject_response.send(response);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::key::testing::{keypair_1, keypair_2};
+    use anoma::types::key::RefTo;
+    use borsh::BorshSerialize;
+    use tonic::Code;
+
+    use super::*;
+
+    /// Test that only the intents on the accepted topics that are signed by
+    /// an allowed publisher are accepted
+    #[test]
+    fn test_rpc_policy() {
+        let config = RpcServer {
+            topics: ["asset_v0".to_owned()].into_iter().collect(),
+            publishers: [keypair_1().ref_to().to_string()]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let policy = RpcPolicy::new(&config).unwrap();
+        let intent_message = |keypair: &common::SecretKey, topic: &str| {
+            let intent = Signed::new(
+                keypair,
+                FungibleTokenIntent {
+                    exchange: HashSet::new(),
+                    nonce: 0,
+                },
+            );
+            let intent =
+                anoma::proto::Intent::new(intent.try_to_vec().unwrap());
+            rpc_message::Message::Intent(services::IntentMessage {
+                intent: Some(intent.into()),
+                topic: topic.to_owned(),
+            })
+        };
+
+        assert!(policy
+            .check(&intent_message(&keypair_1(), "asset_v0"))
+            .is_ok());
+        let status = policy
+            .check(&intent_message(&keypair_2(), "asset_v0"))
+            .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        let status = policy
+            .check(&intent_message(&keypair_1(), "asset_v1"))
+            .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+
+        // Anything is accepted without a topic or a publisher in the config
+        let policy = RpcPolicy::new(&RpcServer::default()).unwrap();
+        assert!(policy
+            .check(&intent_message(&keypair_2(), "asset_v1"))
+            .is_ok());
+    }
+}
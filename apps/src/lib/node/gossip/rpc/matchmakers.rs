@@ -9,6 +9,7 @@ use std::fmt::Debug;
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::sync::atomic::{self, AtomicBool};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use derivative::Derivative;
@@ -22,6 +23,12 @@ use crate::cli;
 pub enum MsgFromServer {
     /// Try to match an intent
     AddIntent { id: Vec<u8>, data: Vec<u8> },
+    /// Find how an intent would be matched, without adding it
+    DryRunIntent {
+        request_id: u64,
+        id: Vec<u8>,
+        data: Vec<u8>,
+    },
 }
 
 /// Message from a matchmaker to intent gossiper
@@ -34,10 +41,27 @@ pub enum MsgFromClient {
     /// The matchmaker doesn't care about this intent, gossip it
     IgnoredIntent { id: Vec<u8> },
     /// Intents were matched into a tx. Remove the matched intents from mempool
-    /// if the tx gets applied.
-    Matched { intent_ids: HashSet<Vec<u8>> },
+    /// if the tx gets applied. The tx hash is only set if the tx has been
+    /// submitted to the ledger.
+    Matched {
+        intent_ids: HashSet<Vec<u8>>,
+        tx_hash: Option<String>,
+    },
     /// An intent was accepted and added, but no match found yet. Gossip it
     Unmatched { id: Vec<u8> },
+    /// The result of an intent's dry-run. The tx data and the matched intent
+    /// IDs are only set if the intent would be matched.
+    DryRunResult {
+        request_id: u64,
+        tx: Option<Vec<u8>>,
+        matched_intents: Option<HashSet<Vec<u8>>>,
+    },
+    /// Intents were matched into a tx, but their exchanges were only filled
+    /// in part. Keep them in mempool, as their remainder can still be matched.
+    PartiallyMatched {
+        intent_ids: HashSet<Vec<u8>>,
+        tx_hash: Option<String>,
+    },
 }
 
 /// Intent gossiper server listener handles connections from [`ClientDialer`]s.
@@ -85,6 +109,15 @@ enum ServerEvent {
     Disconnected(Endpoint),
 }
 
+/// An event handled by a [`ClientListener`]
+#[derive(Clone, Debug)]
+pub enum ClientEvent {
+    /// A message received from the intent gossiper
+    Message(MsgFromServer),
+    /// The tick interval of the listener has elapsed
+    Tick,
+}
+
 /// Matchmaker client listener handles a connection from [`ServerDialer`].
 #[derive(Derivative)]
 #[derivative(Debug)]
@@ -263,10 +296,12 @@ impl ServerListener {
 }
 
 impl ServerDialer {
-    /// Broadcast a message to all connected matchmaker clients
-    pub fn send(&mut self, msg: MsgFromServer) {
+    /// Broadcast a message to all connected matchmaker clients. Returns the
+    /// number of the clients that the message has been sent to.
+    pub fn send(&mut self, msg: MsgFromServer) -> usize {
         let net = self.handler.network();
-        for client in self.clients.read().unwrap().iter() {
+        let clients = self.clients.read().unwrap();
+        for client in clients.iter() {
             let msg_bytes = msg.try_to_vec().unwrap();
             let status = net.send(*client, &msg_bytes);
             tracing::info!(
@@ -276,6 +311,7 @@ impl ServerDialer {
                 status
             );
         }
+        clients.len()
     }
 
     /// Is the server listener ready to start handling incoming connections?
@@ -338,11 +374,31 @@ impl ClientListener {
 
     /// Start the client listener and call `on_msg` on every received message.
     /// The listener can be stopped early by [`ClientDialer::shutdown`].
-    pub fn listen(mut self, mut on_msg: impl FnMut(MsgFromServer)) {
-        // This is safe because `listen` consumes `self`
+    pub fn listen(self, mut on_msg: impl FnMut(MsgFromServer)) {
+        self.listen_with_ticks(None, |event| {
+            if let ClientEvent::Message(msg) = event {
+                on_msg(msg)
+            }
+        })
+    }
+
+    /// Start the client listener and call `on_event` on every received
+    /// message and, if a tick interval is given, every time it elapses. The
+    /// listener can be stopped early by [`ClientDialer::shutdown`].
+    pub fn listen_with_ticks(
+        mut self,
+        tick_interval: Option<Duration>,
+        mut on_event: impl FnMut(ClientEvent),
+    ) {
+        // This is safe because `listen_with_ticks` consumes `self`
         let listener = self.listener.take().unwrap();
 
-        // Start the blocking listener that will call `on_msg` on every message
+        // The ticks are signals of the listener itself
+        if let Some(interval) = tick_interval {
+            self.handler.signals().send_with_timer((), interval);
+        }
+
+        // Start the blocking listener that will call `on_event` on every event
         let server_addr = self.server.addr();
         let local_addr_port = self.local_addr.port();
 
@@ -357,7 +413,7 @@ impl ClientListener {
                         mut msg_bytes,
                     ) => match MsgFromServer::deserialize(&mut msg_bytes) {
                         Ok(msg) => {
-                            on_msg(msg);
+                            on_event(ClientEvent::Message(msg));
                         }
                         Err(err) => {
                             tracing::error!(
@@ -407,7 +463,10 @@ impl ClientListener {
                     }
                 },
                 node::NodeEvent::Signal(()) => {
-                    // unused
+                    on_event(ClientEvent::Tick);
+                    if let Some(interval) = tick_interval {
+                        self.handler.signals().send_with_timer((), interval);
+                    }
                 }
             }
         });
@@ -811,9 +870,15 @@ mod test {
         /// Generate an arbitrary MsgFromServer
         fn arb_msg_from_server()
             (id in proptest::collection::vec(any::<u8>(), 1..100),
-            data in proptest::collection::vec(any::<u8>(), 1..100))
+            data in proptest::collection::vec(any::<u8>(), 1..100),
+            dry_run_request_id in proptest::option::of(any::<u64>()))
         -> MsgFromServer {
-            MsgFromServer::AddIntent { id, data }
+            match dry_run_request_id {
+                Some(request_id) => {
+                    MsgFromServer::DryRunIntent { request_id, id, data }
+                }
+                None => MsgFromServer::AddIntent { id, data },
+            }
         }
     }
 
@@ -832,16 +897,53 @@ mod test {
         let unmatched_intent = arb_intent_id
             .clone()
             .prop_map(|id| MsgFromClient::Unmatched { id });
-        let matched_intent =
-            proptest::collection::hash_set(arb_intent_id, 1..10).prop_map(
-                move |intent_ids| MsgFromClient::Matched { intent_ids },
-            );
+        let arb_tx_hash = proptest::option::of("[0-9A-F]{64}");
+        let matched_intent = (
+            proptest::collection::hash_set(arb_intent_id.clone(), 1..10),
+            arb_tx_hash.clone(),
+        )
+            .prop_map(move |(intent_ids, tx_hash)| {
+                MsgFromClient::Matched {
+                    intent_ids,
+                    tx_hash,
+                }
+            });
+        let partially_matched_intent = (
+            proptest::collection::hash_set(arb_intent_id.clone(), 1..10),
+            arb_tx_hash,
+        )
+            .prop_map(move |(intent_ids, tx_hash)| {
+                MsgFromClient::PartiallyMatched {
+                    intent_ids,
+                    tx_hash,
+                }
+            });
+        let dry_run_result = (
+            any::<u64>(),
+            proptest::option::of(proptest::collection::vec(
+                any::<u8>(),
+                1..100,
+            )),
+            proptest::option::of(proptest::collection::hash_set(
+                arb_intent_id,
+                1..10,
+            )),
+        )
+            .prop_map(|(request_id, tx, matched_intents)| {
+                MsgFromClient::DryRunResult {
+                    request_id,
+                    tx,
+                    matched_intents,
+                }
+            });
         prop_oneof![
             invalid_intent,
             intent_too_complex,
             ignored_intent,
             matched_intent,
+            partially_matched_intent,
             unmatched_intent,
+            dry_run_result,
         ]
     }
 }
@@ -1,2 +1,5 @@
+pub mod auth;
 pub mod client;
 pub mod matchmakers;
+pub mod rate_limit;
+pub mod tls;
@@ -0,0 +1,186 @@
+//! The rate limiting of the messages received by the RPC server of an intent
+//! gossip node. Every client IP address has a token bucket of `burst` tokens
+//! that is refilled at `per_second` tokens per second and every message takes
+//! a token from it. The clients connected via a Unix socket share a bucket.
+//!
+//! A rate limited message is rejected with the `RESOURCE_EXHAUSTED` status,
+//! whose [`RETRY_AFTER_MS`] metadata is the number of milliseconds after
+//! which the client can send a message again.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tonic::metadata::MetadataMap;
+use tonic::{Code, Status};
+
+use crate::config::RpcRateLimit;
+
+/// The metadata key of the delay after which a rate limited client can send
+/// a message again, in milliseconds
+pub const RETRY_AFTER_MS: &str = "retry-after-ms";
+
+/// The number of buckets above which the full buckets are dropped
+const MAX_BUCKETS: usize = 10_000;
+/// The delay in seconds above which a rate limited client is told to retry
+/// after [`Duration::MAX`], so that a very low rate doesn't overflow it
+const MAX_RETRY_AFTER_SECS: f64 = u32::MAX as f64;
+
+/// A token bucket rate limiter keyed by the clients' IP addresses
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RpcRateLimit,
+    buckets: Mutex<HashMap<Option<IpAddr>, Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// A rate limiter with the given thresholds
+    pub fn new(config: RpcRateLimit) -> Self {
+        Self {
+            config,
+            buckets: Mutex::default(),
+        }
+    }
+
+    /// Take a token from the bucket of a client at the given time. If the
+    /// bucket is empty, returns the delay until it has a token again.
+    pub fn check(
+        &self,
+        client: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let burst = f64::from(self.config.burst);
+        let per_second = self.config.per_second;
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_BUCKETS {
+            // The full buckets are the same as new ones
+            buckets.retain(|_client, bucket| {
+                let elapsed = now.saturating_duration_since(bucket.updated);
+                bucket.tokens + elapsed.as_secs_f64() * per_second < burst
+            });
+        }
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated);
+        bucket.tokens =
+            (bucket.tokens + elapsed.as_secs_f64() * per_second).min(burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        // A zero rate gives an infinite delay
+        let retry_after = (1.0 - bucket.tokens) / per_second;
+        if retry_after.is_finite() && retry_after < MAX_RETRY_AFTER_SECS {
+            Err(Duration::from_secs_f64(retry_after.max(0.0)))
+        } else {
+            Err(Duration::MAX)
+        }
+    }
+}
+
+/// The status of a rate limited message, with the delay after which the
+/// client can send a message again
+pub fn rate_limited(retry_after: Duration) -> Status {
+    let retry_after_ms =
+        u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX);
+    let mut metadata = MetadataMap::new();
+    metadata.insert(
+        RETRY_AFTER_MS,
+        retry_after_ms
+            .to_string()
+            .parse()
+            .expect("A number is a valid metadata value"),
+    );
+    Status::with_metadata(
+        Code::ResourceExhausted,
+        format!("Rate limited, retry in {} ms", retry_after_ms),
+        metadata,
+    )
+}
+
+/// The delay after which a client can send a message again, if the status is
+/// a rate limited message's status
+pub fn retry_after(status: &Status) -> Option<Duration> {
+    if status.code() != Code::ResourceExhausted {
+        return None;
+    }
+    let retry_after_ms = status
+        .metadata()
+        .get(RETRY_AFTER_MS)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()?;
+    Some(Duration::from_millis(retry_after_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    /// Test that a client can send a burst of messages and then one message
+    /// per refilled token, independently of other clients
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(RpcRateLimit {
+            burst: 2,
+            per_second: 4.0,
+        });
+        let client = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        let other = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        let start = Instant::now();
+
+        assert!(limiter.check(client, start).is_ok());
+        assert!(limiter.check(client, start).is_ok());
+        let retry_after = limiter.check(client, start).unwrap_err();
+        assert_eq!(retry_after, Duration::from_millis(250));
+        assert!(limiter.check(other, start).is_ok());
+        assert!(limiter.check(None, start).is_ok());
+
+        let later = start + Duration::from_millis(250);
+        assert!(limiter.check(client, later).is_ok());
+        assert!(limiter.check(client, later).is_err());
+
+        let status = rate_limited(retry_after);
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(super::retry_after(&status), Some(retry_after));
+    }
+
+    /// Test that the delay of a client of a rate limiter with a zero or a very
+    /// low rate doesn't overflow and that such rates are valid, unlike an
+    /// empty burst or an invalid rate
+    #[test]
+    fn test_rate_limiter_low_rate() {
+        let client = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        let start = Instant::now();
+        for per_second in [0.0, f64::MIN_POSITIVE] {
+            let config = RpcRateLimit {
+                burst: 1,
+                per_second,
+            };
+            assert!(config.validate().is_ok());
+            let limiter = RateLimiter::new(config);
+            assert!(limiter.check(client, start).is_ok());
+            assert_eq!(limiter.check(client, start), Err(Duration::MAX));
+        }
+
+        for (burst, per_second) in
+            [(0, 1.0), (1, -1.0), (1, f64::NAN), (1, f64::INFINITY)]
+        {
+            let config = RpcRateLimit { burst, per_second };
+            assert!(config.validate().is_err());
+        }
+    }
+}
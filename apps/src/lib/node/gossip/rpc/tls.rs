@@ -0,0 +1,139 @@
+//! TLS for the RPC server of an intent gossip node and its clients. The
+//! server is configured with the PEM encoded certificate and private key of
+//! its `tls` config and, with a `client_ca_path`, it only accepts the clients
+//! with a certificate signed by that CA (mutual TLS).
+//!
+//! The client connects with TLS if the [`ENV_VAR_RPC_TLS_CA_CERT`]
+//! environment variable is set to the path of the CA certificate with which
+//! it verifies the server's certificate. With mutual TLS, the client's own
+//! certificate and private key are at the paths of the
+//! [`ENV_VAR_RPC_TLS_CERT`] and [`ENV_VAR_RPC_TLS_KEY`] environment
+//! variables.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use hyper::http::uri::InvalidUri;
+use thiserror::Error;
+use tonic::transport::{
+    Certificate, Channel, ClientTlsConfig, Endpoint, Identity, ServerTlsConfig,
+};
+
+use crate::config::RpcTls;
+use crate::proto::services::rpc_service_client::RpcServiceClient;
+
+/// The environment variable of the path of the CA certificate with which the
+/// client verifies the RPC server's certificate
+pub const ENV_VAR_RPC_TLS_CA_CERT: &str = "ANOMA_RPC_TLS_CA_CERT";
+
+/// The environment variable of the path of the client's certificate for
+/// mutual TLS
+pub const ENV_VAR_RPC_TLS_CERT: &str = "ANOMA_RPC_TLS_CERT";
+
+/// The environment variable of the path of the client's private key for
+/// mutual TLS
+pub const ENV_VAR_RPC_TLS_KEY: &str = "ANOMA_RPC_TLS_KEY";
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Invalid RPC address {0}: {1}")]
+    InvalidAddress(String, InvalidUri),
+    #[error("Failed to read the TLS file {}: {1}", .0.display())]
+    ReadFile(PathBuf, std::io::Error),
+    #[error(
+        "Both or neither of the {} and {} environment variables must be set",
+        ENV_VAR_RPC_TLS_CERT,
+        ENV_VAR_RPC_TLS_KEY
+    )]
+    IncompleteIdentity,
+    #[error("{0}")]
+    Transport(tonic::transport::Error),
+}
+
+/// The TLS config of the RPC server
+pub fn server_config(config: &RpcTls) -> Result<ServerTlsConfig, Error> {
+    let cert = read_file(&config.cert_path)?;
+    let key = read_file(&config.key_path)?;
+    let tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+    match &config.client_ca_path {
+        Some(path) => {
+            let client_ca = read_file(path)?;
+            Ok(tls.client_ca_root(Certificate::from_pem(client_ca)))
+        }
+        None => Ok(tls),
+    }
+}
+
+/// The TLS config of the RPC client, if the [`ENV_VAR_RPC_TLS_CA_CERT`]
+/// environment variable is set
+pub fn client_config() -> Result<Option<ClientTlsConfig>, Error> {
+    let ca_path = match env::var_os(ENV_VAR_RPC_TLS_CA_CERT) {
+        Some(path) => PathBuf::from(path),
+        None => return Ok(None),
+    };
+    let ca = read_file(&ca_path)?;
+    let tls = ClientTlsConfig::new().ca_certificate(Certificate::from_pem(ca));
+    match (
+        env::var_os(ENV_VAR_RPC_TLS_CERT),
+        env::var_os(ENV_VAR_RPC_TLS_KEY),
+    ) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = read_file(Path::new(&cert_path))?;
+            let key = read_file(Path::new(&key_path))?;
+            Ok(Some(tls.identity(Identity::from_pem(cert, key))))
+        }
+        (None, None) => Ok(Some(tls)),
+        _ => Err(Error::IncompleteIdentity),
+    }
+}
+
+/// Connect an RPC client to the node at the given address, with TLS if the
+/// [`ENV_VAR_RPC_TLS_CA_CERT`] environment variable is set
+pub async fn connect(
+    node_addr: String,
+) -> Result<RpcServiceClient<Channel>, Error> {
+    let mut endpoint = Endpoint::from_shared(node_addr.clone())
+        .map_err(|err| Error::InvalidAddress(node_addr, err))?;
+    if let Some(tls) = client_config()? {
+        endpoint = endpoint.tls_config(tls).map_err(Error::Transport)?;
+    }
+    let channel = endpoint.connect().await.map_err(Error::Transport)?;
+    Ok(RpcServiceClient::new(channel))
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, Error> {
+    std::fs::read(path).map_err(|err| Error::ReadFile(path.to_owned(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use tonic::transport::Server;
+
+    use super::*;
+
+    /// Test that the server TLS config can only be built from the PEM files
+    /// of a valid certificate and private key
+    #[test]
+    fn test_server_config() {
+        let dir = TempDir::new().unwrap();
+        let config = RpcTls {
+            cert_path: dir.path().join("cert.pem"),
+            key_path: dir.path().join("key.pem"),
+            client_ca_path: None,
+        };
+        assert!(matches!(server_config(&config), Err(Error::ReadFile(..))));
+
+        std::fs::write(&config.cert_path, "not a certificate").unwrap();
+        std::fs::write(&config.key_path, "not a key").unwrap();
+        let tls = server_config(&config).unwrap();
+        assert!(Server::builder().tls_config(tls).is_err());
+
+        let config = RpcTls {
+            client_ca_path: Some(dir.path().join("ca.pem")),
+            ..config
+        };
+        assert!(matches!(server_config(&config), Err(Error::ReadFile(..))));
+    }
+}
@@ -0,0 +1,154 @@
+//! The subscriptions of RPC clients to the matches of intents. A client
+//! subscribes to the matches of an intent by its SHA-256 hash, see
+//! [`Intent::sha256`], and is notified when a matchmaker connected to the
+//! node matches the intent, with the hash of the tx that the intent has been
+//! matched into. The subscription ends once the intent is fully matched.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anoma::proto::Intent;
+use thiserror::Error;
+use tokio::sync::mpsc;
+
+/// The number of subscriptions above which the subscriptions of the
+/// disconnected clients are dropped, and new subscriptions are rejected if
+/// there are still as many
+const MAX_SUBSCRIPTIONS: usize = 10_000;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Too many subscriptions to the matches of intents")]
+    TooManySubscriptions,
+}
+
+/// A notification of a match of an intent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchNotification {
+    /// The SHA-256 hash of the intent
+    pub intent_hash: Vec<u8>,
+    /// The hash of the tx that the intent has been matched into, if the
+    /// matchmaker has submitted it to the ledger
+    pub tx_hash: Option<String>,
+    /// Whether the intent's exchanges have only been filled in part
+    pub partial: bool,
+}
+
+/// The subscriptions to the matches of intents, shared by the RPC server and
+/// the matchmakers server
+#[derive(Debug, Default)]
+pub struct MatchSubscriptions {
+    subscribers: Mutex<Subscribers>,
+}
+
+/// The senders of the notifications, keyed by the intents' hashes
+type Subscribers =
+    HashMap<Vec<u8>, Vec<mpsc::UnboundedSender<MatchNotification>>>;
+
+impl MatchSubscriptions {
+    /// Subscribe to the matches of the intent with the given hash. The
+    /// intent doesn't have to be known yet.
+    pub fn subscribe(
+        &self,
+        intent_hash: Vec<u8>,
+    ) -> Result<mpsc::UnboundedReceiver<MatchNotification>, Error> {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if count(&subscribers) >= MAX_SUBSCRIPTIONS {
+            subscribers.retain(|_hash, senders| {
+                senders.retain(|sender| !sender.is_closed());
+                !senders.is_empty()
+            });
+            if count(&subscribers) >= MAX_SUBSCRIPTIONS {
+                return Err(Error::TooManySubscriptions);
+            }
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        subscribers.entry(intent_hash).or_default().push(sender);
+        Ok(receiver)
+    }
+
+    /// Notify the subscribers of a match of an intent. The subscriptions end
+    /// once the intent is fully matched.
+    pub fn notify(
+        &self,
+        intent: &Intent,
+        tx_hash: Option<String>,
+        partial: bool,
+    ) {
+        let intent_hash = intent.sha256().to_vec();
+        let notification = MatchNotification {
+            intent_hash: intent_hash.clone(),
+            tx_hash,
+            partial,
+        };
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if partial {
+            if let Some(senders) = subscribers.get_mut(&intent_hash) {
+                // The subscriptions of the disconnected clients are dropped
+                senders
+                    .retain(|sender| sender.send(notification.clone()).is_ok());
+                if senders.is_empty() {
+                    subscribers.remove(&intent_hash);
+                }
+            }
+        } else if let Some(senders) = subscribers.remove(&intent_hash) {
+            for sender in senders {
+                // The client may have disconnected in the meantime
+                let _ = sender.send(notification.clone());
+            }
+        }
+    }
+}
+
+/// The number of subscriptions
+fn count(subscribers: &Subscribers) -> usize {
+    subscribers.values().map(Vec::len).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the subscribers of an intent are notified of its partial
+    /// matches and that the subscriptions end once it's fully matched
+    #[test]
+    fn test_match_subscriptions() {
+        let subscriptions = MatchSubscriptions::default();
+        let intent = Intent::new(b"intent".to_vec());
+        let other = Intent::new(b"other".to_vec());
+        let intent_hash = intent.sha256().to_vec();
+        let mut first = subscriptions.subscribe(intent_hash.clone()).unwrap();
+        let mut second = subscriptions.subscribe(intent_hash.clone()).unwrap();
+        let mut unmatched =
+            subscriptions.subscribe(other.sha256().to_vec()).unwrap();
+
+        subscriptions.notify(&intent, Some("A1".to_owned()), true);
+        let partial = MatchNotification {
+            intent_hash: intent_hash.clone(),
+            tx_hash: Some("A1".to_owned()),
+            partial: true,
+        };
+        assert_eq!(first.try_recv().unwrap(), partial);
+        assert_eq!(second.try_recv().unwrap(), partial);
+
+        // A disconnected subscriber doesn't prevent notifying the others
+        drop(second);
+        subscriptions.notify(&intent, None, false);
+        let full = MatchNotification {
+            intent_hash,
+            tx_hash: None,
+            partial: false,
+        };
+        assert_eq!(first.try_recv().unwrap(), full);
+        // The subscription has ended
+        assert!(matches!(
+            first.try_recv(),
+            Err(mpsc::error::TryRecvError::Disconnected)
+        ));
+        assert!(matches!(
+            unmatched.try_recv(),
+            Err(mpsc::error::TryRecvError::Empty)
+        ));
+    }
+}
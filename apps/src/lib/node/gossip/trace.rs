@@ -0,0 +1,130 @@
+//! The trace mode of the intent gossip node. A node in the trace mode
+//! announces every intent that it receives to the network with its hop, i.e.
+//! its peer ID, the peer from which it received the intent and the time. The
+//! hops announced by all the nodes in the trace mode are recorded, so that the
+//! propagation path of an intent can be retrieved from any of them via RPC.
+
+use std::collections::{HashMap, VecDeque};
+
+use anoma::proto::{Intent, IntentGossipMessage, IntentHop};
+use anoma::types::time::DateTimeUtc;
+use libp2p::gossipsub::IdentTopic;
+use libp2p::PeerId;
+
+use super::p2p::behaviour::Gossipsub;
+
+/// The most intents of which the hops are recorded. The hops of the oldest
+/// intents are forgotten first.
+const MAX_TRACED_INTENTS: usize = 10_000;
+
+/// The hops of intents recorded by a node in the trace mode
+#[derive(Debug)]
+pub struct IntentTraces {
+    /// The peer ID of this node
+    peer_id: PeerId,
+    /// The hops of the intents by their hashes
+    hops: HashMap<Vec<u8>, Vec<IntentHop>>,
+    /// The hashes of the traced intents, from the oldest
+    order: VecDeque<Vec<u8>>,
+}
+
+impl IntentTraces {
+    pub fn new(peer_id: PeerId) -> Self {
+        Self {
+            peer_id,
+            hops: HashMap::default(),
+            order: VecDeque::default(),
+        }
+    }
+
+    /// Record the receipt of an intent by this node from the given peer, or
+    /// via RPC if there's no peer. Returns the hop to be announced.
+    pub fn receive(
+        &mut self,
+        intent: &Intent,
+        from: Option<&PeerId>,
+    ) -> IntentHop {
+        let hop = IntentHop {
+            intent_hash: intent.sha256().to_vec(),
+            node_id: self.peer_id.to_string(),
+            from_node_id: from.map(PeerId::to_string),
+            received_at: DateTimeUtc::now(),
+        };
+        self.record(hop.clone());
+        hop
+    }
+
+    /// Record a hop of an intent announced by some node. The same hop is only
+    /// recorded once.
+    pub fn record(&mut self, hop: IntentHop) {
+        if !self.hops.contains_key(&hop.intent_hash) {
+            if self.order.len() >= MAX_TRACED_INTENTS {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.hops.remove(&oldest);
+                }
+            }
+            self.order.push_back(hop.intent_hash.clone());
+        }
+        let hops = self.hops.entry(hop.intent_hash.clone()).or_default();
+        if !hops.contains(&hop) {
+            hops.push(hop);
+        }
+    }
+
+    /// The recorded hops of an intent with the given hash, ordered by the time
+    /// of receipt
+    pub fn get(&self, intent_hash: &[u8]) -> Vec<IntentHop> {
+        let mut hops = self.hops.get(intent_hash).cloned().unwrap_or_default();
+        hops.sort_by_key(|hop| hop.received_at);
+        hops
+    }
+}
+
+/// Announce a hop of an intent to the nodes subscribed to the intent's topic
+pub fn announce_hop(
+    gossip_sub: &mut Gossipsub,
+    hop: IntentHop,
+    topic: IdentTopic,
+) {
+    let message = IntentGossipMessage::Hop(hop);
+    if let Err(err) = gossip_sub.publish(topic, message.to_bytes()) {
+        tracing::debug!("Failed to announce an intent hop: {:?}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that the hops of an intent are recorded once, ordered by time
+    /// and forgotten for the oldest intents
+    #[test]
+    fn test_intent_traces() {
+        let peer_id = PeerId::random();
+        let other_peer_id = PeerId::random();
+        let mut traces = IntentTraces::new(peer_id);
+        let intent = Intent::new(b"intent".to_vec());
+        let hash = intent.sha256().to_vec();
+
+        let other_hop = IntentHop {
+            intent_hash: hash.clone(),
+            node_id: other_peer_id.to_string(),
+            from_node_id: None,
+            received_at: intent.timestamp,
+        };
+        let hop = traces.receive(&intent, Some(&other_peer_id));
+        assert_eq!(hop.node_id, peer_id.to_string());
+        assert_eq!(hop.from_node_id, Some(other_peer_id.to_string()));
+        traces.record(other_hop.clone());
+        traces.record(other_hop.clone());
+        assert_eq!(traces.get(&hash), vec![other_hop, hop]);
+        assert!(traces.get(b"unknown").is_empty());
+
+        for i in 0..MAX_TRACED_INTENTS {
+            let intent = Intent::new(i.to_le_bytes().to_vec());
+            traces.receive(&intent, None);
+        }
+        assert!(traces.get(&hash).is_empty());
+        assert_eq!(traces.hops.len(), MAX_TRACED_INTENTS);
+    }
+}
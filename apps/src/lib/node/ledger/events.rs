@@ -1,16 +1,41 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::fmt::{self, Display};
 use std::ops::{Index, IndexMut};
 
 use anoma::ledger::governance::utils::ProposalEvent;
+use anoma::ledger::pos::anoma_proof_of_stake::types::{
+    ValidatorSet, VotingPower,
+};
+use anoma::proto::{SignedTxData, Tx};
+use anoma::types::address::Address;
 use anoma::types::ibc::IbcEvent;
-use anoma::types::transaction::{hash_tx, TxType};
-use borsh::BorshSerialize;
+use anoma::types::storage::Epoch;
+use anoma::types::token;
+use anoma::types::transaction::batch::BatchOpResult;
+use anoma::types::transaction::{hash_tx, schema, DecryptedTx, TxType};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::Serialize;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_config::net::Address as TendermintAddress;
+#[cfg(feature = "ABCI")]
+use tendermint_config_abci::net::Address as TendermintAddress;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::abci::EventAttribute;
 #[cfg(feature = "ABCI")]
 use tendermint_proto_abci::abci::EventAttribute;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::error::Error as TError;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::query::Query;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::{Client, HttpClient, Order};
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::error::Error as TError;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::query::Query;
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::{Client, HttpClient, Order};
 use thiserror::Error;
 
 /// Indicates if an event is emitted do to
@@ -41,6 +66,12 @@ pub enum EventType {
     Ibc(String),
     // The proposal that has been executed
     Proposal,
+    // A validator entered or left the active validator set, or its voting
+    // power changed, at the start of an epoch
+    ValidatorSetChange,
+    // An active validator's self-bond is below the minimum self-bond at the
+    // start of an epoch
+    SelfBondBelowMinimum,
 }
 
 #[cfg(not(feature = "ABCI"))]
@@ -51,6 +82,12 @@ impl Display for EventType {
             EventType::Applied => write!(f, "applied"),
             EventType::Ibc(t) => write!(f, "{}", t),
             EventType::Proposal => write!(f, "proposal"),
+            EventType::ValidatorSetChange => {
+                write!(f, "validator_set_change")
+            }
+            EventType::SelfBondBelowMinimum => {
+                write!(f, "self_bond_below_minimum")
+            }
         }?;
         Ok(())
     }
@@ -64,6 +101,12 @@ impl Display for EventType {
             EventType::Applied => write!(f, "applied"),
             EventType::Ibc(t) => write!(f, "{}", t),
             EventType::Proposal => write!(f, "proposal"),
+            EventType::ValidatorSetChange => {
+                write!(f, "validator_set_change")
+            }
+            EventType::SelfBondBelowMinimum => {
+                write!(f, "self_bond_below_minimum")
+            }
         }?;
         Ok(())
     }
@@ -99,6 +142,11 @@ impl Event {
                     attributes: HashMap::new(),
                 };
                 event["hash"] = decrypted.hash_commitment().to_string();
+                if let DecryptedTx::Decrypted(tx) = decrypted {
+                    if let Some(memo) = transfer_memo(tx) {
+                        event["memo"] = memo;
+                    }
+                }
                 event
             }
             tx @ TxType::Protocol(_) => {
@@ -121,6 +169,26 @@ impl Event {
         event
     }
 
+    /// Creates a new event for an active validator whose self-bond is below
+    /// the minimum self-bond at the start of the given epoch
+    pub fn new_self_bond_below_minimum_event(
+        validator: &Address,
+        self_bond: token::Amount,
+        min_self_bond: token::Amount,
+        epoch: Epoch,
+    ) -> Self {
+        let mut event = Event {
+            event_type: EventType::SelfBondBelowMinimum,
+            level: EventLevel::Block,
+            attributes: HashMap::new(),
+        };
+        event["epoch"] = epoch.to_string();
+        event["validator"] = validator.encode();
+        event["self_bond"] = self_bond.to_string();
+        event["min_self_bond"] = min_self_bond.to_string();
+        event
+    }
+
     /// Check if the events keys contains a given string
     pub fn contains_key(&self, key: &str) -> bool {
         self.attributes.contains_key(key)
@@ -133,6 +201,125 @@ impl Event {
     }
 }
 
+/// A change of a validator in the active validator set, which is the set of
+/// validators participating in the consensus
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidatorSetChange {
+    /// The validator entered the active set
+    Entered {
+        validator: Address,
+        voting_power: VotingPower,
+    },
+    /// The validator left the active set
+    Left {
+        validator: Address,
+        previous_voting_power: VotingPower,
+    },
+    /// The voting power of an active validator changed
+    VotingPower {
+        validator: Address,
+        previous_voting_power: VotingPower,
+        voting_power: VotingPower,
+    },
+}
+
+impl ValidatorSetChange {
+    /// Find the changes of the active validator set from the previous epoch
+    pub fn find(
+        previous: Option<&ValidatorSet<Address>>,
+        current: &ValidatorSet<Address>,
+    ) -> Vec<Self> {
+        let previous: BTreeMap<&Address, VotingPower> = previous
+            .into_iter()
+            .flat_map(|set| &set.active)
+            .map(|validator| (&validator.address, validator.voting_power))
+            .collect();
+        let current: BTreeMap<&Address, VotingPower> = current
+            .active
+            .iter()
+            .map(|validator| (&validator.address, validator.voting_power))
+            .collect();
+        let mut changes = vec![];
+        for (&validator, &voting_power) in &current {
+            match previous.get(validator) {
+                None => changes.push(Self::Entered {
+                    validator: validator.clone(),
+                    voting_power,
+                }),
+                Some(&previous_voting_power)
+                    if previous_voting_power != voting_power =>
+                {
+                    changes.push(Self::VotingPower {
+                        validator: validator.clone(),
+                        previous_voting_power,
+                        voting_power,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for (&validator, &previous_voting_power) in &previous {
+            if !current.contains_key(validator) {
+                changes.push(Self::Left {
+                    validator: validator.clone(),
+                    previous_voting_power,
+                })
+            }
+        }
+        changes
+    }
+
+    /// The event of the change at the start of the given epoch
+    pub fn to_event(&self, epoch: Epoch) -> Event {
+        let mut event = Event {
+            event_type: EventType::ValidatorSetChange,
+            level: EventLevel::Block,
+            attributes: HashMap::new(),
+        };
+        event["epoch"] = epoch.to_string();
+        match self {
+            Self::Entered {
+                validator,
+                voting_power,
+            } => {
+                event["change"] = "entered".to_string();
+                event["validator"] = validator.encode();
+                event["voting_power"] = voting_power.to_string();
+            }
+            Self::Left {
+                validator,
+                previous_voting_power,
+            } => {
+                event["change"] = "left".to_string();
+                event["validator"] = validator.encode();
+                event["previous_voting_power"] =
+                    previous_voting_power.to_string();
+            }
+            Self::VotingPower {
+                validator,
+                previous_voting_power,
+                voting_power,
+            } => {
+                event["change"] = "voting_power".to_string();
+                event["validator"] = validator.encode();
+                event["previous_voting_power"] =
+                    previous_voting_power.to_string();
+                event["voting_power"] = voting_power.to_string();
+            }
+        }
+        event
+    }
+}
+
+/// Get the memo of a token transfer, if the tx data is a signed
+/// `token::Transfer` with a memo
+fn transfer_memo(tx: &Tx) -> Option<String> {
+    let signed = SignedTxData::try_from_slice(tx.data.as_ref()?).ok()?;
+    let transfer =
+        schema::decode::<token::Transfer>(signed.data.as_ref()?).ok()?;
+    transfer.memo
+}
+
 impl Index<&str> for Event {
     type Output = String;
 
@@ -274,3 +461,199 @@ impl TryFrom<&serde_json::Value> for Attributes {
         Ok(Attributes(attributes))
     }
 }
+
+/// A parsed event from tendermint relating to a transaction
+#[derive(Clone, Debug, Serialize)]
+pub struct TxResponse {
+    pub info: String,
+    pub log: String,
+    pub height: String,
+    pub hash: String,
+    pub code: String,
+    pub gas_used: String,
+    pub initialized_accounts: Vec<Address>,
+    /// The results of the operations of a batch tx
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub batch_results: Vec<BatchOpResult>,
+}
+
+/// Represents a query for an event pertaining to the specified transaction
+#[derive(Debug, Clone)]
+pub enum TxEventQuery {
+    Accepted(String),
+    Applied(String),
+}
+
+impl TxEventQuery {
+    /// The event type to which this event query pertains
+    fn event_type(&self) -> &'static str {
+        match self {
+            TxEventQuery::Accepted(_tx_hash) => "accepted",
+            TxEventQuery::Applied(_tx_hash) => "applied",
+        }
+    }
+
+    /// The transaction to which this event query pertains
+    pub fn tx_hash(&self) -> &String {
+        match self {
+            TxEventQuery::Accepted(tx_hash) => tx_hash,
+            TxEventQuery::Applied(tx_hash) => tx_hash,
+        }
+    }
+}
+
+/// Transaction event queries are semantically a subset of general queries
+impl From<TxEventQuery> for Query {
+    fn from(tx_query: TxEventQuery) -> Self {
+        match tx_query {
+            TxEventQuery::Accepted(tx_hash) => {
+                Query::default().and_eq("accepted.hash", tx_hash)
+            }
+            TxEventQuery::Applied(tx_hash) => {
+                Query::default().and_eq("applied.hash", tx_hash)
+            }
+        }
+    }
+}
+
+/// Lookup the full response accompanying the specified transaction event
+pub async fn query_tx_response(
+    ledger_address: &TendermintAddress,
+    tx_query: TxEventQuery,
+) -> Result<TxResponse, TError> {
+    // Connect to the Tendermint server holding the transactions. Only HTTP
+    // RPC is needed, which is exposed by more nodes than websocket.
+    let client = HttpClient::new(ledger_address.clone())?;
+    // Find all blocks that apply a transaction with the specified hash
+    let blocks = &client
+        .block_search(Query::from(tx_query.clone()), 1, 255, Order::Ascending)
+        .await?
+        .blocks;
+    // Get the block results corresponding to a block to which
+    // the specified transaction belongs
+    let block = &blocks
+        .get(0)
+        .ok_or_else(|| {
+            TError::server(
+                "Unable to find a block applying the given transaction"
+                    .to_string(),
+            )
+        })?
+        .block;
+    let response_block_results =
+        client.block_results(block.header.height).await?;
+    // Search for the event where the specified transaction is
+    // applied to the blockchain
+    let query_event_opt =
+        response_block_results.end_block_events.and_then(|events| {
+            (&events)
+                .iter()
+                .find(|event| {
+                    event.type_str == tx_query.event_type()
+                        && (&event.attributes).iter().any(|tag| {
+                            tag.key.as_ref() == "hash"
+                                && tag.value.as_ref() == tx_query.tx_hash()
+                        })
+                })
+                .cloned()
+        });
+    let query_event = query_event_opt.ok_or_else(|| {
+        TError::server(
+            "Unable to find the event corresponding to the specified \
+             transaction"
+                .to_string(),
+        )
+    })?;
+    // Reformat the event attributes so as to ease value extraction
+    let event_map: std::collections::HashMap<&str, &str> = (&query_event
+        .attributes)
+        .iter()
+        .map(|tag| (tag.key.as_ref(), tag.value.as_ref()))
+        .collect();
+    // Summarize the transaction results that we were searching for
+    let result = TxResponse {
+        info: event_map["info"].to_string(),
+        log: event_map["log"].to_string(),
+        height: event_map["height"].to_string(),
+        hash: event_map["hash"].to_string(),
+        code: event_map["code"].to_string(),
+        gas_used: event_map["gas_used"].to_string(),
+        initialized_accounts: serde_json::from_str(
+            event_map["initialized_accounts"],
+        )
+        .unwrap_or_default(),
+        batch_results: event_map
+            .get("batch_results")
+            .and_then(|results| serde_json::from_str(results).ok())
+            .unwrap_or_default(),
+    };
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anoma::ledger::pos::anoma_proof_of_stake::types::WeightedValidator;
+    use anoma::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+    };
+
+    use super::*;
+
+    fn validator_set(active: &[(Address, u64)]) -> ValidatorSet<Address> {
+        ValidatorSet {
+            active: active
+                .iter()
+                .map(|(address, voting_power)| WeightedValidator {
+                    voting_power: (*voting_power).into(),
+                    address: address.clone(),
+                })
+                .collect(),
+            inactive: BTreeSet::default(),
+        }
+    }
+
+    /// Test that the validators entering and leaving the active set and the
+    /// changes of their voting power are found
+    #[test]
+    fn test_validator_set_changes() {
+        let (validator_1, validator_2, validator_3) = (
+            established_address_1(),
+            established_address_2(),
+            established_address_3(),
+        );
+        let previous = validator_set(&[
+            (validator_1.clone(), 10),
+            (validator_2.clone(), 20),
+        ]);
+        let current = validator_set(&[
+            (validator_2.clone(), 25),
+            (validator_3.clone(), 5),
+        ]);
+
+        let changes = ValidatorSetChange::find(Some(&previous), &current);
+        assert_eq!(changes.len(), 3);
+        assert!(changes.contains(&ValidatorSetChange::Entered {
+            validator: validator_3.clone(),
+            voting_power: 5_u64.into(),
+        }));
+        assert!(changes.contains(&ValidatorSetChange::Left {
+            validator: validator_1,
+            previous_voting_power: 10_u64.into(),
+        }));
+        assert!(changes.contains(&ValidatorSetChange::VotingPower {
+            validator: validator_2,
+            previous_voting_power: 20_u64.into(),
+            voting_power: 25_u64.into(),
+        }));
+
+        assert!(ValidatorSetChange::find(Some(&current), &current).is_empty());
+        assert_eq!(ValidatorSetChange::find(None, &current).len(), 2);
+
+        let event = changes[0].to_event(Epoch(2));
+        assert_eq!(event["epoch"], "2");
+        assert!(event.contains_key("change"));
+        assert!(event.contains_key("validator"));
+    }
+}
@@ -0,0 +1,486 @@
+//! An HTTP gateway that serves a JSON API for browser clients. The requests
+//! are translated to the calls of the node's Tendermint RPC, so that browser
+//! clients don't need a proxy to talk to a node.
+//!
+//! The API is:
+//! - `POST /tx` with a body `{"tx": "<hex encoded tx bytes>"}` broadcasts the
+//!   tx and responds with the result of checking it before it's added to the
+//!   mempool. Bodies larger than 4 MB are refused.
+//! - `GET /storage?key=<storage key>&height=<height>` responds with `{"value":
+//!   "<hex encoded bytes>"}` of the value at the percent-encoded storage key.
+//!   The height is optional and defaults to the last committed block.
+//! - `GET /events/<accepted|applied>/<hash>` responds with the event of the tx
+//!   with the given hash
+//!
+//! The errors are responded with `{"error": "<message>"}`.
+//!
+//! The gateway listens on a TCP or a Unix domain socket. When it's configured
+//! with `proxy_protocol`, every connection must start with a PROXY protocol
+//! header from the reverse proxy in front of it.
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anoma::types::storage;
+use hyper::body::HttpBody;
+use hyper::header::{self, HeaderMap, HeaderValue};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "ABCI"))]
+use tendermint::abci::Code;
+#[cfg(not(feature = "ABCI"))]
+use tendermint::block::Height;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_config::net::Address as TendermintAddress;
+#[cfg(feature = "ABCI")]
+use tendermint_config_abci::net::Address as TendermintAddress;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_rpc::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_rpc_abci::{Client, HttpClient};
+#[cfg(feature = "ABCI")]
+use tendermint_stable::abci::Code;
+#[cfg(feature = "ABCI")]
+use tendermint_stable::block::Height;
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+use crate::config::{self, ListenAddress};
+use crate::node::ledger::events::{query_tx_response, TxEventQuery};
+use crate::node::ledger::rpc::Path;
+use crate::node::listener::Listener;
+
+/// The methods allowed in cross-origin requests
+const CORS_ALLOWED_METHODS: &str = "GET, POST, OPTIONS";
+/// The headers allowed in cross-origin requests
+const CORS_ALLOWED_HEADERS: &str = "Content-Type";
+/// How long browsers may cache the response to a preflight request, in
+/// seconds
+const CORS_MAX_AGE: &str = "86400";
+/// The maximum size of the body of a request to submit a tx. This is twice the
+/// body limit of the node's Tendermint RPC, because the tx is hex encoded.
+const MAX_TX_BODY_BYTES: usize = 4_000_000;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Failed to bind the gateway to {0}: {1}")]
+    Bind(ListenAddress, std::io::Error),
+}
+
+/// The errors of API requests
+#[derive(Error, Debug)]
+enum ApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("The request body is larger than {0} bytes")]
+    PayloadTooLarge(usize),
+    #[error("{0}")]
+    Node(String),
+}
+
+/// The body of a request to submit a tx
+#[derive(Debug, Deserialize)]
+struct SubmitTx {
+    /// Hex encoded tx bytes
+    tx: String,
+}
+
+/// The body of the response to a storage query
+#[derive(Debug, Serialize)]
+struct StorageValue {
+    /// Hex encoded value bytes
+    value: String,
+}
+
+/// The body of an error response
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// The gateway, with a client of the node's Tendermint RPC
+pub struct Gateway {
+    config: config::Gateway,
+    rpc_address: TendermintAddress,
+    client: HttpClient,
+}
+
+impl Gateway {
+    /// Create a new gateway that forwards the requests to the given
+    /// Tendermint RPC address
+    pub fn new(config: config::Gateway, rpc_address: SocketAddr) -> Self {
+        let rpc_address =
+            TendermintAddress::from_str(&rpc_address.to_string()).unwrap();
+        let client = HttpClient::new(rpc_address.clone()).unwrap();
+        Self {
+            config,
+            rpc_address,
+            client,
+        }
+    }
+
+    /// Serve the API until an abort signal is received
+    pub async fn run(
+        self,
+        abort_recv: oneshot::Receiver<()>,
+    ) -> Result<(), Error> {
+        let address = self.config.address.clone();
+        let proxy_protocol = self.config.proxy_protocol;
+        let listener = Listener::bind(&address)
+            .await
+            .map_err(|err| Error::Bind(address.clone(), err))?;
+        tracing::info!("Gateway listening on {}", address);
+        let gateway = Arc::new(self);
+        let http = Http::new();
+        let mut abort_recv = abort_recv;
+        loop {
+            let mut stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        tracing::error!("Gateway failed to accept: {}", err);
+                        continue;
+                    }
+                },
+                _ = &mut abort_recv => {
+                    tracing::info!("Shutting down gateway...");
+                    return Ok(());
+                }
+            };
+            let gateway = gateway.clone();
+            let http = http.clone();
+            tokio::spawn(async move {
+                if proxy_protocol {
+                    if let Err(err) = stream.read_proxy_header().await {
+                        tracing::debug!("Gateway connection error: {}", err);
+                        return;
+                    }
+                }
+                tracing::debug!(
+                    "Gateway connection from {:?}",
+                    stream.info().remote_addr
+                );
+                let service =
+                    service_fn(move |req| serve(gateway.clone(), req));
+                if let Err(err) = http.serve_connection(stream, service).await {
+                    tracing::debug!("Gateway connection error: {}", err);
+                }
+            });
+        }
+    }
+
+    /// Handle a request, with the CORS headers for the request's origin
+    async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        let origin = req
+            .headers()
+            .get(header::ORIGIN)
+            .and_then(|origin| origin.to_str().ok())
+            .map(ToOwned::to_owned);
+        let mut response = if req.method() == Method::OPTIONS {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::NO_CONTENT;
+            response
+        } else {
+            match self.route(req).await {
+                Ok(response) => response,
+                Err(err) => error_response(err),
+            }
+        };
+        if let Some(origin) = origin {
+            add_cors_headers(
+                &self.config.cors_allowed_origins,
+                &origin,
+                response.headers_mut(),
+            );
+        }
+        response
+    }
+
+    /// Route a request to its handler
+    async fn route(
+        &self,
+        req: Request<Body>,
+    ) -> Result<Response<Body>, ApiError> {
+        let method = req.method().clone();
+        let path = req.uri().path().to_owned();
+        let segments: Vec<&str> =
+            path.split('/').filter(|s| !s.is_empty()).collect();
+        match (&method, &segments[..]) {
+            (&Method::POST, ["tx"]) => {
+                let body =
+                    read_body(req.into_body(), MAX_TX_BODY_BYTES).await?;
+                self.submit_tx(&body).await
+            }
+            (&Method::GET, ["storage"]) => {
+                let query = req.uri().query();
+                let key = query_param(query, "key").ok_or_else(|| {
+                    ApiError::BadRequest("Missing the storage key".into())
+                })?;
+                let height = query_param(query, "height")
+                    .map(|height| {
+                        height.parse::<u64>().map_err(|err| {
+                            ApiError::BadRequest(format!(
+                                "Invalid height {}: {}",
+                                height, err
+                            ))
+                        })
+                    })
+                    .transpose()?;
+                self.query_storage(&key, height).await
+            }
+            (&Method::GET, ["events", kind, hash]) => {
+                let query = match *kind {
+                    "accepted" => TxEventQuery::Accepted(hash.to_string()),
+                    "applied" => TxEventQuery::Applied(hash.to_string()),
+                    _ => {
+                        return Err(ApiError::NotFound(format!(
+                            "Unknown event kind {}, expected \"accepted\" or \
+                             \"applied\"",
+                            kind
+                        )));
+                    }
+                };
+                let response = query_tx_response(&self.rpc_address, query)
+                    .await
+                    .map_err(|err| ApiError::NotFound(err.to_string()))?;
+                Ok(json_response(StatusCode::OK, &response))
+            }
+            _ => Err(ApiError::NotFound(format!("Unknown path {}", path))),
+        }
+    }
+
+    /// Broadcast a tx
+    async fn submit_tx(&self, body: &[u8]) -> Result<Response<Body>, ApiError> {
+        let SubmitTx { tx } = serde_json::from_slice(body)
+            .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+        let tx = hex::decode(tx)
+            .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+        let response = self
+            .client
+            .broadcast_tx_sync(tx.into())
+            .await
+            .map_err(|err| ApiError::Node(err.to_string()))?;
+        Ok(json_response(StatusCode::OK, &response))
+    }
+
+    /// Query the value at a storage key
+    async fn query_storage(
+        &self,
+        key: &str,
+        height: Option<u64>,
+    ) -> Result<Response<Body>, ApiError> {
+        let key = storage::Key::parse(key)
+            .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+        let height = height
+            .map(Height::try_from)
+            .transpose()
+            .map_err(|err| ApiError::BadRequest(err.to_string()))?;
+        let response = self
+            .client
+            .abci_query(Some(Path::value(key).into()), vec![], height, false)
+            .await
+            .map_err(|err| ApiError::Node(err.to_string()))?;
+        match response.code {
+            Code::Ok => {
+                let value = StorageValue {
+                    value: hex::encode(&response.value),
+                };
+                Ok(json_response(StatusCode::OK, &value))
+            }
+            Code::Err(1) => Err(ApiError::NotFound(response.info)),
+            Code::Err(_) => Err(ApiError::Node(response.info)),
+        }
+    }
+}
+
+/// Serve a request with the shared gateway
+async fn serve(
+    gateway: Arc<Gateway>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    Ok(gateway.handle(req).await)
+}
+
+/// Read the body of a request, failing as soon as it's known to be larger than
+/// the limit, either from its `Content-Length` or from the bytes read so far
+async fn read_body(mut body: Body, limit: usize) -> Result<Vec<u8>, ApiError> {
+    if body.size_hint().lower() > limit as u64 {
+        return Err(ApiError::PayloadTooLarge(limit));
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk =
+            chunk.map_err(|err| ApiError::BadRequest(err.to_string()))?;
+        if bytes.len() + chunk.len() > limit {
+            return Err(ApiError::PayloadTooLarge(limit));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Find the percent-decoded value of a parameter in a URI query
+fn query_param(query: Option<&str>, name: &str) -> Option<String> {
+    query?.split('&').find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        (key == name).then(|| percent_decode(value))
+    })
+}
+
+/// Decode the percent-encoded bytes of a URI component. Invalid escapes are
+/// left as they are.
+fn percent_decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| raw.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Add the CORS headers that allow the given origin, if it's allowed
+fn add_cors_headers(
+    allowed_origins: &[String],
+    origin: &str,
+    headers: &mut HeaderMap,
+) {
+    let allowed = if allowed_origins.iter().any(|allowed| allowed == "*") {
+        "*"
+    } else if allowed_origins.iter().any(|allowed| allowed == origin) {
+        origin
+    } else {
+        return;
+    };
+    if let Ok(allowed) = HeaderValue::from_str(allowed) {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allowed);
+        headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static(CORS_ALLOWED_METHODS),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            HeaderValue::from_static(CORS_ALLOWED_HEADERS),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_MAX_AGE,
+            HeaderValue::from_static(CORS_MAX_AGE),
+        );
+    }
+}
+
+/// A response with a JSON body
+fn json_response(status: StatusCode, body: &impl Serialize) -> Response<Body> {
+    let body = serde_json::to_vec(body).expect("Encoding JSON shouldn't fail");
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+/// A response with the error
+fn error_response(err: ApiError) -> Response<Body> {
+    let status = match &err {
+        ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+        ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+        ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+        ApiError::Node(_) => StatusCode::BAD_GATEWAY,
+    };
+    let body = ErrorResponse {
+        error: err.to_string(),
+    };
+    json_response(status, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cors_headers() {
+        let origin = "https://wallet.example";
+
+        let mut headers = HeaderMap::new();
+        add_cors_headers(&[], origin, &mut headers);
+        assert!(headers.is_empty());
+
+        let mut headers = HeaderMap::new();
+        add_cors_headers(
+            &["https://other.example".to_owned()],
+            origin,
+            &mut headers,
+        );
+        assert!(headers.is_empty());
+
+        let mut headers = HeaderMap::new();
+        add_cors_headers(&[origin.to_owned()], origin, &mut headers);
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], origin);
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_METHODS],
+            CORS_ALLOWED_METHODS
+        );
+
+        let mut headers = HeaderMap::new();
+        add_cors_headers(&["*".to_owned()], origin, &mut headers);
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    }
+
+    /// Test that a request body is only read up to the limit, whether its
+    /// length is known upfront or not
+    #[tokio::test]
+    async fn test_read_body_limit() {
+        let body = read_body(Body::from(vec![1; 10]), 10).await.unwrap();
+        assert_eq!(body, vec![1; 10]);
+
+        let res = read_body(Body::from(vec![1; 11]), 10).await;
+        assert!(matches!(res, Err(ApiError::PayloadTooLarge(10))));
+
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            for _ in 0..3 {
+                if sender.send_data(vec![1; 4].into()).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let res = read_body(body, 10).await;
+        assert!(matches!(res, Err(ApiError::PayloadTooLarge(10))));
+    }
+
+    #[test]
+    fn test_query_param() {
+        let query = Some("key=%23atest%2Fbalance&height=10&other=x%2");
+        assert_eq!(
+            query_param(query, "key").as_deref(),
+            Some("#atest/balance")
+        );
+        assert_eq!(query_param(query, "height").as_deref(), Some("10"));
+        assert_eq!(query_param(query, "other").as_deref(), Some("x%2"));
+        assert_eq!(query_param(query, "missing"), None);
+        assert_eq!(query_param(None, "height"), None);
+    }
+}
@@ -1,5 +1,6 @@
 mod broadcaster;
 pub mod events;
+mod gateway;
 pub mod protocol;
 pub mod rpc;
 mod shell;
@@ -32,6 +33,7 @@ use self::shims::abcipp_shim::AbciService;
 use crate::config::utils::num_of_threads;
 use crate::config::TendermintMode;
 use crate::node::ledger::broadcaster::Broadcaster;
+use crate::node::ledger::gateway::Gateway;
 use crate::node::ledger::shell::{Error, MempoolTxType, Shell};
 use crate::node::ledger::shims::abcipp_shim::AbcippShim;
 use crate::node::ledger::shims::abcipp_shim_types::shim::{Request, Response};
@@ -200,6 +202,11 @@ pub fn reset(config: config::Ledger) -> Result<(), shell::Error> {
     shell::reset(config)
 }
 
+/// Deletes the unreachable data from the database and compacts its files
+pub fn compact(config: config::Ledger) -> Result<(), shell::Error> {
+    shell::compact(config)
+}
+
 /// Runs three concurrent tasks: A tendermint node, a shell which contains an
 /// ABCI, server for talking to the tendermint node, and a broadcaster so that
 /// the ledger may submit txs to the chain. All must be alive for correct
@@ -298,6 +305,7 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         .try_into()
         .expect("expected RFC3339 genesis_time");
     let tendermint_config = config.tendermint.clone();
+    let archive = config.shell.archive;
 
     // Channel for signalling shut down from the shell or from Tendermint
     let (abort_send, abort_recv) =
@@ -326,6 +334,7 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
             genesis_time,
             ledger_address,
             tendermint_config,
+            archive,
             tm_abort_recv,
         )
         .map_err(Error::Tendermint)
@@ -370,6 +379,25 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         None
     };
 
+    // Start the optional HTTP gateway. It doesn't abort the node when it
+    // fails, as the node can function without it.
+    let gateway = config.gateway.clone().map(|gateway_config| {
+        // Channel for signalling shut down to the gateway
+        let (gw_abort_send, gw_abort_recv) =
+            tokio::sync::oneshot::channel::<()>();
+        let gateway =
+            Gateway::new(gateway_config, config.tendermint.rpc_address);
+        (
+            tokio::spawn(async move {
+                if let Err(err) = gateway.run(gw_abort_recv).await {
+                    tracing::error!("{}", err);
+                }
+                tracing::info!("Gateway is no longer running.");
+            }),
+            gw_abort_send,
+        )
+    });
+
     // Construct our ABCI application.
     let ledger_address = config.shell.ledger_address;
     let (shell, abci_service) = AbcippShim::new(
@@ -430,6 +458,14 @@ async fn run_aux(config: config::Ledger, wasm_dir: PathBuf) {
         }
     }
 
+    if let Some((gateway, gw_abort_send)) = gateway {
+        // request the gateway shutdown
+        let _ = gw_abort_send.send(());
+        if let Err(err) = gateway.await {
+            tracing::error!("Gateway error: {}", err);
+        }
+    }
+
     let res = match broadcaster {
         Some((broadcaster, bc_abort_send)) => {
             // request the broadcaster shutdown
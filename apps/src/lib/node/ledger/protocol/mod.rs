@@ -1,5 +1,5 @@
 //! The ledger's protocol
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::panic;
 
 use anoma::ledger::eth_bridge::vp::EthBridge;
@@ -12,12 +12,14 @@ use anoma::ledger::pos::{self, PosVP};
 use anoma::ledger::storage::write_log::WriteLog;
 use anoma::ledger::storage::{DBIter, Storage, StorageHasher, DB};
 use anoma::ledger::treasury::TreasuryVp;
-use anoma::proto::{self, Tx};
+use anoma::proto::{self, SignedTxData, Tx};
 use anoma::types::address::{Address, InternalAddress};
 use anoma::types::storage;
+use anoma::types::transaction::batch::{BatchOpResult, TxBatch};
 use anoma::types::transaction::{DecryptedTx, TxResult, TxType, VpsResult};
 use anoma::vm::wasm::{TxCache, VpCache};
 use anoma::vm::{self, wasm, WasmCacheAccess};
+use borsh::BorshDeserialize;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use thiserror::Error;
 
@@ -55,6 +57,12 @@ pub enum Error {
     EthBridgeNativeVpError(anoma::ledger::eth_bridge::vp::Error),
     #[error("Access to an internal address {0} is forbidden")]
     AccessForbidden(InternalAddress),
+    #[error("A batch transaction must be signed")]
+    BatchMissingSignature,
+    #[error("Error decoding a batch transaction: {0}")]
+    BatchDecodingError(std::io::Error),
+    #[error("Operation {0} of the batch failed: {1}")]
+    BatchOpError(usize, Box<Error>),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -87,14 +95,26 @@ where
     match tx {
         TxType::Raw(_) => Err(Error::TxTypeError),
         TxType::Decrypted(DecryptedTx::Decrypted(tx)) => {
-            let verifiers = execute_tx(
-                &tx,
-                storage,
-                block_gas_meter,
-                write_log,
-                vp_wasm_cache,
-                tx_wasm_cache,
-            )?;
+            let (verifiers, batch_results) = if TxBatch::is_batch(&tx) {
+                execute_batch(
+                    &tx,
+                    storage,
+                    block_gas_meter,
+                    write_log,
+                    vp_wasm_cache,
+                    tx_wasm_cache,
+                )?
+            } else {
+                let verifiers = execute_tx(
+                    &tx,
+                    storage,
+                    block_gas_meter,
+                    write_log,
+                    vp_wasm_cache,
+                    tx_wasm_cache,
+                )?;
+                (verifiers, vec![])
+            };
 
             let vps_result = check_vps(
                 &tx,
@@ -118,6 +138,7 @@ where
                 vps_result,
                 initialized_accounts,
                 ibc_event,
+                batch_results,
             })
         }
         _ => {
@@ -163,6 +184,87 @@ where
     .map_err(Error::TxRunnerError)
 }
 
+/// Execute the operations of a batch transaction one after another on the
+/// same write log. Returns the verifiers requested by all the operations and
+/// the result of each operation. If any of the operations fails, the whole
+/// batch fails.
+fn execute_batch<D, H, CA>(
+    tx: &Tx,
+    storage: &Storage<D, H>,
+    gas_meter: &mut BlockGasMeter,
+    write_log: &mut WriteLog,
+    vp_wasm_cache: &mut VpCache<CA>,
+    tx_wasm_cache: &mut TxCache<CA>,
+) -> Result<(BTreeSet<Address>, Vec<BatchOpResult>)>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    CA: 'static + WasmCacheAccess + Sync,
+{
+    let signed = tx
+        .data
+        .as_ref()
+        .ok_or(Error::BatchMissingSignature)
+        .and_then(|data| {
+            SignedTxData::try_from_slice(data)
+                .map_err(|_| Error::BatchMissingSignature)
+        })?;
+    let batch = signed
+        .data
+        .as_ref()
+        .map(|data| TxBatch::try_from_slice(data))
+        .transpose()
+        .map_err(Error::BatchDecodingError)?
+        .unwrap_or_default();
+
+    let mut verifiers = BTreeSet::new();
+    let mut results = Vec::with_capacity(batch.ops.len());
+    for (index, op) in batch.ops.iter().enumerate() {
+        let op_tx = op.to_tx(tx, &signed.sig);
+        let initial_gas = gas_meter.get_current_transaction_gas();
+        // Snapshot the modifications made by the previous operations to find
+        // the keys modified by this one
+        let initial_keys = write_log.get_keys();
+        let initial_modifications: HashMap<_, _> = initial_keys
+            .iter()
+            .filter_map(|key| {
+                write_log.read(key).0.cloned().map(|value| (key, value))
+            })
+            .collect();
+        let initial_accounts = write_log.get_initialized_accounts();
+
+        let op_verifiers = execute_tx(
+            &op_tx,
+            storage,
+            gas_meter,
+            write_log,
+            vp_wasm_cache,
+            tx_wasm_cache,
+        )
+        .map_err(|err| Error::BatchOpError(index, Box::new(err)))?;
+        verifiers.extend(op_verifiers);
+
+        let changed_keys = write_log
+            .get_keys()
+            .into_iter()
+            .filter(|key| {
+                initial_modifications.get(key) != write_log.read(key).0
+            })
+            .collect();
+        let initialized_accounts = write_log
+            .get_initialized_accounts()
+            .into_iter()
+            .filter(|address| !initial_accounts.contains(address))
+            .collect();
+        results.push(BatchOpResult {
+            gas_used: gas_meter.get_current_transaction_gas() - initial_gas,
+            changed_keys,
+            initialized_accounts,
+        });
+    }
+    Ok((verifiers, results))
+}
+
 /// Check the acceptance of a transaction by validity predicates
 fn check_vps<D, H, CA>(
     tx: &Tx,
@@ -2,27 +2,237 @@
 
 use std::fmt::Display;
 use std::str::FromStr;
+use std::time::Duration;
 
-use anoma::types::address::Address;
-use anoma::types::storage;
+use anoma::ibc::core::ics24_host::identifier::{ChannelId, PortId};
+use anoma::ledger::parameters::EpochDuration;
+use anoma::types::address::{self, Address};
+use anoma::types::hash::Hash;
+use anoma::types::storage::{self, BlockHeight, Epoch};
+use anoma::types::time::DateTimeUtc;
+use anoma::types::token;
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(not(feature = "ABCI"))]
 use tendermint::abci::Path as AbciPath;
 #[cfg(feature = "ABCI")]
 use tendermint_stable::abci::Path as AbciPath;
 use thiserror::Error;
 
-/// RPC query path
+/// RPC query path. The first segment of the path selects the module that
+/// handles the query and the rest of the path is parsed by the module.
 #[derive(Debug, Clone)]
 pub enum Path {
-    /// Dry run a transaction
+    /// Queries handled by the shell (`/shell/...`)
+    Shell(ShellPath),
+    /// Queries of the storage (`/store/...`)
+    Store(StorePath),
+    /// Queries of the proof-of-stake system (`/pos/...`)
+    Pos(PosPath),
+    /// Queries of the events and the history of the chain (`/events/...`)
+    Events(EventsPath),
+    /// Queries of the IBC state (`/ibc/...`)
+    Ibc(IbcPath),
+    /// Queries of the NFT collections (`/nft/...`)
+    Nft(NftPath),
+}
+
+/// RPC query path of the shell module
+#[derive(Debug, Clone)]
+pub enum ShellPath {
+    /// Dry run a transaction. Responds with the borsh encoded
+    /// `TxResult` of the transaction.
     DryRunTx,
-    /// Epoch of the last committed block
+    /// Epoch of the last committed block. Responds with the borsh encoded
+    /// `Epoch`.
     Epoch,
-    /// Read a storage value with exact storage key
+}
+
+/// RPC query path of the proof-of-stake module
+#[derive(Debug, Clone)]
+pub enum PosPath {
+    /// The unbonds of a source that are waiting to be withdrawn. Responds
+    /// with the borsh encoded [`UnbondsInfo`].
+    Unbonds(Address),
+}
+
+/// RPC query path of the events module
+#[derive(Debug, Clone)]
+pub enum EventsPath {
+    /// The historical queries served by the node. Responds with the borsh
+    /// encoded [`HistoryInfo`].
+    History,
+}
+
+/// RPC query path of the IBC module
+#[derive(Debug, Clone)]
+pub enum IbcPath {
+    /// The state of a packet on this chain, as either its source or its
+    /// destination. Responds with the borsh encoded [`PacketStateInfo`].
+    PacketState {
+        /// The port of the channel end on this chain
+        port_id: PortId,
+        /// The channel end on this chain
+        channel_id: ChannelId,
+        /// The sequence number of the packet
+        sequence: u64,
+    },
+}
+
+/// RPC query path of the NFT module
+#[derive(Debug, Clone)]
+pub enum NftPath {
+    /// The NFT collections. Responds with the borsh encoded
+    /// `Vec<NftCollectionInfo>`, ordered by the collections' addresses.
+    Collections,
+    /// A page of the token IDs of an NFT collection. Responds with the borsh
+    /// encoded [`NftTokensPage`].
+    Tokens {
+        /// The address of the collection
+        nft: Address,
+        /// The number of tokens to skip
+        offset: u64,
+        /// The maximum number of tokens in the page, which is capped at
+        /// [`MAX_NFT_TOKENS_PAGE_LEN`]
+        limit: u64,
+    },
+}
+
+/// The maximum number of token IDs in a page of an NFT collection
+pub const MAX_NFT_TOKENS_PAGE_LEN: u64 = 1000;
+
+/// An NFT collection
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct NftCollectionInfo {
+    /// The address of the collection
+    pub address: Address,
+    /// The creator of the collection
+    pub creator: Address,
+    /// The SHA-256 hash of the collection's validity predicate, if any
+    pub vp_hash: Option<Hash>,
+    /// The number of tokens minted in the collection, including the burnt
+    /// ones
+    pub token_count: u64,
+}
+
+/// A page of the token IDs of an NFT collection
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct NftTokensPage {
+    /// The token IDs in the page, in ascending order
+    pub token_ids: Vec<u64>,
+    /// The number of tokens minted in the collection
+    pub total: u64,
+}
+
+impl NftTokensPage {
+    /// The offset of the next page, if there's any token after this page
+    pub fn next_offset(&self, offset: u64) -> Option<u64> {
+        let next = offset + self.token_ids.len() as u64;
+        (!self.token_ids.is_empty() && next < self.total).then(|| next)
+    }
+}
+
+/// The state of an IBC packet on a chain, which is either the source or the
+/// destination of the packet
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct PacketStateInfo {
+    /// Whether the packet has been sent from this chain, i.e. its sequence is
+    /// below the next sequence to send on the channel
+    pub sent: bool,
+    /// Whether the commitment of the packet is stored on this chain. It's
+    /// deleted once the packet is acknowledged or timed out.
+    pub committed: bool,
+    /// Whether the receipt of the packet is stored on this chain
+    pub received: bool,
+    /// Whether the acknowledgement of the packet is stored on this chain
+    pub acknowledged: bool,
+}
+
+/// The historical queries served by a node
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct HistoryInfo {
+    /// Whether the node runs in the archive mode
+    pub archive: bool,
+    /// The lowest block height at which the node serves queries with merkle
+    /// proofs. The values are served at any height from the stored diffs.
+    pub oldest_height: BlockHeight,
+}
+
+impl HistoryInfo {
+    /// Check if the node serves queries at the given height, with or without
+    /// merkle proofs
+    pub fn serves_height(&self, height: BlockHeight, prove: bool) -> bool {
+        !prove || height >= self.oldest_height
+    }
+}
+
+/// The unbonds of a source, with the epoch and its duration to estimate when
+/// they can be withdrawn
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct UnbondsInfo {
+    /// Epoch of the last committed block
+    pub epoch: Epoch,
+    /// Minimum time at which the next epoch may start
+    pub next_epoch_min_start_time: DateTimeUtc,
+    /// The epoch duration parameter
+    pub epoch_duration: EpochDuration,
+    /// The unbonds, ordered by the epoch from which they can be withdrawn
+    pub unbonds: Vec<UnbondEntry>,
+}
+
+/// Tokens unbonded from a validator
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct UnbondEntry {
+    /// The validator from which the tokens have been unbonded
+    pub validator: Address,
+    /// The epoch from which the bond was active
+    pub bond_start: Epoch,
+    /// The first epoch in which the tokens can be withdrawn
+    pub withdrawable_epoch: Epoch,
+    /// The unbonded amount, after slashing
+    pub amount: token::Amount,
+}
+
+impl UnbondsInfo {
+    /// The number of epochs until the unbond can be withdrawn, if it cannot
+    /// be withdrawn in the current epoch
+    pub fn epochs_until_withdrawable(&self, unbond: &UnbondEntry) -> u64 {
+        unbond.withdrawable_epoch.0.saturating_sub(self.epoch.0)
+    }
+
+    /// Estimate the time from `now` until the unbond can be withdrawn. The
+    /// current epoch ends at its minimum start time of the next epoch and the
+    /// following epochs are assumed to last their minimum duration.
+    pub fn time_until_withdrawable(
+        &self,
+        unbond: &UnbondEntry,
+        now: DateTimeUtc,
+    ) -> Duration {
+        match self.epochs_until_withdrawable(unbond) {
+            0 => Duration::ZERO,
+            epochs => {
+                let current_epoch_end = (self.next_epoch_min_start_time.0
+                    - now.0)
+                    .to_std()
+                    .unwrap_or_default();
+                let min_duration: Duration =
+                    self.epoch_duration.min_duration.into();
+                current_epoch_end + min_duration * (epochs - 1) as u32
+            }
+        }
+    }
+}
+
+/// RPC query path of the storage module
+#[derive(Debug, Clone)]
+pub enum StorePath {
+    /// Read a storage value with exact storage key. Responds with the raw
+    /// bytes of the value.
     Value(storage::Key),
-    /// Read a range of storage values with a matching key prefix
+    /// Read a range of storage values with a matching key prefix. Responds
+    /// with the borsh encoded `Vec<PrefixValue>`.
     Prefix(storage::Key),
-    /// Check if the given storage key exists
+    /// Check if the given storage key exists. Responds with the borsh
+    /// encoded `bool`.
     HasKey(storage::Key),
 }
 
@@ -34,52 +244,258 @@ pub struct BalanceQuery {
     token: Option<Address>,
 }
 
+const SHELL_MODULE: &str = "shell";
+const STORE_MODULE: &str = "store";
+const POS_MODULE: &str = "pos";
+const EVENTS_MODULE: &str = "events";
+const IBC_MODULE: &str = "ibc";
+const NFT_MODULE: &str = "nft";
+
 const DRY_RUN_TX_PATH: &str = "dry_run_tx";
 const EPOCH_PATH: &str = "epoch";
+const HISTORY_PATH: &str = "history";
+const UNBONDS_PATH: &str = "unbonds";
+const PACKET_STATE_PATH: &str = "packet_state";
+const NFT_COLLECTIONS_PATH: &str = "collections";
+const NFT_TOKENS_PATH: &str = "tokens";
 const VALUE_PREFIX: &str = "value";
 const PREFIX_PREFIX: &str = "prefix";
 const HAS_KEY_PREFIX: &str = "has_key";
 
+impl Path {
+    /// Dry run a transaction
+    pub fn dry_run_tx() -> Self {
+        Self::Shell(ShellPath::DryRunTx)
+    }
+
+    /// Epoch of the last committed block
+    pub fn epoch() -> Self {
+        Self::Shell(ShellPath::Epoch)
+    }
+
+    /// The historical queries served by the node
+    pub fn history() -> Self {
+        Self::Events(EventsPath::History)
+    }
+
+    /// The unbonds of a source that are waiting to be withdrawn
+    pub fn unbonds(source: Address) -> Self {
+        Self::Pos(PosPath::Unbonds(source))
+    }
+
+    /// The state of an IBC packet on this chain
+    pub fn packet_state(
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequence: u64,
+    ) -> Self {
+        Self::Ibc(IbcPath::PacketState {
+            port_id,
+            channel_id,
+            sequence,
+        })
+    }
+
+    /// The NFT collections
+    pub fn nft_collections() -> Self {
+        Self::Nft(NftPath::Collections)
+    }
+
+    /// A page of the token IDs of an NFT collection
+    pub fn nft_tokens(nft: Address, offset: u64, limit: u64) -> Self {
+        Self::Nft(NftPath::Tokens { nft, offset, limit })
+    }
+
+    /// Read a storage value with exact storage key
+    pub fn value(key: storage::Key) -> Self {
+        Self::Store(StorePath::Value(key))
+    }
+
+    /// Read a range of storage values with a matching key prefix
+    pub fn prefix(key: storage::Key) -> Self {
+        Self::Store(StorePath::Prefix(key))
+    }
+
+    /// Check if the given storage key exists
+    pub fn has_key(key: storage::Key) -> Self {
+        Self::Store(StorePath::HasKey(key))
+    }
+}
+
 impl Display for Path {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Path::DryRunTx => write!(f, "{}", DRY_RUN_TX_PATH),
-            Path::Epoch => write!(f, "{}", EPOCH_PATH),
-            Path::Value(storage_key) => {
-                write!(f, "{}/{}", VALUE_PREFIX, storage_key)
+            Path::Shell(path) => write!(f, "/{}/{}", SHELL_MODULE, path),
+            Path::Store(path) => write!(f, "/{}/{}", STORE_MODULE, path),
+            Path::Pos(path) => write!(f, "/{}/{}", POS_MODULE, path),
+            Path::Events(path) => write!(f, "/{}/{}", EVENTS_MODULE, path),
+            Path::Ibc(path) => write!(f, "/{}/{}", IBC_MODULE, path),
+            Path::Nft(path) => write!(f, "/{}/{}", NFT_MODULE, path),
+        }
+    }
+}
+
+impl FromStr for Path {
+    type Err = PathParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s.strip_prefix('/').unwrap_or(s);
+        match path.split_once('/') {
+            Some((SHELL_MODULE, sub_path)) => {
+                ShellPath::from_str(sub_path).map(Self::Shell)
             }
-            Path::Prefix(storage_key) => {
-                write!(f, "{}/{}", PREFIX_PREFIX, storage_key)
+            Some((STORE_MODULE, sub_path)) => {
+                StorePath::from_str(sub_path).map(Self::Store)
             }
-            Path::HasKey(storage_key) => {
-                write!(f, "{}/{}", HAS_KEY_PREFIX, storage_key)
+            Some((POS_MODULE, sub_path)) => {
+                PosPath::from_str(sub_path).map(Self::Pos)
+            }
+            Some((EVENTS_MODULE, sub_path)) => {
+                EventsPath::from_str(sub_path).map(Self::Events)
+            }
+            Some((IBC_MODULE, sub_path)) => {
+                IbcPath::from_str(sub_path).map(Self::Ibc)
+            }
+            Some((NFT_MODULE, sub_path)) => {
+                NftPath::from_str(sub_path).map(Self::Nft)
             }
+            _ => Err(PathParseError::InvalidPath(s.to_string())),
         }
     }
 }
 
-impl FromStr for Path {
+impl Display for ShellPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShellPath::DryRunTx => write!(f, "{}", DRY_RUN_TX_PATH),
+            ShellPath::Epoch => write!(f, "{}", EPOCH_PATH),
+        }
+    }
+}
+
+impl FromStr for ShellPath {
     type Err = PathParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             DRY_RUN_TX_PATH => Ok(Self::DryRunTx),
             EPOCH_PATH => Ok(Self::Epoch),
+            _ => Err(PathParseError::InvalidPath(s.to_string())),
+        }
+    }
+}
+
+impl Display for PosPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PosPath::Unbonds(source) => {
+                write!(f, "{}/{}", UNBONDS_PATH, source.encode())
+            }
+        }
+    }
+}
+
+impl FromStr for PosPath {
+    type Err = PathParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((UNBONDS_PATH, source)) => Address::decode(source)
+                .map(Self::Unbonds)
+                .map_err(PathParseError::InvalidAddress),
+            _ => Err(PathParseError::InvalidPath(s.to_string())),
+        }
+    }
+}
+
+impl Display for EventsPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventsPath::History => write!(f, "{}", HISTORY_PATH),
+        }
+    }
+}
+
+impl FromStr for EventsPath {
+    type Err = PathParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            HISTORY_PATH => Ok(Self::History),
+            _ => Err(PathParseError::InvalidPath(s.to_string())),
+        }
+    }
+}
+
+impl Display for IbcPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IbcPath::PacketState {
+                port_id,
+                channel_id,
+                sequence,
+            } => write!(
+                f,
+                "{}/{}/{}/{}",
+                PACKET_STATE_PATH, port_id, channel_id, sequence
+            ),
+        }
+    }
+}
+
+impl FromStr for IbcPath {
+    type Err = PathParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PathParseError::InvalidPath(s.to_string());
+        match s.split('/').collect::<Vec<_>>()[..] {
+            [PACKET_STATE_PATH, port_id, channel_id, sequence] => {
+                Ok(Self::PacketState {
+                    port_id: port_id.parse().map_err(|_| invalid())?,
+                    channel_id: channel_id.parse().map_err(|_| invalid())?,
+                    sequence: sequence.parse().map_err(|_| invalid())?,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Display for NftPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NftPath::Collections => write!(f, "{}", NFT_COLLECTIONS_PATH),
+            NftPath::Tokens { nft, offset, limit } => write!(
+                f,
+                "{}/{}/{}/{}",
+                NFT_TOKENS_PATH,
+                nft.encode(),
+                offset,
+                limit
+            ),
+        }
+    }
+}
+
+impl FromStr for NftPath {
+    type Err = PathParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            NFT_COLLECTIONS_PATH => Ok(Self::Collections),
             _ => match s.split_once('/') {
-                Some((VALUE_PREFIX, storage_key)) => {
-                    let key = storage::Key::parse(storage_key)
-                        .map_err(PathParseError::InvalidStorageKey)?;
-                    Ok(Self::Value(key))
-                }
-                Some((PREFIX_PREFIX, storage_key)) => {
-                    let key = storage::Key::parse(storage_key)
-                        .map_err(PathParseError::InvalidStorageKey)?;
-                    Ok(Self::Prefix(key))
-                }
-                Some((HAS_KEY_PREFIX, storage_key)) => {
-                    let key = storage::Key::parse(storage_key)
-                        .map_err(PathParseError::InvalidStorageKey)?;
-                    Ok(Self::HasKey(key))
+                Some((NFT_TOKENS_PATH, page)) => {
+                    let invalid = || PathParseError::InvalidPath(s.to_string());
+                    let (nft, offset, limit) =
+                        match page.split('/').collect::<Vec<_>>()[..] {
+                            [nft, offset, limit] => (nft, offset, limit),
+                            _ => return Err(invalid()),
+                        };
+                    let nft = Address::decode(nft)
+                        .map_err(PathParseError::InvalidAddress)?;
+                    let offset = offset.parse().map_err(|_| invalid())?;
+                    let limit = limit.parse().map_err(|_| invalid())?;
+                    Ok(Self::Tokens { nft, offset, limit })
                 }
                 _ => Err(PathParseError::InvalidPath(s.to_string())),
             },
@@ -87,6 +503,47 @@ impl FromStr for Path {
     }
 }
 
+impl Display for StorePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorePath::Value(storage_key) => {
+                write!(f, "{}/{}", VALUE_PREFIX, storage_key)
+            }
+            StorePath::Prefix(storage_key) => {
+                write!(f, "{}/{}", PREFIX_PREFIX, storage_key)
+            }
+            StorePath::HasKey(storage_key) => {
+                write!(f, "{}/{}", HAS_KEY_PREFIX, storage_key)
+            }
+        }
+    }
+}
+
+impl FromStr for StorePath {
+    type Err = PathParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((VALUE_PREFIX, storage_key)) => {
+                let key = storage::Key::parse(storage_key)
+                    .map_err(PathParseError::InvalidStorageKey)?;
+                Ok(Self::Value(key))
+            }
+            Some((PREFIX_PREFIX, storage_key)) => {
+                let key = storage::Key::parse(storage_key)
+                    .map_err(PathParseError::InvalidStorageKey)?;
+                Ok(Self::Prefix(key))
+            }
+            Some((HAS_KEY_PREFIX, storage_key)) => {
+                let key = storage::Key::parse(storage_key)
+                    .map_err(PathParseError::InvalidStorageKey)?;
+                Ok(Self::HasKey(key))
+            }
+            _ => Err(PathParseError::InvalidPath(s.to_string())),
+        }
+    }
+}
+
 impl From<Path> for AbciPath {
     fn from(path: Path) -> Self {
         let path = path.to_string();
@@ -103,4 +560,152 @@ pub enum PathParseError {
     InvalidPath(String),
     #[error("Invalid storage key: {0}")]
     InvalidStorageKey(storage::Error),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(address::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::time::DurationSecs;
+
+    use super::*;
+
+    #[test]
+    fn test_path_round_trip() {
+        let key = storage::Key::parse("some/storage/key").unwrap();
+        let paths = [
+            Path::dry_run_tx(),
+            Path::epoch(),
+            Path::history(),
+            Path::unbonds(address::testing::established_address_1()),
+            Path::packet_state(
+                PortId::from_str("transfer").unwrap(),
+                ChannelId::new(0),
+                1,
+            ),
+            Path::nft_collections(),
+            Path::nft_tokens(address::testing::established_address_2(), 10, 5),
+            Path::value(key.clone()),
+            Path::prefix(key.clone()),
+            Path::has_key(key),
+        ];
+        for path in paths {
+            let encoded = path.to_string();
+            let decoded = Path::from_str(&encoded).unwrap();
+            assert_eq!(decoded.to_string(), encoded);
+        }
+    }
+
+    #[test]
+    fn test_path_routing() {
+        assert!(matches!(
+            Path::from_str("/shell/epoch"),
+            Ok(Path::Shell(ShellPath::Epoch))
+        ));
+        assert!(matches!(
+            Path::from_str("/store/has_key/key"),
+            Ok(Path::Store(StorePath::HasKey(_)))
+        ));
+        assert!(matches!(
+            Path::from_str(&format!(
+                "/pos/unbonds/{}",
+                address::testing::established_address_1()
+            )),
+            Ok(Path::Pos(PosPath::Unbonds(_)))
+        ));
+        assert!(matches!(
+            Path::from_str("/pos/unbonds/not-an-address"),
+            Err(PathParseError::InvalidAddress(_))
+        ));
+        assert!(matches!(
+            Path::from_str("/events/history"),
+            Ok(Path::Events(EventsPath::History))
+        ));
+        assert!(matches!(
+            Path::from_str("/ibc/packet_state/transfer/channel-0/3"),
+            Ok(Path::Ibc(IbcPath::PacketState { sequence: 3, .. }))
+        ));
+        assert!(matches!(
+            Path::from_str("/ibc/packet_state/transfer/channel-0"),
+            Err(PathParseError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            Path::from_str(&format!(
+                "/nft/tokens/{}/20/10",
+                address::testing::established_address_1()
+            )),
+            Ok(Path::Nft(NftPath::Tokens {
+                offset: 20,
+                limit: 10,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            Path::from_str(&format!(
+                "/nft/tokens/{}/20",
+                address::testing::established_address_1()
+            )),
+            Err(PathParseError::InvalidPath(_))
+        ));
+        // The queries are only served by their own modules
+        assert!(Path::from_str("/shell/history").is_err());
+        assert!(Path::from_str("/store/epoch").is_err());
+        assert!(Path::from_str("/unknown/epoch").is_err());
+    }
+
+    /// Test that the offset of the next page of NFT tokens is found until
+    /// the last page
+    #[test]
+    fn test_nft_tokens_next_offset() {
+        let page = |token_ids: Vec<u64>| NftTokensPage {
+            token_ids,
+            total: 5,
+        };
+        assert_eq!(page(vec![1, 2]).next_offset(0), Some(2));
+        assert_eq!(page(vec![3, 4]).next_offset(2), Some(4));
+        assert_eq!(page(vec![5]).next_offset(4), None);
+        assert_eq!(page(vec![]).next_offset(10), None);
+    }
+
+    /// Test the estimate of the time until unbonds can be withdrawn
+    #[test]
+    fn test_time_until_withdrawable() {
+        let now = DateTimeUtc::now();
+        let info = UnbondsInfo {
+            epoch: Epoch(5),
+            next_epoch_min_start_time: now + DurationSecs(60 * 60),
+            epoch_duration: EpochDuration {
+                min_num_of_blocks: 10,
+                min_duration: DurationSecs(7 * 60 * 60),
+            },
+            unbonds: vec![],
+        };
+        let unbond = |withdrawable_epoch| UnbondEntry {
+            validator: address::testing::established_address_1(),
+            bond_start: Epoch(0),
+            withdrawable_epoch: Epoch(withdrawable_epoch),
+            amount: token::Amount::whole(10),
+        };
+
+        for withdrawable_epoch in [2, 5] {
+            let unbond = unbond(withdrawable_epoch);
+            assert_eq!(info.epochs_until_withdrawable(&unbond), 0);
+            assert_eq!(
+                info.time_until_withdrawable(&unbond, now),
+                Duration::ZERO
+            );
+        }
+        let unbond = unbond(8);
+        assert_eq!(info.epochs_until_withdrawable(&unbond), 3);
+        assert_eq!(
+            info.time_until_withdrawable(&unbond, now),
+            Duration::from_secs(15 * 60 * 60)
+        );
+        // The current epoch may already be past its minimum end
+        let later = now + DurationSecs(2 * 60 * 60);
+        assert_eq!(
+            info.time_until_withdrawable(&unbond, later),
+            Duration::from_secs(14 * 60 * 60)
+        );
+    }
 }
@@ -11,16 +11,16 @@ use anoma::types::address::{xan as m1t, Address};
 use anoma::types::governance::TallyResult;
 use anoma::types::storage::{BlockHash, Epoch, Header};
 #[cfg(not(feature = "ABCI"))]
-use tendermint_proto::abci::Misbehavior as Evidence;
+use tendermint_proto::abci::{Misbehavior as Evidence, VoteInfo};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::crypto::PublicKey as TendermintPublicKey;
 #[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::Evidence;
+use tendermint_proto_abci::abci::{Evidence, VoteInfo};
 #[cfg(feature = "ABCI")]
 use tendermint_proto_abci::crypto::PublicKey as TendermintPublicKey;
 
 use super::*;
-use crate::node::ledger::events::EventType;
+use crate::node::ledger::events::{EventType, ValidatorSetChange};
 
 impl<D, H> Shell<D, H>
 where
@@ -53,8 +53,13 @@ where
     ) -> Result<shim::response::FinalizeBlock> {
         let mut response = shim::response::FinalizeBlock::default();
         // begin the next block and check if a new epoch began
-        let (height, new_epoch) =
-            self.update_state(req.header, req.hash, req.byzantine_validators);
+        let (height, new_epoch) = self.update_state(
+            req.header,
+            req.hash,
+            req.byzantine_validators,
+            req.votes,
+            req.proposer_address,
+        );
 
         if new_epoch {
             for id in std::mem::take(&mut self.proposal_data) {
@@ -379,6 +384,20 @@ where
                                 );
                             }
                         }
+                        if !result.batch_results.is_empty() {
+                            match serde_json::to_string(&result.batch_results) {
+                                Ok(batch_results) => {
+                                    tx_event["batch_results"] = batch_results;
+                                }
+                                Err(err) => {
+                                    tracing::error!(
+                                        "Failed to serialize the batch \
+                                         results: {}",
+                                        err
+                                    );
+                                }
+                            }
+                        }
                     } else {
                         tracing::info!(
                             "some VPs rejected transaction {} storage \
@@ -424,7 +443,12 @@ where
 
     /// Sets the metadata necessary for a new block, including
     /// the hash, height, validator changes, and evidence of
-    /// byzantine behavior. Applies slashes if necessary.
+    /// byzantine behavior. Applies slashes if necessary and
+    /// records the validators' signatures of the last block,
+    /// jailing the validators that have been offline. The
+    /// block rewards are accrued to the proposer and the
+    /// signers and on a new epoch, the rewards accrued in the
+    /// last epoch are distributed to the bonds.
     /// Returns a bool indicating if a new epoch began and
     /// the height of the new block.
     fn update_state(
@@ -432,6 +456,8 @@ where
         header: Header,
         hash: BlockHash,
         byzantine_validators: Vec<Evidence>,
+        votes: Vec<VoteInfo>,
+        proposer_address: Vec<u8>,
     ) -> (BlockHeight, bool) {
         let height = self.storage.last_height + 1;
 
@@ -458,15 +484,62 @@ where
             .update_epoch(height, time)
             .expect("Must be able to update epoch");
 
+        if new_epoch {
+            // The rewards of the last epoch must be distributed before any
+            // rewards are accrued in the new epoch
+            let last_epoch = self.storage.block.epoch - 1_u64;
+            self.storage.distribute_rewards(last_epoch);
+        }
         self.slash();
+        let voters = self.record_block_signatures(votes);
+        self.record_block_rewards(proposer_address, voters);
         (height, new_epoch)
     }
 
     /// If a new epoch begins, we update the response to include
-    /// changes to the validator sets and consensus parameters
+    /// changes to the validator sets and consensus parameters. The changes
+    /// of the active validator set are also emitted as events.
     fn update_epoch(&self, response: &mut shim::response::FinalizeBlock) {
         // Apply validator set update
         let (current_epoch, _gas) = self.storage.get_current_epoch();
+        let validator_sets = self.storage.read_validator_set();
+        if let Some(current) = validator_sets.get(current_epoch) {
+            let previous = current_epoch
+                .0
+                .checked_sub(1)
+                .and_then(|epoch| validator_sets.get(Epoch(epoch)));
+            for change in ValidatorSetChange::find(previous, current) {
+                response.events.push(change.to_event(current_epoch));
+            }
+        }
+        // Report the active validators whose self-bonds are below the
+        // minimum. This check is only informational, the validators are
+        // neither jailed nor removed from the validator set. A self-bond can
+        // only get below the minimum from a slash or when the minimum is
+        // raised by governance, because the PoS VP rejects bonds and unbonds
+        // that would lower it.
+        let min_self_bond = token::Amount::from(
+            self.storage.read_pos_params().min_validator_self_bond,
+        );
+        for (validator, self_bond) in
+            self.storage.validators_below_min_self_bond(current_epoch)
+        {
+            tracing::warn!(
+                "The self-bond {} of the active validator {} is below the \
+                 minimum self-bond {}",
+                token::Amount::from(self_bond),
+                validator,
+                min_self_bond
+            );
+            response
+                .events
+                .push(Event::new_self_bond_below_minimum_event(
+                    &validator,
+                    self_bond.into(),
+                    min_self_bond,
+                    current_epoch,
+                ));
+        }
         // TODO ABCI validator updates on block H affects the validator set
         // on block H+2, do we need to update a block earlier?
         self.storage.validator_set_update(current_epoch, |update| {
@@ -145,6 +145,7 @@ where
             vp_code_path,
             vp_sha256,
             balances,
+            vesting,
         } in genesis.token_accounts
         {
             let vp_code = vp_code_cache
@@ -180,6 +181,15 @@ where
                     )
                     .unwrap();
             }
+
+            for (owner, schedule) in vesting {
+                self.storage
+                    .write(
+                        &token::vesting_key(&address, &owner),
+                        schedule.try_to_vec().unwrap(),
+                    )
+                    .unwrap();
+            }
         }
 
         // Initialize genesis validator accounts
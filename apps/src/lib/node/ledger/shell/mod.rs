@@ -25,6 +25,7 @@ use anoma::ledger::pos::anoma_proof_of_stake::types::{
     ActiveValidator, ValidatorSetUpdate,
 };
 use anoma::ledger::pos::anoma_proof_of_stake::PosBase;
+use anoma::ledger::storage::read_cache::ReadCache;
 use anoma::ledger::storage::write_log::WriteLog;
 use anoma::ledger::storage::{
     DBIter, Sha256Hasher, Storage, StorageHasher, DB,
@@ -50,7 +51,7 @@ use tendermint_proto::abci::response_verify_vote_extension::VerifyStatus;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::abci::{
     Misbehavior as Evidence, MisbehaviorType as EvidenceType,
-    RequestPrepareProposal, ValidatorUpdate,
+    RequestPrepareProposal, ValidatorUpdate, VoteInfo,
 };
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::crypto::public_key;
@@ -59,7 +60,9 @@ use tendermint_proto::types::ConsensusParams;
 #[cfg(feature = "ABCI")]
 use tendermint_proto_abci::abci::ConsensusParams;
 #[cfg(feature = "ABCI")]
-use tendermint_proto_abci::abci::{Evidence, EvidenceType, ValidatorUpdate};
+use tendermint_proto_abci::abci::{
+    Evidence, EvidenceType, ValidatorUpdate, VoteInfo,
+};
 #[cfg(feature = "ABCI")]
 use tendermint_proto_abci::crypto::public_key;
 use thiserror::Error;
@@ -106,6 +109,8 @@ pub enum Error {
     Broadcaster(tokio::sync::mpsc::error::TryRecvError),
     #[error("Error executing proposal {0}: {1}")]
     BadProposal(u64, String),
+    #[error("Storage error: {0}")]
+    Storage(anoma::ledger::storage::Error),
 }
 
 impl From<Error> for TxResult {
@@ -158,6 +163,24 @@ pub fn reset(config: config::Ledger) -> Result<()> {
     Ok(())
 }
 
+pub fn compact(config: config::Ledger) -> Result<()> {
+    let db_path = config.db_dir();
+    let mut storage =
+        storage::PersistentStorage::open(db_path, config.chain_id, None);
+    storage.load_last_state().map_err(Error::Storage)?;
+    if config.shell.archive {
+        tracing::info!("Archive mode, the merkle trees are not deleted");
+    } else if let Some(height) =
+        storage.prune_merkle_tree_stores().map_err(Error::Storage)?
+    {
+        tracing::info!("Deleted the merkle trees below height {}", height);
+    }
+    tracing::info!("Compacting the DB...");
+    storage.db.compact();
+    tracing::info!("Finished compacting the DB");
+    Ok(())
+}
+
 #[derive(Debug)]
 #[allow(dead_code, clippy::large_enum_variant)]
 pub(super) enum ShellMode {
@@ -221,6 +244,8 @@ where
     tx_wasm_cache: TxCache<WasmCacheRwAccess>,
     /// Proposal execution tracking
     pub proposal_data: HashSet<u64>,
+    /// Whether the node runs in the archive mode
+    archive: bool,
 }
 
 impl<D, H> Shell<D, H>
@@ -242,12 +267,16 @@ where
         let db_path = config.shell.db_dir(&chain_id);
         let base_dir = config.shell.base_dir;
         let mode = config.tendermint.tendermint_mode;
+        let archive = config.shell.archive;
         if !Path::new(&base_dir).is_dir() {
             std::fs::create_dir(&base_dir)
                 .expect("Creating directory for Anoma should not fail");
         }
         // load last state from storage
         let mut storage = Storage::open(db_path, chain_id.clone(), db_cache);
+        if let Some(read_cache_bytes) = config.shell.storage_read_cache_bytes {
+            storage.read_cache = ReadCache::new(read_cache_bytes as usize);
+        }
         storage
             .load_last_state()
             .map_err(|e| {
@@ -327,6 +356,7 @@ where
                 tx_wasm_compilation_cache as usize,
             ),
             proposal_data: HashSet::new(),
+            archive,
         }
     }
 
@@ -517,6 +547,93 @@ where
         }
     }
 
+    /// Record the active validators' signatures of the last block from their
+    /// votes. The validators that have missed signing too many consecutive
+    /// blocks are jailed. Returns the addresses of the validators that have
+    /// signed the last block.
+    fn record_block_signatures(
+        &mut self,
+        votes: Vec<VoteInfo>,
+    ) -> Vec<address::Address> {
+        let current_epoch = self.storage.block.epoch;
+        let mut signatures = vec![];
+        for vote in votes {
+            let validator_raw_hash = match vote.validator {
+                Some(validator) => match String::from_utf8(validator.address) {
+                    Ok(raw_hash) => raw_hash,
+                    Err(err) => {
+                        tracing::error!(
+                            "Vote failed to decode validator address from \
+                             utf-8 with {}",
+                            err
+                        );
+                        continue;
+                    }
+                },
+                None => {
+                    tracing::error!("Vote without a validator");
+                    continue;
+                }
+            };
+            match self
+                .storage
+                .read_validator_address_raw_hash(&validator_raw_hash)
+            {
+                Some(validator) => {
+                    signatures.push((validator, vote.signed_last_block))
+                }
+                None => tracing::error!(
+                    "Cannot find validator's address from raw hash {}",
+                    validator_raw_hash
+                ),
+            }
+        }
+        let voters = signatures
+            .iter()
+            .filter(|(_, signed)| *signed)
+            .map(|(validator, _)| validator.clone())
+            .collect();
+        let max_missed_blocks =
+            self.storage.read_pos_params().max_missed_blocks;
+        for validator in self
+            .storage
+            .record_block_signatures(current_epoch, signatures)
+        {
+            tracing::warn!(
+                "Jailing {} in epoch {} for missing more than {} consecutive \
+                 blocks",
+                validator,
+                current_epoch,
+                max_missed_blocks
+            );
+        }
+        voters
+    }
+
+    /// Accrue the block rewards to the proposer of this block and to the
+    /// validators that have signed the last block. The rewards are distributed
+    /// to the validators' bonds at the end of the epoch.
+    fn record_block_rewards(
+        &mut self,
+        proposer_address: Vec<u8>,
+        voters: Vec<address::Address>,
+    ) {
+        let proposer = match String::from_utf8(proposer_address) {
+            Ok(raw_hash) => {
+                self.storage.read_validator_address_raw_hash(&raw_hash)
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Failed to decode block proposer address from utf-8 with \
+                     {}",
+                    err
+                );
+                None
+            }
+        };
+        self.storage.record_block_rewards(proposer.as_ref(), voters);
+    }
+
     #[cfg(not(feature = "ABCI"))]
     /// INVARIANT: This method must be stateless.
     pub fn extend_vote(
@@ -552,6 +669,17 @@ where
                 e
             )
         });
+        // An archive node keeps the merkle trees of all the blocks to serve
+        // the proofs at any height
+        if !self.archive {
+            if let Err(e) = self.storage.prune_merkle_tree_stores() {
+                tracing::error!(
+                    "Encountered a storage error while pruning the merkle \
+                     trees {:?}",
+                    e
+                )
+            }
+        }
 
         let root = self.storage.merkle_root();
         tracing::info!(
@@ -573,7 +701,15 @@ where
     ) -> response::CheckTx {
         let mut response = response::CheckTx::default();
         match Tx::try_from(tx_bytes).map_err(Error::TxDecoding) {
-            Ok(_) => response.log = String::from("Mempool validation passed"),
+            Ok(tx) => match self.check_tx_expiration(&tx) {
+                Ok(()) => {
+                    response.log = String::from("Mempool validation passed")
+                }
+                Err(msg) => {
+                    response.code = 1;
+                    response.log = msg;
+                }
+            },
             Err(msg) => {
                 response.code = 1;
                 response.log = msg.to_string();
@@ -582,6 +718,31 @@ where
         response
     }
 
+    /// Check that a tx has not expired at the time of the last committed
+    /// block. The time of the block that is being processed is not known
+    /// before the block is finalized, so the last committed block is used to
+    /// keep the check deterministic.
+    fn check_tx_expiration(&self, tx: &Tx) -> std::result::Result<(), String> {
+        let expiration = match tx.expiration {
+            Some(expiration) => expiration,
+            None => return Ok(()),
+        };
+        let last_block_time = self
+            .storage
+            .db
+            .read_block_header(self.storage.last_height)
+            .ok()
+            .flatten()
+            .map(|header| header.time);
+        match last_block_time {
+            Some(time) if tx.is_expired(time) => Err(format!(
+                "The transaction expired at {}",
+                expiration.to_rfc3339()
+            )),
+            _ => Ok(()),
+        }
+    }
+
     /// Simulate validation and application of a transaction.
     fn dry_run_tx(&self, tx_bytes: &[u8]) -> response::Query {
         let mut response = response::Query::default();
@@ -603,7 +764,12 @@ where
                 )
                 .map_err(Error::TxApply)
                 {
-                    Ok(result) => response.info = result.to_string(),
+                    Ok(result) => {
+                        response.info = result.to_string();
+                        response.value = result
+                            .try_to_vec()
+                            .expect("Encoding a tx result shouldn't fail");
+                    }
                     Err(error) => {
                         response.code = 1;
                         response.log = format!("{}", error);
@@ -871,6 +1037,8 @@ mod test_utils {
                     next_validators_hash: Hash([0; 32]),
                 },
                 byzantine_validators: vec![],
+                votes: vec![],
+                proposer_address: vec![],
                 txs: vec![],
             }
         }
@@ -957,4 +1125,38 @@ mod test_utils {
         );
         assert!(!shell.storage.tx_queue.is_empty());
     }
+
+    /// Test that the merkle trees of the blocks from the epochs that are no
+    /// longer tracked are deleted on commit, unless the node runs in the
+    /// archive mode
+    #[test]
+    fn test_commit_prunes_merkle_trees() {
+        for archive in [false, true] {
+            let (mut shell, _) = TestShell::new();
+            shell.archive = archive;
+            for height in 1..=3 {
+                shell
+                    .storage
+                    .begin_block(BlockHash::default(), BlockHeight(height))
+                    .unwrap();
+                shell.commit();
+            }
+            // Forget about the epochs that ended before height 2
+            let pred_epochs = &mut shell.storage.block.pred_epochs;
+            pred_epochs.new_epoch(BlockHeight(2), 1);
+            pred_epochs.new_epoch(BlockHeight(3), 1);
+            shell
+                .storage
+                .begin_block(BlockHash::default(), BlockHeight(4))
+                .unwrap();
+            shell.commit();
+
+            let stores =
+                shell.storage.db.read_merkle_tree_stores(BlockHeight(1));
+            assert_eq!(stores.unwrap().is_some(), archive);
+            let stores =
+                shell.storage.db.read_merkle_tree_stores(BlockHeight(2));
+            assert!(stores.unwrap().is_some());
+        }
+    }
 }
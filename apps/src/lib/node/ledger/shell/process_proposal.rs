@@ -67,6 +67,8 @@ where
     ///   4: Invalid order of decrypted txs
     ///   5. More decrypted txs than expected
     ///
+    /// An expired tx is rejected as an invalid tx.
+    ///
     /// INVARIANT: Any changes applied in this method must be reverted if the
     /// proposal is rejected (unless we can simply overwrite them in the
     /// next block).
@@ -81,6 +83,12 @@ where
                 };
             }
         };
+        if let Err(info) = self.check_tx_expiration(&tx) {
+            return TxResult {
+                code: ErrorCodes::InvalidTx.into(),
+                info,
+            };
+        }
         // TODO: This should not be hardcoded
         let privkey = <EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
 
@@ -402,6 +410,7 @@ mod test_process_proposal {
                     .expect("Test failed"),
                 ),
                 timestamp,
+                expiration: None,
             }
         } else {
             panic!("Test failed");
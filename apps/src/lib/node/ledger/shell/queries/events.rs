@@ -0,0 +1,71 @@
+//! Shell methods for the queries of the events and the history of the chain
+
+use super::super::*;
+use super::encoded_response;
+use crate::node::ledger::response;
+use crate::node::ledger::rpc::{EventsPath, HistoryInfo};
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Handle a query of the events module
+    pub(super) fn query_events(&self, path: EventsPath) -> response::Query {
+        match path {
+            EventsPath::History => encoded_response(Ok(self.history_info())),
+        }
+    }
+
+    /// The historical queries served by the node. Every node serves the
+    /// values at any height. An archive node also serves their proofs at any
+    /// height. Other nodes prune the merkle trees of the blocks from the
+    /// epochs that are no longer tracked, so they only serve proofs from the
+    /// first block of the tracked epochs.
+    pub fn history_info(&self) -> HistoryInfo {
+        let oldest_height = if self.archive {
+            BlockHeight::default()
+        } else {
+            self.storage
+                .block
+                .pred_epochs
+                .first_block_height()
+                .unwrap_or_default()
+        };
+        HistoryInfo {
+            archive: self.archive,
+            oldest_height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::ledger::shell::test_utils::TestShell;
+
+    /// Test that a node that isn't in the archive mode serves the values at
+    /// any height, but only serves the proofs from the first block of the
+    /// tracked epochs
+    #[test]
+    fn test_history_info() {
+        let (mut shell, _) = TestShell::new();
+        let history = shell.history_info();
+        assert!(!history.archive);
+        assert!(history.serves_height(BlockHeight(0), true));
+
+        let pred_epochs = &mut shell.storage.block.pred_epochs;
+        pred_epochs.new_epoch(BlockHeight(10), 1);
+        pred_epochs.new_epoch(BlockHeight(20), 1);
+        let history = shell.history_info();
+        assert_eq!(history.oldest_height, BlockHeight(10));
+        assert!(history.serves_height(BlockHeight(9), false));
+        assert!(!history.serves_height(BlockHeight(9), true));
+        assert!(history.serves_height(BlockHeight(10), true));
+
+        shell.archive = true;
+        let history = shell.history_info();
+        assert!(history.archive);
+        assert!(history.serves_height(BlockHeight(0), true));
+    }
+}
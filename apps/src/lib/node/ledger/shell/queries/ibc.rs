@@ -0,0 +1,135 @@
+//! Shell methods for the queries of the IBC state
+
+use std::convert::TryInto;
+
+use anoma::ibc::core::ics04_channel::packet::Sequence;
+use anoma::ibc::core::ics24_host::identifier::{
+    ChannelId, PortChannelId, PortId,
+};
+use anoma::ledger::ibc::storage::{
+    ack_key, commitment_key, next_sequence_send_key, receipt_key,
+};
+
+use super::super::*;
+use super::encoded_response;
+use crate::node::ledger::response;
+use crate::node::ledger::rpc::{IbcPath, PacketStateInfo};
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Handle a query of the IBC module
+    pub(super) fn query_ibc(&self, path: IbcPath) -> response::Query {
+        match path {
+            IbcPath::PacketState {
+                port_id,
+                channel_id,
+                sequence,
+            } => encoded_response(self.packet_state(
+                &port_id,
+                &channel_id,
+                sequence,
+            )),
+        }
+    }
+
+    /// The state of a packet on this chain, as either its source or its
+    /// destination
+    pub fn packet_state(
+        &self,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        sequence: u64,
+    ) -> std::result::Result<PacketStateInfo, String> {
+        let port_channel_id = PortChannelId {
+            port_id: port_id.clone(),
+            channel_id: channel_id.clone(),
+        };
+        // The sequence numbers start at 1 and are incremented by every packet
+        // sent on the channel
+        let next_sequence_send_key = next_sequence_send_key(&port_channel_id);
+        let next_sequence_send = match self
+            .storage
+            .read(&next_sequence_send_key)
+            .map_err(|err| format!("Storage error: {}", err))?
+        {
+            (Some(bytes), _gas) => {
+                let bytes: [u8; 8] = bytes.try_into().map_err(|_| {
+                    format!("Invalid next sequence {}", next_sequence_send_key)
+                })?;
+                u64::from_be_bytes(bytes)
+            }
+            (None, _gas) => 1,
+        };
+        let has_key = |key: Key| {
+            self.storage
+                .has_key(&key)
+                .map(|(has_key, _gas)| has_key)
+                .map_err(|err| format!("Storage error: {}", err))
+        };
+        let seq = Sequence::from(sequence);
+        Ok(PacketStateInfo {
+            sent: sequence != 0 && sequence < next_sequence_send,
+            committed: has_key(commitment_key(port_id, channel_id, seq))?,
+            received: has_key(receipt_key(port_id, channel_id, seq))?,
+            acknowledged: has_key(ack_key(port_id, channel_id, seq))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::TestShell;
+
+    /// Test that the state of a packet is found from its sequence and the
+    /// keys of its commitment, receipt and acknowledgement
+    #[test]
+    fn test_packet_state() {
+        let (mut shell, _) = TestShell::new();
+        let port_id = PortId::from_str("transfer").unwrap();
+        let channel_id = ChannelId::new(0);
+        let port_channel_id = PortChannelId {
+            port_id: port_id.clone(),
+            channel_id: channel_id.clone(),
+        };
+
+        let state = shell.packet_state(&port_id, &channel_id, 1).unwrap();
+        assert!(!state.sent && !state.committed);
+
+        shell
+            .storage
+            .write(
+                &next_sequence_send_key(&port_channel_id),
+                3_u64.to_be_bytes(),
+            )
+            .unwrap();
+        shell
+            .storage
+            .write(
+                &commitment_key(&port_id, &channel_id, Sequence::from(2)),
+                vec![0],
+            )
+            .unwrap();
+        let state = shell.packet_state(&port_id, &channel_id, 1).unwrap();
+        assert!(state.sent && !state.committed);
+        let state = shell.packet_state(&port_id, &channel_id, 2).unwrap();
+        assert!(state.sent && state.committed);
+        let state = shell.packet_state(&port_id, &channel_id, 3).unwrap();
+        assert!(!state.sent);
+
+        shell
+            .storage
+            .write(
+                &receipt_key(&port_id, &channel_id, Sequence::from(1)),
+                vec![0],
+            )
+            .unwrap();
+        let state = shell.packet_state(&port_id, &channel_id, 1).unwrap();
+        assert!(state.received && !state.acknowledged);
+    }
+}
@@ -0,0 +1,214 @@
+//! Shell methods for querying state. The queries are routed by the first
+//! segment of their path to the module that handles them.
+use std::cmp::max;
+
+use anoma::ledger::parameters::EpochDuration;
+use anoma::ledger::pos::PosParams;
+use anoma::types::address::Address;
+use anoma::types::key;
+use anoma::types::key::dkg_session_keys::DkgPublicKey;
+use anoma::types::token::{self, Amount};
+use borsh::{BorshDeserialize, BorshSerialize};
+use ferveo_common::TendermintValidator;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_proto::google::protobuf;
+#[cfg(not(feature = "ABCI"))]
+use tendermint_proto::types::EvidenceParams;
+#[cfg(feature = "ABCI")]
+use tendermint_proto_abci::google::protobuf;
+#[cfg(feature = "ABCI")]
+use tendermint_proto_abci::types::EvidenceParams;
+
+use super::*;
+use crate::node::ledger::response;
+use crate::node::ledger::rpc::{Path, ShellPath};
+
+mod events;
+mod ibc;
+mod nft;
+mod pos;
+mod store;
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Uses `path` in the query to forward the request to the
+    /// module that handles it and returns the result (which may be
+    /// the default if `path` is not a supported string.
+    /// INVARIANT: This method must be stateless.
+    pub fn query(&self, query: request::Query) -> response::Query {
+        let height = match query.height {
+            0 => self.storage.get_block_height().0,
+            1.. => BlockHeight(query.height as u64),
+            _ => {
+                return response::Query {
+                    code: 1,
+                    info: format!(
+                        "The query height is invalid: {}",
+                        query.height
+                    ),
+                    ..Default::default()
+                };
+            }
+        };
+        match Path::from_str(&query.path) {
+            Ok(Path::Shell(path)) => match path {
+                ShellPath::DryRunTx => self.dry_run_tx(&query.data),
+                ShellPath::Epoch => {
+                    let (epoch, _gas) = self.storage.get_last_epoch();
+                    let value = anoma::ledger::storage::types::encode(&epoch);
+                    response::Query {
+                        value,
+                        ..Default::default()
+                    }
+                }
+            },
+            Ok(Path::Store(path)) => {
+                // The latest height is always served
+                let history = self.history_info();
+                if query.height != 0
+                    && !history.serves_height(height, query.prove)
+                {
+                    return response::Query {
+                        code: 1,
+                        info: format!(
+                            "The node doesn't serve proofs below height {}, \
+                             use an archive node for historical proofs",
+                            history.oldest_height
+                        ),
+                        ..Default::default()
+                    };
+                }
+                self.query_store(path, height, query.prove)
+            }
+            Ok(Path::Pos(path)) => self.query_pos(path),
+            Ok(Path::Events(path)) => self.query_events(path),
+            Ok(Path::Ibc(path)) => self.query_ibc(path),
+            Ok(Path::Nft(path)) => self.query_nft(path),
+            Err(err) => response::Query {
+                code: 1,
+                info: format!("RPC error: {}", err),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Simple helper function for the ledger to get balances
+    /// of the specified token at the specified address
+    pub fn get_balance(
+        &self,
+        token: &Address,
+        owner: &Address,
+    ) -> std::result::Result<Amount, String> {
+        let height = self.storage.get_block_height().0;
+        let query_resp = self.read_storage_value(
+            &token::balance_key(token, owner),
+            height,
+            false,
+        );
+        if query_resp.code != 0 {
+            Err(format!(
+                "Unable to read token {} balance of the given address {}",
+                token, owner
+            ))
+        } else {
+            BorshDeserialize::try_from_slice(&query_resp.value[..]).map_err(
+                |_| {
+                    "Unable to deserialize the balance of the given address"
+                        .into()
+                },
+            )
+        }
+    }
+
+    pub fn get_evidence_params(
+        &self,
+        epoch_duration: &EpochDuration,
+        pos_params: &PosParams,
+    ) -> EvidenceParams {
+        // Minimum number of epochs before tokens are unbonded and can be
+        // withdrawn
+        let len_before_unbonded = max(pos_params.unbonding_len as i64 - 1, 0);
+        let max_age_num_blocks: i64 =
+            epoch_duration.min_num_of_blocks as i64 * len_before_unbonded;
+        let min_duration_secs = epoch_duration.min_duration.0 as i64;
+        let max_age_duration = Some(protobuf::Duration {
+            seconds: min_duration_secs * len_before_unbonded,
+            nanos: 0,
+        });
+        EvidenceParams {
+            max_age_num_blocks,
+            max_age_duration,
+            ..EvidenceParams::default()
+        }
+    }
+
+    /// Lookup data about a validator from their protocol signing key
+    #[allow(dead_code)]
+    pub fn get_validator_from_protocol_pk(
+        &self,
+        pk: &key::common::PublicKey,
+    ) -> Option<TendermintValidator<EllipticCurve>> {
+        let pk_bytes = pk
+            .try_to_vec()
+            .expect("Serializing public key should not fail");
+        // get the current epoch
+        let (current_epoch, _) = self.storage.get_current_epoch();
+        // get the active validator set
+        self.storage
+            .read_validator_set()
+            .get(current_epoch)
+            .expect("Validators for the next epoch should be known")
+            .active
+            .iter()
+            .find(|validator| {
+                let pk_key = key::protocol_pk_key(&validator.address);
+                match self.storage.read(&pk_key) {
+                    Ok((Some(bytes), _)) => bytes == pk_bytes,
+                    _ => false,
+                }
+            })
+            .map(|validator| {
+                let dkg_key =
+                    key::dkg_session_keys::dkg_pk_key(&validator.address);
+                let bytes = self
+                    .storage
+                    .read(&dkg_key)
+                    .expect("Validator should have public dkg key")
+                    .0
+                    .expect("Validator should have public dkg key");
+                let dkg_publickey =
+                    &<DkgPublicKey as BorshDeserialize>::deserialize(
+                        &mut bytes.as_ref(),
+                    )
+                    .expect(
+                        "DKG public key in storage should be deserializable",
+                    );
+                TendermintValidator {
+                    power: validator.voting_power.into(),
+                    address: validator.address.to_string(),
+                    public_key: dkg_publickey.into(),
+                }
+            })
+    }
+}
+
+/// The response to a query with the borsh encoded value, or with the error
+/// message in its `info`
+fn encoded_response<T: BorshSerialize>(
+    result: std::result::Result<T, String>,
+) -> response::Query {
+    match result {
+        Ok(value) => response::Query {
+            value: value.try_to_vec().unwrap(),
+            ..Default::default()
+        },
+        Err(info) => response::Query {
+            code: 1,
+            info,
+            ..Default::default()
+        },
+    }
+}
@@ -0,0 +1,174 @@
+//! Shell methods for the queries of the NFT collections
+
+use std::collections::BTreeMap;
+
+use anoma::types::address::Address;
+use anoma::types::hash::Hash;
+use anoma::types::nft;
+use borsh::BorshDeserialize;
+
+use super::super::*;
+use super::encoded_response;
+use crate::node::ledger::response;
+use crate::node::ledger::rpc::{
+    NftCollectionInfo, NftPath, NftTokensPage, MAX_NFT_TOKENS_PAGE_LEN,
+};
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Handle a query of the NFT module
+    pub(super) fn query_nft(&self, path: NftPath) -> response::Query {
+        match path {
+            NftPath::Collections => encoded_response(self.nft_collections()),
+            NftPath::Tokens { nft, offset, limit } => {
+                encoded_response(self.nft_tokens_page(&nft, offset, limit))
+            }
+        }
+    }
+
+    /// The NFT collections, i.e. the addresses with an NFT creator, with the
+    /// number of their tokens. There is no index of the collections in
+    /// storage, so the whole storage is scanned.
+    pub fn nft_collections(
+        &self,
+    ) -> std::result::Result<Vec<NftCollectionInfo>, String> {
+        let (iter, _gas) = self.storage.iter_prefix(&Key { segments: vec![] });
+        let mut creators: BTreeMap<Address, Address> = BTreeMap::new();
+        let mut token_counts: BTreeMap<Address, u64> = BTreeMap::new();
+        for (key, value, _gas) in iter {
+            let key = Key::parse(key)
+                .map_err(|err| format!("Invalid storage key: {}", err))?;
+            let address = match nft::is_nft_key(&key) {
+                Some(address) => address.clone(),
+                None => continue,
+            };
+            if nft::is_nft_creator_key(&key, &address).is_some() {
+                let creator =
+                    Address::try_from_slice(&value).map_err(|err| {
+                        format!("Failed to decode the creator {}: {}", key, err)
+                    })?;
+                creators.insert(address, creator);
+            } else if nft::is_nft_current_owner_key(&key, &address).is_some() {
+                *token_counts.entry(address).or_default() += 1;
+            }
+        }
+        let mut collections = vec![];
+        for (address, creator) in creators {
+            let (vp, _gas) = self
+                .storage
+                .read(&Key::validity_predicate(&address))
+                .map_err(|err| {
+                    format!("Failed to read the VP of {}: {}", address, err)
+                })?;
+            collections.push(NftCollectionInfo {
+                token_count: token_counts
+                    .get(&address)
+                    .copied()
+                    .unwrap_or_default(),
+                vp_hash: vp.map(Hash::sha256),
+                address,
+                creator,
+            });
+        }
+        Ok(collections)
+    }
+
+    /// A page of the token IDs of an NFT collection, in ascending order
+    pub fn nft_tokens_page(
+        &self,
+        nft_address: &Address,
+        offset: u64,
+        limit: u64,
+    ) -> std::result::Result<NftTokensPage, String> {
+        let prefix = nft::_nft_prefix(nft_address);
+        let (iter, _gas) = self.storage.iter_prefix(&prefix);
+        let mut token_ids = vec![];
+        for (key, _value, _gas) in iter {
+            let key = Key::parse(key)
+                .map_err(|err| format!("Invalid storage key: {}", err))?;
+            if let Some((_, id)) =
+                nft::is_nft_current_owner_key(&key, nft_address)
+            {
+                let id: u64 = id.parse().map_err(|err| {
+                    format!("Invalid token ID {}: {}", id, err)
+                })?;
+                token_ids.push(id);
+            }
+        }
+        // The IDs are sorted as numbers rather than as storage keys
+        token_ids.sort_unstable();
+        let total = token_ids.len() as u64;
+        let token_ids = token_ids
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit.min(MAX_NFT_TOKENS_PAGE_LEN) as usize)
+            .collect();
+        Ok(NftTokensPage { token_ids, total })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::address::testing::{
+        established_address_1, established_address_2, established_address_3,
+    };
+    use borsh::BorshSerialize;
+
+    use super::*;
+    use crate::node::ledger::shell::test_utils::TestShell;
+
+    /// Test that the collections are found with the number of their tokens
+    /// and that their token IDs are paginated
+    #[test]
+    fn test_nft_queries() {
+        let (mut shell, _) = TestShell::new();
+        let nft_address = established_address_1();
+        let creator = established_address_2();
+        let owner = established_address_3();
+        let vp = b"vp code".to_vec();
+        shell
+            .storage
+            .write(&Key::validity_predicate(&nft_address), vp.clone())
+            .unwrap();
+        shell
+            .storage
+            .write(
+                &nft::get_creator_key(&nft_address),
+                creator.try_to_vec().unwrap(),
+            )
+            .unwrap();
+        for id in 1..=12_u64 {
+            let key =
+                nft::get_token_current_owner_key(&nft_address, &id.to_string());
+            shell
+                .storage
+                .write(&key, owner.try_to_vec().unwrap())
+                .unwrap();
+        }
+
+        let collections = shell.nft_collections().unwrap();
+        assert_eq!(
+            collections,
+            vec![NftCollectionInfo {
+                address: nft_address.clone(),
+                creator,
+                vp_hash: Some(Hash::sha256(&vp)),
+                token_count: 12,
+            }]
+        );
+
+        let page = shell.nft_tokens_page(&nft_address, 0, 5).unwrap();
+        assert_eq!(page.token_ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(page.total, 12);
+        let page = shell.nft_tokens_page(&nft_address, 10, 5).unwrap();
+        assert_eq!(page.token_ids, vec![11, 12]);
+        assert_eq!(page.next_offset(10), None);
+
+        // The owner isn't a collection
+        let page = shell.nft_tokens_page(&owner, 0, 5).unwrap();
+        assert_eq!(page.total, 0);
+    }
+}
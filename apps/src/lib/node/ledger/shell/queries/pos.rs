@@ -0,0 +1,81 @@
+//! Shell methods for the queries of the proof-of-stake system
+
+use anoma::types::address::Address;
+use anoma::types::token;
+
+use super::super::*;
+use super::encoded_response;
+use crate::node::ledger::response;
+use crate::node::ledger::rpc::{PosPath, UnbondEntry, UnbondsInfo};
+
+impl<D, H> Shell<D, H>
+where
+    D: DB + for<'iter> DBIter<'iter> + Sync + 'static,
+    H: StorageHasher + Sync + 'static,
+{
+    /// Handle a query of the proof-of-stake module
+    pub(super) fn query_pos(&self, path: PosPath) -> response::Query {
+        match path {
+            PosPath::Unbonds(source) => {
+                encoded_response(self.unbonds_info(&source))
+            }
+        }
+    }
+
+    /// The unbonds of the given source that are waiting to be withdrawn, with
+    /// the slashes of their validators applied to the unbonded amounts.
+    pub fn unbonds_info(
+        &self,
+        source: &Address,
+    ) -> std::result::Result<UnbondsInfo, String> {
+        let (epoch_duration, _gas) =
+            parameters::read_epoch_parameter(&self.storage).map_err(|err| {
+                format!("Failed to read the epoch duration: {}", err)
+            })?;
+        let prefix = pos::unbonds_for_source_prefix(source);
+        let (iter, _gas) = self.storage.iter_prefix(&prefix);
+        let mut unbonds = vec![];
+        for (key, value, _gas) in iter {
+            let key = Key::parse(key)
+                .map_err(|err| format!("Invalid storage key: {}", err))?;
+            let bond_id = match pos::is_unbond_key(&key) {
+                Some(bond_id) if &bond_id.source == source => bond_id,
+                _ => continue,
+            };
+            let deltas =
+                pos::Unbonds::try_from_slice(&value).map_err(|err| {
+                    format!("Failed to decode the unbonds {}: {}", key, err)
+                })?;
+            let slashes =
+                self.storage.read_validator_slashes(&bond_id.validator);
+            for unbond in deltas.iter() {
+                for (&(start, end), &amount) in &unbond.deltas {
+                    let withdrawable_epoch = end + 1_u64;
+                    let mut amount = amount;
+                    for slash in &slashes {
+                        if slash.epoch >= start
+                            && slash.epoch < withdrawable_epoch
+                        {
+                            let raw_amount: u64 = amount.into();
+                            amount -=
+                                token::Amount::from(slash.rate * raw_amount);
+                        }
+                    }
+                    unbonds.push(UnbondEntry {
+                        validator: bond_id.validator.clone(),
+                        bond_start: start.into(),
+                        withdrawable_epoch: withdrawable_epoch.into(),
+                        amount,
+                    });
+                }
+            }
+        }
+        unbonds.sort_by_key(|unbond| unbond.withdrawable_epoch);
+        Ok(UnbondsInfo {
+            epoch: self.storage.last_epoch,
+            next_epoch_min_start_time: self.storage.next_epoch_min_start_time,
+            epoch_duration,
+            unbonds,
+        })
+    }
+}
@@ -210,10 +210,12 @@ pub mod shim {
         use anoma::types::time::DateTimeUtc;
         #[cfg(not(feature = "ABCI"))]
         use tendermint_proto::abci::{
-            Misbehavior as Evidence, RequestFinalizeBlock,
+            Misbehavior as Evidence, RequestFinalizeBlock, VoteInfo,
         };
         #[cfg(feature = "ABCI")]
-        use tendermint_proto_abci::abci::{Evidence, RequestBeginBlock};
+        use tendermint_proto_abci::abci::{
+            Evidence, RequestBeginBlock, VoteInfo,
+        };
 
         pub struct VerifyHeader;
 
@@ -231,6 +233,10 @@ pub mod shim {
             pub hash: BlockHash,
             pub header: Header,
             pub byzantine_validators: Vec<Evidence>,
+            /// The validators' votes on the last block
+            pub votes: Vec<VoteInfo>,
+            /// The address of the validator that proposed this block
+            pub proposer_address: Vec<u8>,
             pub txs: Vec<ProcessedTx>,
         }
 
@@ -248,6 +254,11 @@ pub mod shim {
                         .unwrap(),
                     },
                     byzantine_validators: req.byzantine_validators,
+                    votes: req
+                        .decided_last_commit
+                        .map(|commit| commit.votes)
+                        .unwrap_or_default(),
+                    proposer_address: req.proposer_address,
                     txs: vec![],
                 }
             }
@@ -269,6 +280,11 @@ pub mod shim {
                         .unwrap(),
                     },
                     byzantine_validators: req.byzantine_validators,
+                    votes: req
+                        .last_commit_info
+                        .map(|info| info.votes)
+                        .unwrap_or_default(),
+                    proposer_address: header.proposer_address,
                     txs: vec![],
                 }
             }
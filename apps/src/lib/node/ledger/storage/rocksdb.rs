@@ -13,6 +13,8 @@
 //! - `pred`: predecessor values of the top-level keys of the same name
 //!   - `next_epoch_min_start_height`
 //!   - `next_epoch_min_start_time`
+//! - `tree_pruned_height`: the merkle trees of the blocks below this height
+//!   have been deleted
 //! - `subspace`: accounts sub-spaces
 //!   - `{address}/{dyn}`: any byte data associated with accounts
 //! - `h`: for each block at height `h`:
@@ -54,6 +56,10 @@ use crate::config::utils::num_of_threads;
 const ENV_VAR_ROCKSDB_COMPACTION_THREADS: &str =
     "ANOMA_ROCKSDB_COMPACTION_THREADS";
 
+/// The maximum number of blocks whose merkle trees are deleted in a single
+/// write batch
+const PRUNE_BATCH_NUM_BLOCKS: u64 = 1000;
+
 /// RocksDB handle
 #[derive(Debug)]
 pub struct RocksDB(rocksdb::DB);
@@ -237,6 +243,11 @@ impl RocksDB {
             .write_opt(batch, &write_opts)
             .map_err(|e| Error::DBError(e.into_string()))
     }
+
+    /// Compact the whole DB to reclaim the space of the deleted data.
+    pub fn compact(&self) {
+        self.0.compact_range::<&[u8], &[u8]>(None, None);
+    }
 }
 
 impl DB for RocksDB {
@@ -602,6 +613,51 @@ impl DB for RocksDB {
         Ok(Some(merkle_tree_stores))
     }
 
+    fn prune_merkle_tree_stores(&mut self, before: BlockHeight) -> Result<()> {
+        let mut pruned_height: BlockHeight = match self
+            .0
+            .get("tree_pruned_height")
+            .map_err(|e| Error::DBError(e.into_string()))?
+        {
+            Some(bytes) => types::decode(bytes).map_err(Error::CodingError)?,
+            None => BlockHeight::default(),
+        };
+        while pruned_height < before {
+            let batch_end = BlockHeight(std::cmp::min(
+                pruned_height.0 + PRUNE_BATCH_NUM_BLOCKS,
+                before.0,
+            ));
+            let mut batch = WriteBatch::default();
+            for height in pruned_height.0..batch_end.0 {
+                let tree_key = Key::from(BlockHeight(height).to_db_key())
+                    .push(&"tree".to_owned())
+                    .map_err(Error::KeyError)?;
+                for st in StoreType::iter() {
+                    let prefix_key = tree_key
+                        .push(&st.to_string())
+                        .map_err(Error::KeyError)?;
+                    for suffix in ["root", "store"] {
+                        let key = prefix_key
+                            .push(&suffix.to_owned())
+                            .map_err(Error::KeyError)?;
+                        batch.delete(key.to_string());
+                    }
+                }
+            }
+            // Record the progress together with the deletion, so that an
+            // interrupted pruning can be resumed
+            batch.put("tree_pruned_height", types::encode(&batch_end));
+            self.exec_batch(batch)?;
+            tracing::debug!(
+                "Pruned merkle trees of blocks from height {} to {}",
+                pruned_height,
+                batch_end
+            );
+            pruned_height = batch_end;
+        }
+        Ok(())
+    }
+
     fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
         let subspace_key =
             Key::parse("subspace").map_err(Error::KeyError)?.join(key);
@@ -14,13 +14,13 @@ use tendermint_config::net::Address as TendermintAddress;
 #[cfg(not(feature = "ABCI"))]
 use tendermint_config::Error as TendermintError;
 #[cfg(not(feature = "ABCI"))]
-use tendermint_config::TendermintConfig;
+use tendermint_config::{TendermintConfig, TxIndexer};
 #[cfg(feature = "ABCI")]
 use tendermint_config_abci::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
 use tendermint_config_abci::Error as TendermintError;
 #[cfg(feature = "ABCI")]
-use tendermint_config_abci::TendermintConfig;
+use tendermint_config_abci::{TendermintConfig, TxIndexer};
 #[cfg(feature = "ABCI")]
 use tendermint_stable::Genesis;
 use thiserror::Error;
@@ -69,13 +69,14 @@ fn from_env_or_default() -> Result<String> {
     }
 }
 
-/// Run the tendermint node.
+/// Run the tendermint node. An archive node indexes all the events.
 pub async fn run(
     home_dir: PathBuf,
     chain_id: ChainId,
     genesis_time: DateTimeUtc,
     ledger_address: String,
     config: config::Tendermint,
+    archive: bool,
     abort_recv: tokio::sync::oneshot::Receiver<
         tokio::sync::oneshot::Sender<()>,
     >,
@@ -139,7 +140,7 @@ pub async fn run(
         write_tm_genesis(&home_dir, chain_id, genesis_time).await;
     }
 
-    update_tendermint_config(&home_dir, config).await?;
+    update_tendermint_config(&home_dir, config, archive).await?;
 
     let mut tendermint_node = if !cfg!(feature = "ABCI") {
         Command::new(&tendermint_path)
@@ -337,6 +338,7 @@ pub fn write_validator_state(home_dir: impl AsRef<Path>) {
 async fn update_tendermint_config(
     home_dir: impl AsRef<Path>,
     tendermint_config: config::Tendermint,
+    archive: bool,
 ) -> Result<()> {
     let home_dir = home_dir.as_ref();
     let path = home_dir.join("config").join("config.toml");
@@ -393,6 +395,12 @@ async fn update_tendermint_config(
         config.rpc.event_log_max_items = 0;
     }
 
+    // An archive node indexes the events of all the txs, so that they can be
+    // searched for at any height
+    if archive {
+        config.tx_index.indexer = TxIndexer::Kv;
+    }
+
     let mut file = OpenOptions::new()
         .write(true)
         .truncate(true)
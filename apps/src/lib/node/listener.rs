@@ -0,0 +1,467 @@
+//! The listeners of the node's servers, on a TCP or a Unix domain socket. A
+//! server fronted by a reverse proxy may also read the PROXY protocol header
+//! that the proxy sends at the start of every connection with the address of
+//! the original client, which then replaces the connection's remote address.
+//! Both the human-readable version 1 and the binary version 2 of the PROXY
+//! protocol are supported.
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
+use tokio::sync::mpsc;
+
+use crate::config::ListenAddress;
+
+/// The signature at the start of a version 2 PROXY protocol header
+const PROXY_V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// The maximum length of a version 1 PROXY protocol header, including the
+/// trailing CRLF
+const PROXY_V1_MAX_LEN: usize = 107;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum ProxyError {
+    #[error("Failed to read the PROXY header: {0}")]
+    Io(io::Error),
+    #[error("The connection didn't start with a PROXY header")]
+    MissingHeader,
+    #[error("Invalid PROXY header: {0}")]
+    InvalidHeader(String),
+}
+
+/// A listener on a TCP or a Unix domain socket
+#[derive(Debug)]
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+/// A connection accepted by a [`Listener`]
+#[derive(Debug)]
+pub struct Stream {
+    socket: Socket,
+    info: StreamInfo,
+}
+
+/// The socket of a [`Stream`]
+#[derive(Debug)]
+enum Socket {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Listener {
+    /// Bind a listener to the given address. A Unix socket file left over
+    /// by a previous run is replaced.
+    pub async fn bind(address: &ListenAddress) -> io::Result<Self> {
+        match address {
+            ListenAddress::Tcp(address) => {
+                TcpListener::bind(address).await.map(Self::Tcp)
+            }
+            ListenAddress::Unix(path) => {
+                match std::fs::remove_file(path) {
+                    Ok(()) => {}
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(err),
+                }
+                UnixListener::bind(path).map(Self::Unix)
+            }
+        }
+    }
+
+    /// Accept a new connection
+    pub async fn accept(&self) -> io::Result<Stream> {
+        match self {
+            Self::Tcp(listener) => {
+                listener.accept().await.map(|(stream, remote_addr)| Stream {
+                    socket: Socket::Tcp(stream),
+                    info: StreamInfo {
+                        remote_addr: Some(remote_addr),
+                    },
+                })
+            }
+            Self::Unix(listener) => {
+                listener.accept().await.map(|(stream, _)| Stream {
+                    socket: Socket::Unix(stream),
+                    info: StreamInfo { remote_addr: None },
+                })
+            }
+        }
+    }
+
+    /// The accepted connections, e.g. for a tonic server. With
+    /// `proxy_protocol`, the PROXY header of every connection is read in its
+    /// own task, so that a slow client doesn't hold up the others, and the
+    /// connections without a valid header are dropped.
+    pub fn incoming(
+        self,
+        proxy_protocol: bool,
+    ) -> impl futures::Stream<Item = io::Result<Stream>> {
+        let (sender, receiver) = mpsc::channel(1);
+        tokio::spawn(async move {
+            loop {
+                // Stop accepting once the connections are no longer received
+                let accepted = tokio::select! {
+                    accepted = self.accept() => accepted,
+                    _ = sender.closed() => return,
+                };
+                match accepted {
+                    Ok(mut stream) if proxy_protocol => {
+                        let sender = sender.clone();
+                        tokio::spawn(async move {
+                            match stream.read_proxy_header().await {
+                                Ok(()) => {
+                                    let _ = sender.send(Ok(stream)).await;
+                                }
+                                Err(err) => tracing::debug!(
+                                    "Dropped a connection: {}",
+                                    err
+                                ),
+                            }
+                        });
+                    }
+                    accepted => {
+                        let _ = sender.send(accepted).await;
+                    }
+                }
+            }
+        });
+        futures::stream::unfold(receiver, |mut receiver| async move {
+            let stream = receiver.recv().await?;
+            Some((stream, receiver))
+        })
+    }
+}
+
+impl Stream {
+    /// The info of the connection
+    pub fn info(&self) -> &StreamInfo {
+        &self.info
+    }
+
+    /// Read the PROXY protocol header from the start of the connection,
+    /// leaving the stream at the first byte after it. The address of the
+    /// original client, if the proxy has forwarded one, replaces the remote
+    /// address of the connection.
+    pub async fn read_proxy_header(&mut self) -> Result<(), ProxyError> {
+        self.info.remote_addr = read_proxy_header(&mut self.socket).await?;
+        Ok(())
+    }
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().socket).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().socket).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().socket).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().socket).poll_shutdown(cx)
+    }
+}
+
+impl AsyncRead for Socket {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            Self::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Socket {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            Self::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            Self::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            Self::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// The info of a connection accepted by a [`Listener`], which is available in
+/// the extensions of the requests of a tonic server
+#[derive(Clone, Debug)]
+pub struct StreamInfo {
+    /// The address of the client of a TCP connection, or of the original
+    /// client from the PROXY header read from the connection
+    pub remote_addr: Option<SocketAddr>,
+}
+
+impl tonic::transport::server::Connected for Stream {
+    type ConnectInfo = StreamInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.info.clone()
+    }
+}
+
+/// Read the PROXY protocol header from the start of a connection, leaving
+/// the stream at the first byte after it. Returns the address of the original
+/// client, if the proxy has forwarded one.
+pub async fn read_proxy_header<S>(
+    stream: &mut S,
+) -> Result<Option<SocketAddr>, ProxyError>
+where
+    S: AsyncRead + Unpin,
+{
+    // Both versions of the header are at least 12 bytes long
+    let mut start = [0_u8; 12];
+    stream
+        .read_exact(&mut start)
+        .await
+        .map_err(ProxyError::Io)?;
+    if start == PROXY_V2_SIGNATURE {
+        let mut rest = [0_u8; 4];
+        stream.read_exact(&mut rest).await.map_err(ProxyError::Io)?;
+        let len = u16::from_be_bytes([rest[2], rest[3]]) as usize;
+        let mut addresses = vec![0_u8; len];
+        stream
+            .read_exact(&mut addresses)
+            .await
+            .map_err(ProxyError::Io)?;
+        parse_proxy_v2(rest[0], rest[1], &addresses)
+    } else if start.starts_with(b"PROXY ") {
+        // Read the rest of the line byte by byte, so that nothing after the
+        // header is consumed
+        let mut line = start.to_vec();
+        while !line.ends_with(b"\r\n") {
+            if line.len() == PROXY_V1_MAX_LEN {
+                return Err(ProxyError::InvalidHeader(
+                    "the header is too long".to_owned(),
+                ));
+            }
+            line.push(stream.read_u8().await.map_err(ProxyError::Io)?);
+        }
+        let line = std::str::from_utf8(&line[..line.len() - 2])
+            .map_err(|err| ProxyError::InvalidHeader(err.to_string()))?;
+        parse_proxy_v1(line)
+    } else {
+        Err(ProxyError::MissingHeader)
+    }
+}
+
+/// Parse a version 1 PROXY header line without the trailing CRLF, e.g.
+/// `PROXY TCP4 192.168.0.1 192.168.0.11 56324 443`
+fn parse_proxy_v1(line: &str) -> Result<Option<SocketAddr>, ProxyError> {
+    let invalid = || ProxyError::InvalidHeader(line.to_owned());
+    let parts: Vec<&str> = line.split(' ').collect();
+    match &parts[..] {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", "TCP4" | "TCP6", src_ip, _dst_ip, src_port, _dst_port] => {
+            let ip: IpAddr = src_ip.parse().map_err(|_| invalid())?;
+            let port: u16 = src_port.parse().map_err(|_| invalid())?;
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse the version and command byte, the address family and protocol byte
+/// and the addresses block of a version 2 PROXY header
+fn parse_proxy_v2(
+    version_command: u8,
+    family: u8,
+    addresses: &[u8],
+) -> Result<Option<SocketAddr>, ProxyError> {
+    if version_command >> 4 != 2 {
+        return Err(ProxyError::InvalidHeader(format!(
+            "unsupported version {}",
+            version_command >> 4
+        )));
+    }
+    match version_command & 0x0f {
+        // A LOCAL connection is established by the proxy itself, e.g. for
+        // health checks
+        0x0 => return Ok(None),
+        0x1 => {}
+        command => {
+            return Err(ProxyError::InvalidHeader(format!(
+                "unsupported command {}",
+                command
+            )));
+        }
+    }
+    let too_short = || {
+        ProxyError::InvalidHeader(format!(
+            "the addresses block of {} bytes is too short",
+            addresses.len()
+        ))
+    };
+    match family >> 4 {
+        // IPv4: the source and destination addresses, then the ports
+        0x1 => {
+            let block = addresses.get(..12).ok_or_else(too_short)?;
+            let ip = Ipv4Addr::new(block[0], block[1], block[2], block[3]);
+            let port = u16::from_be_bytes([block[8], block[9]]);
+            Ok(Some(SocketAddr::new(ip.into(), port)))
+        }
+        // IPv6: the source and destination addresses, then the ports
+        0x2 => {
+            let block = addresses.get(..36).ok_or_else(too_short)?;
+            let mut ip = [0_u8; 16];
+            ip.copy_from_slice(&block[..16]);
+            let port = u16::from_be_bytes([block[32], block[33]]);
+            Ok(Some(SocketAddr::new(Ipv6Addr::from(ip).into(), port)))
+        }
+        // The addresses of other families, like Unix sockets, are ignored
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_listen_address() {
+        assert_eq!(
+            ListenAddress::from_str("127.0.0.1:26660").unwrap(),
+            ListenAddress::Tcp("127.0.0.1:26660".parse().unwrap())
+        );
+        assert_eq!(
+            ListenAddress::from_str("tcp://0.0.0.0:80").unwrap(),
+            ListenAddress::Tcp("0.0.0.0:80".parse().unwrap())
+        );
+        let unix = ListenAddress::from_str("unix:///run/anoma.sock").unwrap();
+        assert_eq!(unix, ListenAddress::Unix("/run/anoma.sock".into()));
+        assert_eq!(unix.to_string(), "unix:///run/anoma.sock");
+        assert!(ListenAddress::from_str("unix://").is_err());
+        assert!(ListenAddress::from_str("localhost").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_proxy_v1_header() {
+        let mut stream: &[u8] =
+            b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\nGET / HTTP/1.1";
+        let client = read_proxy_header(&mut stream).await.unwrap();
+        assert_eq!(client, Some("192.168.0.1:56324".parse().unwrap()));
+        // The stream continues right after the header
+        assert_eq!(stream, b"GET / HTTP/1.1");
+
+        let mut stream: &[u8] = b"PROXY UNKNOWN\r\n";
+        assert_eq!(read_proxy_header(&mut stream).await.unwrap(), None);
+
+        let mut stream: &[u8] = b"PROXY TCP4 not-an-ip\r\n";
+        assert!(matches!(
+            read_proxy_header(&mut stream).await,
+            Err(ProxyError::InvalidHeader(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_proxy_v2_header() {
+        let mut header = PROXY_V2_SIGNATURE.to_vec();
+        // PROXY command over TCP on IPv4
+        header.extend([0x21, 0x11, 0, 12]);
+        header.extend([10, 0, 0, 1, 10, 0, 0, 2]);
+        header.extend(1234_u16.to_be_bytes());
+        header.extend(443_u16.to_be_bytes());
+        header.extend(b"rest");
+        let mut stream = &header[..];
+        let client = read_proxy_header(&mut stream).await.unwrap();
+        assert_eq!(client, Some("10.0.0.1:1234".parse().unwrap()));
+        assert_eq!(stream, b"rest");
+
+        // LOCAL command without addresses
+        let mut header = PROXY_V2_SIGNATURE.to_vec();
+        header.extend([0x20, 0x00, 0, 0]);
+        let mut stream = &header[..];
+        assert_eq!(read_proxy_header(&mut stream).await.unwrap(), None);
+    }
+
+    /// Test that the incoming connections with a PROXY header have the
+    /// original client's address and that the ones without it are dropped
+    #[tokio::test]
+    async fn test_incoming_with_proxy_protocol() {
+        use futures::StreamExt;
+        use tokio::io::AsyncWriteExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("rpc.sock");
+        let listener = Listener::bind(&ListenAddress::Unix(path.clone()))
+            .await
+            .unwrap();
+        let mut incoming = Box::pin(listener.incoming(true));
+
+        let mut invalid = UnixStream::connect(&path).await.unwrap();
+        invalid.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut valid = UnixStream::connect(&path).await.unwrap();
+        valid
+            .write_all(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n")
+            .await
+            .unwrap();
+
+        let stream = incoming.next().await.unwrap().unwrap();
+        assert_eq!(
+            stream.info().remote_addr,
+            Some("192.168.0.1:56324".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_proxy_header() {
+        let mut stream: &[u8] = b"GET / HTTP/1.1\r\n\r\n";
+        assert!(matches!(
+            read_proxy_header(&mut stream).await,
+            Err(ProxyError::MissingHeader)
+        ));
+    }
+}
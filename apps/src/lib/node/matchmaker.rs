@@ -1,48 +1,118 @@
-use std::env;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use std::{env, fs, process};
 
-use anoma::proto::Tx;
+use anoma::proto::{Intent, IntentId, Tx};
 use anoma::types::address::{self, Address};
 use anoma::types::dylib;
-use anoma::types::intent::{IntentTransfers, MatchedExchanges};
+use anoma::types::intent::{IntentTransfers, MatchedExchanges, MatchmakerFee};
 use anoma::types::key::*;
-use anoma::types::matchmaker::AddIntentResult;
-use anoma::types::transaction::{hash_tx, Fee, WrapperTx};
+use anoma::types::matchmaker::{AddIntentResult, MatchingStrategy};
+use anoma::types::time::DateTimeUtc;
+use anoma::types::transaction::{hash_tx, schema, Fee, WrapperTx};
 use borsh::{BorshDeserialize, BorshSerialize};
 use libc::c_void;
 use libloading::Library;
 #[cfg(not(feature = "ABCI"))]
-use tendermint_config::net;
-#[cfg(not(feature = "ABCI"))]
 use tendermint_config::net::Address as TendermintAddress;
 #[cfg(feature = "ABCI")]
-use tendermint_config_abci::net;
-#[cfg(feature = "ABCI")]
 use tendermint_config_abci::net::Address as TendermintAddress;
+use thiserror::Error;
 
+use super::gossip::admission;
+use super::gossip::replay::SeenIntents;
 use super::gossip::rpc::matchmakers::{
-    ClientDialer, ClientListener, MsgFromClient, MsgFromServer,
+    ClientDialer, ClientEvent, ClientListener, MsgFromClient, MsgFromServer,
 };
 use crate::cli::args;
+use crate::client::progress::TxProgressHook;
 use crate::client::rpc;
 use crate::client::tendermint_rpc_types::TxBroadcastData;
-use crate::client::tx::broadcast_tx;
+use crate::client::tx::submit_tx;
 use crate::{cli, config, wasm_loader};
 
+/// The interval at which the matchmaker's dylib is checked for changes
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The ledger address to which the settlement txs are submitted, if none is
+/// given nor configured
+const DEFAULT_LEDGER_ADDRESS: &str = "127.0.0.1:26657";
+
+/// How long to wait for the result of a settlement tx, if not configured
+const DEFAULT_EVENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The automatic settlement of the matches found by the matchmaker. The
+/// matched txs are signed with the matchmaker's own key and submitted to the
+/// ledger.
+#[derive(Debug)]
+pub struct Settlement {
+    /// The address of the ledger that the settlement txs are submitted to
+    pub ledger_address: TendermintAddress,
+    /// The key with which the settlement txs are signed
+    pub signing_key: Rc<common::SecretKey>,
+    /// The source address of the settlement txs, which receives the fees
+    pub source: Address,
+    /// How to retry broadcasting a settlement tx
+    pub retry: args::BroadcastRetry,
+    /// How long to wait for the result of a settlement tx
+    pub event_timeout: Duration,
+}
+
+impl Settlement {
+    /// The settlement with the given key and source. The given ledger address
+    /// overrides the configured one, if any.
+    pub fn new(
+        signing_key: Rc<common::SecretKey>,
+        source: Address,
+        ledger_address: Option<TendermintAddress>,
+        config: Option<&config::MatchmakerSettlement>,
+    ) -> Self {
+        let ledger_address = ledger_address
+            .or_else(|| config.map(|config| config.ledger_address.clone()))
+            .unwrap_or_else(|| {
+                TendermintAddress::from_str(DEFAULT_LEDGER_ADDRESS).unwrap()
+            });
+        let default_retry = args::BroadcastRetry::default();
+        let retry = args::BroadcastRetry {
+            max_attempts: config
+                .and_then(|config| config.max_broadcast_attempts)
+                .unwrap_or(default_retry.max_attempts),
+            base_delay: config
+                .and_then(|config| config.broadcast_retry_delay)
+                .map(Into::into)
+                .unwrap_or(default_retry.base_delay),
+        };
+        let event_timeout = config
+            .and_then(|config| config.event_timeout)
+            .map(Into::into)
+            .unwrap_or(DEFAULT_EVENT_TIMEOUT);
+        Self {
+            ledger_address,
+            signing_key,
+            source,
+            retry,
+            event_timeout,
+        }
+    }
+}
+
 /// Run a matchmaker
 #[tokio::main]
 pub async fn run(
     config::Matchmaker {
         matchmaker_path,
         tx_code_path,
+        strategy,
+        batch_interval,
+        fee,
+        settlement: _,
     }: config::Matchmaker,
     intent_gossiper_addr: SocketAddr,
-    ledger_addr: TendermintAddress,
-    tx_signing_key: Rc<common::SecretKey>,
-    tx_source_address: Address,
+    settlement: Option<Settlement>,
     wasm_dir: impl AsRef<Path>,
 ) {
     let matchmaker_path = matchmaker_path.unwrap_or_else(|| {
@@ -58,11 +128,18 @@ pub async fn run(
         intent_gossiper_addr,
         matchmaker_path,
         tx_code_path,
-        ledger_addr,
-        tx_signing_key,
-        tx_source_address,
+        settlement,
+        fee,
+        strategy,
+        batch_interval.map(Into::into),
         wasm_dir,
     );
+    if result_handler.settlement.is_none() {
+        tracing::warn!(
+            "The matchmaker has no signing key, the matches will only be \
+             logged and not settled"
+        );
+    }
 
     // Instantiate and run the matchmaker implementation in a dedicated thread
     let runner_join_handle = std::thread::spawn(move || {
@@ -80,14 +157,37 @@ pub async fn run(
 
 /// A matchmaker receive intents and tries to find a match with previously
 /// received intent.
+///
+/// The matchmaker's dylib is reloaded when it's modified, without
+/// disconnecting from the intent gossiper node. The intents that haven't been
+/// matched nor expired are added again to the reloaded matchmaker, in the
+/// order in which they have been received.
+///
+/// The intents that the matchmaker has already seen, e.g. replayed intents,
+/// are ignored.
+///
+/// With a batch interval, the matchmaker matches the intents in batch
+/// auctions: the intents are only collected and the batch is cleared at every
+/// interval, see [`anoma::types::matchmaker::AddIntent::clear_batch`].
 #[derive(Debug)]
 pub struct Runner {
     matchmaker_path: PathBuf,
+    /// The strategy with which the matchmaker matches intents
+    strategy: MatchingStrategy,
+    /// The interval at which the batch of intents is cleared, if the
+    /// matchmaker matches intents in batch auctions
+    batch_interval: Option<Duration>,
     /// The client listener. This is consumed once the listener is started with
     /// [`Runner::listen`].
     listener: Option<ClientListener>,
     /// Sender of results of matched intents to the [`ResultHandler`].
-    result_send: tokio::sync::mpsc::UnboundedSender<AddIntentResult>,
+    result_send: tokio::sync::mpsc::UnboundedSender<RunnerResult>,
+    /// The IDs and data of the intents added to the matchmaker that haven't
+    /// been matched yet, in the order in which they have been received
+    intents: Vec<(Vec<u8>, Vec<u8>)>,
+    /// The intents seen by the matchmaker, whether they have been matched or
+    /// not
+    seen: SeenIntents,
 }
 
 /// Result handler processes the results sent from the matchmaker [`Runner`].
@@ -96,15 +196,28 @@ pub struct ResultHandler {
     /// A dialer can send messages to the connected intent gossip node
     dialer: ClientDialer,
     /// A receiver of matched intents results from the [`Runner`].
-    result_recv: tokio::sync::mpsc::UnboundedReceiver<AddIntentResult>,
-    /// The ledger address to send any crafted transaction to
-    ledger_address: net::Address,
+    result_recv: tokio::sync::mpsc::UnboundedReceiver<RunnerResult>,
     /// The code of the transaction that is going to be send to a ledger.
     tx_code: Vec<u8>,
-    /// A source address for transactions created from intents.
-    tx_source_address: Address,
-    /// A keypair that will be used to sign transactions.
-    tx_signing_key: Rc<common::SecretKey>,
+    /// The settlement of the matches, if the matchmaker has a signing key.
+    /// Otherwise, the matched txs are only logged.
+    settlement: Option<Settlement>,
+    /// The fee charged to the sources of the matched exchanges, if any
+    fee: Option<MatchmakerFee>,
+}
+
+/// A result sent from the matchmaker [`Runner`] to the [`ResultHandler`]
+#[derive(Debug)]
+enum RunnerResult {
+    /// The result of adding an intent
+    AddIntent(AddIntentResult),
+    /// An intent ignored by the matchmaker
+    IgnoredIntent { id: Vec<u8> },
+    /// The result of an intent's dry-run requested by the intent gossiper
+    DryRunIntent {
+        request_id: u64,
+        result: AddIntentResult,
+    },
 }
 
 /// The loaded implementation's dylib and its state
@@ -115,6 +228,8 @@ struct MatchmakerImpl {
     /// NOTE: The `state` field MUST be above the `library` field to ensure
     /// that its destructor is ran before the implementation code is dropped.
     state: MatchmakerState,
+    /// Whether the matchmaker matches intents in batch auctions
+    batch_auction: bool,
     /// Matchmaker's implementation loaded from dylib
     library: Library,
 }
@@ -124,6 +239,27 @@ struct MatchmakerImpl {
 #[derive(Debug)]
 struct MatchmakerState(Arc<*mut c_void>);
 
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+enum ReloadError {
+    #[error("Failed to copy the matchmaker's dylib: {0}")]
+    Copy(std::io::Error),
+    #[error("Failed to load the matchmaker's dylib: {0}")]
+    Load(libloading::Error),
+}
+
+/// The modification times of the matchmaker's dylib, to find when it has to
+/// be reloaded
+#[derive(Debug)]
+struct DylibWatch {
+    /// The path of the dylib
+    path: PathBuf,
+    /// The modification time of the loaded dylib
+    loaded: Option<SystemTime>,
+    /// The modification time of the dylib at the last check
+    last_seen: Option<SystemTime>,
+}
+
 impl Runner {
     /// Create a new matchmaker and a dialer that can be used to send messages
     /// to the intent gossiper node.
@@ -131,9 +267,10 @@ impl Runner {
         intent_gossiper_addr: SocketAddr,
         matchmaker_path: PathBuf,
         tx_code_path: PathBuf,
-        ledger_address: TendermintAddress,
-        tx_signing_key: Rc<common::SecretKey>,
-        tx_source_address: Address,
+        settlement: Option<Settlement>,
+        fee: Option<MatchmakerFee>,
+        strategy: MatchingStrategy,
+        batch_interval: Option<Duration>,
         wasm_dir: impl AsRef<Path>,
     ) -> (Self, ResultHandler) {
         // Setup a channel for sending matchmaker results from `Self` to the
@@ -148,16 +285,19 @@ impl Runner {
         (
             Self {
                 matchmaker_path,
+                strategy,
+                batch_interval,
                 listener: Some(listener),
                 result_send,
+                intents: vec![],
+                seen: SeenIntents::default(),
             },
             ResultHandler {
                 dialer,
                 result_recv,
-                ledger_address,
                 tx_code,
-                tx_source_address,
-                tx_signing_key,
+                settlement,
+                fee,
             },
         )
     }
@@ -166,121 +306,344 @@ impl Runner {
         // Load the implementation's dylib and instantiate it. We have to do
         // that here instead of `Self::new_pair`, because we cannot send
         // it across threads and the listener is launched in a dedicated thread.
-
-        // Check or add a filename extension to matchmaker path
-        let matchmaker_filename =
-            if let Some(ext) = self.matchmaker_path.extension() {
-                if ext != dylib::FILE_EXT {
-                    tracing::warn!(
-                        "Unexpected matchmaker file extension. Expected {}, \
-                         got {}.",
-                        dylib::FILE_EXT,
-                        ext.to_string_lossy(),
-                    );
+        let mut watch = DylibWatch::new(find_dylib(&self.matchmaker_path));
+        tracing::info!(
+            "Running matchmaker from {}",
+            watch.path.to_string_lossy()
+        );
+        let batch_auction = self.batch_interval.is_some();
+        let mut r#impl = MatchmakerImpl::load_dylib(
+            &watch.path,
+            self.strategy,
+            batch_auction,
+        )
+        .unwrap();
+
+        // Run the listener for messages from the connected intent gossiper
+        // node. On every tick, the batch of intents is cleared once the batch
+        // interval has elapsed and the dylib is checked for changes once the
+        // reload check interval has elapsed.
+        let tick_interval = self
+            .batch_interval
+            .map_or(RELOAD_CHECK_INTERVAL, |interval| {
+                interval.min(RELOAD_CHECK_INTERVAL)
+            });
+        let mut next_clearing = self
+            .batch_interval
+            .map(|interval| Instant::now() + interval);
+        let mut next_reload_check = Instant::now() + RELOAD_CHECK_INTERVAL;
+        let listener = self.listener.take().unwrap();
+        listener.listen_with_ticks(Some(tick_interval), |event| {
+            match event {
+                ClientEvent::Message(MsgFromServer::AddIntent { id, data }) => {
+                    match self.seen.insert(&data, DateTimeUtc::now()) {
+                        Ok(()) => self.try_match_intent(&r#impl, id, data),
+                        Err(err) => {
+                            tracing::info!(
+                                "Ignoring intent ID {}: {}",
+                                IntentId(id.clone()),
+                                err
+                            );
+                            self.result_send
+                                .send(RunnerResult::IgnoredIntent { id })
+                                .unwrap();
+                        }
+                    }
                 }
-                self.matchmaker_path.clone()
-            } else {
-                let mut filename = self.matchmaker_path.clone();
-                filename.set_extension(dylib::FILE_EXT);
-                filename
-            };
+                ClientEvent::Message(MsgFromServer::DryRunIntent {
+                    request_id,
+                    id,
+                    data,
+                }) => {
+                    let result = r#impl.dry_run_intent(id, data);
+                    self.result_send
+                        .send(RunnerResult::DryRunIntent { request_id, result })
+                        .unwrap();
+                }
+                ClientEvent::Tick => {
+                    let now = Instant::now();
+                    if let (Some(next), Some(interval)) =
+                        (next_clearing.as_mut(), self.batch_interval)
+                    {
+                        if now >= *next {
+                            // The clearings are kept on schedule, unless
+                            // they're late by more than an interval
+                            *next = (*next + interval).max(now);
+                            self.clear_batch(&r#impl);
+                        }
+                    }
+                    if now >= next_reload_check {
+                        next_reload_check = now + RELOAD_CHECK_INTERVAL;
+                        if watch.is_modified() {
+                            if let Some(reloaded) = self.reload(&watch.path) {
+                                r#impl = reloaded;
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
 
-        let matchmaker_dylib = if matchmaker_filename.is_absolute() {
-            // If the path is absolute, use it as is
-            matchmaker_filename
-        } else {
-            // The dylib should be built in the same directory as where Anoma
-            // binaries are, even when ran via `cargo run`. Anoma's pre-built
-            // binaries are distributed with the dylib(s) in the same directory.
-            let dylib_dir_with_bins = || {
-                let anoma_path = env::current_exe().unwrap();
-                anoma_path
-                    .parent()
-                    .map(|path| path.to_owned())
-                    .unwrap()
-                    .join(&matchmaker_filename)
-            };
-            // Anoma built from source (`make install`) will install the
-            // dylib(s) to `~/.cargo/lib`.
-            let dylib_dir_installed = || {
-                directories::BaseDirs::new()
-                    .expect("Couldn't determine the $HOME directory")
-                    .home_dir()
-                    .join(".cargo")
-                    .join("lib")
-                    .join(&matchmaker_filename)
-            };
-            // Argument with file path relative to the current dir.
-            let dylib_dir_in_cwd = || {
-                let anoma_path = env::current_dir().unwrap();
-                anoma_path.join(&matchmaker_filename)
-            };
+    /// add the intent to the matchmaker mempool and tries to find a match for
+    /// that intent
+    fn try_match_intent(
+        &mut self,
+        r#impl: &MatchmakerImpl,
+        intent_id: Vec<u8>,
+        intent_data: Vec<u8>,
+    ) {
+        if !self.intents.iter().any(|(id, _data)| *id == intent_id) {
+            self.intents.push((intent_id.clone(), intent_data.clone()));
+        }
+        let result = r#impl.add_intent(intent_id, intent_data);
+        self.handle_match(result);
+    }
+
+    /// Clear the batch of intents collected by the matchmaker, if it matches
+    /// intents in batch auctions
+    fn clear_batch(&mut self, r#impl: &MatchmakerImpl) {
+        if let Some(result) = r#impl.clear_batch() {
+            self.handle_match(result);
+        }
+    }
 
-            // Try to find the matchmaker lib in either directory (computed
-            // lazily)
-            let matchmaker_dylib: Option<PathBuf> =
-                check_file_exists(dylib_dir_with_bins)
-                    .or_else(|| check_file_exists(dylib_dir_installed))
-                    .or_else(|| check_file_exists(dylib_dir_in_cwd));
-            matchmaker_dylib.unwrap_or_else(|| {
-                panic!(
-                    "The matchmaker library couldn't not be found. Did you \
-                     build it? Attempted to find it in directories \"{}\", \
-                     \"{}\" and \"{}\".",
-                    dylib_dir_with_bins().to_string_lossy(),
-                    dylib_dir_installed().to_string_lossy(),
-                    dylib_dir_in_cwd().to_string_lossy(),
+    /// Forget the matched intents, except for the partially matched ones, and
+    /// send the result to the [`ResultHandler`]
+    fn handle_match(&mut self, result: AddIntentResult) {
+        if let Some(matched) = &result.matched_intents {
+            // The partially matched intents can still be matched
+            let partially_matched = result.partially_matched_intents.as_ref();
+            self.intents.retain(|(id, _data)| {
+                !matched.contains(id)
+                    || partially_matched.map_or(false, |ids| ids.contains(id))
+            });
+        }
+        self.result_send
+            .send(RunnerResult::AddIntent(result))
+            .unwrap();
+    }
+
+    /// Reload the modified matchmaker's dylib and add the intents that haven't
+    /// been matched nor expired to the reloaded matchmaker. Returns `None` if
+    /// the dylib cannot be loaded, in which case the current matchmaker
+    /// should be kept.
+    fn reload(&mut self, matchmaker_dylib: &Path) -> Option<MatchmakerImpl> {
+        tracing::info!(
+            "Reloading the modified matchmaker from {}",
+            matchmaker_dylib.to_string_lossy()
+        );
+        let reloaded = MatchmakerImpl::reload(
+            matchmaker_dylib,
+            self.strategy,
+            self.batch_interval.is_some(),
+        );
+        let r#impl = match reloaded {
+            Ok(r#impl) => r#impl,
+            Err(err) => {
+                tracing::error!(
+                    "Keeping the current matchmaker, the modified one \
+                     couldn't be reloaded: {}",
+                    err
                 );
-            })
+                return None;
+            }
         };
+
+        let now = DateTimeUtc::now();
+        let mut intents = std::mem::take(&mut self.intents);
+        intents.retain(|(_id, data)| {
+            admission::check_expiry(&Intent::new(data.clone()), now).is_ok()
+        });
+        tracing::info!(
+            "Adding {} intents to the reloaded matchmaker",
+            intents.len()
+        );
+        for (id, data) in intents {
+            self.try_match_intent(&r#impl, id, data);
+        }
+        Some(r#impl)
+    }
+}
+
+/// Run the matchmaker locally on the given intents, without connecting to an
+/// intent gossiper node. The intents are added in the given order and the
+/// result of adding each intent is returned.
+pub fn simulate(
+    matchmaker_path: &Path,
+    strategy: MatchingStrategy,
+    intents: Vec<Vec<u8>>,
+) -> Vec<AddIntentResult> {
+    let r#impl = MatchmakerImpl::load(matchmaker_path, strategy);
+    intents
+        .into_iter()
+        .map(|data| {
+            let id = Intent::new(data.clone()).id();
+            r#impl.add_intent(id.0, data)
+        })
+        .collect()
+}
+
+impl MatchmakerImpl {
+    /// Find the matchmaker's dylib, load it and instantiate the matchmaker.
+    fn load(matchmaker_path: &Path, strategy: MatchingStrategy) -> Self {
+        let matchmaker_dylib = find_dylib(matchmaker_path);
         tracing::info!(
             "Running matchmaker from {}",
             matchmaker_dylib.to_string_lossy()
         );
+        Self::load_dylib(&matchmaker_dylib, strategy, false).unwrap()
+    }
 
-        let matchmaker_code =
-            unsafe { Library::new(matchmaker_dylib).unwrap() };
+    /// Load a modified matchmaker's dylib and instantiate the matchmaker. The
+    /// dylib is loaded from a copy, because a library that is already loaded
+    /// from the same path would be reused.
+    fn reload(
+        matchmaker_dylib: &Path,
+        strategy: MatchingStrategy,
+        batch_auction: bool,
+    ) -> Result<Self, ReloadError> {
+        let file_name = matchmaker_dylib
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let copy = env::temp_dir().join(format!(
+            "anoma-{}-{}-{}",
+            process::id(),
+            nanos,
+            file_name
+        ));
+        fs::copy(matchmaker_dylib, &copy).map_err(ReloadError::Copy)?;
+        let result = Self::load_dylib(&copy, strategy, batch_auction)
+            .map_err(ReloadError::Load);
+        // The copy is no longer needed once it's loaded
+        if let Err(err) = fs::remove_file(&copy) {
+            tracing::warn!(
+                "Failed to remove the copy of the matchmaker {}: {}",
+                copy.to_string_lossy(),
+                err
+            );
+        }
+        result
+    }
+
+    /// Load the matchmaker's dylib and instantiate the matchmaker with the
+    /// given strategy, matching intents in batch auctions if `batch_auction`.
+    fn load_dylib(
+        matchmaker_dylib: &Path,
+        strategy: MatchingStrategy,
+        batch_auction: bool,
+    ) -> Result<Self, libloading::Error> {
+        let matchmaker_code = unsafe { Library::new(matchmaker_dylib)? };
 
         // Instantiate the matchmaker
         let new_matchmaker: libloading::Symbol<
             unsafe extern "C" fn() -> *mut c_void,
-        > = unsafe { matchmaker_code.get(b"_new_matchmaker").unwrap() };
+        > = unsafe { matchmaker_code.get(b"_new_matchmaker")? };
 
         let state = MatchmakerState(Arc::new(unsafe { new_matchmaker() }));
 
-        let r#impl = MatchmakerImpl {
-            state,
-            library: matchmaker_code,
-        };
+        // A matchmaker built before the strategies were introduced doesn't
+        // have the binding to set one, in which case it matches intents with
+        // its own strategy
+        let set_strategy: Result<
+            libloading::Symbol<
+                unsafe extern "C" fn(*mut c_void, MatchingStrategy),
+            >,
+            _,
+        > = unsafe { matchmaker_code.get(b"_set_strategy") };
+        match set_strategy {
+            Ok(set_strategy) => unsafe { set_strategy(*state.0, strategy) },
+            Err(_) if strategy != MatchingStrategy::default() => {
+                tracing::warn!(
+                    "The matchmaker doesn't support matching strategies, the \
+                     configured strategy {:?} is ignored",
+                    strategy
+                )
+            }
+            Err(_) => {}
+        }
 
-        // Run the listener for messages from the connected intent gossiper node
-        self.listener.take().unwrap().listen(|msg| match msg {
-            MsgFromServer::AddIntent { id, data } => {
-                self.try_match_intent(&r#impl, id, data);
+        // Likewise, a matchmaker built before the batch auctions were
+        // introduced matches every intent as soon as it's added
+        let batch_auction = batch_auction && {
+            let set_batch_auction: Result<
+                libloading::Symbol<unsafe extern "C" fn(*mut c_void, bool)>,
+                _,
+            > = unsafe { matchmaker_code.get(b"_set_batch_auction") };
+            match set_batch_auction {
+                Ok(set_batch_auction) => {
+                    unsafe { set_batch_auction(*state.0, true) };
+                    true
+                }
+                Err(_) => {
+                    tracing::warn!(
+                        "The matchmaker doesn't support batch auctions, the \
+                         intents are matched as soon as they're received"
+                    );
+                    false
+                }
             }
+        };
+
+        Ok(Self {
+            state,
+            batch_auction,
+            library: matchmaker_code,
         })
     }
 
-    /// add the intent to the matchmaker mempool and tries to find a match for
+    /// Add the intent to the matchmaker mempool and try to find a match for
     /// that intent
-    fn try_match_intent(
+    fn add_intent(
         &self,
-        r#impl: &MatchmakerImpl,
         intent_id: Vec<u8>,
         intent_data: Vec<u8>,
-    ) {
+    ) -> AddIntentResult {
         let add_intent: libloading::Symbol<
             unsafe extern "C" fn(
                 *mut c_void,
                 &Vec<u8>,
                 &Vec<u8>,
             ) -> AddIntentResult,
-        > = unsafe { r#impl.library.get(b"_add_intent").unwrap() };
+        > = unsafe { self.library.get(b"_add_intent").unwrap() };
 
-        let result =
-            unsafe { add_intent(*r#impl.state.0, &intent_id, &intent_data) };
+        unsafe { add_intent(*self.state.0, &intent_id, &intent_data) }
+    }
 
-        self.result_send.send(result).unwrap();
+    /// Find how the intent would be matched, without changing the matchmaker's
+    /// state
+    fn dry_run_intent(
+        &self,
+        intent_id: Vec<u8>,
+        intent_data: Vec<u8>,
+    ) -> AddIntentResult {
+        let dry_run_intent: libloading::Symbol<
+            unsafe extern "C" fn(
+                *mut c_void,
+                &Vec<u8>,
+                &Vec<u8>,
+            ) -> AddIntentResult,
+        > = unsafe { self.library.get(b"_dry_run_intent").unwrap() };
+
+        unsafe { dry_run_intent(*self.state.0, &intent_id, &intent_data) }
+    }
+
+    /// Clear the batch of intents collected by the matchmaker, if it matches
+    /// intents in batch auctions
+    fn clear_batch(&self) -> Option<AddIntentResult> {
+        if !self.batch_auction {
+            return None;
+        }
+        let clear_batch: libloading::Symbol<
+            unsafe extern "C" fn(*mut c_void) -> AddIntentResult,
+        > = unsafe { self.library.get(b"_clear_batch").unwrap() };
+
+        Some(unsafe { clear_batch(*self.state.0) })
     }
 }
 
@@ -297,26 +660,73 @@ impl Drop for MatchmakerImpl {
 impl ResultHandler {
     async fn run(mut self) {
         while let Some(result) = self.result_recv.recv().await {
-            if let Some(tx) = result.tx {
-                self.submit_tx(tx).await
-            }
-            if let Some(intent_ids) = result.matched_intents {
-                self.dialer.send(MsgFromClient::Matched { intent_ids })
+            match result {
+                RunnerResult::AddIntent(result) => {
+                    let tx_hash = match result.tx {
+                        Some(tx) => self.submit_tx(tx).await,
+                        None => None,
+                    };
+                    let partially_matched =
+                        result.partially_matched_intents.unwrap_or_default();
+                    if let Some(mut intent_ids) = result.matched_intents {
+                        intent_ids.retain(|id| !partially_matched.contains(id));
+                        if !intent_ids.is_empty() {
+                            self.dialer.send(MsgFromClient::Matched {
+                                intent_ids,
+                                tx_hash: tx_hash.clone(),
+                            })
+                        }
+                    }
+                    if !partially_matched.is_empty() {
+                        self.dialer.send(MsgFromClient::PartiallyMatched {
+                            intent_ids: partially_matched,
+                            tx_hash,
+                        })
+                    }
+                }
+                RunnerResult::IgnoredIntent { id } => {
+                    self.dialer.send(MsgFromClient::IgnoredIntent { id })
+                }
+                RunnerResult::DryRunIntent { request_id, result } => {
+                    self.dialer.send(MsgFromClient::DryRunResult {
+                        request_id,
+                        tx: result.tx,
+                        matched_intents: result.matched_intents,
+                    })
+                }
             }
         }
     }
 
-    async fn submit_tx(&self, tx_data: Vec<u8>) {
+    /// Submit a tx with the matched intents to the ledger, if the matchmaker
+    /// has a signing key. Returns the hash of the tx applied by the ledger,
+    /// if it has been submitted.
+    async fn submit_tx(&self, tx_data: Vec<u8>) -> Option<String> {
+        let settlement = match &self.settlement {
+            Some(settlement) => settlement,
+            None => {
+                tracing::info!(
+                    "Matched transaction data, not settled: {}",
+                    hex::encode(&tx_data)
+                );
+                return None;
+            }
+        };
         let tx_code = self.tx_code.clone();
-        let matches = MatchedExchanges::try_from_slice(&tx_data[..]).unwrap();
+        let mut matches =
+            MatchedExchanges::try_from_slice(&tx_data[..]).unwrap();
+        // The fees are paid to the source of the tx
+        if let Some(fee) = &self.fee {
+            matches.add_fees(fee);
+        }
         let intent_transfers = IntentTransfers {
             matches,
-            source: self.tx_source_address.clone(),
+            source: settlement.source.clone(),
         };
-        let tx_data = intent_transfers.try_to_vec().unwrap();
-        let to_broadcast = {
+        let tx_data = schema::encode(&intent_transfers);
+        let (to_broadcast, tx_hash) = {
             let epoch = rpc::query_epoch(args::Query {
-                ledger_address: self.ledger_address.clone(),
+                ledger_address: settlement.ledger_address.clone(),
             })
             .await;
             let tx = WrapperTx::new(
@@ -324,10 +734,10 @@ impl ResultHandler {
                     amount: 0.into(),
                     token: address::xan(),
                 },
-                &self.tx_signing_key,
+                &settlement.signing_key,
                 epoch,
                 0.into(),
-                Tx::new(tx_code, Some(tx_data)).sign(&self.tx_signing_key),
+                Tx::new(tx_code, Some(tx_data)).sign(&settlement.signing_key),
                 // TODO: Actually use the fetched encryption key
                 Default::default(),
             );
@@ -342,23 +752,48 @@ impl ResultHandler {
             } else {
                 None
             };
-            TxBroadcastData::Wrapper {
+            // The inner tx is the one applied, unless it's not wrapped
+            let tx_hash = decrypted_hash
+                .clone()
+                .unwrap_or_else(|| wrapper_hash.clone());
+            let to_broadcast = TxBroadcastData::Wrapper {
                 tx: tx
-                    .sign(&self.tx_signing_key)
+                    .sign(&settlement.signing_key)
                     .expect("Wrapper tx signing keypair should be correct"),
                 wrapper_hash,
                 decrypted_hash,
-            }
+            };
+            (to_broadcast, tx_hash)
         };
 
-        let response =
-            broadcast_tx(self.ledger_address.clone(), &to_broadcast).await;
+        let response = submit_tx(
+            settlement.ledger_address.clone(),
+            to_broadcast,
+            settlement.retry,
+            settlement.event_timeout,
+            &TxProgressHook::default(),
+        )
+        .await;
         match response {
-            Ok(tx_response) => {
+            Ok(response) if response.code == 0.to_string() => {
                 tracing::info!(
-                    "Injected transaction from matchmaker with result: {:#?}",
-                    tx_response
+                    "Settled the matched transaction {} at height {}, used \
+                     gas {}",
+                    tx_hash,
+                    response.height,
+                    response.gas_used
                 );
+                Some(tx_hash)
+            }
+            Ok(response) => {
+                tracing::error!(
+                    "The matched transaction {} has been rejected by the \
+                     ledger with code {}: {}",
+                    tx_hash,
+                    response.code,
+                    response.info
+                );
+                None
             }
             Err(err) => {
                 tracing::error!(
@@ -366,11 +801,121 @@ impl ResultHandler {
                      ledger: {}",
                     err
                 );
+                None
             }
         }
     }
 }
 
+/// Find the matchmaker's dylib. If the path is relative, the dylib is looked
+/// up in the directory of the Anoma binaries, then in `~/.cargo/lib` and in
+/// the current directory.
+fn find_dylib(matchmaker_path: &Path) -> PathBuf {
+    // Check or add a filename extension to matchmaker path
+    let matchmaker_filename = match matchmaker_path.extension() {
+        Some(ext) => {
+            if ext != dylib::FILE_EXT {
+                tracing::warn!(
+                    "Unexpected matchmaker file extension. Expected {}, got \
+                     {}.",
+                    dylib::FILE_EXT,
+                    ext.to_string_lossy(),
+                );
+            }
+            matchmaker_path.to_owned()
+        }
+        None => {
+            let mut filename = matchmaker_path.to_owned();
+            filename.set_extension(dylib::FILE_EXT);
+            filename
+        }
+    };
+
+    if matchmaker_filename.is_absolute() {
+        // If the path is absolute, use it as is
+        matchmaker_filename
+    } else {
+        // The dylib should be built in the same directory as where Anoma
+        // binaries are, even when ran via `cargo run`. Anoma's pre-built
+        // binaries are distributed with the dylib(s) in the same directory.
+        let dylib_dir_with_bins = || {
+            let anoma_path = env::current_exe().unwrap();
+            anoma_path
+                .parent()
+                .map(|path| path.to_owned())
+                .unwrap()
+                .join(&matchmaker_filename)
+        };
+        // Anoma built from source (`make install`) will install the
+        // dylib(s) to `~/.cargo/lib`.
+        let dylib_dir_installed = || {
+            directories::BaseDirs::new()
+                .expect("Couldn't determine the $HOME directory")
+                .home_dir()
+                .join(".cargo")
+                .join("lib")
+                .join(&matchmaker_filename)
+        };
+        // Argument with file path relative to the current dir.
+        let dylib_dir_in_cwd = || {
+            let anoma_path = env::current_dir().unwrap();
+            anoma_path.join(&matchmaker_filename)
+        };
+
+        // Try to find the matchmaker lib in either directory (computed
+        // lazily)
+        let matchmaker_dylib: Option<PathBuf> =
+            check_file_exists(dylib_dir_with_bins)
+                .or_else(|| check_file_exists(dylib_dir_installed))
+                .or_else(|| check_file_exists(dylib_dir_in_cwd));
+        matchmaker_dylib.unwrap_or_else(|| {
+            panic!(
+                "The matchmaker library couldn't not be found. Did you build \
+                 it? Attempted to find it in directories \"{}\", \"{}\" and \
+                 \"{}\".",
+                dylib_dir_with_bins().to_string_lossy(),
+                dylib_dir_installed().to_string_lossy(),
+                dylib_dir_in_cwd().to_string_lossy(),
+            );
+        })
+    }
+}
+
+impl DylibWatch {
+    fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        Self {
+            path,
+            loaded: modified,
+            last_seen: modified,
+        }
+    }
+
+    /// Check if the dylib has been modified since it's been loaded. A dylib
+    /// that is still being written, i.e. that has been modified since the
+    /// last check, is not considered modified until the next check.
+    fn is_modified(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        let is_stable = modified == self.last_seen;
+        self.last_seen = modified;
+        if modified.is_some() && is_stable && modified != self.loaded {
+            // A dylib that fails to load is only retried once it's modified
+            // again
+            self.loaded = modified;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The modification time of a file, if it can be read
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
 /// Return the path of the file returned by `lazy_path` argument, if it exists.
 fn check_file_exists(lazy_path: impl Fn() -> PathBuf) -> Option<PathBuf> {
     let path = lazy_path();
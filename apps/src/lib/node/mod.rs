@@ -1,3 +1,5 @@
+pub mod doctor;
 pub mod gossip;
 pub mod ledger;
+pub mod listener;
 pub mod matchmaker;
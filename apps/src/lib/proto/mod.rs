@@ -2,4 +2,8 @@ mod generated;
 mod types;
 
 pub use generated::services;
-pub use types::{IntentMessage, RpcMessage, SubscribeTopicMessage};
+pub use types::{
+    IntentCancelMessage, IntentDryRunMessage, IntentMessage, IntentReceipt,
+    IntentTraceMessage, IntentsQueryMessage, OrderbookDepthQueryMessage,
+    RpcMessage, SubscribeTopicMessage,
+};
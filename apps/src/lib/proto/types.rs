@@ -1,6 +1,11 @@
 use std::convert::{TryFrom, TryInto};
 
-use anoma::proto::{Dkg, Error, Intent};
+use anoma::proto::{Dkg, Error, Intent, IntentCancel};
+use anoma::types::address::{self, Address};
+use anoma::types::time::DateTimeUtc;
+use borsh::BorshSerialize;
+use libp2p::identity::{ed25519, PublicKey};
+use libp2p::PeerId;
 
 use super::generated::services;
 
@@ -10,6 +15,11 @@ pub enum RpcMessage {
     IntentMessage(IntentMessage),
     SubscribeTopicMessage(SubscribeTopicMessage),
     Dkg(Dkg),
+    IntentTrace(IntentTraceMessage),
+    IntentDryRun(IntentDryRunMessage),
+    IntentCancel(IntentCancelMessage),
+    IntentsQuery(IntentsQueryMessage),
+    OrderbookDepthQuery(OrderbookDepthQueryMessage),
 }
 
 impl From<RpcMessage> for services::RpcMessage {
@@ -22,6 +32,21 @@ impl From<RpcMessage> for services::RpcMessage {
                 services::rpc_message::Message::Topic(m.into())
             }
             RpcMessage::Dkg(d) => services::rpc_message::Message::Dkg(d.into()),
+            RpcMessage::IntentTrace(m) => {
+                services::rpc_message::Message::Trace(m.into())
+            }
+            RpcMessage::IntentDryRun(m) => {
+                services::rpc_message::Message::DryRun(m.into())
+            }
+            RpcMessage::IntentCancel(m) => {
+                services::rpc_message::Message::Cancel(m.into())
+            }
+            RpcMessage::IntentsQuery(m) => {
+                services::rpc_message::Message::Intents(m.into())
+            }
+            RpcMessage::OrderbookDepthQuery(m) => {
+                services::rpc_message::Message::Depth(m.into())
+            }
         };
         services::RpcMessage {
             message: Some(message),
@@ -41,6 +66,36 @@ impl RpcMessage {
     pub fn new_dkg(dkg: Dkg) -> Self {
         RpcMessage::Dkg(dkg)
     }
+
+    pub fn new_intent_trace(intent_hash: Vec<u8>) -> Self {
+        RpcMessage::IntentTrace(IntentTraceMessage { intent_hash })
+    }
+
+    pub fn new_intent_dry_run(intent: Intent) -> Self {
+        RpcMessage::IntentDryRun(IntentDryRunMessage { intent })
+    }
+
+    pub fn new_cancel_intent(cancel: IntentCancel, topic: String) -> Self {
+        RpcMessage::IntentCancel(IntentCancelMessage { cancel, topic })
+    }
+
+    pub fn new_intents_query(topic: String) -> Self {
+        RpcMessage::IntentsQuery(IntentsQueryMessage { topic })
+    }
+
+    pub fn new_orderbook_depth_query(
+        topic: String,
+        base_token: Address,
+        quote_token: Address,
+        levels: u32,
+    ) -> Self {
+        RpcMessage::OrderbookDepthQuery(OrderbookDepthQueryMessage {
+            topic,
+            base_token,
+            quote_token,
+            levels,
+        })
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -105,6 +160,252 @@ impl SubscribeTopicMessage {
     }
 }
 
+/// A request for the hops of an intent recorded by a gossip node in the trace
+/// mode
+#[derive(Debug, PartialEq)]
+pub struct IntentTraceMessage {
+    /// The SHA-256 hash of the intent, see [`Intent::sha256`]
+    pub intent_hash: Vec<u8>,
+}
+
+impl From<services::IntentTraceMessage> for IntentTraceMessage {
+    fn from(message: services::IntentTraceMessage) -> Self {
+        IntentTraceMessage {
+            intent_hash: message.intent_hash,
+        }
+    }
+}
+
+impl From<IntentTraceMessage> for services::IntentTraceMessage {
+    fn from(message: IntentTraceMessage) -> Self {
+        services::IntentTraceMessage {
+            intent_hash: message.intent_hash,
+        }
+    }
+}
+
+/// A request for the matches that the connected matchmakers would make for an
+/// intent, without gossiping the intent or adding it to the matchmakers
+#[derive(Debug, PartialEq)]
+pub struct IntentDryRunMessage {
+    pub intent: Intent,
+}
+
+impl TryFrom<services::IntentDryRunMessage> for IntentDryRunMessage {
+    type Error = Error;
+
+    fn try_from(message: services::IntentDryRunMessage) -> Result<Self> {
+        match message.intent {
+            Some(intent) => Ok(IntentDryRunMessage {
+                intent: intent.try_into()?,
+            }),
+            None => Err(Error::NoIntentError),
+        }
+    }
+}
+
+impl From<IntentDryRunMessage> for services::IntentDryRunMessage {
+    fn from(message: IntentDryRunMessage) -> Self {
+        services::IntentDryRunMessage {
+            intent: Some(message.intent.into()),
+        }
+    }
+}
+
+/// A cancellation of an intent to be gossiped on the topic, so that the nodes
+/// drop the intent from their mempool
+#[derive(Debug, PartialEq)]
+pub struct IntentCancelMessage {
+    pub cancel: IntentCancel,
+    pub topic: String,
+}
+
+impl TryFrom<services::IntentCancelMessage> for IntentCancelMessage {
+    type Error = Error;
+
+    fn try_from(message: services::IntentCancelMessage) -> Result<Self> {
+        match message.cancel {
+            Some(cancel) => Ok(IntentCancelMessage {
+                cancel: cancel.try_into()?,
+                topic: message.topic,
+            }),
+            None => Err(Error::NoIntentError),
+        }
+    }
+}
+
+impl From<IntentCancelMessage> for services::IntentCancelMessage {
+    fn from(message: IntentCancelMessage) -> Self {
+        services::IntentCancelMessage {
+            cancel: Some(message.cancel.into()),
+            topic: message.topic,
+        }
+    }
+}
+
+/// A request for the intents held in a gossip node's mempool that have been
+/// received on the topic
+#[derive(Debug, PartialEq)]
+pub struct IntentsQueryMessage {
+    pub topic: String,
+}
+
+impl From<services::IntentsQueryMessage> for IntentsQueryMessage {
+    fn from(message: services::IntentsQueryMessage) -> Self {
+        IntentsQueryMessage {
+            topic: message.topic,
+        }
+    }
+}
+
+impl From<IntentsQueryMessage> for services::IntentsQueryMessage {
+    fn from(message: IntentsQueryMessage) -> Self {
+        services::IntentsQueryMessage {
+            topic: message.topic,
+        }
+    }
+}
+
+/// A request for the depth of the orderbook of a token pair, aggregated from
+/// the intents held in a gossip node's mempool that have been received on the
+/// topic
+#[derive(Debug, PartialEq)]
+pub struct OrderbookDepthQueryMessage {
+    pub topic: String,
+    /// The token that is bought and sold
+    pub base_token: Address,
+    /// The token in which the prices are quoted
+    pub quote_token: Address,
+    /// The maximum number of price levels on each side
+    pub levels: u32,
+}
+
+impl TryFrom<services::OrderbookDepthQueryMessage>
+    for OrderbookDepthQueryMessage
+{
+    type Error = address::Error;
+
+    fn try_from(
+        message: services::OrderbookDepthQueryMessage,
+    ) -> address::Result<Self> {
+        Ok(OrderbookDepthQueryMessage {
+            topic: message.topic,
+            base_token: Address::decode(message.base_token)?,
+            quote_token: Address::decode(message.quote_token)?,
+            levels: message.levels,
+        })
+    }
+}
+
+impl From<OrderbookDepthQueryMessage> for services::OrderbookDepthQueryMessage {
+    fn from(message: OrderbookDepthQueryMessage) -> Self {
+        services::OrderbookDepthQueryMessage {
+            topic: message.topic,
+            base_token: message.base_token.encode(),
+            quote_token: message.quote_token.encode(),
+            levels: message.levels,
+        }
+    }
+}
+
+/// A gossip node's signed proof of the time at which it received an intent,
+/// returned to the client that submitted the intent. It can be verified by
+/// anyone with the intent against the node's peer ID.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntentReceipt {
+    /// The SHA-256 hash of the intent, see [`Intent::sha256`]
+    pub intent_hash: Vec<u8>,
+    pub topic: String,
+    pub received_at: DateTimeUtc,
+    /// The node's encoded ed25519 public key
+    pub node_public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl IntentReceipt {
+    /// Sign a receipt of an intent received now with the node's key
+    pub fn new(intent: &Intent, topic: String, key: &ed25519::Keypair) -> Self {
+        let intent_hash = intent.sha256().to_vec();
+        let received_at = DateTimeUtc::now();
+        let signature =
+            key.sign(&Self::signed_bytes(&intent_hash, &topic, &received_at));
+        Self {
+            intent_hash,
+            topic,
+            received_at,
+            node_public_key: key.public().encode().to_vec(),
+            signature,
+        }
+    }
+
+    /// The bytes signed by the node
+    fn signed_bytes(
+        intent_hash: &[u8],
+        topic: &str,
+        received_at: &DateTimeUtc,
+    ) -> Vec<u8> {
+        (intent_hash, topic, received_at)
+            .try_to_vec()
+            .expect("Encoding an intent receipt shouldn't fail")
+    }
+
+    /// The peer ID of the node that signed the receipt, if its public key is
+    /// valid
+    pub fn node_peer_id(&self) -> Option<PeerId> {
+        let pk = ed25519::PublicKey::decode(&self.node_public_key).ok()?;
+        Some(PeerId::from(PublicKey::Ed25519(pk)))
+    }
+
+    /// Check that this is a receipt of the given intent with a valid
+    /// signature
+    pub fn verify(&self, intent: &Intent) -> bool {
+        if self.intent_hash != intent.sha256() {
+            return false;
+        }
+        match ed25519::PublicKey::decode(&self.node_public_key) {
+            Ok(pk) => pk.verify(
+                &Self::signed_bytes(
+                    &self.intent_hash,
+                    &self.topic,
+                    &self.received_at,
+                ),
+                &self.signature,
+            ),
+            Err(_) => false,
+        }
+    }
+}
+
+impl TryFrom<services::IntentReceipt> for IntentReceipt {
+    type Error = Error;
+
+    fn try_from(receipt: services::IntentReceipt) -> Result<Self> {
+        let received_at = match receipt.received_at {
+            Some(t) => t.try_into().map_err(Error::InvalidTimestamp)?,
+            None => return Err(Error::NoTimestampError),
+        };
+        Ok(IntentReceipt {
+            intent_hash: receipt.intent_hash,
+            topic: receipt.topic,
+            received_at,
+            node_public_key: receipt.node_public_key,
+            signature: receipt.signature,
+        })
+    }
+}
+
+impl From<IntentReceipt> for services::IntentReceipt {
+    fn from(receipt: IntentReceipt) -> Self {
+        services::IntentReceipt {
+            intent_hash: receipt.intent_hash,
+            topic: receipt.topic,
+            received_at: Some(receipt.received_at.into()),
+            node_public_key: receipt.node_public_key,
+            signature: receipt.signature,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,6 +430,99 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_intent_dry_run_message() {
+        let intent = Intent::new("arbitrary data".as_bytes().to_owned());
+        let dry_run_message = IntentDryRunMessage {
+            intent: intent.clone(),
+        };
+
+        let dry_run_rpc_message = RpcMessage::new_intent_dry_run(intent);
+        let services_rpc_message: services::RpcMessage =
+            dry_run_rpc_message.into();
+        match services_rpc_message.message {
+            Some(services::rpc_message::Message::DryRun(m)) => {
+                let message_from_types =
+                    IntentDryRunMessage::try_from(m).expect("no intent");
+                assert_eq!(dry_run_message, message_from_types);
+            }
+            _ => panic!("no intent dry-run message"),
+        }
+    }
+
+    #[test]
+    fn test_intent_cancel_message() {
+        let intent = Intent::new("arbitrary data".as_bytes().to_owned());
+        let cancel = IntentCancel::new(
+            intent.sha256().to_vec(),
+            &anoma::types::key::testing::keypair_1(),
+        );
+        let topic = "arbitrary string".to_owned();
+        let cancel_message = IntentCancelMessage {
+            cancel: cancel.clone(),
+            topic: topic.clone(),
+        };
+
+        let cancel_rpc_message = RpcMessage::new_cancel_intent(cancel, topic);
+        let services_rpc_message: services::RpcMessage =
+            cancel_rpc_message.into();
+        match services_rpc_message.message {
+            Some(services::rpc_message::Message::Cancel(m)) => {
+                let message_from_types =
+                    IntentCancelMessage::try_from(m).expect("no cancellation");
+                assert_eq!(cancel_message, message_from_types);
+            }
+            _ => panic!("no intent cancellation message"),
+        }
+    }
+
+    #[test]
+    fn test_intents_query_message() {
+        let topic = "arbitrary string".to_owned();
+        let query_message = IntentsQueryMessage {
+            topic: topic.clone(),
+        };
+
+        let query_rpc_message = RpcMessage::new_intents_query(topic);
+        let services_rpc_message: services::RpcMessage =
+            query_rpc_message.into();
+        match services_rpc_message.message {
+            Some(services::rpc_message::Message::Intents(m)) => {
+                let message_from_types = IntentsQueryMessage::from(m);
+                assert_eq!(query_message, message_from_types);
+            }
+            _ => panic!("no intents query message"),
+        }
+    }
+
+    #[test]
+    fn test_orderbook_depth_query_message() {
+        let topic = "arbitrary string".to_owned();
+        let query_message = OrderbookDepthQueryMessage {
+            topic: topic.clone(),
+            base_token: address::btc(),
+            quote_token: address::xan(),
+            levels: 10,
+        };
+
+        let query_rpc_message = RpcMessage::new_orderbook_depth_query(
+            topic,
+            address::btc(),
+            address::xan(),
+            10,
+        );
+        let services_rpc_message: services::RpcMessage =
+            query_rpc_message.into();
+        match services_rpc_message.message {
+            Some(services::rpc_message::Message::Depth(m)) => {
+                let message_from_types =
+                    OrderbookDepthQueryMessage::try_from(m).unwrap();
+                assert_eq!(query_message, message_from_types);
+            }
+            _ => panic!("no orderbook depth query message"),
+        }
+    }
+
     #[test]
     fn test_topic_message() {
         let topic = "arbitrary string".to_owned();
@@ -145,4 +539,31 @@ mod tests {
             _ => panic!("no intent message"),
         }
     }
+
+    #[test]
+    fn test_intent_receipt() {
+        let intent = Intent::new(b"arbitrary data".to_vec());
+        let key = ed25519::Keypair::generate();
+        let receipt =
+            IntentReceipt::new(&intent, "arbitrary string".to_owned(), &key);
+        assert!(receipt.verify(&intent));
+        assert_eq!(
+            receipt.node_peer_id(),
+            Some(PeerId::from(PublicKey::Ed25519(key.public())))
+        );
+
+        // The receipt survives the conversion to and from the RPC response
+        let rpc_receipt: services::IntentReceipt = receipt.clone().into();
+        let receipt = IntentReceipt::try_from(rpc_receipt).unwrap();
+        assert!(receipt.verify(&intent));
+
+        // A receipt doesn't verify for another intent or if it's tampered
+        let other_intent = Intent::new(b"other data".to_vec());
+        assert!(!receipt.verify(&other_intent));
+        let tampered = IntentReceipt {
+            topic: "other topic".to_owned(),
+            ..receipt
+        };
+        assert!(!tampered.verify(&intent));
+    }
 }
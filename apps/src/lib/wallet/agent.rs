@@ -0,0 +1,338 @@
+//! An optional wallet agent, similar to `ssh-agent`.
+//!
+//! The agent is a long-running process that caches decrypted keys for a
+//! limited time and signs data with them over a local Unix socket. The
+//! cached keys never leave the agent. When a transaction has to be signed
+//! with a key that is stored encrypted in the wallet, the client first asks
+//! the agent to sign it and only prompts for the password if the agent is not
+//! running or doesn't have the key. A key decrypted with a password is then
+//! added to the agent, so that the password doesn't have to be entered again
+//! for every transaction in a session.
+//!
+//! Every request is sent over a new connection, Borsh encoded. The client
+//! shuts down the writing half of the connection after the request and the
+//! agent replies with a Borsh encoded response. The agent handles every
+//! connection in its own thread, so a slow client doesn't hold up the others.
+//! The socket is only accessible to the user who started the agent.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anoma::types::key::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Env. var to set the path to the agent's socket
+pub const ENV_VAR_AGENT_SOCK: &str = "ANOMA_WALLET_AGENT_SOCK";
+
+/// The default file name of the agent's socket inside the wallet's directory
+const AGENT_SOCK_FILE: &str = "wallet-agent.sock";
+
+/// The default time for which the agent keeps a decrypted key
+pub const DEFAULT_KEY_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// How long a client waits for the agent's response and the agent waits for
+/// a client's request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A request to the agent
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub enum Request {
+    /// Cache a decrypted key
+    AddKey { key: common::SecretKey },
+    /// Sign the data with a cached key with the given public key hash
+    Sign { pkh: PublicKeyHash, data: Vec<u8> },
+    /// Remove all the cached keys
+    RemoveAll,
+}
+
+/// A response from the agent
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub enum Response {
+    /// The request has been handled
+    Ok,
+    /// The signature of the requested data
+    Signature(common::Signature),
+    /// The requested key is not cached
+    NotFound,
+}
+
+/// Get the path to the agent's socket. It can be set via the
+/// `ANOMA_WALLET_AGENT_SOCK` env var, otherwise it's inside the wallet's
+/// directory.
+pub fn socket_path(store_dir: &Path) -> PathBuf {
+    match env::var(ENV_VAR_AGENT_SOCK) {
+        Ok(path) => path.into(),
+        Err(_) => store_dir.join(AGENT_SOCK_FILE),
+    }
+}
+
+/// Run the agent, listening on the given socket. Cached keys are removed
+/// after the given time-to-live. Returns only on error.
+pub fn run(socket: &Path, key_ttl: Duration) -> std::io::Result<()> {
+    imp::run(socket, key_ttl)
+}
+
+/// Send a request to the agent listening on the given socket.
+pub fn request(socket: &Path, req: &Request) -> std::io::Result<Response> {
+    imp::request(socket, req)
+}
+
+/// Try to sign the data with a key cached by the agent, if it's running and
+/// it has the key of the given public key hash.
+pub fn sign(
+    socket: &Path,
+    pkh: PublicKeyHash,
+    data: Vec<u8>,
+) -> Option<common::Signature> {
+    match request(socket, &Request::Sign { pkh, data }) {
+        Ok(Response::Signature(sig)) => Some(sig),
+        _ => None,
+    }
+}
+
+/// Try to add a decrypted key to the agent, if it's running.
+pub fn add_key(socket: &Path, key: common::SecretKey) {
+    if let Err(err) = request(socket, &Request::AddKey { key }) {
+        tracing::debug!("The key has not been added to wallet agent: {}", err);
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::collections::HashMap;
+    use std::io::{self, Read, Write};
+    use std::net::Shutdown;
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use std::{fs, thread};
+
+    use super::*;
+
+    /// A decrypted key cached by the agent
+    struct CachedKey {
+        key: common::SecretKey,
+        expires_at: Instant,
+    }
+
+    /// The decrypted keys cached by the agent, shared by the threads that
+    /// handle the connections
+    type Keys = Arc<Mutex<HashMap<PublicKeyHash, CachedKey>>>;
+
+    pub fn run(socket: &Path, key_ttl: Duration) -> io::Result<()> {
+        if socket.exists() {
+            if UnixStream::connect(socket).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!(
+                        "A wallet agent is already listening on {}",
+                        socket.to_string_lossy()
+                    ),
+                ));
+            }
+            // Remove a stale socket left by an agent that's no longer running
+            fs::remove_file(socket)?;
+        }
+        let listener = bind_private(socket)?;
+        tracing::info!(
+            "Wallet agent listening on {}. Keys are cached for {} seconds.",
+            socket.to_string_lossy(),
+            key_ttl.as_secs()
+        );
+
+        let keys: Keys = Default::default();
+        for stream in listener.incoming() {
+            match stream {
+                Ok(mut stream) => {
+                    let keys = keys.clone();
+                    thread::spawn(move || {
+                        if let Err(err) = handle(&mut stream, &keys, key_ttl) {
+                            tracing::warn!(
+                                "Wallet agent request failed: {}",
+                                err
+                            );
+                        }
+                    });
+                }
+                Err(err) => {
+                    tracing::warn!("Wallet agent connection failed: {}", err)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Bind a socket that only the owner may connect to. The socket is bound
+    /// inside a new directory that only the owner can access and moved to the
+    /// given path after its permissions are restricted, so there's no time in
+    /// which others could connect to it.
+    fn bind_private(socket: &Path) -> io::Result<UnixListener> {
+        let parent = match socket.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let private_dir =
+            parent.join(format!(".wallet-agent-{}", std::process::id()));
+        fs::DirBuilder::new().mode(0o700).create(&private_dir)?;
+        let private_socket = private_dir.join(AGENT_SOCK_FILE);
+        let result = UnixListener::bind(&private_socket).and_then(|listener| {
+            fs::set_permissions(
+                &private_socket,
+                fs::Permissions::from_mode(0o600),
+            )?;
+            fs::rename(&private_socket, socket)?;
+            Ok(listener)
+        });
+        let _ = fs::remove_file(&private_socket);
+        fs::remove_dir(&private_dir)?;
+        result
+    }
+
+    fn handle(
+        stream: &mut UnixStream,
+        keys: &Keys,
+        key_ttl: Duration,
+    ) -> io::Result<()> {
+        stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+        let mut bytes = vec![];
+        stream.read_to_end(&mut bytes)?;
+        let req = Request::try_from_slice(&bytes)?;
+        let response = {
+            let mut keys = keys.lock().unwrap();
+            let now = Instant::now();
+            keys.retain(|_pkh, cached| cached.expires_at > now);
+            match req {
+                Request::AddKey { key } => {
+                    let pk: common::PublicKey = key.ref_to();
+                    let expires_at = now + key_ttl;
+                    keys.insert(
+                        PublicKeyHash::from(&pk),
+                        CachedKey { key, expires_at },
+                    );
+                    Response::Ok
+                }
+                Request::Sign { pkh, data } => match keys.get(&pkh) {
+                    Some(cached) => Response::Signature(
+                        common::SigScheme::sign(&cached.key, data),
+                    ),
+                    None => Response::NotFound,
+                },
+                Request::RemoveAll => {
+                    keys.clear();
+                    Response::Ok
+                }
+            }
+        };
+        stream.write_all(&response.try_to_vec()?)
+    }
+
+    pub fn request(socket: &Path, req: &Request) -> io::Result<Response> {
+        let mut stream = UnixStream::connect(socket)?;
+        stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+        stream.write_all(&req.try_to_vec()?)?;
+        stream.shutdown(Shutdown::Write)?;
+        let mut bytes = vec![];
+        stream.read_to_end(&mut bytes)?;
+        Response::try_from_slice(&bytes)
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io;
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::*;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "The wallet agent is only supported on Unix systems",
+        )
+    }
+
+    pub fn run(_socket: &Path, _key_ttl: Duration) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn request(_socket: &Path, _req: &Request) -> io::Result<Response> {
+        Err(unsupported())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixStream;
+    use std::thread;
+
+    use super::*;
+    use crate::wallet::store::gen_sk;
+
+    /// Start an agent in a new thread and wait for it to start listening
+    fn start_agent(socket: &Path) {
+        {
+            let socket = socket.to_owned();
+            thread::spawn(move || run(&socket, DEFAULT_KEY_TTL));
+        }
+        while request(socket, &Request::RemoveAll).is_err() {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Test that the agent caches keys and signs with them
+    #[test]
+    fn test_agent_caches_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join(AGENT_SOCK_FILE);
+        start_agent(&socket);
+
+        let key = gen_sk();
+        let pk: common::PublicKey = key.ref_to();
+        let pkh = PublicKeyHash::from(&pk);
+        let data = b"data".to_vec();
+        assert!(sign(&socket, pkh.clone(), data.clone()).is_none());
+
+        add_key(&socket, key);
+        let sig =
+            sign(&socket, pkh, data.clone()).expect("The key should be cached");
+        common::SigScheme::verify_signature_raw(&pk, &data, &sig)
+            .expect("The signature should be valid");
+
+        // Only the owner may connect to the agent
+        let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    /// Test that a client that doesn't finish its request doesn't hold up
+    /// the requests of other clients
+    #[test]
+    fn test_agent_handles_clients_concurrently() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join(AGENT_SOCK_FILE);
+        start_agent(&socket);
+
+        // A client that connects but never shuts down its writing half
+        let _stalled = UnixStream::connect(&socket).unwrap();
+        let key = gen_sk();
+        let pk: common::PublicKey = key.ref_to();
+        add_key(&socket, key);
+        // The request would time out if it had to wait for the stalled client
+        match request(
+            &socket,
+            &Request::Sign {
+                pkh: PublicKeyHash::from(&pk),
+                data: vec![],
+            },
+        ) {
+            Ok(Response::Signature(_)) => {}
+            other => panic!("Unexpected response {:?}", other),
+        }
+    }
+}
@@ -1,9 +1,11 @@
+pub mod agent;
 mod alias;
 pub mod defaults;
 mod keys;
 pub mod pre_genesis;
 mod store;
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
@@ -25,7 +27,53 @@ use crate::config::genesis::genesis_config::GenesisConfig;
 pub struct Wallet {
     store_dir: PathBuf,
     store: Store,
-    decrypted_key_cache: HashMap<Alias, Rc<common::SecretKey>>,
+    decrypted_key_cache: DecryptedKeyCache,
+}
+
+/// A cache of the decrypted keys. A clone of the cache shares the same keys,
+/// so that they can be carried over to a wallet that is loaded again from the
+/// store, e.g. by the commands of the client console.
+#[derive(Clone, Debug, Default)]
+pub struct DecryptedKeyCache(
+    Rc<RefCell<HashMap<Alias, Rc<common::SecretKey>>>>,
+);
+
+impl DecryptedKeyCache {
+    fn get(&self, alias: &Alias) -> Option<Rc<common::SecretKey>> {
+        self.0.borrow().get(alias).cloned()
+    }
+
+    fn insert(&self, alias: Alias, key: Rc<common::SecretKey>) {
+        self.0.borrow_mut().insert(alias, key);
+    }
+}
+
+/// A key to sign with. A key that is stored encrypted is used through the
+/// wallet agent, so that it doesn't have to be decrypted for every
+/// transaction.
+#[derive(Clone, Debug)]
+pub enum Signer {
+    /// A decrypted key
+    Key(Rc<common::SecretKey>),
+    /// An encrypted key, which is used through the wallet agent if the agent
+    /// has it
+    Agent(common::PublicKey),
+}
+
+impl Signer {
+    /// The public key of the signer
+    pub fn public_key(&self) -> common::PublicKey {
+        match self {
+            Signer::Key(key) => key.ref_to(),
+            Signer::Agent(pk) => pk.clone(),
+        }
+    }
+}
+
+impl From<Rc<common::SecretKey>> for Signer {
+    fn from(key: Rc<common::SecretKey>) -> Self {
+        Signer::Key(key)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -46,7 +94,7 @@ impl Wallet {
         Some(Self {
             store_dir: store_dir.to_path_buf(),
             store,
-            decrypted_key_cache: HashMap::default(),
+            decrypted_key_cache: DecryptedKeyCache::default(),
         })
     }
 
@@ -60,7 +108,7 @@ impl Wallet {
         Self {
             store_dir: store_dir.to_path_buf(),
             store,
-            decrypted_key_cache: HashMap::default(),
+            decrypted_key_cache: DecryptedKeyCache::default(),
         }
     }
 
@@ -78,7 +126,7 @@ impl Wallet {
         Self {
             store_dir: store_dir.to_path_buf(),
             store,
-            decrypted_key_cache: HashMap::default(),
+            decrypted_key_cache: DecryptedKeyCache::default(),
         }
     }
 
@@ -111,6 +159,33 @@ impl Wallet {
         (alias.into(), key)
     }
 
+    /// Generate `count` new keypairs and implicit addresses like
+    /// [`Wallet::gen_key`], but only prompt for the password once. When
+    /// more than one key is generated, the provided alias is suffixed with
+    /// the key's number, starting from 1.
+    pub fn gen_keys(
+        &mut self,
+        alias: Option<String>,
+        count: u64,
+        unsafe_dont_encrypt: bool,
+    ) -> Vec<(String, Rc<common::SecretKey>)> {
+        let password = read_and_confirm_pwd(unsafe_dont_encrypt);
+        (1..=count)
+            .map(|number| {
+                let alias = match &alias {
+                    Some(alias) if count > 1 => {
+                        Some(format!("{}-{}", alias, number))
+                    }
+                    alias => alias.clone(),
+                };
+                let (alias, key) = self.store.gen_key(alias, password.clone());
+                // Cache the newly added key
+                self.decrypted_key_cache.insert(alias.clone(), key.clone());
+                (alias.into(), key)
+            })
+            .collect()
+    }
+
     /// Generate keypair
     /// for signing protocol txs and for the DKG (which will also be stored)
     /// A protocol keypair may be optionally provided, indicating that
@@ -159,6 +234,24 @@ impl Wallet {
         self.store.validator_data()
     }
 
+    /// Get the cache of the keys decrypted by this wallet. The returned cache
+    /// is shared with the wallet.
+    pub fn decrypted_key_cache(&self) -> DecryptedKeyCache {
+        self.decrypted_key_cache.clone()
+    }
+
+    /// Use the given cache of decrypted keys, e.g. one shared with a wallet
+    /// that has been loaded before, so that its keys don't have to be
+    /// decrypted again.
+    pub fn set_decrypted_key_cache(&mut self, cache: DecryptedKeyCache) {
+        self.decrypted_key_cache = cache;
+    }
+
+    /// Get the path to the socket of the wallet agent for this wallet.
+    pub fn agent_socket(&self) -> PathBuf {
+        agent::socket_path(&self.store_dir)
+    }
+
     /// Find the stored key by an alias, a public key hash or a public key.
     /// If the key is encrypted, will prompt for password from stdin.
     /// Any keys that are decrypted are stored in and read from a cache to avoid
@@ -172,7 +265,7 @@ impl Wallet {
             .decrypted_key_cache
             .get(&alias_pkh_or_pk.as_ref().into())
         {
-            return Ok(cached_key.clone());
+            return Ok(cached_key);
         }
         // If not cached, look-up in store
         let stored_key = self
@@ -180,7 +273,8 @@ impl Wallet {
             .find_key(alias_pkh_or_pk.as_ref())
             .ok_or(FindKeyError::KeyNotFound)?;
         Self::decrypt_stored_key(
-            &mut self.decrypted_key_cache,
+            &self.decrypted_key_cache,
+            &self.store_dir,
             stored_key,
             alias_pkh_or_pk.into(),
         )
@@ -202,7 +296,7 @@ impl Wallet {
             .unwrap_or_else(|| pkh.to_string().into());
         // Try read cache
         if let Some(cached_key) = self.decrypted_key_cache.get(&alias) {
-            return Ok(cached_key.clone());
+            return Ok(cached_key);
         }
         // Look-up from store
         let stored_key = self
@@ -210,7 +304,8 @@ impl Wallet {
             .find_key_by_pk(pk)
             .ok_or(FindKeyError::KeyNotFound)?;
         Self::decrypt_stored_key(
-            &mut self.decrypted_key_cache,
+            &self.decrypted_key_cache,
+            &self.store_dir,
             stored_key,
             alias,
         )
@@ -231,7 +326,7 @@ impl Wallet {
             .unwrap_or_else(|| pkh.to_string().into());
         // Try read cache
         if let Some(cached_key) = self.decrypted_key_cache.get(&alias) {
-            return Ok(cached_key.clone());
+            return Ok(cached_key);
         }
         // Look-up from store
         let stored_key = self
@@ -239,17 +334,68 @@ impl Wallet {
             .find_key_by_pkh(pkh)
             .ok_or(FindKeyError::KeyNotFound)?;
         Self::decrypt_stored_key(
-            &mut self.decrypted_key_cache,
+            &self.decrypted_key_cache,
+            &self.store_dir,
             stored_key,
             alias,
         )
     }
 
+    /// Find the signer for a public key. A key that is stored encrypted and
+    /// hasn't been decrypted by this wallet yet is used through the wallet
+    /// agent, if its socket exists.
+    pub fn find_signer_by_pk(
+        &mut self,
+        pk: &common::PublicKey,
+    ) -> Result<Signer, FindKeyError> {
+        let pkh: PublicKeyHash = pk.into();
+        let alias = self
+            .store
+            .find_alias_by_pkh(&pkh)
+            .unwrap_or_else(|| pkh.to_string().into());
+        if let Some(cached_key) = self.decrypted_key_cache.get(&alias) {
+            return Ok(Signer::Key(cached_key));
+        }
+        match self.store.find_key_by_pk(pk) {
+            Some(StoredKeypair::Encrypted(_))
+                if self.agent_socket().exists() =>
+            {
+                Ok(Signer::Agent(pk.clone()))
+            }
+            _ => self.find_key_by_pk(pk).map(Signer::Key),
+        }
+    }
+
+    /// Sign the data with the given signer. If the signer's key is used
+    /// through the wallet agent, but the agent doesn't have it, e.g. because
+    /// its time-to-live has passed, the key is decrypted with a password from
+    /// stdin.
+    pub fn sign(
+        &mut self,
+        signer: &Signer,
+        data: &[u8],
+    ) -> Result<common::Signature, FindKeyError> {
+        match signer {
+            Signer::Key(key) => Ok(common::SigScheme::sign(key, data)),
+            Signer::Agent(pk) => {
+                if let Some(sig) =
+                    agent::sign(&self.agent_socket(), pk.into(), data.to_vec())
+                {
+                    return Ok(sig);
+                }
+                let key = self.find_key_by_pk(pk)?;
+                Ok(common::SigScheme::sign(&key, data))
+            }
+        }
+    }
+
     /// Decrypt stored key, if it's not stored un-encrypted.
     /// If a given storage key needs to be decrypted, prompt for password from
-    /// stdin and if successfully decrypted, store it in a cache.
+    /// stdin and if successfully decrypted, add it to the wallet agent, if
+    /// it's running. The decrypted key is stored in a cache.
     fn decrypt_stored_key(
-        decrypted_key_cache: &mut HashMap<Alias, Rc<common::SecretKey>>,
+        decrypted_key_cache: &DecryptedKeyCache,
+        store_dir: &Path,
         stored_key: &StoredKeypair,
         alias: Alias,
     ) -> Result<Rc<common::SecretKey>, FindKeyError> {
@@ -259,11 +405,10 @@ impl Wallet {
                 let key = encrypted
                     .decrypt(password)
                     .map_err(FindKeyError::KeyDecryptionError)?;
-                decrypted_key_cache.insert(alias.clone(), Rc::new(key));
-                decrypted_key_cache
-                    .get(&alias)
-                    .cloned()
-                    .ok_or(FindKeyError::KeyNotFound)
+                agent::add_key(&agent::socket_path(store_dir), key.clone());
+                let key = Rc::new(key);
+                decrypted_key_cache.insert(alias, key.clone());
+                Ok(key)
             }
             StoredKeypair::Raw(raw) => Ok(raw.clone()),
         }
@@ -24,6 +24,13 @@ pub enum Error {
     WasmNotFound(String),
     #[error("Error while downloading {0}: {1}")]
     ServerError(String, String),
+    #[error(
+        "The checksum of {0} doesn't match the checksums file: Got {1}, \
+         expected {2}. The WASM may be stale or tampered with."
+    )]
+    ChecksumMismatch(String, String, String),
+    #[error("Could not read file {0}")]
+    FileRead(String),
 }
 
 /// A hash map where keys are simple file names and values their full file name
@@ -58,6 +65,13 @@ impl Checksums {
         }
     }
 
+    /// Find the name of the WASM with the given SHA256 hash, if it's known
+    pub fn find_name(&self, hash: &str) -> Option<&str> {
+        self.0.iter().find_map(|(name, full_name)| {
+            (full_name.split('.').nth(1) == Some(hash)).then(|| name.as_str())
+        })
+    }
+
     /// Read WASM checksums from "checksums.json" in the given directory
     pub fn read_checksums(wasm_directory: impl AsRef<Path>) -> Self {
         let checksums_path =
@@ -142,14 +156,7 @@ pub async fn pre_fetch_wasm(wasm_directory: impl AsRef<Path>) {
                 // if the file exist, first check the hash. If not matching
                 // download it again.
                 Ok(bytes) => {
-                    let mut hasher = Sha256::new();
-                    hasher.update(bytes);
-                    let result = hex::encode(hasher.finalize());
-                    let derived_name = format!(
-                        "{}.{}.wasm",
-                        &name.split('.').collect::<Vec<&str>>()[0],
-                        result
-                    );
+                    let derived_name = checksum_name(&name, &bytes);
                     if full_name == derived_name {
                         return;
                     }
@@ -257,70 +264,116 @@ pub async fn pre_fetch_wasm(wasm_directory: impl AsRef<Path>) {
     .await;
 }
 
+/// Read the given WASM file from the WASM directory, via its full file name in
+/// the checksums file if it's listed, or from an absolute path.
 pub fn read_wasm(
     wasm_directory: impl AsRef<Path>,
     file_path: impl AsRef<Path>,
 ) -> Vec<u8> {
+    match try_read_wasm(wasm_directory, file_path, false) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("{}", err);
+            safe_exit(1);
+        }
+    }
+}
+
+/// Read the given WASM file like [`read_wasm`] and verify it against the
+/// checksums file. A custom WASM that isn't listed in the checksums file is
+/// read with a warning with its checksum, as it cannot be verified.
+pub fn read_verified_wasm(
+    wasm_directory: impl AsRef<Path>,
+    file_path: impl AsRef<Path>,
+) -> Vec<u8> {
+    match try_read_wasm(wasm_directory, file_path, true) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("{}", err);
+            safe_exit(1);
+        }
+    }
+}
+
+fn try_read_wasm(
+    wasm_directory: impl AsRef<Path>,
+    file_path: impl AsRef<Path>,
+    verify: bool,
+) -> Result<Vec<u8>, Error> {
+    let file_path = file_path.as_ref();
+    let name = file_path
+        .file_name()
+        .and_then(|os_name| os_name.to_str())
+        .ok_or_else(|| {
+            Error::FileRead(file_path.to_string_lossy().into_owned())
+        })?;
+
     // load json with wasm hashes
     let checksums = Checksums::read_checksums(&wasm_directory);
 
-    if let Some(os_name) = file_path.as_ref().file_name() {
-        if let Some(name) = os_name.to_str() {
-            match checksums.0.get(name) {
-                Some(wasm_filename) => {
-                    let wasm_path = wasm_directory.as_ref().join(wasm_filename);
-                    match fs::read(&wasm_path) {
-                        Ok(bytes) => {
-                            return bytes;
-                        }
-                        Err(_) => {
-                            eprintln!(
-                                "File {} not found. ",
-                                wasm_path.to_string_lossy()
-                            );
-                            safe_exit(1);
-                        }
-                    }
-                }
-                None => {
-                    if !file_path.as_ref().is_absolute() {
-                        match fs::read(
-                            wasm_directory.as_ref().join(file_path.as_ref()),
-                        ) {
-                            Ok(bytes) => {
-                                return bytes;
-                            }
-                            Err(_) => {
-                                eprintln!(
-                                    "Could not read file {}. ",
-                                    file_path.as_ref().to_string_lossy()
-                                );
-                                safe_exit(1);
-                            }
-                        }
-                    } else {
-                        match fs::read(file_path.as_ref()) {
-                            Ok(bytes) => {
-                                return bytes;
-                            }
-                            Err(_) => {
-                                eprintln!(
-                                    "Could not read file {}. ",
-                                    file_path.as_ref().to_string_lossy()
-                                );
-                                safe_exit(1);
-                            }
-                        }
-                    }
-                }
+    match checksums.0.get(name) {
+        Some(wasm_filename) => {
+            let wasm_path = wasm_directory.as_ref().join(wasm_filename);
+            let bytes = fs::read(&wasm_path).map_err(|_| {
+                Error::FileRead(wasm_path.to_string_lossy().into_owned())
+            })?;
+            if verify {
+                verify_checksum(name, wasm_filename, &bytes)?;
+            }
+            Ok(bytes)
+        }
+        None => {
+            let wasm_path = if file_path.is_absolute() {
+                file_path.to_owned()
+            } else {
+                wasm_directory.as_ref().join(file_path)
+            };
+            let bytes = fs::read(&wasm_path).map_err(|_| {
+                Error::FileRead(wasm_path.to_string_lossy().into_owned())
+            })?;
+            if verify {
+                eprintln!(
+                    "Warning: {} is not listed in the checksums file and \
+                     cannot be verified. Its full name is {}.",
+                    file_path.to_string_lossy(),
+                    checksum_name(name, &bytes)
+                );
             }
+            Ok(bytes)
         }
     }
-    eprintln!(
-        "File  {} does not exist.",
-        file_path.as_ref().to_string_lossy()
-    );
-    safe_exit(1);
+}
+
+/// The full file name of a WASM with the given simple file name, including
+/// the SHA256 hash of its bytes
+fn checksum_name(name: &str, bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    let result = hex::encode(hasher.finalize());
+    format!(
+        "{}.{}.wasm",
+        &name.split('.').collect::<Vec<&str>>()[0],
+        result
+    )
+}
+
+/// Verify that the SHA256 hash of a WASM's bytes matches its full file name
+/// in the checksums file
+pub fn verify_checksum(
+    name: &str,
+    full_name: &str,
+    bytes: &[u8],
+) -> Result<(), Error> {
+    let derived_name = checksum_name(name, bytes);
+    if derived_name == full_name {
+        Ok(())
+    } else {
+        Err(Error::ChecksumMismatch(
+            name.to_owned(),
+            derived_name,
+            full_name.to_owned(),
+        ))
+    }
 }
 
 async fn download_wasm(url: String) -> Result<Vec<u8>, Error> {
@@ -344,3 +397,63 @@ async fn download_wasm(url: String) -> Result<Vec<u8>, Error> {
         Err(e) => Err(Error::Download(url, e)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that a WASM is only read if it matches the checksums file
+    #[test]
+    fn test_verify_checksum() {
+        let code = b"tx code";
+        let full_name = checksum_name("tx_test.wasm", code);
+        assert!(full_name.starts_with("tx_test."));
+        verify_checksum("tx_test.wasm", &full_name, code).unwrap();
+        assert!(matches!(
+            verify_checksum("tx_test.wasm", &full_name, b"other code"),
+            Err(Error::ChecksumMismatch(_, _, _))
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let checksums =
+            Checksums([("tx_test.wasm".to_owned(), full_name.clone())].into());
+        fs::write(
+            dir.path().join(DEFAULT_WASM_CHECKSUMS_FILE),
+            serde_json::to_vec(&checksums).unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.path().join(&full_name), code).unwrap();
+        assert_eq!(
+            read_verified_wasm(dir.path(), "tx_test.wasm"),
+            code.to_vec()
+        );
+
+        // Only the client verifies the checksums, the node reads a WASM
+        // that doesn't match as before
+        fs::write(dir.path().join(&full_name), b"other code").unwrap();
+        assert!(matches!(
+            try_read_wasm(dir.path(), "tx_test.wasm", true),
+            Err(Error::ChecksumMismatch(_, _, _))
+        ));
+        assert_eq!(read_wasm(dir.path(), "tx_test.wasm"), b"other code");
+    }
+
+    /// Test that an unlisted WASM is read both with and without verification
+    #[test]
+    fn test_read_unlisted_wasm() {
+        let code = b"custom tx code";
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(DEFAULT_WASM_CHECKSUMS_FILE),
+            serde_json::to_vec(&Checksums(HashMap::new())).unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.path().join("tx_custom.wasm"), code).unwrap();
+
+        assert_eq!(read_wasm(dir.path(), "tx_custom.wasm"), code.to_vec());
+        assert_eq!(
+            read_verified_wasm(dir.path(), dir.path().join("tx_custom.wasm")),
+            code.to_vec()
+        );
+    }
+}
@@ -74,9 +74,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let init_account_schema = transaction::InitAccount::schema_container();
     let init_validator_schema = transaction::InitValidator::schema_container();
     let token_transfer_schema = token::Transfer::schema_container();
+    let token_disbursement_schema = token::Disbursement::schema_container();
     let update_vp_schema = transaction::UpdateVp::schema_container();
     let pos_bond_schema = pos::Bond::schema_container();
     let pos_withdraw_schema = pos::Withdraw::schema_container();
+    let pos_commission_change_schema =
+        pos::CommissionChange::schema_container();
     let wrapper_tx_schema = transaction::WrapperTx::schema_container();
     // TODO derive BorshSchema after <https://github.com/near/borsh-rs/issues/82>
     // let tx_result_schema = transaction::TxResult::schema_container();
@@ -100,9 +103,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     definitions.extend(init_account_schema.definitions);
     definitions.extend(init_validator_schema.definitions);
     definitions.extend(token_transfer_schema.definitions);
+    definitions.extend(token_disbursement_schema.definitions);
     definitions.extend(update_vp_schema.definitions);
     definitions.extend(pos_bond_schema.definitions);
     definitions.extend(pos_withdraw_schema.definitions);
+    definitions.extend(pos_commission_change_schema.definitions);
     definitions.extend(wrapper_tx_schema.definitions);
     // definitions.extend(tx_result_schema.definitions);
     definitions.extend(tx_type_schema.definitions);
@@ -190,6 +195,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/token/struct.Transfer.html");
     tables.push(token_transfer_table);
 
+    let token_disbursement_definition = definitions
+        .remove(&token_disbursement_schema.declaration)
+        .unwrap();
+    let token_disbursement_table = definition_to_table(
+        token_disbursement_schema.declaration,
+        token_disbursement_definition,
+    ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/token/struct.Disbursement.html");
+    tables.push(token_disbursement_table);
+
     let update_vp_definition =
         definitions.remove(&update_vp_schema.declaration).unwrap();
     let update_vp_table =
@@ -211,6 +225,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.Withdraw.html");
     tables.push(pos_withdraw_table);
 
+    let pos_commission_change_definition = definitions
+        .remove(&pos_commission_change_schema.declaration)
+        .unwrap();
+    let pos_commission_change_table = definition_to_table(
+        pos_commission_change_schema.declaration,
+        pos_commission_change_definition,
+    ).with_rust_doc_link("https://dev.anoma.net/master/rustdoc/anoma/types/transaction/pos/struct.CommissionChange.html");
+    tables.push(pos_commission_change_table);
+
     let wrapper_tx_definition =
         definitions.remove(&wrapper_tx_schema.declaration).unwrap();
     let wrapper_tx_table = definition_to_table(
@@ -10,7 +10,9 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput, ItemFn};
 
-/// Generate WASM binding for a transaction main entrypoint function.
+/// Generate WASM binding for a transaction main entrypoint function. The
+/// version of the host functions ABI is embedded in the WASM in a custom
+/// section, which is checked by the VM.
 ///
 /// This macro expects a function with signature:
 ///
@@ -21,11 +23,14 @@ use syn::{parse_macro_input, DeriveInput, ItemFn};
 pub fn transaction(_attr: TokenStream, input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as ItemFn);
     let ident = &ast.sig.ident;
+    let abi_version = abi_version();
     let gen = quote! {
         // Use `wee_alloc` as the global allocator.
         #[global_allocator]
         static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+        #abi_version
+
         #ast
 
         // The module entrypoint callable by wasm runtime
@@ -44,7 +49,9 @@ pub fn transaction(_attr: TokenStream, input: TokenStream) -> TokenStream {
     TokenStream::from(gen)
 }
 
-/// Generate WASM binding for validity predicate main entrypoint function.
+/// Generate WASM binding for validity predicate main entrypoint function. The
+/// version of the host functions ABI is embedded in the WASM in a custom
+/// section, which is checked by the VM.
 ///
 /// This macro expects a function with signature:
 ///
@@ -63,11 +70,14 @@ pub fn validity_predicate(
 ) -> TokenStream {
     let ast = parse_macro_input!(input as ItemFn);
     let ident = &ast.sig.ident;
+    let abi_version = abi_version();
     let gen = quote! {
         // Use `wee_alloc` as the global allocator.
         #[global_allocator]
         static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+        #abi_version
+
         #ast
 
         // The module entrypoint callable by wasm runtime
@@ -124,11 +134,24 @@ pub fn validity_predicate(
     TokenStream::from(gen)
 }
 
+/// The custom section with the version of the host functions ABI, which must
+/// be named the same as `anoma::vm::WASM_ABI_VERSION_SECTION`. The section
+/// is only added when building for wasm, because the section names of the
+/// native targets are platform specific.
+fn abi_version() -> impl quote::ToTokens {
+    quote! {
+        #[cfg_attr(target_arch = "wasm32", link_section = "anoma_abi_version")]
+        #[used]
+        static _ABI_VERSION: [u8; 4] = WASM_ABI_VERSION.to_le_bytes();
+    }
+}
+
 /// Derive dynamic library binding for a matchmaker implementation.
 ///
 /// This macro requires that the data structure implements
-/// [`std::default::Default`] that is used to instantiate the matchmaker and
-/// `anoma::types::matchmaker::AddIntent` to implement a custom matchmaker
+/// [`std::default::Default`] that is used to instantiate the matchmaker,
+/// [`std::clone::Clone`] that is used to dry-run intents on a copy of its state
+/// and `anoma::types::matchmaker::AddIntent` to implement a custom matchmaker
 /// algorithm.
 ///
 /// # Examples
@@ -137,7 +160,7 @@ pub fn validity_predicate(
 /// use anoma::types::matchmaker::AddIntent;
 /// use anoma_macros::Matchmaker;
 ///
-/// #[derive(Default, Matchmaker)]
+/// #[derive(Clone, Default, Matchmaker)]
 /// struct Matchmaker;
 ///
 /// impl AddIntent for Matchmaker {
@@ -188,6 +211,49 @@ pub fn matchmaker(input: TokenStream) -> TokenStream {
             unsafe { std::ptr::write(state_ptr, state) };
             result
         }
+
+        /// Ask the matchmaker how it would match a new intent, without
+        /// changing its state
+        #[allow(clippy::ptr_arg)]
+        #[no_mangle]
+        #[automatically_derived]
+        fn _dry_run_intent(
+            state_ptr: *mut std::ffi::c_void,
+            intent_id: &Vec<u8>,
+            intent_data: &Vec<u8>,
+        ) -> anoma::types::matchmaker::AddIntentResult {
+            let state = unsafe { &*(state_ptr as *const #ident) };
+            state.dry_run_intent(intent_id, intent_data)
+        }
+
+        /// Set the strategy with which the matchmaker matches intents
+        #[no_mangle]
+        #[automatically_derived]
+        fn _set_strategy(
+            state_ptr: *mut std::ffi::c_void,
+            strategy: anoma::types::matchmaker::MatchingStrategy,
+        ) {
+            let state = unsafe { &mut *(state_ptr as *mut #ident) };
+            state.set_strategy(strategy)
+        }
+
+        /// Set whether the matchmaker matches intents in batch auctions
+        #[no_mangle]
+        #[automatically_derived]
+        fn _set_batch_auction(state_ptr: *mut std::ffi::c_void, enabled: bool) {
+            let state = unsafe { &mut *(state_ptr as *mut #ident) };
+            state.set_batch_auction(enabled)
+        }
+
+        /// Clear the batch of intents collected by the matchmaker
+        #[no_mangle]
+        #[automatically_derived]
+        fn _clear_batch(
+            state_ptr: *mut std::ffi::c_void,
+        ) -> anoma::types::matchmaker::AddIntentResult {
+            let state = unsafe { &mut *(state_ptr as *mut #ident) };
+            state.clear_batch()
+        }
     };
     TokenStream::from(gen)
 }
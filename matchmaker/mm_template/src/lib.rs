@@ -1,7 +1,7 @@
 use anoma::types::matchmaker::{AddIntent, AddIntentResult};
 use anoma_macros::Matchmaker;
 
-#[derive(Default, Matchmaker)]
+#[derive(Clone, Default, Matchmaker)]
 struct MyMatchmaker;
 
 impl AddIntent for MyMatchmaker {
@@ -0,0 +1,387 @@
+//! The batch auctions, in which the exchanges collected during a batch
+//! interval are matched together at a uniform clearing price per token pair,
+//! instead of as soon as their intent is added.
+//!
+//! For a token pair, the exchanges that sell the base token for the quote
+//! token are the asks and the ones that sell the quote token for the base
+//! token are the bids, with their limit prices in units of the quote token per
+//! unit of the base token. The clearing price is the limit price at which the
+//! largest amount of the base token is exchanged. The asks at or below it and
+//! the bids at or above it are filled in the order of their prices and then
+//! of their time priority. An exchange that doesn't fill in parts is only
+//! matched if it's filled completely.
+//!
+//! The matches of all the pairs are settled by a single tx, in which an
+//! address can only have one exchange. The exchanges that aren't matched are
+//! kept for the next batch.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use anoma::types::address::Address;
+use anoma::types::intent::MatchedExchanges;
+use anoma::types::matchmaker::AddIntentResult;
+use anoma::types::token;
+use petgraph::graph::{DiGraph, NodeIndex};
+
+use super::{create_transfer, min_rate, settle, ExchangeNode};
+
+/// The relative difference under which two prices are the same
+const PRICE_EPSILON: f64 = 1e-9;
+
+/// The maximum number of rounds in which the filled amounts of the asks and
+/// the bids are balanced, see [`fills`]
+const MAX_BALANCING_ROUNDS: usize = 16;
+
+/// An exchange of a token pair at its limit price
+#[derive(Debug, Clone, Copy)]
+struct Order {
+    index: NodeIndex,
+    /// The amount of the quote token per unit of the base token
+    price: f64,
+    /// The order in which the exchange has been received
+    sequence: u64,
+}
+
+/// The amount of the base token sold by an ask or bought by a bid
+type Fill = (NodeIndex, token::Amount);
+
+/// Clear the batch of the exchanges in the graph. If any of them cross,
+/// returns the tx bytes and the matched intent IDs.
+pub fn clear(
+    graph: &mut DiGraph<ExchangeNode, Address>,
+) -> Option<AddIntentResult> {
+    let pairs: BTreeSet<(Address, Address)> = graph
+        .raw_nodes()
+        .iter()
+        .map(|node| {
+            let exchange = &node.weight.exchange.data;
+            let sell = exchange.token_sell.clone();
+            let buy = exchange.token_buy.clone();
+            if sell < buy {
+                (sell, buy)
+            } else {
+                (buy, sell)
+            }
+        })
+        .collect();
+
+    let mut used_addrs: HashSet<Address> = HashSet::new();
+    let mut matched: Vec<NodeIndex> = vec![];
+    let mut tx_data = MatchedExchanges::empty();
+    for (base, quote) in pairs {
+        let (asks, bids) = orders(graph, &base, &quote, &used_addrs);
+        let (indices, transfers) = match match_pair(graph, &asks, &bids) {
+            Some(matches) => matches,
+            None => continue,
+        };
+        for index in &indices {
+            let node = &graph[*index];
+            used_addrs.insert(node.exchange.data.addr.clone());
+            node.add_to_tx_data(&mut tx_data);
+        }
+        tx_data.transfers.extend(transfers);
+        matched.extend(indices);
+    }
+    if matched.is_empty() {
+        println!("no crossing exchanges in the batch");
+        return None;
+    }
+    println!("tx data: {:?}", tx_data.transfers);
+    // The exchanges of all the pairs are settled together
+    Some(settle(graph, &[matched], tx_data))
+}
+
+/// The asks and the bids of a token pair. An address can only have one
+/// exchange in the tx, so only its earliest received exchange is kept and
+/// the addresses already matched on other pairs are skipped.
+fn orders(
+    graph: &DiGraph<ExchangeNode, Address>,
+    base: &Address,
+    quote: &Address,
+    used_addrs: &HashSet<Address>,
+) -> (Vec<Order>, Vec<Order>) {
+    let mut indices: Vec<NodeIndex> = graph.node_indices().collect();
+    indices.sort_by_key(|index| graph[*index].sequence);
+    let mut addrs = used_addrs.clone();
+    let mut asks = vec![];
+    let mut bids = vec![];
+    for index in indices {
+        let node = &graph[index];
+        let exchange = &node.exchange.data;
+        let is_ask =
+            &exchange.token_sell == base && &exchange.token_buy == quote;
+        let is_bid =
+            &exchange.token_sell == quote && &exchange.token_buy == base;
+        if !(is_ask || is_bid)
+            || node.remaining() == token::Amount::default()
+            || !addrs.insert(exchange.addr.clone())
+        {
+            continue;
+        }
+        let rate = limit_rate(node);
+        if is_ask {
+            asks.push(Order {
+                index,
+                price: rate,
+                sequence: node.sequence,
+            });
+        } else {
+            // A bid pays at most the inverse of its rate for a unit of the
+            // base token
+            bids.push(Order {
+                index,
+                price: 1.0 / rate,
+                sequence: node.sequence,
+            });
+        }
+    }
+    (asks, bids)
+}
+
+/// Match the asks and the bids of a token pair at their clearing price. If
+/// any of them cross, returns the matched exchanges and their transfers, in
+/// which every ask sells its base token to the bids and receives their quote
+/// token at the clearing price.
+fn match_pair(
+    graph: &DiGraph<ExchangeNode, Address>,
+    asks: &[Order],
+    bids: &[Order],
+) -> Option<(Vec<NodeIndex>, Vec<token::Transfer>)> {
+    let price = clearing_price(graph, asks, bids)?;
+    let (ask_fills, bid_fills) = fills(graph, asks, bids, price)?;
+    println!("clearing a token pair at the price {}", price);
+
+    let mut transfers = vec![];
+    let mut sold: HashMap<NodeIndex, token::Amount> = HashMap::new();
+    let mut received: HashMap<NodeIndex, token::Amount> = HashMap::new();
+    let mut bid_fills = bid_fills.into_iter();
+    let mut bid_fill = bid_fills.next();
+    for (ask, mut ask_left) in ask_fills {
+        while ask_left != token::Amount::default() {
+            let (bid, bid_left) = bid_fill.as_mut()?;
+            let bid = *bid;
+            let base = ask_left.min(*bid_left);
+            let quote = quote_amount(&graph[ask], base, price);
+            transfers.push(create_transfer(&graph[ask], &graph[bid], base));
+            transfers.push(create_transfer(&graph[bid], &graph[ask], quote));
+            *sold.entry(ask).or_default() += base;
+            *received.entry(bid).or_default() += base;
+            *sold.entry(bid).or_default() += quote;
+            *received.entry(ask).or_default() += quote;
+            ask_left -= base;
+            *bid_left -= base;
+            if *bid_left == token::Amount::default() {
+                bid_fill = bid_fills.next();
+            }
+        }
+    }
+
+    // The amounts are rounded, so they're checked against the exchanges
+    let indices: Vec<NodeIndex> = sold.keys().copied().collect();
+    let is_valid = indices.iter().all(|index| {
+        let received = received.get(index).copied().unwrap_or_default();
+        is_filled(&graph[*index], sold[index], received)
+    });
+    if !is_valid {
+        println!("the exchanges cannot be filled at the clearing price");
+        return None;
+    }
+    Some((indices, transfers))
+}
+
+/// The amount of the quote token paid to an ask for the given amount of the
+/// base token at the clearing price. It's rounded up if the ask is at its
+/// limit price, so that it receives at least its rate, or down otherwise, in
+/// favour of the bid.
+fn quote_amount(
+    ask: &ExchangeNode,
+    base: token::Amount,
+    price: f64,
+) -> token::Amount {
+    let quote = u64::from(base) as f64 * price;
+    let quote = if compare_prices(limit_rate(ask), price) == Ordering::Equal {
+        quote.ceil()
+    } else {
+        quote.floor()
+    };
+    token::Amount::from(quote as u64)
+}
+
+/// The limit price at which the largest amount of the base token is
+/// exchanged and, for the same amount, with the smallest difference between
+/// the amounts offered by the asks and the bids, if any
+fn clearing_price(
+    graph: &DiGraph<ExchangeNode, Address>,
+    asks: &[Order],
+    bids: &[Order],
+) -> Option<f64> {
+    let mut best: Option<(f64, f64, f64)> = None;
+    let prices = asks
+        .iter()
+        .chain(bids)
+        .map(|order| order.price)
+        .filter(|price| price.is_finite() && *price > 0.0);
+    for price in prices {
+        let supply: f64 = asks
+            .iter()
+            .filter(|ask| compare_prices(ask.price, price) != Ordering::Greater)
+            .map(|ask| f64::from(graph[ask.index].remaining()))
+            .sum();
+        let demand: f64 = bids
+            .iter()
+            .filter(|bid| compare_prices(bid.price, price) != Ordering::Less)
+            .map(|bid| f64::from(graph[bid.index].remaining()) / price)
+            .sum();
+        let volume = supply.min(demand);
+        let imbalance = (supply - demand).abs();
+        let is_better =
+            best.map_or(true, |(_, best_volume, best_imbalance)| {
+                volume > best_volume
+                    || (volume == best_volume && imbalance < best_imbalance)
+            });
+        if volume > 0.0 && is_better {
+            best = Some((price, volume, imbalance));
+        }
+    }
+    best.map(|(price, _volume, _imbalance)| price)
+}
+
+/// The amounts of the base token sold by the asks and bought by the bids
+/// that cross at the clearing price, in the order of their prices and then of
+/// their time priority. The exchanges that don't fill in parts and don't fit
+/// in the exchanged amount are skipped, so the amounts of both sides are
+/// balanced in rounds until they're equal.
+fn fills(
+    graph: &DiGraph<ExchangeNode, Address>,
+    asks: &[Order],
+    bids: &[Order],
+    price: f64,
+) -> Option<(Vec<Fill>, Vec<Fill>)> {
+    let mut asks: Vec<Order> = asks
+        .iter()
+        .filter(|ask| compare_prices(ask.price, price) != Ordering::Greater)
+        .copied()
+        .collect();
+    asks.sort_by(|a, b| {
+        compare_prices(a.price, b.price).then(a.sequence.cmp(&b.sequence))
+    });
+    let mut bids: Vec<Order> = bids
+        .iter()
+        .filter(|bid| compare_prices(bid.price, price) != Ordering::Less)
+        .copied()
+        .collect();
+    bids.sort_by(|a, b| {
+        compare_prices(b.price, a.price).then(a.sequence.cmp(&b.sequence))
+    });
+    let in_parts =
+        |order: &Order| graph[order.index].exchange.data.fills_in_parts();
+    let ask_sizes: Vec<(NodeIndex, token::Amount, bool)> = asks
+        .iter()
+        .map(|ask| (ask.index, graph[ask.index].remaining(), in_parts(ask)))
+        .collect();
+    // The amount of the base token that a bid can buy at the clearing price,
+    // rounded down
+    let bid_sizes: Vec<(NodeIndex, token::Amount, bool)> = bids
+        .iter()
+        .map(|bid| {
+            let quote = u64::from(graph[bid.index].remaining()) as f64;
+            let base = token::Amount::from((quote / price).floor() as u64);
+            (bid.index, base, in_parts(bid))
+        })
+        .collect();
+
+    let mut volume = ask_sizes
+        .iter()
+        .fold(token::Amount::default(), |total, (_index, size, _)| {
+            total + *size
+        });
+    for _ in 0..MAX_BALANCING_ROUNDS {
+        let ask_fills = allocate(&ask_sizes, volume);
+        let bid_fills = allocate(&bid_sizes, volume);
+        let sold = total(&ask_fills);
+        let bought = total(&bid_fills);
+        if sold == bought {
+            return (sold != token::Amount::default())
+                .then(|| (ask_fills, bid_fills));
+        }
+        volume = sold.min(bought);
+    }
+    None
+}
+
+/// Allocate up to the given amount to the exchanges in order. An exchange
+/// that doesn't fill in parts is skipped if its whole amount doesn't fit.
+fn allocate(
+    sizes: &[(NodeIndex, token::Amount, bool)],
+    volume: token::Amount,
+) -> Vec<Fill> {
+    let mut left = volume;
+    let mut fills = vec![];
+    for (index, size, in_parts) in sizes {
+        let fill = if *size <= left {
+            *size
+        } else if *in_parts {
+            left
+        } else {
+            continue;
+        };
+        if fill != token::Amount::default() {
+            left -= fill;
+            fills.push((*index, fill));
+        }
+    }
+    fills
+}
+
+/// The total filled amount
+fn total(fills: &[Fill]) -> token::Amount {
+    fills
+        .iter()
+        .fold(token::Amount::default(), |total, (_index, amount)| {
+            total + *amount
+        })
+}
+
+/// The minimum amount of the `token_buy` per unit of the `token_sell` at
+/// which an exchange can be filled, see [`min_rate`]. An exchange that
+/// doesn't fill in parts must also buy its `min_buy` with its remaining
+/// amount.
+fn limit_rate(node: &ExchangeNode) -> f64 {
+    let exchange = &node.exchange.data;
+    let rate = min_rate(exchange);
+    if exchange.fills_in_parts() {
+        rate
+    } else {
+        rate.max(f64::from(exchange.min_buy) / f64::from(node.remaining()))
+    }
+}
+
+/// Check that an exchange can sell and receive the given amounts
+fn is_filled(
+    node: &ExchangeNode,
+    sold: token::Amount,
+    received: token::Amount,
+) -> bool {
+    let exchange = &node.exchange.data;
+    let min_buy = if exchange.fills_in_parts() {
+        exchange.min_buy_for(sold)
+    } else {
+        exchange.min_buy
+    };
+    sold <= node.remaining()
+        && received != token::Amount::default()
+        && received >= min_buy
+        // up to the precision of the rates
+        && f64::from(received)
+            >= f64::from(sold) * min_rate(exchange) * (1.0 - PRICE_EPSILON)
+}
+
+/// Compare prices, which are the same up to [`PRICE_EPSILON`]
+fn compare_prices(a: f64, b: f64) -> Ordering {
+    if (a - b).abs() <= PRICE_EPSILON * a.abs().max(b.abs()) {
+        Ordering::Equal
+    } else {
+        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+    }
+}
@@ -1,23 +1,42 @@
+mod batch;
+mod strategy;
+
 use std::collections::{HashMap, HashSet, VecDeque};
 
+use anoma::proto::Signed;
 use anoma::types::address::Address;
-use anoma::types::intent::{Exchange, FungibleTokenIntent, MatchedExchanges};
-use anoma::types::matchmaker::{AddIntent, AddIntentResult};
+use anoma::types::intent::{
+    Exchange, FungibleTokenIntent, MatchedExchanges, NftTransfer,
+    RevealedRingIntent, RingSettlement,
+};
+use anoma::types::matchmaker::{AddIntent, AddIntentResult, MatchingStrategy};
+use anoma::types::time::DateTimeUtc;
 use anoma::types::token;
 use anoma_macros::Matchmaker;
 use borsh::{BorshDeserialize, BorshSerialize};
 use good_lp::{
     constraint, default_solver, variable, variables, Expression,
-    ResolutionError, SolverModel, Variable, VariableDefinition,
+    ResolutionError, Solution, SolverModel, Variable,
 };
 use petgraph::graph::{node_index, DiGraph, NodeIndex};
-use petgraph::visit::{depth_first_search, Control, DfsEvent, EdgeRef};
+use petgraph::visit::{depth_first_search, Control, DfsEvent};
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
+use strategy::{candidates, Strategy};
 
-#[derive(Default, Matchmaker)]
+#[derive(Clone, Default, Matchmaker)]
 struct TokenExchange {
     graph: DiGraph<ExchangeNode, Address>,
+    /// The exchanges of tokens of NFTs, which are matched in pairs outside
+    /// of the graph
+    nft_orders: Vec<ExchangeNode>,
+    /// The strategy with which the exchanges of a new intent are matched
+    strategy: MatchingStrategy,
+    /// The sequence of the next received exchange
+    next_sequence: u64,
+    /// Whether the exchanges are matched in batch auctions, in which case
+    /// they're only collected until the batch is cleared
+    batch_auction: bool,
 }
 
 impl AddIntent for TokenExchange {
@@ -26,34 +45,131 @@ impl AddIntent for TokenExchange {
         intent_id: &Vec<u8>,
         intent_data: &Vec<u8>,
     ) -> AddIntentResult {
-        let intent = decode_intent_data(&intent_data[..]);
-        let exchanges = intent.data.exchange.clone();
+        let (exchanges, intent) = match decode_intent_data(&intent_data[..]) {
+            Some(intent) => intent,
+            None => {
+                // e.g. a ring-signed intent, which can only be matched once
+                // its source is revealed
+                println!("skipping an intent that cannot be decoded");
+                return AddIntentResult::default();
+            }
+        };
+        // An intent that is known already, e.g. because it's been gossiped
+        // again, is skipped to keep the filled amounts of its exchanges
+        if self
+            .graph
+            .raw_nodes()
+            .iter()
+            .any(|node| &node.weight.id == intent_id)
+            || self.nft_orders.iter().any(|order| &order.id == intent_id)
+        {
+            println!("skipping a known intent");
+            return AddIntentResult::default();
+        }
+        // Expired exchanges are no longer matched
+        let now = DateTimeUtc::now();
+        remove_expired(&mut self.graph, &mut self.nft_orders, now);
 
         println!("trying to match new intent");
-        exchanges.into_iter().for_each(|exchange| {
-            add_intent_node(
-                &mut self.graph,
-                intent_id.to_vec(),
-                exchange,
-                intent.clone(),
-            )
-        });
-        let (tx, matched_intents) = match try_match(&mut self.graph) {
-            Some((tx, matched_intents)) => (Some(tx), Some(matched_intents)),
-            None => (None, None),
-        };
-        AddIntentResult {
-            tx,
-            matched_intents,
+        let mut takers = vec![];
+        exchanges
+            .into_iter()
+            .filter(|exchange| !exchange.data.is_expired(now))
+            .for_each(|exchange| {
+                let sequence = self.next_sequence;
+                self.next_sequence += 1;
+                if exchange.data.nft.is_some() {
+                    self.nft_orders.push(ExchangeNode {
+                        id: intent_id.to_vec(),
+                        exchange,
+                        intent: intent.clone(),
+                        filled: token::Amount::default(),
+                        sequence,
+                    })
+                } else {
+                    takers.push(add_intent_node(
+                        &mut self.graph,
+                        intent_id.to_vec(),
+                        exchange,
+                        intent.clone(),
+                        sequence,
+                    ))
+                }
+            });
+        if self.batch_auction {
+            // The exchanges of fungible tokens are matched once the batch is
+            // cleared
+            return try_match_nft(&mut self.nft_orders).unwrap_or_default();
         }
+        let strategy = strategy::strategy(self.strategy);
+        try_match(&mut self.graph, &takers, strategy)
+            .or_else(|| try_match_nft(&mut self.nft_orders))
+            .unwrap_or_default()
+    }
+
+    fn set_strategy(&mut self, strategy: MatchingStrategy) {
+        println!("matching intents with the strategy {:?}", strategy);
+        self.strategy = strategy;
+    }
+
+    fn set_batch_auction(&mut self, enabled: bool) {
+        if enabled {
+            println!("matching intents in batch auctions");
+        }
+        self.batch_auction = enabled;
+    }
+
+    fn clear_batch(&mut self) -> AddIntentResult {
+        // Expired exchanges are no longer matched
+        let now = DateTimeUtc::now();
+        remove_expired(&mut self.graph, &mut self.nft_orders, now);
+
+        batch::clear(&mut self.graph).unwrap_or_default()
     }
 }
 
+/// The intent that authorizes the settlement of an exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum NodeIntent {
+    /// A fungible token intent signed by the exchange's source
+    Signed(Signed<FungibleTokenIntent>),
+    /// A ring-signed intent revealed by the exchange's source
+    Ring(Signed<RingSettlement>),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ExchangeNode {
     id: Vec<u8>,
-    exchange: anoma::proto::Signed<Exchange>,
-    intent: anoma::proto::Signed<FungibleTokenIntent>,
+    exchange: Signed<Exchange>,
+    intent: NodeIntent,
+    /// The amount sold by the matched parts of an exchange filled in parts
+    filled: token::Amount,
+    /// The order in which the exchange has been received, for time priority
+    sequence: u64,
+}
+
+impl ExchangeNode {
+    /// The amount of the `token_sell` that can still be sold
+    fn remaining(&self) -> token::Amount {
+        self.exchange.data.max_sell - self.filled
+    }
+
+    /// Add the exchange and the intent that authorizes it to the data of the
+    /// tx that settles it
+    fn add_to_tx_data(&self, tx_data: &mut MatchedExchanges) {
+        let addr = self.exchange.data.addr.clone();
+        tx_data
+            .exchanges
+            .insert(addr.clone(), self.exchange.clone());
+        match &self.intent {
+            NodeIntent::Signed(intent) => {
+                tx_data.intents.insert(addr, intent.clone());
+            }
+            NodeIntent::Ring(settlement) => {
+                tx_data.ring_intents.insert(addr, settlement.clone());
+            }
+        }
+    }
 }
 
 impl PartialEq for ExchangeNode {
@@ -62,17 +178,20 @@ impl PartialEq for ExchangeNode {
     }
 }
 
-/// Add a new node to the graph for the intent
+/// Add a new node to the graph for the intent and return its index
 fn add_intent_node(
     graph: &mut DiGraph<ExchangeNode, Address>,
     id: Vec<u8>,
-    exchange: anoma::proto::Signed<Exchange>,
-    intent: anoma::proto::Signed<FungibleTokenIntent>,
-) {
+    exchange: Signed<Exchange>,
+    intent: NodeIntent,
+    sequence: u64,
+) -> NodeIndex {
     let new_node = ExchangeNode {
         id,
         exchange,
         intent,
+        filled: token::Amount::default(),
+        sequence,
     };
     let new_node_index = graph.add_node(new_node.clone());
     let (connect_sell, connect_buy) = find_nodes_to_update(graph, &new_node);
@@ -84,6 +203,24 @@ fn add_intent_node(
     for node_index in connect_buy {
         graph.update_edge(node_index, new_node_index, buy_edge.clone());
     }
+    new_node_index
+}
+
+/// Remove the exchanges that are expired at the given time from the graph and
+/// from the NFT orders
+fn remove_expired(
+    graph: &mut DiGraph<ExchangeNode, Address>,
+    nft_orders: &mut Vec<ExchangeNode>,
+    time: DateTimeUtc,
+) {
+    graph.retain_nodes(|graph, index| {
+        let expired = graph[index].exchange.data.is_expired(time);
+        if expired {
+            println!("removing an expired exchange");
+        }
+        !expired
+    });
+    nft_orders.retain(|order| !order.exchange.data.is_expired(time));
 }
 
 /// Find the nodes that are matching the intent on sell side and buy side.
@@ -117,238 +254,328 @@ fn find_nodes_to_update(
     (connect_sell, connect_buy)
 }
 
-// The cycle returned by tarjan_scc only contains the node_index in an arbitrary
-// order without edges. we must reorder them to craft the transfer
-fn sort_intents(
+/// Find a cycle of exchanges in the graph, in which every exchange sells the
+/// token bought by the next one and the last one sells to the first one. A
+/// cycle can involve any number of intents, e.g. an intent selling X for Y,
+/// another one selling Y for Z and a third one selling Z for X. Returns the
+/// shortest cycle through a node of the first strongly connected component
+/// that has more than one node.
+fn find_cycle(
     graph: &DiGraph<ExchangeNode, Address>,
-    matched_intents_indices: &[NodeIndex],
-) -> Vec<NodeIndex> {
-    let mut cycle_ordered = Vec::new();
-    let mut cycle_intents = VecDeque::from(matched_intents_indices.to_vec());
-    let mut to_connect_node = cycle_intents.pop_front().unwrap();
-    cycle_ordered.push(to_connect_node);
-    while !cycle_intents.is_empty() {
-        let pop_node = cycle_intents.pop_front().unwrap();
-        if graph.contains_edge(to_connect_node, pop_node) {
-            cycle_ordered.push(pop_node);
-            to_connect_node = pop_node;
-        } else {
-            cycle_intents.push_back(pop_node);
+) -> Option<Vec<NodeIndex>> {
+    let component = petgraph::algo::tarjan_scc(graph)
+        .into_iter()
+        .find(|component| component.len() > 1)?;
+    let start = component[0];
+    // A breadth-first search for the shortest path back to the start node
+    let mut predecessors: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(index) = queue.pop_front() {
+        for next in graph.neighbors(index) {
+            if next == start {
+                // a node is a cycle with itself
+                if index == start {
+                    continue;
+                }
+                let mut cycle = vec![index];
+                let mut current = index;
+                while current != start {
+                    current = predecessors[&current];
+                    cycle.push(current);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if component.contains(&next) && !predecessors.contains_key(&next) {
+                predecessors.insert(next, index);
+                queue.push_back(next);
+            }
         }
     }
-    cycle_ordered.reverse();
-    cycle_ordered
+    None
 }
 
-/// Try to find matching intents in the graph. If found, returns the tx bytes
-/// and a hash set of the matched intent IDs.
+/// Try to find matching intents in the graph for the exchanges of a new
+/// intent, the takers, in the order of the given strategy. If there's none,
+/// e.g. because the cycles through the takers are too long, any cycle in the
+/// graph is matched, see [`find_cycle`]. If found, returns the tx bytes and
+/// the matched intent IDs. The intents of the cycles of any length are
+/// settled by a single tx. The exchanges filled in parts are kept in the
+/// graph with their filled amount until their whole `max_sell` is sold.
 fn try_match(
     graph: &mut DiGraph<ExchangeNode, Address>,
-) -> Option<(Vec<u8>, HashSet<Vec<u8>>)> {
-    // We only use the first found cycle, because an intent cannot be matched
-    // into more than one tx
-    if let Some(mut matchned_intents_indices) =
-        petgraph::algo::tarjan_scc(&*graph).into_iter().next()
-    {
-        // a node is a cycle with itself
-        if matchned_intents_indices.len() > 1 {
-            println!("found a match: {:?}", matchned_intents_indices);
-            // Must be sorted in reverse order because it removes the node by
-            // index otherwise it would not remove the correct node
-            matchned_intents_indices.sort_by(|a, b| b.cmp(a));
+    takers: &[NodeIndex],
+    strategy: &dyn Strategy,
+) -> Option<AddIntentResult> {
+    // We only use the first group of cycles that can be matched, because an
+    // intent cannot be matched into more than one tx
+    for taker in takers {
+        for group in strategy.groups(candidates(graph, *taker)) {
+            let cycles: Vec<Vec<NodeIndex>> =
+                group.into_iter().map(|candidate| candidate.cycle).collect();
+            println!("found a match: {:?}", cycles);
             if let Some(tx_data) =
-                prepare_tx_data(graph, &matchned_intents_indices)
+                prepare_tx_data(graph, &cycles, strategy.pro_rata())
             {
-                let removed_intent_ids = matchned_intents_indices
-                    .into_iter()
-                    .filter_map(|i| {
-                        if let Some(removed) = graph.remove_node(i) {
-                            Some(removed.id)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                return Some((tx_data, removed_intent_ids));
+                return Some(settle(graph, &cycles, tx_data));
             }
         }
     }
-    None
+    let cycles = [find_cycle(graph)?];
+    println!("found a match: {:?}", cycles[0]);
+    let tx_data = prepare_tx_data(graph, &cycles, false)?;
+    Some(settle(graph, &cycles, tx_data))
 }
 
-/// Prepare the transaction's data from the matched intents
+/// Remove the intents of the matched cycles from the graph, except for the
+/// remainder of the exchanges filled in parts, and return the result of the
+/// match
+fn settle(
+    graph: &mut DiGraph<ExchangeNode, Address>,
+    cycles: &[Vec<NodeIndex>],
+    tx_data: MatchedExchanges,
+) -> AddIntentResult {
+    // Must be sorted in reverse order because it removes the node by index
+    // otherwise it would not remove the correct node. The taker shared by
+    // the cycles is only updated once.
+    let mut matched_intents_indices: Vec<NodeIndex> =
+        cycles.iter().flatten().copied().collect();
+    matched_intents_indices.sort_by(|a, b| b.cmp(a));
+    matched_intents_indices.dedup();
+    let mut matched_intent_ids = HashSet::new();
+    let mut partially_matched_intent_ids = HashSet::new();
+    for i in matched_intents_indices {
+        let node = &mut graph[i];
+        matched_intent_ids.insert(node.id.clone());
+        if node.exchange.data.fills_in_parts() {
+            node.filled += tx_data.sold_by(&node.exchange.data);
+            if node.filled < node.exchange.data.max_sell {
+                println!(
+                    "keeping the remainder {} of a partially matched exchange",
+                    node.remaining()
+                );
+                partially_matched_intent_ids.insert(node.id.clone());
+                continue;
+            }
+        }
+        graph.remove_node(i);
+    }
+    let partially_matched_intents = if partially_matched_intent_ids.is_empty() {
+        None
+    } else {
+        Some(partially_matched_intent_ids)
+    };
+    AddIntentResult {
+        tx: Some(tx_data.try_to_vec().unwrap()),
+        matched_intents: Some(matched_intent_ids),
+        partially_matched_intents,
+    }
+}
+
+/// Prepare the transaction's data from the matched intents of cycles that
+/// share their first exchange, see [`compute_amounts`]. Every exchange
+/// transfers its sold amount to the next one.
 fn prepare_tx_data(
     graph: &DiGraph<ExchangeNode, Address>,
-    matched_intent_indices: &[NodeIndex],
-) -> Option<Vec<u8>> {
+    cycles: &[Vec<NodeIndex>],
+    pro_rata: bool,
+) -> Option<MatchedExchanges> {
     println!(
         "found match; creating tx with {:?} nodes",
-        matched_intent_indices.len()
+        cycles.iter().map(Vec::len).collect::<Vec<usize>>()
     );
-    let matched_intents = sort_intents(graph, matched_intent_indices);
-    let amounts = compute_amounts(graph, &matched_intents);
-
-    match amounts {
-        Ok(res) => {
-            println!(
-                "amounts: {}",
-                res.values()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            );
-            let mut matched_intents = matched_intents.into_iter();
-            let first_node = matched_intents.next().map(|i| &graph[i]).unwrap();
-            let mut tx_data = MatchedExchanges::empty();
-
-            let last_node =
-                matched_intents.fold(first_node, |prev_node, intent_index| {
-                    let node = &graph[intent_index];
-                    let exchanged_amount =
-                        *res.get(&node.exchange.data).unwrap();
-                    println!(
-                        "crafting transfer: {}, {}, {}",
-                        node.exchange.data.addr.clone(),
-                        prev_node.exchange.data.addr.clone(),
-                        exchanged_amount
-                    );
-                    tx_data.transfers.insert(create_transfer(
-                        node,
-                        prev_node,
-                        exchanged_amount, /* safe as we have as many amounts
-                                           * as intents */
-                    ));
-                    tx_data.exchanges.insert(
-                        node.exchange.data.addr.clone(),
-                        node.exchange.clone(),
-                    );
-                    tx_data.intents.insert(
-                        node.exchange.data.addr.clone(),
-                        node.intent.clone(),
-                    );
-                    node
-                });
-            let last_amount = *res.get(&first_node.exchange.data).unwrap();
+    let amounts = match compute_amounts(graph, cycles, pro_rata) {
+        Ok(amounts) => amounts,
+        Err(err) => {
+            println!("Invalid exchange: {}.", err);
+            return None;
+        }
+    };
+    println!(
+        "amounts: {}",
+        amounts
+            .iter()
+            .flatten()
+            .map(|amount| amount.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    let mut tx_data = MatchedExchanges::empty();
+    for (cycle, amounts) in cycles.iter().zip(amounts) {
+        for (ix, index) in cycle.iter().enumerate() {
+            let node = &graph[*index];
+            let buyer = &graph[cycle[(ix + 1) % cycle.len()]];
+            let exchanged_amount = amounts[ix];
             println!(
                 "crafting transfer: {}, {}, {}",
-                first_node.exchange.data.addr.clone(),
-                last_node.exchange.data.addr.clone(),
-                last_amount
+                node.exchange.data.addr,
+                buyer.exchange.data.addr,
+                exchanged_amount
             );
             tx_data.transfers.insert(create_transfer(
-                first_node,
-                last_node,
-                last_amount,
+                node,
+                buyer,
+                exchanged_amount,
             ));
-            tx_data.exchanges.insert(
-                first_node.exchange.data.addr.clone(),
-                first_node.exchange.clone(),
-            );
-            tx_data.intents.insert(
-                first_node.exchange.data.addr.clone(),
-                first_node.intent.clone(),
-            );
-            println!("tx data: {:?}", tx_data.transfers);
-            Some(tx_data.try_to_vec().unwrap())
-        }
-        Err(err) => {
-            println!("Invalid exchange: {}.", err);
-            None
+            node.add_to_tx_data(&mut tx_data);
         }
     }
+    println!("tx data: {:?}", tx_data.transfers);
+    Some(tx_data)
 }
 
+/// Try to find an order that buys the token of an NFT sold by another order
+/// for at least the seller's price. If found, returns the tx bytes and the
+/// matched intent IDs.
+fn try_match_nft(orders: &mut Vec<ExchangeNode>) -> Option<AddIntentResult> {
+    let (seller, buyer) = orders.iter().find_map(|seller| {
+        let sell = &seller.exchange.data;
+        let sold = sell.nft_sold()?;
+        let buyer = orders.iter().find(|buyer| {
+            let buy = &buyer.exchange.data;
+            buy.nft_bought() == Some(sold)
+                && buy.addr != sell.addr
+                && buy.token_sell == sell.token_buy
+                && buy.max_sell >= sell.min_buy
+        })?;
+        Some((seller, buyer))
+    })?;
+    println!(
+        "found a match of NFT exchanges: {}, {}",
+        seller.exchange.data.addr, buyer.exchange.data.addr
+    );
+    let tx_data = prepare_nft_tx_data(seller, buyer);
+    let matched_intent_ids: HashSet<Vec<u8>> =
+        HashSet::from([seller.id.clone(), buyer.id.clone()]);
+    orders.retain(|order| !matched_intent_ids.contains(&order.id));
+    Some(AddIntentResult {
+        tx: Some(tx_data),
+        matched_intents: Some(matched_intent_ids),
+        partially_matched_intents: None,
+    })
+}
+
+/// Prepare the transaction's data from matched NFT exchanges. The buyer pays
+/// the seller's price and the royalty of the NFT is added by the tx.
+fn prepare_nft_tx_data(seller: &ExchangeNode, buyer: &ExchangeNode) -> Vec<u8> {
+    let sell = &seller.exchange.data;
+    let buy = &buyer.exchange.data;
+    let (nft, token_id) = sell.nft_sold().expect("The seller sells an NFT");
+    let mut tx_data = MatchedExchanges::empty();
+    tx_data.transfers.insert(token::Transfer {
+        source: buy.addr.clone(),
+        target: sell.addr.clone(),
+        token: sell.token_buy.clone(),
+        amount: sell.min_buy,
+        memo: None,
+    });
+    tx_data.nft_transfers.insert(NftTransfer {
+        nft: nft.clone(),
+        token_id: token_id.to_owned(),
+        source: sell.addr.clone(),
+        target: buy.addr.clone(),
+    });
+    for node in [seller, buyer] {
+        node.add_to_tx_data(&mut tx_data);
+    }
+    println!("tx data: {:?}", tx_data.nft_transfers);
+    tx_data.try_to_vec().unwrap()
+}
+
+/// Compute the amounts sold by the exchanges of cycles that share their first
+/// exchange, the taker, see [`find_cycle`] and [`strategy`]. The total sold
+/// amount is maximised, while every exchange receives at least its minimum
+/// rate of the amount it sells from the previous exchange. If `pro_rata`,
+/// the taker sells to the cycles in proportion to the amount that the
+/// exchange selling to it in each cycle can still sell. The amounts are
+/// returned in the order of the exchanges of every cycle.
 fn compute_amounts(
     graph: &DiGraph<ExchangeNode, Address>,
-    cycle_intents: &[NodeIndex],
-) -> Result<HashMap<Exchange, token::Amount>, ResolutionError> {
-    let nodes = graph
-        .raw_nodes()
-        .iter()
-        .map(|x| x.weight.exchange.data.clone())
-        .collect::<Vec<Exchange>>();
+    cycles: &[Vec<NodeIndex>],
+    pro_rata: bool,
+) -> Result<Vec<Vec<token::Amount>>, ResolutionError> {
+    let shared_taker = cycles.len() > 1;
     let mut vars = variables!();
+    let sold: Vec<Vec<Variable>> = cycles
+        .iter()
+        .map(|cycle| {
+            cycle
+                .iter()
+                .enumerate()
+                .map(|(ix, index)| {
+                    let node = &graph[*index];
+                    let buyer_ix = (ix + 1) % cycle.len();
+                    let buyer = &graph[cycle[buyer_ix]];
+                    // An exchange filled in parts can buy any part, down to
+                    // the smallest amount. A shared taker buys its `min_buy`
+                    // from all the cycles.
+                    let min_buy = if buyer.exchange.data.fills_in_parts()
+                        || (shared_taker && buyer_ix == 0)
+                    {
+                        token::Amount::from(1)
+                    } else {
+                        buyer.exchange.data.min_buy
+                    };
+                    vars.add(variable().min(min_buy).max(node.remaining()))
+                })
+                .collect()
+        })
+        .collect();
 
-    let mut var_set: HashMap<NodeIndex, VariableDefinition> = HashMap::new();
-
-    let mut intent_graph = graph.filter_map(
-        |node_index, node| {
-            if cycle_intents.contains(&node_index) {
-                let edges = graph.neighbors(node_index);
-
-                *edges
-                    .map(|target_node_index| {
-                        let target = graph[target_node_index].clone();
-
-                        let variable_definition = variable();
-                        var_set.insert(node_index, variable_definition.clone());
-
-                        let var_def = variable_definition
-                            .min(target.exchange.data.min_buy)
-                            .max(node.exchange.data.max_sell);
-
-                        let var = vars.add(var_def);
-
-                        Some((var, node))
-                    })
-                    .collect::<Vec<Option<(Variable, &ExchangeNode)>>>()
-                    .get(0)
-                    .unwrap()
-            } else {
-                None
-            }
-        },
-        |_edge_index, edge| Some(edge),
-    );
-
-    let variables_iter = vars.iter_variables_with_def().map(|(var, _)| var);
-    let obj_function: Expression = variables_iter.sum();
+    let obj_function: Expression = sold.iter().flatten().copied().sum();
     let mut model = vars.maximise(obj_function).using(default_solver);
-
-    let mut constrains = Vec::new();
-
-    // we need to invert the graph otherwise we are not able to build the
-    // constrains
-    intent_graph.reverse();
-
-    let start = node_index(0);
-    depth_first_search(&intent_graph, Some(start), |event| {
-        if let DfsEvent::Discover(index, _time) = event {
-            let edges = graph.edges(index);
-
-            edges.for_each(|edge| {
-                let source = intent_graph[edge.source()];
-                let target = intent_graph[edge.target()];
-
-                constrains.push((
-                    source.0,
-                    target.0,
-                    target.1.exchange.data.rate_min.0.to_f64().unwrap(),
+    for (cycle, sold) in cycles.iter().zip(&sold) {
+        for (ix, index) in cycle.iter().enumerate() {
+            let received = sold[(ix + cycle.len() - 1) % cycle.len()];
+            let rate = min_rate(&graph[*index].exchange.data);
+            model = model.with(constraint!(received >= sold[ix] * rate));
+        }
+    }
+    if shared_taker {
+        let taker = &graph[cycles[0][0]];
+        let taker_sold: Expression = sold.iter().map(|sold| sold[0]).sum();
+        let taker_received: Expression =
+            sold.iter().map(|sold| sold[sold.len() - 1]).sum();
+        let remaining = f64::from(taker.remaining());
+        model = model.with(constraint!(taker_sold <= remaining));
+        if !taker.exchange.data.fills_in_parts() {
+            let min_buy = f64::from(taker.exchange.data.min_buy);
+            model = model.with(constraint!(taker_received >= min_buy));
+        }
+        if pro_rata {
+            let size = |cycle: &[NodeIndex]| {
+                f64::from(graph[cycle[cycle.len() - 1]].remaining())
+            };
+            let first_size = size(&cycles[0]);
+            for (cycle, sold_in_cycle) in cycles.iter().zip(&sold).skip(1) {
+                model = model.with(constraint!(
+                    sold_in_cycle[0] * first_size == sold[0][0] * size(cycle)
                 ));
-            });
+            }
         }
-        Control::<()>::Continue
-    });
-
-    for constrain in constrains.iter() {
-        let constrain = constraint!(constrain.0 >= constrain.1 * constrain.2);
-        model = model.with(constrain);
     }
 
-    match model.solve() {
-        Ok(solution) => {
-            let mut amount_map = HashMap::new();
-            let amounts = solution
-                .into_inner()
-                .iter()
-                .map(|(_, amount)| token::Amount::from(*amount))
-                .collect::<Vec<_>>();
-            nodes.iter().enumerate().for_each(|(index, exchange)| {
-                amount_map.insert(exchange.clone(), amounts[index]);
-            });
-            Ok(amount_map)
-        }
-        Err(error) => Err(error),
+    let solution = model.solve()?;
+    Ok(sold
+        .iter()
+        .map(|sold| {
+            sold.iter()
+                .map(|var| token::Amount::from(solution.value(*var)))
+                .collect()
+        })
+        .collect())
+}
+
+/// The minimum rate of an exchange. The parts of an exchange filled in parts
+/// must also buy at least their proportion of its `min_buy`.
+fn min_rate(exchange: &Exchange) -> f64 {
+    let rate_min = exchange.rate_min.0.to_f64().unwrap();
+    if exchange.fills_in_parts()
+        && exchange.max_sell != token::Amount::default()
+    {
+        rate_min.max(f64::from(exchange.min_buy) / f64::from(exchange.max_sell))
+    } else {
+        rate_min
     }
 }
 
@@ -362,11 +589,20 @@ fn create_transfer(
         target: to_node.exchange.data.addr.clone(),
         token: to_node.exchange.data.token_buy.clone(),
         amount,
+        memo: None,
     }
 }
 
+/// Decode the exchanges of a fungible token intent or of a revealed
+/// ring-signed intent, with the intent that authorizes them
 fn decode_intent_data(
     bytes: &[u8],
-) -> anoma::proto::Signed<FungibleTokenIntent> {
-    anoma::proto::Signed::<FungibleTokenIntent>::try_from_slice(bytes).unwrap()
+) -> Option<(HashSet<Signed<Exchange>>, NodeIntent)> {
+    if let Ok(intent) = Signed::<FungibleTokenIntent>::try_from_slice(bytes) {
+        Some((intent.data.exchange.clone(), NodeIntent::Signed(intent)))
+    } else if let Ok(intent) = RevealedRingIntent::try_from_slice(bytes) {
+        Some((intent.exchange, NodeIntent::Ring(intent.settlement)))
+    } else {
+        None
+    }
 }
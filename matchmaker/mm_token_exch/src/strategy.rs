@@ -0,0 +1,216 @@
+//! The strategies with which the exchanges of a new intent, the takers, are
+//! matched with the exchanges in the graph, see [`MatchingStrategy`].
+//!
+//! The candidates of a taker are the cycles of exchanges through it, in which
+//! the exchanges other than the taker are its counterparties. A strategy
+//! orders the candidates in groups and the first group whose amounts can be
+//! computed is matched. The cycles of a group share only the taker, whose
+//! sold amount is split between them.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use anoma::types::address::Address;
+use anoma::types::matchmaker::MatchingStrategy;
+use anoma::types::token;
+use petgraph::graph::{DiGraph, NodeIndex};
+
+use super::{min_rate, ExchangeNode};
+
+/// The maximum number of exchanges in a candidate cycle. Longer cycles are
+/// only found by [`super::find_cycle`].
+const MAX_CYCLE_LEN: usize = 6;
+
+/// The maximum number of candidates of a taker
+const MAX_CANDIDATES: usize = 64;
+
+/// The relative difference under which two prices are the same
+const PRICE_EPSILON: f64 = 1e-9;
+
+/// A cycle of exchanges through a taker, see [`candidates`]
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// The exchanges of the cycle, starting with the taker. Every exchange
+    /// sells to the next one and the last one sells to the taker.
+    pub cycle: Vec<NodeIndex>,
+    /// The number of units of its `token_sell` that the taker has to sell
+    /// for a unit of its `token_buy`, at the minimum rates of the
+    /// counterparties. The lower, the better for the taker.
+    pub price: f64,
+    /// The sequence of the latest received counterparty, i.e. since when the
+    /// cycle can be matched
+    pub sequence: u64,
+    /// The amount that the counterparty selling to the taker can still sell
+    pub size: token::Amount,
+    /// The addresses of the counterparties
+    pub counterparties: HashSet<Address>,
+}
+
+/// A matching strategy
+pub trait Strategy {
+    /// Order the candidates of a taker in the groups to try to match, in
+    /// order of priority
+    fn groups(&self, candidates: Vec<Candidate>) -> Vec<Vec<Candidate>>;
+
+    /// Whether the taker's sold amount is split between the cycles of a group
+    /// in proportion to their size. Otherwise, the total sold amount is
+    /// maximised.
+    fn pro_rata(&self) -> bool {
+        false
+    }
+}
+
+/// The best price first and, at the same price, the earliest received
+/// counterparties
+#[derive(Debug)]
+pub struct PriceTimePriority;
+
+/// The best price first, split between all the candidates at that price with
+/// different counterparties in proportion to their size
+#[derive(Debug)]
+pub struct ProRata;
+
+/// The best price first and, at the same price, the largest size
+#[derive(Debug)]
+pub struct BestPrice;
+
+/// The implementation of a matching strategy
+pub fn strategy(kind: MatchingStrategy) -> &'static dyn Strategy {
+    match kind {
+        MatchingStrategy::PriceTimePriority => &PriceTimePriority,
+        MatchingStrategy::ProRata => &ProRata,
+        MatchingStrategy::BestPrice => &BestPrice,
+    }
+}
+
+impl Strategy for PriceTimePriority {
+    fn groups(&self, mut candidates: Vec<Candidate>) -> Vec<Vec<Candidate>> {
+        candidates.sort_by(|a, b| {
+            compare_prices(a.price, b.price).then(a.sequence.cmp(&b.sequence))
+        });
+        candidates
+            .into_iter()
+            .map(|candidate| vec![candidate])
+            .collect()
+    }
+}
+
+impl Strategy for ProRata {
+    fn groups(&self, mut candidates: Vec<Candidate>) -> Vec<Vec<Candidate>> {
+        candidates.sort_by(|a, b| {
+            compare_prices(a.price, b.price).then(a.sequence.cmp(&b.sequence))
+        });
+        let mut groups = vec![];
+        let mut rest = candidates.into_iter().peekable();
+        while let Some(first) = rest.next() {
+            let price = first.price;
+            let mut level = vec![first];
+            while let Some(next) = rest.next_if(|next| {
+                compare_prices(price, next.price) == Ordering::Equal
+            }) {
+                level.push(next);
+            }
+            // The candidates that don't share a counterparty with an earlier
+            // one are matched together, then each one on its own
+            let mut group: Vec<Candidate> = vec![];
+            for candidate in &level {
+                if group.iter().all(|other| {
+                    other.counterparties.is_disjoint(&candidate.counterparties)
+                }) {
+                    group.push(candidate.clone());
+                }
+            }
+            if group.len() > 1 {
+                groups.push(group);
+            }
+            groups.extend(level.into_iter().map(|candidate| vec![candidate]));
+        }
+        groups
+    }
+
+    fn pro_rata(&self) -> bool {
+        true
+    }
+}
+
+impl Strategy for BestPrice {
+    fn groups(&self, mut candidates: Vec<Candidate>) -> Vec<Vec<Candidate>> {
+        candidates.sort_by(|a, b| {
+            compare_prices(a.price, b.price).then(b.size.cmp(&a.size))
+        });
+        candidates
+            .into_iter()
+            .map(|candidate| vec![candidate])
+            .collect()
+    }
+}
+
+/// Find the cycles of exchanges through a taker in the graph, up to
+/// [`MAX_CANDIDATES`] cycles of up to [`MAX_CYCLE_LEN`] exchanges
+pub(crate) fn candidates(
+    graph: &DiGraph<ExchangeNode, Address>,
+    taker: NodeIndex,
+) -> Vec<Candidate> {
+    let mut candidates = vec![];
+    let mut path = vec![taker];
+    extend_path(graph, &mut path, &mut candidates);
+    candidates
+}
+
+/// Extend a path from the taker with the exchanges that buy what its last
+/// exchange sells, until it's back to the taker
+fn extend_path(
+    graph: &DiGraph<ExchangeNode, Address>,
+    path: &mut Vec<NodeIndex>,
+    candidates: &mut Vec<Candidate>,
+) {
+    let last = path[path.len() - 1];
+    for next in graph.neighbors(last) {
+        if candidates.len() >= MAX_CANDIDATES {
+            return;
+        }
+        if next == path[0] {
+            // a node is a cycle with itself
+            if path.len() > 1 {
+                candidates.push(Candidate::new(graph, path.clone()));
+            }
+        } else if path.len() < MAX_CYCLE_LEN && !path.contains(&next) {
+            path.push(next);
+            extend_path(graph, path, candidates);
+            path.pop();
+        }
+    }
+}
+
+impl Candidate {
+    fn new(
+        graph: &DiGraph<ExchangeNode, Address>,
+        cycle: Vec<NodeIndex>,
+    ) -> Self {
+        let nodes = || cycle[1..].iter().map(|index| &graph[*index]);
+        let price: f64 =
+            nodes().map(|node| min_rate(&node.exchange.data)).product();
+        let sequence =
+            nodes().map(|node| node.sequence).max().unwrap_or_default();
+        let size = graph[cycle[cycle.len() - 1]].remaining();
+        let counterparties: HashSet<Address> = nodes()
+            .map(|node| node.exchange.data.addr.clone())
+            .collect();
+        Self {
+            cycle,
+            price,
+            sequence,
+            size,
+            counterparties,
+        }
+    }
+}
+
+/// Compare prices, which are the same up to [`PRICE_EPSILON`]
+fn compare_prices(a: f64, b: f64) -> Ordering {
+    if (a - b).abs() <= PRICE_EPSILON * a.abs().max(b.abs()) {
+        Ordering::Equal
+    } else {
+        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+    }
+}
@@ -33,10 +33,12 @@ use epoched::{
 use parameters::PosParams;
 use thiserror::Error;
 use types::{
-    ActiveValidator, Bonds, Epoch, GenesisValidator, Slash, SlashType, Slashes,
-    TotalVotingPowers, Unbond, Unbonds, ValidatorConsensusKeys, ValidatorSet,
-    ValidatorSetUpdate, ValidatorSets, ValidatorState, ValidatorStates,
-    ValidatorTotalDeltas, ValidatorVotingPowers, VotingPower, VotingPowerDelta,
+    ActiveValidator, BasisPoints, Bonds, Epoch, GenesisValidator, Slash,
+    SlashType, Slashes, TotalVotingPowers, Unbond, Unbonds,
+    ValidatorCommissionRates, ValidatorConsensusKeys, ValidatorRewards,
+    ValidatorSet, ValidatorSetUpdate, ValidatorSets, ValidatorState,
+    ValidatorStates, ValidatorTotalDeltas, ValidatorVotingPowers, VotingPower,
+    VotingPowerDelta,
 };
 
 use crate::btree_set::BTreeSetShims;
@@ -118,6 +120,11 @@ pub trait PosReadOnly {
         &self,
         key: &Self::Address,
     ) -> Option<ValidatorStates>;
+    /// Read PoS validator's commission rate.
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<ValidatorCommissionRates>;
     /// Read PoS validator's total deltas of their bonds (validator self-bonds
     /// and delegations).
     fn read_validator_total_deltas(
@@ -142,6 +149,12 @@ pub trait PosReadOnly {
         &self,
         key: &BondId<Self::Address>,
     ) -> Option<Unbonds<Self::TokenAmount>>;
+    /// Read PoS bond rewards that haven't been claimed yet (from validator
+    /// self-bond or a delegation).
+    fn read_bond_rewards(
+        &self,
+        key: &BondId<Self::Address>,
+    ) -> Option<Self::TokenAmount>;
     /// Read PoS validator set (active and inactive).
     fn read_validator_set(&self) -> ValidatorSets<Self::Address>;
     /// Read PoS total voting power of all validators (active and inactive).
@@ -174,6 +187,12 @@ pub trait PosActions: PosReadOnly {
         key: &Self::Address,
         value: ValidatorStates,
     );
+    /// Write PoS validator's commission rate.
+    fn write_validator_commission_rate(
+        &mut self,
+        key: &Self::Address,
+        value: ValidatorCommissionRates,
+    );
     /// Write PoS validator's total deltas of their bonds (validator self-bonds
     /// and delegations).
     fn write_validator_total_deltas(
@@ -210,6 +229,14 @@ pub trait PosActions: PosReadOnly {
     /// Delete an emptied PoS unbond (unbonded tokens from validator self-bond
     /// or a delegation).
     fn delete_unbond(&mut self, key: &BondId<Self::Address>);
+    /// Delete claimed PoS bond rewards (from validator self-bond or a
+    /// delegation).
+    fn delete_bond_rewards(&mut self, key: &BondId<Self::Address>);
+    /// Write PoS bond's auto-compounding flag, with which the bond's rewards
+    /// are re-bonded to its validator at the end of each epoch.
+    fn write_bond_auto_compound(&mut self, key: &BondId<Self::Address>);
+    /// Delete PoS bond's auto-compounding flag.
+    fn delete_bond_auto_compound(&mut self, key: &BondId<Self::Address>);
 
     /// Transfer tokens from the `src` to the `dest`.
     fn transfer(
@@ -220,12 +247,14 @@ pub trait PosActions: PosReadOnly {
         dest: &Self::Address,
     );
 
-    /// Attempt to update the given account to become a validator.
+    /// Attempt to update the given account to become a validator with the
+    /// given initial commission rate.
     fn become_validator(
         &mut self,
         address: &Self::Address,
         staking_reward_address: &Self::Address,
         consensus_key: &Self::PublicKey,
+        commission_rate: BasisPoints,
         current_epoch: impl Into<Epoch>,
     ) -> Result<(), BecomeValidatorError<Self::Address>> {
         let current_epoch = current_epoch.into();
@@ -236,6 +265,11 @@ pub trait PosActions: PosReadOnly {
                 address.clone(),
             ));
         }
+        if commission_rate > BasisPoints::new(10_000) {
+            return Err(BecomeValidatorError::CommissionRateTooHigh(
+                commission_rate,
+            ));
+        }
         if address == staking_reward_address {
             return Err(
                 BecomeValidatorError::StakingRewardAddressEqValidatorAddress(
@@ -260,6 +294,10 @@ pub trait PosActions: PosReadOnly {
             staking_reward_address.clone(),
         );
         self.write_validator_consensus_key(address, consensus_key);
+        self.write_validator_commission_rate(
+            address,
+            Epoched::init_at_genesis(commission_rate, current_epoch),
+        );
         self.write_validator_state(address, state);
         self.write_validator_set(validator_set);
         self.write_validator_address_raw_hash(address);
@@ -300,6 +338,15 @@ pub trait PosActions: PosReadOnly {
             validator: validator.clone(),
         };
         let bond = self.read_bond(&bond_id);
+        // The validator's self-bond is needed to find the delegated amount
+        let validator_self_bond = if source == validator {
+            None
+        } else {
+            self.read_bond(&BondId {
+                source: validator.clone(),
+                validator: validator.clone(),
+            })
+        };
         let validator_total_deltas =
             self.read_validator_total_deltas(validator);
         let validator_voting_power =
@@ -316,6 +363,7 @@ pub trait PosActions: PosReadOnly {
             validator_state,
             &bond_id,
             bond,
+            validator_self_bond.as_ref(),
             amount,
             validator_total_deltas,
             validator_voting_power,
@@ -353,6 +401,9 @@ pub trait PosActions: PosReadOnly {
     ) -> Result<(), UnbondError<Self::Address, Self::TokenAmount>> {
         let current_epoch = current_epoch.into();
         let params = self.read_pos_params();
+        // The bonds of a jailed validator may still be unbonded, so that its
+        // delegators are not locked in. A jailed validator is not in the
+        // validator set, so only its voting power is updated.
         let source = source.unwrap_or(validator);
         let bond_id = BondId {
             source: source.clone(),
@@ -397,8 +448,10 @@ pub trait PosActions: PosReadOnly {
                 self.write_bond(&bond_id, bond);
             }
             _ => {
-                // If the bond is left empty, delete it
-                self.delete_bond(&bond_id)
+                // If the bond is left empty, delete it together with its
+                // auto-compounding flag
+                self.delete_bond(&bond_id);
+                self.delete_bond_auto_compound(&bond_id);
             }
         }
         self.write_unbond(&bond_id, unbond);
@@ -467,6 +520,135 @@ pub trait PosActions: PosReadOnly {
 
         Ok(slashed)
     }
+
+    /// Claim the rewards of a self-bond to a validator when `source` is `None`
+    /// or equal to the `validator` address, or claim the rewards of tokens
+    /// delegated to the `validator` from the `source`. The rewards of a
+    /// self-bond are credited to the validator's staking reward address and
+    /// the rewards of a delegation to the `source`. Returns the claimed
+    /// amount.
+    fn claim_rewards(
+        &mut self,
+        source: Option<&Self::Address>,
+        validator: &Self::Address,
+    ) -> Result<Self::TokenAmount, ClaimRewardsError<Self::Address>> {
+        let source = source.unwrap_or(validator);
+        let bond_id = BondId {
+            source: source.clone(),
+            validator: validator.clone(),
+        };
+
+        let rewards = match self.read_bond_rewards(&bond_id) {
+            Some(rewards) if Into::<u64>::into(rewards) != 0 => rewards,
+            _ => return Err(ClaimRewardsError::NoRewards(bond_id)),
+        };
+        self.delete_bond_rewards(&bond_id);
+
+        let target = if source == validator {
+            self.read_validator_staking_reward_address(validator)
+                .unwrap_or_else(|| validator.clone())
+        } else {
+            source.clone()
+        };
+        // Transfer the rewards from PoS to the target
+        self.transfer(
+            &Self::staking_token_address(),
+            rewards,
+            &Self::POS_ADDRESS,
+            &target,
+        );
+
+        Ok(rewards)
+    }
+
+    /// Enable or disable auto-compounding of the rewards of a self-bond to a
+    /// validator when `source` is `None` or equal to the `validator` address,
+    /// or of tokens delegated from the `source` to the `validator`. With
+    /// auto-compounding, the bond's rewards are re-bonded to the validator at
+    /// the end of each epoch. Any rewards accumulated before it's enabled can
+    /// still be claimed.
+    fn set_auto_compound(
+        &mut self,
+        source: Option<&Self::Address>,
+        validator: &Self::Address,
+        auto_compound: bool,
+    ) -> Result<(), AutoCompoundError<Self::Address>> {
+        let source = source.unwrap_or(validator);
+        let bond_id = BondId {
+            source: source.clone(),
+            validator: validator.clone(),
+        };
+        if auto_compound {
+            if self.read_bond(&bond_id).is_none() {
+                return Err(AutoCompoundError::NoBondFound(bond_id));
+            }
+            self.write_bond_auto_compound(&bond_id);
+        } else {
+            self.delete_bond_auto_compound(&bond_id);
+        }
+        Ok(())
+    }
+
+    /// Change a validator's commission rate. The new rate will take effect at
+    /// the pipeline offset.
+    fn change_validator_commission_rate(
+        &mut self,
+        validator: &Self::Address,
+        new_rate: BasisPoints,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), CommissionRateChangeError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        if !self.is_validator(validator) {
+            return Err(CommissionRateChangeError::NotAValidator(
+                validator.clone(),
+            ));
+        }
+        let params = self.read_pos_params();
+        let commission_rates = self.read_validator_commission_rate(validator);
+
+        let commission_rates = change_commission_rate(
+            &params,
+            validator,
+            commission_rates,
+            new_rate,
+            current_epoch,
+        )?;
+
+        self.write_validator_commission_rate(validator, commission_rates);
+
+        Ok(())
+    }
+
+    /// Unjail a validator that has been jailed for downtime, once its jail
+    /// period is over. The validator is added back to the validator set at
+    /// the pipeline offset.
+    fn unjail_validator(
+        &mut self,
+        validator: &Self::Address,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), UnjailError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        let params = self.read_pos_params();
+        let mut state = self
+            .read_validator_state(validator)
+            .ok_or_else(|| UnjailError::NotAValidator(validator.clone()))?;
+        let total_deltas = self.read_validator_total_deltas(validator);
+        let mut validator_set = self.read_validator_set();
+
+        unjail(
+            &params,
+            validator,
+            &mut state,
+            total_deltas.as_ref(),
+            &mut validator_set,
+            current_epoch,
+        )?;
+
+        self.write_validator_state(validator, state);
+        self.write_validator_set(validator_set);
+
+        Ok(())
+    }
 }
 
 /// PoS system base trait for system initialization on genesis block, updating
@@ -554,6 +736,11 @@ pub trait PosBase {
         &self,
         key: &Self::Address,
     ) -> Option<ValidatorStates>;
+    /// Read PoS validator's commission rate.
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<ValidatorCommissionRates>;
     /// Read PoS validator's total deltas of their bonds (validator self-bonds
     /// and delegations).
     fn read_validator_total_deltas(
@@ -567,6 +754,31 @@ pub trait PosBase {
     ) -> Option<ValidatorVotingPowers>;
     /// Read PoS slashes applied to a validator.
     fn read_validator_slashes(&self, key: &Self::Address) -> Slashes;
+    /// Read the number of consecutive blocks that a PoS validator has missed
+    /// signing.
+    fn read_validator_missed_blocks(&self, key: &Self::Address) -> u64;
+    /// Read PoS bond (validator self-bond or a delegation).
+    fn read_bond(
+        &self,
+        key: &BondId<Self::Address>,
+    ) -> Option<Bonds<Self::TokenAmount>>;
+    /// Read all the PoS bonds to a validator (validator self-bond and
+    /// delegations).
+    fn read_validator_bonds(
+        &self,
+        validator: &Self::Address,
+    ) -> Vec<(BondId<Self::Address>, Bonds<Self::TokenAmount>)>;
+    /// Read PoS bond rewards that haven't been claimed yet (from validator
+    /// self-bond or a delegation).
+    fn read_bond_rewards(
+        &self,
+        key: &BondId<Self::Address>,
+    ) -> Option<Self::TokenAmount>;
+    /// Read whether the rewards of a PoS bond are re-bonded to its validator
+    /// at the end of each epoch.
+    fn read_bond_auto_compound(&self, key: &BondId<Self::Address>) -> bool;
+    /// Read PoS rewards accrued by the validators in the current epoch.
+    fn read_validator_rewards(&self) -> ValidatorRewards<Self::Address>;
     /// Read PoS validator set (active and inactive).
     fn read_validator_set(&self) -> ValidatorSets<Self::Address>;
     /// Read PoS total voting power of all validators (active and inactive).
@@ -614,12 +826,31 @@ pub trait PosBase {
         validator: &Self::Address,
         value: Slash,
     );
+    /// Write the number of consecutive blocks that a PoS validator has missed
+    /// signing.
+    fn write_validator_missed_blocks(
+        &mut self,
+        key: &Self::Address,
+        value: u64,
+    );
     /// Write PoS bond (validator self-bond or a delegation).
     fn write_bond(
         &mut self,
         key: &BondId<Self::Address>,
         value: &Bonds<Self::TokenAmount>,
     );
+    /// Write PoS bond rewards that haven't been claimed yet (from validator
+    /// self-bond or a delegation).
+    fn write_bond_rewards(
+        &mut self,
+        key: &BondId<Self::Address>,
+        value: Self::TokenAmount,
+    );
+    /// Write PoS rewards accrued by the validators in the current epoch.
+    fn write_validator_rewards(
+        &mut self,
+        value: &ValidatorRewards<Self::Address>,
+    );
     /// Write PoS validator set (active and inactive).
     fn write_validator_set(&mut self, value: &ValidatorSets<Self::Address>);
     /// Read PoS total voting power of all validators (active and inactive).
@@ -631,7 +862,7 @@ pub trait PosBase {
         pk: &Self::PublicKey,
     );
     /// Credit tokens to the `target` account. This should only be used at
-    /// genesis.
+    /// genesis and to mint the distributed staking rewards.
     fn credit_tokens(
         &mut self,
         token: &Self::Address,
@@ -711,6 +942,43 @@ pub trait PosBase {
         Ok(())
     }
 
+    /// Find the active validators in the given epoch whose self-bond, after
+    /// any slashes have been applied, is below the minimum self-bond. Returns
+    /// the validators' addresses with their slashed self-bond amounts.
+    fn validators_below_min_self_bond(
+        &self,
+        epoch: impl Into<Epoch>,
+    ) -> Vec<(Self::Address, u64)> {
+        let epoch: Epoch = epoch.into();
+        let params = self.read_pos_params();
+        let validator_sets = self.read_validator_set();
+        let validator_set = match validator_sets.get(epoch) {
+            Some(validator_set) => validator_set,
+            None => return vec![],
+        };
+        validator_set
+            .active
+            .iter()
+            .filter_map(|validator: &WeightedValidator<Self::Address>| {
+                let bond_id = BondId {
+                    source: validator.address.clone(),
+                    validator: validator.address.clone(),
+                };
+                let slashes = self.read_validator_slashes(&validator.address);
+                let self_bond = self
+                    .read_bond(&bond_id)
+                    .and_then(|bond| bond.get(epoch))
+                    .map(|bond| slashed_bond_amount(&bond, &slashes))
+                    .unwrap_or_default();
+                if self_bond < params.min_validator_self_bond {
+                    Some((validator.address.clone(), self_bond))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     /// Calls a closure on each validator update element.
     fn validator_set_update(
         &self,
@@ -823,7 +1091,34 @@ pub trait PosBase {
                 Some(ValidatorSetUpdate::Deactivated(consensus_key))
             },
         );
-        active_validators.chain(inactive_validators).for_each(f)
+        // The validators that were active in the previous epoch, but are in
+        // neither of the sets in the current epoch, i.e. they've been jailed,
+        // have to be deactivated too
+        let removed_validators = prev_validators
+            .into_iter()
+            .flat_map(|prev_validators| prev_validators.active.iter())
+            .filter(|validator| {
+                !cur_validators
+                    .active
+                    .iter()
+                    .chain(cur_validators.inactive.iter())
+                    .any(|cur_validator| {
+                        cur_validator.address == validator.address
+                    })
+            })
+            .map(|validator| {
+                let consensus_key = self
+                    .read_validator_consensus_key(&validator.address)
+                    .unwrap()
+                    .get(current_epoch)
+                    .unwrap()
+                    .clone();
+                ValidatorSetUpdate::Deactivated(consensus_key)
+            });
+        active_validators
+            .chain(inactive_validators)
+            .chain(removed_validators)
+            .for_each(f)
     }
 
     /// Apply a slash to a byzantine validator for the given evidence.
@@ -886,6 +1181,220 @@ pub trait PosBase {
         );
         Ok(())
     }
+
+    /// Record whether the active validators have signed the last block. The
+    /// validators that have missed signing more than the maximum number of
+    /// consecutive blocks are jailed. Returns the addresses of the validators
+    /// that have been jailed.
+    fn record_block_signatures(
+        &mut self,
+        current_epoch: impl Into<Epoch>,
+        signatures: impl IntoIterator<Item = (Self::Address, bool)>,
+    ) -> Vec<Self::Address> {
+        let current_epoch = current_epoch.into();
+        let params = self.read_pos_params();
+        let mut jailed = vec![];
+        for (validator, signed) in signatures {
+            let missed_blocks = self.read_validator_missed_blocks(&validator);
+            if signed {
+                if missed_blocks > 0 {
+                    self.write_validator_missed_blocks(&validator, 0);
+                }
+                continue;
+            }
+            let missed_blocks = missed_blocks + 1;
+            if missed_blocks > params.max_missed_blocks {
+                if self.jail_validator(&params, current_epoch, &validator) {
+                    jailed.push(validator.clone());
+                }
+                self.write_validator_missed_blocks(&validator, 0);
+            } else {
+                self.write_validator_missed_blocks(&validator, missed_blocks);
+            }
+        }
+        jailed
+    }
+
+    /// Jail a validator for downtime. The validator is removed from the
+    /// validator set at the pipeline offset. Returns `false` if the validator
+    /// is not a validator or if it's already jailed.
+    fn jail_validator(
+        &mut self,
+        params: &PosParams,
+        current_epoch: impl Into<Epoch>,
+        validator: &Self::Address,
+    ) -> bool {
+        let current_epoch = current_epoch.into();
+        let mut state = match self.read_validator_state(validator) {
+            Some(state) => state,
+            None => return false,
+        };
+        let mut validator_set = self.read_validator_set();
+        if !jail(
+            params,
+            validator,
+            &mut state,
+            &mut validator_set,
+            current_epoch,
+        ) {
+            return false;
+        }
+        self.write_validator_state(validator, &state);
+        self.write_validator_set(&validator_set);
+        true
+    }
+
+    /// Accrue the rewards for a block to its proposer, if known, and to the
+    /// validators that have voted on it. The accrued rewards are distributed
+    /// to the validators' bonds at the end of the epoch with
+    /// [`PosBase::distribute_rewards`].
+    fn record_block_rewards(
+        &mut self,
+        proposer: Option<&Self::Address>,
+        voters: impl IntoIterator<Item = Self::Address>,
+    ) {
+        let mut voters = voters.into_iter().peekable();
+        if proposer.is_none() && voters.peek().is_none() {
+            return;
+        }
+        let params = self.read_pos_params();
+        let mut rewards = self.read_validator_rewards();
+        for voter in voters {
+            *rewards.entry(voter).or_default() += params.block_vote_reward;
+        }
+        if let Some(proposer) = proposer {
+            *rewards.entry(proposer.clone()).or_default() +=
+                params.block_proposer_reward;
+        }
+        self.write_validator_rewards(&rewards);
+    }
+
+    /// Distribute the rewards accrued by the validators in the given epoch,
+    /// which should be the last epoch, to their bonds in proportion to the
+    /// bonded amounts, after any slashes have been applied. The validator's
+    /// commission is taken from the rewards of its delegations and added to
+    /// its self-bond. The distributed rewards are minted into the PoS account,
+    /// from which they can be claimed. The rewards of bonds with
+    /// auto-compounding are instead re-bonded to their validator at the
+    /// pipeline offset from the following epoch, unless the validator
+    /// cannot accept the bond (e.g. it's jailed), in which case they're
+    /// accumulated as usual.
+    fn distribute_rewards(&mut self, epoch: impl Into<Epoch>) {
+        let epoch: Epoch = epoch.into();
+        let params = self.read_pos_params();
+        let rewards = self.read_validator_rewards();
+        if rewards.is_empty() {
+            return;
+        }
+        let mut total_rewards: u64 = 0;
+        for (validator, reward) in rewards {
+            let slashes = self.read_validator_slashes(&validator);
+            let bonds = self
+                .read_validator_bonds(&validator)
+                .into_iter()
+                .filter_map(|(bond_id, bond)| {
+                    let amount = bond
+                        .get(epoch)
+                        .map(|bond| slashed_bond_amount(&bond, &slashes))
+                        .unwrap_or_default();
+                    (amount != 0).then(|| (bond_id, amount))
+                })
+                .collect();
+            let commission_rate = self
+                .read_validator_commission_rate(&validator)
+                .and_then(|rates| rates.get(epoch).copied())
+                .unwrap_or_else(|| BasisPoints::new(0));
+            for (bond_id, share) in
+                split_rewards(&validator, reward, commission_rate, bonds)
+            {
+                if share != 0
+                    && self.read_bond_auto_compound(&bond_id)
+                    && self
+                        .restake_rewards(
+                            &params,
+                            &bond_id,
+                            Self::TokenAmount::from(share),
+                            epoch + 1_u64,
+                        )
+                        .is_ok()
+                {
+                    continue;
+                }
+                let bond_rewards: u64 = self
+                    .read_bond_rewards(&bond_id)
+                    .map(Into::into)
+                    .unwrap_or_default();
+                self.write_bond_rewards(
+                    &bond_id,
+                    Self::TokenAmount::from(bond_rewards + share),
+                );
+            }
+            total_rewards += reward;
+        }
+        self.write_validator_rewards(&ValidatorRewards::default());
+        // Mint the distributed rewards into the PoS account
+        self.credit_tokens(
+            &Self::staking_token_address(),
+            &Self::POS_ADDRESS,
+            Self::TokenAmount::from(total_rewards),
+        );
+    }
+
+    /// Re-bond the given rewards of a bond to its validator at the pipeline
+    /// offset from the `current_epoch`. The rewards are already in the PoS
+    /// account, so no tokens are transferred.
+    fn restake_rewards(
+        &mut self,
+        params: &PosParams,
+        bond_id: &BondId<Self::Address>,
+        amount: Self::TokenAmount,
+        current_epoch: impl Into<Epoch>,
+    ) -> Result<(), BondError<Self::Address>> {
+        let current_epoch = current_epoch.into();
+        let validator = &bond_id.validator;
+        let validator_state = self.read_validator_state(validator);
+        let bond = self.read_bond(bond_id);
+        // The validator's self-bond is needed to find the delegated amount
+        let validator_self_bond = if &bond_id.source == validator {
+            None
+        } else {
+            self.read_bond(&BondId {
+                source: validator.clone(),
+                validator: validator.clone(),
+            })
+        };
+        let validator_total_deltas =
+            self.read_validator_total_deltas(validator);
+        let validator_voting_power =
+            self.read_validator_voting_power(validator);
+        let mut total_voting_power = self.read_total_voting_power();
+        let mut validator_set = self.read_validator_set();
+
+        let BondData {
+            bond,
+            validator_total_deltas,
+            validator_voting_power,
+        } = bond_tokens(
+            params,
+            validator_state,
+            bond_id,
+            bond,
+            validator_self_bond.as_ref(),
+            amount,
+            validator_total_deltas,
+            validator_voting_power,
+            &mut total_voting_power,
+            &mut validator_set,
+            current_epoch,
+        )?;
+
+        self.write_bond(bond_id, &bond);
+        self.write_validator_total_deltas(validator, &validator_total_deltas);
+        self.write_validator_voting_power(validator, &validator_voting_power);
+        self.write_total_voting_power(&total_voting_power);
+        self.write_validator_set(&validator_set);
+        Ok(())
+    }
 }
 
 #[allow(missing_docs)]
@@ -893,6 +1402,11 @@ pub trait PosBase {
 pub enum GenesisError {
     #[error("Voting power overflow: {0}")]
     VotingPowerOverflow(TryFromIntError),
+    #[error(
+        "The genesis validator's self-bond {0} is below the minimum self-bond \
+         {1}"
+    )]
+    SelfBondBelowMinimum(u64, u64),
 }
 
 #[allow(missing_docs)]
@@ -905,6 +1419,8 @@ pub enum BecomeValidatorError<Address: Display + Debug> {
          address {0}"
     )]
     StakingRewardAddressEqValidatorAddress(Address),
+    #[error("The commission rate cannot be greater than 100%, got {0}")]
+    CommissionRateTooHigh(BasisPoints),
 }
 
 #[allow(missing_docs)]
@@ -919,10 +1435,30 @@ pub enum BondError<Address: Display + Debug> {
     SourceMustNotBeAValidator(Address),
     #[error("The given validator address {0} is inactive")]
     InactiveValidator(Address),
+    #[error("The given validator address {0} is jailed")]
+    JailedValidator(Address),
     #[error("Voting power overflow: {0}")]
     VotingPowerOverflow(TryFromIntError),
     #[error("Given zero amount to unbond")]
     ZeroAmount,
+    #[error(
+        "The self-bond of validator {validator} would be {self_bond}, which \
+         is below the minimum self-bond {min_self_bond}"
+    )]
+    SelfBondBelowMinimum {
+        validator: Address,
+        self_bond: u64,
+        min_self_bond: u64,
+    },
+    #[error(
+        "The tokens delegated to validator {validator} would be \
+         {delegations}, which is over the delegation cap {max_delegations}"
+    )]
+    DelegationCapExceeded {
+        validator: Address,
+        delegations: u64,
+        max_delegations: u64,
+    },
 }
 
 #[allow(missing_docs)]
@@ -942,6 +1478,11 @@ pub enum UnbondError<Address: Display + Debug, TokenAmount: Display + Debug> {
     VotingPowerOverflow(TryFromIntError),
     #[error("Given zero amount to unbond")]
     ZeroAmount,
+    #[error(
+        "The remaining self-bond {0} would be below the minimum self-bond \
+         {1}. Either unbond less or unbond the whole self-bond."
+    )]
+    SelfBondBelowMinimum(u64, u64),
 }
 
 #[allow(missing_docs)]
@@ -964,6 +1505,78 @@ where
     NoWithdrawableUnbond(BondId<Address>),
 }
 
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum ClaimRewardsError<Address>
+where
+    Address: Display
+        + Debug
+        + Clone
+        + PartialOrd
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    #[error("No rewards to claim for {0}")]
+    NoRewards(BondId<Address>),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum AutoCompoundError<Address>
+where
+    Address: Display
+        + Debug
+        + Clone
+        + PartialOrd
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    #[error("No bond could be found for {0}")]
+    NoBondFound(BondId<Address>),
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum CommissionRateChangeError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error("The commission rate cannot be greater than 100%, got {0}")]
+    RateTooHigh(BasisPoints),
+    #[error(
+        "The commission rate of validator {validator} cannot change by more \
+         than {max_change} per epoch, got a change of {change}"
+    )]
+    ChangeTooLarge {
+        validator: Address,
+        change: BasisPoints,
+        max_change: BasisPoints,
+    },
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum UnjailError<Address: Display + Debug> {
+    #[error("The given address {0} is not a validator address")]
+    NotAValidator(Address),
+    #[error("The validator {0} is not jailed")]
+    NotJailed(Address),
+    #[error(
+        "The validator {validator} is jailed until epoch {until}, it cannot \
+         be back in the validator set in epoch {pipeline_epoch}"
+    )]
+    JailPeriodNotOver {
+        validator: Address,
+        until: Epoch,
+        pipeline_epoch: Epoch,
+    },
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum SlashError<Address>
@@ -1119,6 +1732,13 @@ where
         address, tokens, ..
     } in validators.clone()
     {
+        let self_bond: u64 = (*tokens).into();
+        if self_bond < params.min_validator_self_bond {
+            return Err(GenesisError::SelfBondBelowMinimum(
+                self_bond,
+                params.min_validator_self_bond,
+            ));
+        }
         total_bonded_balance += *tokens;
         let delta = VotingPowerDelta::try_from_tokens(*tokens, params)
             .map_err(GenesisError::VotingPowerOverflow)?;
@@ -1405,6 +2025,7 @@ fn bond_tokens<Address, TokenAmount, TokenChange>(
     validator_state: Option<ValidatorStates>,
     bond_id: &BondId<Address>,
     current_bond: Option<Bonds<TokenAmount>>,
+    validator_self_bond: Option<&Bonds<TokenAmount>>,
     amount: TokenAmount,
     validator_total_deltas: Option<ValidatorTotalDeltas<TokenChange>>,
     validator_voting_power: Option<ValidatorVotingPowers>,
@@ -1472,6 +2093,17 @@ where
                     ));
                 }
             }
+            // Check that it's not jailed at the pipeline offset, where the
+            // bond is added
+            let pipeline_epoch =
+                current_epoch + DynEpochOffset::PipelineLen.value(params);
+            if let Some(ValidatorState::Jailed { .. }) =
+                validator_state.get(pipeline_epoch)
+            {
+                return Err(BondError::JailedValidator(
+                    bond_id.validator.clone(),
+                ));
+            }
         }
     }
 
@@ -1492,6 +2124,42 @@ where
         }
     };
 
+    // Check the bond limits at the pipeline offset, where the bond is added
+    let pipeline_epoch = current_epoch + update_offset.value(params);
+    if bond_id.source == bond_id.validator {
+        let self_bond: u64 = bond
+            .get(pipeline_epoch)
+            .map(|bond| bond.sum().into())
+            .unwrap_or_default();
+        if self_bond < params.min_validator_self_bond {
+            return Err(BondError::SelfBondBelowMinimum {
+                validator: bond_id.validator.clone(),
+                self_bond,
+                min_self_bond: params.min_validator_self_bond,
+            });
+        }
+    } else if let Some(max_delegations) = params.max_validator_delegations {
+        let total_stake: i128 = validator_total_deltas
+            .as_ref()
+            .and_then(|deltas| deltas.get(pipeline_epoch))
+            .map(Into::into)
+            .unwrap_or_default();
+        let self_bond: u64 = validator_self_bond
+            .and_then(|bond| bond.get(pipeline_epoch))
+            .map(|bond| bond.sum().into())
+            .unwrap_or_default();
+        let amount: u64 = amount.into();
+        let delegations =
+            (total_stake - self_bond as i128).max(0) + amount as i128;
+        if delegations > max_delegations as i128 {
+            return Err(BondError::DelegationCapExceeded {
+                validator: bond_id.validator.clone(),
+                delegations: u64::try_from(delegations).unwrap_or(u64::MAX),
+                max_delegations,
+            });
+        }
+    }
+
     // Update validator set. This has to be done before we update the
     // `validator_total_deltas`, because we need to look-up the validator with
     // its voting power before the change.
@@ -1638,6 +2306,19 @@ where
             unbondable_amount,
         ));
     }
+    // A validator may unbond its whole self-bond, but what's left after a
+    // partial unbond must not be below the minimum self-bond
+    if bond_id.source == bond_id.validator {
+        let unbondable_amount: u64 = unbondable_amount.into();
+        let amount: u64 = amount.into();
+        let remaining = unbondable_amount - amount;
+        if remaining != 0 && remaining < params.min_validator_self_bond {
+            return Err(UnbondError::SelfBondBelowMinimum(
+                remaining,
+                params.min_validator_self_bond,
+            ));
+        }
+    }
 
     let mut unbond = match unbond {
         Some(unbond) => unbond,
@@ -1732,7 +2413,8 @@ where
 }
 
 /// Update validator set when a validator's receives a new bond and when its
-/// bond is unbonded (self-bond or delegation).
+/// bond is unbonded (self-bond or delegation). A validator that is not in the
+/// validator set, i.e. a jailed validator, is left out of it.
 fn update_validator_set<Address, TokenChange>(
     params: &PosParams,
     validator: &Address,
@@ -1810,10 +2492,7 @@ fn update_validator_set<Address, TokenChange>(
                         validator_set.inactive.remove(&validator_pre);
                         validator_set.inactive.insert(validator_post);
                     }
-                } else {
-                    debug_assert!(
-                        validator_set.active.contains(&validator_pre)
-                    );
+                } else if validator_set.active.contains(&validator_pre) {
                     let max_inactive_validator =
                         validator_set.inactive.last_shim();
                     let max_voting_power = max_inactive_validator
@@ -1985,3 +2664,258 @@ where
         slashed,
     })
 }
+
+/// Set a validator's new commission rate at the pipeline offset. The rate must
+/// not differ from the rate in the epoch before the pipeline offset by more
+/// than the maximum change per epoch. A validator that has never set its
+/// commission rate has the rate of 0.
+fn change_commission_rate<Address>(
+    params: &PosParams,
+    validator: &Address,
+    commission_rates: Option<ValidatorCommissionRates>,
+    new_rate: BasisPoints,
+    current_epoch: Epoch,
+) -> Result<ValidatorCommissionRates, CommissionRateChangeError<Address>>
+where
+    Address: Display + Debug + Clone,
+{
+    if new_rate > BasisPoints::new(10_000) {
+        return Err(CommissionRateChangeError::RateTooHigh(new_rate));
+    }
+    let pipeline_epoch =
+        current_epoch + DynEpochOffset::PipelineLen.value(params);
+    let previous_rate = commission_rates
+        .as_ref()
+        .and_then(|rates| rates.get(pipeline_epoch - 1_u64))
+        .copied()
+        .unwrap_or_else(|| BasisPoints::new(0));
+    let change = new_rate.abs_diff(previous_rate);
+    if change > params.max_commission_rate_change {
+        return Err(CommissionRateChangeError::ChangeTooLarge {
+            validator: validator.clone(),
+            change,
+            max_change: params.max_commission_rate_change,
+        });
+    }
+    let commission_rates = match commission_rates {
+        Some(mut commission_rates) => {
+            commission_rates.set(new_rate, current_epoch, params);
+            commission_rates
+        }
+        None => Epoched::init(new_rate, current_epoch, params),
+    };
+    Ok(commission_rates)
+}
+
+/// The amount of a bond after the slashes applied to the validator at or after
+/// the start epoch of each of its deltas.
+fn slashed_bond_amount<TokenAmount>(
+    bond: &Bond<TokenAmount>,
+    slashes: &[Slash],
+) -> u64
+where
+    TokenAmount: Default + Copy + Into<u64>,
+{
+    bond.deltas.iter().fold(0_u64, |acc, (start_epoch, delta)| {
+        let mut delta: u64 = (*delta).into();
+        for slash in slashes {
+            if slash.epoch >= *start_epoch {
+                delta -= slash.rate * delta;
+            }
+        }
+        acc + delta
+    })
+}
+
+/// Split a validator's reward between its bonds in proportion to their
+/// amounts. The validator's commission is taken from the shares of the
+/// delegations. The commission and the remainder of the integer division go to
+/// the validator's self-bond, which also gets the whole reward if the
+/// validator has no bonds.
+fn split_rewards<Address>(
+    validator: &Address,
+    reward: u64,
+    commission_rate: BasisPoints,
+    bonds: Vec<(BondId<Address>, u64)>,
+) -> Vec<(BondId<Address>, u64)>
+where
+    Address: Display
+        + Debug
+        + Clone
+        + PartialEq
+        + Eq
+        + PartialOrd
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    let self_bond_id = BondId {
+        source: validator.clone(),
+        validator: validator.clone(),
+    };
+    let total_bonded: u128 =
+        bonds.iter().map(|(_, amount)| u128::from(*amount)).sum();
+    let mut shares: Vec<(BondId<Address>, u64)> = if total_bonded == 0 {
+        vec![]
+    } else {
+        bonds
+            .into_iter()
+            .filter(|(bond_id, _)| *bond_id != self_bond_id)
+            .filter_map(|(bond_id, amount)| {
+                // The share is at most the reward, so it fits into `u64`
+                let share = (u128::from(reward) * u128::from(amount)
+                    / total_bonded) as u64;
+                let share = share - commission_rate * share;
+                (share != 0).then(|| (bond_id, share))
+            })
+            .collect()
+    };
+    let delegations_rewards: u64 = shares.iter().map(|(_, share)| share).sum();
+    let self_bond_share = reward - delegations_rewards;
+    if self_bond_share != 0 {
+        shares.push((self_bond_id, self_bond_share));
+    }
+    shares
+}
+
+/// Jail a validator at the pipeline offset and remove it from the validator
+/// set. If the validator was active, the inactive validator with the most
+/// voting power takes its place. Returns `false` if the validator is already
+/// jailed at the pipeline offset.
+fn jail<Address>(
+    params: &PosParams,
+    validator: &Address,
+    state: &mut ValidatorStates,
+    validator_set: &mut ValidatorSets<Address>,
+    current_epoch: Epoch,
+) -> bool
+where
+    Address: Debug
+        + Clone
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    let pipeline_epoch =
+        current_epoch + DynEpochOffset::PipelineLen.value(params);
+    if let Some(ValidatorState::Jailed { .. }) = state.get(pipeline_epoch) {
+        return false;
+    }
+    state.set(
+        ValidatorState::Jailed {
+            until: pipeline_epoch + params.jail_len,
+        },
+        current_epoch,
+        params,
+    );
+    validator_set.update_from_offset(
+        |validator_set, _epoch| {
+            let active = validator_set
+                .active
+                .iter()
+                .find(|active| &active.address == validator)
+                .cloned();
+            match active {
+                Some(active) => {
+                    validator_set.active.remove(&active);
+                    if let Some(activate_max) =
+                        validator_set.inactive.pop_last_shim()
+                    {
+                        validator_set.active.insert(activate_max);
+                    }
+                }
+                None => validator_set
+                    .inactive
+                    .retain(|inactive| &inactive.address != validator),
+            }
+        },
+        current_epoch,
+        DynEpochOffset::PipelineLen,
+        params,
+    );
+    true
+}
+
+/// Unjail a validator at the pipeline offset, if its jail period is over by
+/// then, and add it back to the validator set with its voting power.
+fn unjail<Address, TokenChange>(
+    params: &PosParams,
+    validator: &Address,
+    state: &mut ValidatorStates,
+    total_deltas: Option<&ValidatorTotalDeltas<TokenChange>>,
+    validator_set: &mut ValidatorSets<Address>,
+    current_epoch: Epoch,
+) -> Result<(), UnjailError<Address>>
+where
+    Address: Display
+        + Debug
+        + Clone
+        + Ord
+        + Hash
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+    TokenChange: Debug
+        + Default
+        + Clone
+        + Copy
+        + Add<Output = TokenChange>
+        + Into<i128>
+        + BorshDeserialize
+        + BorshSerialize
+        + BorshSchema,
+{
+    let pipeline_epoch =
+        current_epoch + DynEpochOffset::PipelineLen.value(params);
+    match state.get(pipeline_epoch) {
+        Some(ValidatorState::Jailed { until }) if *until > pipeline_epoch => {
+            return Err(UnjailError::JailPeriodNotOver {
+                validator: validator.clone(),
+                until: *until,
+                pipeline_epoch,
+            });
+        }
+        Some(ValidatorState::Jailed { .. }) => {}
+        _ => return Err(UnjailError::NotJailed(validator.clone())),
+    }
+    state.set(ValidatorState::Candidate, current_epoch, params);
+    validator_set.update_from_offset(
+        |validator_set, epoch| {
+            // Find the validator's voting power at the epoch that's being
+            // updated from its total deltas
+            let tokens: i128 = total_deltas
+                .and_then(|d| d.get(epoch))
+                .unwrap_or_default()
+                .into();
+            let tokens: u64 = TryFrom::try_from(tokens).unwrap_or_default();
+            let validator = WeightedValidator {
+                voting_power: VotingPower::from_tokens(tokens, params),
+                address: validator.clone(),
+            };
+            let min_active_validator = validator_set.active.first_shim();
+            let min_voting_power = min_active_validator
+                .map(|v| v.voting_power)
+                .unwrap_or_default();
+            if validator_set.active.len() < params.max_validator_slots as usize
+            {
+                validator_set.active.insert(validator);
+            } else if validator.voting_power > min_voting_power {
+                let deactivate_min = validator_set.active.pop_first_shim();
+                validator_set.active.insert(validator);
+                if let Some(deactivate_min) = deactivate_min {
+                    validator_set.inactive.insert(deactivate_min);
+                }
+            } else {
+                validator_set.inactive.insert(validator);
+            }
+        },
+        current_epoch,
+        DynEpochOffset::PipelineLen,
+        params,
+    );
+    Ok(())
+}
@@ -32,6 +32,21 @@ pub struct PosParams {
     /// Portion of validator's stake that should be slashed on a light client
     /// attack. Given in basis points (slashed amount per ten thousand tokens).
     pub light_client_attack_slash_rate: BasisPoints,
+    /// Minimum amount of tokens that a validator has to self-bond. Given in
+    /// micro units of the staking token.
+    pub min_validator_self_bond: u64,
+    /// Optional maximum amount of tokens that may be delegated to a single
+    /// validator. Given in micro units of the staking token.
+    pub max_validator_delegations: Option<u64>,
+    /// Maximum change of a validator's commission rate from one epoch to the
+    /// next. Given in basis points.
+    pub max_commission_rate_change: BasisPoints,
+    /// Maximum number of consecutive blocks that an active validator may miss
+    /// signing before it's jailed for downtime.
+    pub max_missed_blocks: u64,
+    /// Minimum number of epochs for which a validator is jailed for downtime,
+    /// counted from the epoch in which it's removed from the validator set.
+    pub jail_len: u64,
 }
 
 impl Default for PosParams {
@@ -48,6 +63,13 @@ impl Default for PosParams {
             duplicate_vote_slash_rate: BasisPoints::new(500),
             // slash 5%
             light_client_attack_slash_rate: BasisPoints::new(500),
+            // 1000 tokens
+            min_validator_self_bond: 1_000_000_000,
+            max_validator_delegations: None,
+            // 1% per epoch
+            max_commission_rate_change: BasisPoints::new(100),
+            max_missed_blocks: 500,
+            jail_len: 2,
         }
     }
 }
@@ -69,6 +91,10 @@ pub enum ValidationError {
          pipeline: {1}"
     )]
     UnbondingLenTooShort(u64, u64),
+    #[error(
+        "Maximum commission rate change cannot be greater than 100%, got {0}"
+    )]
+    MaxCommissionRateChangeTooLarge(BasisPoints),
 }
 
 /// From Tendermint: <https://github.com/tendermint/tendermint/blob/master/spec/abci/apps.md#updating-the-validator-set>
@@ -95,6 +121,12 @@ impl PosParams {
             ))
         }
 
+        if self.max_commission_rate_change > BasisPoints::new(10_000) {
+            errors.push(ValidationError::MaxCommissionRateChangeTooLarge(
+                self.max_commission_rate_change,
+            ))
+        }
+
         // Check maximum total voting power cannot get larger than what
         // Tendermint allows
         let max_total_voting_power = self.max_validator_slots
@@ -167,6 +199,9 @@ pub mod testing {
                 pipeline_len,
                 unbonding_len,
                 votes_per_token: BasisPoints::new(votes_per_token),
+                // No bond limits, so that arbitrary bonds can be applied
+                min_validator_self_bond: 0,
+                max_validator_delegations: None,
                 // The rest of the parameters that are not being used in the PoS
                 // VP are constant for now
                 ..Default::default()
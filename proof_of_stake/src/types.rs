@@ -1,7 +1,7 @@
 //! Proof of Stake data types
 
 use core::fmt::Debug;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::hash::Hash;
@@ -20,6 +20,8 @@ pub type ValidatorConsensusKeys<PublicKey> =
     Epoched<PublicKey, OffsetPipelineLen>;
 /// Epoched validator's state.
 pub type ValidatorStates = Epoched<ValidatorState, OffsetPipelineLen>;
+/// Epoched validator's commission rate.
+pub type ValidatorCommissionRates = Epoched<BasisPoints, OffsetPipelineLen>;
 /// Epoched validator's total deltas.
 pub type ValidatorTotalDeltas<TokenChange> =
     EpochedDelta<TokenChange, OffsetUnboundingLen>;
@@ -38,6 +40,9 @@ pub type ValidatorSets<Address> =
 /// Epoched total voting power.
 pub type TotalVotingPowers =
     EpochedDelta<VotingPowerDelta, OffsetUnboundingLen>;
+/// Rewards accrued by the validators in the current epoch, which haven't been
+/// distributed to their bonds yet.
+pub type ValidatorRewards<Address> = BTreeMap<Address, u64>;
 
 /// Epoch identifier. Epochs are identified by consecutive natural numbers.
 ///
@@ -279,7 +284,13 @@ pub enum ValidatorState {
     /// A `Candidate` validator may participate in the consensus. It is either
     /// in the active or inactive validator set.
     Candidate,
-    // TODO consider adding `Jailed`
+    /// A `Jailed` validator has been removed from the validator set for
+    /// downtime. It may not participate in the consensus until it's unjailed.
+    Jailed {
+        /// The first epoch in which the validator may be back in the
+        /// validator set
+        until: Epoch,
+    },
 }
 
 /// A bond is validator's self-bond or a delegation from a regular account to a
@@ -729,6 +740,17 @@ impl BasisPoints {
     pub fn new(value: u64) -> Self {
         Self(value)
     }
+
+    /// The absolute difference between two basis points values.
+    pub fn abs_diff(self, other: Self) -> Self {
+        Self(self.0.abs_diff(other.0))
+    }
+}
+
+impl From<BasisPoints> for u64 {
+    fn from(rate: BasisPoints) -> Self {
+        rate.0
+    }
 }
 
 impl Display for BasisPoints {
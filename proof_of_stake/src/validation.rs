@@ -1,7 +1,7 @@
 //! Validation of updated PoS data
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
@@ -14,10 +14,10 @@ use crate::btree_set::BTreeSetShims;
 use crate::epoched::DynEpochOffset;
 use crate::parameters::PosParams;
 use crate::types::{
-    BondId, Bonds, Epoch, Slashes, TotalVotingPowers, Unbonds,
-    ValidatorConsensusKeys, ValidatorSets, ValidatorState, ValidatorStates,
-    ValidatorTotalDeltas, ValidatorVotingPowers, VotingPower, VotingPowerDelta,
-    WeightedValidator,
+    BasisPoints, BondId, Bonds, Epoch, Slashes, TotalVotingPowers, Unbonds,
+    ValidatorCommissionRates, ValidatorConsensusKeys, ValidatorSets,
+    ValidatorState, ValidatorStates, ValidatorTotalDeltas,
+    ValidatorVotingPowers, VotingPower, VotingPowerDelta, WeightedValidator,
 };
 
 #[allow(missing_docs)]
@@ -47,6 +47,19 @@ where
     MissingNewValidatorConsensusKey(u64),
     #[error("Invalid validator consensus key update in epoch {0}")]
     InvalidValidatorConsensusKeyUpdate(u64),
+    #[error("Invalid validator commission rate update in epoch {0}")]
+    InvalidCommissionRateUpdate(u64),
+    #[error("Commission rate {1} of validator {0} is greater than 100%")]
+    CommissionRateTooHigh(Address, BasisPoints),
+    #[error(
+        "Commission rate of validator {address} changed by {change}, expected \
+         at most {max_change}"
+    )]
+    CommissionRateChangeTooLarge {
+        address: Address,
+        change: BasisPoints,
+        max_change: BasisPoints,
+    },
     #[error("Validator staking reward address is required for validator {0}")]
     StakingRewardAddressIsRequired(Address),
     #[error(
@@ -64,12 +77,13 @@ where
     InvalidLastUpdate,
     #[error(
         "Invalid staking token balances. Balance Δ {balance_delta}, bonds Δ \
-         {bond_delta}, unbonds Δ {unbond_delta}"
+         {bond_delta}, unbonds Δ {unbond_delta}, rewards Δ {rewards_delta}"
     )]
     InvalidBalances {
         balance_delta: TokenChange,
         bond_delta: TokenChange,
         unbond_delta: TokenChange,
+        rewards_delta: TokenChange,
     },
     #[error(
         "Data must be set or updated in the correct epoch. Got epoch {got}, \
@@ -163,6 +177,20 @@ where
     NewValidatorMissingInValidatorSet(Address),
     #[error("Validator set has not been updated for new validators.")]
     MissingValidatorSetUpdate,
+    #[error("Rewards of bond {0} can only be claimed in full")]
+    InvalidRewardsClaim(BondId<Address>),
+    #[error("The claimed rewards {1} have not been credited to {0}")]
+    RewardsNotCredited(Address, TokenChange),
+    #[error(
+        "The self-bond of validator {0} is {1}, which is below the minimum \
+         self-bond {2}"
+    )]
+    SelfBondBelowMinimum(Address, u64, u64),
+    #[error(
+        "The tokens delegated to validator {0} are {1}, which is over the \
+         delegation cap {2}"
+    )]
+    DelegationCapExceeded(Address, u64, u64),
 }
 
 /// An update of PoS data.
@@ -226,6 +254,18 @@ where
         /// List of slashes applied to the bond's validator
         slashes: Slashes,
     },
+    /// Bond rewards update
+    Rewards {
+        /// Bond ID
+        id: BondId<Address>,
+        /// Bond rewards prior and posterior state
+        data: Data<TokenAmount>,
+        /// The address to which the claimed rewards must be credited, i.e.
+        /// the bond's source for a delegation or the validator's staking
+        /// reward address for a self-bond, with its staking token balance
+        /// prior and posterior state
+        target: (Address, Data<TokenAmount>),
+    },
     /// A validator update
     Validator {
         /// Validator's address
@@ -269,6 +309,8 @@ where
     State(Data<ValidatorStates>),
     /// Consensus key update
     ConsensusKey(Data<ValidatorConsensusKeys<PublicKey>>),
+    /// Commission rate update
+    CommissionRate(Data<ValidatorCommissionRates>),
     /// Staking reward address update
     StakingRewardAddress(Data<Address>),
     /// Total deltas update
@@ -380,6 +422,13 @@ where
     let mut bond_delta: HashMap<Address, TokenChange> = HashMap::default();
     // Changes of validators' unbonds
     let mut unbond_delta: HashMap<Address, TokenChange> = HashMap::default();
+    // Changes of bonds' rewards
+    let mut rewards_delta = TokenChange::default();
+    // The claimed rewards that must be credited to their targets
+    let mut rewards_credits: HashMap<Address, TokenChange> = HashMap::default();
+    // Changes of the staking token balances of the claimed rewards' targets
+    let mut rewards_target_deltas: HashMap<Address, TokenChange> =
+        HashMap::default();
 
     // Changes of all validator total deltas (up to `unbonding_epoch`)
     let mut total_deltas: HashMap<Address, TokenChange> = HashMap::default();
@@ -409,6 +458,50 @@ where
     > = HashMap::default();
 
     let mut new_validators: HashMap<Address, NewValidator> = HashMap::default();
+    // Validators that are being unjailed, which are added back to the
+    // validator set
+    let mut unjailed_validators: HashSet<Address> = HashSet::default();
+    // Validators initialized in this transaction, which may set any initial
+    // commission rate
+    let initialized_validators: HashSet<Address> = changes
+        .iter()
+        .filter_map(|change| match change {
+            Validator {
+                address,
+                update: State(data),
+            } if data.pre.is_none() && data.post.is_some() => {
+                Some(address.clone())
+            }
+            _ => None,
+        })
+        .collect();
+
+    // A validator's self-bond may be unbonded in full, but otherwise it must
+    // not be below the minimum self-bond at the pipeline offset
+    for change in &changes {
+        if let Bond {
+            id,
+            data: Data {
+                post: Some(post), ..
+            },
+            ..
+        } = change
+        {
+            if id.source == id.validator {
+                let self_bond: u64 = post
+                    .get(pipeline_epoch)
+                    .map(|bond| bond.sum().into())
+                    .unwrap_or_default();
+                if self_bond < params.min_validator_self_bond {
+                    errors.push(Error::SelfBondBelowMinimum(
+                        id.validator.clone(),
+                        self_bond,
+                        params.min_validator_self_bond,
+                    ))
+                }
+            }
+        }
+    }
 
     for change in changes {
         match change {
@@ -477,6 +570,13 @@ where
                                 Some(Inactive),
                                 Some(Candidate) | Some(Pending),
                             ) => {}
+                            // A jailed validator may be unjailed once its jail
+                            // period is over
+                            (Some(Jailed { until }), Some(Candidate))
+                                if *until <= pipeline_epoch =>
+                            {
+                                unjailed_validators.insert(address.clone());
+                            }
                             _ => errors.push(Error::InvalidNewValidatorState(
                                 pipeline_epoch.into(),
                             )),
@@ -530,6 +630,95 @@ where
                         .push(Error::ValidatorStateIsRequired(address.clone())),
                     (None, None) => continue,
                 },
+                CommissionRate(data) => match (data.pre, data.post) {
+                    (None, Some(post))
+                        if initialized_validators.contains(&address) =>
+                    {
+                        if post.last_update() != current_epoch {
+                            errors.push(Error::InvalidLastUpdate)
+                        }
+                        // The initial rate must be set from the current epoch
+                        // and it must not be greater than 100%
+                        for epoch in Epoch::iter_range(
+                            current_epoch,
+                            pipeline_offset + 1,
+                        ) {
+                            match post.get(epoch) {
+                                Some(rate)
+                                    if *rate > BasisPoints::new(10_000) =>
+                                {
+                                    errors.push(Error::CommissionRateTooHigh(
+                                        address.clone(),
+                                        *rate,
+                                    ))
+                                }
+                                Some(_) => {}
+                                None => errors.push(
+                                    Error::InvalidCommissionRateUpdate(
+                                        epoch.into(),
+                                    ),
+                                ),
+                            }
+                        }
+                    }
+                    (pre, Some(post)) => {
+                        if post.last_update() != current_epoch {
+                            errors.push(Error::InvalidLastUpdate)
+                        }
+                        // Before pipeline epoch, the rate must not change
+                        for epoch in
+                            Epoch::iter_range(current_epoch, pipeline_offset)
+                        {
+                            let rate_pre =
+                                pre.as_ref().and_then(|pre| pre.get(epoch));
+                            if rate_pre != post.get(epoch) {
+                                errors.push(Error::InvalidCommissionRateUpdate(
+                                    epoch.into(),
+                                ))
+                            }
+                        }
+                        // At pipeline epoch, the rate must be set, it must not
+                        // be greater than 100% and it must not differ from
+                        // the rate in the epoch before by more than the maximum
+                        // change. A missing rate before it is 0.
+                        match post.get(pipeline_epoch) {
+                            Some(rate) => {
+                                if *rate > BasisPoints::new(10_000) {
+                                    errors.push(Error::CommissionRateTooHigh(
+                                        address.clone(),
+                                        *rate,
+                                    ))
+                                }
+                                let previous_rate = post
+                                    .get(pipeline_epoch - 1_u64)
+                                    .copied()
+                                    .unwrap_or_else(|| BasisPoints::new(0));
+                                let change = rate.abs_diff(previous_rate);
+                                if change > params.max_commission_rate_change {
+                                    errors.push(
+                                        Error::CommissionRateChangeTooLarge {
+                                            address: address.clone(),
+                                            change,
+                                            max_change: params
+                                                .max_commission_rate_change,
+                                        },
+                                    )
+                                }
+                            }
+                            None => {
+                                errors.push(Error::InvalidCommissionRateUpdate(
+                                    pipeline_epoch.into(),
+                                ))
+                            }
+                        }
+                    }
+                    (Some(_), None) => {
+                        errors.push(Error::InvalidCommissionRateUpdate(
+                            current_epoch.into(),
+                        ))
+                    }
+                    (None, None) => continue,
+                },
                 StakingRewardAddress(data) => match (data.pre, data.post) {
                     (Some(_), Some(post)) => {
                         if post == address {
@@ -1248,6 +1437,41 @@ where
                     _ => continue,
                 }
             }
+            Rewards {
+                id,
+                data,
+                target: (target, balance),
+            } => match (data.pre, data.post) {
+                // Rewards are only ever claimed in full, any other changes
+                // are applied by the protocol
+                (Some(pre), None) => {
+                    let rewards = TokenChange::from(pre);
+                    rewards_delta -= rewards;
+                    *rewards_credits.entry(target.clone()).or_default() +=
+                        rewards;
+                    let balance_pre =
+                        balance.pre.map(TokenChange::from).unwrap_or_default();
+                    let balance_post =
+                        balance.post.map(TokenChange::from).unwrap_or_default();
+                    rewards_target_deltas
+                        .insert(target, balance_post - balance_pre);
+                }
+                (pre, post) if pre != post => {
+                    errors.push(Error::InvalidRewardsClaim(id))
+                }
+                _ => continue,
+            },
+        }
+    }
+
+    // Check that the claimed rewards have been credited to their targets
+    for (target, credit) in rewards_credits {
+        let balance_delta = rewards_target_deltas
+            .get(&target)
+            .copied()
+            .unwrap_or_default();
+        if balance_delta < credit {
+            errors.push(Error::RewardsNotCredited(target, credit))
         }
     }
 
@@ -1339,6 +1563,8 @@ where
                                         && !pre.inactive.contains(validator)
                                         && !new_validators
                                             .contains_key(&validator.address)
+                                        && !unjailed_validators
+                                            .contains(&validator.address)
                                     {
                                         let mut is_valid = false;
 
@@ -1413,6 +1639,8 @@ where
                                         && !pre.inactive.contains(validator)
                                         && !new_validators
                                             .contains_key(&validator.address)
+                                        && !unjailed_validators
+                                            .contains(&validator.address)
                                     {
                                         let mut is_valid = false;
 
@@ -1685,11 +1913,12 @@ where
         .into_iter()
         .fold(TokenChange::default(), |acc, delta| acc + (*delta));
 
-    if balance_delta != bond_delta + unbond_delta {
+    if balance_delta != bond_delta + unbond_delta + rewards_delta {
         errors.push(Error::InvalidBalances {
             balance_delta,
             bond_delta,
             unbond_delta,
+            rewards_delta,
         })
     }
 
@@ -0,0 +1,276 @@
+//! Benchmarks of the transaction pipeline: decoding of the protobuf
+//! transaction, encoding of the transaction data, wasm execution of
+//! transactions and validity predicates and committing the storage.
+//!
+//! The wasm benchmarks use the pre-built wasm from `wasm_for_tests` and the
+//! standard transactions from the `wasm` directory, which have to be built
+//! first with `make build-wasm-scripts`.
+//!
+//! Run with `make bench`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
+
+use anoma::ledger::gas::{BlockGasMeter, VpGasMeter};
+use anoma::ledger::storage::testing::TestStorage;
+use anoma::ledger::storage::write_log::WriteLog;
+use anoma::proto::Tx;
+use anoma::types::address::{self, Address};
+use anoma::types::key::testing::keypair_1;
+use anoma::types::storage::{BlockHash, BlockHeight, Key};
+use anoma::types::token;
+use anoma::types::transaction::pos;
+use anoma::vm::wasm::{self, compilation_cache};
+use borsh::{BorshDeserialize, BorshSerialize};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const WASM_FOR_TESTS_DIR: &str = "../wasm_for_tests";
+const WASM_DIR: &str = "../wasm";
+const TX_NO_OP_WASM: &str = "tx_no_op.wasm";
+const TX_WRITE_STORAGE_KEY_WASM: &str = "tx_write_storage_key.wasm";
+const TX_TRANSFER_WASM: &str = "tx_transfer.wasm";
+const VP_ALWAYS_TRUE_WASM: &str = "vp_always_true.wasm";
+const VP_USER_WASM: &str = "vp_user.wasm";
+
+/// Read a wasm from the `wasm_for_tests` directory
+fn wasm_for_tests(name: &str) -> Vec<u8> {
+    let path = std::path::Path::new(WASM_FOR_TESTS_DIR).join(name);
+    std::fs::read(&path)
+        .unwrap_or_else(|err| panic!("cannot load wasm {:?}: {}", path, err))
+}
+
+/// Read a standard wasm from the `wasm` directory, resolving its name via
+/// the checksums file
+fn standard_wasm(name: &str) -> Vec<u8> {
+    let checksums_path = std::path::Path::new(WASM_DIR).join("checksums.json");
+    let checksums: HashMap<String, String> = serde_json::from_slice(
+        &std::fs::read(&checksums_path).expect("cannot load wasm checksums"),
+    )
+    .expect("wasm checksums must be valid JSON");
+    let file_name = checksums.get(name).map(String::as_str).unwrap_or(name);
+    let path = std::path::Path::new(WASM_DIR).join(file_name);
+    std::fs::read(&path).unwrap_or_else(|err| {
+        panic!(
+            "cannot load wasm {:?}: {}. Build the wasm with `make \
+             build-wasm-scripts` first.",
+            path, err
+        )
+    })
+}
+
+/// Wrap the given data in a signed tx data, as expected by the tx wasm
+fn signed_tx_data(data: Vec<u8>) -> Vec<u8> {
+    Tx::new(vec![], Some(data))
+        .sign(&keypair_1())
+        .data
+        .expect("signed tx must have data")
+}
+
+fn transfer(source: Address, target: Address) -> token::Transfer {
+    token::Transfer {
+        source,
+        target,
+        token: address::xan(),
+        amount: token::Amount::from(10_000_000),
+        memo: None,
+    }
+}
+
+fn proto_decoding(c: &mut Criterion) {
+    let keypair = keypair_1();
+    let data = transfer(
+        address::testing::established_address_1(),
+        address::testing::established_address_2(),
+    )
+    .try_to_vec()
+    .unwrap();
+    let tx = Tx::new(vec![0; 1024], Some(data)).sign(&keypair);
+    let tx_bytes = tx.to_bytes();
+
+    c.bench_function("proto_tx_decode", |b| {
+        b.iter(|| Tx::try_from(black_box(&tx_bytes[..])).unwrap())
+    });
+    c.bench_function("proto_tx_encode", |b| {
+        b.iter(|| black_box(&tx).to_bytes())
+    });
+}
+
+fn borsh_tx_data(c: &mut Criterion) {
+    let transfer = transfer(
+        address::testing::established_address_1(),
+        address::testing::established_address_2(),
+    );
+    let bond = pos::Bond {
+        validator: address::testing::established_address_1(),
+        amount: token::Amount::from(1_000_000_000),
+        source: Some(address::testing::established_address_2()),
+    };
+
+    let mut group = c.benchmark_group("borsh_tx_data");
+    group.bench_function("transfer_encode", |b| {
+        b.iter(|| black_box(&transfer).try_to_vec().unwrap())
+    });
+    let transfer_bytes = transfer.try_to_vec().unwrap();
+    group.bench_function("transfer_decode", |b| {
+        b.iter(|| {
+            token::Transfer::try_from_slice(black_box(&transfer_bytes[..]))
+                .unwrap()
+        })
+    });
+    group.bench_function("bond_encode", |b| {
+        b.iter(|| black_box(&bond).try_to_vec().unwrap())
+    });
+    let bond_bytes = bond.try_to_vec().unwrap();
+    group.bench_function("bond_decode", |b| {
+        b.iter(|| pos::Bond::try_from_slice(black_box(&bond_bytes[..])).unwrap())
+    });
+    group.finish();
+}
+
+fn wasm_tx(c: &mut Criterion) {
+    let storage = TestStorage::default();
+    let (mut vp_cache, _vp_cache_dir) =
+        compilation_cache::common::testing::cache();
+    let (mut tx_cache, _tx_cache_dir) =
+        compilation_cache::common::testing::cache();
+
+    let mut group = c.benchmark_group("wasm_tx");
+    let tx_no_op = wasm_for_tests(TX_NO_OP_WASM);
+    group.bench_function("tx_no_op", |b| {
+        b.iter(|| {
+            let mut write_log = WriteLog::default();
+            let mut gas_meter = BlockGasMeter::default();
+            wasm::run::tx(
+                &storage,
+                &mut write_log,
+                &mut gas_meter,
+                &tx_no_op,
+                &[],
+                &mut vp_cache,
+                &mut tx_cache,
+            )
+            .unwrap()
+        })
+    });
+
+    let tx_write = wasm_for_tests(TX_WRITE_STORAGE_KEY_WASM);
+    let key = Key::parse("bench_key").unwrap();
+    let tx_data = signed_tx_data(key.try_to_vec().unwrap());
+    group.bench_function("tx_write_storage_key", |b| {
+        b.iter(|| {
+            let mut write_log = WriteLog::default();
+            let mut gas_meter = BlockGasMeter::default();
+            wasm::run::tx(
+                &storage,
+                &mut write_log,
+                &mut gas_meter,
+                &tx_write,
+                &tx_data,
+                &mut vp_cache,
+                &mut tx_cache,
+            )
+            .unwrap()
+        })
+    });
+
+    // The standard transfer with a funded source
+    let mut storage = TestStorage::default();
+    let source = address::testing::established_address_1();
+    let target = address::testing::established_address_2();
+    let balance_key = token::balance_key(&address::xan(), &source);
+    storage
+        .write(
+            &balance_key,
+            token::Amount::from(1_000_000_000_000).try_to_vec().unwrap(),
+        )
+        .unwrap();
+    let tx_transfer = standard_wasm(TX_TRANSFER_WASM);
+    let tx_data = signed_tx_data(transfer(source, target).try_to_vec().unwrap());
+    group.bench_function("tx_transfer", |b| {
+        b.iter(|| {
+            let mut write_log = WriteLog::default();
+            let mut gas_meter = BlockGasMeter::default();
+            wasm::run::tx(
+                &storage,
+                &mut write_log,
+                &mut gas_meter,
+                &tx_transfer,
+                &tx_data,
+                &mut vp_cache,
+                &mut tx_cache,
+            )
+            .unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn wasm_vp(c: &mut Criterion) {
+    let mut storage = TestStorage::default();
+    let addr = storage.address_gen.generate_address("bench seed");
+    let write_log = WriteLog::default();
+    let keys_changed = BTreeSet::new();
+    let verifiers = BTreeSet::new();
+    let (vp_cache, _vp_cache_dir) = compilation_cache::common::testing::cache();
+
+    let keypair = keypair_1();
+    let data = transfer(addr.clone(), address::testing::established_address_2())
+        .try_to_vec()
+        .unwrap();
+    let tx = Tx::new(vec![], Some(data)).sign(&keypair);
+
+    let mut group = c.benchmark_group("wasm_vp");
+    for (name, vp_code) in [
+        ("vp_always_true", wasm_for_tests(VP_ALWAYS_TRUE_WASM)),
+        ("vp_user", standard_wasm(VP_USER_WASM)),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut gas_meter = VpGasMeter::new(0);
+                wasm::run::vp(
+                    &vp_code,
+                    &tx,
+                    &addr,
+                    &storage,
+                    &write_log,
+                    &mut gas_meter,
+                    &keys_changed,
+                    &verifiers,
+                    vp_cache.clone(),
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn storage_commit(c: &mut Criterion) {
+    const KEYS_PER_BLOCK: u64 = 100;
+
+    let mut storage = TestStorage::default();
+    let mut height = 0_u64;
+    c.bench_function("storage_commit_100_writes", |b| {
+        b.iter(|| {
+            height += 1;
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .unwrap();
+            for ix in 0..KEYS_PER_BLOCK {
+                let key = Key::parse(format!("bench_key_{}", ix)).unwrap();
+                storage.write(&key, height.try_to_vec().unwrap()).unwrap();
+            }
+            storage.commit().unwrap()
+        })
+    });
+}
+
+criterion_group!(
+    tx_pipeline,
+    proto_decoding,
+    borsh_tx_data,
+    wasm_tx,
+    wasm_vp,
+    storage_commit
+);
+criterion_main!(tx_pipeline);
@@ -1,8 +1,11 @@
 //! Proof-of-Stake storage keys and storage integration via [`PosBase`] trait.
 
+use std::str::FromStr;
+
 use anoma_proof_of_stake::parameters::PosParams;
 use anoma_proof_of_stake::types::{
-    TotalVotingPowers, ValidatorStates, ValidatorVotingPowers,
+    TotalVotingPowers, ValidatorCommissionRates, ValidatorStates,
+    ValidatorVotingPowers,
 };
 use anoma_proof_of_stake::{types, PosBase};
 
@@ -23,11 +26,16 @@ const VALIDATOR_STAKING_REWARD_ADDRESS_STORAGE_KEY: &str =
     "staking_reward_address";
 const VALIDATOR_CONSENSUS_KEY_STORAGE_KEY: &str = "consensus_key";
 const VALIDATOR_STATE_STORAGE_KEY: &str = "state";
+const VALIDATOR_COMMISSION_RATE_STORAGE_KEY: &str = "commission_rate";
 const VALIDATOR_TOTAL_DELTAS_STORAGE_KEY: &str = "total_deltas";
 const VALIDATOR_VOTING_POWER_STORAGE_KEY: &str = "voting_power";
+const VALIDATOR_MISSED_BLOCKS_STORAGE_KEY: &str = "missed_blocks";
 const SLASHES_PREFIX: &str = "slash";
 const BOND_STORAGE_KEY: &str = "bond";
 const UNBOND_STORAGE_KEY: &str = "unbond";
+const BOND_REWARDS_STORAGE_KEY: &str = "rewards";
+const BOND_AUTO_COMPOUND_STORAGE_KEY: &str = "auto_compound";
+const VALIDATOR_REWARDS_STORAGE_KEY: &str = "validator_rewards";
 const VALIDATOR_SET_STORAGE_KEY: &str = "validator_set";
 const TOTAL_VOTING_POWER_STORAGE_KEY: &str = "total_voting_power";
 
@@ -58,11 +66,16 @@ pub fn is_params_key(key: &Key) -> bool {
     }
 }
 
-/// Storage key prefix for validator data.
-fn validator_prefix(validator: &Address) -> Key {
+/// Storage key prefix for the data of all validators.
+pub fn validators_prefix() -> Key {
     Key::from(ADDRESS.to_db_key())
         .push(&VALIDATOR_STORAGE_PREFIX.to_owned())
         .expect("Cannot obtain a storage key")
+}
+
+/// Storage key prefix for validator data.
+fn validator_prefix(validator: &Address) -> Key {
+    validators_prefix()
         .push(&validator.to_db_key())
         .expect("Cannot obtain a storage key")
 }
@@ -167,6 +180,31 @@ pub fn is_validator_state_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Storage key for validator's commission rate.
+pub fn validator_commission_rate_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_COMMISSION_RATE_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for validator's commission rate?
+pub fn is_validator_commission_rate_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_COMMISSION_RATE_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
 /// Storage key for validator's total deltas.
 pub fn validator_total_deltas_key(validator: &Address) -> Key {
     validator_prefix(validator)
@@ -217,6 +255,33 @@ pub fn is_validator_voting_power_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Storage key for the number of consecutive blocks that a validator has
+/// missed signing.
+pub fn validator_missed_blocks_key(validator: &Address) -> Key {
+    validator_prefix(validator)
+        .push(&VALIDATOR_MISSED_BLOCKS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the number of consecutive blocks that a validator has
+/// missed signing?
+pub fn is_validator_missed_blocks_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(validator),
+            DbKeySeg::StringSeg(key),
+        ] if addr == &ADDRESS
+            && prefix == VALIDATOR_STORAGE_PREFIX
+            && key == VALIDATOR_MISSED_BLOCKS_STORAGE_KEY =>
+        {
+            Some(validator)
+        }
+        _ => None,
+    }
+}
+
 /// Storage prefix for slashes.
 pub fn slashes_prefix() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -317,6 +382,97 @@ pub fn is_unbond_key(key: &Key) -> Option<BondId> {
     }
 }
 
+/// Storage key prefix for all bond rewards.
+pub fn bond_rewards_prefix() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&BOND_REWARDS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Storage key for the rewards of a bond with the given ID (source and
+/// validator).
+pub fn bond_rewards_key(bond_id: &BondId) -> Key {
+    bond_rewards_prefix()
+        .push(&bond_id.source.to_db_key())
+        .expect("Cannot obtain a storage key")
+        .push(&bond_id.validator.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the rewards of a bond?
+pub fn is_bond_rewards_key(key: &Key) -> Option<BondId> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(source),
+            DbKeySeg::AddressSeg(validator),
+        ] if addr == &ADDRESS && prefix == BOND_REWARDS_STORAGE_KEY => {
+            Some(BondId {
+                source: source.clone(),
+                validator: validator.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Storage key prefix for all bonds with auto-compounding rewards.
+pub fn bond_auto_compound_prefix() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&BOND_AUTO_COMPOUND_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Storage key for the auto-compounding flag of a bond with the given ID
+/// (source and validator). When set, the bond's rewards are re-bonded to its
+/// validator at the end of each epoch instead of being accumulated.
+pub fn bond_auto_compound_key(bond_id: &BondId) -> Key {
+    bond_auto_compound_prefix()
+        .push(&bond_id.source.to_db_key())
+        .expect("Cannot obtain a storage key")
+        .push(&bond_id.validator.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the auto-compounding flag of a bond?
+pub fn is_bond_auto_compound_key(key: &Key) -> Option<BondId> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::AddressSeg(source),
+            DbKeySeg::AddressSeg(validator),
+        ] if addr == &ADDRESS && prefix == BOND_AUTO_COMPOUND_STORAGE_KEY => {
+            Some(BondId {
+                source: source.clone(),
+                validator: validator.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Storage key for the rewards accrued by the validators in the current epoch.
+pub fn validator_rewards_key() -> Key {
+    Key::from(ADDRESS.to_db_key())
+        .push(&VALIDATOR_REWARDS_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Is storage key for the rewards accrued by the validators in the current
+/// epoch?
+pub fn is_validator_rewards_key(key: &Key) -> bool {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(addr), DbKeySeg::StringSeg(key)]
+            if addr == &ADDRESS && key == VALIDATOR_REWARDS_STORAGE_KEY =>
+        {
+            true
+        }
+        _ => false,
+    }
+}
+
 /// Storage key for validator set (active and inactive).
 pub fn validator_set_key() -> Key {
     Key::from(ADDRESS.to_db_key())
@@ -415,6 +571,15 @@ where
         value.map(|value| decode(value).unwrap())
     }
 
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<ValidatorCommissionRates> {
+        let (value, _gas) =
+            self.read(&validator_commission_rate_key(key)).unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
     fn read_validator_total_deltas(
         &self,
         key: &Self::Address,
@@ -440,6 +605,54 @@ where
             .unwrap_or_default()
     }
 
+    fn read_validator_missed_blocks(&self, key: &Self::Address) -> u64 {
+        let (value, _gas) =
+            self.read(&validator_missed_blocks_key(key)).unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
+    fn read_bond(&self, key: &BondId) -> Option<Bonds> {
+        let (value, _gas) = self.read(&bond_key(key)).unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
+    fn read_validator_bonds(
+        &self,
+        validator: &Self::Address,
+    ) -> Vec<(BondId, Bonds)> {
+        let (iter, _gas) = self.iter_prefix(&bonds_prefix());
+        iter.filter_map(|(key, value, _gas)| {
+            let key = Key::from_str(&key).ok()?;
+            let bond_id = is_bond_key(&key)?;
+            if &bond_id.validator != validator {
+                return None;
+            }
+            Some((bond_id, decode(value).unwrap()))
+        })
+        .collect()
+    }
+
+    fn read_bond_rewards(&self, key: &BondId) -> Option<token::Amount> {
+        let (value, _gas) = self.read(&bond_rewards_key(key)).unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
+    fn read_bond_auto_compound(&self, key: &BondId) -> bool {
+        let (value, _gas) = self.read(&bond_auto_compound_key(key)).unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
+    fn read_validator_rewards(&self) -> types::ValidatorRewards<Address> {
+        let (value, _gas) = self.read(&validator_rewards_key()).unwrap();
+        value
+            .map(|value| decode(value).unwrap())
+            .unwrap_or_default()
+    }
+
     fn read_validator_set(&self) -> ValidatorSets {
         let (value, _gas) = self.read(&validator_set_key()).unwrap();
         decode(value.unwrap()).unwrap()
@@ -516,10 +729,30 @@ where
             .unwrap();
     }
 
+    fn write_validator_missed_blocks(
+        &mut self,
+        key: &Self::Address,
+        value: u64,
+    ) {
+        self.write(&validator_missed_blocks_key(key), encode(&value))
+            .unwrap();
+    }
+
     fn write_bond(&mut self, key: &BondId, value: &Bonds) {
         self.write(&bond_key(key), encode(value)).unwrap();
     }
 
+    fn write_bond_rewards(&mut self, key: &BondId, value: token::Amount) {
+        self.write(&bond_rewards_key(key), encode(&value)).unwrap();
+    }
+
+    fn write_validator_rewards(
+        &mut self,
+        value: &types::ValidatorRewards<Address>,
+    ) {
+        self.write(&validator_rewards_key(), encode(value)).unwrap();
+    }
+
     fn write_validator_set(&mut self, value: &ValidatorSets) {
         self.write(&validator_set_key(), encode(value)).unwrap();
     }
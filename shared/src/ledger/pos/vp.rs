@@ -6,8 +6,8 @@ use std::panic::{RefUnwindSafe, UnwindSafe};
 pub use anoma_proof_of_stake;
 pub use anoma_proof_of_stake::parameters::PosParams;
 pub use anoma_proof_of_stake::types::{
-    self, Slash, Slashes, TotalVotingPowers, ValidatorStates,
-    ValidatorVotingPowers,
+    self, Slash, Slashes, TotalVotingPowers, ValidatorCommissionRates,
+    ValidatorStates, ValidatorVotingPowers,
 };
 use anoma_proof_of_stake::validation::validate;
 use anoma_proof_of_stake::{validation, PosReadOnly};
@@ -16,7 +16,8 @@ use itertools::Itertools;
 use thiserror::Error;
 
 use super::{
-    bond_key, is_bond_key, is_params_key, is_total_voting_power_key,
+    bond_key, bond_rewards_key, is_bond_auto_compound_key, is_bond_key,
+    is_bond_rewards_key, is_params_key, is_total_voting_power_key,
     is_unbond_key, is_validator_set_key,
     is_validator_staking_reward_address_key, is_validator_total_deltas_key,
     is_validator_voting_power_key, params_key, staking_token_address,
@@ -29,13 +30,14 @@ use super::{
 use crate::ledger::governance::vp::is_proposal_accepted;
 use crate::ledger::native_vp::{self, Ctx, NativeVp};
 use crate::ledger::pos::{
-    is_validator_address_raw_hash_key, is_validator_consensus_key_key,
-    is_validator_state_key,
+    is_validator_address_raw_hash_key, is_validator_commission_rate_key,
+    is_validator_consensus_key_key, is_validator_state_key,
+    validator_commission_rate_key,
 };
 use crate::ledger::storage::types::decode;
 use crate::ledger::storage::{self as ledger_storage, StorageHasher};
 use crate::types::address::{Address, InternalAddress};
-use crate::types::storage::{Key, KeySeg};
+use crate::types::storage::{Epoch, Key, KeySeg};
 use crate::types::{key, token};
 use crate::vm::WasmCacheAccess;
 
@@ -70,6 +72,38 @@ where
     pub fn new(ctx: Ctx<'a, DB, H, CA>) -> Self {
         Self { ctx }
     }
+
+    /// Find the amount of tokens delegated to the given validator at the
+    /// given epoch in the posterior state, i.e. the validator's total stake
+    /// without its self-bond.
+    fn read_delegations_post(
+        &self,
+        validator: &Address,
+        epoch: Epoch,
+    ) -> Result<u64> {
+        let total_stake: i128 = self
+            .ctx
+            .read_post(&validator_total_deltas_key(validator))?
+            .and_then(|bytes| {
+                ValidatorTotalDeltas::try_from_slice(&bytes[..]).ok()
+            })
+            .and_then(|deltas| deltas.get(epoch))
+            .map(Into::into)
+            .unwrap_or_default();
+        let self_bond_id = BondId {
+            source: validator.clone(),
+            validator: validator.clone(),
+        };
+        let self_bond: u64 = self
+            .ctx
+            .read_post(&bond_key(&self_bond_id))?
+            .and_then(|bytes| Bonds::try_from_slice(&bytes[..]).ok())
+            .and_then(|bonds| bonds.get(epoch))
+            .map(|bond| bond.sum().into())
+            .unwrap_or_default();
+        let delegations = (total_stake - self_bond as i128).max(0);
+        Ok(u64::try_from(delegations).unwrap_or(u64::MAX))
+    }
 }
 
 // TODO this is temporarily to run PoS native VP in a new thread to avoid
@@ -119,6 +153,10 @@ where
         let addr = Address::Internal(Self::ADDR);
         let mut changes: Vec<DataUpdate<_, _, _, _>> = vec![];
         let current_epoch = self.ctx.get_block_epoch()?;
+        let params = self.read_pos_params();
+        let pipeline_epoch = current_epoch + params.pipeline_len;
+        // Validators whose delegations have increased in this tx
+        let mut delegated_validators: BTreeSet<Address> = BTreeSet::default();
         for key in keys_changed {
             if is_params_key(key) {
                 let proposal_id = u64::try_from_slice(tx_data).ok();
@@ -151,10 +189,41 @@ where
                 let post = self.ctx.read_post(key)?.and_then(|bytes| {
                     ValidatorStates::try_from_slice(&bytes[..]).ok()
                 });
+                // The state of an existing validator can only be changed by
+                // the validator itself, i.e. to unjail it
+                if pre.is_some() && !verifiers.contains(validator) {
+                    return Ok(false);
+                }
                 changes.push(Validator {
                     address: validator.clone(),
                     update: State(Data { pre, post }),
                 });
+            } else if let Some(validator) =
+                is_validator_commission_rate_key(key)
+            {
+                // The commission rate can only be changed by an existing
+                // validator or set by a new validator initialized in this tx
+                let is_new_validator =
+                    self.read_validator_state(validator).is_none()
+                        && self
+                            .ctx
+                            .has_key_post(&validator_state_key(validator))?;
+                if !is_new_validator
+                    && (!verifiers.contains(validator)
+                        || self.read_validator_state(validator).is_none())
+                {
+                    return Ok(false);
+                }
+                let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
+                    ValidatorCommissionRates::try_from_slice(&bytes[..]).ok()
+                });
+                let post = self.ctx.read_post(key)?.and_then(|bytes| {
+                    ValidatorCommissionRates::try_from_slice(&bytes[..]).ok()
+                });
+                changes.push(Validator {
+                    address: validator.clone(),
+                    update: CommissionRate(Data { pre, post }),
+                });
             } else if let Some(validator) =
                 is_validator_staking_reward_address_key(key)
             {
@@ -252,6 +321,19 @@ where
                     .ctx
                     .read_post(key)?
                     .and_then(|bytes| Bonds::try_from_slice(&bytes[..]).ok());
+                // New delegations are checked against the delegation cap
+                let bonded_at_pipeline = |bonds: &Option<Bonds>| -> u64 {
+                    bonds
+                        .as_ref()
+                        .and_then(|bonds| bonds.get(pipeline_epoch))
+                        .map(|bond| bond.sum().into())
+                        .unwrap_or_default()
+                };
+                if bond_id.source != bond_id.validator
+                    && bonded_at_pipeline(&post) > bonded_at_pipeline(&pre)
+                {
+                    delegated_validators.insert(bond_id.validator.clone());
+                }
                 // For bonds, we need to look-up slashes
                 let slashes = self
                     .ctx
@@ -283,6 +365,62 @@ where
                     data: Data { pre, post },
                     slashes,
                 });
+            } else if let Some(bond_id) = is_bond_rewards_key(key) {
+                // The rewards can only be claimed by the bond's source
+                if !verifiers.contains(&bond_id.source) {
+                    return Ok(false);
+                }
+                let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
+                    token::Amount::try_from_slice(&bytes[..]).ok()
+                });
+                let post = self.ctx.read_post(key)?.and_then(|bytes| {
+                    token::Amount::try_from_slice(&bytes[..]).ok()
+                });
+                // The rewards of a self-bond are credited to the validator's
+                // staking reward address and those of a delegation to the
+                // delegator
+                let target = if bond_id.source == bond_id.validator {
+                    self.read_validator_staking_reward_address(
+                        &bond_id.validator,
+                    )
+                    .unwrap_or_else(|| bond_id.validator.clone())
+                } else {
+                    bond_id.source.clone()
+                };
+                let balance_key =
+                    token::balance_key(&staking_token_address(), &target);
+                let balance_pre =
+                    self.ctx.read_pre(&balance_key)?.and_then(|bytes| {
+                        token::Amount::try_from_slice(&bytes[..]).ok()
+                    });
+                let balance_post =
+                    self.ctx.read_post(&balance_key)?.and_then(|bytes| {
+                        token::Amount::try_from_slice(&bytes[..]).ok()
+                    });
+                changes.push(Rewards {
+                    id: bond_id,
+                    data: Data { pre, post },
+                    target: (
+                        target,
+                        Data {
+                            pre: balance_pre,
+                            post: balance_post,
+                        },
+                    ),
+                });
+            } else if let Some(bond_id) = is_bond_auto_compound_key(key) {
+                // The auto-compounding flag can only be changed by the
+                // bond's source and it can only be set for an existing bond
+                if !verifiers.contains(&bond_id.source) {
+                    return Ok(false);
+                }
+                if let Some(bytes) = self.ctx.read_post(key)? {
+                    let is_set =
+                        matches!(bool::try_from_slice(&bytes[..]), Ok(true));
+                    if !is_set || !self.ctx.has_key_post(&bond_key(&bond_id))? {
+                        return Ok(false);
+                    }
+                }
             } else if is_total_voting_power_key(key) {
                 let pre = self.ctx.read_pre(key)?.and_then(|bytes| {
                     TotalVotingPowers::try_from_slice(&bytes[..]).ok()
@@ -301,8 +439,20 @@ where
             }
         }
 
-        let params = self.read_pos_params();
-        let errors = validate(&params, changes, current_epoch);
+        let mut errors = validate(&params, changes, current_epoch);
+        if let Some(max_delegations) = params.max_validator_delegations {
+            for validator in delegated_validators {
+                let delegations =
+                    self.read_delegations_post(&validator, pipeline_epoch)?;
+                if delegations > max_delegations {
+                    errors.push(validation::Error::DelegationCapExceeded(
+                        validator,
+                        delegations,
+                        max_delegations,
+                    ));
+                }
+            }
+        }
         Ok(if errors.is_empty() {
             true
         } else {
@@ -367,6 +517,17 @@ where
         value.map(|value| decode(value).unwrap())
     }
 
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<ValidatorCommissionRates> {
+        let value = self
+            .ctx
+            .read_pre(&validator_commission_rate_key(key))
+            .unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
     fn read_validator_total_deltas(
         &self,
         key: &Self::Address,
@@ -402,6 +563,11 @@ where
         value.map(|value| decode(value).unwrap())
     }
 
+    fn read_bond_rewards(&self, key: &BondId) -> Option<token::Amount> {
+        let value = self.ctx.read_pre(&bond_rewards_key(key)).unwrap();
+        value.map(|value| decode(value).unwrap())
+    }
+
     fn read_validator_set(&self) -> ValidatorSets {
         let value = self.ctx.read_pre(&validator_set_key()).unwrap().unwrap();
         decode(value).unwrap()
@@ -331,6 +331,21 @@ impl DB for MockDB {
         Ok(Some(merkle_tree_stores))
     }
 
+    fn prune_merkle_tree_stores(&mut self, before: BlockHeight) -> Result<()> {
+        self.0.borrow_mut().retain(|key, _| {
+            let segments: Vec<&str> =
+                key.split(KEY_SEGMENT_SEPARATOR).collect();
+            match (segments.first(), segments.get(1)) {
+                (Some(height), Some(&"tree")) => match height.parse::<u64>() {
+                    Ok(height) => height >= before.0,
+                    Err(_) => true,
+                },
+                _ => true,
+            }
+        });
+        Ok(())
+    }
+
     fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
         let key = Key::parse("subspace").map_err(Error::KeyError)?.join(key);
         Ok(self.0.borrow().get(&key.to_string()).cloned())
@@ -3,6 +3,7 @@
 mod merkle_tree;
 #[cfg(any(test, feature = "testing"))]
 pub mod mockdb;
+pub mod read_cache;
 pub mod types;
 pub mod write_log;
 
@@ -25,6 +26,7 @@ pub use crate::ledger::storage::merkle_tree::{
     MerkleTree, MerkleTreeStoresRead, MerkleTreeStoresWrite, Sha256Hasher,
     StorageHasher, StoreType,
 };
+use crate::ledger::storage::read_cache::ReadCache;
 use crate::types::address::{Address, EstablishedAddressGen, InternalAddress};
 use crate::types::chain::{ChainId, CHAIN_ID_LENGTH};
 #[cfg(feature = "ferveo-tpke")]
@@ -66,6 +68,8 @@ where
     /// Wrapper txs to be decrypted in the next block proposal
     #[cfg(feature = "ferveo-tpke")]
     pub tx_queue: TxQueue,
+    /// Cache of the values read from the DB
+    pub read_cache: ReadCache,
 }
 
 /// The block storage data
@@ -183,6 +187,9 @@ pub trait DB: std::fmt::Debug {
         height: BlockHeight,
     ) -> Result<Option<MerkleTreeStoresRead>>;
 
+    /// Delete the merkle tree stores of all the blocks below the given height
+    fn prune_merkle_tree_stores(&mut self, before: BlockHeight) -> Result<()>;
+
     /// Read the latest value for account subspace key from the DB
     fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>>;
 
@@ -294,6 +301,7 @@ where
             ),
             #[cfg(feature = "ferveo-tpke")]
             tx_queue: TxQueue::default(),
+            read_cache: ReadCache::default(),
         }
     }
 
@@ -327,6 +335,8 @@ where
             {
                 self.tx_queue = tx_queue;
             }
+            // The cached values may not match the loaded state
+            self.read_cache.clear();
             tracing::debug!("Loaded storage from DB");
         } else {
             tracing::info!("No state could be found");
@@ -362,9 +372,31 @@ where
         self.db.write_block(state)?;
         self.last_height = self.block.height;
         self.header = None;
+        let stats = self.read_cache.stats();
+        tracing::debug!(
+            "Storage read cache hits: {}, misses: {}, hit rate: {:?}",
+            stats.hits,
+            stats.misses,
+            stats.hit_rate()
+        );
         Ok(())
     }
 
+    /// Delete the merkle tree stores of the blocks from the epochs that are no
+    /// longer tracked in the predecessor epochs. The proofs for these blocks
+    /// cannot be requested, so their trees are unreachable. Returns the height
+    /// below which the trees have been deleted. This must not be called on a
+    /// node that runs in the archive mode.
+    pub fn prune_merkle_tree_stores(&mut self) -> Result<Option<BlockHeight>> {
+        match self.block.pred_epochs.first_block_height() {
+            Some(height) => {
+                self.db.prune_merkle_tree_stores(height)?;
+                Ok(Some(height))
+            }
+            None => Ok(None),
+        }
+    }
+
     /// Find the root hash of the merkle tree
     pub fn merkle_root(&self) -> MerkleRoot {
         self.block.tree.root()
@@ -384,8 +416,13 @@ where
             return Ok((None, gas));
         }
 
+        if let Some(v) = self.read_cache.get(key) {
+            let gas = key.len() + v.len();
+            return Ok((Some(v), gas as _));
+        }
         match self.db.read_subspace_val(key)? {
             Some(v) => {
+                self.read_cache.put(key, v.clone());
                 let gas = key.len() + v.len();
                 Ok((Some(v), gas as _))
             }
@@ -434,7 +471,9 @@ where
         let len = value.as_ref().len();
         let gas = key.len() + len;
         let size_diff =
-            self.db.write_subspace_val(self.last_height, key, value)?;
+            self.db
+                .write_subspace_val(self.last_height, key, value.clone())?;
+        self.read_cache.put(key, value.as_ref().to_vec());
         Ok((gas as _, size_diff))
     }
 
@@ -446,6 +485,7 @@ where
             self.block.tree.delete(key)?;
             deleted_bytes_len =
                 self.db.delete_subspace_val(self.last_height, key)?;
+            self.read_cache.remove(key);
         }
         let gas = key.len() + deleted_bytes_len as usize;
         Ok((gas as _, deleted_bytes_len))
@@ -655,7 +695,9 @@ where
 
     /// Execute write batch.
     fn exec_batch(&mut self, batch: D::WriteBatch) -> Result<()> {
-        self.db.exec_batch(batch)
+        let result = self.db.exec_batch(batch);
+        self.read_cache.finish_batch(result.is_ok());
+        result
     }
 
     /// Batch write the value with the given height and account subspace key to
@@ -669,8 +711,14 @@ where
     ) -> Result<i64> {
         let value = value.as_ref();
         self.block.tree.update(key, value)?;
-        self.db
-            .batch_write_subspace_val(batch, self.block.height, key, value)
+        let size_diff = self.db.batch_write_subspace_val(
+            batch,
+            self.block.height,
+            key,
+            value,
+        )?;
+        self.read_cache.stage_invalidation(key);
+        Ok(size_diff)
     }
 
     /// Batch delete the value with the given height and account subspace key
@@ -682,8 +730,11 @@ where
         key: &Key,
     ) -> Result<i64> {
         self.block.tree.delete(key)?;
-        self.db
-            .batch_delete_subspace_val(batch, self.block.height, key)
+        let deleted_bytes_len =
+            self.db
+                .batch_delete_subspace_val(batch, self.block.height, key)?;
+        self.read_cache.stage_invalidation(key);
+        Ok(deleted_bytes_len)
     }
 }
 
@@ -729,6 +780,7 @@ pub mod testing {
                 ),
                 #[cfg(feature = "ferveo-tpke")]
                 tx_queue: TxQueue::default(),
+                read_cache: ReadCache::default(),
             }
         }
     }
@@ -882,4 +934,61 @@ mod tests {
                 time_of_update + parameters.epoch_duration.min_duration);
         }
     }
+
+    /// Test that the values read through the cache are updated by writes and
+    /// deletes.
+    #[test]
+    fn test_read_cache_invalidation() {
+        let mut storage = TestStorage::default();
+        let key = Key::parse("key").unwrap();
+
+        storage.write(&key, vec![1]).unwrap();
+        assert_eq!(storage.read(&key).unwrap().0, Some(vec![1]));
+        assert_eq!(storage.read(&key).unwrap().0, Some(vec![1]));
+
+        storage.write(&key, vec![2]).unwrap();
+        assert_eq!(storage.read(&key).unwrap().0, Some(vec![2]));
+
+        // The cached value is only invalidated once the batch is executed
+        let mut batch = TestStorage::batch();
+        storage
+            .batch_write_subspace_val(&mut batch, &key, vec![3])
+            .unwrap();
+        assert_eq!(storage.read_cache.get(&key), Some(vec![2]));
+        storage.exec_batch(batch).unwrap();
+        assert_eq!(storage.read_cache.get(&key), None);
+        assert_eq!(storage.read(&key).unwrap().0, Some(vec![3]));
+
+        storage.delete(&key).unwrap();
+        assert_eq!(storage.read(&key).unwrap().0, None);
+
+        assert!(storage.read_cache.stats().hits > 0);
+    }
+
+    /// Test that the merkle trees of the blocks from the epochs that are no
+    /// longer tracked are deleted.
+    #[test]
+    fn test_prune_merkle_tree_stores() {
+        let mut storage = TestStorage::default();
+        for height in 1..=3 {
+            storage
+                .begin_block(BlockHash::default(), BlockHeight(height))
+                .unwrap();
+            storage.commit().unwrap();
+        }
+        // Start new epochs at heights 3 and 4 and forget about the epochs
+        // that ended before height 4
+        storage.block.pred_epochs.new_epoch(BlockHeight(3), 1);
+        storage.block.pred_epochs.new_epoch(BlockHeight(4), 1);
+
+        let pruned = storage.prune_merkle_tree_stores().unwrap();
+        assert_eq!(pruned, Some(BlockHeight(3)));
+        for height in 1..=2 {
+            let stores =
+                storage.db.read_merkle_tree_stores(BlockHeight(height));
+            assert!(stores.unwrap().is_none());
+        }
+        let stores = storage.db.read_merkle_tree_stores(BlockHeight(3));
+        assert!(stores.unwrap().is_some());
+    }
 }
@@ -0,0 +1,213 @@
+//! In-memory LRU cache of the values read from the storage DB. The cache is
+//! kept in sync with the storage's writes and deletes, so a cached value is
+//! always the same as the one that would be read from the DB. The keys written
+//! in a DB write batch are only invalidated once the batch is executed.
+
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use clru::{CLruCache, CLruCacheConfig, WeightScale};
+
+use crate::types::storage::Key;
+
+/// The default maximum size of the cached keys and values in bytes
+pub const DEFAULT_READ_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Cache of the values read from the storage DB. Thread-safe.
+#[derive(Debug)]
+pub struct ReadCache {
+    /// LRU cache of the values
+    values: Mutex<ValuesCache>,
+    /// The keys written in a DB write batch that hasn't been executed yet
+    staged: Mutex<Vec<Key>>,
+    /// The number of reads found in the cache
+    hits: AtomicU64,
+    /// The number of reads not found in the cache
+    misses: AtomicU64,
+}
+
+/// LRU cache of the values
+type ValuesCache = CLruCache<Key, Vec<u8>, RandomState, ValueScale>;
+
+/// The number of cache hits and misses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadCacheStats {
+    /// The number of reads found in the cache
+    pub hits: u64,
+    /// The number of reads not found in the cache
+    pub misses: u64,
+}
+
+impl ReadCacheStats {
+    /// The ratio of the reads found in the cache, if there were any reads
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            None
+        } else {
+            Some(self.hits as f64 / total as f64)
+        }
+    }
+}
+
+/// Configures the cache scale of values that limits the maximum capacity
+/// of the cache (CLruCache::len + CLruCache::weight <= CLruCache::capacity).
+#[derive(Debug)]
+struct ValueScale;
+
+impl WeightScale<Key, Vec<u8>> for ValueScale {
+    fn weight(&self, key: &Key, value: &Vec<u8>) -> usize {
+        // We only want to limit the max memory size, not the number of
+        // elements, so we use the size of the key and value as its scale
+        // and subtract 1 from it to negate the increment of the cache length.
+        cmp::max(1, key.len() + value.len()) - 1
+    }
+}
+
+impl ReadCache {
+    /// Create a cache with a given size limit of the cached keys and values.
+    ///
+    /// # Panics
+    /// The `max_bytes` must be non-zero.
+    pub fn new(max_bytes: usize) -> Self {
+        let values = CLruCache::with_config(
+            CLruCacheConfig::new(NonZeroUsize::new(max_bytes).unwrap())
+                .with_scale(ValueScale),
+        );
+        Self {
+            values: Mutex::new(values),
+            staged: Mutex::new(Vec::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Get a cached value and count the cache hit or miss
+    pub fn get(&self, key: &Key) -> Option<Vec<u8>> {
+        let value = self.values.lock().unwrap().get(key).cloned();
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    /// Cache the value of a key. A value that is larger than the cache's
+    /// capacity is not cached.
+    pub fn put(&self, key: &Key, value: Vec<u8>) {
+        let mut values = self.values.lock().unwrap();
+        // Remove the old value first, in case the new one cannot be cached
+        values.pop(key);
+        let _ = values.put_with_weight(key.clone(), value);
+    }
+
+    /// Remove a key from the cache
+    pub fn remove(&self, key: &Key) {
+        self.values.lock().unwrap().pop(key);
+    }
+
+    /// Remove all the cached values
+    pub fn clear(&self) {
+        self.values.lock().unwrap().clear();
+    }
+
+    /// Stage a key written in a DB write batch to be removed from the cache
+    /// once the batch is executed. Until then, the cache keeps the value that
+    /// is still in the DB.
+    pub fn stage_invalidation(&self, key: &Key) {
+        self.staged.lock().unwrap().push(key.clone());
+    }
+
+    /// Remove the staged keys from the cache after their write batch has been
+    /// executed, or only forget them if it failed and the DB is unchanged
+    pub fn finish_batch(&self, executed: bool) {
+        let staged = std::mem::take(&mut *self.staged.lock().unwrap());
+        if executed {
+            let mut values = self.values.lock().unwrap();
+            for key in staged {
+                values.pop(&key);
+            }
+        }
+    }
+
+    /// Get the number of cache hits and misses since the cache was created
+    pub fn stats(&self) -> ReadCacheStats {
+        ReadCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for ReadCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_READ_CACHE_BYTES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_cache_hits_and_misses() {
+        let cache = ReadCache::default();
+        let key = Key::parse("key").unwrap();
+
+        assert_eq!(cache.get(&key), None);
+        cache.put(&key, vec![1]);
+        assert_eq!(cache.get(&key), Some(vec![1]));
+        cache.put(&key, vec![2]);
+        assert_eq!(cache.get(&key), Some(vec![2]));
+        cache.remove(&key);
+        assert_eq!(cache.get(&key), None);
+
+        let stats = cache.stats();
+        assert_eq!(stats, ReadCacheStats { hits: 2, misses: 2 });
+        assert_eq!(stats.hit_rate(), Some(0.5));
+    }
+
+    #[test]
+    fn test_read_cache_size_limit() {
+        let key = Key::parse("key").unwrap();
+        let cache = ReadCache::new(key.len() + 10);
+
+        // A value that fits is cached
+        cache.put(&key, vec![0; 10]);
+        assert_eq!(cache.get(&key), Some(vec![0; 10]));
+
+        // A value that is too large is not cached and the old value is
+        // removed
+        cache.put(&key, vec![0; 11]);
+        assert_eq!(cache.get(&key), None);
+
+        // The least recently used value is evicted
+        let other_key = Key::parse("other").unwrap();
+        cache.put(&key, vec![0; 5]);
+        cache.put(&other_key, vec![0; 5]);
+        assert_eq!(cache.get(&key), None);
+        assert_eq!(cache.get(&other_key), Some(vec![0; 5]));
+    }
+
+    #[test]
+    fn test_read_cache_staged_invalidation() {
+        let cache = ReadCache::default();
+        let key = Key::parse("key").unwrap();
+        cache.put(&key, vec![1]);
+
+        // The value is kept until the batch is executed
+        cache.stage_invalidation(&key);
+        assert_eq!(cache.get(&key), Some(vec![1]));
+        cache.finish_batch(false);
+        assert_eq!(cache.get(&key), Some(vec![1]));
+
+        cache.stage_invalidation(&key);
+        cache.finish_batch(true);
+        assert_eq!(cache.get(&key), None);
+    }
+}
@@ -4,7 +4,8 @@ pub mod generated;
 mod types;
 
 pub use types::{
-    Dkg, Error, Intent, IntentGossipMessage, IntentId, Signed, SignedTxData, Tx,
+    Dkg, Error, Intent, IntentCancel, IntentGossipMessage, IntentHop, IntentId,
+    Signed, SignedTxData, Tx,
 };
 
 #[cfg(test)]
@@ -20,6 +21,7 @@ mod tests {
             code: "wasm code".as_bytes().to_owned(),
             data: Some("arbitrary data".as_bytes().to_owned()),
             timestamp: Some(std::time::SystemTime::now().into()),
+            expiration: None,
         };
         let mut tx_bytes = vec![];
         tx.encode(&mut tx_bytes).unwrap();
@@ -29,6 +29,8 @@ pub enum Error {
     NoTimestampError,
     #[error("Timestamp is invalid: {0}")]
     InvalidTimestamp(prost_types::TimestampOutOfSystemRangeError),
+    #[error("Error decoding an intent cancellation: {0}")]
+    IntentCancelDecodingError(std::io::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -127,6 +129,8 @@ pub struct Tx {
     pub code: Vec<u8>,
     pub data: Option<Vec<u8>>,
     pub timestamp: DateTimeUtc,
+    /// The time after which the tx is no longer valid, if any
+    pub expiration: Option<DateTimeUtc>,
 }
 
 impl TryFrom<&[u8]> for Tx {
@@ -138,10 +142,15 @@ impl TryFrom<&[u8]> for Tx {
             Some(t) => t.try_into().map_err(Error::InvalidTimestamp)?,
             None => return Err(Error::NoTimestampError),
         };
+        let expiration = tx
+            .expiration
+            .map(|t| t.try_into().map_err(Error::InvalidTimestamp))
+            .transpose()?;
         Ok(Tx {
             code: tx.code,
             data: tx.data,
             timestamp,
+            expiration,
         })
     }
 }
@@ -149,10 +158,12 @@ impl TryFrom<&[u8]> for Tx {
 impl From<Tx> for types::Tx {
     fn from(tx: Tx) -> Self {
         let timestamp = Some(tx.timestamp.into());
+        let expiration = tx.expiration.map(Into::into);
         types::Tx {
             code: tx.code,
             data: tx.data,
             timestamp,
+            expiration,
         }
     }
 }
@@ -163,9 +174,22 @@ impl Tx {
             code,
             data,
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
     }
 
+    /// Set the time after which the tx is no longer valid. This must be set
+    /// before the tx is signed, as the signature covers the expiration.
+    pub fn with_expiration(mut self, expiration: Option<DateTimeUtc>) -> Self {
+        self.expiration = expiration;
+        self
+    }
+
+    /// Check if the tx is expired at the given time
+    pub fn is_expired(&self, time: DateTimeUtc) -> bool {
+        matches!(self.expiration, Some(expiration) if time > expiration)
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = vec![];
         let tx: types::Tx = self.clone().into();
@@ -184,8 +208,17 @@ impl Tx {
 
     /// Sign a transaction using [`SignedTxData`].
     pub fn sign(self, keypair: &common::SecretKey) -> Self {
+        self.sign_with(|to_sign| common::SigScheme::sign(keypair, to_sign))
+    }
+
+    /// Sign a transaction using [`SignedTxData`] with the given signing
+    /// function, e.g. one that signs with a key held by another process.
+    pub fn sign_with(
+        self,
+        sign: impl FnOnce(&[u8]) -> common::Signature,
+    ) -> Self {
         let to_sign = self.hash();
-        let sig = common::SigScheme::sign(keypair, &to_sign);
+        let sig = sign(&to_sign);
         let signed = SignedTxData {
             data: self.data,
             sig,
@@ -196,6 +229,7 @@ impl Tx {
             code: self.code,
             data: Some(signed),
             timestamp: self.timestamp,
+            expiration: self.expiration,
         }
     }
 
@@ -215,15 +249,22 @@ impl Tx {
             code: self.code.clone(),
             data,
             timestamp: self.timestamp,
+            expiration: self.expiration,
         };
         let signed_data = tx.hash();
         common::SigScheme::verify_signature_raw(pk, &signed_data, sig)
     }
 }
 
+/// A message of the intent gossip network
 #[derive(Clone, Debug, PartialEq)]
-pub struct IntentGossipMessage {
-    pub intent: Intent,
+pub enum IntentGossipMessage {
+    /// An intent
+    Intent(Intent),
+    /// A hop of an intent, announced by a node in the trace mode
+    Hop(IntentHop),
+    /// A cancellation of an intent
+    Cancel(IntentCancel),
 }
 
 impl TryFrom<&[u8]> for IntentGossipMessage {
@@ -232,11 +273,15 @@ impl TryFrom<&[u8]> for IntentGossipMessage {
     fn try_from(intent_bytes: &[u8]) -> Result<Self> {
         let intent = types::IntentGossipMessage::decode(intent_bytes)
             .map_err(Error::IntentDecodingError)?;
-        match &intent.msg {
+        match intent.msg {
             Some(types::intent_gossip_message::Msg::Intent(intent)) => {
-                Ok(IntentGossipMessage {
-                    intent: intent.clone().try_into()?,
-                })
+                Ok(IntentGossipMessage::Intent(intent.try_into()?))
+            }
+            Some(types::intent_gossip_message::Msg::Hop(hop)) => {
+                Ok(IntentGossipMessage::Hop(hop.try_into()?))
+            }
+            Some(types::intent_gossip_message::Msg::Cancel(cancel)) => {
+                Ok(IntentGossipMessage::Cancel(cancel.try_into()?))
             }
             None => Err(Error::NoIntentError),
         }
@@ -245,17 +290,24 @@ impl TryFrom<&[u8]> for IntentGossipMessage {
 
 impl From<IntentGossipMessage> for types::IntentGossipMessage {
     fn from(message: IntentGossipMessage) -> Self {
-        types::IntentGossipMessage {
-            msg: Some(types::intent_gossip_message::Msg::Intent(
-                message.intent.into(),
-            )),
-        }
+        let msg = match message {
+            IntentGossipMessage::Intent(intent) => {
+                types::intent_gossip_message::Msg::Intent(intent.into())
+            }
+            IntentGossipMessage::Hop(hop) => {
+                types::intent_gossip_message::Msg::Hop(hop.into())
+            }
+            IntentGossipMessage::Cancel(cancel) => {
+                types::intent_gossip_message::Msg::Cancel(cancel.into())
+            }
+        };
+        types::IntentGossipMessage { msg: Some(msg) }
     }
 }
 
 impl IntentGossipMessage {
     pub fn new(intent: Intent) -> Self {
-        IntentGossipMessage { intent }
+        IntentGossipMessage::Intent(intent)
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -268,6 +320,124 @@ impl IntentGossipMessage {
     }
 }
 
+/// A hop of an intent through the gossip network, i.e. the receipt of the
+/// intent by a node
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntentHop {
+    /// The SHA-256 hash of the intent, see [`Intent::sha256`]
+    pub intent_hash: Vec<u8>,
+    /// The peer ID of the node that received the intent
+    pub node_id: String,
+    /// The peer ID of the node from which the intent was received, if it was
+    /// not submitted to the node via RPC
+    pub from_node_id: Option<String>,
+    /// The time at which the node received the intent
+    pub received_at: DateTimeUtc,
+}
+
+impl TryFrom<types::IntentHop> for IntentHop {
+    type Error = Error;
+
+    fn try_from(hop: types::IntentHop) -> Result<Self> {
+        let received_at = match hop.received_at {
+            Some(t) => t.try_into().map_err(Error::InvalidTimestamp)?,
+            None => return Err(Error::NoTimestampError),
+        };
+        Ok(IntentHop {
+            intent_hash: hop.intent_hash,
+            node_id: hop.node_id,
+            from_node_id: Some(hop.from_node_id)
+                .filter(|node_id| !node_id.is_empty()),
+            received_at,
+        })
+    }
+}
+
+impl From<IntentHop> for types::IntentHop {
+    fn from(hop: IntentHop) -> Self {
+        types::IntentHop {
+            intent_hash: hop.intent_hash,
+            node_id: hop.node_id,
+            from_node_id: hop.from_node_id.unwrap_or_default(),
+            received_at: Some(hop.received_at.into()),
+        }
+    }
+}
+
+/// A cancellation of an intent, signed with the key that signed the intent.
+/// The nodes that have the intent drop it from their mempool.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntentCancel {
+    /// The SHA-256 hash of the intent, see [`Intent::sha256`]
+    pub intent_hash: Vec<u8>,
+    /// The public key of the intent's signer
+    pub public_key: common::PublicKey,
+    /// The signature of the intent hash
+    pub signature: common::Signature,
+}
+
+impl IntentCancel {
+    /// Sign a cancellation of the intent with the given hash
+    pub fn new(intent_hash: Vec<u8>, keypair: &common::SecretKey) -> Self {
+        let signature =
+            common::SigScheme::sign(keypair, &Self::signed_bytes(&intent_hash));
+        Self {
+            intent_hash,
+            public_key: keypair.ref_to(),
+            signature,
+        }
+    }
+
+    /// The bytes signed by the intent's signer. They are prefixed, so that
+    /// the signature can't be mistaken for the signature of other data.
+    fn signed_bytes(intent_hash: &[u8]) -> Vec<u8> {
+        ("anoma/intent/cancel", intent_hash)
+            .try_to_vec()
+            .expect("Encoding an intent cancellation shouldn't fail")
+    }
+
+    /// Verify the signature of the cancellation by its public key
+    pub fn verify(&self) -> std::result::Result<(), VerifySigError> {
+        common::SigScheme::verify_signature_raw(
+            &self.public_key,
+            &Self::signed_bytes(&self.intent_hash),
+            &self.signature,
+        )
+    }
+}
+
+impl TryFrom<types::IntentCancel> for IntentCancel {
+    type Error = Error;
+
+    fn try_from(cancel: types::IntentCancel) -> Result<Self> {
+        let public_key = common::PublicKey::try_from_slice(&cancel.public_key)
+            .map_err(Error::IntentCancelDecodingError)?;
+        let signature = common::Signature::try_from_slice(&cancel.signature)
+            .map_err(Error::IntentCancelDecodingError)?;
+        Ok(IntentCancel {
+            intent_hash: cancel.intent_hash,
+            public_key,
+            signature,
+        })
+    }
+}
+
+impl From<IntentCancel> for types::IntentCancel {
+    fn from(cancel: IntentCancel) -> Self {
+        types::IntentCancel {
+            intent_hash: cancel.intent_hash,
+            public_key: cancel
+                .public_key
+                .try_to_vec()
+                .expect("Encoding a public key shouldn't fail"),
+            signature: cancel
+                .signature
+                .try_to_vec()
+                .expect("Encoding a signature shouldn't fail"),
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct DkgGossipMessage {
@@ -361,6 +531,20 @@ impl Intent {
         self.hash(&mut hasher);
         IntentId::from(hasher.finish().to_string())
     }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        let intent: types::Intent = self.clone().into();
+        intent
+            .encode(&mut bytes)
+            .expect("encoding an intent failed");
+        bytes
+    }
+
+    /// The SHA-256 hash of the encoded intent
+    pub fn sha256(&self) -> [u8; 32] {
+        hash_tx(&self.to_bytes()).0
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -422,6 +606,7 @@ mod tests {
             code,
             data: Some(data),
             timestamp: None,
+            expiration: None,
         };
         let mut bytes = vec![];
         types_tx.encode(&mut bytes).expect("encoding failed");
@@ -431,16 +616,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tx_expiration() {
+        let code = "wasm code".as_bytes().to_owned();
+        let tx = Tx::new(code, None);
+        assert!(!tx.is_expired(DateTimeUtc::now()));
+
+        let expiration = DateTimeUtc::now();
+        let tx = tx.with_expiration(Some(expiration));
+        let bytes = tx.to_bytes();
+        let tx_from_bytes =
+            Tx::try_from(bytes.as_ref()).expect("decoding failed");
+        assert_eq!(tx_from_bytes, tx);
+        assert!(!tx.is_expired(expiration));
+        let later = expiration + crate::types::time::DurationSecs(1);
+        assert!(tx.is_expired(later));
+    }
+
     #[test]
     fn test_intent_gossip_message() {
         let data = "arbitrary data".as_bytes().to_owned();
         let intent = Intent::new(data);
-        let message = IntentGossipMessage::new(intent);
+        let message = IntentGossipMessage::new(intent.clone());
 
         let bytes = message.to_bytes();
         let message_from_bytes = IntentGossipMessage::try_from(bytes.as_ref())
             .expect("decoding failed");
         assert_eq!(message_from_bytes, message);
+
+        for from_node_id in [None, Some("from node".to_owned())] {
+            let message = IntentGossipMessage::Hop(IntentHop {
+                intent_hash: intent.sha256().to_vec(),
+                node_id: "node".to_owned(),
+                from_node_id,
+                received_at: DateTimeUtc::now(),
+            });
+            let bytes = message.to_bytes();
+            let message_from_bytes =
+                IntentGossipMessage::try_from(bytes.as_ref())
+                    .expect("decoding failed");
+            assert_eq!(message_from_bytes, message);
+        }
+    }
+
+    #[test]
+    fn test_intent_cancel() {
+        let intent = Intent::new(b"arbitrary data".to_vec());
+        let keypair = crate::types::key::testing::keypair_1();
+        let cancel = IntentCancel::new(intent.sha256().to_vec(), &keypair);
+        cancel.verify().expect("the cancellation should be valid");
+
+        let message = IntentGossipMessage::Cancel(cancel.clone());
+        let bytes = message.to_bytes();
+        let message_from_bytes = IntentGossipMessage::try_from(bytes.as_ref())
+            .expect("decoding failed");
+        assert_eq!(message_from_bytes, message);
+
+        // A cancellation of another intent doesn't verify with the signature
+        let tampered = IntentCancel {
+            intent_hash: vec![0; 32],
+            ..cancel
+        };
+        assert!(tampered.verify().is_err());
     }
 
     #[test]
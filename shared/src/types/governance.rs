@@ -31,6 +31,9 @@ pub enum ProposalVote {
     Yay,
     /// No
     Nay,
+    /// Neither yes nor no. The voter's stake isn't counted as a yay, so it
+    /// has the same effect on the tally as a nay.
+    Abstain,
 }
 
 impl ProposalVote {
@@ -38,7 +41,7 @@ impl ProposalVote {
     pub fn is_yay(&self) -> bool {
         match self {
             ProposalVote::Yay => true,
-            ProposalVote::Nay => false,
+            ProposalVote::Nay | ProposalVote::Abstain => false,
         }
     }
 }
@@ -48,6 +51,7 @@ impl Display for ProposalVote {
         match self {
             ProposalVote::Yay => write!(f, "yay"),
             ProposalVote::Nay => write!(f, "nay"),
+            ProposalVote::Abstain => write!(f, "abstain"),
         }
     }
 }
@@ -55,7 +59,7 @@ impl Display for ProposalVote {
 #[allow(missing_docs)]
 #[derive(Debug, Error)]
 pub enum ProposalVoteParseError {
-    #[error("Invalid vote. Vote shall be yay or nay.")]
+    #[error("Invalid vote. Vote shall be yay, nay or abstain.")]
     InvalidVote,
 }
 
@@ -67,6 +71,8 @@ impl FromStr for ProposalVote {
             Ok(ProposalVote::Yay)
         } else if s.eq("nay") {
             Ok(ProposalVote::Nay)
+        } else if s.eq("abstain") {
+            Ok(ProposalVote::Abstain)
         } else {
             Err(ProposalVoteParseError::InvalidVote)
         }
@@ -12,7 +12,10 @@ use thiserror::Error;
 
 use crate::proto::Signed;
 use crate::types::address::Address;
+use crate::types::key::common;
+use crate::types::key::ring::{self, RingSignature};
 use crate::types::storage::{DbKeySeg, Key, KeySeg};
+use crate::types::time::DateTimeUtc;
 use crate::types::token;
 
 /// A simple intent for fungible token trade
@@ -29,6 +32,11 @@ use crate::types::token;
 pub struct FungibleTokenIntent {
     /// List of exchange definitions
     pub exchange: HashSet<Signed<Exchange>>,
+    /// A number chosen by the signer to tell apart the intents with the same
+    /// exchanges, e.g. the time of signing. The intent gossip nodes and the
+    /// matchmakers reject the intents that they have already seen, so that a
+    /// signed intent cannot be replayed.
+    pub nonce: u64,
 }
 
 #[derive(
@@ -61,6 +69,189 @@ pub struct Exchange {
     /// The vp code
     #[derivative(Debug = "ignore")]
     pub vp: Option<Vec<u8>>,
+    /// A token of an NFT exchanged instead of an amount of a fungible token
+    /// on one side, if any. The NFT's address is then the `token_sell` or the
+    /// `token_buy` of that side, whose amount is ignored, as is the
+    /// `rate_min`.
+    pub nft: Option<ExchangeNft>,
+    /// The time after which the exchange expires, if any. Expired exchanges
+    /// are neither gossiped nor matched and cannot be settled.
+    pub expires: Option<DateTimeUtc>,
+    /// Whether the exchange can be filled in parts by multiple matches. Each
+    /// part must buy at least the part of the `min_buy` proportional to the
+    /// part of the `max_sell` that it sells, and the parts can sell at most
+    /// the `max_sell` in total. Exchanges of tokens of NFTs are always filled
+    /// at once.
+    pub partial: bool,
+    /// The maximum fee paid to the matchmaker in the `token_sell`, on top of
+    /// the sold amount. Each part of an exchange filled in parts can pay at
+    /// most the part of the `max_fee` proportional to the part of the
+    /// `max_sell` that it sells.
+    pub max_fee: token::Amount,
+}
+
+/// Whether an [`Exchange`] sells or buys a token of an NFT
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Hash,
+    PartialOrd,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NftSide {
+    /// The token is sold for at least the `min_buy` of the `token_buy`
+    Sell,
+    /// The token is bought for at most the `max_sell` of the `token_sell`,
+    /// including the NFT's royalty, if any
+    Buy,
+}
+
+/// A token of an NFT on one side of an [`Exchange`]
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Hash,
+    PartialOrd,
+)]
+pub struct ExchangeNft {
+    /// The side of the token
+    pub side: NftSide,
+    /// The ID of the token
+    pub token_id: String,
+}
+
+/// The terms of an exchange of a [`RingIntent`], i.e. an [`Exchange`] without
+/// its source address. The exchanges of ring-signed intents are always filled
+/// at once.
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Hash,
+    Derivative,
+)]
+pub struct RingExchange {
+    /// The token to be sold
+    pub token_sell: Address,
+    /// The minimum rate
+    pub rate_min: DecimalWrapper,
+    /// The maximum amount of token to be sold
+    pub max_sell: token::Amount,
+    /// The token to be bought
+    pub token_buy: Address,
+    /// The amount of token to be bought
+    pub min_buy: token::Amount,
+    /// The vp code
+    #[derivative(Debug = "ignore")]
+    pub vp: Option<Vec<u8>>,
+    /// A token of an NFT on one side, if any
+    pub nft: Option<ExchangeNft>,
+    /// The time after which the exchange expires, if any
+    pub expires: Option<DateTimeUtc>,
+    /// The maximum fee paid to the matchmaker in the `token_sell`
+    pub max_fee: token::Amount,
+}
+
+/// A fungible token intent whose source is one of the addresses in a ring.
+/// The order book shows the terms of its exchanges, but not which of the
+/// addresses is their source until it is settled.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Eq,
+)]
+pub struct RingIntent {
+    /// The addresses in the ring, one of which is the source
+    pub ring: Vec<Address>,
+    /// List of exchange definitions
+    pub exchange: Vec<RingExchange>,
+    /// A number chosen by the signer to tell apart the intents with the same
+    /// exchanges, see [`FungibleTokenIntent::nonce`]
+    pub nonce: u64,
+}
+
+/// A [`RingIntent`] signed with a ring signature over the public keys of the
+/// addresses in its ring, in the same order
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Eq,
+)]
+pub struct RingSignedIntent {
+    /// The intent
+    pub data: RingIntent,
+    /// The ring signature of the intent
+    pub sig: RingSignature,
+}
+
+/// A [`RingSignedIntent`] with its source revealed to settle it. It must be
+/// signed by the source's key, so that the source's VP can verify that it is
+/// the real signer.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Eq,
+)]
+pub struct RingSettlement {
+    /// The source of the intent
+    pub source: Address,
+    /// The ring-signed intent
+    pub intent: RingSignedIntent,
+}
+
+/// A [`RingSignedIntent`] whose source has revealed itself to have it matched
+/// and settled, see [`RingSignedIntent::reveal`]. The matchmakers match its
+/// exchanges like the exchanges of a [`FungibleTokenIntent`] and settle them
+/// with its [`RingSettlement`].
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Eq,
+)]
+pub struct RevealedRingIntent {
+    /// The settlement signed by the source's key
+    pub settlement: Signed<RingSettlement>,
+    /// The exchanges of the intent with the revealed source, signed by the
+    /// source's key
+    pub exchange: HashSet<Signed<Exchange>>,
 }
 
 /// These are transfers crafted from matched [`Exchange`]s created by a
@@ -77,6 +268,8 @@ pub struct Exchange {
 pub struct MatchedExchanges {
     /// Transfers crafted from the matched intents
     pub transfers: HashSet<token::Transfer>,
+    /// Transfers of NFT tokens crafted from the matched intents
+    pub nft_transfers: HashSet<NftTransfer>,
     // TODO benchmark between an map or a set, see which is less costly
     /// The exchanges that were matched
     pub exchanges: HashMap<Address, Signed<Exchange>>,
@@ -85,6 +278,56 @@ pub struct MatchedExchanges {
     // `exchanges` hashmap are already contained in the FungibleTokenIntents
     // belows
     pub intents: HashMap<Address, Signed<FungibleTokenIntent>>,
+    /// The revealed ring-signed intents, instead of the `intents` of their
+    /// sources
+    pub ring_intents: HashMap<Address, Signed<RingSettlement>>,
+    /// The fees paid to the matchmaker, i.e. the source of the
+    /// [`IntentTransfers`], by the sources of the exchanges in the tokens
+    /// that they sell
+    pub fees: HashMap<Address, token::Amount>,
+}
+
+/// The fee that a matchmaker charges the sources of the exchanges that it
+/// matches, in the tokens that they sell. It's capped by the `max_fee` of
+/// every exchange.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MatchmakerFee {
+    /// A flat amount for every matched exchange
+    Flat {
+        /// The amount of the fee
+        amount: token::Amount,
+    },
+    /// Basis points of the amount sold by every matched exchange
+    Bps {
+        /// The number of basis points, i.e. hundredths of a percent
+        bps: u64,
+    },
+}
+
+/// A transfer of a token of an NFT crafted from matched [`Exchange`]s
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Hash,
+    PartialOrd,
+    Ord,
+)]
+pub struct NftTransfer {
+    /// The address of the NFT
+    pub nft: Address,
+    /// The ID of the token
+    pub token_id: String,
+    /// The current owner of the token
+    pub source: Address,
+    /// The new owner of the token
+    pub target: Address,
 }
 
 /// These are transfers crafted from matched [`Exchange`]s with a source address
@@ -125,11 +368,24 @@ impl From<Decimal> for DecimalWrapper {
     }
 }
 
+impl From<&DecimalWrapper> for f64 {
+    /// Warning: `f64` loses precision and it should not be used when exact
+    /// values are required.
+    fn from(decimal: &DecimalWrapper) -> Self {
+        decimal
+            .0
+            .to_f64()
+            .expect("A decimal should be convertible to f64")
+    }
+}
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Error parsing as decimal: {0}.")]
     DecimalParseError(String),
+    #[error("Error parsing the matchmaker fee {0}: {1}")]
+    FeeParseError(String, String),
 }
 
 impl TryFrom<token::Amount> for DecimalWrapper {
@@ -204,13 +460,305 @@ impl MatchedExchanges {
     pub fn empty() -> Self {
         Self {
             transfers: HashSet::new(),
+            nft_transfers: HashSet::new(),
             exchanges: HashMap::new(),
             intents: HashMap::new(),
+            ring_intents: HashMap::new(),
+            fees: HashMap::new(),
+        }
+    }
+
+    /// The amount of the `token_sell` of an exchange transferred from its
+    /// source
+    pub fn sold_by(&self, exchange: &Exchange) -> token::Amount {
+        self.transfers
+            .iter()
+            .filter(|transfer| {
+                transfer.source == exchange.addr
+                    && transfer.token == exchange.token_sell
+            })
+            .fold(token::Amount::default(), |sold, transfer| {
+                sold + transfer.amount
+            })
+    }
+
+    /// Add the fees charged by a matchmaker to the sources of the matched
+    /// exchanges of fungible tokens, capped by their `max_fee`
+    pub fn add_fees(&mut self, fee: &MatchmakerFee) {
+        let fees: Vec<(Address, token::Amount)> = self
+            .exchanges
+            .values()
+            .filter(|exchange| exchange.data.nft.is_none())
+            .filter_map(|exchange| {
+                let exchange = &exchange.data;
+                let sold = self.sold_by(exchange);
+                let fee = fee.fee_for(sold).min(exchange.max_fee_for(sold));
+                (fee != token::Amount::default())
+                    .then(|| (exchange.addr.clone(), fee))
+            })
+            .collect();
+        self.fees.extend(fees);
+    }
+}
+
+impl MatchmakerFee {
+    /// The fee for an exchange that sells the given amount, rounded down
+    pub fn fee_for(&self, sold: token::Amount) -> token::Amount {
+        match self {
+            Self::Flat { amount } => *amount,
+            Self::Bps { bps } => {
+                let fee = u64::from(sold) as u128 * *bps as u128 / 10_000;
+                token::Amount::from(u64::try_from(fee).unwrap_or(u64::MAX))
+            }
+        }
+    }
+}
+
+impl FromStr for MatchmakerFee {
+    type Err = Error;
+
+    /// Parse a fee in basis points with the `bps` suffix, e.g. `30bps`, or a
+    /// flat amount otherwise
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_error = |err: String| Error::FeeParseError(s.to_owned(), err);
+        match s.strip_suffix("bps") {
+            Some(bps) => u64::from_str(bps.trim())
+                .map(|bps| Self::Bps { bps })
+                .map_err(|err| parse_error(err.to_string())),
+            None => token::Amount::from_str(s)
+                .map(|amount| Self::Flat { amount })
+                .map_err(|err| parse_error(err.to_string())),
         }
     }
 }
 
+impl std::fmt::Display for MatchmakerFee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Flat { amount } => write!(f, "{}", amount),
+            Self::Bps { bps } => write!(f, "{}bps", bps),
+        }
+    }
+}
+
+impl From<Exchange> for RingExchange {
+    fn from(exchange: Exchange) -> Self {
+        Self {
+            token_sell: exchange.token_sell,
+            rate_min: exchange.rate_min,
+            max_sell: exchange.max_sell,
+            token_buy: exchange.token_buy,
+            min_buy: exchange.min_buy,
+            vp: exchange.vp,
+            nft: exchange.nft,
+            expires: exchange.expires,
+            max_fee: exchange.max_fee,
+        }
+    }
+}
+
+impl FungibleTokenIntent {
+    /// The time after which all the exchanges of the intent are expired, if
+    /// they all expire
+    pub fn expires(&self) -> Option<DateTimeUtc> {
+        latest_expiry(
+            self.exchange.iter().map(|exchange| exchange.data.expires),
+        )
+    }
+}
+
+impl RingIntent {
+    /// The time after which all the exchanges of the intent are expired, if
+    /// they all expire
+    pub fn expires(&self) -> Option<DateTimeUtc> {
+        latest_expiry(self.exchange.iter().map(|exchange| exchange.expires))
+    }
+}
+
+/// The latest of the expiry times, if none of them is `None`
+fn latest_expiry(
+    expiry_times: impl Iterator<Item = Option<DateTimeUtc>>,
+) -> Option<DateTimeUtc> {
+    expiry_times.collect::<Option<Vec<_>>>()?.into_iter().max()
+}
+
+impl Exchange {
+    /// The NFT and the ID of its token sold by the exchange, if any
+    pub fn nft_sold(&self) -> Option<(&Address, &str)> {
+        match &self.nft {
+            Some(ExchangeNft {
+                side: NftSide::Sell,
+                token_id,
+            }) => Some((&self.token_sell, token_id)),
+            _ => None,
+        }
+    }
+
+    /// The NFT and the ID of its token bought by the exchange, if any
+    pub fn nft_bought(&self) -> Option<(&Address, &str)> {
+        match &self.nft {
+            Some(ExchangeNft {
+                side: NftSide::Buy,
+                token_id,
+            }) => Some((&self.token_buy, token_id)),
+            _ => None,
+        }
+    }
+
+    /// Check if the exchange is expired at the given time
+    pub fn is_expired(&self, time: DateTimeUtc) -> bool {
+        matches!(self.expires, Some(expires) if time > expires)
+    }
+
+    /// Check if the exchange can be filled in parts
+    pub fn fills_in_parts(&self) -> bool {
+        self.partial && self.nft.is_none()
+    }
+
+    /// The minimum amount of the `token_buy` to be bought for the given part
+    /// of the `max_sell`, rounded up
+    pub fn min_buy_for(&self, sell: token::Amount) -> token::Amount {
+        let max_sell = u64::from(self.max_sell) as u128;
+        if max_sell == 0 {
+            return self.min_buy;
+        }
+        let min_buy = u64::from(self.min_buy) as u128 * u64::from(sell) as u128;
+        let min_buy = (min_buy + max_sell - 1) / max_sell;
+        token::Amount::from(u64::try_from(min_buy).unwrap_or(u64::MAX))
+    }
+
+    /// The maximum fee paid to the matchmaker for the given sold amount. For
+    /// an exchange filled in parts, it's the part of the `max_fee`
+    /// proportional to the part of the `max_sell` sold, rounded down, so that
+    /// the parts pay at most the `max_fee` in total.
+    pub fn max_fee_for(&self, sell: token::Amount) -> token::Amount {
+        let max_sell = u64::from(self.max_sell) as u128;
+        if !self.fills_in_parts() || max_sell == 0 {
+            return self.max_fee;
+        }
+        let max_fee = u64::from(self.max_fee) as u128 * u64::from(sell) as u128;
+        let max_fee = (max_fee / max_sell).min(u64::from(self.max_fee) as u128);
+        token::Amount::from(max_fee as u64)
+    }
+}
+
+impl RingExchange {
+    /// The exchange with the given source
+    pub fn with_source(&self, addr: Address) -> Exchange {
+        Exchange {
+            addr,
+            token_sell: self.token_sell.clone(),
+            rate_min: self.rate_min.clone(),
+            max_sell: self.max_sell,
+            token_buy: self.token_buy.clone(),
+            min_buy: self.min_buy,
+            vp: self.vp.clone(),
+            nft: self.nft.clone(),
+            expires: self.expires,
+            partial: false,
+            max_fee: self.max_fee,
+        }
+    }
+}
+
+impl RingSignedIntent {
+    /// Sign the intent with a key of one of the addresses in its ring. The
+    /// `ring_keys` are the public keys of the addresses in the ring.
+    pub fn new(
+        keypair: &common::SecretKey,
+        ring_keys: &[common::PublicKey],
+        data: RingIntent,
+    ) -> Result<Self, ring::Error> {
+        let to_sign = data
+            .try_to_vec()
+            .expect("Encoding data for signing shouldn't fail");
+        let sig = RingSignature::sign(keypair, ring_keys, &to_sign)?;
+        Ok(Self { data, sig })
+    }
+
+    /// Verify that the intent has been signed by the key of one of the
+    /// addresses in its ring, given their public keys
+    pub fn verify(
+        &self,
+        ring_keys: &[common::PublicKey],
+    ) -> Result<(), ring::Error> {
+        if ring_keys.len() != self.data.ring.len() {
+            return Err(ring::Error::RingSizeMismatch(
+                self.data.ring.len(),
+                ring_keys.len(),
+            ));
+        }
+        let bytes = self
+            .data
+            .try_to_vec()
+            .expect("Encoding data for verifying signature shouldn't fail");
+        self.sig.verify(ring_keys, &bytes)
+    }
+
+    /// Reveal the source of the intent with its key to have the intent
+    /// matched and settled
+    pub fn reveal(
+        self,
+        keypair: &common::SecretKey,
+        source: Address,
+    ) -> RevealedRingIntent {
+        let exchange = self
+            .data
+            .exchange
+            .iter()
+            .map(|terms| {
+                Signed::new(keypair, terms.with_source(source.clone()))
+            })
+            .collect();
+        let settlement = Signed::new(
+            keypair,
+            RingSettlement {
+                source,
+                intent: self,
+            },
+        );
+        RevealedRingIntent {
+            settlement,
+            exchange,
+        }
+    }
+}
+
+impl RevealedRingIntent {
+    /// The time after which all the exchanges of the intent are expired, if
+    /// they all expire
+    pub fn expires(&self) -> Option<DateTimeUtc> {
+        self.settlement.data.intent.data.expires()
+    }
+
+    /// Check that the intent has been revealed with the key of its source and
+    /// that its exchanges are the exchanges of the ring-signed intent. The
+    /// ring signature is verified by the source's VP at settlement.
+    pub fn verify(&self, pk: &common::PublicKey) -> bool {
+        self.settlement.verify(pk).is_ok()
+            && self.exchange.len()
+                == self.settlement.data.intent.data.exchange.len()
+            && self.exchange.iter().all(|exchange| {
+                exchange.verify(pk).is_ok()
+                    && self.settlement.data.settles(&exchange.data)
+            })
+    }
+}
+
+impl RingSettlement {
+    /// Check that the given exchange is one of the exchanges of the intent,
+    /// with the revealed source, which must be in the ring
+    pub fn settles(&self, exchange: &Exchange) -> bool {
+        exchange.addr == self.source
+            && self.intent.data.ring.contains(&self.source)
+            && self.intent.data.exchange.iter().any(|terms| {
+                &terms.with_source(self.source.clone()) == exchange
+            })
+    }
+}
+
 const INVALID_INTENT_STORAGE_KEY: &str = "invalid_intent";
+const FILLED_INTENT_STORAGE_KEY: &str = "filled_intent";
 
 /// Obtain a storage key for user's invalid intent set.
 pub fn invalid_intent_key(owner: &Address) -> Key {
@@ -232,6 +780,27 @@ pub fn is_invalid_intent_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Obtain a storage key for the amounts sold by user's exchanges that are
+/// filled in parts, keyed by their signatures.
+pub fn filled_intent_key(owner: &Address) -> Key {
+    Key::from(owner.to_db_key())
+        .push(&FILLED_INTENT_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is a key for the filled amounts of
+/// exchanges. If it is, returns the owner.
+pub fn is_filled_intent_key(key: &Key) -> Option<&Address> {
+    match &key.segments[..] {
+        [DbKeySeg::AddressSeg(owner), DbKeySeg::StringSeg(key)]
+            if key == FILLED_INTENT_STORAGE_KEY =>
+        {
+            Some(owner)
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::env;
@@ -241,7 +810,7 @@ mod tests {
 
     use super::*;
     use crate::ledger::storage::types::{decode, encode};
-    use crate::types::key;
+    use crate::types::key::{self, RefTo};
 
     #[test]
     fn test_encode_decode_intent_transfer_without_vp() {
@@ -259,6 +828,10 @@ mod tests {
             min_buy: token::Amount::from(1),
             rate_min: DecimalWrapper::from_str("0.1").unwrap(),
             vp: None,
+            nft: None,
+            expires: None,
+            partial: false,
+            max_fee: token::Amount::default(),
         };
         let exchange_two = Exchange {
             addr: Address::from_str(ALBERT).unwrap(),
@@ -268,6 +841,10 @@ mod tests {
             min_buy: token::Amount::from(100),
             rate_min: DecimalWrapper::from_str("10").unwrap(),
             vp: None,
+            nft: None,
+            expires: None,
+            partial: false,
+            max_fee: token::Amount::default(),
         };
 
         let signed_exchange_one = Signed::new(&bertha_keypair, exchange_one);
@@ -292,6 +869,7 @@ mod tests {
                             exchange: HashSet::from_iter(vec![
                                 signed_exchange_one,
                             ]),
+                            nonce: 0,
                         },
                     ),
                 ),
@@ -303,6 +881,7 @@ mod tests {
                             exchange: HashSet::from_iter(vec![
                                 signed_exchange_two,
                             ]),
+                            nonce: 0,
                         },
                     ),
                 ),
@@ -317,12 +896,14 @@ mod tests {
                     target: albert_addr.clone(),
                     token: Address::from_str(BTC).unwrap(),
                     amount: token::Amount::from(100),
+                    memo: None,
                 },
                 token::Transfer {
                     source: albert_addr,
                     target: bertha_addr,
                     token: Address::from_str(XAN).unwrap(),
                     amount: token::Amount::from(1),
+                    memo: None,
                 },
             ]
             .into_iter(),
@@ -360,6 +941,10 @@ mod tests {
                 ))
                 .unwrap(),
             ),
+            nft: None,
+            expires: None,
+            partial: false,
+            max_fee: token::Amount::default(),
         };
         let exchange_two = Exchange {
             addr: Address::from_str(ALBERT).unwrap(),
@@ -376,6 +961,10 @@ mod tests {
                 ))
                 .unwrap(),
             ),
+            nft: None,
+            expires: None,
+            partial: false,
+            max_fee: token::Amount::default(),
         };
 
         let signed_exchange_one = Signed::new(&bertha_keypair, exchange_one);
@@ -400,6 +989,7 @@ mod tests {
                             exchange: HashSet::from_iter(vec![
                                 signed_exchange_one,
                             ]),
+                            nonce: 0,
                         },
                     ),
                 ),
@@ -411,6 +1001,7 @@ mod tests {
                             exchange: HashSet::from_iter(vec![
                                 signed_exchange_two,
                             ]),
+                            nonce: 0,
                         },
                     ),
                 ),
@@ -425,12 +1016,14 @@ mod tests {
                     target: albert_addr.clone(),
                     token: Address::from_str(BTC).unwrap(),
                     amount: token::Amount::from(100),
+                    memo: None,
                 },
                 token::Transfer {
                     source: albert_addr,
                     target: bertha_addr,
                     token: Address::from_str(XAN).unwrap(),
                     amount: token::Amount::from(1),
+                    memo: None,
                 },
             ]
             .into_iter(),
@@ -443,6 +1036,315 @@ mod tests {
         assert!(decoded_intent_transfer == it);
     }
 
+    /// Test that a ring-signed intent verifies against the keys of its ring
+    /// and that its revealed source settles its exchanges
+    #[test]
+    fn test_ring_signed_intent() {
+        let bertha_addr = Address::from_str(BERTHA).unwrap();
+        let albert_addr = Address::from_str(ALBERT).unwrap();
+        let bertha_keypair = key::testing::keypair_1();
+        let albert_keypair = key::testing::keypair_2();
+        let ring_keys = vec![albert_keypair.ref_to(), bertha_keypair.ref_to()];
+
+        let exchange = Exchange {
+            addr: bertha_addr.clone(),
+            token_buy: Address::from_str(XAN).unwrap(),
+            token_sell: Address::from_str(BTC).unwrap(),
+            max_sell: token::Amount::from(100),
+            min_buy: token::Amount::from(1),
+            rate_min: DecimalWrapper::from_str("0.1").unwrap(),
+            vp: None,
+            nft: None,
+            expires: None,
+            partial: false,
+            max_fee: token::Amount::default(),
+        };
+        let intent = RingIntent {
+            ring: vec![albert_addr.clone(), bertha_addr.clone()],
+            exchange: vec![exchange.clone().into()],
+            nonce: 0,
+        };
+        let signed =
+            RingSignedIntent::new(&bertha_keypair, &ring_keys, intent.clone())
+                .unwrap();
+        signed.verify(&ring_keys).unwrap();
+        assert!(signed.verify(&ring_keys[..1]).is_err());
+
+        let other = RingSignedIntent {
+            data: RingIntent {
+                ring: vec![bertha_addr.clone()],
+                ..intent
+            },
+            sig: signed.sig.clone(),
+        };
+        assert!(other.verify(&ring_keys[1..]).is_err());
+
+        let revealed = signed.reveal(&bertha_keypair, bertha_addr);
+        assert!(revealed.verify(&bertha_keypair.ref_to()));
+        assert!(!revealed.verify(&albert_keypair.ref_to()));
+        assert_eq!(
+            revealed
+                .exchange
+                .iter()
+                .map(|exchange| &exchange.data)
+                .collect::<Vec<_>>(),
+            vec![&exchange]
+        );
+        let settlement = revealed.settlement;
+        assert!(settlement.data.settles(&exchange));
+        assert!(!settlement.data.settles(&Exchange {
+            addr: albert_addr,
+            ..exchange.clone()
+        }));
+        assert!(!settlement.data.settles(&Exchange {
+            max_sell: token::Amount::from(200),
+            ..exchange
+        }));
+    }
+
+    /// Test that the NFT token of an exchange is found on its side
+    #[test]
+    fn test_exchange_nft() {
+        let nft = Address::from_str(DOT).unwrap();
+        let xan = Address::from_str(XAN).unwrap();
+        let sell = Exchange {
+            addr: Address::from_str(BERTHA).unwrap(),
+            token_sell: nft.clone(),
+            rate_min: DecimalWrapper::default(),
+            max_sell: token::Amount::default(),
+            token_buy: xan.clone(),
+            min_buy: token::Amount::from(100),
+            vp: None,
+            nft: Some(ExchangeNft {
+                side: NftSide::Sell,
+                token_id: "7".to_owned(),
+            }),
+            expires: None,
+            partial: false,
+            max_fee: token::Amount::default(),
+        };
+        assert_eq!(sell.nft_sold(), Some((&nft, "7")));
+        assert_eq!(sell.nft_bought(), None);
+
+        let buy = Exchange {
+            addr: Address::from_str(ALBERT).unwrap(),
+            token_sell: xan,
+            max_sell: token::Amount::from(150),
+            token_buy: nft.clone(),
+            min_buy: token::Amount::default(),
+            nft: Some(ExchangeNft {
+                side: NftSide::Buy,
+                token_id: "7".to_owned(),
+            }),
+            ..sell.clone()
+        };
+        assert_eq!(buy.nft_sold(), None);
+        assert_eq!(buy.nft_bought(), Some((&nft, "7")));
+
+        // The NFT side is kept by the terms of a ring-signed intent
+        let terms = RingExchange::from(sell.clone());
+        assert_eq!(terms.with_source(sell.addr.clone()), sell);
+    }
+
+    /// Test that an intent expires once all of its exchanges have expired
+    #[test]
+    fn test_intent_expiry() {
+        let keypair = key::testing::keypair_1();
+        let time = |raw: &str| DateTimeUtc::from_str(raw).unwrap();
+        let exchange = |expires: Option<DateTimeUtc>| Exchange {
+            addr: Address::from_str(BERTHA).unwrap(),
+            token_buy: Address::from_str(XAN).unwrap(),
+            token_sell: Address::from_str(BTC).unwrap(),
+            max_sell: token::Amount::from(100),
+            min_buy: token::Amount::from(1),
+            rate_min: DecimalWrapper::from_str("0.1").unwrap(),
+            vp: None,
+            nft: None,
+            expires,
+            partial: false,
+            max_fee: token::Amount::default(),
+        };
+        let early = exchange(Some(time("2022-01-01T00:00:00Z")));
+        let late = exchange(Some(time("2022-01-02T00:00:00Z")));
+        assert!(!early.is_expired(time("2022-01-01T00:00:00Z")));
+        assert!(early.is_expired(time("2022-01-01T00:00:01Z")));
+        assert!(!exchange(None).is_expired(time("2100-01-01T00:00:00Z")));
+
+        let intent = |exchanges: Vec<Exchange>| FungibleTokenIntent {
+            exchange: exchanges
+                .into_iter()
+                .map(|exchange| Signed::new(&keypair, exchange))
+                .collect(),
+            nonce: 0,
+        };
+        assert_eq!(
+            intent(vec![early.clone(), late.clone()]).expires(),
+            late.expires
+        );
+        assert_eq!(intent(vec![early.clone(), exchange(None)]).expires(), None);
+        assert_eq!(intent(vec![]).expires(), None);
+
+        let ring_intent = RingIntent {
+            ring: vec![early.addr.clone()],
+            exchange: vec![early.into(), late.clone().into()],
+            nonce: 0,
+        };
+        assert_eq!(ring_intent.expires(), late.expires);
+    }
+
+    /// Test that the parts of an exchange filled in parts must buy at least
+    /// their proportion of its `min_buy`
+    #[test]
+    fn test_exchange_filled_in_parts() {
+        let bertha_addr = Address::from_str(BERTHA).unwrap();
+        let albert_addr = Address::from_str(ALBERT).unwrap();
+        let btc = Address::from_str(BTC).unwrap();
+        let exchange = Exchange {
+            addr: bertha_addr.clone(),
+            token_buy: Address::from_str(XAN).unwrap(),
+            token_sell: btc.clone(),
+            max_sell: token::Amount::from(300),
+            min_buy: token::Amount::from(100),
+            rate_min: DecimalWrapper::from_str("0.1").unwrap(),
+            vp: None,
+            nft: None,
+            expires: None,
+            partial: true,
+            max_fee: token::Amount::default(),
+        };
+        assert!(exchange.fills_in_parts());
+        assert_eq!(
+            exchange.min_buy_for(token::Amount::from(150)),
+            token::Amount::from(50)
+        );
+        // Rounded up
+        assert_eq!(
+            exchange.min_buy_for(token::Amount::from(100)),
+            token::Amount::from(34)
+        );
+        assert_eq!(exchange.min_buy_for(exchange.max_sell), exchange.min_buy);
+
+        // The parts pay their proportion of the `max_fee`, rounded down
+        let exchange = Exchange {
+            max_fee: token::Amount::from(10),
+            ..exchange
+        };
+        assert_eq!(
+            exchange.max_fee_for(token::Amount::from(150)),
+            token::Amount::from(5)
+        );
+        assert_eq!(
+            exchange.max_fee_for(token::Amount::from(100)),
+            token::Amount::from(3)
+        );
+        assert_eq!(exchange.max_fee_for(exchange.max_sell), exchange.max_fee);
+
+        let nft_exchange = Exchange {
+            nft: Some(ExchangeNft {
+                side: NftSide::Buy,
+                token_id: "7".to_owned(),
+            }),
+            ..exchange.clone()
+        };
+        assert!(!nft_exchange.fills_in_parts());
+        assert_eq!(
+            nft_exchange.max_fee_for(token::Amount::from(100)),
+            nft_exchange.max_fee
+        );
+
+        let mut matches = MatchedExchanges::empty();
+        matches.transfers = HashSet::from_iter([
+            token::Transfer {
+                source: bertha_addr.clone(),
+                target: albert_addr.clone(),
+                token: btc.clone(),
+                amount: token::Amount::from(100),
+                memo: None,
+            },
+            token::Transfer {
+                source: bertha_addr.clone(),
+                target: Address::from_str(CHRISTEL).unwrap(),
+                token: btc,
+                amount: token::Amount::from(50),
+                memo: None,
+            },
+            token::Transfer {
+                source: albert_addr,
+                target: bertha_addr,
+                token: exchange.token_buy.clone(),
+                amount: token::Amount::from(50),
+                memo: None,
+            },
+        ]);
+        assert_eq!(matches.sold_by(&exchange), token::Amount::from(150));
+    }
+
+    /// Test that the matchmaker's fees are computed from the sold amounts and
+    /// capped by the `max_fee` of the exchanges
+    #[test]
+    fn test_matchmaker_fee() {
+        let keypair = key::testing::keypair_1();
+        let bertha_addr = Address::from_str(BERTHA).unwrap();
+        let albert_addr = Address::from_str(ALBERT).unwrap();
+        let btc = Address::from_str(BTC).unwrap();
+        let xan = Address::from_str(XAN).unwrap();
+
+        let bps = MatchmakerFee::from_str("30bps").unwrap();
+        assert_eq!(bps, MatchmakerFee::Bps { bps: 30 });
+        assert_eq!(bps.to_string(), "30bps");
+        assert_eq!(
+            bps.fee_for(token::Amount::whole(1000)),
+            token::Amount::whole(3)
+        );
+        let flat = MatchmakerFee::from_str("0.5").unwrap();
+        assert_eq!(
+            flat.fee_for(token::Amount::whole(1000)),
+            token::Amount::from(500_000)
+        );
+        assert!(MatchmakerFee::from_str("abps").is_err());
+
+        let exchange = |addr: &Address,
+                        token_sell: &Address,
+                        token_buy: &Address,
+                        max_fee: u64| Exchange {
+            addr: addr.clone(),
+            token_sell: token_sell.clone(),
+            rate_min: DecimalWrapper::from_str("1").unwrap(),
+            max_sell: token::Amount::whole(1000),
+            token_buy: token_buy.clone(),
+            min_buy: token::Amount::whole(1000),
+            vp: None,
+            nft: None,
+            expires: None,
+            partial: false,
+            max_fee: token::Amount::whole(max_fee),
+        };
+        let mut matches = MatchedExchanges::empty();
+        for (exchange, counterparty) in [
+            (exchange(&bertha_addr, &btc, &xan, 10), &albert_addr),
+            (exchange(&albert_addr, &xan, &btc, 1), &bertha_addr),
+        ] {
+            matches.transfers.insert(token::Transfer {
+                source: exchange.addr.clone(),
+                target: counterparty.clone(),
+                token: exchange.token_sell.clone(),
+                amount: exchange.max_sell,
+                memo: None,
+            });
+            matches
+                .exchanges
+                .insert(exchange.addr.clone(), Signed::new(&keypair, exchange));
+        }
+        matches.add_fees(&bps);
+        assert_eq!(
+            matches.fees,
+            HashMap::from_iter([
+                (bertha_addr, token::Amount::whole(3)),
+                (albert_addr, token::Amount::whole(1)),
+            ])
+        );
+    }
+
     #[cfg(test)]
     #[allow(dead_code)]
     mod constants {
@@ -19,6 +19,7 @@ use crate::types::address;
 
 pub mod common;
 pub mod ed25519;
+pub mod ring;
 
 const PK_STORAGE_KEY: &str = "public_key";
 const PROTOCOL_PK_STORAGE_KEY: &str = "protocol_public_key";
@@ -0,0 +1,232 @@
+//! Ring signatures over Ed25519 keys.
+//!
+//! A ring signature proves that the data has been signed by the secret key
+//! of one of the public keys in a ring, without revealing which one. This is
+//! the Schnorr ring signature of Abe, Ohkubo and Suzuki on the Ed25519 curve.
+//! The nonces are derived from the signer's secret key and the signed data,
+//! so signing doesn't need a source of randomness.
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use curve25519_dalek_ng::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek_ng::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek_ng::scalar::Scalar;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use thiserror::Error;
+
+use super::{common, ed25519, RefTo};
+
+const DOMAIN: &[u8] = b"anoma ring signature";
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The ring is empty")]
+    EmptyRing,
+    #[error("The signing key is not in the ring")]
+    SignerNotInRing,
+    #[error("The public key {0} in the ring is not a valid curve point")]
+    InvalidKey(common::PublicKey),
+    #[error(
+        "The signature has {0} responses, but the ring has {1} public keys"
+    )]
+    RingSizeMismatch(usize, usize),
+    #[error("The ring signature is not valid")]
+    Invalid,
+}
+
+/// A ring signature, i.e. the first challenge of the ring and a response for
+/// every public key in the ring, in the order of the ring
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Serialize,
+    Deserialize,
+)]
+pub struct RingSignature {
+    /// The challenge of the first key in the ring
+    pub challenge: [u8; 32],
+    /// The responses
+    pub responses: Vec<[u8; 32]>,
+}
+
+impl RingSignature {
+    /// Sign the data with the keypair, whose public key must be in the ring
+    pub fn sign(
+        keypair: &common::SecretKey,
+        ring: &[common::PublicKey],
+        data: impl AsRef<[u8]>,
+    ) -> Result<Self, Error> {
+        let signer = ring
+            .iter()
+            .position(|pk| pk == &keypair.ref_to())
+            .ok_or(Error::SignerNotInRing)?;
+        let points = decompress_ring(ring)?;
+        let secret = secret_scalar(keypair);
+        let prefix = hash_prefix(ring, data.as_ref());
+
+        let n = ring.len();
+        let nonce = derive_scalar(&secret, &prefix, signer, b"nonce");
+        let mut challenges = vec![Scalar::zero(); n];
+        let mut responses = vec![Scalar::zero(); n];
+        challenges[(signer + 1) % n] =
+            challenge(&prefix, &(&nonce * &ED25519_BASEPOINT_TABLE));
+        for step in 1..n {
+            let i = (signer + step) % n;
+            responses[i] = derive_scalar(&secret, &prefix, i, b"response");
+            let commitment = EdwardsPoint::vartime_double_scalar_mul_basepoint(
+                &challenges[i],
+                &points[i],
+                &responses[i],
+            );
+            challenges[(i + 1) % n] = challenge(&prefix, &commitment);
+        }
+        responses[signer] = nonce - challenges[signer] * secret;
+
+        Ok(Self {
+            challenge: challenges[0].to_bytes(),
+            responses: responses.iter().map(Scalar::to_bytes).collect(),
+        })
+    }
+
+    /// Verify that the data has been signed by the secret key counterpart of
+    /// one of the public keys in the ring
+    pub fn verify(
+        &self,
+        ring: &[common::PublicKey],
+        data: impl AsRef<[u8]>,
+    ) -> Result<(), Error> {
+        if self.responses.len() != ring.len() {
+            return Err(Error::RingSizeMismatch(
+                self.responses.len(),
+                ring.len(),
+            ));
+        }
+        let points = decompress_ring(ring)?;
+        let prefix = hash_prefix(ring, data.as_ref());
+        let first = Scalar::from_canonical_bytes(self.challenge)
+            .ok_or(Error::Invalid)?;
+        let mut current = first;
+        for (point, response) in points.iter().zip(&self.responses) {
+            let response = Scalar::from_canonical_bytes(*response)
+                .ok_or(Error::Invalid)?;
+            let commitment = EdwardsPoint::vartime_double_scalar_mul_basepoint(
+                &current, point, &response,
+            );
+            current = challenge(&prefix, &commitment);
+        }
+        if current == first {
+            Ok(())
+        } else {
+            Err(Error::Invalid)
+        }
+    }
+}
+
+fn decompress_ring(
+    ring: &[common::PublicKey],
+) -> Result<Vec<EdwardsPoint>, Error> {
+    if ring.is_empty() {
+        return Err(Error::EmptyRing);
+    }
+    ring.iter()
+        .map(|pk| {
+            let common::PublicKey::Ed25519(ed25519::PublicKey(key)) = pk;
+            CompressedEdwardsY(key.to_bytes())
+                .decompress()
+                .ok_or_else(|| Error::InvalidKey(pk.clone()))
+        })
+        .collect()
+}
+
+/// The secret scalar of an Ed25519 key, expanded from its seed
+fn secret_scalar(keypair: &common::SecretKey) -> Scalar {
+    let common::SecretKey::Ed25519(ed25519::SecretKey(key)) = keypair;
+    let hash = Sha512::digest(&key.to_bytes());
+    let mut bits = [0u8; 32];
+    bits.copy_from_slice(&hash[..32]);
+    bits[0] &= 248;
+    bits[31] &= 127;
+    bits[31] |= 64;
+    Scalar::from_bytes_mod_order(bits)
+}
+
+/// The hash of the ring and the data, which is a prefix of every challenge
+fn hash_prefix(ring: &[common::PublicKey], data: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha512::new();
+    hasher.update(DOMAIN);
+    hasher.update(&(ring.len() as u64).to_le_bytes());
+    for pk in ring {
+        let common::PublicKey::Ed25519(ed25519::PublicKey(key)) = pk;
+        hasher.update(&key.to_bytes());
+    }
+    hasher.update(data);
+    hasher.finalize().to_vec()
+}
+
+fn challenge(prefix: &[u8], commitment: &EdwardsPoint) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(prefix);
+    hasher.update(commitment.compress().as_bytes());
+    Scalar::from_hash(hasher)
+}
+
+/// A secret scalar derived from the signer's secret, the signed data and the
+/// index in the ring
+fn derive_scalar(
+    secret: &Scalar,
+    prefix: &[u8],
+    index: usize,
+    purpose: &[u8],
+) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(purpose);
+    hasher.update(secret.as_bytes());
+    hasher.update(prefix);
+    hasher.update(&(index as u64).to_le_bytes());
+    Scalar::from_hash(hasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::key::testing::{gen_keypair, keypair_1, keypair_2};
+
+    /// Test that a ring signature can be produced by any key in the ring and
+    /// that it only verifies with the same ring and data
+    #[test]
+    fn test_ring_signature() {
+        let outsider = keypair_2();
+        let keypairs: Vec<common::SecretKey> =
+            (0..3).map(|_| gen_keypair::<common::SigScheme>()).collect();
+        let ring: Vec<common::PublicKey> =
+            keypairs.iter().map(RefTo::ref_to).collect();
+
+        for keypair in &keypairs {
+            let sig = RingSignature::sign(keypair, &ring, b"data").unwrap();
+            sig.verify(&ring, b"data").unwrap();
+            assert!(sig.verify(&ring, b"other data").is_err());
+            assert!(sig.verify(&ring[..2], b"data").is_err());
+
+            let mut reordered = ring.clone();
+            reordered.swap(0, 1);
+            assert!(sig.verify(&reordered, b"data").is_err());
+        }
+
+        assert!(matches!(
+            RingSignature::sign(&outsider, &ring, b"data"),
+            Err(Error::SignerNotInRing)
+        ));
+        let single = vec![keypair_1().ref_to()];
+        RingSignature::sign(&keypair_1(), &single, b"data")
+            .unwrap()
+            .verify(&single, b"data")
+            .unwrap();
+    }
+}
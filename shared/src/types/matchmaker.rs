@@ -2,13 +2,15 @@
 
 use std::collections::HashSet;
 
+use serde::{Deserialize, Serialize};
+
 /// A matchmaker marker trait. This should not be implemented manually. Instead,
 /// it is added by the derive `Matchmaker` macro, which also adds necessary
 /// binding code for matchmaker dylib runner.
 pub trait Matchmaker: AddIntent {}
 
 /// A matchmaker must implement this trait
-pub trait AddIntent: Default {
+pub trait AddIntent: Default + Clone {
     // TODO: For some reason, using `&[u8]` causes the `decode_intent_data` to
     // fail decoding
     /// Add a new intent to matchmaker's state
@@ -18,6 +20,58 @@ pub trait AddIntent: Default {
         intent_id: &Vec<u8>,
         intent_data: &Vec<u8>,
     ) -> AddIntentResult;
+
+    /// Find how a hypothetical intent would be matched with the intents in the
+    /// matchmaker's state, without adding it to the state. By default, the
+    /// intent is added to a copy of the state.
+    #[allow(clippy::ptr_arg)]
+    fn dry_run_intent(
+        &self,
+        intent_id: &Vec<u8>,
+        intent_data: &Vec<u8>,
+    ) -> AddIntentResult {
+        self.clone().add_intent(intent_id, intent_data)
+    }
+
+    /// Set the strategy with which the matchmaker matches the intents. It's
+    /// set before any intent is added. By default, it's ignored.
+    fn set_strategy(&mut self, _strategy: MatchingStrategy) {}
+
+    /// Set whether the matchmaker matches the intents in batch auctions. If
+    /// so, the added intents are only collected until the batch is cleared
+    /// with [`AddIntent::clear_batch`]. It's set before any intent is added.
+    /// By default, it's ignored.
+    fn set_batch_auction(&mut self, _enabled: bool) {}
+
+    /// Clear the batch of the intents collected since the last clearing, i.e.
+    /// match all the intents that cross at a uniform clearing price into a
+    /// single tx. It's called at every batch interval, if the matchmaker
+    /// matches the intents in batch auctions. By default, nothing is
+    /// matched.
+    fn clear_batch(&mut self) -> AddIntentResult {
+        AddIntentResult::default()
+    }
+}
+
+/// The strategy with which a matchmaker chooses between the intents that
+/// a new intent can be matched with, i.e. the fairness policy of its
+/// orderbook
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchingStrategy {
+    /// The best price first and, at the same price, the earliest received
+    PriceTimePriority,
+    /// The best price first, split between all the intents at that price in
+    /// proportion to their size
+    ProRata,
+    /// The best price first and, at the same price, the largest size
+    BestPrice,
+}
+
+impl Default for MatchingStrategy {
+    fn default() -> Self {
+        Self::PriceTimePriority
+    }
 }
 
 /// The result of calling matchmaker's `add_intent` function
@@ -27,4 +81,7 @@ pub struct AddIntentResult {
     pub tx: Option<Vec<u8>>,
     /// The intent IDs that were matched into the tx, if any
     pub matched_intents: Option<HashSet<Vec<u8>>>,
+    /// The intent IDs of the `matched_intents` whose exchanges were only
+    /// filled in part, if any. Their remainder can still be matched.
+    pub partially_matched_intents: Option<HashSet<Vec<u8>>>,
 }
@@ -1,15 +1,20 @@
 //! Nft types
+use std::collections::BTreeMap;
 use std::fmt;
+use std::str::FromStr;
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
 
 use super::address::Address;
 use super::storage::{DbKeySeg, Key, KeySeg};
+use super::token::Amount;
 
 const NFT_KEY: &str = "nft";
 const TAG_KEY: &str = "tag";
 const CREATOR_KEY: &str = "creator";
+const ROYALTY_KEY: &str = "royalty";
 const KEYS: &str = "keys";
 const OPTIONAL_KEYS: &str = "optional_keys";
 const METADATA_KEY: &str = "metadata";
@@ -21,6 +26,37 @@ const PAST_OWNERS_KEY: &str = "past_owners";
 const VALUE_KEY: &str = "value";
 const OPTIONAL_VALUE: &str = "optional_value";
 
+/// The maximum length of the name in an NFT token's metadata
+pub const MAX_METADATA_NAME_LEN: usize = 128;
+/// The maximum length of the description in an NFT token's metadata
+pub const MAX_METADATA_DESCRIPTION_LEN: usize = 4096;
+/// The schemes of the URIs accepted for the media of an NFT token
+pub const METADATA_URI_SCHEMES: [&str; 4] = ["https", "http", "ipfs", "ar"];
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum MetadataError {
+    #[error("The metadata is not valid: {0}")]
+    Decoding(serde_json::Error),
+    #[error("The name must not be empty")]
+    EmptyName,
+    #[error("The {field} is {len} characters long, the maximum is {max}")]
+    TooLong {
+        field: &'static str,
+        len: usize,
+        max: usize,
+    },
+    #[error(
+        "The media URI \"{0}\" must be an absolute URI with the scheme https, \
+         http, ipfs or ar"
+    )]
+    InvalidMediaUri(String),
+    #[error("The media hash {0} is not a hex-encoded SHA-256 hash")]
+    InvalidMediaHash(String),
+    #[error("The names of the attributes must not be empty")]
+    EmptyAttributeName,
+}
+
 #[derive(
     Debug,
     Clone,
@@ -81,6 +117,61 @@ pub struct Nft {
     pub opt_keys: Vec<String>,
     /// The list of tokens
     pub tokens: Vec<NftToken>,
+    #[serde(default)]
+    /// The royalty paid out of the payments for the tokens
+    pub royalty: Option<Royalty>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Hash,
+    PartialOrd,
+)]
+/// The royalty of an NFT. Whenever a token changes owner in a tx that also
+/// pays the previous owner in any fungible token, the beneficiary must be
+/// paid its share of the payment in the same token.
+pub struct Royalty {
+    /// The beneficiary's share of a payment in percent, at most 100
+    pub percentage: u8,
+    /// The address that receives the royalty
+    pub beneficiary: Address,
+}
+
+impl Royalty {
+    /// Check that the percentage is at most 100
+    pub fn is_valid(&self) -> bool {
+        self.percentage <= 100
+    }
+
+    /// Check that the beneficiary has been paid its share of a payment, which
+    /// is the sum of the amounts received by the seller and the beneficiary
+    pub fn is_paid(&self, to_seller: Amount, to_beneficiary: Amount) -> bool {
+        let to_seller = u64::from(to_seller) as u128;
+        let to_beneficiary = u64::from(to_beneficiary) as u128;
+        to_beneficiary * 100
+            >= (to_seller + to_beneficiary) * self.percentage as u128
+    }
+
+    /// The smallest amount to pay to the beneficiary on top of a payment to
+    /// the seller for the royalty to be paid. Returns `None` if the royalty
+    /// is 100% and the seller can't be paid at all.
+    pub fn on_top_of(&self, to_seller: Amount) -> Option<Amount> {
+        let percentage = self.percentage as u128;
+        if percentage >= 100 {
+            return None;
+        }
+        let to_seller = u64::from(to_seller) as u128;
+        let to_beneficiary =
+            (to_seller * percentage + 99 - percentage) / (100 - percentage);
+        u64::try_from(to_beneficiary).ok().map(Amount::from)
+    }
 }
 
 impl fmt::Display for Nft {
@@ -109,7 +200,9 @@ impl fmt::Display for Nft {
 pub struct NftToken {
     /// The token id
     pub id: u64,
-    /// The URI containing metadata
+    /// The metadata of the token, following the [`NftTokenMetadata`]
+    /// standard. In a JSON file, it may be given as an object.
+    #[serde(deserialize_with = "deserialize_metadata")]
     pub metadata: String,
     /// Current owner
     pub current_owner: Option<Address>,
@@ -138,6 +231,108 @@ impl fmt::Display for NftToken {
     }
 }
 
+impl NftToken {
+    /// Decode the token's metadata and check that it conforms to the
+    /// standard
+    pub fn decode_metadata(&self) -> Result<NftTokenMetadata, MetadataError> {
+        let metadata: NftTokenMetadata = self.metadata.parse()?;
+        metadata.validate()?;
+        Ok(metadata)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+/// The standard metadata of an NFT token, stored encoded as JSON
+pub struct NftTokenMetadata {
+    /// The name of the token
+    pub name: String,
+    /// The description of the token
+    #[serde(default)]
+    pub description: String,
+    /// The URI of the token's media, e.g. an image
+    pub media_uri: String,
+    /// The hex-encoded SHA-256 hash of the content at the media URI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_hash: Option<String>,
+    /// Additional attributes of the token by their names
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl NftTokenMetadata {
+    /// Check that the metadata conforms to the standard
+    pub fn validate(&self) -> Result<(), MetadataError> {
+        if self.name.trim().is_empty() {
+            return Err(MetadataError::EmptyName);
+        }
+        let lengths = [
+            ("name", &self.name, MAX_METADATA_NAME_LEN),
+            (
+                "description",
+                &self.description,
+                MAX_METADATA_DESCRIPTION_LEN,
+            ),
+        ];
+        for (field, value, max) in lengths {
+            let len = value.chars().count();
+            if len > max {
+                return Err(MetadataError::TooLong { field, len, max });
+            }
+        }
+        match self.media_uri.split_once("://") {
+            Some((scheme, rest))
+                if METADATA_URI_SCHEMES.contains(&scheme)
+                    && !rest.is_empty()
+                    && !rest.contains(char::is_whitespace) => {}
+            _ => {
+                return Err(MetadataError::InvalidMediaUri(
+                    self.media_uri.clone(),
+                ));
+            }
+        }
+        if let Some(hash) = &self.media_hash {
+            match hex::decode(hash) {
+                Ok(bytes) if bytes.len() == 32 => {}
+                _ => return Err(MetadataError::InvalidMediaHash(hash.clone())),
+            }
+        }
+        if self.attributes.keys().any(|name| name.trim().is_empty()) {
+            return Err(MetadataError::EmptyAttributeName);
+        }
+        Ok(())
+    }
+
+    /// Encode the metadata as JSON
+    pub fn encode(&self) -> String {
+        serde_json::to_string(self)
+            .expect("Encoding NFT token metadata shouldn't fail")
+    }
+}
+
+impl FromStr for NftTokenMetadata {
+    type Err = MetadataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(MetadataError::Decoding)
+    }
+}
+
+/// Deserialize the metadata of a token given either as a string or as a JSON
+/// object, which is then encoded to a string
+fn deserialize_metadata<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(metadata) => Ok(metadata),
+        metadata @ serde_json::Value::Object(_) => Ok(metadata.to_string()),
+        _ => Err(serde::de::Error::custom(
+            "expected the metadata as a string or an object",
+        )),
+    }
+}
+
 fn default_opt_keys() -> Vec<String> {
     Vec::new()
 }
@@ -184,6 +379,13 @@ pub fn get_creator_key(address: &Address) -> Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the nft royalty storage key
+pub fn get_royalty_key(address: &Address) -> Key {
+    _nft_prefix(address)
+        .push(&ROYALTY_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get the nft keys storage key
 pub fn get_keys_key(address: &Address) -> Key {
     _nft_prefix(address)
@@ -264,6 +466,23 @@ pub fn is_nft_creator_key(key: &Key, address: &Address) -> Option<Address> {
     }
 }
 
+/// Check that a particular key is the royalty storage key of the nft
+pub fn is_nft_royalty_key(key: &Key, address: &Address) -> Option<Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(nft_addr),
+            DbKeySeg::StringSeg(prefix),
+            DbKeySeg::StringSeg(royalty_key),
+        ] if nft_addr == address
+            && prefix == NFT_KEY
+            && royalty_key == ROYALTY_KEY =>
+        {
+            Some(nft_addr.to_owned())
+        }
+        _ => None,
+    }
+}
+
 /// Check that a particular key is a approval storage key
 pub fn is_nft_approval_key(
     key: &Key,
@@ -367,3 +586,134 @@ pub fn is_nft_key(key: &Key) -> Option<&Address> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::address::testing::established_address_1;
+    use crate::types::hash::Hash;
+
+    fn metadata() -> NftTokenMetadata {
+        NftTokenMetadata {
+            name: "Token".to_owned(),
+            description: "A token".to_owned(),
+            media_uri: "ipfs://QmToken".to_owned(),
+            media_hash: None,
+            attributes: BTreeMap::from([(
+                "color".to_owned(),
+                "red".to_owned(),
+            )]),
+        }
+    }
+
+    #[test]
+    fn test_metadata_validation() {
+        assert!(metadata().validate().is_ok());
+
+        let mut invalid = metadata();
+        invalid.name = " ".to_owned();
+        assert!(matches!(invalid.validate(), Err(MetadataError::EmptyName)));
+
+        let mut invalid = metadata();
+        invalid.description = "a".repeat(MAX_METADATA_DESCRIPTION_LEN + 1);
+        assert!(matches!(
+            invalid.validate(),
+            Err(MetadataError::TooLong {
+                field: "description",
+                ..
+            })
+        ));
+
+        for uri in ["ftp://example.com/a.png", "example.com/a.png", "https://"]
+        {
+            let mut invalid = metadata();
+            invalid.media_uri = uri.to_owned();
+            assert!(matches!(
+                invalid.validate(),
+                Err(MetadataError::InvalidMediaUri(_))
+            ));
+        }
+
+        let mut with_hash = metadata();
+        with_hash.media_hash = Some(Hash::sha256(b"media").to_string());
+        assert!(with_hash.validate().is_ok());
+        with_hash.media_hash = Some("abcd".to_owned());
+        assert!(matches!(
+            with_hash.validate(),
+            Err(MetadataError::InvalidMediaHash(_))
+        ));
+    }
+
+    #[test]
+    fn test_token_metadata_as_object() {
+        let token: NftToken = serde_json::from_str(
+            r#"{
+                "id": 1,
+                "metadata": {"name": "Token", "media_uri": "https://example.com/1.png"},
+                "current_owner": null,
+                "approvals": [],
+                "values": []
+            }"#,
+        )
+        .unwrap();
+        let metadata = token.decode_metadata().unwrap();
+        assert_eq!(metadata.name, "Token");
+        assert_eq!(metadata.media_uri, "https://example.com/1.png");
+
+        // The metadata can also be given already encoded
+        let encoded: NftToken = serde_json::from_str(&format!(
+            r#"{{
+                "id": 1,
+                "metadata": {},
+                "current_owner": null,
+                "approvals": [],
+                "values": []
+            }}"#,
+            serde_json::to_string(&metadata.encode()).unwrap()
+        ))
+        .unwrap();
+        assert_eq!(encoded.decode_metadata().unwrap(), metadata);
+
+        // Unknown fields are rejected
+        let unknown: Result<NftTokenMetadata, _> =
+            r#"{"name": "Token", "media_uri": "https://a.b", "mediaUri": ""}"#
+                .parse();
+        assert!(matches!(unknown, Err(MetadataError::Decoding(_))));
+    }
+
+    #[test]
+    fn test_royalty_is_paid() {
+        let royalty = Royalty {
+            percentage: 10,
+            beneficiary: established_address_1(),
+        };
+        assert!(royalty.is_valid());
+        let amount = Amount::whole;
+        assert!(royalty.is_paid(amount(90), amount(10)));
+        assert!(royalty.is_paid(amount(80), amount(20)));
+        assert!(!royalty.is_paid(amount(91), amount(9)));
+        assert!(!royalty.is_paid(amount(100), amount(0)));
+        assert!(royalty.is_paid(amount(0), amount(0)));
+
+        for to_seller in [0, 1, 9, 90, 1_000_001] {
+            let to_seller = Amount::from(to_seller);
+            let on_top = royalty.on_top_of(to_seller).unwrap();
+            assert!(royalty.is_paid(to_seller, on_top));
+            if on_top != Amount::default() {
+                let less = on_top - Amount::from(1);
+                assert!(!royalty.is_paid(to_seller, less));
+            }
+        }
+        let full = Royalty {
+            percentage: 100,
+            ..royalty.clone()
+        };
+        assert_eq!(full.on_top_of(amount(1)), None);
+
+        let invalid = Royalty {
+            percentage: 101,
+            ..royalty
+        };
+        assert!(!invalid.is_valid());
+    }
+}
@@ -656,6 +656,11 @@ impl Epochs {
         self.first_block_heights.push(block_height);
     }
 
+    /// Get the block height of the first block of the oldest known epoch.
+    pub fn first_block_height(&self) -> Option<BlockHeight> {
+        self.first_block_heights.first().copied()
+    }
+
     /// Look-up the epoch of a given block height.
     pub fn get_epoch(&self, block_height: BlockHeight) -> Option<Epoch> {
         if let Some((first_known_epoch_height, rest)) =
@@ -3,9 +3,10 @@
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Display;
 use std::ops::{Add, Sub};
+use std::str::FromStr;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
-pub use chrono::{DateTime, Duration, TimeZone, Utc};
+pub use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
 #[cfg(not(feature = "ABCI"))]
 use tendermint_proto::google::protobuf;
 #[cfg(feature = "ABCI")]
@@ -116,6 +117,16 @@ impl DateTimeUtc {
     }
 }
 
+impl FromStr for DateTimeUtc {
+    type Err = chrono::ParseError;
+
+    /// Parse an RFC 3339 timestamp (e.g., "1970-01-01T00:00:00Z")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let utc = DateTime::parse_from_rfc3339(s)?;
+        Ok(Self(utc.into()))
+    }
+}
+
 impl Add<DurationSecs> for DateTimeUtc {
     type Output = DateTimeUtc;
 
@@ -176,6 +187,25 @@ impl BorshSchema for DateTimeUtc {
     }
 }
 
+impl serde::Serialize for DateTimeUtc {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_rfc3339())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for DateTimeUtc {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        Self::from_str(&raw).map_err(serde::de::Error::custom)
+    }
+}
+
 impl From<DateTime<Utc>> for DateTimeUtc {
     fn from(dt: DateTime<Utc>) -> Self {
         Self(dt)
@@ -11,7 +11,7 @@ use thiserror::Error;
 
 use crate::types::address::{Address, Error as AddressError, InternalAddress};
 use crate::types::ibc::data::FungibleTokenPacketData;
-use crate::types::storage::{DbKeySeg, Key, KeySeg};
+use crate::types::storage::{DbKeySeg, Epoch, Key, KeySeg};
 
 /// Amount in micro units. For different granularity another representation
 /// might be more appropriate.
@@ -279,6 +279,68 @@ pub fn is_any_token_balance_key(key: &Key) -> Option<&Address> {
     }
 }
 
+/// Key segment for a vesting schedule key
+pub const VESTING_STORAGE_KEY: &str = "vesting";
+
+/// Obtain a storage key for user's vesting schedule of the given token.
+pub fn vesting_key(token_addr: &Address, owner: &Address) -> Key {
+    Key::from(token_addr.to_db_key())
+        .push(&VESTING_STORAGE_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+        .push(&owner.to_db_key())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Check if the given storage key is a vesting schedule key for the given
+/// token. If it is, returns the owner.
+pub fn is_vesting_key<'a>(
+    token_addr: &Address,
+    key: &'a Key,
+) -> Option<&'a Address> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(addr),
+            DbKeySeg::StringSeg(key),
+            DbKeySeg::AddressSeg(owner),
+        ] if key == VESTING_STORAGE_KEY && addr == token_addr => Some(owner),
+        _ => None,
+    }
+}
+
+/// A vesting schedule of tokens that are locked in an account's balance at
+/// genesis. The locked tokens are released in equal parts in every epoch
+/// from the `start_epoch` and they cannot be spent until then.
+#[derive(
+    Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct VestingSchedule {
+    /// The amount of tokens locked at genesis
+    pub locked: Amount,
+    /// The first epoch in which a part of the locked tokens is released
+    pub start_epoch: Epoch,
+    /// The number of epochs over which the locked tokens are released
+    pub epochs: u64,
+}
+
+impl VestingSchedule {
+    /// Find the amount that is still locked in the given epoch.
+    pub fn locked_at(&self, epoch: Epoch) -> Amount {
+        if epoch < self.start_epoch {
+            return self.locked;
+        }
+        let released_epochs = epoch.0 - self.start_epoch.0 + 1;
+        if released_epochs >= self.epochs {
+            return Amount::default();
+        }
+        let remaining_epochs = self.epochs - released_epochs;
+        let locked = self.locked.micro as u128 * remaining_epochs as u128
+            / self.epochs as u128;
+        Amount {
+            micro: locked as u64,
+        }
+    }
+}
+
 /// Check if the given storage key is non-owner's balance key. If it is, returns
 /// the address.
 pub fn is_non_owner_balance_key(key: &Key) -> Option<&Address> {
@@ -297,7 +359,22 @@ pub fn is_non_owner_balance_key(key: &Key) -> Option<&Address> {
     }
 }
 
-/// A simple bilateral token transfer
+/// The maximum length of a transfer's memo in bytes
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// Check that an optional memo is no longer than [`MAX_MEMO_LEN`]
+pub fn is_valid_memo(memo: &Option<String>) -> bool {
+    memo.as_ref()
+        .map(|memo| memo.len() <= MAX_MEMO_LEN)
+        .unwrap_or(true)
+}
+
+/// A simple bilateral token transfer.
+///
+/// The Borsh encoding of a transfer ends with its `memo`, which is encoded as
+/// a `0` byte when it's `None`, or as a `1` byte followed by the
+/// length-prefixed UTF-8 string otherwise. Transfers encoded before the memo
+/// was added lack that byte and can no longer be decoded.
 #[derive(
     Debug,
     Clone,
@@ -320,6 +397,83 @@ pub struct Transfer {
     pub token: Address,
     /// The amount of tokens
     pub amount: Amount,
+    /// An optional memo, e.g. to correlate a deposit with an off-chain
+    /// payment. It's at most [`MAX_MEMO_LEN`] bytes long.
+    pub memo: Option<String>,
+}
+
+impl Transfer {
+    /// Check that the memo, if any, is no longer than [`MAX_MEMO_LEN`]
+    pub fn has_valid_memo(&self) -> bool {
+        is_valid_memo(&self.memo)
+    }
+}
+
+/// A transfer of a token from a single source to many targets, e.g. for an
+/// airdrop or a payroll. It's applied in a single tx, so the source's VP only
+/// runs once.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct Disbursement {
+    /// Source address will spend the tokens
+    pub source: Address,
+    /// Token's address
+    pub token: Address,
+    /// The targets with their amounts and memos
+    pub entries: Vec<DisbursementEntry>,
+}
+
+/// A target of a [`Disbursement`]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
+pub struct DisbursementEntry {
+    /// Target address will receive the tokens
+    pub target: Address,
+    /// The amount of tokens
+    pub amount: Amount,
+    /// An optional memo for this target, e.g. to identify a payment. It's at
+    /// most [`MAX_MEMO_LEN`] bytes long.
+    pub memo: Option<String>,
+}
+
+impl Disbursement {
+    /// The total amount spent by the source, or `None` on overflow
+    pub fn total(&self) -> Option<Amount> {
+        self.entries
+            .iter()
+            .try_fold(0_u64, |total, entry| {
+                total.checked_add(entry.amount.micro)
+            })
+            .map(Amount::from)
+    }
+
+    /// Check that the memos of all the entries are no longer than
+    /// [`MAX_MEMO_LEN`]
+    pub fn has_valid_memos(&self) -> bool {
+        self.entries.iter().all(|entry| is_valid_memo(&entry.memo))
+    }
 }
 
 #[allow(missing_docs)]
@@ -355,6 +509,7 @@ impl TryFrom<FungibleTokenPacketData> for Transfer {
             target,
             token,
             amount,
+            memo: None,
         })
     }
 }
@@ -364,6 +519,9 @@ mod tests {
     use proptest::prelude::*;
 
     use super::*;
+    use crate::types::address::testing::{
+        established_address_1, established_address_2,
+    };
 
     proptest! {
             /// The upper limit is set to `2^51`, because then the float is
@@ -378,6 +536,59 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_disbursement_total() {
+        let entry = |micro: u64| DisbursementEntry {
+            target: established_address_2(),
+            amount: Amount::from(micro),
+            memo: None,
+        };
+        let mut disbursement = Disbursement {
+            source: established_address_1(),
+            token: established_address_1(),
+            entries: vec![],
+        };
+        assert_eq!(disbursement.total(), Some(Amount::from(0)));
+        disbursement.entries = vec![entry(1), entry(2)];
+        assert_eq!(disbursement.total(), Some(Amount::from(3)));
+        disbursement.entries.push(entry(u64::MAX));
+        assert_eq!(disbursement.total(), None);
+    }
+
+    /// Test that the memos longer than [`MAX_MEMO_LEN`] bytes are invalid
+    #[test]
+    fn test_memo_len() {
+        let mut transfer = Transfer {
+            source: established_address_1(),
+            target: established_address_2(),
+            token: established_address_1(),
+            amount: Amount::from(1),
+            memo: None,
+        };
+        assert!(transfer.has_valid_memo());
+        transfer.memo = Some("a".repeat(MAX_MEMO_LEN));
+        assert!(transfer.has_valid_memo());
+        transfer.memo = Some("a".repeat(MAX_MEMO_LEN + 1));
+        assert!(!transfer.has_valid_memo());
+
+        let mut disbursement = Disbursement {
+            source: established_address_1(),
+            token: established_address_1(),
+            entries: vec![DisbursementEntry {
+                target: established_address_2(),
+                amount: Amount::from(1),
+                memo: Some("a".repeat(MAX_MEMO_LEN)),
+            }],
+        };
+        assert!(disbursement.has_valid_memos());
+        disbursement.entries.push(DisbursementEntry {
+            target: established_address_2(),
+            amount: Amount::from(1),
+            memo: Some("a".repeat(MAX_MEMO_LEN + 1)),
+        });
+        assert!(!disbursement.has_valid_memos());
+    }
+
     #[test]
     fn test_token_display() {
         let max = Amount::from(u64::MAX);
@@ -392,4 +603,28 @@ mod tests {
         let zero = Amount::from(0);
         assert_eq!("0", zero.to_string());
     }
+
+    #[test]
+    fn test_vesting_schedule_locked_at() {
+        let schedule = VestingSchedule {
+            locked: Amount::whole(100),
+            start_epoch: Epoch(10),
+            epochs: 4,
+        };
+        assert_eq!(schedule.locked_at(Epoch(0)), Amount::whole(100));
+        assert_eq!(schedule.locked_at(Epoch(9)), Amount::whole(100));
+        assert_eq!(schedule.locked_at(Epoch(10)), Amount::whole(75));
+        assert_eq!(schedule.locked_at(Epoch(11)), Amount::whole(50));
+        assert_eq!(schedule.locked_at(Epoch(12)), Amount::whole(25));
+        assert_eq!(schedule.locked_at(Epoch(13)), Amount::default());
+        assert_eq!(schedule.locked_at(Epoch(100)), Amount::default());
+
+        // Everything is released in the start epoch when there are no epochs
+        let schedule = VestingSchedule {
+            epochs: 0,
+            ..schedule
+        };
+        assert_eq!(schedule.locked_at(Epoch(9)), Amount::whole(100));
+        assert_eq!(schedule.locked_at(Epoch(10)), Amount::default());
+    }
 }
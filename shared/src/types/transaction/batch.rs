@@ -0,0 +1,148 @@
+//! Types used for batches of operations that are applied atomically in a
+//! single transaction.
+//!
+//! A batch transaction's code is the [`BATCH_TX_CODE`] marker instead of WASM
+//! code and its data is a Borsh encoded [`TxBatch`]. The batch is signed as a
+//! whole. The operations are executed one after another on the same write
+//! log and the validity predicates are then checked once against all their
+//! changes, so that either all the operations are applied or none of them.
+
+use std::collections::BTreeSet;
+
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::proto::{SignedTxData, Tx};
+use crate::types::address::Address;
+use crate::types::key::common;
+use crate::types::storage;
+
+/// The code of a batch transaction. It is not a valid WASM module, so it
+/// cannot be confused with the code of a regular transaction.
+pub const BATCH_TX_CODE: &[u8] = b"anoma-tx-batch";
+
+/// A batch of operations applied atomically in a single transaction
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub struct TxBatch {
+    /// The operations, in the order in which they are executed
+    pub ops: Vec<BatchOp>,
+}
+
+/// An operation of a batch, i.e. a transaction code with its data
+#[derive(
+    Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
+pub struct BatchOp {
+    /// The WASM code of the operation
+    pub code: Vec<u8>,
+    /// The data of the operation, if any
+    pub data: Option<Vec<u8>>,
+}
+
+/// The result of an operation of an applied batch
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct BatchOpResult {
+    /// The gas used by the operation, not including the gas used by the VPs
+    pub gas_used: u64,
+    /// Storage keys modified by the operation
+    pub changed_keys: BTreeSet<storage::Key>,
+    /// New established addresses created by the operation
+    pub initialized_accounts: Vec<Address>,
+}
+
+impl TxBatch {
+    /// Check if a transaction is a batch
+    pub fn is_batch(tx: &Tx) -> bool {
+        tx.code == BATCH_TX_CODE
+    }
+
+    /// Build an unsigned batch transaction
+    pub fn to_tx(&self) -> Tx {
+        let data = self
+            .try_to_vec()
+            .expect("Encoding a tx batch shouldn't fail");
+        Tx::new(BATCH_TX_CODE.to_vec(), Some(data))
+    }
+}
+
+impl BatchOp {
+    /// Build the transaction that executes this operation within a signed
+    /// batch transaction. The operation's data are wrapped in
+    /// [`SignedTxData`] with the batch's signature, the same as the data of a
+    /// regular signed transaction, so that the transaction code can decode
+    /// them as usual.
+    pub fn to_tx(&self, batch: &Tx, sig: &common::Signature) -> Tx {
+        let data = SignedTxData {
+            data: self.data.clone(),
+            sig: sig.clone(),
+        }
+        .try_to_vec()
+        .expect("Encoding transaction data shouldn't fail");
+        Tx {
+            code: self.code.clone(),
+            data: Some(data),
+            timestamp: batch.timestamp,
+            expiration: batch.expiration,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::key::testing::keypair_1;
+
+    /// Test that the operations of a signed batch get the batch's signature
+    #[test]
+    fn test_batch_op_tx() {
+        let batch = TxBatch {
+            ops: vec![
+                BatchOp {
+                    code: b"op 1".to_vec(),
+                    data: Some(b"data 1".to_vec()),
+                },
+                BatchOp {
+                    code: b"op 2".to_vec(),
+                    data: None,
+                },
+            ],
+        };
+        let tx = batch.to_tx().sign(&keypair_1());
+        assert!(TxBatch::is_batch(&tx));
+
+        let signed =
+            SignedTxData::try_from_slice(tx.data.as_ref().unwrap()).unwrap();
+        let decoded =
+            TxBatch::try_from_slice(signed.data.as_ref().unwrap()).unwrap();
+        assert_eq!(decoded, batch);
+
+        for op in &decoded.ops {
+            let op_tx = op.to_tx(&tx, &signed.sig);
+            assert!(!TxBatch::is_batch(&op_tx));
+            assert_eq!(op_tx.code, op.code);
+            assert_eq!(op_tx.timestamp, tx.timestamp);
+            let op_data =
+                SignedTxData::try_from_slice(op_tx.data.as_ref().unwrap())
+                    .unwrap();
+            assert_eq!(op_data.data, op.data);
+            assert_eq!(op_data.sig, signed.sig);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 //! Types that are used in transactions.
 
+/// txs that apply a batch of operations atomically
+pub mod batch;
 /// txs that contain decrypted payloads or assertions of
 /// non-decryptability
 pub mod decrypted;
@@ -12,12 +14,15 @@ pub mod nft;
 pub mod pos;
 /// transaction protocols made by validators
 pub mod protocol;
+/// self-describing tx data
+pub mod schema;
 /// wrapper txs with encrypted payloads
 pub mod wrapper;
 
 use std::collections::BTreeSet;
 use std::fmt;
 
+use batch::BatchOpResult;
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 pub use decrypted::*;
 #[cfg(feature = "ferveo-tpke")]
@@ -33,6 +38,7 @@ use crate::ledger::gas::VpsGas;
 use crate::types::address::Address;
 use crate::types::hash::Hash;
 use crate::types::key::*;
+use crate::types::token;
 
 /// Get the hash of a transaction
 pub fn hash_tx(tx_bytes: &[u8]) -> Hash {
@@ -54,6 +60,9 @@ pub struct TxResult {
     pub initialized_accounts: Vec<Address>,
     /// Optional IBC event emitted by the transaction
     pub ibc_event: Option<IbcEvent>,
+    /// The results of the operations of a batch transaction, in order. Empty
+    /// for other transactions.
+    pub batch_results: Vec<BatchOpResult>,
 }
 
 impl TxResult {
@@ -199,6 +208,11 @@ pub struct InitValidator {
     pub validator_vp_code: Vec<u8>,
     /// The VP code for validator's staking reward account
     pub rewards_vp_code: Vec<u8>,
+    /// The initial commission rate in basis points (parts per ten thousand)
+    pub commission_rate: u64,
+    /// The source of the tokens for the validator's initial self-bond and
+    /// their amount, if any
+    pub self_bond: Option<(Address, token::Amount)>,
 }
 
 /// Module that includes helper functions for classifying
@@ -299,12 +313,14 @@ pub mod tx_types {
                 code: tx.code,
                 data: Some(data.clone()),
                 timestamp: tx.timestamp,
+                expiration: tx.expiration,
             }
             .hash();
             match TxType::try_from(Tx {
                 code: vec![],
                 data: Some(data),
                 timestamp: tx.timestamp,
+                expiration: tx.expiration,
             })
             .map_err(|err| TxError::Deserialization(err.to_string()))?
             {
@@ -2,7 +2,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 use crate::types::address::Address;
-use crate::types::nft::NftToken;
+use crate::types::nft::{NftToken, Royalty};
 
 /// A tx data type to create a new NFT
 #[derive(
@@ -27,6 +27,8 @@ pub struct CreateNft {
     pub opt_keys: Vec<String>,
     /// The nft tokens descriptions
     pub tokens: Vec<NftToken>,
+    /// The royalty paid out of the payments for the tokens
+    pub royalty: Option<Royalty>,
 }
 
 /// A tx data type to mint nft tokens
@@ -53,3 +53,68 @@ pub struct Withdraw {
     /// from self-bonds, the validator is also the source
     pub source: Option<Address>,
 }
+
+/// A claim of the rewards of a bond.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct ClaimRewards {
+    /// Validator address
+    pub validator: Address,
+    /// Source address for claiming the rewards of delegations. For claiming
+    /// the rewards of self-bonds, the validator is also the source
+    pub source: Option<Address>,
+}
+
+/// A change of the auto-compounding of the rewards of a bond.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct AutoCompound {
+    /// Validator address
+    pub validator: Address,
+    /// Source address for delegations. For self-bonds, the validator is
+    /// also the source
+    pub source: Option<Address>,
+    /// Whether the bond's rewards should be re-bonded to the validator at the
+    /// end of each epoch
+    pub auto_compound: bool,
+}
+
+/// A change of a validator's commission rate.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+    Hash,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub struct CommissionChange {
+    /// Validator address
+    pub validator: Address,
+    /// The new commission rate in basis points (parts per ten thousand)
+    pub new_rate: u64,
+}
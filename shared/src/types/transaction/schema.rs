@@ -0,0 +1,225 @@
+//! Self-describing data of transactions. The Borsh encoding of the data that
+//! a transaction's code receives is prefixed with the identifier of its
+//! schema, so that tools can find out what the data are and decode them
+//! without guessing from the transaction's code.
+//!
+//! Tagged data start with the [`MAGIC`] bytes, followed by the 32 bytes of
+//! the [`SchemaId`] and the Borsh encoding of the data. Data without the
+//! magic bytes are decoded as plain Borsh, as they were encoded before the
+//! schemas were introduced, e.g. by older clients or matchmakers.
+//!
+//! The registry of the schemas is this module rather than a crate of its own,
+//! because the registered types are defined in this crate, on which the
+//! transactions, the VPs and the tools already depend.
+
+use std::fmt::{self, Debug, Display};
+use std::io::{self, ErrorKind};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::types::address::Address;
+use crate::types::hash::Hash;
+use crate::types::intent::IntentTransfers;
+use crate::types::key::common;
+use crate::types::token;
+use crate::types::transaction::governance::{
+    InitProposalData, VoteProposalData,
+};
+use crate::types::transaction::nft::{CreateNft, MintNft};
+use crate::types::transaction::pos::{
+    AutoCompound, Bond, ClaimRewards, CommissionChange, Withdraw,
+};
+use crate::types::transaction::{InitAccount, InitValidator, UpdateVp};
+
+/// The bytes at the start of tagged data. No address, option or public key
+/// is encoded with the first byte, so untagged data that start with one of
+/// them can't be mistaken for tagged data.
+pub const MAGIC: [u8; 4] = [0xff, b'A', b'T', b'D'];
+
+/// The length of the prefix of tagged data
+const PREFIX_LEN: usize = MAGIC.len() + 32;
+
+/// The identifier of a schema, i.e. the SHA-256 hash of its name
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SchemaId(pub Hash);
+
+impl SchemaId {
+    /// The identifier of the schema with the given name
+    pub fn from_name(name: &str) -> Self {
+        Self(Hash::sha256(name))
+    }
+}
+
+impl Display for SchemaId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The data of transactions with a registered schema
+pub trait TxDataSchema: BorshSerialize + BorshDeserialize + Debug {
+    /// The unique name of the schema. It must be changed, e.g. by bumping its
+    /// version suffix, whenever the Borsh encoding of the type changes.
+    const NAME: &'static str;
+
+    /// The identifier of the schema
+    fn schema_id() -> SchemaId {
+        SchemaId::from_name(Self::NAME)
+    }
+}
+
+/// A schema in the [`REGISTRY`]
+#[derive(Clone, Copy)]
+pub struct Schema {
+    /// The name of the schema
+    pub name: &'static str,
+    /// Decode the Borsh encoded data of the schema, without the prefix, and
+    /// format them for display
+    pub describe: fn(&[u8]) -> io::Result<String>,
+}
+
+impl Debug for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Schema").field("name", &self.name).finish()
+    }
+}
+
+impl Schema {
+    /// The identifier of the schema
+    pub fn id(&self) -> SchemaId {
+        SchemaId::from_name(self.name)
+    }
+}
+
+macro_rules! tx_data_schemas {
+    ($($ty:ty => $name:literal),* $(,)?) => {
+        $(
+            impl TxDataSchema for $ty {
+                const NAME: &'static str = $name;
+            }
+        )*
+
+        /// The schemas of the data of the transactions' code in the
+        /// `wasm` directory
+        pub const REGISTRY: &[Schema] = &[
+            $(
+                Schema {
+                    name: $name,
+                    describe: describe::<$ty>,
+                },
+            )*
+        ];
+    };
+}
+
+tx_data_schemas! {
+    token::Transfer => "anoma/token/transfer/v1",
+    token::Disbursement => "anoma/token/disbursement/v1",
+    InitAccount => "anoma/init-account/v1",
+    UpdateVp => "anoma/update-vp/v1",
+    InitValidator => "anoma/pos/init-validator/v1",
+    Bond => "anoma/pos/bond/v1",
+    Withdraw => "anoma/pos/withdraw/v1",
+    ClaimRewards => "anoma/pos/claim-rewards/v1",
+    AutoCompound => "anoma/pos/auto-compound/v1",
+    CommissionChange => "anoma/pos/commission-change/v1",
+    CreateNft => "anoma/nft/create/v2",
+    MintNft => "anoma/nft/mint/v1",
+    InitProposalData => "anoma/governance/init-proposal/v1",
+    VoteProposalData => "anoma/governance/vote-proposal/v1",
+    IntentTransfers => "anoma/intent/transfers/v6",
+    common::PublicKey => "anoma/public-key/v1",
+    Address => "anoma/address/v1",
+}
+
+fn describe<T: TxDataSchema>(data: &[u8]) -> io::Result<String> {
+    T::try_from_slice(data).map(|data| format!("{:#?}", data))
+}
+
+/// Encode the data prefixed with the identifier of their schema
+pub fn encode<T: TxDataSchema>(data: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(PREFIX_LEN);
+    bytes.extend(MAGIC);
+    bytes.extend(T::schema_id().0 .0);
+    data.serialize(&mut bytes)
+        .expect("Encoding tx data shouldn't fail");
+    bytes
+}
+
+/// Decode data of the given schema. Untagged data are decoded as plain
+/// Borsh.
+pub fn decode<T: TxDataSchema>(bytes: &[u8]) -> io::Result<T> {
+    match split(bytes) {
+        Some((id, data)) if id == T::schema_id() => T::try_from_slice(data),
+        Some((id, _)) => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Expected data of the schema {} ({}), got the schema {}",
+                T::NAME,
+                T::schema_id(),
+                id
+            ),
+        )),
+        None => T::try_from_slice(bytes),
+    }
+}
+
+/// Split tagged data into the identifier of their schema and their Borsh
+/// encoding. Returns `None` for untagged data.
+pub fn split(bytes: &[u8]) -> Option<(SchemaId, &[u8])> {
+    if bytes.len() < PREFIX_LEN || !bytes.starts_with(&MAGIC) {
+        return None;
+    }
+    let id = Hash::try_from(&bytes[MAGIC.len()..PREFIX_LEN]).ok()?;
+    Some((SchemaId(id), &bytes[PREFIX_LEN..]))
+}
+
+/// Find the registered schema of tagged data
+pub fn identify(bytes: &[u8]) -> Option<(&'static Schema, &[u8])> {
+    let (id, data) = split(bytes)?;
+    let schema = REGISTRY.iter().find(|schema| schema.id() == id)?;
+    Some((schema, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::types::address::testing::established_address_1;
+
+    /// Test that tagged data are decoded and identified, and that untagged
+    /// data are still decoded
+    #[test]
+    fn test_tagged_data() {
+        let withdraw = Withdraw {
+            validator: established_address_1(),
+            source: None,
+        };
+        let bytes = encode(&withdraw);
+        assert_eq!(decode::<Withdraw>(&bytes).unwrap(), withdraw);
+
+        let (schema, data) = identify(&bytes).unwrap();
+        assert_eq!(schema.name, Withdraw::NAME);
+        assert_eq!(data, withdraw.try_to_vec().unwrap());
+        assert_eq!(
+            (schema.describe)(data).unwrap(),
+            format!("{:#?}", withdraw)
+        );
+
+        // Tagged data of another schema are rejected
+        assert!(decode::<ClaimRewards>(&bytes).is_err());
+
+        let untagged = withdraw.try_to_vec().unwrap();
+        assert!(split(&untagged).is_none());
+        assert_eq!(decode::<Withdraw>(&untagged).unwrap(), withdraw);
+    }
+
+    /// Test that the names of the registered schemas are unique
+    #[test]
+    fn test_unique_schema_names() {
+        let names: HashSet<_> =
+            REGISTRY.iter().map(|schema| schema.name).collect();
+        assert_eq!(names.len(), REGISTRY.len());
+    }
+}
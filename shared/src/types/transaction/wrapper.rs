@@ -15,6 +15,7 @@ pub mod wrapper_tx {
     use crate::types::address::Address;
     use crate::types::key::*;
     use crate::types::storage::Epoch;
+    use crate::types::time::DateTimeUtc;
     use crate::types::token::Amount;
     use crate::types::transaction::encrypted::EncryptedTx;
     use crate::types::transaction::{
@@ -190,11 +191,31 @@ pub mod wrapper_tx {
             gas_limit: GasLimit,
             tx: Tx,
             encryption_key: EncryptionKey,
+        ) -> WrapperTx {
+            Self::new_with_pk(
+                fee,
+                keypair.ref_to(),
+                epoch,
+                gas_limit,
+                tx,
+                encryption_key,
+            )
+        }
+
+        /// Create a new wrapper tx like [`WrapperTx::new`], given only the
+        /// public key of the keypair that will sign it
+        pub fn new_with_pk(
+            fee: Fee,
+            pk: common::PublicKey,
+            epoch: Epoch,
+            gas_limit: GasLimit,
+            tx: Tx,
+            encryption_key: EncryptionKey,
         ) -> WrapperTx {
             let inner_tx = EncryptedTx::encrypt(&tx.to_bytes(), encryption_key);
             Self {
                 fee,
-                pk: keypair.ref_to(),
+                pk,
                 epoch,
                 gas_limit,
                 inner_tx,
@@ -240,11 +261,35 @@ pub mod wrapper_tx {
         pub fn sign(
             &self,
             keypair: &common::SecretKey,
+        ) -> Result<Tx, WrapperTxErr> {
+            self.sign_with_expiration(keypair, None)
+        }
+
+        /// Sign the wrapper transaction and convert to a normal Tx type that
+        /// is no longer valid after the given expiration time, if any
+        pub fn sign_with_expiration(
+            &self,
+            keypair: &common::SecretKey,
+            expiration: Option<DateTimeUtc>,
         ) -> Result<Tx, WrapperTxErr> {
             if self.pk != keypair.ref_to() {
                 return Err(WrapperTxErr::InvalidKeyPair);
             }
-            Ok(Tx::new(
+            Ok(self.sign_with(expiration, |to_sign| {
+                common::SigScheme::sign(keypair, to_sign)
+            }))
+        }
+
+        /// Sign the wrapper transaction with the given signing function, e.g.
+        /// one that signs with a key held by another process, and convert to
+        /// a normal Tx type like [`WrapperTx::sign_with_expiration`]. The
+        /// function must sign with the key of the wrapper's public key.
+        pub fn sign_with(
+            &self,
+            expiration: Option<DateTimeUtc>,
+            sign: impl FnOnce(&[u8]) -> common::Signature,
+        ) -> Tx {
+            Tx::new(
                 vec![],
                 Some(
                     TxType::Wrapper(self.clone())
@@ -252,7 +297,8 @@ pub mod wrapper_tx {
                         .expect("Could not serialize WrapperTx"),
                 ),
             )
-            .sign(keypair))
+            .with_expiration(expiration)
+            .sign_with(sign)
         }
 
         /// Validate the signature of a wrapper tx
@@ -479,6 +525,87 @@ pub mod wrapper_tx {
                 .expect_err("Test failed");
             assert_matches!(err, TxError::SigError(_));
         }
+
+        /// We check that the expiration of a signed wrapper tx cannot be
+        /// changed without invalidating the signature.
+        #[test]
+        fn test_expiration_is_signed() {
+            let keypair = gen_keypair();
+            let tx = Tx::new(
+                "wasm code".as_bytes().to_owned(),
+                Some("transaction data".as_bytes().to_owned()),
+            );
+            let expiration = DateTimeUtc::now();
+            let mut tx = WrapperTx::new(
+                Fee {
+                    amount: 10.into(),
+                    token: xan(),
+                },
+                &keypair,
+                Epoch(0),
+                0.into(),
+                tx,
+                Default::default(),
+            )
+            .sign_with_expiration(&keypair, Some(expiration))
+            .expect("Test failed");
+            assert_eq!(tx.expiration, Some(expiration));
+            assert!(!tx.is_expired(expiration));
+            crate::types::transaction::process_tx(tx.clone())
+                .expect("Test failed");
+
+            // we try to remove the expiration
+            tx.expiration = None;
+            let err = crate::types::transaction::process_tx(tx)
+                .expect_err("Test failed");
+            assert_matches!(err, TxError::SigError(_));
+        }
+
+        /// Test that the wrapper can be signed by a fee payer other than the
+        /// signer of the inner tx, whose signature is kept in the inner tx
+        #[test]
+        fn test_fee_payer_distinct_from_signer() {
+            let signer = gen_keypair();
+            let fee_payer = gen_keypair();
+            let tx = Tx::new(
+                "wasm code".as_bytes().to_owned(),
+                Some("transaction data".as_bytes().to_owned()),
+            )
+            .sign(&signer);
+            let wrapper = WrapperTx::new(
+                Fee {
+                    amount: 10.into(),
+                    token: xan(),
+                },
+                &fee_payer,
+                Epoch(0),
+                0.into(),
+                tx.clone(),
+                Default::default(),
+            );
+            let fee_payer_pk: common::PublicKey = fee_payer.ref_to();
+            assert_eq!(wrapper.fee_payer(), Address::from(&fee_payer_pk));
+
+            // only the fee payer can sign the wrapper
+            assert_matches!(
+                wrapper.sign(&signer),
+                Err(WrapperTxErr::InvalidKeyPair)
+            );
+            let signed = wrapper.sign(&fee_payer).expect("Test failed");
+            crate::types::transaction::process_tx(signed).expect("Test failed");
+
+            // the inner tx is still signed by the signer
+            let privkey = <EllipticCurve as PairingEngine>::G2Affine::prime_subgroup_generator();
+            let decrypted = wrapper.decrypt(privkey).expect("Test failed");
+            assert_eq!(tx, decrypted);
+            let signed_data = SignedTxData::try_from_slice(
+                &decrypted.data.clone().expect("Test failed"),
+            )
+            .expect("Test failed");
+            decrypted
+                .verify_sig(&signer.ref_to(), &signed_data.sig)
+                .expect("Test failed");
+        }
     }
 }
 
@@ -17,11 +17,12 @@ use crate::ledger::storage::write_log::{self, WriteLog};
 use crate::ledger::storage::{self, Storage, StorageHasher};
 use crate::ledger::vp_env;
 use crate::proto::Tx;
-use crate::types::address::{self, Address};
+use crate::types::address::{self, Address, InternalAddress};
 use crate::types::ibc::IbcEvent;
 use crate::types::internal::HostEnvResult;
 use crate::types::key::*;
 use crate::types::storage::Key;
+use crate::types::token;
 use crate::vm::memory::VmMemory;
 use crate::vm::prefix_iter::{PrefixIteratorId, PrefixIterators};
 use crate::vm::types::KeyVal;
@@ -63,6 +64,8 @@ pub enum TxRuntimeError {
     NumConversionError(TryFromIntError),
     #[error("Memory error: {0}")]
     MemoryError(Box<dyn std::error::Error + Sync + Send + 'static>),
+    #[error("Invalid token transfer: {0}")]
+    InvalidTransfer(String),
 }
 
 type TxResult<T> = std::result::Result<T, TxRuntimeError>;
@@ -960,6 +963,182 @@ where
     tx_add_gas(env, gas)
 }
 
+/// Token transfer function exposed to the wasm VM Tx environment. The given
+/// amount is debited from the source's and credited to the destination's
+/// balance in the write log, the same way as the token transfer of the
+/// transaction WASMs. The balances of the IBC mint and burn addresses are
+/// written as temporary values.
+#[allow(clippy::too_many_arguments)]
+pub fn tx_token_transfer<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    src_ptr: u64,
+    src_len: u64,
+    dest_ptr: u64,
+    dest_len: u64,
+    token_ptr: u64,
+    token_len: u64,
+    amount: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let src = tx_read_address(env, src_ptr, src_len)?;
+    let dest = tx_read_address(env, dest_ptr, dest_len)?;
+    let token = tx_read_address(env, token_ptr, token_len)?;
+
+    tracing::debug!(
+        "tx_token_transfer {} of {} from {} to {}",
+        token::Amount::from(amount),
+        token,
+        src,
+        dest
+    );
+
+    if let Address::Internal(InternalAddress::IbcBurn) = &src {
+        return Err(TxRuntimeError::InvalidTransfer(
+            "transfer from the burn address".to_string(),
+        ));
+    }
+    if let Address::Internal(InternalAddress::IbcMint) = &dest {
+        return Err(TxRuntimeError::InvalidTransfer(
+            "transfer to the mint address".to_string(),
+        ));
+    }
+
+    let src_key = token::balance_key(&token, &src);
+    let src_balance: u64 = match tx_read_balance(env, &src_key)? {
+        Some(balance) => balance.into(),
+        // The mint address has an unlimited balance
+        None if src == Address::Internal(InternalAddress::IbcMint) => {
+            token::Amount::max().into()
+        }
+        None => 0,
+    };
+    let src_balance = src_balance.checked_sub(amount).ok_or_else(|| {
+        TxRuntimeError::InvalidTransfer(format!(
+            "the balance of {} is lower than {}",
+            src,
+            token::Amount::from(amount)
+        ))
+    })?;
+    tx_write_balance(env, &src_key, src_balance.into(), is_ibc_token(&src))?;
+
+    // The destination's balance is read after the source's has been written
+    // for a transfer to self
+    let dest_key = token::balance_key(&token, &dest);
+    let dest_balance: u64 = tx_read_balance(env, &dest_key)?
+        .map(u64::from)
+        .unwrap_or_default();
+    let dest_balance = dest_balance.checked_add(amount).ok_or_else(|| {
+        TxRuntimeError::InvalidTransfer(format!(
+            "the balance of {} overflows",
+            dest
+        ))
+    })?;
+    tx_write_balance(env, &dest_key, dest_balance.into(), is_ibc_token(&dest))
+}
+
+/// Read an address from the VM memory
+fn tx_read_address<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    addr_ptr: u64,
+    addr_len: u64,
+) -> TxResult<Address>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (addr, gas) = env
+        .memory
+        .read_string(addr_ptr, addr_len as _)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    tx_add_gas(env, gas)?;
+    Address::decode(&addr).map_err(TxRuntimeError::AddressError)
+}
+
+/// Read a token balance from the write log first and if no entry found then
+/// from the storage.
+fn tx_read_balance<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    key: &Key,
+) -> TxResult<Option<token::Amount>>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let (log_val, gas) = write_log.read(key);
+    tx_add_gas(env, gas)?;
+    let value = match log_val {
+        Some(
+            write_log::StorageModification::Write { value }
+            | write_log::StorageModification::Temp { value },
+        ) => Some(value.clone()),
+        Some(
+            write_log::StorageModification::Delete
+            | write_log::StorageModification::InitAccount { .. },
+        ) => None,
+        None => {
+            let storage = unsafe { env.ctx.storage.get() };
+            let (value, gas) =
+                storage.read(key).map_err(TxRuntimeError::StorageError)?;
+            tx_add_gas(env, gas)?;
+            value
+        }
+    };
+    value
+        .map(|value| {
+            token::Amount::try_from_slice(&value[..])
+                .map_err(TxRuntimeError::EncodingError)
+        })
+        .transpose()
+}
+
+/// Write a token balance to the write log. When `temp` is `true`, it's only
+/// written to the write log and it will be never written to the storage.
+fn tx_write_balance<MEM, DB, H, CA>(
+    env: &TxEnv<MEM, DB, H, CA>,
+    key: &Key,
+    balance: token::Amount,
+    temp: bool,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    DB: storage::DB + for<'iter> storage::DBIter<'iter>,
+    H: StorageHasher,
+    CA: WasmCacheAccess,
+{
+    check_address_existence(env, key)?;
+
+    let value = balance
+        .try_to_vec()
+        .map_err(TxRuntimeError::EncodingError)?;
+    let write_log = unsafe { env.ctx.write_log.get() };
+    let (gas, _size_diff) = if temp {
+        write_log.write_temp(key, value)
+    } else {
+        write_log.write(key, value)
+    }
+    .map_err(TxRuntimeError::StorageModificationError)?;
+    tx_add_gas(env, gas)
+    // TODO: charge the size diff
+}
+
+/// The IBC mint and burn addresses hold token balances only temporarily
+fn is_ibc_token(addr: &Address) -> bool {
+    matches!(
+        addr,
+        Address::Internal(InternalAddress::IbcMint | InternalAddress::IbcBurn)
+    )
+}
+
 /// Storage read prior state (before tx execution) function exposed to the wasm
 /// VM VP environment. It will try to read from the storage.
 ///
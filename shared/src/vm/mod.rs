@@ -5,7 +5,7 @@ use std::ffi::c_void;
 use std::marker::PhantomData;
 use std::slice;
 
-use wasmparser::{Validator, WasmFeatures};
+use wasmparser::{Parser, Payload, Validator, WasmFeatures};
 
 pub mod host_env;
 pub mod memory;
@@ -34,6 +34,32 @@ const UNTRUSTED_WASM_FEATURES: WasmFeatures = WasmFeatures {
     extended_const: false,
 };
 
+/// The name of the custom section of tx and VP WASM that holds the version of
+/// the host functions ABI that the WASM has been built against
+pub const WASM_ABI_VERSION_SECTION: &str = "anoma_abi_version";
+
+/// The version of the host functions ABI. It must be incremented on any
+/// change of the host functions that is incompatible with the WASM built
+/// against the previous version. The version is embedded in the WASM built
+/// with the `transaction` and `validity_predicate` macros as a little-endian
+/// `u32` in the [`WASM_ABI_VERSION_SECTION`] custom section.
+pub const WASM_ABI_VERSION: u32 = 1;
+
+/// The ABI version of tx and VP WASM built before the version has been
+/// embedded, i.e. the WASM without the [`WASM_ABI_VERSION_SECTION`]
+pub const LEGACY_WASM_ABI_VERSION: u32 = 0;
+
+/// The ABI versions of tx and VP WASM that can be run by the VM
+pub const SUPPORTED_WASM_ABI_VERSIONS: &[u32] = &[WASM_ABI_VERSION];
+
+/// The older ABI versions of tx and VP WASM that can still be run by the VM
+/// for a deprecation window. This allows a chain to upgrade its node software
+/// without recompiling every custom VP at the same time. A version is removed
+/// from here once its deprecation window is over. The host functions haven't
+/// changed since these versions, so their WASM is run with the current host
+/// functions.
+pub const DEPRECATED_WASM_ABI_VERSIONS: &[u32] = &[LEGACY_WASM_ABI_VERSION];
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum WasmValidationError {
@@ -42,6 +68,18 @@ pub enum WasmValidationError {
          {UNTRUSTED_WASM_FEATURES:?}"
     )]
     ForbiddenWasmFeatures(wasmparser::BinaryReaderError),
+    #[error("Malformed WASM: {0}")]
+    MalformedWasm(wasmparser::BinaryReaderError),
+    #[error(
+        "Invalid WASM ABI version section, expected 4 bytes, got {0} bytes"
+    )]
+    InvalidAbiVersion(usize),
+    #[error(
+        "Unsupported WASM ABI version {0}. Supported ABI versions: \
+         {SUPPORTED_WASM_ABI_VERSIONS:?}, deprecated ABI versions: \
+         {DEPRECATED_WASM_ABI_VERSIONS:?}"
+    )]
+    UnsupportedAbiVersion(u32),
 }
 
 /// WASM Cache access level, used to limit dry-ran transactions to read-only
@@ -229,7 +267,9 @@ impl<'a, T: 'a> MutHostSlice<'a, &[T]> {
 }
 
 /// Validate an untrusted wasm code with restrictions that we place such code
-/// (e.g. transaction and validity predicates)
+/// (e.g. transaction and validity predicates). The code must have been built
+/// against one of the [`SUPPORTED_WASM_ABI_VERSIONS`] or
+/// [`DEPRECATED_WASM_ABI_VERSIONS`].
 pub fn validate_untrusted_wasm(
     wasm_code: impl AsRef<[u8]>,
 ) -> Result<(), WasmValidationError> {
@@ -237,5 +277,43 @@ pub fn validate_untrusted_wasm(
     validator.wasm_features(UNTRUSTED_WASM_FEATURES);
     validator
         .validate_all(wasm_code.as_ref())
-        .map_err(WasmValidationError::ForbiddenWasmFeatures)
+        .map_err(WasmValidationError::ForbiddenWasmFeatures)?;
+
+    let version =
+        wasm_abi_version(wasm_code)?.unwrap_or(LEGACY_WASM_ABI_VERSION);
+    if SUPPORTED_WASM_ABI_VERSIONS.contains(&version)
+        || DEPRECATED_WASM_ABI_VERSIONS.contains(&version)
+    {
+        Ok(())
+    } else {
+        Err(WasmValidationError::UnsupportedAbiVersion(version))
+    }
+}
+
+/// Read the host functions ABI version embedded in a wasm code, if any. The
+/// version is not checked against the supported versions.
+pub fn wasm_abi_version(
+    wasm_code: impl AsRef<[u8]>,
+) -> Result<Option<u32>, WasmValidationError> {
+    // Sections with the same name are concatenated, the same as the linker
+    // does, so a duplicate section is invalid
+    let mut section: Option<Vec<u8>> = None;
+    for payload in Parser::new(0).parse_all(wasm_code.as_ref()) {
+        if let Payload::CustomSection { name, data, .. } =
+            payload.map_err(WasmValidationError::MalformedWasm)?
+        {
+            if name == WASM_ABI_VERSION_SECTION {
+                section.get_or_insert_with(Vec::new).extend_from_slice(data);
+            }
+        }
+    }
+    section
+        .map(|bytes| {
+            <[u8; 4]>::try_from(bytes.as_slice())
+                .map(u32::from_le_bytes)
+                .map_err(|_| {
+                    WasmValidationError::InvalidAbiVersion(bytes.len())
+                })
+        })
+        .transpose()
 }
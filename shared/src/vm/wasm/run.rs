@@ -406,7 +406,10 @@ mod tests {
     use super::*;
     use crate::ledger::storage::testing::TestStorage;
     use crate::types::validity_predicate::EvalVp;
-    use crate::vm::wasm;
+    use crate::vm::{
+        wasm, wasm_abi_version, DEPRECATED_WASM_ABI_VERSIONS,
+        LEGACY_WASM_ABI_VERSION, WASM_ABI_VERSION, WASM_ABI_VERSION_SECTION,
+    };
 
     const TX_MEMORY_LIMIT_WASM: &str = "../wasm_for_tests/tx_memory_limit.wasm";
     const TX_NO_OP_WASM: &str = "../wasm_for_tests/tx_no_op.wasm";
@@ -883,6 +886,104 @@ mod tests {
         assert!(!passed);
     }
 
+    /// Test that a transaction wasm with an unsupported ABI version is
+    /// rejected before it's run, while a legacy wasm without the ABI version
+    /// still runs with the deprecated ABI version.
+    #[test]
+    fn test_tx_abi_version() {
+        let tx_no_op = std::fs::read(TX_NO_OP_WASM).expect("cannot load wasm");
+        assert_eq!(
+            wasm_abi_version(&tx_no_op).unwrap(),
+            Some(WASM_ABI_VERSION)
+        );
+        validate_untrusted_wasm(&tx_no_op).unwrap();
+
+        let tx_code = wasmer::wat2wasm(
+            br#"
+            (module
+                (type (;0;) (func (param i64 i64)))
+                (func $_apply_tx (type 0) (param i64 i64))
+                (memory (;0;) 16)
+                (export "memory" (memory 0))
+                (export "_apply_tx" (func $_apply_tx)))
+            "#,
+        )
+        .expect("unexpected error converting wat2wasm")
+        .into_owned();
+        assert_eq!(wasm_abi_version(&tx_code).unwrap(), None);
+        assert!(DEPRECATED_WASM_ABI_VERSIONS.contains(&LEGACY_WASM_ABI_VERSION));
+        let result = run_tx_code(tx_code.clone());
+        assert!(result.is_ok(), "Expected success. Got {:?}", result);
+
+        let legacy = with_abi_version(tx_code.clone(), LEGACY_WASM_ABI_VERSION);
+        let result = run_tx_code(legacy);
+        assert!(result.is_ok(), "Expected success. Got {:?}", result);
+
+        let unsupported_version = WASM_ABI_VERSION + 1;
+        let unsupported =
+            with_abi_version(tx_code.clone(), unsupported_version);
+        let result = run_tx_code(unsupported);
+        assert!(
+            matches!(
+                result,
+                Err(Error::ValidationError(
+                    WasmValidationError::UnsupportedAbiVersion(version)
+                )) if version == unsupported_version
+            ),
+            "Expected an unsupported ABI version, got {:?}",
+            result
+        );
+
+        // A duplicate section is invalid
+        let duplicate = with_abi_version(
+            with_abi_version(tx_code.clone(), WASM_ABI_VERSION),
+            WASM_ABI_VERSION,
+        );
+        assert!(matches!(
+            wasm_abi_version(&duplicate),
+            Err(WasmValidationError::InvalidAbiVersion(8))
+        ));
+
+        let supported = with_abi_version(tx_code, WASM_ABI_VERSION);
+        let result = run_tx_code(supported);
+        assert!(result.is_ok(), "Expected success. Got {:?}", result);
+    }
+
+    fn run_tx_code(tx_code: Vec<u8>) -> Result<BTreeSet<Address>> {
+        let tx_data = vec![];
+        let storage = TestStorage::default();
+        let mut write_log = WriteLog::default();
+        let mut gas_meter = BlockGasMeter::default();
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let (mut tx_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        tx(
+            &storage,
+            &mut write_log,
+            &mut gas_meter,
+            tx_code,
+            tx_data,
+            &mut vp_cache,
+            &mut tx_cache,
+        )
+    }
+
+    /// Append a custom section with the host functions ABI version to a wasm
+    /// code, as the `transaction` and `validity_predicate` macros do
+    fn with_abi_version(mut code: Vec<u8>, version: u32) -> Vec<u8> {
+        let name = WASM_ABI_VERSION_SECTION.as_bytes();
+        let mut section = vec![name.len() as u8];
+        section.extend_from_slice(name);
+        section.extend_from_slice(&version.to_le_bytes());
+        // The custom section's ID followed by its size, both short enough
+        // to be encoded in a single LEB128 byte
+        code.push(0);
+        code.push(section.len() as u8);
+        code.extend(section);
+        code
+    }
+
     fn loop_in_tx_wasm(loops: u32) -> Result<BTreeSet<Address>> {
         // A transaction with a recursive loop.
         // The boilerplate code is generated from tx_template.wasm using
@@ -917,24 +1018,9 @@ mod tests {
         )
         .expect("unexpected error converting wat2wasm")
         .into_owned();
+        let tx_code = with_abi_version(tx_code, WASM_ABI_VERSION);
 
-        let tx_data = vec![];
-        let storage = TestStorage::default();
-        let mut write_log = WriteLog::default();
-        let mut gas_meter = BlockGasMeter::default();
-        let (mut vp_cache, _) =
-            wasm::compilation_cache::common::testing::cache();
-        let (mut tx_cache, _) =
-            wasm::compilation_cache::common::testing::cache();
-        tx(
-            &storage,
-            &mut write_log,
-            &mut gas_meter,
-            tx_code,
-            tx_data,
-            &mut vp_cache,
-            &mut tx_cache,
-        )
+        run_tx_code(tx_code)
     }
 
     fn loop_in_vp_wasm(loops: u32) -> Result<bool> {
@@ -965,6 +1051,7 @@ mod tests {
             "#, loops).as_bytes(),
         )
         .expect("unexpected error converting wat2wasm").into_owned();
+        let vp_code = with_abi_version(vp_code, WASM_ABI_VERSION);
 
         let tx = Tx::new(vec![], None);
         let mut storage = TestStorage::default();
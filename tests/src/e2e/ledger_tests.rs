@@ -441,6 +441,7 @@ fn invalid_transactions() -> Result<()> {
         target: find_address(&test, ALBERT)?,
         token: find_address(&test, XAN)?,
         amount: token::Amount::whole(1),
+        memo: None,
     };
     let data = transfer
         .try_to_vec()
@@ -103,12 +103,14 @@
 mod tests {
 
     use anoma::ledger::pos::anoma_proof_of_stake::PosBase;
-    use anoma::ledger::pos::PosParams;
+    use anoma::ledger::pos::{self, BondId, GenesisValidator, PosParams};
+    use anoma::types::key::RefTo;
     use anoma::types::storage::Epoch;
-    use anoma::types::token;
+    use anoma::types::{address, key, token};
     use anoma_vm_env::proof_of_stake::parameters::testing::arb_pos_params;
-    use anoma_vm_env::proof_of_stake::{staking_token_address, PosVP};
-    use anoma_vm_env::tx_prelude::Address;
+    use anoma_vm_env::proof_of_stake::{self, staking_token_address, PosVP};
+    use anoma_vm_env::tx_prelude::{self, Address};
+    use borsh::BorshSerialize;
     use proptest::prelude::*;
     use proptest::prop_state_machine;
     use proptest::state_machine::{AbstractStateMachine, StateMachineTest};
@@ -524,6 +526,245 @@ mod tests {
                 })
         }
     }
+
+    /// Initialize the tx env with a genesis validator and a delegator with
+    /// some staking tokens
+    fn init_rewards_test(params: &PosParams) -> (Address, Address) {
+        let mut tx_env = TestTxEnv::default();
+        let validator = address::testing::established_address_1();
+        let delegator = address::testing::established_address_3();
+        let genesis_validator = GenesisValidator {
+            address: validator.clone(),
+            staking_reward_address: address::testing::established_address_2(),
+            tokens: token::Amount::from(params.min_validator_self_bond),
+            consensus_key: key::testing::keypair_1().ref_to(),
+            staking_reward_key: key::testing::keypair_2().ref_to(),
+        };
+        tx_env
+            .storage
+            .init_genesis(params, [genesis_validator].iter(), Epoch(0))
+            .unwrap();
+        tx_env.spawn_accounts([&delegator, &staking_token_address()]);
+        tx_env.credit_tokens(
+            &delegator,
+            &staking_token_address(),
+            token::Amount::from(params.min_validator_self_bond),
+        );
+        tx_host_env::set(tx_env);
+        (validator, delegator)
+    }
+
+    /// Test that the rewards of a delegation with auto-compounding are
+    /// re-bonded to the validator at the end of the epoch, while the rewards
+    /// of the validator's self-bond still accumulate.
+    #[test]
+    fn test_auto_compound_rewards() {
+        let params = PosParams::default();
+        let (validator, delegator) = init_rewards_test(&params);
+        let amount = token::Amount::from(params.min_validator_self_bond);
+        let delegation = BondId {
+            source: delegator.clone(),
+            validator: validator.clone(),
+        };
+
+        // Delegate with auto-compounding in a single tx
+        proof_of_stake::bond_tokens(Some(&delegator), &validator, amount)
+            .unwrap();
+        proof_of_stake::set_auto_compound(Some(&delegator), &validator, true)
+            .unwrap();
+        let vp_env = TestNativeVpEnv::new(tx_host_env::take());
+        let result = vp_env.validate_tx(PosVP::new, |_tx_data| {});
+        assert!(result.expect("Validation of valid changes must not fail!"));
+        let mut tx_env = vp_env.tx_env;
+        tx_env.commit_tx_and_block();
+
+        // Accrue a block reward in the epoch in which the delegation is
+        // active and distribute it
+        let epoch = Epoch(params.pipeline_len);
+        let storage = &mut tx_env.storage;
+        storage.record_block_rewards(Some(&validator), std::iter::empty());
+        storage.distribute_rewards(epoch);
+
+        assert_eq!(storage.read_bond_rewards(&delegation), None);
+        let self_bond = BondId {
+            source: validator.clone(),
+            validator: validator.clone(),
+        };
+        let self_bond_rewards = storage.read_bond_rewards(&self_bond);
+        assert!(self_bond_rewards.is_some());
+        // The delegation's share of the reward is bonded at the pipeline
+        // offset from the next epoch
+        let rewards = token::Amount::from(params.block_proposer_reward)
+            - self_bond_rewards.unwrap();
+        let bond = storage.read_bond(&delegation).unwrap();
+        let pipeline_epoch = epoch + 1_u64 + params.pipeline_len;
+        assert_eq!(bond.get(epoch).unwrap().sum(), amount);
+        assert_eq!(bond.get(pipeline_epoch).unwrap().sum(), amount + rewards);
+    }
+
+    /// Test that the rewards of a delegation can be claimed by the delegator,
+    /// but cannot be credited to a third party.
+    #[test]
+    fn test_claim_rewards() {
+        let params = PosParams::default();
+        let (validator, delegator) = init_rewards_test(&params);
+        let amount = token::Amount::from(params.min_validator_self_bond);
+        let delegation = BondId {
+            source: delegator.clone(),
+            validator: validator.clone(),
+        };
+        proof_of_stake::bond_tokens(Some(&delegator), &validator, amount)
+            .unwrap();
+        let mut tx_env = tx_host_env::take();
+        tx_env.commit_tx_and_block();
+
+        // Accrue a block reward in the epoch in which the delegation is
+        // active and distribute it
+        let epoch = Epoch(params.pipeline_len);
+        tx_env
+            .storage
+            .record_block_rewards(Some(&validator), std::iter::empty());
+        tx_env.storage.distribute_rewards(epoch);
+        let rewards = tx_env.storage.read_bond_rewards(&delegation).unwrap();
+        let thief = address::testing::established_address_4();
+        tx_env.spawn_accounts([&thief]);
+        tx_host_env::set(tx_env);
+
+        // The delegator can claim the rewards
+        proof_of_stake::claim_rewards(Some(&delegator), &validator).unwrap();
+        let vp_env = TestNativeVpEnv::new(tx_host_env::take());
+        let result = vp_env.validate_tx(PosVP::new, |_tx_data| {});
+        assert!(result.expect("Validation of valid changes must not fail!"));
+
+        // The rewards cannot be claimed to a third party's account
+        let mut tx_env = vp_env.tx_env;
+        tx_env.write_log.drop_tx();
+        tx_host_env::set(tx_env);
+        tx_host_env::with(|env| {
+            env.write_log
+                .delete(&pos::bond_rewards_key(&delegation))
+                .unwrap();
+        });
+        tx_prelude::token::transfer(
+            &pos::ADDRESS,
+            &thief,
+            &staking_token_address(),
+            rewards,
+        );
+        let vp_env = TestNativeVpEnv::new(tx_host_env::take());
+        let result = vp_env.validate_tx(PosVP::new, |_tx_data| {});
+        assert!(!result.expect("Validation of invalid changes must not fail!"));
+    }
+
+    /// Test that the auto-compounding flag cannot be set for a bond that
+    /// doesn't exist.
+    #[test]
+    fn test_auto_compound_without_bond() {
+        let params = PosParams::default();
+        let (validator, delegator) = init_rewards_test(&params);
+        let key = pos::bond_auto_compound_key(&BondId {
+            source: delegator,
+            validator,
+        });
+        tx_host_env::with(|env| {
+            env.write_log
+                .write(&key, true.try_to_vec().unwrap())
+                .unwrap();
+        });
+        let vp_env = TestNativeVpEnv::new(tx_host_env::take());
+        let result = vp_env.validate_tx(PosVP::new, |_tx_data| {});
+        assert!(!result.expect("Validation of invalid changes must not fail!"));
+    }
+
+    /// Test that a delegator can unbond from a jailed validator.
+    #[test]
+    fn test_unbond_from_jailed_validator() {
+        let params = PosParams::default();
+        let (validator, delegator) = init_rewards_test(&params);
+        let amount = token::Amount::from(params.min_validator_self_bond);
+        proof_of_stake::bond_tokens(Some(&delegator), &validator, amount)
+            .unwrap();
+        let mut tx_env = tx_host_env::take();
+        tx_env.commit_tx_and_block();
+
+        // Jail the validator, which removes it from the validator set
+        assert!(tx_env.storage.jail_validator(&params, Epoch(0), &validator));
+        tx_host_env::set(tx_env);
+
+        proof_of_stake::unbond_tokens(Some(&delegator), &validator, amount)
+            .unwrap();
+        let vp_env = TestNativeVpEnv::new(tx_host_env::take());
+        let result = vp_env.validate_tx(PosVP::new, |_tx_data| {});
+        assert!(result.expect("Validation of valid changes must not fail!"));
+    }
+
+    /// Test that a validator's self-bond cannot be partially unbonded below
+    /// the minimum self-bond.
+    #[test]
+    fn test_self_bond_below_minimum() {
+        let params = PosParams::default();
+        let (validator, _delegator) = init_rewards_test(&params);
+
+        // Relax the minimum self-bond for the tx, so that the unbond is only
+        // checked by the VP
+        tx_host_env::with(|env| {
+            env.storage.write_pos_params(&PosParams {
+                min_validator_self_bond: 0,
+                ..params.clone()
+            })
+        });
+        proof_of_stake::unbond_tokens(None, &validator, token::Amount::from(1))
+            .unwrap();
+        tx_host_env::with(|env| env.storage.write_pos_params(&params));
+
+        let vp_env = TestNativeVpEnv::new(tx_host_env::take());
+        let result = vp_env.validate_tx(PosVP::new, |_tx_data| {});
+        assert!(!result.expect("Validation of invalid changes must not fail!"));
+    }
+
+    /// Test that the delegations to a validator cannot exceed the delegation
+    /// cap.
+    #[test]
+    fn test_delegation_cap() {
+        let max_delegations = 1_000_000;
+        let params = PosParams {
+            max_validator_delegations: Some(max_delegations),
+            ..PosParams::default()
+        };
+
+        // Delegating up to the cap is valid
+        let (validator, delegator) = init_rewards_test(&params);
+        proof_of_stake::bond_tokens(
+            Some(&delegator),
+            &validator,
+            token::Amount::from(max_delegations),
+        )
+        .unwrap();
+        let vp_env = TestNativeVpEnv::new(tx_host_env::take());
+        let result = vp_env.validate_tx(PosVP::new, |_tx_data| {});
+        assert!(result.expect("Validation of valid changes must not fail!"));
+
+        // Relax the cap for the tx, so that the delegation over the cap is
+        // only checked by the VP
+        let (validator, delegator) = init_rewards_test(&params);
+        tx_host_env::with(|env| {
+            env.storage.write_pos_params(&PosParams {
+                max_validator_delegations: None,
+                ..params.clone()
+            })
+        });
+        proof_of_stake::bond_tokens(
+            Some(&delegator),
+            &validator,
+            token::Amount::from(max_delegations + 1),
+        )
+        .unwrap();
+        tx_host_env::with(|env| env.storage.write_pos_params(&params));
+
+        let vp_env = TestNativeVpEnv::new(tx_host_env::take());
+        let result = vp_env.validate_tx(PosVP::new, |_tx_data| {});
+        assert!(!result.expect("Validation of invalid changes must not fail!"));
+    }
 }
 
 /// Testing helpers
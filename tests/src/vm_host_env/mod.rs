@@ -194,6 +194,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tx_token_transfer() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let src = address::testing::established_address_1();
+        let dest = address::testing::established_address_2();
+        let token = address::xan();
+        tx_host_env::with(|env| {
+            env.spawn_accounts([&src, &dest, &token]);
+            env.credit_tokens(&src, &token, Amount::whole(100));
+        });
+
+        tx_host_env::token_transfer(&src, &dest, &token, Amount::whole(40));
+
+        let src_key = token::balance_key(&token, &src).to_string();
+        let dest_key = token::balance_key(&token, &dest).to_string();
+        assert_eq!(
+            tx_host_env::read::<Amount>(&src_key),
+            Some(Amount::whole(60)),
+            "The amount should have been debited from the source"
+        );
+        assert_eq!(
+            tx_host_env::read::<Amount>(&dest_key),
+            Some(Amount::whole(40)),
+            "The amount should have been credited to the destination"
+        );
+
+        // A transfer to self doesn't change the balance
+        tx_host_env::token_transfer(&src, &src, &token, Amount::whole(60));
+        assert_eq!(
+            tx_host_env::read::<Amount>(&src_key),
+            Some(Amount::whole(60))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tx_token_transfer_insufficient_balance() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let src = address::testing::established_address_1();
+        let dest = address::testing::established_address_2();
+        let token = address::xan();
+        tx_host_env::with(|env| {
+            env.spawn_accounts([&src, &dest, &token]);
+            env.credit_tokens(&src, &token, Amount::whole(100));
+        });
+
+        tx_host_env::token_transfer(&src, &dest, &token, Amount::whole(101));
+    }
+
     #[test]
     #[should_panic]
     fn test_tx_init_account_with_invalid_vp() {
@@ -499,6 +552,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // get and increment the connection counter
@@ -536,6 +590,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
 
@@ -573,6 +628,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // get and update the client without a header
@@ -617,6 +673,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // update the client with the message
@@ -650,6 +707,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // upgrade the client with the message
@@ -692,6 +750,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // get and increment the connection counter
@@ -729,6 +788,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // init a connection with the message
@@ -759,6 +819,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // open the connection with the message
@@ -799,6 +860,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // open try a connection with the message
@@ -830,6 +892,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // open the connection with the mssage
@@ -875,6 +938,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // not bind a port
@@ -919,6 +983,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // bind a port
@@ -963,6 +1028,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // init a channel with the message
@@ -991,6 +1057,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // open the channle with the message
@@ -1033,6 +1100,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // try open a channel with the message
@@ -1062,6 +1130,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // open a channel with the message
@@ -1106,6 +1175,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // close the channel with the message
@@ -1150,6 +1220,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
 
@@ -1199,6 +1270,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // send the token and a packet with the data
@@ -1243,6 +1315,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // ack the packet with the message
@@ -1289,6 +1362,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // send the token and a packet with the data
@@ -1351,6 +1425,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // receive a packet with the message
@@ -1433,6 +1508,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // receive a packet with the message
@@ -1488,6 +1564,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // send a packet with the message
@@ -1521,6 +1598,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // ack the packet with the message
@@ -1576,6 +1654,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
         // receive a packet with the message
@@ -1642,6 +1721,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
 
@@ -1719,6 +1799,7 @@ mod tests {
             code: vec![],
             data: Some(tx_data.clone()),
             timestamp: DateTimeUtc::now(),
+            expiration: None,
         }
         .sign(&key::testing::keypair_1());
 
@@ -340,6 +340,15 @@ mod native_tx_host_env {
         result_ptr: u64
     ));
     native_host_fn!(tx_emit_ibc_event(event_ptr: u64, event_len: u64));
+    native_host_fn!(tx_token_transfer(
+        src_ptr: u64,
+        src_len: u64,
+        dest_ptr: u64,
+        dest_len: u64,
+        token_ptr: u64,
+        token_len: u64,
+        amount: u64
+    ));
     native_host_fn!(tx_get_chain_id(result_ptr: u64));
     native_host_fn!(tx_get_block_height() -> u64);
     native_host_fn!(tx_get_block_time() -> i64);
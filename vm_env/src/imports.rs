@@ -54,7 +54,6 @@ pub mod tx {
     use std::convert::TryFrom;
     use std::marker::PhantomData;
 
-    use anoma::types::address;
     use anoma::types::address::Address;
     use anoma::types::chain::CHAIN_ID_LENGTH;
     use anoma::types::ibc::IbcEvent;
@@ -63,6 +62,7 @@ pub mod tx {
         BlockHash, BlockHeight, Epoch, BLOCK_HASH_LENGTH,
     };
     use anoma::types::time::Rfc3339String;
+    use anoma::types::{address, token};
     pub use borsh::{BorshDeserialize, BorshSerialize};
 
     #[derive(Debug)]
@@ -221,6 +221,32 @@ pub mod tx {
         };
     }
 
+    /// Transfer the amount of the token from the source to the destination.
+    /// Same as [`crate::token::tx::transfer`], but the balances are updated
+    /// natively by the host. The transaction aborts if the source's balance
+    /// is too low.
+    pub fn token_transfer(
+        src: &Address,
+        dest: &Address,
+        token: &Address,
+        amount: token::Amount,
+    ) {
+        let src = src.encode();
+        let dest = dest.encode();
+        let token = token.encode();
+        unsafe {
+            anoma_tx_token_transfer(
+                src.as_ptr() as _,
+                src.len() as _,
+                dest.as_ptr() as _,
+                dest.len() as _,
+                token.as_ptr() as _,
+                token.len() as _,
+                amount.into(),
+            )
+        };
+    }
+
     /// Get the chain ID
     pub fn get_chain_id() -> String {
         let result = Vec::with_capacity(CHAIN_ID_LENGTH);
@@ -335,6 +361,17 @@ pub mod tx {
         // Emit an IBC event
         fn anoma_tx_emit_ibc_event(event_ptr: u64, event_len: u64);
 
+        // Transfer tokens between two accounts
+        fn anoma_tx_token_transfer(
+            src_ptr: u64,
+            src_len: u64,
+            dest_ptr: u64,
+            dest_len: u64,
+            token_ptr: u64,
+            token_len: u64,
+            amount: u64,
+        );
+
         // Get the chain ID
         fn anoma_tx_get_chain_id(result_ptr: u64);
 
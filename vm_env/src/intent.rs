@@ -1,8 +1,8 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use anoma::proto::Signed;
-use anoma::types::intent;
 use anoma::types::key::*;
+use anoma::types::{intent, token};
 
 /// Tx imports and functions.
 pub mod tx {
@@ -17,6 +17,23 @@ pub mod tx {
         invalid_intent.insert(intent.sig.clone());
         tx::write(&key.to_string(), &invalid_intent)
     }
+
+    /// Add the amount sold by a part of an exchange filled in parts to its
+    /// filled amount. The exchange is invalidated once its whole `max_sell`
+    /// has been sold.
+    pub fn fill_exchange(intent: &Signed<Exchange>, amount: token::Amount) {
+        use crate::imports::tx;
+        let key = intent::filled_intent_key(&intent.data.addr);
+        let mut filled_intent: HashMap<common::Signature, token::Amount> =
+            tx::read(&key.to_string()).unwrap_or_default();
+        let filled = filled_intent.entry(intent.sig.clone()).or_default();
+        *filled += amount;
+        let is_filled = *filled >= intent.data.max_sell;
+        tx::write(&key.to_string(), &filled_intent);
+        if is_filled {
+            invalidate_exchange(intent)
+        }
+    }
 }
 
 /// Vp imports and functions.
@@ -36,4 +53,30 @@ pub mod vp {
         !invalid_intent_pre.contains(&intent.sig)
             && invalid_intent_post.contains(&intent.sig)
     }
+
+    /// Check that the amount sold by a part of an exchange filled in parts
+    /// has been added to its filled amount, which must not exceed its
+    /// `max_sell`, and that the other filled amounts are unchanged.
+    pub fn vp_partial_exchange(
+        intent: &Signed<Exchange>,
+        amount: token::Amount,
+    ) -> bool {
+        use crate::imports::vp;
+        let key = intent::invalid_intent_key(&intent.data.addr);
+        let invalid_intent_pre: HashSet<common::Signature> =
+            vp::read_pre(&key.to_string()).unwrap_or_default();
+        if invalid_intent_pre.contains(&intent.sig) {
+            return false;
+        }
+
+        let key = intent::filled_intent_key(&intent.data.addr);
+        let mut filled_intent_pre: HashMap<common::Signature, token::Amount> =
+            vp::read_pre(&key.to_string()).unwrap_or_default();
+        let filled_intent_post: HashMap<common::Signature, token::Amount> =
+            vp::read_post(&key.to_string()).unwrap_or_default();
+        let filled = filled_intent_pre.entry(intent.sig.clone()).or_default();
+        *filled += amount;
+        *filled <= intent.data.max_sell
+            && filled_intent_pre == filled_intent_post
+    }
 }
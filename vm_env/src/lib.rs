@@ -24,6 +24,7 @@ pub mod tx_prelude {
     pub use anoma::types::address::Address;
     pub use anoma::types::storage::Key;
     pub use anoma::types::*;
+    pub use anoma::vm::WASM_ABI_VERSION;
     pub use anoma_macros::transaction;
 
     pub use crate::governance::tx as governance;
@@ -45,6 +46,7 @@ pub mod vp_prelude {
     pub use anoma::types::address::Address;
     pub use anoma::types::storage::Key;
     pub use anoma::types::*;
+    pub use anoma::vm::WASM_ABI_VERSION;
     pub use anoma_macros::validity_predicate;
 
     pub use crate::imports::vp::*;
@@ -27,6 +27,12 @@ pub mod tx {
         let optional_keys_key = nft::get_optional_keys_key(&address);
         tx::write(&optional_keys_key.to_string(), nft.opt_keys);
 
+        // write royalty
+        if let Some(royalty) = &nft.royalty {
+            let royalty_key = nft::get_royalty_key(&address);
+            tx::write(&royalty_key.to_string(), royalty);
+        }
+
         // mint tokens
         aux_mint_token(&address, &nft.creator, nft.tokens, &nft.creator);
 
@@ -39,6 +45,34 @@ pub mod tx {
         aux_mint_token(&nft.address, &nft.creator, nft.tokens, &nft.creator);
     }
 
+    /// Transfer a token from its current owner, who must authorize it in
+    /// their VP, to a new owner. The previous owner is added to the token's
+    /// past owners.
+    pub fn transfer_token(
+        nft_address: &Address,
+        token_id: &str,
+        source: &Address,
+        target: &Address,
+    ) {
+        let past_owners_key =
+            nft::get_token_past_owners_key(nft_address, token_id).to_string();
+        let mut past_owners: Vec<Address> =
+            tx::read(&past_owners_key).unwrap_or_default();
+        past_owners.push(source.clone());
+        tx::write(&past_owners_key, past_owners);
+
+        let current_owner_key =
+            nft::get_token_current_owner_key(nft_address, token_id);
+        tx::write(&current_owner_key.to_string(), target);
+
+        tx::insert_verifier(source);
+    }
+
+    /// Read the royalty of an NFT, if any
+    pub fn read_royalty(nft_address: &Address) -> Option<nft::Royalty> {
+        tx::read(&nft::get_royalty_key(nft_address).to_string())
+    }
+
     fn aux_mint_token(
         nft_address: &Address,
         creator_address: &Address,
@@ -91,11 +125,12 @@ pub mod tx {
 
 /// A Nft validity predicate
 pub mod vp {
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeMap, BTreeSet};
 
     use anoma::types::address::Address;
     pub use anoma::types::nft::*;
-    use anoma::types::storage::Key;
+    use anoma::types::storage::{DbKeySeg, Key};
+    use anoma::types::token::{self, Amount};
 
     use crate::imports::vp;
 
@@ -104,6 +139,7 @@ pub mod vp {
         Approval(Address, String),
         CurrentOwner(Address, String),
         Creator(Address),
+        Royalty(Address),
         PastOwners(Address, String),
         Unknown,
     }
@@ -121,6 +157,10 @@ pub mod vp {
                     vp::log_string("creator cannot be changed.");
                     false
                 }
+                KeyType::Royalty(_nft_address) => {
+                    vp::log_string("royalty cannot be changed.");
+                    false
+                }
                 KeyType::Approval(nft_address, token_id) => {
                     vp::log_string(format!(
                         "nft vp, checking approvals with token id: {}",
@@ -141,8 +181,93 @@ pub mod vp {
                     ));
                     is_creator(&nft_address, verifiers)
                 }
+                KeyType::CurrentOwner(nft_address, token_id)
+                | KeyType::PastOwners(nft_address, token_id) => {
+                    vp::log_string(format!(
+                        "nft vp, checking the owners of token id: {}",
+                        token_id
+                    ));
+                    is_creator(&nft_address, verifiers)
+                        || is_owner(&nft_address, &token_id, verifiers)
+                }
                 _ => is_creator(nft_address, verifiers),
             })
+            && is_royalty_paid(nft_address, keys_changed)
+    }
+
+    /// Check that when tokens change owner in a tx that pays their previous
+    /// owners, the royalty's beneficiary is paid its share in every fungible
+    /// token used for the payment
+    fn is_royalty_paid(
+        nft_address: &Address,
+        keys_changed: &BTreeSet<Key>,
+    ) -> bool {
+        let royalty_key = get_royalty_key(nft_address).to_string();
+        let royalty: Royalty = match vp::read_pre(royalty_key) {
+            Some(royalty) => royalty,
+            None => return true,
+        };
+        let sellers: BTreeSet<Address> = keys_changed
+            .iter()
+            .filter(|key| is_nft_current_owner_key(key, nft_address).is_some())
+            .filter_map(|key| {
+                let previous_owner: Address = vp::read_pre(key.to_string())?;
+                let owner: Option<Address> = vp::read_post(key.to_string());
+                (owner.as_ref() != Some(&previous_owner)
+                    && previous_owner != royalty.beneficiary)
+                    .then(|| previous_owner)
+            })
+            .collect();
+        if sellers.is_empty() {
+            return true;
+        }
+
+        // The amounts received by the sellers and by the beneficiary in
+        // every token
+        let mut payments: BTreeMap<&Address, (u64, u64)> = BTreeMap::new();
+        for key in keys_changed {
+            let owner = match token::is_any_token_balance_key(key) {
+                Some(owner) => owner,
+                None => continue,
+            };
+            let is_seller = sellers.contains(owner);
+            if !is_seller && owner != &royalty.beneficiary {
+                continue;
+            }
+            let token = match &key.segments[0] {
+                DbKeySeg::AddressSeg(token) => token,
+                _ => continue,
+            };
+            let pre: Amount = vp::read_pre(key.to_string()).unwrap_or_default();
+            let post: Amount =
+                vp::read_post(key.to_string()).unwrap_or_default();
+            if post <= pre {
+                continue;
+            }
+            let received = u64::from(post - pre);
+            let (to_sellers, to_beneficiary) =
+                payments.entry(token).or_default();
+            if is_seller {
+                *to_sellers = to_sellers.saturating_add(received);
+            } else {
+                *to_beneficiary = received;
+            }
+        }
+        payments
+            .into_iter()
+            .all(|(token, (to_sellers, to_beneficiary))| {
+                let paid = royalty.is_paid(
+                    Amount::from(to_sellers),
+                    Amount::from(to_beneficiary),
+                );
+                if !paid {
+                    vp::log_string(format!(
+                        "nft vp, the royalty in the token {} isn't paid",
+                        token
+                    ));
+                }
+                paid
+            })
     }
 
     fn is_approved(
@@ -159,6 +284,19 @@ pub mod vp {
             .any(|addr| verifiers.contains(addr));
     }
 
+    /// Check that the current owner of a token, who must authorize the
+    /// change of its owners in their VP, is a verifier
+    fn is_owner(
+        nft_address: &Address,
+        nft_token_id: &str,
+        verifiers: &BTreeSet<Address>,
+    ) -> bool {
+        let current_owner_key =
+            get_token_current_owner_key(nft_address, nft_token_id).to_string();
+        let current_owner: Option<Address> = vp::read_pre(current_owner_key);
+        current_owner.map_or(false, |owner| verifiers.contains(&owner))
+    }
+
     fn is_creator(
         nft_address: &Address,
         verifiers: &BTreeSet<Address>,
@@ -170,6 +308,7 @@ pub mod vp {
 
     fn get_key_type(key: &Key, nft_address: &Address) -> KeyType {
         let is_creator_key = is_nft_creator_key(key, nft_address);
+        let is_royalty_key = is_nft_royalty_key(key, nft_address);
         let is_metadata_key = is_nft_metadata_key(key, nft_address);
         let is_approval_key = is_nft_approval_key(key, nft_address);
         let is_current_owner_key = is_nft_current_owner_key(key, nft_address);
@@ -177,6 +316,9 @@ pub mod vp {
         if let Some(nft_address) = is_creator_key {
             return KeyType::Creator(nft_address);
         }
+        if let Some(nft_address) = is_royalty_key {
+            return KeyType::Royalty(nft_address);
+        }
         if let Some((nft_address, token_id)) = is_metadata_key {
             return KeyType::Metadata(nft_address, token_id);
         }
@@ -1,14 +1,16 @@
 //! Proof of Stake system integration with functions for transactions
 
 use anoma::ledger::pos::anoma_proof_of_stake::{
-    BecomeValidatorError, BondError, UnbondError, WithdrawError,
+    AutoCompoundError, BecomeValidatorError, BondError, ClaimRewardsError,
+    CommissionRateChangeError, UnbondError, UnjailError, WithdrawError,
 };
-use anoma::ledger::pos::types::Slash;
+use anoma::ledger::pos::types::{BasisPoints, Slash};
 pub use anoma::ledger::pos::*;
 use anoma::ledger::pos::{
-    anoma_proof_of_stake, bond_key, params_key, total_voting_power_key,
-    unbond_key, validator_address_raw_hash_key, validator_consensus_key_key,
-    validator_set_key, validator_slashes_key,
+    anoma_proof_of_stake, bond_auto_compound_key, bond_key, bond_rewards_key,
+    params_key, total_voting_power_key, unbond_key,
+    validator_address_raw_hash_key, validator_commission_rate_key,
+    validator_consensus_key_key, validator_set_key, validator_slashes_key,
     validator_staking_reward_address_key, validator_state_key,
     validator_total_deltas_key, validator_voting_power_key,
 };
@@ -56,8 +58,53 @@ pub fn withdraw_tokens(
     PoS.withdraw_tokens(source, validator, current_epoch)
 }
 
-/// Attempt to initialize a validator account. On success, returns the
-/// initialized validator account's address and its staking reward address.
+/// Claim the rewards of a self-bond to a validator when `source` is `None`
+/// or equal to the `validator` address, or claim the rewards of tokens
+/// delegated from the `source` to the `validator`.
+pub fn claim_rewards(
+    source: Option<&Address>,
+    validator: &Address,
+) -> Result<token::Amount, ClaimRewardsError<Address>> {
+    PoS.claim_rewards(source, validator)
+}
+
+/// Enable or disable auto-compounding of the rewards of a self-bond to a
+/// validator when `source` is `None` or equal to the `validator` address, or
+/// of tokens delegated from the `source` to the `validator`.
+pub fn set_auto_compound(
+    source: Option<&Address>,
+    validator: &Address,
+    auto_compound: bool,
+) -> Result<(), AutoCompoundError<Address>> {
+    PoS.set_auto_compound(source, validator, auto_compound)
+}
+
+/// Change a validator's commission rate. The validator is added to the
+/// verifiers, so that its validity predicate has to authorize the change.
+pub fn change_commission_rate(
+    validator: &Address,
+    new_rate: BasisPoints,
+) -> Result<(), CommissionRateChangeError<Address>> {
+    let current_epoch = tx::get_block_epoch();
+    tx::insert_verifier(validator);
+    PoS.change_validator_commission_rate(validator, new_rate, current_epoch)
+}
+
+/// Unjail a validator that has been jailed for downtime. The validator is
+/// added to the verifiers, so that its validity predicate has to authorize
+/// it.
+pub fn unjail_validator(
+    validator: &Address,
+) -> Result<(), UnjailError<Address>> {
+    let current_epoch = tx::get_block_epoch();
+    tx::insert_verifier(validator);
+    PoS.unjail_validator(validator, current_epoch)
+}
+
+/// Attempt to initialize a validator account with its initial commission
+/// rate. On success, returns the initialized validator account's address and
+/// its staking reward address. The initial self-bond, if any, is not applied
+/// here.
 pub fn init_validator(
     InitValidator {
         account_key,
@@ -67,6 +114,8 @@ pub fn init_validator(
         dkg_key,
         validator_vp_code,
         rewards_vp_code,
+        commission_rate,
+        self_bond: _,
     }: InitValidator,
 ) -> Result<(Address, Address), BecomeValidatorError<Address>> {
     let current_epoch = tx::get_block_epoch();
@@ -88,6 +137,7 @@ pub fn init_validator(
         &validator_address,
         &rewards_address,
         &consensus_key,
+        BasisPoints::new(commission_rate),
         current_epoch,
     )?;
     Ok((validator_address, rewards_address))
@@ -134,6 +184,13 @@ impl anoma_proof_of_stake::PosReadOnly for PoS {
         tx::read(validator_state_key(key).to_string())
     }
 
+    fn read_validator_commission_rate(
+        &self,
+        key: &Self::Address,
+    ) -> Option<ValidatorCommissionRates> {
+        tx::read(validator_commission_rate_key(key).to_string())
+    }
+
     fn read_validator_total_deltas(
         &self,
         key: &Self::Address,
@@ -160,6 +217,10 @@ impl anoma_proof_of_stake::PosReadOnly for PoS {
         tx::read(unbond_key(key).to_string())
     }
 
+    fn read_bond_rewards(&self, key: &BondId) -> Option<token::Amount> {
+        tx::read(bond_rewards_key(key).to_string())
+    }
+
     fn read_validator_set(&self) -> ValidatorSets {
         tx::read(validator_set_key().to_string()).unwrap()
     }
@@ -209,6 +270,14 @@ impl anoma_proof_of_stake::PosActions for PoS {
         tx::write(validator_state_key(key).to_string(), &value)
     }
 
+    fn write_validator_commission_rate(
+        &mut self,
+        key: &Self::Address,
+        value: ValidatorCommissionRates,
+    ) {
+        tx::write(validator_commission_rate_key(key).to_string(), &value)
+    }
+
     fn write_validator_total_deltas(
         &mut self,
         key: &Self::Address,
@@ -249,6 +318,22 @@ impl anoma_proof_of_stake::PosActions for PoS {
         tx::delete(unbond_key(key).to_string())
     }
 
+    fn delete_bond_rewards(&mut self, key: &BondId) {
+        tx::delete(bond_rewards_key(key).to_string())
+    }
+
+    fn write_bond_auto_compound(&mut self, key: &BondId) {
+        tx::write(bond_auto_compound_key(key).to_string(), true)
+    }
+
+    fn delete_bond_auto_compound(&mut self, key: &BondId) {
+        let key = bond_auto_compound_key(key).to_string();
+        // Avoid touching the key when the flag is not set
+        if tx::has_key(&key) {
+            tx::delete(key)
+        }
+    }
+
     fn transfer(
         &mut self,
         token: &Self::Address,
@@ -51,7 +51,19 @@ pub mod vp {
                     change += this_change;
                     // make sure that the spender approved the transaction
                     if this_change < 0 {
-                        return verifiers.contains(owner);
+                        if !verifiers.contains(owner) {
+                            return false;
+                        }
+                        // the tokens that are still locked by a vesting
+                        // schedule cannot be spent
+                        let vesting_key =
+                            token::vesting_key(token, owner).to_string();
+                        if let Some(schedule) =
+                            vp::read_pre::<VestingSchedule>(&vesting_key)
+                        {
+                            let epoch = vp::get_block_epoch();
+                            return post >= schedule.locked_at(epoch);
+                        }
                     }
                     true
                 }
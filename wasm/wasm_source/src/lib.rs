@@ -1,5 +1,11 @@
+#[cfg(feature = "tx_auto_compound")]
+pub mod tx_auto_compound;
 #[cfg(feature = "tx_bond")]
 pub mod tx_bond;
+#[cfg(feature = "tx_change_commission")]
+pub mod tx_change_commission;
+#[cfg(feature = "tx_claim_rewards")]
+pub mod tx_claim_rewards;
 #[cfg(feature = "tx_from_intent")]
 pub mod tx_from_intent;
 #[cfg(feature = "tx_ibc")]
@@ -14,10 +20,16 @@ pub mod tx_init_proposal;
 pub mod tx_init_validator;
 #[cfg(feature = "tx_mint_nft")]
 pub mod tx_mint_nft;
+#[cfg(feature = "tx_multi_transfer")]
+pub mod tx_multi_transfer;
+#[cfg(feature = "tx_reveal_pk")]
+pub mod tx_reveal_pk;
 #[cfg(feature = "tx_transfer")]
 pub mod tx_transfer;
 #[cfg(feature = "tx_unbond")]
 pub mod tx_unbond;
+#[cfg(feature = "tx_unjail")]
+pub mod tx_unjail;
 #[cfg(feature = "tx_update_vp")]
 pub mod tx_update_vp;
 #[cfg(feature = "tx_vote_proposal")]
@@ -0,0 +1,24 @@
+//! A tx to enable or disable auto-compounding of the staking rewards of a
+//! self-bond or a delegation. With auto-compounding, the rewards are re-bonded
+//! to the validator at the end of each epoch.
+
+use anoma_tx_prelude::proof_of_stake::set_auto_compound;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let auto_compound = transaction::schema::decode::<
+        transaction::pos::AutoCompound,
+    >(&signed.data.unwrap()[..])
+    .unwrap();
+
+    if let Err(err) = set_auto_compound(
+        auto_compound.source.as_ref(),
+        &auto_compound.validator,
+        auto_compound.auto_compound,
+    ) {
+        debug_log!("Setting auto-compounding failed with: {}", err);
+        panic!()
+    }
+}
@@ -0,0 +1,21 @@
+//! A tx for a validator to change its commission rate. The new rate takes
+//! effect at the pipeline offset.
+
+use anoma_tx_prelude::proof_of_stake::change_commission_rate;
+use anoma_tx_prelude::proof_of_stake::types::BasisPoints;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let change: transaction::pos::CommissionChange =
+        transaction::schema::decode(&signed.data.unwrap()[..]).unwrap();
+
+    if let Err(err) = change_commission_rate(
+        &change.validator,
+        BasisPoints::new(change.new_rate),
+    ) {
+        debug_log!("Commission rate change failed with: {}", err);
+        panic!()
+    }
+}
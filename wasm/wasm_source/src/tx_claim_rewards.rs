@@ -0,0 +1,25 @@
+//! A tx to claim the staking rewards of a self-bond or a delegation. The
+//! rewards are credited to the validator's staking reward address for a
+//! self-bond or to the delegator for a delegation.
+
+use anoma_tx_prelude::proof_of_stake::claim_rewards;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let claim = transaction::schema::decode::<transaction::pos::ClaimRewards>(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+
+    match claim_rewards(claim.source.as_ref(), &claim.validator) {
+        Ok(rewards) => {
+            debug_log!("Claimed rewards {}", rewards);
+        }
+        Err(err) => {
+            debug_log!("Claiming rewards failed with: {}", err);
+            panic!()
+        }
+    }
+}
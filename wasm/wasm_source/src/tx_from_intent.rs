@@ -1,6 +1,11 @@
 //! A tx for a token transfer crafted by matchmaker from intents.
 //! This tx uses `intent::IntentTransfers` wrapped inside
-//! `SignedTxData` as its input as declared in `shared` crate.
+//! `SignedTxData` as its input as declared in `shared` crate. The tokens of
+//! NFTs exchanged by the intents are transferred with it, with the NFTs'
+//! royalties paid by the buyers. Exchanges that have expired by the time of
+//! the block cannot be settled. The amounts sold by exchanges filled in parts
+//! are added to their filled amounts. The matchmaker's fees are paid to the
+//! source of the tx in the tokens sold by the exchanges.
 
 use anoma_tx_prelude::*;
 
@@ -8,28 +13,82 @@ use anoma_tx_prelude::*;
 fn apply_tx(tx_data: Vec<u8>) {
     let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
 
-    let tx_data =
-        intent::IntentTransfers::try_from_slice(&signed.data.unwrap()[..]);
+    let tx_data = transaction::schema::decode::<intent::IntentTransfers>(
+        &signed.data.unwrap()[..],
+    );
 
     let tx_data = tx_data.unwrap();
 
     // make sure that the matchmaker has to validate this tx
     insert_verifier(&tx_data.source);
 
+    let block_time = time::DateTimeUtc::try_from(get_block_time())
+        .expect("The block time should be valid");
+    for exchange in tx_data.matches.exchanges.values() {
+        if exchange.data.is_expired(block_time) {
+            debug_log!("The exchange of {} has expired", exchange.data.addr);
+            panic!()
+        }
+    }
+
     for token::Transfer {
         source,
         target,
         token,
         amount,
-    } in tx_data.matches.transfers
+        memo: _,
+    } in &tx_data.matches.transfers
+    {
+        token::transfer(source, target, token, *amount);
+    }
+
+    for intent::NftTransfer {
+        nft,
+        token_id,
+        source,
+        target,
+    } in &tx_data.matches.nft_transfers
     {
-        token::transfer(&source, &target, &token, amount);
+        nft::transfer_token(nft, token_id, source, target);
+        // The buyer pays the NFT's royalty on top of the payments to the
+        // seller
+        if let Some(royalty) = nft::read_royalty(nft) {
+            for payment in &tx_data.matches.transfers {
+                if &payment.source == target && &payment.target == source {
+                    let amount = royalty
+                        .on_top_of(payment.amount)
+                        .expect("The royalty of a sold NFT must be under 100%");
+                    token::transfer(
+                        target,
+                        &royalty.beneficiary,
+                        &payment.token,
+                        amount,
+                    );
+                }
+            }
+        }
     }
 
-    tx_data
-        .matches
-        .exchanges
-        .values()
-        .into_iter()
-        .for_each(intent::invalidate_exchange);
+    for (payer, fee) in &tx_data.matches.fees {
+        let exchange = tx_data
+            .matches
+            .exchanges
+            .get(payer)
+            .expect("A fee must be paid by the source of an exchange");
+        token::transfer(
+            payer,
+            &tx_data.source,
+            &exchange.data.token_sell,
+            *fee,
+        );
+    }
+
+    for exchange in tx_data.matches.exchanges.values() {
+        if exchange.data.fills_in_parts() {
+            let sold = tx_data.matches.sold_by(&exchange.data);
+            intent::fill_exchange(exchange, sold);
+        } else {
+            intent::invalidate_exchange(exchange);
+        }
+    }
 }
@@ -1,5 +1,6 @@
 //! A tx to initialize a new validator account and staking reward account with a
-//! given public keys and a validity predicates.
+//! given public keys and a validity predicates. The validator's initial
+//! self-bond, if any, is transferred from the given source and bonded.
 
 use anoma_tx_prelude::transaction::InitValidator;
 use anoma_tx_prelude::*;
@@ -8,21 +9,36 @@ use anoma_tx_prelude::*;
 fn apply_tx(tx_data: Vec<u8>) {
     let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
     let init_validator =
-        InitValidator::try_from_slice(&signed.data.unwrap()[..]).unwrap();
+        transaction::schema::decode::<InitValidator>(&signed.data.unwrap()[..])
+            .unwrap();
     debug_log!("apply_tx called to init a new validator account");
+    let self_bond = init_validator.self_bond.clone();
 
     // Register the validator in PoS
-    match proof_of_stake::init_validator(init_validator) {
+    let validator_address = match proof_of_stake::init_validator(init_validator)
+    {
         Ok((validator_address, staking_reward_address)) => {
             debug_log!(
                 "Created validator {} and staking reward account {}",
                 validator_address.encode(),
                 staking_reward_address.encode()
-            )
+            );
+            validator_address
         }
         Err(err) => {
             debug_log!("Validator creation failed with: {}", err);
             panic!()
         }
+    };
+
+    // Fund the new validator account and self-bond the tokens
+    if let Some((source, amount)) = self_bond {
+        token::transfer(&source, &validator_address, &address::xan(), amount);
+        if let Err(err) =
+            proof_of_stake::bond_tokens(None, &validator_address, amount)
+        {
+            debug_log!("Validator self-bond failed with: {}", err);
+            panic!()
+        }
     }
 }
@@ -0,0 +1,23 @@
+//! A tx for a token transfer from a single source to many targets.
+//! This tx uses `token::Disbursement` wrapped inside `SignedTxData`
+//! as its input as declared in `shared` crate.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let disbursement = transaction::schema::decode::<token::Disbursement>(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+    debug_log!("apply_tx called with disbursement: {:#?}", disbursement);
+    let token::Disbursement {
+        source,
+        token,
+        entries,
+    } = disbursement;
+    for token::DisbursementEntry { target, amount, .. } in entries {
+        token::transfer(&source, &target, &token, amount)
+    }
+}
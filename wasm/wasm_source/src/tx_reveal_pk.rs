@@ -0,0 +1,19 @@
+//! A tx to reveal the public key of an implicit address, so that the ledger
+//! can verify its signatures. The address is derived from the public key,
+//! which is wrapped inside `SignedTxData` as its input.
+
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let public_key = transaction::schema::decode::<key::common::PublicKey>(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
+    let address = Address::from(&public_key);
+    debug_log!("reveal the public key of {}", address);
+
+    let pk_key = key::pk_key(&address);
+    write(&pk_key.to_string(), &public_key);
+}
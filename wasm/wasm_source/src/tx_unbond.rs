@@ -7,9 +7,10 @@ use anoma_tx_prelude::*;
 #[transaction]
 fn apply_tx(tx_data: Vec<u8>) {
     let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
-    let unbond =
-        transaction::pos::Unbond::try_from_slice(&signed.data.unwrap()[..])
-            .unwrap();
+    let unbond = transaction::schema::decode::<transaction::pos::Unbond>(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
 
     if let Err(err) =
         unbond_tokens(unbond.source.as_ref(), &unbond.validator, unbond.amount)
@@ -0,0 +1,18 @@
+//! A tx for a validator that has been jailed for downtime to unjail itself.
+//! The validator is added back to the validator set at the pipeline offset.
+
+use anoma_tx_prelude::proof_of_stake::unjail_validator;
+use anoma_tx_prelude::*;
+
+#[transaction]
+fn apply_tx(tx_data: Vec<u8>) {
+    let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
+    let validator =
+        transaction::schema::decode::<Address>(&signed.data.unwrap()[..])
+            .unwrap();
+
+    if let Err(err) = unjail_validator(&validator) {
+        debug_log!("Unjail failed with: {}", err);
+        panic!()
+    }
+}
@@ -7,9 +7,10 @@ use anoma_tx_prelude::*;
 #[transaction]
 fn apply_tx(tx_data: Vec<u8>) {
     let signed = SignedTxData::try_from_slice(&tx_data[..]).unwrap();
-    let withdraw =
-        transaction::pos::Withdraw::try_from_slice(&signed.data.unwrap()[..])
-            .unwrap();
+    let withdraw = transaction::schema::decode::<transaction::pos::Withdraw>(
+        &signed.data.unwrap()[..],
+    )
+    .unwrap();
 
     match withdraw_tokens(withdraw.source.as_ref(), &withdraw.validator) {
         Ok(slashed) => {
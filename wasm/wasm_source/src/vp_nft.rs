@@ -66,6 +66,7 @@ mod tests {
             keys: vec![],
             opt_keys: vec![],
             tokens: vec![],
+            royalty: None,
         });
 
         let mut tx_env = tx_host_env::take();
@@ -105,6 +106,7 @@ mod tests {
             keys: vec![],
             opt_keys: vec![],
             tokens: vec![],
+            royalty: None,
         });
 
         let mut tx_env = tx_host_env::take();
@@ -151,6 +153,7 @@ mod tests {
             keys: vec![],
             opt_keys: vec![],
             tokens: vec![],
+            royalty: None,
         });
 
         let mut tx_env = tx_host_env::take();
@@ -205,6 +208,7 @@ mod tests {
             keys: vec![],
             opt_keys: vec![],
             tokens: vec![],
+            royalty: None,
         });
 
         let mut tx_env = tx_host_env::take();
@@ -266,6 +270,7 @@ mod tests {
             keys: vec![],
             opt_keys: vec![],
             tokens: vec![],
+            royalty: None,
         });
 
         let mut tx_env = tx_host_env::take();
@@ -338,6 +343,7 @@ mod tests {
             keys: vec![],
             opt_keys: vec![],
             tokens: vec![],
+            royalty: None,
         });
 
         let mut tx_env = tx_host_env::take();
@@ -403,6 +409,7 @@ mod tests {
             keys: vec![],
             opt_keys: vec![],
             tokens: vec![],
+            royalty: None,
         });
 
         let mut tx_env = tx_host_env::take();
@@ -422,4 +429,207 @@ mod tests {
 
         assert!(!validate_tx(tx_data, nft_address, keys_changed, verifiers));
     }
+
+    /// Transfer the token of an NFT from the given source, which is inserted
+    /// as a verifier, and validate the tx
+    fn validate_transfer(owner: &Address, source: &Address) -> bool {
+        let mut tx_env = TestTxEnv::default();
+
+        let nft_creator = address::testing::established_address_1();
+        let buyer = address::testing::established_address_3();
+        tx_env.spawn_accounts([&nft_creator, owner, source, &buyer]);
+
+        // just a dummy vp, its not used during testing
+        let vp_code =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+
+        tx_host_env::set(tx_env);
+        let nft_address = tx_host_env::nft::init_nft(CreateNft {
+            tag: "v1".to_string(),
+            creator: nft_creator,
+            vp_code,
+            keys: vec![],
+            opt_keys: vec![],
+            tokens: vec![NftToken {
+                id: 1,
+                values: vec![],
+                opt_values: vec![],
+                metadata: "".to_string(),
+                approvals: vec![],
+                current_owner: Some(owner.clone()),
+                past_owners: vec![],
+                burnt: false,
+            }],
+            royalty: None,
+        });
+
+        let mut tx_env = tx_host_env::take();
+        tx_env.commit_tx_and_block();
+
+        vp_host_env::init_from_tx(nft_address.clone(), tx_env, |_| {
+            tx_host_env::nft::transfer_token(&nft_address, "1", source, &buyer);
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+
+        validate_tx(tx_data, nft_address, keys_changed, verifiers)
+    }
+
+    /// Test that a token can be transferred with the authorization of its
+    /// owner, but not of another address
+    #[test]
+    fn test_transfer_token() {
+        let owner = address::testing::established_address_2();
+        let other = address::testing::established_address_4();
+        assert!(validate_transfer(&owner, &owner));
+        assert!(!validate_transfer(&owner, &other));
+    }
+
+    /// Sell the token of an NFT with a 10% royalty for the given payments to
+    /// the seller and to the beneficiary of the royalty, and validate the tx
+    fn validate_sale(to_seller: u64, to_beneficiary: u64) -> bool {
+        let mut tx_env = TestTxEnv::default();
+
+        let nft_creator = address::testing::established_address_1();
+        let seller = address::testing::established_address_2();
+        let buyer = address::testing::established_address_3();
+        let beneficiary = address::testing::established_address_4();
+        let token = address::xan();
+        tx_env.spawn_accounts([
+            &nft_creator,
+            &seller,
+            &buyer,
+            &beneficiary,
+            &token,
+        ]);
+        tx_env.credit_tokens(&buyer, &token, token::Amount::whole(1_000));
+
+        // just a dummy vp, its not used during testing
+        let vp_code =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+
+        tx_host_env::set(tx_env);
+        let nft_address = tx_host_env::nft::init_nft(CreateNft {
+            tag: "v1".to_string(),
+            creator: nft_creator.clone(),
+            vp_code,
+            keys: vec![],
+            opt_keys: vec![],
+            tokens: vec![NftToken {
+                id: 1,
+                values: vec![],
+                opt_values: vec![],
+                metadata: "".to_string(),
+                approvals: vec![],
+                current_owner: Some(seller.clone()),
+                past_owners: vec![],
+                burnt: false,
+            }],
+            royalty: Some(nft::Royalty {
+                percentage: 10,
+                beneficiary: beneficiary.clone(),
+            }),
+        });
+
+        let mut tx_env = tx_host_env::take();
+        tx_env.commit_tx_and_block();
+
+        vp_host_env::init_from_tx(nft_address.clone(), tx_env, |_| {
+            let amount = token::Amount::whole;
+            tx_host_env::token::transfer(
+                &buyer,
+                &seller,
+                &token,
+                amount(to_seller),
+            );
+            tx_host_env::token::transfer(
+                &buyer,
+                &beneficiary,
+                &token,
+                amount(to_beneficiary),
+            );
+            let current_owner_key =
+                nft::get_token_current_owner_key(&nft_address, "1").to_string();
+            tx_host_env::write(current_owner_key, &buyer);
+            tx_host_env::insert_verifier(&nft_creator);
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+
+        validate_tx(tx_data, nft_address, keys_changed, verifiers)
+    }
+
+    /// Test that a sale that pays the royalty is accepted
+    #[test]
+    fn test_sale_with_royalty_paid() {
+        assert!(validate_sale(90, 10));
+    }
+
+    /// Test that a sale that doesn't pay the royalty is rejected
+    #[test]
+    fn test_sale_without_royalty_paid() {
+        assert!(!validate_sale(100, 0));
+        assert!(!validate_sale(95, 5));
+    }
+
+    /// Test that the royalty cannot be changed
+    #[test]
+    fn test_cant_change_royalty() {
+        let mut tx_env = TestTxEnv::default();
+
+        let nft_creator = address::testing::established_address_2();
+        tx_env.spawn_accounts([&nft_creator]);
+
+        // just a dummy vp, its not used during testing
+        let vp_code =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+
+        tx_host_env::set(tx_env);
+        let nft_address = tx_host_env::nft::init_nft(CreateNft {
+            tag: "v1".to_string(),
+            creator: nft_creator.clone(),
+            vp_code,
+            keys: vec![],
+            opt_keys: vec![],
+            tokens: vec![],
+            royalty: Some(nft::Royalty {
+                percentage: 10,
+                beneficiary: nft_creator.clone(),
+            }),
+        });
+
+        let mut tx_env = tx_host_env::take();
+        tx_env.commit_tx_and_block();
+
+        vp_host_env::init_from_tx(nft_address.clone(), tx_env, |_| {
+            let royalty_key = nft::get_royalty_key(&nft_address).to_string();
+            tx_host_env::write(
+                royalty_key,
+                nft::Royalty {
+                    percentage: 0,
+                    beneficiary: nft_creator.clone(),
+                },
+            );
+            tx_host_env::insert_verifier(&nft_creator);
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = vp_env.get_verifiers();
+        vp_host_env::set(vp_env);
+
+        assert!(!validate_tx(tx_data, nft_address, keys_changed, verifiers));
+    }
 }
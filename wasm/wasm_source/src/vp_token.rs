@@ -1,11 +1,13 @@
 //! A VP for a fungible token. Enforces that the total supply is unchanged in a
-//! transaction that moves balance(s).
+//! transaction that moves balance(s) and that the memos of a transfer,
+//! including the transfers in a batch, are no longer than
+//! `token::MAX_MEMO_LEN` bytes.
 
 use anoma_vp_prelude::*;
 
 #[validity_predicate]
 fn validate_tx(
-    _tx_data: Vec<u8>,
+    tx_data: Vec<u8>,
     addr: Address,
     keys_changed: BTreeSet<storage::Key>,
     verifiers: BTreeSet<Address>,
@@ -33,5 +35,74 @@ fn validate_tx(
                 None => true,
             });
 
-    vp_check && token::vp(&addr, &keys_changed, &verifiers)
+    vp_check
+        && is_valid_memo(&tx_data)
+        && token::vp(&addr, &keys_changed, &verifiers)
+}
+
+/// Check the memos of a transfer or a disbursement in the tx data, or of the
+/// ones in the operations of a batch. The data of other txs are accepted.
+fn is_valid_memo(tx_data: &[u8]) -> bool {
+    let data = match SignedTxData::try_from_slice(tx_data) {
+        Ok(SignedTxData {
+            data: Some(data), ..
+        }) => data,
+        _ => return true,
+    };
+    if let Ok(batch) = transaction::batch::TxBatch::try_from_slice(&data[..]) {
+        return batch
+            .ops
+            .iter()
+            .filter_map(|op| op.data.as_ref())
+            .all(|data| has_valid_memos(data));
+    }
+    has_valid_memos(&data)
+}
+
+/// Check the memos of a transfer or a disbursement in the data of a tx or of
+/// an operation of a batch
+fn has_valid_memos(data: &[u8]) -> bool {
+    if let Ok(transfer) = transaction::schema::decode::<token::Transfer>(data) {
+        return transfer.has_valid_memo();
+    }
+    if let Ok(disbursement) =
+        transaction::schema::decode::<token::Disbursement>(data)
+    {
+        return disbursement.has_valid_memos();
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use anoma::types::key::testing::keypair_1;
+    use anoma::types::transaction::batch::{BatchOp, TxBatch};
+
+    use super::*;
+
+    /// Test that the memos of the transfers in a batch are no longer than
+    /// `token::MAX_MEMO_LEN` bytes
+    #[test]
+    fn test_batch_memo() {
+        let transfer = |memo_len: usize| BatchOp {
+            code: vec![],
+            data: Some(transaction::schema::encode(&token::Transfer {
+                source: address::testing::established_address_1(),
+                target: address::testing::established_address_2(),
+                token: address::xan(),
+                amount: token::Amount::whole(10),
+                memo: Some("a".repeat(memo_len)),
+            })),
+        };
+        let tx_data = |ops: Vec<BatchOp>| {
+            let tx = TxBatch { ops }.to_tx().sign(&keypair_1());
+            tx.data.unwrap()
+        };
+
+        let valid = transfer(token::MAX_MEMO_LEN);
+        let invalid = transfer(token::MAX_MEMO_LEN + 1);
+        assert!(is_valid_memo(&tx_data(vec![valid.clone()])));
+        assert!(!is_valid_memo(&tx_data(vec![invalid.clone()])));
+        assert!(!is_valid_memo(&tx_data(vec![valid, invalid])));
+    }
 }
@@ -3,17 +3,25 @@
 //! This VP currently provides a signature verification against a public key for
 //! sending tokens (receiving tokens is permissive).
 //!
-//! It allows to bond, unbond and withdraw tokens to and from PoS system with a
-//! valid signature.
+//! It allows to bond, unbond and withdraw tokens to and from PoS system and to
+//! change the commission rate of a validator account or unjail it with a valid
+//! signature.
 //!
 //! It allows to fulfil intents that were signed by this account's key if they
 //! haven't already been fulfilled (fulfilled intents are added to the owner's
-//! invalid intent set).
+//! invalid intent set). The exchanges that are filled in parts stay valid
+//! until the sum of their filled parts reaches their `max_sell`. The fee paid
+//! to the matchmaker on top of the sold amount can't exceed the exchange's
+//! `max_fee`, or its proportion of it for a part. A ring-signed intent can be
+//! fulfilled once this account reveals itself as its source with a signature
+//! by its key. The NFT tokens owned by this account can only change owner with
+//! a valid signature or when they are sold by the exchange of a fulfilled
+//! intent.
 //!
 //! Any other storage key changes are allowed only with a valid signature.
 
 use anoma_vp_prelude::intent::{
-    Exchange, FungibleTokenIntent, IntentTransfers,
+    Exchange, FungibleTokenIntent, IntentTransfers, RingSettlement,
 };
 use anoma_vp_prelude::storage::KeySeg;
 use anoma_vp_prelude::*;
@@ -24,6 +32,7 @@ enum KeyType<'a> {
     Token(&'a Address),
     PoS,
     InvalidIntentSet(&'a Address),
+    FilledIntents(&'a Address),
     Nft(&'a Address),
     Vp(&'a Address),
     GovernanceVote(&'a Address),
@@ -38,6 +47,8 @@ impl<'a> From<&'a storage::Key> for KeyType<'a> {
             Self::PoS
         } else if let Some(address) = intent::is_invalid_intent_key(key) {
             Self::InvalidIntentSet(address)
+        } else if let Some(address) = intent::is_filled_intent_key(key) {
+            Self::FilledIntents(address)
         } else if let Some(address) = nft::is_nft_key(key) {
             Self::Nft(address)
         } else if gov_storage::is_vote_key(key) {
@@ -83,10 +94,11 @@ fn validate_tx(
         _ => false,
     });
 
-    let valid_intent = Lazy::new(|| match &*signed_tx_data {
+    let intent_exchange = Lazy::new(|| match &*signed_tx_data {
         Ok(signed_tx_data) => check_intent_transfers(&addr, signed_tx_data),
-        _ => false,
+        _ => None,
     });
+    let valid_intent = Lazy::new(|| intent_exchange.is_some());
 
     if !is_tx_whitelisted() {
         return false;
@@ -129,16 +141,28 @@ fn validate_tx(
             KeyType::PoS => {
                 // Allow the account to be used in PoS
                 let bond_id = proof_of_stake::is_bond_key(key)
-                    .or_else(|| proof_of_stake::is_unbond_key(key));
+                    .or_else(|| proof_of_stake::is_unbond_key(key))
+                    .or_else(|| proof_of_stake::is_bond_rewards_key(key))
+                    .or_else(|| proof_of_stake::is_bond_auto_compound_key(key));
                 let valid = match bond_id {
                     Some(bond_id) => {
-                        // Bonds and unbonds changes for this address
-                        // must be signed
+                        // Bonds, unbonds, rewards and auto-compounding
+                        // changes for this address must be signed
                         bond_id.source != addr || *valid_sig
                     }
                     None => {
-                        // Any other PoS changes are allowed without signature
-                        true
+                        match proof_of_stake::is_validator_commission_rate_key(
+                            key,
+                        )
+                        .or_else(|| proof_of_stake::is_validator_state_key(key))
+                        {
+                            // Commission rate and state changes (i.e.
+                            // unjailing) of this validator must be signed
+                            Some(validator) => validator != &addr || *valid_sig,
+                            // Any other PoS changes are allowed without
+                            // signature
+                            None => true,
+                        }
                     }
                 };
                 debug_log!(
@@ -169,9 +193,40 @@ fn validate_tx(
                     true
                 }
             }
+            KeyType::FilledIntents(owner) => {
+                if owner == &addr {
+                    // The filled amounts are checked with the exchange that
+                    // is filled in parts
+                    matches!(
+                        &*intent_exchange,
+                        Some(exchange) if exchange.fills_in_parts()
+                    )
+                } else {
+                    true
+                }
+            }
             KeyType::Nft(owner) => {
                 if owner == &addr {
                     *valid_sig
+                } else if let Some(token_id) =
+                    owned_nft_token(key, owner, &addr)
+                {
+                    // The owners of a token of this account can only be
+                    // changed with a signature or by an intent that sells it
+                    let sold = matches!(
+                        &*intent_exchange,
+                        Some(exchange)
+                            if exchange.nft_sold() == Some((owner, token_id.as_str()))
+                    );
+                    debug_log!(
+                        "NFT {} token {} of this account, valid_sig: {}, sold \
+                         by intent: {}",
+                        owner,
+                        token_id,
+                        *valid_sig,
+                        sold
+                    );
+                    *valid_sig || sold
                 } else {
                     true
                 }
@@ -218,65 +273,159 @@ fn validate_tx(
     true
 }
 
+/// The ID of the token of an NFT owned by this account whose current or past
+/// owners are changed by the key, if any
+fn owned_nft_token(
+    key: &storage::Key,
+    nft_address: &Address,
+    addr: &Address,
+) -> Option<String> {
+    let (_, token_id) = nft::is_nft_current_owner_key(key, nft_address)
+        .or_else(|| nft::is_nft_past_owners_key(key, nft_address))?;
+    let current_owner_key =
+        nft::get_token_current_owner_key(nft_address, &token_id).to_string();
+    let current_owner: Address = read_pre(current_owner_key)?;
+    (&current_owner == addr).then(|| token_id)
+}
+
+/// Returns the exchange of this account's intent fulfilled by the tx, if the
+/// intent is valid
 fn check_intent_transfers(
     addr: &Address,
     signed_tx_data: &SignedTxData,
-) -> bool {
-    if let Some((raw_intent_transfers, exchange, intent)) =
-        try_decode_intent(addr, signed_tx_data)
-    {
-        log_string("check intent");
-        return check_intent(addr, exchange, intent, raw_intent_transfers);
-    }
-    false
+) -> Option<Exchange> {
+    let (raw_intent_transfers, exchange, intent, fee) =
+        try_decode_intent(addr, signed_tx_data)?;
+    log_string("check intent");
+    let data = exchange.data.clone();
+    check_intent(addr, exchange, intent, fee, raw_intent_transfers)
+        .then(|| data)
 }
 
+/// The intent that authorizes an exchange
+enum IntentAuth {
+    Signed(anoma_vp_prelude::Signed<FungibleTokenIntent>),
+    Ring(anoma_vp_prelude::Signed<RingSettlement>),
+}
+
+/// Decode the exchange of this account, the intent that authorizes it and the
+/// fee paid to the matchmaker for it
 fn try_decode_intent(
     addr: &Address,
     signed_tx_data: &SignedTxData,
 ) -> Option<(
     Vec<u8>,
     anoma_vp_prelude::Signed<Exchange>,
-    anoma_vp_prelude::Signed<FungibleTokenIntent>,
+    IntentAuth,
+    token::Amount,
 )> {
     let raw_intent_transfers = signed_tx_data.data.as_ref().cloned()?;
-    let mut tx_data =
-        IntentTransfers::try_from_slice(&raw_intent_transfers[..]).ok()?;
+    let mut tx_data = transaction::schema::decode::<IntentTransfers>(
+        &raw_intent_transfers[..],
+    )
+    .ok()?;
     debug_log!(
         "tx_data.matches.exchanges: {:?}, {}",
         tx_data.matches.exchanges,
         &addr
     );
-    if let (Some(exchange), Some(intent)) = (
-        tx_data.matches.exchanges.remove(addr),
-        tx_data.matches.intents.remove(addr),
-    ) {
-        return Some((raw_intent_transfers, exchange, intent));
+    let exchange = tx_data.matches.exchanges.remove(addr);
+    let intent = tx_data
+        .matches
+        .intents
+        .remove(addr)
+        .map(IntentAuth::Signed)
+        .or_else(|| {
+            tx_data
+                .matches
+                .ring_intents
+                .remove(addr)
+                .map(IntentAuth::Ring)
+        });
+    let fee = tx_data.matches.fees.remove(addr).unwrap_or_default();
+    if let (Some(exchange), Some(intent)) = (exchange, intent) {
+        return Some((raw_intent_transfers, exchange, intent, fee));
     } else {
         log_string("no intent with a matching address");
     }
     None
 }
 
+/// Verify that a ring-signed intent has been revealed by this account, that
+/// its ring signature is valid and that it contains the exchange
+fn verify_ring_settlement(
+    addr: &Address,
+    pk: &key::common::PublicKey,
+    settlement: &anoma_vp_prelude::Signed<RingSettlement>,
+    exchange: &Exchange,
+) -> bool {
+    if settlement.verify(pk).is_err() {
+        log_string("invalid reveal sig");
+        return false;
+    }
+    if &settlement.data.source != addr || !settlement.data.settles(exchange) {
+        log_string("the exchange is not settled by the ring intent");
+        return false;
+    }
+    let ring_keys: Option<Vec<key::common::PublicKey>> = settlement
+        .data
+        .intent
+        .data
+        .ring
+        .iter()
+        .map(key::get)
+        .collect();
+    match ring_keys {
+        Some(ring_keys) => {
+            let valid = settlement.data.intent.verify(&ring_keys).is_ok();
+            if !valid {
+                log_string("invalid ring sig");
+            }
+            valid
+        }
+        None => {
+            log_string("a ring address has no public key");
+            false
+        }
+    }
+}
+
 fn check_intent(
     addr: &Address,
     exchange: anoma_vp_prelude::Signed<Exchange>,
-    intent: anoma_vp_prelude::Signed<FungibleTokenIntent>,
+    intent: IntentAuth,
+    fee: token::Amount,
     raw_intent_transfers: Vec<u8>,
 ) -> bool {
     // verify signature
     let pk = key::get(addr);
     if let Some(pk) = pk {
-        if intent.verify(&pk).is_err() {
-            log_string("invalid sig");
-            return false;
+        match &intent {
+            IntentAuth::Signed(intent) => {
+                if intent.verify(&pk).is_err() {
+                    log_string("invalid sig");
+                    return false;
+                }
+            }
+            IntentAuth::Ring(settlement) => {
+                if !verify_ring_settlement(
+                    addr,
+                    &pk,
+                    settlement,
+                    &exchange.data,
+                ) {
+                    return false;
+                }
+            }
         }
     } else {
         return false;
     }
 
-    // verify the intent have not been already used
-    if !intent::vp_exchange(&exchange) {
+    // verify the intent have not been already used. The filled amount of an
+    // exchange filled in parts is verified with the amount sold below.
+    let in_parts = exchange.data.fills_in_parts();
+    if !in_parts && !intent::vp_exchange(&exchange) {
         return false;
     }
 
@@ -289,6 +438,10 @@ fn check_intent(
         min_buy,
         max_sell,
         vp,
+        nft,
+        expires: _,
+        partial: _,
+        max_fee,
     } = &exchange.data;
 
     debug_log!("vp is: {}", vp.is_some());
@@ -301,6 +454,15 @@ fn check_intent(
         }
     }
 
+    if &fee > max_fee {
+        debug_log!("fee {} exceeds max_fee {}", fee, max_fee);
+        return false;
+    }
+
+    if nft.is_some() {
+        return check_nft_exchange(&exchange.data);
+    }
+
     debug_log!(
         "exchange description: {}, {}, {}, {}, {}",
         token_sell,
@@ -325,6 +487,30 @@ fn check_intent(
 
     buy_difference.spend(&buy_pre);
 
+    // The fee is paid on top of the sold amount
+    if fee > sell_difference {
+        log_string("fee exceeds the sold amount");
+        return false;
+    }
+    sell_difference.spend(&fee);
+
+    if in_parts && !intent::vp_partial_exchange(&exchange, sell_difference) {
+        log_string("invalid filled amount");
+        return false;
+    }
+    // A part can pay at most its proportion of the `max_fee`
+    let max_fee = exchange.data.max_fee_for(sell_difference);
+    if fee > max_fee {
+        debug_log!("fee {} exceeds the part's max_fee {}", fee, max_fee);
+        return false;
+    }
+    // A part must buy at least its proportion of the `min_buy`
+    let min_buy = if in_parts {
+        exchange.data.min_buy_for(sell_difference)
+    } else {
+        *min_buy
+    };
+
     let sell_diff: Decimal = sell_difference.change().into(); // -> how many token I sold
     let buy_diff: Decimal = buy_difference.change().into(); // -> how many token I got
 
@@ -360,9 +546,61 @@ fn check_intent(
     }
 }
 
+/// Verify that an exchange of a token of an NFT is fulfilled. The seller
+/// must get at least the `min_buy` of the `token_buy` for the token and the
+/// buyer must get the token for at most the `max_sell` of the `token_sell`,
+/// which includes the royalty paid on top of the price.
+fn check_nft_exchange(exchange: &Exchange) -> bool {
+    let owner_post =
+        |nft_address: &Address, token_id: &str| -> Option<Address> {
+            let key = nft::get_token_current_owner_key(nft_address, token_id);
+            read_post(key.to_string())
+        };
+    if let Some((nft_address, token_id)) = exchange.nft_sold() {
+        let sold = owner_post(nft_address, token_id)
+            .map_or(false, |owner| owner != exchange.addr);
+        let received = balance_change(&exchange.token_buy, &exchange.addr);
+        debug_log!(
+            "NFT {} token {} sold: {}, received: {}, min_buy: {}",
+            nft_address,
+            token_id,
+            sold,
+            received,
+            exchange.min_buy.change()
+        );
+        sold && received >= exchange.min_buy.change()
+    } else if let Some((nft_address, token_id)) = exchange.nft_bought() {
+        let bought =
+            owner_post(nft_address, token_id).as_ref() == Some(&exchange.addr);
+        let spent = -balance_change(&exchange.token_sell, &exchange.addr);
+        debug_log!(
+            "NFT {} token {} bought: {}, spent: {}, max_sell: {}",
+            nft_address,
+            token_id,
+            bought,
+            spent,
+            exchange.max_sell.change()
+        );
+        bought && spent <= exchange.max_sell.change()
+    } else {
+        false
+    }
+}
+
+/// The change of an owner's balance of a token in the tx
+fn balance_change(token: &Address, owner: &Address) -> token::Change {
+    let key = token::balance_key(token, owner).to_string();
+    let pre: token::Amount = read_pre(&key).unwrap_or_default();
+    let post: token::Amount = read_post(&key).unwrap_or_default();
+    post.change() - pre.change()
+}
+
 #[cfg(test)]
 mod tests {
     use address::testing::arb_non_internal_address;
+    use anoma::proto::Tx;
+    use anoma::types::nft::NftToken;
+    use anoma::types::transaction::nft::CreateNft;
     // Use this as `#[test]` annotation to enable logging
     use anoma_tests::log::test;
     use anoma_tests::tx::{tx_host_env, TestTxEnv};
@@ -630,6 +868,68 @@ mod tests {
         }
     }
 
+    /// Test that a commission rate change of the VP owner without a valid
+    /// signature is rejected.
+    #[test]
+    fn test_unsigned_commission_rate_change_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner]);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Write the commission rate in a transaction
+            let key = proof_of_stake::validator_commission_rate_key(address);
+            tx_host_env::write(
+                key.to_string(),
+                &proof_of_stake::types::BasisPoints::new(100),
+            );
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
+    /// Test that a state change, i.e. unjailing, of the VP owner without a
+    /// valid signature is rejected.
+    #[test]
+    fn test_unsigned_validator_state_change_rejected() {
+        // Initialize a tx environment
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+
+        // Spawn the accounts to be able to modify their storage
+        tx_env.spawn_accounts([&vp_owner]);
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            // Write the validator state in a transaction
+            let key = proof_of_stake::validator_state_key(address);
+            tx_host_env::write(
+                key.to_string(),
+                &proof_of_stake::types::ValidatorState::Candidate,
+            );
+        });
+
+        let vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = vec![];
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        assert!(!validate_tx(tx_data, vp_owner, keys_changed, verifiers));
+    }
+
     /// Test that a validity predicate update without a valid signature is
     /// rejected.
     #[test]
@@ -848,4 +1148,232 @@ mod tests {
         vp_host_env::set(vp_env);
         assert!(validate_tx(tx_data, vp_owner, keys_changed, verifiers));
     }
+
+    /// Transfer a token of an NFT owned by the VP's owner to another address
+    /// and validate the tx, optionally signed by the owner
+    fn validate_nft_transfer(signed: bool) -> bool {
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let nft_creator = address::testing::established_address_2();
+        let target = address::testing::established_address_3();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let vp_code =
+            std::fs::read(VP_ALWAYS_TRUE_WASM).expect("cannot load wasm");
+
+        tx_env.spawn_accounts([&vp_owner, &nft_creator, &target]);
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        tx_host_env::set(tx_env);
+        let nft_address = tx_host_env::nft::init_nft(CreateNft {
+            tag: "v1".to_string(),
+            creator: nft_creator,
+            vp_code,
+            keys: vec![],
+            opt_keys: vec![],
+            tokens: vec![NftToken {
+                id: 1,
+                values: vec![],
+                opt_values: vec![],
+                metadata: "".to_string(),
+                approvals: vec![],
+                current_owner: Some(vp_owner.clone()),
+                past_owners: vec![],
+                burnt: false,
+            }],
+            royalty: None,
+        });
+        let mut tx_env = tx_host_env::take();
+        tx_env.commit_tx_and_block();
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |address| {
+            tx_host_env::nft::transfer_token(
+                &nft_address,
+                "1",
+                address,
+                &target,
+            );
+        });
+
+        let mut vp_env = vp_host_env::take();
+        let tx_data: Vec<u8> = if signed {
+            let signed_tx = vp_env.tx.clone().sign(&keypair);
+            let tx_data = signed_tx.data.as_ref().cloned().unwrap();
+            vp_env.tx = signed_tx;
+            tx_data
+        } else {
+            vec![]
+        };
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        validate_tx(tx_data, vp_owner, keys_changed, verifiers)
+    }
+
+    /// Settle a part of an exchange of the VP's owner filled in parts, after
+    /// its `filled` amount has already been settled, and validate the tx. The
+    /// part sells the `sold` amount for as much of the `token_buy` and pays
+    /// the `fee` to the matchmaker.
+    fn validate_exchange_part(
+        filled: token::Amount,
+        sold: token::Amount,
+        fee: token::Amount,
+    ) -> bool {
+        let mut tx_env = TestTxEnv::default();
+
+        let vp_owner = address::testing::established_address_1();
+        let counterparty = address::testing::established_address_2();
+        let matchmaker = address::testing::established_address_3();
+        let keypair = key::testing::keypair_1();
+        let public_key = keypair.ref_to();
+        let token_sell = address::btc();
+        let token_buy = address::xan();
+
+        tx_env.spawn_accounts([
+            &vp_owner,
+            &counterparty,
+            &matchmaker,
+            &token_sell,
+            &token_buy,
+        ]);
+        tx_env.credit_tokens(
+            &vp_owner,
+            &token_sell,
+            token::Amount::whole(1000),
+        );
+        tx_env.credit_tokens(
+            &counterparty,
+            &token_buy,
+            token::Amount::whole(1000),
+        );
+        tx_env.write_public_key(&vp_owner, &public_key);
+
+        let exchange = Signed::new(
+            &keypair,
+            Exchange {
+                addr: vp_owner.clone(),
+                token_sell: token_sell.clone(),
+                rate_min: intent::DecimalWrapper(Decimal::from(1)),
+                max_sell: token::Amount::whole(500),
+                token_buy: token_buy.clone(),
+                min_buy: token::Amount::whole(500),
+                vp: None,
+                nft: None,
+                expires: None,
+                partial: true,
+                max_fee: token::Amount::whole(10),
+            },
+        );
+        let settle = |sold: token::Amount, fee: token::Amount| {
+            tx_host_env::token::transfer(
+                &vp_owner,
+                &counterparty,
+                &token_sell,
+                sold,
+            );
+            tx_host_env::token::transfer(
+                &counterparty,
+                &vp_owner,
+                &token_buy,
+                sold,
+            );
+            tx_host_env::token::transfer(
+                &vp_owner,
+                &matchmaker,
+                &token_sell,
+                fee,
+            );
+            tx_host_env::intent::fill_exchange(&exchange, sold);
+        };
+
+        // Settle the filled part with its proportion of the `max_fee`
+        if filled != token::Amount::default() {
+            tx_host_env::set(tx_env);
+            settle(filled, exchange.data.max_fee_for(filled));
+            tx_env = tx_host_env::take();
+            tx_env.commit_tx_and_block();
+        }
+
+        // Initialize VP environment from a transaction
+        vp_host_env::init_from_tx(vp_owner.clone(), tx_env, |_address| {
+            settle(sold, fee);
+        });
+
+        let mut matches = intent::MatchedExchanges::empty();
+        matches.transfers.insert(token::Transfer {
+            source: vp_owner.clone(),
+            target: counterparty.clone(),
+            token: token_sell,
+            amount: sold,
+            memo: None,
+        });
+        matches.transfers.insert(token::Transfer {
+            source: counterparty,
+            target: vp_owner.clone(),
+            token: token_buy,
+            amount: sold,
+            memo: None,
+        });
+        matches.intents.insert(
+            vp_owner.clone(),
+            Signed::new(
+                &keypair,
+                FungibleTokenIntent {
+                    exchange: HashSet::from_iter([exchange.clone()]),
+                    nonce: 0,
+                },
+            ),
+        );
+        matches.exchanges.insert(vp_owner.clone(), exchange);
+        matches.fees.insert(vp_owner.clone(), fee);
+        let intent_transfers = IntentTransfers {
+            matches,
+            source: matchmaker,
+        };
+
+        let mut vp_env = vp_host_env::take();
+        let signed_tx = Tx::new(
+            vec![],
+            Some(transaction::schema::encode(&intent_transfers)),
+        )
+        .sign(&key::testing::keypair_2());
+        let tx_data: Vec<u8> = signed_tx.data.as_ref().cloned().unwrap();
+        vp_env.tx = signed_tx;
+        let keys_changed: BTreeSet<storage::Key> =
+            vp_env.all_touched_storage_keys();
+        let verifiers: BTreeSet<Address> = BTreeSet::default();
+        vp_host_env::set(vp_env);
+        validate_tx(tx_data, vp_owner, keys_changed, verifiers)
+    }
+
+    /// Test that each part of an exchange filled in parts can pay at most its
+    /// proportion of the `max_fee` to the matchmaker
+    #[test]
+    fn test_exchange_part_fee() {
+        let zero = token::Amount::default();
+        let half = token::Amount::whole(250);
+        // The first part pays the half of the `max_fee`
+        assert!(validate_exchange_part(zero, half, token::Amount::whole(5)));
+        // The second part can't pay the rest of the `max_fee` on top of its
+        // own proportion of it
+        assert!(!validate_exchange_part(half, half, token::Amount::whole(6)));
+        assert!(validate_exchange_part(half, half, token::Amount::whole(5)));
+        // A part that sells the whole `max_sell` can pay the whole `max_fee`
+        assert!(validate_exchange_part(
+            zero,
+            token::Amount::whole(500),
+            token::Amount::whole(10)
+        ));
+    }
+
+    /// Test that a token of an NFT owned by the VP's owner can only be
+    /// transferred with a valid signature when there's no intent that sells it
+    #[test]
+    fn test_nft_token_transfer() {
+        assert!(!validate_nft_transfer(false));
+        assert!(validate_nft_transfer(true));
+    }
 }
@@ -141,6 +141,7 @@ pub mod main {
             target,
             token,
             amount,
+            memo: _,
         } = transfer;
         let target_key = token::balance_key(&token, &target);
         let mut target_bal: token::Amount =